@@ -1,9 +1,20 @@
-use std::{collections::BTreeMap, sync::Arc};
-
-use crate::{serialize_struct, static_compile::DeScalarHandle, ControllerError};
-use anyhow::Result as AnyResult;
+use std::{
+    collections::BTreeMap,
+    mem::replace,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    serialize_struct,
+    static_compile::{DeScalarHandle, Field},
+    ControllerError,
+};
+use anyhow::{anyhow, Result as AnyResult};
 use dbsp::InputHandle;
-use pipeline_types::format::json::JsonFlavor;
+use pipeline_types::format::{
+    csv::CsvLineTerminator,
+    json::{JsonFlavor, JsonOverflowPolicy},
+};
 use pipeline_types::query::OutputQuery;
 use serde::{Deserialize, Serialize};
 
@@ -17,8 +28,58 @@ pub enum RecordFormat {
     // * Raw - Only applicable to single-column tables.  Input records contain
     // raw encoding of this column only.  This is particularly useful for
     // tables that store raw JSON or binary data to be parsed using SQL.
-    Json(JsonFlavor),
-    Csv,
+    Json(JsonOptions),
+    Csv(CsvOptions),
+    /// Records are serialized column by column using
+    /// [`SerCursor::serialize_key_fields`] to build Parquet files.  Other
+    /// serialization methods produce JSON, e.g., for error messages.
+    Parquet,
+}
+
+/// Options that control the encoding of JSON records.
+#[derive(Clone, Default, Deserialize, Serialize, Debug, PartialEq, Eq)]
+pub struct JsonOptions {
+    pub flavor: JsonFlavor,
+    /// Output column names that replace the names declared in the table
+    /// schema.  Ignored by deserializers.
+    pub rename_columns: BTreeMap<String, String>,
+    /// Number of decimal places used to serialize floating point values.
+    ///
+    /// `None` uses the shortest representation that round-trips.  Ignored
+    /// by deserializers.
+    pub float_precision: Option<usize>,
+    /// How integer columns handle numbers that don't fit into the column's
+    /// type.  Ignored by serializers.
+    pub on_overflow: JsonOverflowPolicy,
+}
+
+impl From<JsonFlavor> for JsonOptions {
+    fn from(flavor: JsonFlavor) -> Self {
+        Self {
+            flavor,
+            rename_columns: BTreeMap::new(),
+            float_precision: None,
+            on_overflow: JsonOverflowPolicy::Error,
+        }
+    }
+}
+
+/// Options that control the encoding of CSV records.
+#[derive(Clone, Default, Deserialize, Serialize, Debug, PartialEq, Eq)]
+pub struct CsvOptions {
+    /// Number of decimal places used to serialize floating point values.
+    ///
+    /// `None` uses the shortest representation that round-trips.
+    pub float_precision: Option<usize>,
+    /// Output column names that replace the names declared in the table
+    /// schema.  Ignored by deserializers.
+    pub rename_columns: BTreeMap<String, String>,
+    /// Terminator written after each record.  Ignored by deserializers.
+    pub line_terminator: CsvLineTerminator,
+    /// Output column names in the order in which they are written; the
+    /// remaining columns follow in their declared order.  Ignored by
+    /// deserializers.
+    pub column_order: Option<Vec<String>>,
 }
 
 // Helper type only used to serialize neighborhoods as a map vs tuple.
@@ -133,6 +194,114 @@ pub trait DeCollectionHandle: Send {
     ) -> Result<Box<dyn DeCollectionStream>, ControllerError>;
 }
 
+/// A switch that pauses and resumes ingestion through a set of
+/// [`DeCollectionStream`]s.
+///
+/// Streams wrapped in a [`GatedCollectionStream`] keep accepting
+/// [`insert`](`DeCollectionStream::insert`) and
+/// [`delete`](`DeCollectionStream::delete`) calls while the gate is paused,
+/// but their updates are held back instead of being pushed to the circuit:
+/// each [`flush`](`DeCollectionStream::flush`) call hands the updates
+/// buffered so far to the gate, which keeps them until
+/// [`resume`](`Self::resume`).  This allows the controller to stop new
+/// updates from reaching the circuit, e.g., while it takes a snapshot,
+/// without dropping any data or stalling the parsers.
+///
+/// Updates held by the gate are pushed to the circuit in the order in which
+/// they were flushed.  They are unaffected by
+/// [`clear_buffer`](`DeCollectionStream::clear_buffer`), which only clears
+/// updates buffered after the last `flush`, same as for an open gate.
+#[derive(Clone, Default)]
+pub struct IngestionGate {
+    inner: Arc<Mutex<IngestionGateInner>>,
+}
+
+#[derive(Default)]
+struct IngestionGateInner {
+    paused: bool,
+    /// Streams whose buffered updates were flushed while the gate was paused.
+    held: Vec<Box<dyn DeCollectionStream>>,
+}
+
+impl IngestionGate {
+    /// Create an open gate.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hold back updates flushed from now on until [`Self::resume`] is called.
+    pub fn pause(&self) {
+        self.inner.lock().unwrap().paused = true;
+    }
+
+    /// Push all updates held while the gate was paused to the circuit and let
+    /// subsequent flushes through.
+    pub fn resume(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.paused = false;
+        for mut stream in inner.held.drain(..) {
+            stream.flush();
+        }
+    }
+
+    /// Returns `true` if the gate is paused.
+    pub fn is_paused(&self) -> bool {
+        self.inner.lock().unwrap().paused
+    }
+
+    /// Wrap `stream` so that its updates are controlled by this gate.
+    pub fn wrap(&self, stream: Box<dyn DeCollectionStream>) -> GatedCollectionStream {
+        GatedCollectionStream {
+            stream,
+            gate: self.clone(),
+        }
+    }
+}
+
+/// A [`DeCollectionStream`] whose flushes are controlled by an
+/// [`IngestionGate`].
+pub struct GatedCollectionStream {
+    stream: Box<dyn DeCollectionStream>,
+    gate: IngestionGate,
+}
+
+impl DeCollectionStream for GatedCollectionStream {
+    fn insert(&mut self, data: &[u8]) -> AnyResult<()> {
+        self.stream.insert(data)
+    }
+
+    fn delete(&mut self, data: &[u8]) -> AnyResult<()> {
+        self.stream.delete(data)
+    }
+
+    fn reserve(&mut self, reservation: usize) {
+        self.stream.reserve(reservation);
+    }
+
+    fn flush(&mut self) {
+        let mut gate = self.gate.inner.lock().unwrap();
+        if gate.paused {
+            // Hand the buffered updates over to the gate and continue with
+            // an empty stream connected to the same input handle.  Some
+            // streams copy their buffers when forked, so clear the fork
+            // explicitly.
+            let mut fork = self.stream.fork();
+            fork.clear_buffer();
+            gate.held.push(replace(&mut self.stream, fork));
+        } else {
+            self.stream.flush();
+        }
+    }
+
+    fn clear_buffer(&mut self) {
+        self.stream.clear_buffer();
+    }
+
+    fn fork(&self) -> Box<dyn DeCollectionStream> {
+        Box::new(self.gate.wrap(self.stream.fork()))
+    }
+}
+
 /// A type-erased batch whose contents can be serialized.
 ///
 /// This is a wrapper around the DBSP `Batch` trait that returns a cursor that
@@ -184,6 +353,31 @@ pub trait SerCursor {
     /// serde serializers.
     fn serialize_key_weight(&mut self, dst: &mut Vec<u8>) -> AnyResult<()>;
 
+    /// Serialize the names of the columns of the current key, e.g., as a
+    /// CSV header row.  Panics if invalid.
+    ///
+    /// Returns an error if the cursor doesn't support this operation.
+    fn serialize_key_header(&mut self, _dst: &mut Vec<u8>) -> AnyResult<()> {
+        Err(anyhow!(
+            "serializing column names is not supported by this record format"
+        ))
+    }
+
+    /// Append the columns of the current key to `fields` and the names of
+    /// the columns they belong to to `names`, e.g., to build columnar
+    /// output.  Panics if invalid.
+    ///
+    /// Returns an error if the cursor doesn't support this operation.
+    fn serialize_key_fields(
+        &mut self,
+        _fields: &mut Vec<Field>,
+        _names: &mut Vec<Option<&'static str>>,
+    ) -> AnyResult<()> {
+        Err(anyhow!(
+            "serializing individual columns is not supported by this record format"
+        ))
+    }
+
     /// Serialize current value. Panics if invalid.
     fn serialize_val(&mut self, dst: &mut Vec<u8>) -> AnyResult<()>;
 
@@ -281,6 +475,18 @@ impl<'a> SerCursor for CursorWithPolarity<'a> {
         self.cursor.serialize_key_weight(dst)
     }
 
+    fn serialize_key_header(&mut self, dst: &mut Vec<u8>) -> AnyResult<()> {
+        self.cursor.serialize_key_header(dst)
+    }
+
+    fn serialize_key_fields(
+        &mut self,
+        fields: &mut Vec<Field>,
+        names: &mut Vec<Option<&'static str>>,
+    ) -> AnyResult<()> {
+        self.cursor.serialize_key_fields(fields, names)
+    }
+
     fn serialize_val(&mut self, dst: &mut Vec<u8>) -> AnyResult<()> {
         self.cursor.serialize_val(dst)
     }
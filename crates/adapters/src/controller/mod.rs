@@ -74,6 +74,12 @@ pub(crate) const MAX_API_CONNECTIONS: u64 = 100;
 
 pub(crate) type EndpointId = u64;
 
+/// Initial and maximal delays before the output thread retries pushing data
+/// to a consumer that applies backpressure.  The delay doubles after each
+/// retry that finds the consumer still blocked.
+const MIN_OUTPUT_RETRY_DELAY: Duration = Duration::from_millis(1);
+const MAX_OUTPUT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
 /// Controller that coordinates the creation, reconfiguration, teardown of
 /// input/output adapters, and implements runtime flow control.
 ///
@@ -1110,6 +1116,11 @@ impl ControllerInner {
         disconnect_flag: Arc<AtomicBool>,
         controller: Arc<ControllerInner>,
     ) {
+        // Stats of the last batch whose encoded buffers haven't all been
+        // accepted by the consumer yet.
+        let mut pending_stats: Option<(u64, usize)> = None;
+        let mut retry_delay = MIN_OUTPUT_RETRY_DELAY;
+
         loop {
             if controller.state() == PipelineState::Terminated {
                 return;
@@ -1119,6 +1130,43 @@ impl ControllerInner {
                 return;
             }
 
+            // Retry buffers that the consumer refused to accept earlier.
+            // While the consumer is blocked, leave new batches in the queue.
+            // Once the queue exceeds the high water mark, the circuit thread
+            // pauses until the consumer catches up.
+            if let Some((processed_records, num_records)) = pending_stats {
+                // Don't open a transport batch until the consumer is ready to
+                // accept data.
+                let flushed = if encoder.consumer().would_block() {
+                    Ok(false)
+                } else {
+                    encoder.consumer().batch_start();
+                    let flushed = encoder.flush_pending();
+                    encoder.consumer().batch_end();
+                    flushed
+                };
+
+                match flushed {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        parker.park_timeout(retry_delay);
+                        retry_delay = (retry_delay * 2).min(MAX_OUTPUT_RETRY_DELAY);
+                        continue;
+                    }
+                    Err(e) => controller.encode_error(endpoint_id, &endpoint_name, e),
+                }
+                retry_delay = MIN_OUTPUT_RETRY_DELAY;
+
+                // All buffers of the batch have been transmitted.
+                controller.status.output_batch(
+                    endpoint_id,
+                    processed_records,
+                    num_records,
+                    &controller.circuit_thread_unparker,
+                );
+                pending_stats = None;
+            }
+
             // Dequeue the next output batch and push it to the encoder.
             if let Some((data, processed_records)) = queue.pop() {
                 let num_records = data.iter().map(|b| b.len()).sum();
@@ -1127,17 +1175,29 @@ impl ControllerInner {
                 encoder
                     .encode(data.as_slice())
                     .unwrap_or_else(|e| controller.encode_error(endpoint_id, &endpoint_name, e));
+                let flushed = encoder.flush_pending();
                 encoder.consumer().batch_end();
 
-                // `num_records` output records have been transmitted --
-                // update output stats, wake up the circuit thread if the
-                // number of queued records drops below high water mark.
-                controller.status.output_batch(
-                    endpoint_id,
-                    processed_records,
-                    num_records,
-                    &controller.circuit_thread_unparker,
-                );
+                match flushed {
+                    // Update the stats once the consumer accepts the
+                    // remaining buffers.
+                    Ok(false) => pending_stats = Some((processed_records, num_records)),
+                    flushed => {
+                        if let Err(e) = flushed {
+                            controller.encode_error(endpoint_id, &endpoint_name, e);
+                        }
+
+                        // `num_records` output records have been transmitted --
+                        // update output stats, wake up the circuit thread if the
+                        // number of queued records drops below high water mark.
+                        controller.status.output_batch(
+                            endpoint_id,
+                            processed_records,
+                            num_records,
+                            &controller.circuit_thread_unparker,
+                        );
+                    }
+                }
             } else {
                 // Queue is empty -- wait for the circuit thread to wake us up when
                 // more data is available.
@@ -1203,6 +1263,7 @@ impl ControllerInner {
     }
 
     fn parse_error(&self, endpoint_id: EndpointId, endpoint_name: &str, error: ParseError) {
+        error.trace_event(endpoint_name);
         self.status.parse_error(endpoint_id);
         self.error(ControllerError::parse_error(endpoint_name, error));
     }
@@ -1,26 +1,35 @@
 use crate::{
-    catalog::{CursorWithPolarity, DeCollectionStream, RecordFormat, SerBatch, SerCursor},
-    format::{Encoder, InputFormat, OutputFormat, ParseError, Parser},
-    util::{split_on_newline, truncate_ellipse},
+    catalog::{
+        CsvOptions, CursorWithPolarity, DeCollectionStream, RecordFormat, SerBatch, SerCursor,
+    },
+    format::{
+        Encoder, InputFormat, OutputFormat, ParseError, ParseErrorKind, Parser, ParserCheckpoint,
+        PushStatus, MAX_PENDING_BUFFERS,
+    },
+    util::{split_on_unquoted_byte, truncate_ellipse},
     ControllerError, DeCollectionHandle, OutputConsumer,
 };
 use actix_web::HttpRequest;
 use anyhow::{bail, Result as AnyResult};
-use csv_core::{ReadRecordResult, Reader as CsvReader};
+use csv_core::{
+    ReadRecordResult, Reader as CsvReader, ReaderBuilder as CsvReaderBuilder, Terminator,
+};
 use erased_serde::Serialize as ErasedSerialize;
-use pipeline_types::format::csv::{CsvEncoderConfig, CsvParserConfig};
+use pipeline_types::format::csv::{CsvEncoderConfig, CsvLineTerminator, CsvParserConfig, TrimMode};
 use serde::Deserialize;
 use serde_urlencoded::Deserializer as UrlDeserializer;
 use serde_yaml::Value as YamlValue;
-use std::{borrow::Cow, mem::take, sync::Arc};
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, VecDeque},
+    mem::take,
+    sync::Arc,
+};
 
 pub(crate) mod deserializer;
 pub use deserializer::byte_record_deserializer;
 pub use deserializer::string_record_deserializer;
-
-/// When including a long CSV record in an error message,
-/// truncate it to `MAX_RECORD_LEN_IN_ERRMSG` bytes.
-static MAX_RECORD_LEN_IN_ERRMSG: usize = 4096;
+use deserializer::{coerce_record, DeserializeError, DeserializeErrorKind};
 
 /// CSV format parser.
 pub struct CsvInputFormat;
@@ -35,20 +44,54 @@ impl InputFormat for CsvInputFormat {
     // HTTP query, but a specialized method gives us more flexibility.
     fn config_from_http_request(
         &self,
-        _endpoint_name: &str,
-        _request: &HttpRequest,
+        endpoint_name: &str,
+        request: &HttpRequest,
     ) -> Result<Box<dyn ErasedSerialize>, ControllerError> {
-        Ok(Box::new(CsvParserConfig {}))
+        Ok(Box::new(
+            CsvParserConfig::deserialize(UrlDeserializer::new(form_urlencoded::parse(
+                request.query_string().as_bytes(),
+            )))
+            .map_err(|e| {
+                ControllerError::parser_config_parse_error(
+                    endpoint_name,
+                    &e,
+                    request.query_string(),
+                )
+            })?,
+        ))
     }
 
     fn new_parser(
         &self,
-        _endpoint_name: &str,
+        endpoint_name: &str,
         input_stream: &dyn DeCollectionHandle,
-        _config: &YamlValue,
+        config: &YamlValue,
     ) -> Result<Box<dyn Parser>, ControllerError> {
-        let input_stream = input_stream.configure_deserializer(RecordFormat::Csv)?;
-        Ok(Box::new(CsvParser::new(input_stream)) as Box<dyn Parser>)
+        let config = CsvParserConfig::deserialize(config).map_err(|e| {
+            ControllerError::parser_config_parse_error(
+                endpoint_name,
+                &e,
+                &serde_yaml::to_string(&config).unwrap_or_default(),
+            )
+        })?;
+
+        // Records are validated against `strict_columns`, so projecting
+        // columns past it would reject every record.
+        if let (Some(columns), Some(strict_columns)) = (&config.columns, config.strict_columns) {
+            if let Some(column) = columns.iter().find(|&&column| column >= strict_columns) {
+                return Err(ControllerError::parser_config_parse_error(
+                    endpoint_name,
+                    &format!(
+                        "column {column} listed in 'columns' is out of range for records with {strict_columns} columns ('strict_columns')"
+                    ),
+                    &serde_yaml::to_string(&config).unwrap_or_default(),
+                ));
+            }
+        }
+
+        let input_stream =
+            input_stream.configure_deserializer(RecordFormat::Csv(Default::default()))?;
+        Ok(Box::new(CsvParser::new(input_stream, config)) as Box<dyn Parser>)
     }
 }
 
@@ -56,20 +99,102 @@ struct CsvParser {
     /// Input handle to push parsed data to.
     input_stream: Box<dyn DeCollectionStream>,
 
-    /// Since we cannot assume that the input buffer ends on line end,
-    /// we save the "leftover" part of the buffer after the last new-line
-    /// character and prepend it to the next input buffer.
+    config: CsvParserConfig,
+
+    /// Since we cannot assume that the input buffer ends on a record
+    /// boundary, we save the "leftover" part of the buffer after the last
+    /// record terminator and prepend it to the next input buffer.
     leftover: Vec<u8>,
 
+    /// `true` if `leftover` ends inside a quoted field, which may contain
+    /// record terminators.
+    leftover_in_quotes: bool,
+
+    /// `true` if the parser is discarding the remainder of a record that
+    /// exceeded `max_record_len`.
+    skipping: bool,
+
+    /// Byte offset of the start of the record being skipped.
+    skipping_from: u64,
+
     last_event_number: u64,
+
+    /// Total number of bytes received from the input stream, including
+    /// `leftover`.
+    bytes_received: u64,
 }
 
 impl CsvParser {
-    fn new(input_stream: Box<dyn DeCollectionStream>) -> Self {
+    fn new(input_stream: Box<dyn DeCollectionStream>, config: CsvParserConfig) -> Self {
         Self {
             input_stream,
+            config,
             leftover: Vec::new(),
+            leftover_in_quotes: false,
+            skipping: false,
+            skipping_from: 0,
             last_event_number: 0,
+            bytes_received: 0,
+        }
+    }
+
+    /// Returns `true` if records must be coerced before they are passed to
+    /// the record deserializer.
+    fn needs_coercion(&self) -> bool {
+        !self.config.coercions.is_empty()
+            || self.config.trim != TrimMode::None
+            || self.config.columns.is_some()
+            || !self.config.date_formats.is_empty()
+    }
+
+    /// Apply whitespace trimming, column coercions, date/time formats, and
+    /// column projection configured for this parser to `record`, whose
+    /// decoded fields are `fields`.
+    fn coerce<'a>(&self, record: &'a [u8], fields: &[&[u8]]) -> AnyResult<Cow<'a, [u8]>> {
+        if self.needs_coercion() {
+            Ok(Cow::Owned(coerce_record(fields, &self.config)?))
+        } else {
+            Ok(Cow::Borrowed(record))
+        }
+    }
+
+    /// Returns an error if `record`, excluding its terminator, is longer
+    /// than `max_record_len`.
+    fn check_record_len(&self, record: &[u8], event_number: u64) -> Option<ParseError> {
+        let record = &record[..record_len(record)];
+        match self.config.max_record_len {
+            Some(max_record_len) if record.len() > max_record_len => {
+                let text = String::from_utf8_lossy(record);
+                Some(ParseError::new(
+                    format!("CSV record exceeds the maximum length of {max_record_len} bytes"),
+                    Some(event_number),
+                    None,
+                    Some(&truncate_ellipse(
+                        &text,
+                        self.config.max_record_len_in_errmsg,
+                        "...",
+                    )),
+                    None,
+                    None,
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Discard the incomplete record in `leftover` if it is longer than
+    /// `max_record_len`, reporting an error.
+    fn check_leftover_len(&mut self) -> Vec<ParseError> {
+        match self.check_record_len(&self.leftover, self.last_event_number + 1) {
+            Some(error) => {
+                self.last_event_number += 1;
+                self.skipping_from = self.bytes_received - self.leftover.len() as u64;
+                self.leftover.clear();
+                self.leftover_in_quotes = false;
+                self.skipping = true;
+                vec![error]
+            }
+            None => Vec::new(),
         }
     }
 
@@ -77,19 +202,52 @@ impl CsvParser {
         let mut errors = Vec::new();
         let mut num_records = 0;
 
-        let mut csv_reader = CsvReader::new();
+        let terminator = self.config.record_terminator;
+        let mut csv_reader = if terminator == b'\n' {
+            CsvReader::new()
+        } else {
+            CsvReaderBuilder::new()
+                .terminator(Terminator::Any(terminator))
+                .build()
+        };
 
         // println!("parse_from_buffer:{}", std::str::from_utf8(buffer).unwrap());
 
         let mut output = vec![0u8; 1024];
         let mut ends = [0usize; 128];
 
+        // Decoded fields of the current record, collected only if records
+        // need to be coerced, so that coercion doesn't have to parse them
+        // again.
+        let collect_fields = self.needs_coercion();
+        let mut decoded = Vec::new();
+        let mut decoded_ends = Vec::new();
+
         let mut total_bytes_read = 0;
+        let mut num_fields = 0;
+        // Offsets of the ends of the last two fields of the current record in
+        // its decoded output, used to detect a trailing empty field.
+        let mut total_bytes_written = 0;
+        let mut field_ends = (0, 0);
         let mut record_buffer = buffer;
         loop {
-            let (result, mut bytes_read, _, _) =
+            let (result, mut bytes_read, bytes_written, ends_written) =
                 csv_reader.read_record(buffer, &mut output, &mut ends);
             total_bytes_read += bytes_read;
+            num_fields += ends_written;
+            // `ends` are relative to the output of this call.
+            for end in &ends[0..ends_written] {
+                field_ends = (field_ends.1, total_bytes_written + end);
+            }
+            if collect_fields {
+                decoded.extend_from_slice(&output[0..bytes_written]);
+                decoded_ends.extend(
+                    ends[0..ends_written]
+                        .iter()
+                        .map(|end| total_bytes_written + end),
+                );
+            }
+            total_bytes_written += bytes_written;
             match result {
                 ReadRecordResult::End => break,
                 // `InputEmpty` status can be returned when there is no newline character in
@@ -103,39 +261,116 @@ impl CsvParser {
                             .unwrap_or("invalid utf-8"),
                         &record_buffer[0..total_bytes_read],
                     );*/
-                    match self
-                        .input_stream
-                        .insert(&record_buffer[0..total_bytes_read])
+                    let mut record = &record_buffer[0..total_bytes_read];
+                    // The record deserializer only recognizes new-line
+                    // terminators, so strip custom ones.
+                    if terminator != b'\n' {
+                        record = record.strip_suffix(&[terminator]).unwrap_or(record);
+                    }
+                    let max_record_len = self.config.max_record_len_in_errmsg;
+                    let record_text = || {
+                        let text = std::str::from_utf8(record)
+                            .map(|s| s.to_string())
+                            .unwrap_or_else(|_| format!("{:?}", record));
+                        truncate_ellipse(&text, max_record_len, "...").into_owned()
+                    };
+
+                    // When the input ends without a newline, the end of the
+                    // last field hasn't been recorded yet.
+                    if result == ReadRecordResult::InputEmpty && total_bytes_read > 0 {
+                        num_fields += 1;
+                        field_ends = (field_ends.1, total_bytes_written);
+                        if collect_fields {
+                            decoded_ends.push(total_bytes_written);
+                        }
+                    }
+
+                    let stripped = if self.config.allow_trailing_delimiter
+                        && num_fields > 1
+                        && field_ends.0 == field_ends.1
                     {
-                        Err(e) => {
-                            errors.push(ParseError::text_event_error(
-                                "failed to deserialize CSV record",
-                                e,
-                                self.last_event_number + 1,
-                                Some(
-                                    &std::str::from_utf8(&record_buffer[0..total_bytes_read])
-                                        .map(|s| s.to_string())
-                                        .unwrap_or_else(|_| {
-                                            format!("{:?}", &record_buffer[0..total_bytes_read])
-                                        })
-                                        .to_string(),
-                                ),
-                                None,
-                            ));
+                        num_fields -= 1;
+                        decoded_ends.truncate(num_fields);
+                        strip_trailing_field(record)
+                    } else {
+                        record
+                    };
+                    let mut start = 0;
+                    let fields = decoded_ends
+                        .iter()
+                        .map(|&end| {
+                            let field = &decoded[start..end];
+                            start = end;
+                            field
+                        })
+                        .collect::<Vec<_>>();
+
+                    let record_len_error =
+                        self.check_record_len(record, self.last_event_number + 1);
+                    match self.config.strict_columns {
+                        _ if record_len_error.is_some() => {
+                            errors.extend(record_len_error);
                         }
-                        Ok(()) => {
-                            num_records += 1;
+                        Some(expected) if expected != num_fields => {
+                            errors.push(ParseError::new(
+                                format!("CSV record has {num_fields} columns, but {expected} columns were expected"),
+                                Some(self.last_event_number + 1),
+                                None,
+                                Some(&record_text()),
+                                None,
+                                None,
+                            ).with_kind(ParseErrorKind::ColumnCount));
                         }
+                        _ => match self
+                            .coerce(stripped, &fields)
+                            .and_then(|record| self.input_stream.insert(&record))
+                        {
+                            Err(e) => {
+                                let deserialize_error = e.downcast_ref::<DeserializeError>();
+                                let kind = match deserialize_error.map(DeserializeError::kind) {
+                                    Some(DeserializeErrorKind::InvalidUtf8(_)) => {
+                                        ParseErrorKind::Encoding
+                                    }
+                                    _ => ParseErrorKind::Deserialization,
+                                };
+                                let field_index = deserialize_error
+                                    .and_then(DeserializeError::field)
+                                    .map(|field| field as usize);
+
+                                errors.push(
+                                    ParseError::text_event_error(
+                                        "failed to deserialize CSV record",
+                                        e,
+                                        self.last_event_number + 1,
+                                        Some(&record_text()),
+                                        None,
+                                    )
+                                    .with_kind(kind)
+                                    .with_field_index(field_index),
+                                );
+                            }
+                            Ok(()) => {
+                                num_records += 1;
+                            }
+                        },
                     }
                     // Lines ending in "\r\n" get broken up after `\r` by the parser.
                     // Consume the remaining `\n`; otherwise it gets prepended to the
                     // next buffer.
-                    if buffer.len() > bytes_read && buffer[bytes_read] == b'\n' {
+                    if terminator == b'\n'
+                        && buffer.len() > bytes_read
+                        && buffer[bytes_read] == b'\n'
+                    {
                         bytes_read += 1;
                     }
                     record_buffer = &buffer[bytes_read..];
                     self.last_event_number += 1;
                     total_bytes_read = 0;
+                    num_fields = 0;
+                    total_bytes_written = 0;
+                    field_ends = (0, 0);
+                    decoded.clear();
+                    decoded_ends.clear();
                     if result == ReadRecordResult::InputEmpty {
                         break;
                     }
@@ -161,16 +396,37 @@ impl Parser for CsvParser {
                 .unwrap_or_else(|e| format!("invalid csv: {e}"))
         );*/
 
-        let leftover = split_on_newline(data);
+        self.bytes_received += data.len() as u64;
+
+        let mut data = data;
+        if self.skipping {
+            // Discard the remainder of a record that exceeded
+            // `max_record_len`, up to and including the next terminator.
+            let terminator = self.config.record_terminator;
+            match data.iter().position(|&x| x == terminator) {
+                Some(end) => {
+                    data = &data[end + 1..];
+                    self.skipping = false;
+                }
+                None => return (0, Vec::new()),
+            }
+        }
+
+        // Only split on record terminators outside of quoted fields.  We
+        // split at the end of a record, so the quote state at the end of
+        // `data` is also the quote state at the end of the new leftover.
+        let (leftover, in_quotes) =
+            split_on_unquoted_byte(data, self.config.record_terminator, self.leftover_in_quotes);
+        self.leftover_in_quotes = in_quotes;
 
         // println!("leftover: {leftover}");
 
         if leftover == 0 {
-            // `data` doesn't contain a new-line character; append it to
+            // `data` doesn't contain a record terminator; append it to
             // the `leftover` buffer so it gets processed with the next input
             // buffer.
             self.leftover.extend_from_slice(data);
-            (0, Vec::new())
+            (0, self.check_leftover_len())
         } else {
             let mut leftover_buf = take(&mut self.leftover);
             leftover_buf.extend_from_slice(&data[0..leftover]);
@@ -182,7 +438,9 @@ impl Parser for CsvParser {
             leftover_buf.extend_from_slice(&data[leftover..]);
             self.leftover = leftover_buf;
 
-            res
+            let (num_records, mut errors) = res;
+            errors.extend(self.check_leftover_len());
+            (num_records, errors)
         }
     }
 
@@ -192,6 +450,7 @@ impl Parser for CsvParser {
         }
 
         // Try to interpret the leftover chunk as a complete CSV line.
+        self.leftover_in_quotes = false;
         let mut leftover_buf = take(&mut self.leftover);
         let res = self.parse_from_buffer(leftover_buf.as_slice());
         leftover_buf.clear();
@@ -200,8 +459,59 @@ impl Parser for CsvParser {
     }
 
     fn fork(&self) -> Box<dyn Parser> {
-        Box::new(Self::new(self.input_stream.fork()))
+        Box::new(Self::new(self.input_stream.fork(), self.config.clone()))
+    }
+
+    fn checkpoint(&self) -> ParserCheckpoint {
+        if self.skipping {
+            // The record being skipped hasn't been consumed yet.  After
+            // restoring from the checkpoint, it is read, reported, and
+            // skipped again.
+            ParserCheckpoint {
+                records: self.last_event_number - 1,
+                bytes: self.skipping_from,
+            }
+        } else {
+            ParserCheckpoint {
+                records: self.last_event_number,
+                bytes: self.bytes_received - self.leftover.len() as u64,
+            }
+        }
     }
+
+    fn restore(&mut self, checkpoint: ParserCheckpoint) {
+        self.leftover.clear();
+        self.leftover_in_quotes = false;
+        self.skipping = false;
+        self.last_event_number = checkpoint.records;
+        self.bytes_received = checkpoint.bytes;
+    }
+}
+
+/// Returns the length of `record` without its line terminator.
+fn record_len(record: &[u8]) -> usize {
+    record.len()
+        - record
+            .iter()
+            .rev()
+            .take_while(|&&byte| byte == b'\n' || byte == b'\r')
+            .count()
+}
+
+/// Remove the line terminator and the trailing empty field, along with the
+/// delimiter that precedes it, from `record`.
+fn strip_trailing_field(record: &[u8]) -> &[u8] {
+    let mut record = record;
+    while let Some(rest) = record
+        .strip_suffix(b"\n")
+        .or_else(|| record.strip_suffix(b"\r"))
+    {
+        record = rest;
+    }
+
+    // The empty field can be quoted.
+    let record = record.strip_suffix(b"\"\"").unwrap_or(record);
+    record.strip_suffix(b",").unwrap_or(record)
 }
 
 /// CSV format encoder.
@@ -245,6 +555,28 @@ impl OutputFormat for CsvOutputFormat {
             )
         })?;
 
+        // The CSV writer quotes fields that contain the record terminator
+        // only for CRLF and single-byte terminators.
+        if config.line_terminator != CsvLineTerminator::Crlf
+            && config.line_terminator.as_bytes().len() != 1
+        {
+            return Err(ControllerError::encoder_config_parse_error(
+                endpoint_name,
+                &"custom 'line_terminator' must be a single byte",
+                &serde_yaml::to_string(&config).unwrap_or_default(),
+            ));
+        }
+
+        if let Some(column_order) = &config.column_order {
+            if column_order.iter().collect::<BTreeSet<_>>().len() != column_order.len() {
+                return Err(ControllerError::encoder_config_parse_error(
+                    endpoint_name,
+                    &"'column_order' must not contain duplicate columns",
+                    &serde_yaml::to_string(&config).unwrap_or_default(),
+                ));
+            }
+        }
+
         Ok(Box::new(CsvEncoder::new(consumer, config)))
     }
 }
@@ -256,6 +588,13 @@ struct CsvEncoder {
     config: CsvEncoderConfig,
     buffer: Vec<u8>,
     max_buffer_size: usize,
+
+    /// Encoded buffers rejected by the consumer with
+    /// [`PushStatus::WouldBlock`], waiting to be pushed again.
+    pending: VecDeque<Vec<u8>>,
+
+    /// Set once the header row has been written.
+    header_written: bool,
 }
 
 impl CsvEncoder {
@@ -267,6 +606,26 @@ impl CsvEncoder {
             config,
             buffer: Vec::new(),
             max_buffer_size,
+            pending: VecDeque::new(),
+            header_written: false,
+        }
+    }
+
+    /// Push `buffer` to the consumer.
+    ///
+    /// Queues the buffer if the consumer is blocked or if there are older
+    /// buffers waiting to be pushed.  Queued buffers are retried by
+    /// `flush_pending`.  Once [`MAX_PENDING_BUFFERS`] buffers are queued,
+    /// waits for the consumer to accept the oldest one.
+    fn push_buffer(&mut self, buffer: &[u8]) {
+        if !self.pending.is_empty()
+            || self.output_consumer.try_push_buffer(buffer) == PushStatus::WouldBlock
+        {
+            if self.pending.len() >= MAX_PENDING_BUFFERS {
+                let oldest = self.pending.pop_front().unwrap();
+                self.output_consumer.push_buffer(&oldest);
+            }
+            self.pending.push_back(buffer.to_vec());
         }
     }
 }
@@ -277,22 +636,61 @@ impl Encoder for CsvEncoder {
     }
 
     fn encode(&mut self, batches: &[Arc<dyn SerBatch>]) -> AnyResult<()> {
+        // Give pending buffers a chance to go out before new data.
+        self.flush_pending()?;
+
         let mut buffer = take(&mut self.buffer);
         //let mut writer = self.builder.from_writer(buffer);
         let mut num_records = 0;
+        // Number of copies of the current record written so far when
+        // `include_weight` is false.
+        let mut copies = 0;
 
         for batch in batches.iter() {
-            let mut cursor = CursorWithPolarity::new(batch.cursor(RecordFormat::Csv)?);
+            let mut cursor =
+                CursorWithPolarity::new(batch.cursor(RecordFormat::Csv(CsvOptions {
+                    float_precision: self.config.float_precision,
+                    rename_columns: self.config.rename_columns.clone(),
+                    line_terminator: self.config.line_terminator.clone(),
+                    column_order: self.config.column_order.clone(),
+                }))?);
 
             while cursor.key_valid() {
                 if !cursor.val_valid() {
                     cursor.step_key();
                     continue;
                 }
+
+                if self.config.header && !self.header_written {
+                    cursor.serialize_key_header(&mut buffer)?;
+                    if self.config.include_weight {
+                        // Insert the weight column before the record terminator.
+                        let terminator = self.config.line_terminator.as_bytes();
+                        buffer.truncate(buffer.len() - terminator.len());
+                        buffer.extend_from_slice(b",weight");
+                        buffer.extend_from_slice(terminator);
+                    }
+                    self.header_written = true;
+                }
+
                 let prev_len = buffer.len();
 
-                // `serialize_key_weight`
-                cursor.serialize_key_weight(&mut buffer)?;
+                // Without weights, a record with weight `w` is written as `w`
+                // rows, one row per iteration, so that each row is checked
+                // against the buffer size limits.
+                let last_row = if self.config.include_weight {
+                    cursor.serialize_key_weight(&mut buffer)?;
+                    true
+                } else {
+                    let w = cursor.weight();
+                    cursor.serialize_key(&mut buffer)?;
+                    if w < 0 {
+                        let record = std::str::from_utf8(&buffer[prev_len..]).unwrap_or_default();
+                        bail!("CSV encoder is configured with 'include_weight: false', which cannot represent retractions, but the following record has negative weight {w}: '{}'.",
+                              truncate_ellipse(record.trim_end(), self.config.max_record_len_in_errmsg, "..."));
+                    }
+                    copies + 1 >= w as usize
+                };
 
                 // Drop the last encoded record if it exceeds max_buffer_size.
                 // The record will be included in the next buffer.
@@ -305,7 +703,7 @@ impl Encoder for CsvEncoder {
                         bail!("CSV record exceeds maximum buffer size supported by the output transport. Max supported buffer size is {} bytes, but the following record requires {} bytes: '{}'.",
                               self.max_buffer_size,
                               new_len - prev_len,
-                              truncate_ellipse(record, MAX_RECORD_LEN_IN_ERRMSG, "..."));
+                              truncate_ellipse(record, self.config.max_record_len_in_errmsg, "..."));
                     }
                     true
                 } else {
@@ -319,19 +717,24 @@ impl Encoder for CsvEncoder {
                     }
                     // println!("push_buffer {}", buffer.len()
                     // /*std::str::from_utf8(&buffer).unwrap()*/);
-                    self.output_consumer.push_buffer(&buffer);
+                    self.push_buffer(&buffer);
                     buffer.clear();
                     num_records = 0;
                 }
 
                 if !overflow {
-                    cursor.step_key();
+                    if last_row {
+                        copies = 0;
+                        cursor.step_key();
+                    } else {
+                        copies += 1;
+                    }
                 }
             }
         }
 
         if num_records > 0 {
-            self.output_consumer.push_buffer(&buffer);
+            self.push_buffer(&buffer);
             buffer.clear();
         }
 
@@ -339,4 +742,1321 @@ impl Encoder for CsvEncoder {
 
         Ok(())
     }
+
+    fn flush_pending(&mut self) -> AnyResult<bool> {
+        while let Some(buffer) = self.pending.front() {
+            if self.output_consumer.try_push_buffer(buffer) == PushStatus::WouldBlock {
+                return Ok(false);
+            }
+            self.pending.pop_front();
+        }
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CsvEncoder, CsvInputFormat, CsvOutputFormat};
+    use crate::{
+        catalog::SerBatch,
+        deserialize_without_context,
+        format::{Encoder, InputFormat, OutputFormat, ParserCheckpoint, MAX_PENDING_BUFFERS},
+        static_compile::seroutput::SerBatchImpl,
+        test::{
+            mock_parser_pipeline, BlockingOutputConsumer, MockDeZSet, MockOutputConsumer,
+            TestStruct,
+        },
+        transport::InputConsumer,
+        FormatConfig, ParseError, ParseErrorKind,
+    };
+    use chrono::NaiveDate;
+    use dbsp::{algebra::F64, trace::Batch, OrdZSet};
+    use pipeline_types::format::csv::{
+        CsvColumnCoercion, CsvEncoderConfig, CsvLineTerminator, CsvParserConfig, TrimMode,
+    };
+    use serde::Deserialize;
+    use serde_json::json;
+    use std::{
+        borrow::Cow,
+        collections::BTreeMap,
+        fmt::{Debug, Write},
+        sync::{atomic::Ordering, Arc, Mutex},
+    };
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+        Event, Metadata, Subscriber,
+    };
+
+    fn csv_format_config(config: CsvParserConfig) -> FormatConfig {
+        FormatConfig {
+            name: Cow::from("csv"),
+            config: serde_yaml::to_value(config).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_strict_columns() {
+        let (mut consumer, outputs) =
+            mock_parser_pipeline::<TestStruct>(&csv_format_config(CsvParserConfig {
+                strict_columns: Some(3),
+                ..Default::default()
+            }))
+            .unwrap();
+        consumer.on_error(Some(Box::new(|_| {})));
+
+        assert_eq!(
+            consumer.input_fragment(b"1,true\n"),
+            vec![ParseError::new(
+                "CSV record has 2 columns, but 3 columns were expected".to_string(),
+                Some(1),
+                None,
+                Some("1,true\n"),
+                None,
+                None
+            )
+            .with_kind(ParseErrorKind::ColumnCount)]
+        );
+        assert_eq!(consumer.eoi(), Vec::new());
+        assert!(outputs.state().flushed.is_empty());
+
+        // Records with the expected number of columns are parsed as usual,
+        // including the last record without a trailing newline.
+        let (mut consumer, outputs) =
+            mock_parser_pipeline::<TestStruct>(&csv_format_config(CsvParserConfig {
+                strict_columns: Some(4),
+                ..Default::default()
+            }))
+            .unwrap();
+        consumer.on_error(Some(Box::new(|_| {})));
+
+        assert_eq!(
+            consumer.input_fragment(b"1,true,5,foo\n2,false,,bar"),
+            Vec::new()
+        );
+        assert_eq!(consumer.eoi(), Vec::new());
+        assert_eq!(
+            outputs.state().flushed,
+            vec![
+                (
+                    TestStruct {
+                        id: 1,
+                        b: true,
+                        i: Some(5),
+                        s: "foo".to_string()
+                    },
+                    true
+                ),
+                (
+                    TestStruct {
+                        id: 2,
+                        b: false,
+                        i: None,
+                        s: "bar".to_string()
+                    },
+                    true
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn test_column_coercions() {
+        let (mut consumer, outputs) =
+            mock_parser_pipeline::<TestStruct>(&csv_format_config(CsvParserConfig {
+                coercions: vec![CsvColumnCoercion {
+                    column: 2,
+                    strip_chars: "$,".to_string(),
+                }],
+                ..Default::default()
+            }))
+            .unwrap();
+        consumer.on_error(Some(Box::new(|_| {})));
+
+        assert_eq!(
+            consumer.input_fragment(b"1,true,\"$1,234\",\"foo, bar\"\n2,false,,baz\n"),
+            Vec::new()
+        );
+        assert_eq!(consumer.eoi(), Vec::new());
+        assert_eq!(
+            outputs.state().flushed,
+            vec![
+                (
+                    TestStruct {
+                        id: 1,
+                        b: true,
+                        i: Some(1234),
+                        s: "foo, bar".to_string()
+                    },
+                    true
+                ),
+                (
+                    TestStruct {
+                        id: 2,
+                        b: false,
+                        i: None,
+                        s: "baz".to_string()
+                    },
+                    true
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn test_column_projection() {
+        let (mut consumer, outputs) =
+            mock_parser_pipeline::<TestStruct>(&csv_format_config(CsvParserConfig {
+                columns: Some(vec![0, 2, 4, 5]),
+                strict_columns: Some(6),
+                ..Default::default()
+            }))
+            .unwrap();
+        consumer.on_error(Some(Box::new(|_| {})));
+
+        // Unprojected columns are not deserialized, so they can contain
+        // values that don't match any field of the record.
+        assert_eq!(
+            consumer
+                .input_fragment(b"1,not a number,true,\"x, y\",5,foo\n2,,false,\"\"\"\",,bar\n"),
+            Vec::new()
+        );
+        assert_eq!(consumer.eoi(), Vec::new());
+        assert_eq!(
+            outputs.state().flushed,
+            vec![
+                (
+                    TestStruct {
+                        id: 1,
+                        b: true,
+                        i: Some(5),
+                        s: "foo".to_string()
+                    },
+                    true
+                ),
+                (
+                    TestStruct {
+                        id: 2,
+                        b: false,
+                        i: None,
+                        s: "bar".to_string()
+                    },
+                    true
+                )
+            ]
+        );
+
+        // Without `strict_columns`, records that don't contain the projected
+        // columns are rejected.
+        let (mut consumer, _outputs) =
+            mock_parser_pipeline::<TestStruct>(&csv_format_config(CsvParserConfig {
+                columns: Some(vec![0, 2, 4, 5]),
+                ..Default::default()
+            }))
+            .unwrap();
+        consumer.on_error(Some(Box::new(|_| {})));
+        let errors = consumer.input_fragment(b"1,x,true,y\n");
+        assert_eq!(errors.len(), 1);
+        assert!(
+            errors[0]
+                .to_string()
+                .contains("CSV record has 4 columns, but column 4 is listed in 'columns'"),
+            "{}",
+            errors[0]
+        );
+
+        // Columns past `strict_columns` are rejected when the parser is
+        // created.
+        assert!(CsvInputFormat
+            .new_parser(
+                "test",
+                &<MockDeZSet<TestStruct>>::new(),
+                &csv_format_config(CsvParserConfig {
+                    columns: Some(vec![0, 2, 4, 6]),
+                    strict_columns: Some(6),
+                    ..Default::default()
+                })
+                .config,
+            )
+            .is_err());
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct DateRecord {
+        id: u32,
+        date: Option<NaiveDate>,
+    }
+
+    deserialize_without_context!(DateRecord);
+
+    #[test]
+    fn test_date_formats() {
+        let (mut consumer, outputs) =
+            mock_parser_pipeline::<DateRecord>(&csv_format_config(CsvParserConfig {
+                date_formats: BTreeMap::from([(1, "%m/%d/%Y".to_string())]),
+                ..Default::default()
+            }))
+            .unwrap();
+        consumer.on_error(Some(Box::new(|_| {})));
+
+        assert_eq!(consumer.input_fragment(b"1,06/30/2022\n2,\n"), Vec::new());
+
+        // Values that don't match the format are rejected.
+        let errors = consumer.input_fragment(b"3,2022-06-30\n");
+        assert_eq!(errors.len(), 1);
+        assert!(
+            errors[0].to_string().contains(
+                "value '2022-06-30' in column 1 does not match the date/time format '%m/%d/%Y'"
+            ),
+            "{}",
+            errors[0]
+        );
+
+        assert_eq!(consumer.eoi(), Vec::new());
+        assert_eq!(
+            outputs.state().flushed,
+            vec![
+                (
+                    DateRecord {
+                        id: 1,
+                        date: Some(NaiveDate::from_ymd_opt(2022, 6, 30).unwrap())
+                    },
+                    true
+                ),
+                (DateRecord { id: 2, date: None }, true)
+            ]
+        );
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Pair {
+        a: String,
+        b: String,
+    }
+
+    deserialize_without_context!(Pair);
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Triple {
+        a: String,
+        b: String,
+        c: String,
+    }
+
+    deserialize_without_context!(Triple);
+
+    #[test]
+    fn test_trailing_delimiter() {
+        let (mut consumer, outputs) =
+            mock_parser_pipeline::<Pair>(&csv_format_config(CsvParserConfig {
+                allow_trailing_delimiter: true,
+                strict_columns: Some(2),
+                ..Default::default()
+            }))
+            .unwrap();
+        consumer.on_error(Some(Box::new(|_| {})));
+
+        // The last record doesn't end with a newline.
+        assert_eq!(consumer.input_fragment(b"a,b,\nc,d,"), Vec::new());
+        assert_eq!(consumer.eoi(), Vec::new());
+        assert_eq!(
+            outputs.state().flushed,
+            vec![
+                (
+                    Pair {
+                        a: "a".to_string(),
+                        b: "b".to_string()
+                    },
+                    true
+                ),
+                (
+                    Pair {
+                        a: "c".to_string(),
+                        b: "d".to_string()
+                    },
+                    true
+                )
+            ]
+        );
+
+        // Without the option, the trailing delimiter starts an empty column.
+        let (mut consumer, outputs) =
+            mock_parser_pipeline::<Triple>(&csv_format_config(CsvParserConfig {
+                strict_columns: Some(3),
+                ..Default::default()
+            }))
+            .unwrap();
+        consumer.on_error(Some(Box::new(|_| {})));
+
+        assert_eq!(consumer.input_fragment(b"a,b,\n"), Vec::new());
+        assert_eq!(consumer.eoi(), Vec::new());
+        assert_eq!(
+            outputs.state().flushed,
+            vec![(
+                Triple {
+                    a: "a".to_string(),
+                    b: "b".to_string(),
+                    c: String::new()
+                },
+                true
+            )]
+        );
+    }
+
+    #[test]
+    fn test_record_terminator() {
+        let (mut consumer, outputs) =
+            mock_parser_pipeline::<Pair>(&csv_format_config(CsvParserConfig {
+                record_terminator: 0x1E,
+                strict_columns: Some(2),
+                ..Default::default()
+            }))
+            .unwrap();
+        consumer.on_error(Some(Box::new(|_| {})));
+
+        // Records are split across fragments, and the terminator inside the
+        // quoted field doesn't end the record.
+        assert_eq!(consumer.input_fragment(b"a,b\x1Ec,"), Vec::new());
+        assert_eq!(
+            consumer.input_fragment(b"d\x1E\"e\x1Ef\",g\x1Eh"),
+            Vec::new()
+        );
+        assert_eq!(consumer.input_fragment(b",i"), Vec::new());
+        assert_eq!(consumer.eoi(), Vec::new());
+
+        let pair = |a: &str, b: &str| {
+            (
+                Pair {
+                    a: a.to_string(),
+                    b: b.to_string(),
+                },
+                true,
+            )
+        };
+        assert_eq!(
+            outputs.state().flushed,
+            vec![
+                pair("a", "b"),
+                pair("c", "d"),
+                pair("e\x1Ef", "g"),
+                pair("h", "i")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_record_terminator_with_trim() {
+        let (mut consumer, outputs) =
+            mock_parser_pipeline::<Pair>(&csv_format_config(CsvParserConfig {
+                record_terminator: 0x1E,
+                trim: TrimMode::Fields,
+                ..Default::default()
+            }))
+            .unwrap();
+        consumer.on_error(Some(Box::new(|_| {})));
+
+        // New-line characters are regular data when the record terminator is
+        // customized, including when fields are trimmed.
+        assert_eq!(consumer.input_fragment(b" a\nb , c \x1E"), Vec::new());
+        assert_eq!(consumer.eoi(), Vec::new());
+        assert_eq!(
+            outputs.state().flushed,
+            vec![(
+                Pair {
+                    a: "a\nb".to_string(),
+                    b: "c".to_string(),
+                },
+                true
+            )]
+        );
+    }
+
+    #[test]
+    fn test_trim_fields() {
+        let (mut consumer, outputs) =
+            mock_parser_pipeline::<TestStruct>(&csv_format_config(CsvParserConfig {
+                trim: TrimMode::Fields,
+                ..Default::default()
+            }))
+            .unwrap();
+        consumer.on_error(Some(Box::new(|_| {})));
+
+        assert_eq!(
+            consumer.input_fragment(b"\" 42 \",true, 5 , foo \n"),
+            Vec::new()
+        );
+        assert_eq!(consumer.eoi(), Vec::new());
+        assert_eq!(
+            outputs.state().flushed,
+            vec![(
+                TestStruct {
+                    id: 42,
+                    b: true,
+                    i: Some(5),
+                    s: "foo".to_string()
+                },
+                true
+            )]
+        );
+    }
+
+    #[test]
+    fn test_quoted_newline_across_fragments() {
+        let (mut consumer, outputs) =
+            mock_parser_pipeline::<TestStruct>(&csv_format_config(CsvParserConfig::default()))
+                .unwrap();
+        consumer.on_error(Some(Box::new(|_| {})));
+
+        // The second record contains a quoted new-line character, and the
+        // fragment ends right after it.
+        assert_eq!(
+            consumer.input_fragment(b"1,true,5,foo\n2,false,6,\"bar\n"),
+            Vec::new()
+        );
+        assert_eq!(
+            consumer.input_fragment(b"baz \"\"quoted\"\"\"\n3,true,,\"\"\n"),
+            Vec::new()
+        );
+        assert_eq!(consumer.eoi(), Vec::new());
+        assert_eq!(
+            outputs.state().flushed,
+            vec![
+                (
+                    TestStruct {
+                        id: 1,
+                        b: true,
+                        i: Some(5),
+                        s: "foo".to_string()
+                    },
+                    true
+                ),
+                (
+                    TestStruct {
+                        id: 2,
+                        b: false,
+                        i: Some(6),
+                        s: "bar\nbaz \"quoted\"".to_string()
+                    },
+                    true
+                ),
+                (
+                    TestStruct {
+                        id: 3,
+                        b: true,
+                        i: None,
+                        s: "".to_string()
+                    },
+                    true
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn test_checkpoint_restore() {
+        let input: &[u8] = b"1,true,5,foo\n2,false,6,bar\n3,true,,baz\n4,maybe,7,qux\n";
+        let first_fragment = &input[..30];
+
+        let (mut consumer, outputs) =
+            mock_parser_pipeline::<TestStruct>(&csv_format_config(CsvParserConfig::default()))
+                .unwrap();
+        consumer.on_error(Some(Box::new(|_| {})));
+
+        // The fragment ends in the middle of the third record, which doesn't
+        // count towards the checkpoint.
+        assert_eq!(consumer.input_fragment(first_fragment), Vec::new());
+        let checkpoint = consumer.state().parser.checkpoint();
+        assert_eq!(
+            checkpoint,
+            ParserCheckpoint {
+                records: 2,
+                bytes: b"1,true,5,foo\n2,false,6,bar\n".len() as u64
+            }
+        );
+        assert_eq!(outputs.state().flushed.len(), 2);
+
+        // Resume from the checkpoint in a new pipeline.
+        let (mut consumer, outputs) =
+            mock_parser_pipeline::<TestStruct>(&csv_format_config(CsvParserConfig::default()))
+                .unwrap();
+        consumer.on_error(Some(Box::new(|_| {})));
+        consumer.state().parser.restore(checkpoint);
+
+        let errors = consumer.input_fragment(&input[checkpoint.bytes as usize..]);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].event_number(), Some(4));
+        assert_eq!(consumer.eoi(), Vec::new());
+        assert_eq!(
+            consumer.state().parser.checkpoint(),
+            ParserCheckpoint {
+                records: 4,
+                bytes: input.len() as u64
+            }
+        );
+        assert_eq!(
+            outputs.state().flushed,
+            vec![(
+                TestStruct {
+                    id: 3,
+                    b: true,
+                    i: None,
+                    s: "baz".to_string()
+                },
+                true
+            )]
+        );
+    }
+
+    #[test]
+    fn test_checkpoint_while_skipping() {
+        let input: &[u8] = b"1,true,5,foo\n2,false,6,barbarbarbar\n3,true,,baz\n";
+        let first_fragment = &input[..30];
+        let config = || {
+            csv_format_config(CsvParserConfig {
+                max_record_len: Some(15),
+                ..Default::default()
+            })
+        };
+
+        let (mut consumer, _outputs) = mock_parser_pipeline::<TestStruct>(&config()).unwrap();
+        consumer.on_error(Some(Box::new(|_| {})));
+
+        // The fragment ends in the middle of the oversized second record,
+        // which the parser starts skipping.  The checkpoint points to the
+        // start of that record.
+        assert_eq!(consumer.input_fragment(first_fragment).len(), 1);
+        let checkpoint = consumer.state().parser.checkpoint();
+        assert_eq!(
+            checkpoint,
+            ParserCheckpoint {
+                records: 1,
+                bytes: b"1,true,5,foo\n".len() as u64
+            }
+        );
+
+        // Resuming from the checkpoint reports and skips the oversized record
+        // again.
+        let (mut consumer, outputs) = mock_parser_pipeline::<TestStruct>(&config()).unwrap();
+        consumer.on_error(Some(Box::new(|_| {})));
+        consumer.state().parser.restore(checkpoint);
+
+        let errors = consumer.input_fragment(&input[checkpoint.bytes as usize..]);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].event_number(), Some(2));
+        assert_eq!(consumer.eoi(), Vec::new());
+        assert_eq!(
+            consumer.state().parser.checkpoint(),
+            ParserCheckpoint {
+                records: 3,
+                bytes: input.len() as u64
+            }
+        );
+        assert_eq!(
+            outputs.state().flushed,
+            vec![(
+                TestStruct {
+                    id: 3,
+                    b: true,
+                    i: None,
+                    s: "baz".to_string()
+                },
+                true
+            )]
+        );
+    }
+
+    #[test]
+    fn test_parse_error_json() {
+        let (mut consumer, outputs) =
+            mock_parser_pipeline::<TestStruct>(&csv_format_config(CsvParserConfig::default()))
+                .unwrap();
+        consumer.on_error(Some(Box::new(|_| {})));
+
+        let errors = consumer.input_fragment(b"1,maybe,5,foo\n");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind(), ParseErrorKind::Deserialization);
+        assert_eq!(errors[0].field_index(), Some(1));
+        assert_eq!(
+            errors[0].to_json(),
+            json!({
+                "description": "failed to deserialize CSV record: field 1: provided string was not `true` or `false`",
+                "kind": "deserialization",
+                "event_number": 1,
+                "field": null,
+                "field_index": 1,
+                "invalid_bytes": null,
+                "invalid_text": "1,maybe,5,foo\n",
+                "suggestion": null
+            })
+        );
+        // The error reported by the REST API doesn't include the new fields.
+        let serialized = serde_json::to_value(&errors[0]).unwrap();
+        assert!(serialized.get("kind").is_none());
+        assert!(serialized.get("field_index").is_none());
+        assert!(outputs.state().flushed.is_empty());
+    }
+
+    /// Subscriber that records the fields of all `tracing` events.
+    #[derive(Clone, Default)]
+    struct EventCollector {
+        events: Arc<Mutex<Vec<BTreeMap<String, String>>>>,
+    }
+
+    struct FieldCollector<'a>(&'a mut BTreeMap<String, String>);
+
+    impl Visit for FieldCollector<'_> {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.insert(field.name().to_string(), value.to_string());
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{value:?}"));
+        }
+    }
+
+    impl Subscriber for EventCollector {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _span: &Attributes<'_>) -> Id {
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = BTreeMap::new();
+            event.record(&mut FieldCollector(&mut fields));
+            self.events.lock().unwrap().push(fields);
+        }
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    #[test]
+    fn test_parse_error_trace_event() {
+        let (mut consumer, _outputs) =
+            mock_parser_pipeline::<TestStruct>(&csv_format_config(CsvParserConfig::default()))
+                .unwrap();
+        consumer.on_error(Some(Box::new(|_| {})));
+
+        let long_field = "x".repeat(300);
+        let errors = consumer.input_fragment(
+            format!("1,true,5,foo\n2,maybe,5,bar\n3,{long_field},5,baz\n").as_bytes(),
+        );
+        assert_eq!(errors.len(), 2);
+
+        let collector = EventCollector::default();
+        tracing::subscriber::with_default(collector.clone(), || {
+            for error in errors.iter() {
+                error.trace_event("test_input");
+            }
+        });
+
+        let events = collector.events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["endpoint"], "test_input");
+        assert_eq!(events[0]["event_number"], "2");
+        assert_eq!(events[0]["kind"], "Deserialization");
+        assert_eq!(events[0]["record"], "2,maybe,5,bar\n");
+        assert_eq!(
+            events[0]["message"],
+            "failed to deserialize CSV record: field 1: provided string was not `true` or `false`"
+        );
+        assert!(!events[0].contains_key("field"));
+
+        // Long records are truncated.
+        assert_eq!(events[1]["event_number"], "3");
+        assert_eq!(events[1]["record"], format!("3,{}...", &long_field[..254]));
+    }
+
+    fn test_batch(ids: std::ops::Range<u32>) -> Arc<dyn SerBatch> {
+        let zset = OrdZSet::from_keys(
+            (),
+            ids.map(|id| {
+                (
+                    TestStruct {
+                        id,
+                        b: true,
+                        i: None,
+                        s: "foo".to_string(),
+                    },
+                    1,
+                )
+            })
+            .collect::<Vec<_>>(),
+        );
+        Arc::new(<SerBatchImpl<_, TestStruct, ()>>::new(zset)) as Arc<dyn SerBatch>
+    }
+
+    #[test]
+    fn test_backpressure() {
+        let consumer = BlockingOutputConsumer::new();
+        let data = consumer.data.clone();
+        let blocked = consumer.blocked.clone();
+        let mut encoder = CsvEncoder::new(
+            Box::new(consumer),
+            CsvEncoderConfig {
+                buffer_size_records: 2,
+                include_weight: true,
+                float_precision: None,
+                header: false,
+                rename_columns: Default::default(),
+                column_order: None,
+                max_record_len_in_errmsg: 4096,
+                line_terminator: Default::default(),
+            },
+        );
+
+        // The consumer is blocked: all buffers are queued.
+        encoder.encode(&[test_batch(0..3)]).unwrap();
+        encoder.encode(&[test_batch(3..5)]).unwrap();
+        assert!(!encoder.flush_pending().unwrap());
+        assert!(data.lock().unwrap().is_empty());
+
+        // Drain the queue in order once the consumer unblocks.
+        blocked.store(false, Ordering::Release);
+        assert!(encoder.flush_pending().unwrap());
+        assert_eq!(
+            std::str::from_utf8(&data.lock().unwrap()).unwrap(),
+            "0,true,,foo,1\n1,true,,foo,1\n2,true,,foo,1\n3,true,,foo,1\n4,true,,foo,1\n"
+        );
+
+        // New data goes straight to the consumer.
+        encoder.encode(&[test_batch(5..6)]).unwrap();
+        assert!(encoder.flush_pending().unwrap());
+        assert!(std::str::from_utf8(&data.lock().unwrap())
+            .unwrap()
+            .ends_with("4,true,,foo,1\n5,true,,foo,1\n"));
+    }
+
+    #[test]
+    fn test_max_pending_buffers() {
+        // CSV encoding of `test_batch(0..n)`.
+        let expected_output = |n| {
+            (0..n).fold(String::new(), |mut output, i| {
+                writeln!(output, "{i},true,,foo,1").unwrap();
+                output
+            })
+        };
+
+        let consumer = BlockingOutputConsumer::new();
+        let data = consumer.data.clone();
+        let blocked = consumer.blocked.clone();
+        let mut encoder = CsvEncoder::new(
+            Box::new(consumer),
+            CsvEncoderConfig {
+                buffer_size_records: 1,
+                include_weight: true,
+                float_precision: None,
+                header: false,
+                rename_columns: Default::default(),
+                column_order: None,
+                max_record_len_in_errmsg: 4096,
+                line_terminator: Default::default(),
+            },
+        );
+
+        // Once the queue is full, the oldest buffers are pushed using the
+        // blocking API.
+        encoder.encode(&[test_batch(0..40)]).unwrap();
+        assert_eq!(encoder.pending.len(), MAX_PENDING_BUFFERS);
+        assert_eq!(
+            std::str::from_utf8(&data.lock().unwrap()).unwrap(),
+            expected_output(40 - MAX_PENDING_BUFFERS)
+        );
+
+        blocked.store(false, Ordering::Release);
+        assert!(encoder.flush_pending().unwrap());
+        assert_eq!(
+            std::str::from_utf8(&data.lock().unwrap()).unwrap(),
+            expected_output(40)
+        );
+    }
+
+    fn weighted_batch(weights: &[i32]) -> Arc<dyn SerBatch> {
+        let zset = OrdZSet::from_keys(
+            (),
+            weights
+                .iter()
+                .enumerate()
+                .map(|(id, w)| {
+                    (
+                        TestStruct {
+                            id: id as u32,
+                            b: false,
+                            i: Some(id as i64),
+                            s: "bar".to_string(),
+                        },
+                        *w as i64,
+                    )
+                })
+                .collect::<Vec<_>>(),
+        );
+        Arc::new(<SerBatchImpl<_, TestStruct, ()>>::new(zset)) as Arc<dyn SerBatch>
+    }
+
+    fn weight_encoder(include_weight: bool) -> (CsvEncoder, Arc<Mutex<Vec<u8>>>) {
+        let consumer = MockOutputConsumer::new();
+        let data = consumer.data.clone();
+        let encoder = CsvEncoder::new(
+            Box::new(consumer),
+            CsvEncoderConfig {
+                buffer_size_records: 10,
+                include_weight,
+                float_precision: None,
+                header: false,
+                rename_columns: Default::default(),
+                column_order: None,
+                max_record_len_in_errmsg: 4096,
+                line_terminator: Default::default(),
+            },
+        );
+        (encoder, data)
+    }
+
+    #[test]
+    fn test_include_weight() {
+        let (mut encoder, data) = weight_encoder(true);
+        encoder.encode(&[weighted_batch(&[1, 2, -1])]).unwrap();
+        // Retractions are encoded before insertions.
+        assert_eq!(
+            std::str::from_utf8(&data.lock().unwrap()).unwrap(),
+            "2,false,2,bar,-1\n0,false,0,bar,1\n1,false,1,bar,2\n"
+        );
+
+        // Without weights, each record is repeated according to its weight.
+        let (mut encoder, data) = weight_encoder(false);
+        encoder.encode(&[weighted_batch(&[1, 2])]).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&data.lock().unwrap()).unwrap(),
+            "0,false,0,bar\n1,false,1,bar\n1,false,1,bar\n"
+        );
+
+        // Retractions cannot be represented without weights.
+        let (mut encoder, _data) = weight_encoder(false);
+        let err = encoder
+            .encode(&[weighted_batch(&[1, -1])])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("negative weight -1: '1,false,1,bar'"), "{err}");
+    }
+
+    #[test]
+    fn test_buffer_size_without_weight() {
+        let consumer = BlockingOutputConsumer::new();
+        let data = consumer.data.clone();
+        let blocked = consumer.blocked.clone();
+        let mut encoder = CsvEncoder::new(
+            Box::new(consumer),
+            CsvEncoderConfig {
+                buffer_size_records: 2,
+                include_weight: false,
+                float_precision: None,
+                header: false,
+                rename_columns: Default::default(),
+                column_order: None,
+                max_record_len_in_errmsg: 4096,
+                line_terminator: Default::default(),
+            },
+        );
+
+        // Buffers are sized by the number of rows written, so the two copies of
+        // the first record fill up a buffer on their own.
+        encoder.encode(&[weighted_batch(&[2, 1])]).unwrap();
+        assert_eq!(encoder.pending.len(), 2);
+
+        blocked.store(false, Ordering::Release);
+        assert!(encoder.flush_pending().unwrap());
+        assert_eq!(
+            std::str::from_utf8(&data.lock().unwrap()).unwrap(),
+            "0,false,0,bar\n0,false,0,bar\n1,false,1,bar\n"
+        );
+    }
+
+    #[test]
+    fn test_max_buffer_size_without_weight() {
+        let consumer = MockOutputConsumer::with_max_buffer_size_bytes(30);
+        let data = consumer.data.clone();
+        let mut encoder = CsvEncoder::new(
+            Box::new(consumer),
+            CsvEncoderConfig {
+                buffer_size_records: 10,
+                include_weight: false,
+                float_precision: None,
+                header: false,
+                rename_columns: Default::default(),
+                column_order: None,
+                max_record_len_in_errmsg: 4096,
+                line_terminator: Default::default(),
+            },
+        );
+
+        // Each row takes 14 bytes, so the 5 copies of the record don't fit in
+        // a single buffer, but each copy does.
+        encoder.encode(&[weighted_batch(&[5])]).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&data.lock().unwrap()).unwrap(),
+            "0,false,0,bar\n".repeat(5)
+        );
+    }
+
+    #[test]
+    fn test_max_record_len_in_errmsg() {
+        let (mut consumer, _outputs) =
+            mock_parser_pipeline::<TestStruct>(&csv_format_config(CsvParserConfig {
+                strict_columns: Some(3),
+                max_record_len_in_errmsg: 4,
+                ..Default::default()
+            }))
+            .unwrap();
+        consumer.on_error(Some(Box::new(|_| {})));
+
+        assert_eq!(
+            consumer.input_fragment(b"1,true\n"),
+            vec![ParseError::new(
+                "CSV record has 2 columns, but 3 columns were expected".to_string(),
+                Some(1),
+                None,
+                Some("1,tr..."),
+                None,
+                None
+            )
+            .with_kind(ParseErrorKind::ColumnCount)]
+        );
+
+        let (mut encoder, _data) = weight_encoder(false);
+        encoder.config.max_record_len_in_errmsg = 4;
+        let err = encoder
+            .encode(&[weighted_batch(&[1, -1])])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("negative weight -1: '1,fa...'"), "{err}");
+    }
+
+    #[test]
+    fn test_max_record_len() {
+        let (mut consumer, outputs) =
+            mock_parser_pipeline::<TestStruct>(&csv_format_config(CsvParserConfig {
+                max_record_len: Some(16),
+                ..Default::default()
+            }))
+            .unwrap();
+        consumer.on_error(Some(Box::new(|_| {})));
+
+        assert_eq!(consumer.input_fragment(b"1,true,5,foo\n"), Vec::new());
+
+        // A record without a new-line is buffered until it exceeds the limit.
+        assert_eq!(consumer.input_fragment(b"xxxxxxxxxx"), Vec::new());
+        assert_eq!(
+            consumer.input_fragment(b"xxxxxxxxxx"),
+            vec![ParseError::new(
+                "CSV record exceeds the maximum length of 16 bytes".to_string(),
+                Some(2),
+                None,
+                Some("xxxxxxxxxxxxxxxxxxxx"),
+                None,
+                None
+            )]
+        );
+
+        // The rest of the record is discarded up to the next new-line.
+        for _ in 0..100 {
+            assert_eq!(consumer.input_fragment(b"xxxxxxxxxx"), Vec::new());
+        }
+        assert_eq!(consumer.input_fragment(b"xx\n2,false,,bar\n"), Vec::new());
+
+        // Complete records are also checked.
+        assert_eq!(
+            consumer.input_fragment(b"3,true,5,foooooooooo\n4,true,,x\n"),
+            vec![ParseError::new(
+                "CSV record exceeds the maximum length of 16 bytes".to_string(),
+                Some(4),
+                None,
+                Some("3,true,5,foooooooooo"),
+                None,
+                None
+            )]
+        );
+
+        // So is the incomplete record that follows the last new-line.
+        assert_eq!(
+            consumer.input_fragment(b"5,true,,y\nzzzzzzzzzzzzzzzzzz"),
+            vec![ParseError::new(
+                "CSV record exceeds the maximum length of 16 bytes".to_string(),
+                Some(7),
+                None,
+                Some("zzzzzzzzzzzzzzzzzz"),
+                None,
+                None
+            )]
+        );
+        assert_eq!(consumer.input_fragment(b"z\n6,false,,w\n"), Vec::new());
+        assert_eq!(consumer.eoi(), Vec::new());
+        assert_eq!(
+            outputs.state().flushed,
+            vec![
+                (
+                    TestStruct {
+                        id: 1,
+                        b: true,
+                        i: Some(5),
+                        s: "foo".to_string()
+                    },
+                    true
+                ),
+                (
+                    TestStruct {
+                        id: 2,
+                        b: false,
+                        i: None,
+                        s: "bar".to_string()
+                    },
+                    true
+                ),
+                (
+                    TestStruct {
+                        id: 4,
+                        b: true,
+                        i: None,
+                        s: "x".to_string()
+                    },
+                    true
+                ),
+                (
+                    TestStruct {
+                        id: 5,
+                        b: true,
+                        i: None,
+                        s: "y".to_string()
+                    },
+                    true
+                ),
+                (
+                    TestStruct {
+                        id: 6,
+                        b: false,
+                        i: None,
+                        s: "w".to_string()
+                    },
+                    true
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn test_float_precision() {
+        let encode = |float_precision| {
+            let consumer = MockOutputConsumer::new();
+            let data = consumer.data.clone();
+            let mut encoder = CsvEncoder::new(
+                Box::new(consumer),
+                CsvEncoderConfig {
+                    buffer_size_records: 10,
+                    include_weight: true,
+                    float_precision,
+                    header: false,
+                    rename_columns: Default::default(),
+                    column_order: None,
+                    max_record_len_in_errmsg: 4096,
+                    line_terminator: Default::default(),
+                },
+            );
+
+            let zset = OrdZSet::from_keys(
+                (),
+                vec![((F64::from(96.542000000001), F64::from(1.5)), 1i64)],
+            );
+            let batch = Arc::new(<SerBatchImpl<_, (F64, F64), ()>>::new(zset)) as Arc<dyn SerBatch>;
+            encoder.encode(&[batch]).unwrap();
+
+            let data = data.lock().unwrap();
+            std::str::from_utf8(&data).unwrap().to_string()
+        };
+
+        assert_eq!(encode(None), "96.542000000001,1.5,1\n");
+        assert_eq!(encode(Some(2)), "96.54,1.50,1\n");
+    }
+
+    #[test]
+    fn test_rename_columns() {
+        let consumer = MockOutputConsumer::new();
+        let data = consumer.data.clone();
+        let mut encoder = CsvEncoder::new(
+            Box::new(consumer),
+            CsvEncoderConfig {
+                buffer_size_records: 10,
+                include_weight: true,
+                float_precision: None,
+                header: true,
+                rename_columns: BTreeMap::from([
+                    ("id".to_string(), "ID".to_string()),
+                    ("s".to_string(), "name".to_string()),
+                ]),
+                column_order: None,
+                max_record_len_in_errmsg: 4096,
+                line_terminator: Default::default(),
+            },
+        );
+
+        // The header is only written once.
+        encoder.encode(&[weighted_batch(&[1])]).unwrap();
+        encoder.encode(&[weighted_batch(&[1, 2])]).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&data.lock().unwrap()).unwrap(),
+            "ID,b,i,name,weight\n0,false,0,bar,1\n0,false,0,bar,1\n1,false,1,bar,2\n"
+        );
+    }
+
+    #[test]
+    fn test_column_order() {
+        let encode = |column_order: &[&str]| {
+            let consumer = MockOutputConsumer::new();
+            let data = consumer.data.clone();
+            let mut encoder = CsvEncoder::new(
+                Box::new(consumer),
+                CsvEncoderConfig {
+                    buffer_size_records: 10,
+                    include_weight: true,
+                    float_precision: None,
+                    header: true,
+                    rename_columns: BTreeMap::from([("s".to_string(), "name".to_string())]),
+                    column_order: Some(column_order.iter().map(|c| c.to_string()).collect()),
+                    max_record_len_in_errmsg: 4096,
+                    line_terminator: Default::default(),
+                },
+            );
+
+            encoder.encode(&[weighted_batch(&[1, 2])])?;
+            let data = data.lock().unwrap();
+            Ok::<_, anyhow::Error>(std::str::from_utf8(&data).unwrap().to_string())
+        };
+
+        // Columns are referred to by their output names.  Unlisted columns
+        // follow in declared order, and the weight is always last.
+        assert_eq!(
+            encode(&["name", "i"]).unwrap(),
+            "name,i,id,b,weight\nbar,0,0,false,1\nbar,1,1,false,2\n"
+        );
+        assert_eq!(
+            encode(&["b", "name", "i", "id"]).unwrap(),
+            "b,name,i,id,weight\nfalse,bar,0,0,1\nfalse,bar,1,1,2\n"
+        );
+        assert!(encode(&["s"]).is_err());
+
+        // Records that don't serialize as structs have no column names.
+        let mut encoder = CsvEncoder::new(
+            Box::new(MockOutputConsumer::new()),
+            CsvEncoderConfig {
+                buffer_size_records: 10,
+                include_weight: true,
+                float_precision: None,
+                header: true,
+                rename_columns: Default::default(),
+                column_order: Some(vec!["id".to_string()]),
+                max_record_len_in_errmsg: 4096,
+                line_terminator: Default::default(),
+            },
+        );
+        let zset = OrdZSet::from_keys((), vec![(1u32, 1i64)]);
+        let batch = Arc::new(<SerBatchImpl<_, u32, ()>>::new(zset)) as Arc<dyn SerBatch>;
+        let error = encoder.encode(&[batch]).unwrap_err();
+        assert!(error.to_string().contains("named columns"), "{error}");
+    }
+
+    #[test]
+    fn test_line_terminator() {
+        let encode = |line_terminator| {
+            let consumer = MockOutputConsumer::new();
+            let data = consumer.data.clone();
+            let mut encoder = CsvEncoder::new(
+                Box::new(consumer),
+                CsvEncoderConfig {
+                    buffer_size_records: 2,
+                    include_weight: true,
+                    float_precision: None,
+                    header: true,
+                    rename_columns: Default::default(),
+                    column_order: None,
+                    max_record_len_in_errmsg: 4096,
+                    line_terminator,
+                },
+            );
+
+            // Records are split across several buffers and batches.
+            encoder.encode(&[test_batch(0..3)]).unwrap();
+            encoder.encode(&[test_batch(3..4)]).unwrap();
+
+            let data = data.lock().unwrap();
+            std::str::from_utf8(&data).unwrap().to_string()
+        };
+
+        assert_eq!(
+            encode(CsvLineTerminator::Crlf),
+            "id,b,i,s,weight\r\n0,true,,foo,1\r\n1,true,,foo,1\r\n2,true,,foo,1\r\n3,true,,foo,1\r\n"
+        );
+        assert_eq!(
+            encode(CsvLineTerminator::Custom(";".to_string())),
+            "id,b,i,s,weight;0,true,,foo,1;1,true,,foo,1;2,true,,foo,1;3,true,,foo,1;"
+        );
+
+        // Fields that contain the terminator are quoted.
+        let consumer = MockOutputConsumer::new();
+        let data = consumer.data.clone();
+        let mut encoder = CsvEncoder::new(
+            Box::new(consumer),
+            CsvEncoderConfig {
+                buffer_size_records: 2,
+                include_weight: false,
+                float_precision: None,
+                header: false,
+                rename_columns: Default::default(),
+                column_order: None,
+                max_record_len_in_errmsg: 4096,
+                line_terminator: CsvLineTerminator::Custom(";".to_string()),
+            },
+        );
+        let zset = OrdZSet::from_keys(
+            (),
+            vec![(
+                TestStruct {
+                    id: 0,
+                    b: true,
+                    i: None,
+                    s: "a;b".to_string(),
+                },
+                1,
+            )],
+        );
+        let batch = Arc::new(<SerBatchImpl<_, TestStruct, ()>>::new(zset)) as Arc<dyn SerBatch>;
+        encoder.encode(&[batch]).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&data.lock().unwrap()).unwrap(),
+            "0,true,,\"a;b\";"
+        );
+
+        // Multi-byte custom terminators are rejected.
+        let config = serde_yaml::to_value(CsvEncoderConfig {
+            buffer_size_records: 2,
+            include_weight: true,
+            float_precision: None,
+            header: true,
+            rename_columns: Default::default(),
+            column_order: None,
+            max_record_len_in_errmsg: 4096,
+            line_terminator: CsvLineTerminator::Custom(";\n".to_string()),
+        })
+        .unwrap();
+        assert!(CsvOutputFormat
+            .new_encoder("test", &config, Box::new(MockOutputConsumer::new()))
+            .is_err());
+    }
 }
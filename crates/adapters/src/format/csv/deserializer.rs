@@ -34,8 +34,11 @@ use serde::de::{
 };
 use serde::serde_if_integer128;
 
+use anyhow::{anyhow, Result as AnyResult};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use csv::StringRecordIter;
 use csv::{ByteRecord, ByteRecordIter, StringRecord};
+use pipeline_types::format::csv::{CsvParserConfig, TrimMode};
 
 use self::DeserializeErrorKind as DEK;
 
@@ -63,6 +66,99 @@ pub fn byte_record_deserializer<'de>(
     })
 }
 
+/// Parse `text` using the chrono format string `format` and convert it to
+/// the default string representation of the SQL `TIMESTAMP`, `DATE`, or
+/// `TIME` type, depending on which components the format contains.
+fn reformat_datetime(text: &str, format: &str) -> Option<String> {
+    if let Ok(timestamp) = NaiveDateTime::parse_from_str(text, format) {
+        Some(timestamp.format("%F %T%.f").to_string())
+    } else if let Ok(date) = NaiveDate::parse_from_str(text, format) {
+        Some(date.format("%Y-%m-%d").to_string())
+    } else {
+        NaiveTime::parse_from_str(text, format)
+            .ok()
+            .map(|time| time.format("%H:%M:%S%.f").to_string())
+    }
+}
+
+/// Apply whitespace trimming, per-column coercions, and date/time formats
+/// configured in `config` to the decoded fields of a single CSV record, and
+/// project it to the configured columns.
+///
+/// Returns the re-encoded record, ready to be passed to the record
+/// deserializer.  When `config.columns` is specified, the output record only
+/// contains the listed columns of the input record, in the listed order.
+/// Coercions are only applied to projected columns.  Fails if the record
+/// doesn't contain one of the listed columns or if a non-empty value in a
+/// column listed in `config.date_formats` doesn't match the format of the
+/// column.  Columns that are not valid UTF-8 are left unmodified.
+pub(crate) fn coerce_record(fields: &[&[u8]], config: &CsvParserConfig) -> AnyResult<Vec<u8>> {
+    let trim = config.trim == TrimMode::Fields;
+    let (coercions, date_formats) = (&config.coercions, &config.date_formats);
+
+    let projection = match config.columns.as_deref() {
+        Some(columns) => columns
+            .iter()
+            .map(|&column| match fields.get(column) {
+                Some(field) => Ok((column, *field)),
+                None => Err(anyhow!(
+                    "CSV record has {} columns, but column {column} is listed in 'columns'",
+                    fields.len()
+                )),
+            })
+            .collect::<AnyResult<Vec<_>>>()?,
+        None => fields.iter().copied().enumerate().collect(),
+    };
+
+    let mut coerced = ByteRecord::with_capacity(
+        fields.iter().map(|field| field.len()).sum(),
+        projection.len(),
+    );
+    for (column, field) in projection {
+        let field = if trim { trim_ascii(field) } else { field };
+        let strip_chars = coercions
+            .iter()
+            .filter(|coercion| coercion.column == column)
+            .flat_map(|coercion| coercion.strip_chars.chars())
+            .collect::<Vec<char>>();
+        match str::from_utf8(field) {
+            Ok(text) if !strip_chars.is_empty() || date_formats.contains_key(&column) => {
+                let mut text = text.replace(strip_chars.as_slice(), "");
+                if let Some(format) = date_formats.get(&column) {
+                    if !text.is_empty() {
+                        text = reformat_datetime(&text, format).ok_or_else(|| {
+                            anyhow!("value '{text}' in column {column} does not match the date/time format '{format}'")
+                        })?;
+                    }
+                }
+                coerced.push_field(text.as_bytes());
+            }
+            _ => coerced.push_field(field),
+        }
+    }
+
+    let mut writer = csv::WriterBuilder::new()
+        .flexible(true)
+        .from_writer(Vec::with_capacity(coerced.as_slice().len() + coerced.len()));
+    writer.write_byte_record(&coerced)?;
+    writer
+        .into_inner()
+        .map_err(|e| csv::Error::from(e.into_error()).into())
+}
+
+/// Remove leading and trailing ASCII whitespace from `field`.
+fn trim_ascii(field: &[u8]) -> &[u8] {
+    let start = field
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .unwrap_or(field.len());
+    let end = field
+        .iter()
+        .rposition(|byte| !byte.is_ascii_whitespace())
+        .map_or(start, |end| end + 1);
+    &field[start..end]
+}
+
 /// An over-engineered internal trait that permits writing a single Serde
 /// deserializer that works on both ByteRecord and StringRecord.
 ///
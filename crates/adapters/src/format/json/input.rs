@@ -2,9 +2,9 @@
 
 use super::{DebeziumUpdate, InsDelUpdate, WeightedUpdate};
 use crate::{
-    catalog::{DeCollectionStream, RecordFormat},
+    catalog::{DeCollectionStream, JsonOptions, RecordFormat},
     format::{InputFormat, ParseError, Parser},
-    util::split_on_newline,
+    util::split_on_byte,
     ControllerError, DeCollectionHandle,
 };
 use actix_web::HttpRequest;
@@ -179,8 +179,11 @@ impl InputFormat for JsonInputFormat {
             )
         })?;
         validate_parser_config(&config, endpoint_name)?;
-        let input_stream =
-            input_stream.configure_deserializer(RecordFormat::Json(config.json_flavor.clone()))?;
+        let record_format = RecordFormat::Json(JsonOptions {
+            on_overflow: config.on_overflow,
+            ..config.json_flavor.clone().into()
+        });
+        let input_stream = input_stream.configure_deserializer(record_format)?;
         Ok(Box::new(JsonParser::new(input_stream, config)) as Box<dyn Parser>)
     }
 
@@ -383,7 +386,7 @@ impl JsonParser {
 impl Parser for JsonParser {
     fn input_fragment(&mut self, data: &[u8]) -> (usize, Vec<ParseError>) {
         // println!("input_fragment {}", std::str::from_utf8(data).unwrap());
-        let leftover = split_on_newline(data);
+        let leftover = split_on_byte(data, b'\n');
 
         if leftover == 0 {
             // `data` doesn't contain a new-line character; append it to
@@ -433,7 +436,9 @@ mod test {
         DeserializeWithContext, FormatConfig, ParseError, SqlSerdeConfig,
     };
     use log::trace;
-    use pipeline_types::format::json::{JsonFlavor, JsonParserConfig, JsonUpdateFormat};
+    use pipeline_types::format::json::{
+        JsonFlavor, JsonOverflowPolicy, JsonParserConfig, JsonUpdateFormat,
+    };
     use std::{borrow::Cow, fmt::Debug};
 
     #[derive(PartialEq, Debug, Eq)]
@@ -535,6 +540,7 @@ mod test {
                     update_format: JsonUpdateFormat::Raw,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![(r#"{"b": true, "i": 0}"#.to_string(), Vec::new())],
                 vec![(TestStruct::new(true, 0, None), true)],
@@ -546,6 +552,7 @@ mod test {
                     update_format: JsonUpdateFormat::Raw,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![(r#"[true, 0, "a"]"#.to_string(), Vec::new())],
                 vec![(TestStruct::new(true, 0, Some("a")), true)],
@@ -557,6 +564,7 @@ mod test {
                     update_format: JsonUpdateFormat::Raw,
                     json_flavor: JsonFlavor::Default,
                     array: true,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![(r#"[{"b": true, "i": 0}]"#.to_string(), Vec::new())],
                 vec![(TestStruct::new(true, 0, None), true)],
@@ -568,6 +576,7 @@ mod test {
                     update_format: JsonUpdateFormat::Raw,
                     json_flavor: JsonFlavor::Default,
                     array: true,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![(r#"[[true, 0, "b"]]"#.to_string(), Vec::new())],
                 vec![(TestStruct::new(true, 0, Some("b")), true)],
@@ -580,6 +589,7 @@ mod test {
                     update_format: JsonUpdateFormat::Raw,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![(r#"{"b": true, "i": 0}{"b": false, "i": 100, "s": "foo"}"#.to_string(), Vec::new())],
                 vec![(TestStruct::new(true, 0, None), true), (TestStruct::new(false, 100, Some("foo")), true)],
@@ -591,6 +601,7 @@ mod test {
                     update_format: JsonUpdateFormat::Raw,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![(r#"[true, 0, "c"][false, 100, "foo"]"#.to_string(), Vec::new())],
                 vec![(TestStruct::new(true, 0, Some("c")), true), (TestStruct::new(false, 100, Some("foo")), true)],
@@ -602,6 +613,7 @@ mod test {
                     update_format: JsonUpdateFormat::Raw,
                     json_flavor: JsonFlavor::Default,
                     array: true,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![(r#"[{"b": true, "i": 0},{"b": false, "i": 100, "s": "foo"}]"#.to_string(), Vec::new())],
                 vec![(TestStruct::new(true, 0, None), true), (TestStruct::new(false, 100, Some("foo")), true)],
@@ -613,6 +625,7 @@ mod test {
                     update_format: JsonUpdateFormat::Raw,
                     json_flavor: JsonFlavor::Default,
                     array: true,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![(r#"[[true, 0, "d"],[false, 100, "foo"]]"#.to_string(), Vec::new())],
                 vec![(TestStruct::new(true, 0, Some("d")), true), (TestStruct::new(false, 100, Some("foo")), true)],
@@ -625,6 +638,7 @@ mod test {
                     update_format: JsonUpdateFormat::Raw,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"{"b": true, "i": 0}"#.to_string(), Vec::new())
                     , (r#"{"b": false, "i": 100, "s": "foo"}"#.to_string(), Vec::new())],
@@ -637,6 +651,7 @@ mod test {
                     update_format: JsonUpdateFormat::Raw,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"[true, 0, "e"]"#.to_string(), Vec::new())
                     , (r#"[false, 100, "foo"]"#.to_string(), Vec::new())],
@@ -649,6 +664,7 @@ mod test {
                     update_format: JsonUpdateFormat::Raw,
                     json_flavor: JsonFlavor::Default,
                     array: true,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"[{"b": true, "i": 0}]"#.to_string(), Vec::new())
                     , (r#"[{"b": false, "i": 100, "s": "foo"}]"#.to_string(), Vec::new())],
@@ -661,6 +677,7 @@ mod test {
                     update_format: JsonUpdateFormat::Raw,
                     json_flavor: JsonFlavor::Default,
                     array: true,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"[[true, 0, "e"]]"#.to_string(), Vec::new())
                     , (r#"[[false, 100, "foo"]]"#.to_string(), Vec::new())],
@@ -674,6 +691,7 @@ mod test {
                     update_format: JsonUpdateFormat::Raw,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"{"b": true, "i": 0}"#.to_string(), Vec::new())
                     , (r#"{"b": false, "i": 100, "s":"#.to_string(), vec![ParseError::text_envelope_error("failed to parse string as a JSON document: EOF while parsing a value at line 1 column 27".to_string(), "{\"b\": false, \"i\": 100, \"s\":", None)])],
@@ -686,6 +704,7 @@ mod test {
                     update_format: JsonUpdateFormat::Raw,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"[true, 0, "f"]"#.to_string(), Vec::new())
                     , (r#"[false, 100, "#.to_string(), vec![ParseError::text_envelope_error("failed to parse string as a JSON document: EOF while parsing a value at line 1 column 13".to_string(), "[false, 100, ", None)])],
@@ -698,6 +717,7 @@ mod test {
                     update_format: JsonUpdateFormat::Raw,
                     json_flavor: JsonFlavor::Default,
                     array: true,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"[{"b": true, "i": 0}]"#.to_string(), Vec::new())
                     , (r#"[{"b": false, "i": 100, "s":"#.to_string(), vec![ParseError::text_envelope_error("failed to parse string as a JSON document: EOF while parsing a value at line 1 column 28".to_string(), "[{\"b\": false, \"i\": 100, \"s\":", None)])],
@@ -710,6 +730,7 @@ mod test {
                     update_format: JsonUpdateFormat::Raw,
                     json_flavor: JsonFlavor::Default,
                     array: true,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"[[true, 0, "g"]]"#.to_string(), Vec::new())
                     , (r#"[[false, 100, "s":"#.to_string(), vec![ParseError::text_envelope_error("failed to parse string as a JSON document: expected `,` or `]` at line 1 column 18".to_string(), "[[false, 100, \"s\":", None)])],
@@ -723,6 +744,7 @@ mod test {
                     update_format: JsonUpdateFormat::Raw,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"{"b": true, "i": 0}"#.to_string(), Vec::new())
                     , (r#"{"b": false, "i": 5}{"b": false}{"b": false, "I": "hello"}"#.to_string(), vec![ParseError::new("failed to deserialize JSON record: missing field `I` at line 1 column 12".to_string(), Some(3), None, Some("{\"b\": false}"), None, None), ParseError::new("failed to deserialize JSON record: error parsing field 'I': invalid type: string \"hello\", expected i32 at line 1 column 25".to_string(), Some(4), Some("I".to_string()), Some("{\"b\": false, \"I\": \"hello\"}"), None, None)])],
@@ -735,6 +757,7 @@ mod test {
                     update_format: JsonUpdateFormat::Raw,
                     json_flavor: JsonFlavor::Default,
                     array: true,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"[{"b": true, "i": 0}]"#.to_string(), Vec::new())
                     , (r#"[{"b": false, "i": 5},{"b": false}]"#.to_string(), vec![ParseError::new("failed to deserialize JSON record: missing field `I` at line 1 column 12".to_string(), Some(3), None, Some("{\"b\": false}"), None, None)])
@@ -749,6 +772,7 @@ mod test {
                     update_format: JsonUpdateFormat::Raw,
                     json_flavor: JsonFlavor::Default,
                     array: true,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"[[true, 0, "h"]]"#.to_string(), Vec::new())
                     , (r#"[{"b": false, "i": 5},[false]]"#.to_string(), vec![ParseError::new("failed to deserialize JSON record: invalid length 1, expected 3 columns at line 1 column 7".to_string(), Some(3), None, Some("[false]"), None, None)])],
@@ -762,6 +786,7 @@ mod test {
                     update_format: JsonUpdateFormat::Raw,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"{"b": true, "i": 0}"#.to_string(), Vec::new())
                     , (r#"{"b": false, "i": 5}
@@ -776,6 +801,7 @@ mod test {
                     update_format: JsonUpdateFormat::Raw,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"[true, 0, "i"]"#.to_string(), Vec::new())
                     , (r#"{"b": false, "i": 5}
@@ -790,6 +816,7 @@ mod test {
                     update_format: JsonUpdateFormat::Raw,
                     json_flavor: JsonFlavor::Default,
                     array: true,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"[{"b": true, "i": 0}]"#.to_string(), Vec::new())
                     , (r#"[{"b": false, "i": 5}, {"b": false, "i":"#.to_string(), Vec::new())
@@ -804,6 +831,7 @@ mod test {
                     update_format: JsonUpdateFormat::Raw,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"{"b": true, "i": 0}"#.to_string(), Vec::new())
                     , (r#"{"b": false, "i": 5}
@@ -819,6 +847,7 @@ mod test {
                     update_format: JsonUpdateFormat::Raw,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"{"b": true, "i": 0}"#.to_string(), Vec::new())
                     , (r#"[false, 5, ""]
@@ -838,6 +867,7 @@ mod test {
                     update_format: JsonUpdateFormat::InsertDelete,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![(r#"{"insert": {"b": true, "i": 0}}"#.to_string(), Vec::new())],
                 vec![(TestStruct::new(true, 0, None), true)],
@@ -849,6 +879,7 @@ mod test {
                     update_format: JsonUpdateFormat::InsertDelete,
                     json_flavor: JsonFlavor::Default,
                     array: true,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![(r#"[{"insert": {"b": true, "i": 0}}]"#.to_string(), Vec::new())],
                 vec![(TestStruct::new(true, 0, None), true)],
@@ -861,6 +892,7 @@ mod test {
                     update_format: JsonUpdateFormat::InsertDelete,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![(r#"{"insert": {"b": true, "i": 0}}{"delete": {"b": false, "i": 100, "s": "foo"}}"#.to_string(), Vec::new())],
                 vec![(TestStruct::new(true, 0, None), true), (TestStruct::new(false, 100, Some("foo")), false)],
@@ -872,6 +904,7 @@ mod test {
                     update_format: JsonUpdateFormat::InsertDelete,
                     json_flavor: JsonFlavor::Default,
                     array: true,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![(r#"[{"insert": {"b": true, "i": 0}}, {"delete": {"b": false, "i": 100, "s": "foo"}}]"#.to_string(), Vec::new())],
                 vec![(TestStruct::new(true, 0, None), true), (TestStruct::new(false, 100, Some("foo")), false)],
@@ -883,6 +916,7 @@ mod test {
                     update_format: JsonUpdateFormat::InsertDelete,
                     json_flavor: JsonFlavor::Default,
                     array: true,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![(r#"[{"insert": [true, 0, "a"]}, {"delete": {"b": false, "i": 100, "s": "foo"}}]"#.to_string(), Vec::new())],
                 vec![(TestStruct::new(true, 0, Some("a")), true), (TestStruct::new(false, 100, Some("foo")), false)],
@@ -895,6 +929,7 @@ mod test {
                     update_format: JsonUpdateFormat::InsertDelete,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"{"insert": {"b": true, "i": 0}}"#.to_string(), Vec::new())
                     , (r#"{"delete": {"b": false, "i": 100, "s": "foo"}}"#.to_string(), Vec::new())],
@@ -908,6 +943,7 @@ mod test {
                     update_format: JsonUpdateFormat::InsertDelete,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"{"insert": {"b": true, "i": 0}}"#.to_string(), Vec::new())
                     , (r#"{"delete": {"b": false, "i": 100, "s":"#.to_string(), vec![ParseError::text_envelope_error("failed to parse string as a JSON document: EOF while parsing a value at line 1 column 38".to_string(), "{\"delete\": {\"b\": false, \"i\": 100, \"s\":", None)])],
@@ -920,6 +956,7 @@ mod test {
                     update_format: JsonUpdateFormat::InsertDelete,
                     json_flavor: JsonFlavor::Default,
                     array: true,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"[{"insert": {"b": true, "i": 0}}]"#.to_string(), Vec::new())
                     , (r#"[{"delete": {"b": false, "i": 100, "s":"#.to_string(), vec![ParseError::text_envelope_error("failed to parse string as a JSON document: EOF while parsing a value at line 1 column 39".to_string(), "[{\"delete\": {\"b\": false, \"i\": 100, \"s\":", None)])],
@@ -933,6 +970,7 @@ mod test {
                     update_format: JsonUpdateFormat::InsertDelete,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"{"insert": {"b": true, "i": 0}}"#.to_string(), Vec::new())
                     , (r#"{"insert": {"b": false, "i": 5}}{"delete": {"b": false}}"#.to_string(), vec![ParseError::new("failed to deserialize JSON record: missing field `I` at line 1 column 12".to_string(), Some(3), None, Some("{\"b\": false}"), None, None)])],
@@ -945,6 +983,7 @@ mod test {
                     update_format: JsonUpdateFormat::InsertDelete,
                     json_flavor: JsonFlavor::Default,
                     array: true,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"[{"insert": {"b": true, "i": 0}}]"#.to_string(), Vec::new())
                     , (r#"[{"insert": {"b": false, "i": 5}},{"delete": {"b": false}}]"#.to_string(), vec![ParseError::new("failed to deserialize JSON record: missing field `I` at line 1 column 12".to_string(), Some(3), None, Some("{\"b\": false}"), None, None)])],
@@ -957,6 +996,7 @@ mod test {
                     update_format: JsonUpdateFormat::InsertDelete,
                     json_flavor: JsonFlavor::Default,
                     array: true,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"[{"insert": {"b": true, "i": 0}}]"#.to_string(), Vec::new())
                     , (r#"[{"insert": {"b": false, "i": 5}},{"delete": {"b": false}}]"#.to_string(), vec![ParseError::new("failed to deserialize JSON record: missing field `I` at line 1 column 12".to_string(), Some(3), None, Some("{\"b\": false}"), None, None)])
@@ -973,6 +1013,7 @@ mod test {
                     update_format: JsonUpdateFormat::InsertDelete,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"{"insert": {"b": true, "i": 0}}"#.to_string(), Vec::new())
                     , (r#"{"insert": {"b": false, "i": 5}}
@@ -987,6 +1028,7 @@ mod test {
                     update_format: JsonUpdateFormat::InsertDelete,
                     json_flavor: JsonFlavor::Default,
                     array: true,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"[{"insert": {"b": true, "i": 0}}]"#.to_string(), Vec::new())
                     , (r#"[{"insert": {"b": false, "i": 5}}, {"delete": {"b": false, "i":"#.to_string(), Vec::new())
@@ -1001,6 +1043,7 @@ mod test {
                     update_format: JsonUpdateFormat::InsertDelete,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"{"insert": {"b": true, "i": 0}}"#.to_string(), Vec::new())
                     , (r#"{"insert": {"b": false, "i": 5}}
@@ -1016,6 +1059,7 @@ mod test {
                     update_format: JsonUpdateFormat::InsertDelete,
                     json_flavor: JsonFlavor::Default,
                     array: true,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"[{"insert": {"b": true, "i": 0}}]"#.to_string(), Vec::new())
                     , (r#"[{"insert": {"b": false, "i": 5}},{"delete""#.to_string(), Vec::new())
@@ -1030,6 +1074,7 @@ mod test {
                     update_format: JsonUpdateFormat::InsertDelete,
                     json_flavor: JsonFlavor::Default,
                     array: true,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"[{"insert": [true, 0, "a"]}]"#.to_string(), Vec::new())
                     , (r#"[{"insert": [false, 5, "b"]},{"delete""#.to_string(), Vec::new())
@@ -1048,6 +1093,7 @@ mod test {
                     update_format: JsonUpdateFormat::Debezium,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![(r#"{"payload": {"op": "c", "after": {"b": true, "i": 0}}}"#.to_string(), Vec::new())],
                 vec![(TestStruct::new(true, 0, None), true)],
@@ -1060,6 +1106,7 @@ mod test {
                     update_format: JsonUpdateFormat::Debezium,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![(r#"{"payload": {"op": "u", "before": {"b": true, "i": 123}, "after": {"b": true, "i": 0}}}"#.to_string(), Vec::new())],
                 vec![(TestStruct::new(true, 123, None), false), (TestStruct::new(true, 0, None), true)],
@@ -1071,6 +1118,7 @@ mod test {
                     update_format: JsonUpdateFormat::Debezium,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![(r#"{"payload": {"op": "u", "before": [true, 123, "abc"], "after": [true, 0, "def"]}}"#.to_string(), Vec::new())],
                 vec![(TestStruct::new(true, 123, Some("abc")), false), (TestStruct::new(true, 0, Some("def")), true)],
@@ -1083,6 +1131,7 @@ mod test {
                     update_format: JsonUpdateFormat::Debezium,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![(r#"{"payload": {"op": "c", "after": {"b": true, "i": 0}}}{"payload": {"op": "d", "before": {"b": false, "i": 100, "s": "foo"}}}"#.to_string(), Vec::new())],
                 vec![(TestStruct::new(true, 0, None), true), (TestStruct::new(false, 100, Some("foo")), false)],
@@ -1095,6 +1144,7 @@ mod test {
                     update_format: JsonUpdateFormat::Debezium,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"{"payload": {"op": "c", "after": {"b": true, "i": 0}}}"#.to_string(), Vec::new())
                     , (r#"{"payload": {"op": "d", "before": {"b": false, "i": 100, "s": "foo"}}}"#.to_string(), Vec::new())],
@@ -1108,6 +1158,7 @@ mod test {
                     update_format: JsonUpdateFormat::Debezium,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"{"payload": {"op": "c", "after": {"b": true, "i": 0}}}"#.to_string(), Vec::new())
                     , (r#"{"payload": {"op": "d", "before": {"b": false, "i": 100, "s":"#.to_string(), vec![ParseError::text_envelope_error("failed to parse string as a JSON document: EOF while parsing a value at line 1 column 61".to_string(), "{\"payload\": {\"op\": \"d\", \"before\": {\"b\": false, \"i\": 100, \"s\":", None)])],
@@ -1121,6 +1172,7 @@ mod test {
                     update_format: JsonUpdateFormat::Debezium,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"{"payload": {"op": "c", "after": {"b": true, "i": 0}}}"#.to_string(), Vec::new())
                     , (r#"{"payload": {"op": "c", "after": {"b": false, "i": 5}}}{"payload": {"op": "d", "before": {"b": false}}}"#.to_string(), vec![ParseError::new("failed to deserialize JSON record: missing field `I` at line 1 column 12".to_string(), Some(3), None, Some("{\"b\": false}"), None, None)])],
@@ -1134,6 +1186,7 @@ mod test {
                     update_format: JsonUpdateFormat::Debezium,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"{"payload": {"op": "c", "after": {"b": true, "i": 0}}}"#.to_string(), Vec::new())
                     , (r#"{"payload": {"op": "c", "after": {"b": false, "i": 5}}}
@@ -1149,6 +1202,7 @@ mod test {
                     update_format: JsonUpdateFormat::Debezium,
                     json_flavor: JsonFlavor::Default,
                     array: false,
+                    on_overflow: JsonOverflowPolicy::Error,
                 },
                 vec![ (r#"{"payload": {"op": "c", "after": {"b": true, "i": 0}}}"#.to_string(), Vec::new())
                     , (r#"{"payload": {"op": "c", "after": {"b": false, "i": 5}}}
@@ -1,18 +1,21 @@
 use crate::{
-    catalog::{CursorWithPolarity, RecordFormat, SerBatch, SerCursor},
+    catalog::{CursorWithPolarity, JsonOptions, RecordFormat, SerBatch, SerCursor},
+    format::{MAX_PENDING_BUFFERS, MAX_RECORD_LEN_IN_ERRMSG},
     util::truncate_ellipse,
-    ControllerError, Encoder, OutputConsumer, OutputFormat,
+    ControllerError, Encoder, OutputConsumer, OutputFormat, PushStatus,
 };
 use actix_web::HttpRequest;
 use anyhow::{bail, Result as AnyResult};
 use erased_serde::Serialize as ErasedSerialize;
-use pipeline_types::format::json::{JsonEncoderConfig, JsonFlavor, JsonUpdateFormat};
+use pipeline_types::format::json::{
+    JsonEncoderConfig, JsonFlavor, JsonOverflowPolicy, JsonUpdateFormat,
+};
 use rand::{rngs::StdRng, Rng, SeedableRng};
 use serde::Deserialize;
 use serde_urlencoded::Deserializer as UrlDeserializer;
 use serde_yaml::Value as YamlValue;
 use std::io::Write;
-use std::{borrow::Cow, mem::take, sync::Arc};
+use std::{borrow::Cow, collections::VecDeque, mem::take, sync::Arc};
 
 /// JSON format encoder.
 pub struct JsonOutputFormat;
@@ -23,10 +26,6 @@ pub struct JsonOutputFormat;
 /// weights (and is most likely not what the user intends).
 const MAX_DUPLICATES: i64 = 1_000_000;
 
-/// When including a long JSON record in an error message,
-/// truncate it to `MAX_RECORD_LEN_IN_ERRMSG` bytes.
-static MAX_RECORD_LEN_IN_ERRMSG: usize = 4096;
-
 impl OutputFormat for JsonOutputFormat {
     fn name(&self) -> Cow<'static, str> {
         Cow::Borrowed("json")
@@ -106,6 +105,10 @@ struct JsonEncoder {
     stream_id: u64,
     /// Sequence number of the last record produced by this encoder.
     seq_number: u64,
+
+    /// Encoded buffers rejected by the consumer with
+    /// [`PushStatus::WouldBlock`], waiting to be pushed again.
+    pending: VecDeque<Vec<u8>>,
 }
 
 impl JsonEncoder {
@@ -128,6 +131,25 @@ impl JsonEncoder {
             // id into a negative number.
             stream_id: StdRng::from_entropy().gen_range(0..i64::MAX) as u64,
             seq_number: 0,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Push `buffer` to the consumer.
+    ///
+    /// Queues the buffer if the consumer is blocked or if there are older
+    /// buffers waiting to be pushed.  Queued buffers are retried by
+    /// `flush_pending`.  Once [`MAX_PENDING_BUFFERS`] buffers are queued,
+    /// waits for the consumer to accept the oldest one.
+    fn push_buffer(&mut self, buffer: &[u8]) {
+        if !self.pending.is_empty()
+            || self.output_consumer.try_push_buffer(buffer) == PushStatus::WouldBlock
+        {
+            if self.pending.len() >= MAX_PENDING_BUFFERS {
+                let oldest = self.pending.pop_front().unwrap();
+                self.output_consumer.push_buffer(&oldest);
+            }
+            self.pending.push_back(buffer.to_vec());
         }
     }
 }
@@ -138,6 +160,9 @@ impl Encoder for JsonEncoder {
     }
 
     fn encode(&mut self, batches: &[Arc<dyn SerBatch>]) -> AnyResult<()> {
+        // Give pending buffers a chance to go out before new data.
+        self.flush_pending()?;
+
         let mut buffer = take(&mut self.buffer);
 
         // Reserve one extra byte for the closing bracket `]`.
@@ -149,9 +174,13 @@ impl Encoder for JsonEncoder {
 
         let mut num_records = 0;
         for batch in batches.iter() {
-            let mut cursor = CursorWithPolarity::new(
-                batch.cursor(RecordFormat::Json(self.config.json_flavor.clone().unwrap()))?,
-            );
+            let mut cursor =
+                CursorWithPolarity::new(batch.cursor(RecordFormat::Json(JsonOptions {
+                    flavor: self.config.json_flavor.clone().unwrap(),
+                    rename_columns: self.config.rename_columns.clone(),
+                    float_precision: self.config.float_precision,
+                    on_overflow: JsonOverflowPolicy::Error,
+                }))?);
 
             while cursor.key_valid() {
                 if !cursor.val_valid() {
@@ -260,7 +289,7 @@ impl Encoder for JsonEncoder {
                         //     "push_buffer: {} bytes",
                         //     buffer.len() /*std::str::from_utf8(&buffer).unwrap()*/
                         // );
-                        self.output_consumer.push_buffer(&buffer);
+                        self.push_buffer(&buffer);
                         buffer.clear();
                         num_records = 0;
                     }
@@ -274,7 +303,7 @@ impl Encoder for JsonEncoder {
             if self.config.array {
                 buffer.extend_from_slice(b"]\n");
             }
-            self.output_consumer.push_buffer(&buffer);
+            self.push_buffer(&buffer);
             buffer.clear();
         }
 
@@ -282,6 +311,17 @@ impl Encoder for JsonEncoder {
 
         Ok(())
     }
+
+    fn flush_pending(&mut self) -> AnyResult<bool> {
+        while let Some(buffer) = self.pending.front() {
+            if self.output_consumer.try_push_buffer(buffer) == PushStatus::WouldBlock {
+                return Ok(false);
+            }
+            self.pending.pop_front();
+        }
+
+        Ok(true)
+    }
 }
 
 #[cfg(test)]
@@ -295,16 +335,20 @@ mod test {
             Encoder,
         },
         static_compile::seroutput::SerBatchImpl,
-        test::{MockOutputConsumer, TestStruct},
+        test::{BlockingOutputConsumer, MockOutputConsumer, TestStruct},
     };
-    use dbsp::{trace::Batch, IndexedZSet, OrdZSet};
+    use dbsp::{algebra::F64, trace::Batch, IndexedZSet, OrdZSet};
     use log::trace;
     use pipeline_types::format::json::JsonUpdateFormat;
     use proptest::prelude::*;
     use serde::Deserialize;
     use std::cell::RefCell;
     use std::rc::Rc;
-    use std::{fmt::Debug, sync::Arc};
+    use std::{
+        collections::BTreeMap,
+        fmt::Debug,
+        sync::{atomic::Ordering, Arc},
+    };
 
     trait OutputUpdate: Debug + for<'de> Deserialize<'de> + Eq + Ord {
         type Val;
@@ -367,15 +411,25 @@ mod test {
     fn test_json<U: OutputUpdate<Val = TestStruct>>(
         array: bool,
         batches: Vec<Vec<(TestStruct, i64)>>,
+    ) {
+        test_json_with_buffer_size::<U>(array, batches, usize::MAX)
+    }
+
+    fn test_json_with_buffer_size<U: OutputUpdate<Val = TestStruct>>(
+        array: bool,
+        batches: Vec<Vec<(TestStruct, i64)>>,
+        max_buffer_size: usize,
     ) {
         let config = JsonEncoderConfig {
             update_format: U::update_format(),
             json_flavor: None,
             buffer_size_records: 3,
             array,
+            rename_columns: Default::default(),
+            float_precision: None,
         };
 
-        let consumer = MockOutputConsumer::new();
+        let consumer = MockOutputConsumer::with_max_buffer_size_bytes(max_buffer_size);
         let consumer_data = consumer.data.clone();
         let mut encoder = JsonEncoder::new(Box::new(consumer), config);
         let zsets = batches
@@ -543,6 +597,8 @@ mod test {
             json_flavor: None,
             buffer_size_records: 3,
             array: false,
+            rename_columns: Default::default(),
+            float_precision: None,
         };
 
         let consumer = MockOutputConsumer::with_max_buffer_size_bytes(32);
@@ -555,6 +611,131 @@ mod test {
         assert_eq!(format!("{err}"), "JSON record exceeds maximum buffer size supported by the output transport. Max supported buffer size is 32 bytes, but the following record requires 46 bytes: '{\"delete\":{\"id\":1,\"b\":false,\"i\":10,\"s\":\"bar\"}}'.");
     }
 
+    #[test]
+    fn test_rename_columns() {
+        let config = JsonEncoderConfig {
+            update_format: JsonUpdateFormat::InsertDelete,
+            json_flavor: None,
+            buffer_size_records: 3,
+            array: false,
+            rename_columns: BTreeMap::from([
+                ("id".to_string(), "ID".to_string()),
+                ("s".to_string(), "name".to_string()),
+            ]),
+            float_precision: None,
+        };
+
+        let consumer = MockOutputConsumer::new();
+        let consumer_data = consumer.data.clone();
+        let mut encoder = JsonEncoder::new(Box::new(consumer), config);
+        let zset = OrdZSet::from_keys(
+            (),
+            vec![(
+                TestStruct {
+                    id: 1,
+                    b: true,
+                    i: None,
+                    s: "foo".to_string(),
+                },
+                1,
+            )],
+        );
+
+        encoder
+            .encode(&[Arc::new(<SerBatchImpl<_, TestStruct, ()>>::new(zset)) as Arc<dyn SerBatch>])
+            .unwrap();
+        assert_eq!(
+            std::str::from_utf8(&consumer_data.lock().unwrap())
+                .unwrap()
+                .trim_end(),
+            r#"{"insert":{"ID":1,"b":true,"i":null,"name":"foo"}}"#
+        );
+    }
+
+    #[test]
+    fn test_float_precision() {
+        let encode = |float_precision| {
+            let consumer = MockOutputConsumer::new();
+            let consumer_data = consumer.data.clone();
+            let mut encoder = JsonEncoder::new(
+                Box::new(consumer),
+                JsonEncoderConfig {
+                    update_format: JsonUpdateFormat::InsertDelete,
+                    json_flavor: None,
+                    buffer_size_records: 3,
+                    array: false,
+                    rename_columns: Default::default(),
+                    float_precision,
+                },
+            );
+
+            let zset =
+                OrdZSet::from_keys((), vec![((F64::from(96.542000000001), F64::from(1.5)), 1)]);
+            encoder
+                .encode(&[
+                    Arc::new(<SerBatchImpl<_, (F64, F64), ()>>::new(zset)) as Arc<dyn SerBatch>
+                ])
+                .unwrap();
+
+            let data = consumer_data.lock().unwrap();
+            std::str::from_utf8(&data).unwrap().trim_end().to_string()
+        };
+
+        assert_eq!(encode(None), r#"{"insert":[96.542000000001,1.5]}"#);
+        // Floats are written as numbers rather than strings.
+        assert_eq!(encode(Some(2)), r#"{"insert":[96.54,1.50]}"#);
+    }
+
+    #[test]
+    fn test_backpressure() {
+        let config = JsonEncoderConfig {
+            update_format: JsonUpdateFormat::InsertDelete,
+            json_flavor: None,
+            buffer_size_records: 1,
+            array: false,
+            rename_columns: Default::default(),
+            float_precision: None,
+        };
+
+        let consumer = BlockingOutputConsumer::new();
+        let consumer_data = consumer.data.clone();
+        let blocked = consumer.blocked.clone();
+        let mut encoder = JsonEncoder::new(Box::new(consumer), config);
+        let zset = OrdZSet::from_keys((), test_data()[1].clone());
+        let batch = Arc::new(<SerBatchImpl<_, TestStruct, ()>>::new(zset)) as Arc<dyn SerBatch>;
+
+        // The consumer is blocked: all buffers are queued.
+        encoder.encode(&[batch.clone()]).unwrap();
+        assert!(!encoder.flush_pending().unwrap());
+        assert!(consumer_data.lock().unwrap().is_empty());
+
+        // Drain the queue in order once the consumer unblocks.
+        blocked.store(false, Ordering::Release);
+        assert!(encoder.flush_pending().unwrap());
+        let expected = std::str::from_utf8(&consumer_data.lock().unwrap())
+            .unwrap()
+            .to_string();
+
+        let consumer = MockOutputConsumer::new();
+        let unblocked_data = consumer.data.clone();
+        let mut encoder = JsonEncoder::new(
+            Box::new(consumer),
+            JsonEncoderConfig {
+                update_format: JsonUpdateFormat::InsertDelete,
+                json_flavor: None,
+                buffer_size_records: 1,
+                array: false,
+                rename_columns: Default::default(),
+                float_precision: None,
+            },
+        );
+        encoder.encode(&[batch]).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&unblocked_data.lock().unwrap()).unwrap(),
+            expected
+        );
+    }
+
     #[test]
     fn test_ndjson_insdel() {
         test_json::<InsDelUpdate<TestStruct>>(false, test_data());
@@ -565,6 +746,18 @@ mod test {
         test_json::<InsDelUpdate<TestStruct>>(true, test_data());
     }
 
+    // Records that don't fit in the current buffer are deferred to the next
+    // buffer.
+    #[test]
+    fn test_ndjson_insdel_small_buffer() {
+        test_json_with_buffer_size::<InsDelUpdate<TestStruct>>(false, test_data(), 128);
+    }
+
+    #[test]
+    fn test_arrayjson_insdel_small_buffer() {
+        test_json_with_buffer_size::<InsDelUpdate<TestStruct>>(true, test_data(), 128);
+    }
+
     #[test]
     fn test_ndjson_snowflake() {
         test_json::<SnowflakeUpdate<TestStruct>>(false, test_data());
@@ -1,27 +1,47 @@
-use crate::{catalog::SerBatch, ControllerError, DeCollectionHandle, FieldParseError};
+use crate::{
+    catalog::SerBatch, util::truncate_ellipse, ControllerError, DeCollectionHandle, FieldParseError,
+};
 use actix_web::HttpRequest;
 use anyhow::Result as AnyResult;
 use erased_serde::Serialize as ErasedSerialize;
 use once_cell::sync::Lazy;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
 use serde_yaml::Value as YamlValue;
 use std::{
     borrow::Cow,
     collections::BTreeMap,
     error::Error as StdError,
     fmt::{Display, Error as FmtError, Formatter},
-    sync::Arc,
+    sync::{Arc, RwLock},
 };
+use tracing::warn;
 
 pub(crate) mod csv;
 mod json;
+mod parquet;
+mod partitioned;
+mod protobuf;
+mod tee;
 
 pub use self::csv::{byte_record_deserializer, string_record_deserializer};
+pub use self::partitioned::{PartitionFunc, PartitionedEncoder};
+pub use self::tee::{tee, TeeEncoder};
 use self::{
     csv::{CsvInputFormat, CsvOutputFormat},
     json::{JsonInputFormat, JsonOutputFormat},
+    parquet::ParquetOutputFormat,
+    protobuf::ProtobufInputFormat,
 };
 
+/// Maximum number of characters of the invalid input included in the
+/// `tracing` events emitted by [`ParseError::trace_event`].
+const MAX_TRACED_RECORD_LEN: usize = 256;
+
+/// When including a long record in an error message,
+/// truncate it to `MAX_RECORD_LEN_IN_ERRMSG` bytes.
+pub(crate) static MAX_RECORD_LEN_IN_ERRMSG: usize = 4096;
+
 /// Error parsing input data.
 #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
 #[serde(transparent)]
@@ -111,6 +131,100 @@ impl ParseError {
             suggestion,
         )))
     }
+
+    /// Set the category of the error.
+    pub fn with_kind(mut self, kind: ParseErrorKind) -> Self {
+        self.0.kind = kind;
+        self
+    }
+
+    /// Set the index (starting from 0) of the field that failed to parse.
+    pub fn with_field_index(mut self, field_index: Option<usize>) -> Self {
+        self.0.field_index = field_index;
+        self
+    }
+
+    /// Category of the error.
+    pub fn kind(&self) -> ParseErrorKind {
+        self.0.kind
+    }
+
+    /// Event number relative to the start of the stream, if known.
+    pub fn event_number(&self) -> Option<u64> {
+        self.0.event_number
+    }
+
+    /// Name of the field that failed to parse, if known.
+    pub fn field(&self) -> Option<&str> {
+        self.0.field.as_deref()
+    }
+
+    /// Index (starting from 0) of the field that failed to parse, if known.
+    pub fn field_index(&self) -> Option<usize> {
+        self.0.field_index
+    }
+
+    /// Structured representation of the error for error sinks.
+    ///
+    /// Unlike the serialized form of the error returned by the REST API, it
+    /// also includes the error kind and field index.
+    pub fn to_json(&self) -> JsonValue {
+        let mut json = serde_json::to_value(self).unwrap_or_default();
+        if let JsonValue::Object(fields) = &mut json {
+            fields.insert(
+                "kind".to_string(),
+                serde_json::to_value(self.0.kind).unwrap_or_default(),
+            );
+            fields.insert("field_index".to_string(), self.0.field_index.into());
+        }
+        json
+    }
+
+    /// Emit the error as a structured `tracing` event.
+    ///
+    /// The event is logged at the `WARN` level with the error description as
+    /// its message and the name of the input endpoint, the event number, the
+    /// error kind, the field, and the invalid input as separate fields, so
+    /// that parse errors can be searched in log aggregators.  The invalid
+    /// input is truncated to `MAX_TRACED_RECORD_LEN` characters.
+    pub fn trace_event(&self, endpoint_name: &str) {
+        let record = if let Some(invalid_text) = &self.0.invalid_text {
+            Some(truncate_ellipse(invalid_text, MAX_TRACED_RECORD_LEN, "...").into_owned())
+        } else {
+            self.0.invalid_bytes.as_ref().map(|invalid_bytes| {
+                let len = invalid_bytes.len().min(MAX_TRACED_RECORD_LEN);
+                format!("{:?}", &invalid_bytes[..len])
+            })
+        };
+
+        warn!(
+            endpoint = endpoint_name,
+            event_number = self.event_number(),
+            kind = ?self.kind(),
+            field = self.field(),
+            record = record.as_deref(),
+            "{}",
+            self.0.description
+        );
+    }
+}
+
+/// Category of a [`ParseError`].
+#[derive(Clone, Copy, Debug, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ParseErrorKind {
+    /// Input data could not be deserialized into a record.
+    Deserialization,
+
+    /// A record has an unexpected number of columns.
+    ColumnCount,
+
+    /// Input data is not valid in the expected character encoding.
+    Encoding,
+
+    /// Uncategorized error.
+    #[default]
+    Other,
 }
 
 #[derive(Clone, Debug, Serialize, PartialEq, Eq)]
@@ -118,6 +232,13 @@ pub struct ParseErrorInner {
     /// Error description.
     description: String,
 
+    /// Error category.
+    ///
+    /// Not serialized, so that the error reported by the REST API keeps its
+    /// shape; see [`ParseError::to_json`].
+    #[serde(skip)]
+    kind: ParseErrorKind,
+
     /// Event number relative to the start of the stream.
     ///
     /// An input stream is a series data change events (row insertions,
@@ -133,6 +254,14 @@ pub struct ParseErrorInner {
     /// specific field.
     field: Option<String>,
 
+    /// Index (starting from 0) of the field that failed to parse.
+    ///
+    /// Only set when the parsing error can be attributed to a specific
+    /// field and the format identifies fields by position, e.g., CSV.
+    /// Not serialized, like `kind`.
+    #[serde(skip)]
+    field_index: Option<usize>,
+
     /// Invalid fragment of input data.
     ///
     /// Used for binary data formats and for text-based formats when the input
@@ -190,8 +319,10 @@ impl ParseErrorInner {
     ) -> Self {
         Self {
             description,
+            kind: ParseErrorKind::Other,
             event_number,
             field,
+            field_index: None,
             invalid_text: invalid_text.map(str::to_string),
             invalid_bytes: invalid_bytes.map(ToOwned::to_owned),
             suggestion,
@@ -297,23 +428,91 @@ impl ParseErrorInner {
     }
 }
 
-/// Static map of supported input formats.
-// TODO: support for registering new formats at runtime in order to allow
-// external crates to implement new formats.
-static INPUT_FORMATS: Lazy<BTreeMap<&'static str, Box<dyn InputFormat>>> = Lazy::new(|| {
-    BTreeMap::from([
-        ("csv", Box::new(CsvInputFormat) as Box<dyn InputFormat>),
-        ("json", Box::new(JsonInputFormat) as Box<dyn InputFormat>),
-    ])
-});
-
-/// Static map of supported output formats.
-static OUTPUT_FORMATS: Lazy<BTreeMap<&'static str, Box<dyn OutputFormat>>> = Lazy::new(|| {
-    BTreeMap::from([
-        ("csv", Box::new(CsvOutputFormat) as Box<dyn OutputFormat>),
-        ("json", Box::new(JsonOutputFormat) as Box<dyn OutputFormat>),
-    ])
-});
+/// Registry of data formats available to the controller.
+///
+/// Formats are referenced by name in endpoint configurations.  The registry
+/// maps these names to [`InputFormat`] and [`OutputFormat`] implementations.
+/// It is pre-populated with formats implemented in this crate.  External
+/// crates can add their own formats at runtime using
+/// [`register_input`](`Self::register_input`) and
+/// [`register_output`](`Self::register_output`).
+///
+/// The process-wide registry instance is returned by
+/// [`FormatRegistry::global`].
+pub struct FormatRegistry {
+    input_formats: RwLock<BTreeMap<String, &'static dyn InputFormat>>,
+    output_formats: RwLock<BTreeMap<String, &'static dyn OutputFormat>>,
+}
+
+static FORMAT_REGISTRY: Lazy<FormatRegistry> = Lazy::new(FormatRegistry::new);
+
+impl FormatRegistry {
+    /// Create a registry that contains built-in formats.
+    fn new() -> Self {
+        let registry = Self {
+            input_formats: RwLock::new(BTreeMap::new()),
+            output_formats: RwLock::new(BTreeMap::new()),
+        };
+
+        registry.register_input("csv", Box::new(CsvInputFormat));
+        registry.register_input("json", Box::new(JsonInputFormat));
+        registry.register_input("protobuf", Box::new(ProtobufInputFormat));
+
+        registry.register_output("csv", Box::new(CsvOutputFormat));
+        registry.register_output("json", Box::new(JsonOutputFormat));
+        registry.register_output("parquet", Box::new(ParquetOutputFormat));
+
+        registry
+    }
+
+    /// Process-wide format registry.
+    pub fn global() -> &'static Self {
+        &FORMAT_REGISTRY
+    }
+
+    /// Register input format under `name`.
+    ///
+    /// Replaces any existing format with the same name.  Returns the
+    /// previously registered format, if any.
+    ///
+    /// Registered formats are never deallocated, as parsers and endpoints
+    /// may keep referring to them for the lifetime of the process.
+    pub fn register_input(
+        &self,
+        name: &str,
+        format: Box<dyn InputFormat>,
+    ) -> Option<&'static dyn InputFormat> {
+        self.input_formats
+            .write()
+            .unwrap()
+            .insert(name.to_string(), Box::leak(format))
+    }
+
+    /// Register output format under `name`.
+    ///
+    /// Replaces any existing format with the same name.  Returns the
+    /// previously registered format, if any.
+    pub fn register_output(
+        &self,
+        name: &str,
+        format: Box<dyn OutputFormat>,
+    ) -> Option<&'static dyn OutputFormat> {
+        self.output_formats
+            .write()
+            .unwrap()
+            .insert(name.to_string(), Box::leak(format))
+    }
+
+    /// Lookup input format by name.
+    pub fn input_format(&self, name: &str) -> Option<&'static dyn InputFormat> {
+        self.input_formats.read().unwrap().get(name).copied()
+    }
+
+    /// Lookup output format by name.
+    pub fn output_format(&self, name: &str) -> Option<&'static dyn OutputFormat> {
+        self.output_formats.read().unwrap().get(name).copied()
+    }
+}
 
 /// Trait that represents a specific data format.
 ///
@@ -357,9 +556,9 @@ pub trait InputFormat: Send + Sync {
 }
 
 impl dyn InputFormat {
-    /// Lookup input format by name.
+    /// Lookup input format by name in the global [`FormatRegistry`].
     pub fn get_format(name: &str) -> Option<&'static dyn InputFormat> {
-        INPUT_FORMATS.get(name).map(|f| &**f)
+        FormatRegistry::global().input_format(name)
     }
 }
 
@@ -416,6 +615,37 @@ pub trait Parser: Send {
     /// Used by multithreaded transport endpoints to create multiple parallel
     /// input pipelines.
     fn fork(&self) -> Box<dyn Parser>;
+
+    /// Report the progress of the parser through the input stream.
+    ///
+    /// The checkpoint only covers complete records.  Bytes buffered as part
+    /// of an incomplete record are not included, so a transport can commit
+    /// its source offset to `ParserCheckpoint::bytes` and resume from there
+    /// after calling [`Self::restore`].
+    ///
+    /// The default implementation returns an empty checkpoint.
+    fn checkpoint(&self) -> ParserCheckpoint {
+        ParserCheckpoint::default()
+    }
+
+    /// Resume parsing from `checkpoint`.
+    ///
+    /// Discards any buffered incomplete records.  The next input fragment
+    /// is expected to start at byte offset `checkpoint.bytes` of the input
+    /// stream.
+    ///
+    /// The default implementation does nothing.
+    fn restore(&mut self, _checkpoint: ParserCheckpoint) {}
+}
+
+/// Progress of a [`Parser`] through its input stream.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ParserCheckpoint {
+    /// Number of records consumed, including records that failed to parse.
+    pub records: u64,
+
+    /// Number of bytes of input consumed as part of complete records.
+    pub bytes: u64,
 }
 
 pub trait OutputFormat: Send + Sync {
@@ -448,9 +678,9 @@ pub trait OutputFormat: Send + Sync {
 }
 
 impl dyn OutputFormat {
-    /// Lookup output format by name.
+    /// Lookup output format by name in the global [`FormatRegistry`].
     pub fn get_format(name: &str) -> Option<&'static dyn OutputFormat> {
-        OUTPUT_FORMATS.get(name).map(|f| &**f)
+        FormatRegistry::global().output_format(name)
     }
 }
 
@@ -461,6 +691,41 @@ pub trait Encoder: Send {
     /// Encode a batch of updates, push encoded buffers to the consumer
     /// using [`OutputConsumer::push_buffer`].
     fn encode(&mut self, batches: &[Arc<dyn SerBatch>]) -> AnyResult<()>;
+
+    /// Retry pushing buffers that the consumer refused to accept.
+    ///
+    /// Encoders that use [`OutputConsumer::try_push_buffer`] queue buffers
+    /// rejected by the consumer with [`PushStatus::WouldBlock`] and push
+    /// them again, in order, before any new data.  This method pushes as many
+    /// queued buffers as the consumer accepts.
+    ///
+    /// Returns `true` if there are no more pending buffers and `false` if the
+    /// consumer is still blocked.  The caller should not feed new batches to
+    /// the encoder while it is blocked.  The controller uses this method to
+    /// stop dequeuing output batches for a slow consumer, so that the output
+    /// queue fills up and pauses the circuit.
+    fn flush_pending(&mut self) -> AnyResult<bool> {
+        Ok(true)
+    }
+}
+
+/// Maximum number of buffers an encoder queues while the consumer is blocked.
+///
+/// A single batch can encode to many buffers.  Once this many buffers are
+/// waiting, the encoder pushes the oldest one using the blocking
+/// [`OutputConsumer::push_buffer`], so that a stalled consumer cannot make the
+/// queue grow without bound.
+pub(crate) const MAX_PENDING_BUFFERS: usize = 16;
+
+/// Result of [`OutputConsumer::try_push_buffer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PushStatus {
+    /// The buffer has been accepted by the consumer.
+    Pushed,
+
+    /// The consumer cannot accept more data at the moment.  The buffer has
+    /// not been consumed and must be pushed again later.
+    WouldBlock,
 }
 
 pub trait OutputConsumer: Send {
@@ -470,5 +735,148 @@ pub trait OutputConsumer: Send {
 
     fn batch_start(&mut self);
     fn push_buffer(&mut self, buffer: &[u8]);
+
+    /// Non-blocking version of [`push_buffer`](`Self::push_buffer`).
+    ///
+    /// Allows a slow consumer to apply backpressure by returning
+    /// [`PushStatus::WouldBlock`] instead of accepting the buffer.
+    /// The default implementation always accepts the buffer.
+    fn try_push_buffer(&mut self, buffer: &[u8]) -> PushStatus {
+        self.push_buffer(buffer);
+        PushStatus::Pushed
+    }
+
+    /// Returns `true` if [`try_push_buffer`](`Self::try_push_buffer`) is
+    /// expected to return [`PushStatus::WouldBlock`].
+    ///
+    /// The controller polls this method before retrying pending buffers, so
+    /// that it doesn't open a transport batch while the consumer cannot
+    /// accept any data.  The default implementation returns `false`.
+    fn would_block(&self) -> bool {
+        false
+    }
+
     fn batch_end(&mut self);
 }
+
+#[cfg(test)]
+mod test {
+    use super::{
+        csv::{CsvInputFormat, CsvOutputFormat},
+        Encoder, FormatRegistry, InputFormat, OutputConsumer, OutputFormat, Parser,
+    };
+    use crate::{
+        test::{mock_parser_pipeline, TestStruct},
+        transport::InputConsumer,
+        ControllerError, DeCollectionHandle, FormatConfig,
+    };
+    use actix_web::HttpRequest;
+    use erased_serde::Serialize as ErasedSerialize;
+    use serde_yaml::Value as YamlValue;
+    use std::borrow::Cow;
+
+    /// Custom format that parses CSV under a different name.
+    struct CustomInputFormat;
+
+    impl InputFormat for CustomInputFormat {
+        fn name(&self) -> Cow<'static, str> {
+            Cow::Borrowed("custom_csv")
+        }
+
+        fn config_from_http_request(
+            &self,
+            endpoint_name: &str,
+            request: &HttpRequest,
+        ) -> Result<Box<dyn ErasedSerialize>, ControllerError> {
+            CsvInputFormat.config_from_http_request(endpoint_name, request)
+        }
+
+        fn new_parser(
+            &self,
+            endpoint_name: &str,
+            input_stream: &dyn DeCollectionHandle,
+            config: &YamlValue,
+        ) -> Result<Box<dyn Parser>, ControllerError> {
+            CsvInputFormat.new_parser(endpoint_name, input_stream, config)
+        }
+    }
+
+    /// Custom format that encodes CSV under a different name.
+    struct CustomOutputFormat;
+
+    impl OutputFormat for CustomOutputFormat {
+        fn name(&self) -> Cow<'static, str> {
+            Cow::Borrowed("custom_csv")
+        }
+
+        fn config_from_http_request(
+            &self,
+            endpoint_name: &str,
+            request: &HttpRequest,
+        ) -> Result<Box<dyn ErasedSerialize>, ControllerError> {
+            CsvOutputFormat.config_from_http_request(endpoint_name, request)
+        }
+
+        fn new_encoder(
+            &self,
+            endpoint_name: &str,
+            config: &YamlValue,
+            consumer: Box<dyn OutputConsumer>,
+        ) -> Result<Box<dyn Encoder>, ControllerError> {
+            CsvOutputFormat.new_encoder(endpoint_name, config, consumer)
+        }
+    }
+
+    #[test]
+    fn test_builtin_formats() {
+        assert_eq!(<dyn InputFormat>::get_format("csv").unwrap().name(), "csv");
+        assert_eq!(
+            <dyn OutputFormat>::get_format("json").unwrap().name(),
+            "json"
+        );
+        assert!(<dyn InputFormat>::get_format("no_such_format").is_none());
+        assert!(<dyn OutputFormat>::get_format("no_such_format").is_none());
+    }
+
+    #[test]
+    fn test_custom_format() {
+        assert!(<dyn InputFormat>::get_format("custom_csv").is_none());
+
+        assert!(FormatRegistry::global()
+            .register_input("custom_csv", Box::new(CustomInputFormat))
+            .is_none());
+        assert!(FormatRegistry::global()
+            .register_output("custom_csv", Box::new(CustomOutputFormat))
+            .is_none());
+
+        assert_eq!(
+            <dyn InputFormat>::get_format("custom_csv").unwrap().name(),
+            "custom_csv"
+        );
+        assert_eq!(
+            <dyn OutputFormat>::get_format("custom_csv").unwrap().name(),
+            "custom_csv"
+        );
+
+        // Resolve the format by name when building an input pipeline.
+        let format_config = FormatConfig {
+            name: Cow::from("custom_csv"),
+            config: YamlValue::Null,
+        };
+        let (mut consumer, outputs) = mock_parser_pipeline::<TestStruct>(&format_config).unwrap();
+        assert_eq!(consumer.input_fragment(b"1,true,5,foo\n"), Vec::new());
+        assert_eq!(consumer.eoi(), Vec::new());
+        assert_eq!(
+            outputs.state().flushed,
+            vec![(
+                TestStruct {
+                    id: 1,
+                    b: true,
+                    i: Some(5),
+                    s: "foo".to_string(),
+                },
+                true
+            )]
+        );
+    }
+}
@@ -0,0 +1,674 @@
+use crate::{
+    catalog::{RecordFormat, SerBatch},
+    format::{Encoder, OutputFormat, MAX_RECORD_LEN_IN_ERRMSG},
+    static_compile::Field,
+    util::truncate_ellipse,
+    ControllerError, OutputConsumer,
+};
+use actix_web::HttpRequest;
+use anyhow::{bail, Result as AnyResult};
+use arrow::{
+    array::{
+        ArrayRef, BinaryArray, BooleanArray, Date32Array, Decimal256Array, Float32Array,
+        Float64Array, Int16Array, Int32Array, Int64Array, Int8Array, NullArray, StringArray,
+        Time64MicrosecondArray, TimestampMillisecondArray, UInt16Array, UInt32Array, UInt64Array,
+        UInt8Array,
+    },
+    datatypes::{i256, DataType, Field as ArrowField, Schema, SchemaRef, TimeUnit},
+    record_batch::RecordBatch,
+    util::display::array_value_to_string,
+};
+use erased_serde::Serialize as ErasedSerialize;
+use parquet::arrow::ArrowWriter;
+use pipeline_types::format::parquet::ParquetEncoderConfig;
+use serde::Deserialize;
+use serde_urlencoded::Deserializer as UrlDeserializer;
+use serde_yaml::Value as YamlValue;
+use std::{borrow::Cow, ops::Range, sync::Arc};
+
+/// Name of the column that stores the weight of each record.
+pub const WEIGHT_COLUMN: &str = "__weight";
+
+/// Precision of the Arrow type that stores `DECIMAL` columns.
+const DECIMAL_PRECISION: u8 = 76;
+
+/// Scale of the Arrow type that stores `DECIMAL` columns: the largest scale
+/// of a [`Decimal`](rust_decimal::Decimal), so that all values are
+/// represented without rounding.
+const DECIMAL_SCALE: u32 = 28;
+
+/// Parquet format encoder.
+pub struct ParquetOutputFormat;
+
+impl OutputFormat for ParquetOutputFormat {
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("parquet")
+    }
+
+    fn config_from_http_request(
+        &self,
+        endpoint_name: &str,
+        request: &HttpRequest,
+    ) -> Result<Box<dyn ErasedSerialize>, ControllerError> {
+        Ok(Box::new(
+            ParquetEncoderConfig::deserialize(UrlDeserializer::new(form_urlencoded::parse(
+                request.query_string().as_bytes(),
+            )))
+            .map_err(|e| {
+                ControllerError::encoder_config_parse_error(
+                    endpoint_name,
+                    &e,
+                    request.query_string(),
+                )
+            })?,
+        ))
+    }
+
+    fn new_encoder(
+        &self,
+        endpoint_name: &str,
+        config: &YamlValue,
+        consumer: Box<dyn OutputConsumer>,
+    ) -> Result<Box<dyn Encoder>, ControllerError> {
+        let config = ParquetEncoderConfig::deserialize(config).map_err(|e| {
+            ControllerError::encoder_config_parse_error(
+                endpoint_name,
+                &e,
+                &serde_yaml::to_string(&config).unwrap_or_default(),
+            )
+        })?;
+
+        Ok(Box::new(ParquetEncoder::new(consumer, config)))
+    }
+}
+
+/// Encodes batches as a sequence of Parquet files.
+///
+/// Each buffer pushed to the output consumer is a complete Parquet file
+/// containing a single row group of up to `buffer_size_records` records.
+/// The weight of each record is stored in the [`WEIGHT_COLUMN`] column.
+///
+/// The Arrow schema is derived from the records: column names are the names
+/// of the fields of the record struct, and the type of each column is the
+/// type of its first non-null value.  Once a column's type is known, it is
+/// used for all subsequent files.  Columns that have only contained nulls so
+/// far are written with the Arrow `Null` type.
+struct ParquetEncoder {
+    /// Input handle to push serialized data to.
+    output_consumer: Box<dyn OutputConsumer>,
+
+    config: ParquetEncoderConfig,
+
+    /// Column names, derived from the first record.
+    names: Option<Vec<&'static str>>,
+    /// Arrow types of the columns; `None` for columns that have only
+    /// contained nulls so far.
+    types: Vec<Option<DataType>>,
+
+    /// Values buffered for the next row group, one vector per column.
+    columns: Vec<Vec<Field>>,
+    weights: Vec<i64>,
+
+    /// Fields of the current record and their names, reused across records.
+    fields: Vec<Field>,
+    field_names: Vec<Option<&'static str>>,
+
+    max_buffer_size: usize,
+}
+
+impl ParquetEncoder {
+    fn new(output_consumer: Box<dyn OutputConsumer>, config: ParquetEncoderConfig) -> Self {
+        let max_buffer_size = output_consumer.max_buffer_size_bytes();
+
+        Self {
+            output_consumer,
+            config,
+            names: None,
+            types: Vec::new(),
+            columns: Vec::new(),
+            weights: Vec::new(),
+            fields: Vec::new(),
+            field_names: Vec::new(),
+            max_buffer_size,
+        }
+    }
+
+    /// Arrow type of `field`, or `None` if the field is null.
+    fn data_type(field: &Field) -> AnyResult<Option<DataType>> {
+        Ok(Some(match field {
+            Field::None => return Ok(None),
+            Field::Bool(_) => DataType::Boolean,
+            Field::I8(_) => DataType::Int8,
+            Field::I16(_) => DataType::Int16,
+            Field::I32(_) => DataType::Int32,
+            Field::I64(_) => DataType::Int64,
+            Field::U8(_) => DataType::UInt8,
+            Field::U16(_) => DataType::UInt16,
+            Field::U32(_) => DataType::UInt32,
+            Field::U64(_) => DataType::UInt64,
+            Field::F32(_) => DataType::Float32,
+            Field::F64(_) => DataType::Float64,
+            Field::Str(_) => DataType::Utf8,
+            Field::Bytes(_) => DataType::Binary,
+            Field::Date(_) => DataType::Date32,
+            Field::Time(_) => DataType::Time64(TimeUnit::Microsecond),
+            Field::Timestamp(_) => DataType::Timestamp(TimeUnit::Millisecond, None),
+            Field::Decimal(_) => DataType::Decimal256(DECIMAL_PRECISION, DECIMAL_SCALE as i8),
+            field => bail!("Parquet encoder does not support values of this type: {field:?}"),
+        }))
+    }
+
+    /// Derive column names from the names of the fields of the first record.
+    fn init_columns(&mut self) -> AnyResult<()> {
+        let mut names = Vec::with_capacity(self.field_names.len());
+        for name in self.field_names.iter() {
+            match name {
+                Some(name) if !names.contains(name) => names.push(*name),
+                _ => bail!("Parquet encoder requires records that serialize as structs with one named field per column"),
+            }
+        }
+
+        self.types = vec![None; names.len()];
+        self.columns = vec![Vec::new(); names.len()];
+        self.names = Some(names);
+        Ok(())
+    }
+
+    /// Append the fields of the current record to the buffered columns.
+    fn push_record(&mut self, weight: i64) -> AnyResult<()> {
+        if self.names.is_none() {
+            self.init_columns()?;
+        }
+        let names = self.names.as_ref().unwrap();
+        if self.fields.len() != names.len() {
+            bail!(
+                "Parquet encoder expects records with {} columns, but found a record with {} columns",
+                names.len(),
+                self.fields.len()
+            );
+        }
+
+        for (index, field) in self.fields.drain(..).enumerate() {
+            if let Some(data_type) = Self::data_type(&field)? {
+                match &self.types[index] {
+                    None => self.types[index] = Some(data_type),
+                    Some(column_type) if *column_type != data_type => bail!(
+                        "column '{}' contains values of types {column_type} and {data_type}",
+                        names[index]
+                    ),
+                    Some(_) => {}
+                }
+            }
+            self.columns[index].push(field);
+        }
+        self.weights.push(weight);
+
+        Ok(())
+    }
+
+    fn schema(&self) -> SchemaRef {
+        let mut fields = self
+            .names
+            .iter()
+            .flatten()
+            .zip(self.types.iter())
+            .map(|(name, data_type)| {
+                ArrowField::new(*name, data_type.clone().unwrap_or(DataType::Null), true)
+            })
+            .collect::<Vec<_>>();
+        fields.push(ArrowField::new(WEIGHT_COLUMN, DataType::Int64, false));
+
+        Arc::new(Schema::new(fields))
+    }
+
+    /// Build an Arrow array of type `data_type` from `values`.
+    ///
+    /// Values of other types than `data_type` are nulls.
+    fn build_array(data_type: &Option<DataType>, values: &[Field]) -> AnyResult<ArrayRef> {
+        macro_rules! build {
+            ($array:ty, $variant:ident) => {
+                Arc::new(
+                    values
+                        .iter()
+                        .map(|value| match value {
+                            Field::$variant(value) => Some(*value),
+                            _ => None,
+                        })
+                        .collect::<$array>(),
+                )
+            };
+        }
+
+        let Some(data_type) = data_type else {
+            return Ok(Arc::new(NullArray::new(values.len())));
+        };
+
+        Ok(match data_type {
+            DataType::Boolean => build!(BooleanArray, Bool),
+            DataType::Int8 => build!(Int8Array, I8),
+            DataType::Int16 => build!(Int16Array, I16),
+            DataType::Int32 => build!(Int32Array, I32),
+            DataType::Int64 => build!(Int64Array, I64),
+            DataType::UInt8 => build!(UInt8Array, U8),
+            DataType::UInt16 => build!(UInt16Array, U16),
+            DataType::UInt32 => build!(UInt32Array, U32),
+            DataType::UInt64 => build!(UInt64Array, U64),
+            DataType::Float32 => build!(Float32Array, F32),
+            DataType::Float64 => build!(Float64Array, F64),
+            DataType::Date32 => build!(Date32Array, Date),
+            DataType::Timestamp(..) => build!(TimestampMillisecondArray, Timestamp),
+            DataType::Time64(_) => Arc::new(
+                values
+                    .iter()
+                    .map(|value| match value {
+                        Field::Time(micros) => Some(*micros as i64),
+                        _ => None,
+                    })
+                    .collect::<Time64MicrosecondArray>(),
+            ),
+            DataType::Utf8 => Arc::new(
+                values
+                    .iter()
+                    .map(|value| match value {
+                        Field::Str(value) => Some(value.as_str()),
+                        _ => None,
+                    })
+                    .collect::<StringArray>(),
+            ),
+            DataType::Binary => Arc::new(
+                values
+                    .iter()
+                    .map(|value| match value {
+                        Field::Bytes(value) => Some(value.as_slice()),
+                        _ => None,
+                    })
+                    .collect::<BinaryArray>(),
+            ),
+            DataType::Decimal256(..) => Arc::new(
+                values
+                    .iter()
+                    .map(|value| match value {
+                        Field::Decimal(value) => Some(
+                            i256::from_i128(value.mantissa())
+                                * i256::from_i128(10).wrapping_pow(DECIMAL_SCALE - value.scale()),
+                        ),
+                        _ => None,
+                    })
+                    .collect::<Decimal256Array>()
+                    .with_precision_and_scale(DECIMAL_PRECISION, DECIMAL_SCALE as i8)?,
+            ),
+            data_type => bail!("Parquet encoder does not support columns of type {data_type}"),
+        })
+    }
+
+    /// Build a record batch from the buffered records in `rows`.
+    fn record_batch(&self, schema: &SchemaRef, rows: Range<usize>) -> AnyResult<RecordBatch> {
+        let mut arrays = self
+            .types
+            .iter()
+            .zip(self.columns.iter())
+            .map(|(data_type, values)| Self::build_array(data_type, &values[rows.clone()]))
+            .collect::<AnyResult<Vec<_>>>()?;
+        arrays.push(Arc::new(Int64Array::from(self.weights[rows].to_vec())));
+
+        Ok(RecordBatch::try_new(schema.clone(), arrays)?)
+    }
+
+    /// Encode `batch` as a Parquet file with a single row group.
+    fn encode_row_group(batch: &RecordBatch) -> AnyResult<Vec<u8>> {
+        let mut writer = ArrowWriter::try_new(Vec::new(), batch.schema(), None)?;
+        writer.write(batch)?;
+        Ok(writer.into_inner()?)
+    }
+
+    /// Encode `batch` and push the result to the output consumer.
+    ///
+    /// If the encoded row group exceeds the maximum buffer size supported by
+    /// the consumer, splits `batch` in half and closes the row group early.
+    fn push_batch(&mut self, batch: &RecordBatch) -> AnyResult<()> {
+        if batch.num_rows() == 0 {
+            return Ok(());
+        }
+
+        let buffer = Self::encode_row_group(batch)?;
+
+        if buffer.len() > self.max_buffer_size {
+            if batch.num_rows() == 1 {
+                let record = batch
+                    .schema()
+                    .fields()
+                    .iter()
+                    .zip(batch.columns())
+                    .map(|(field, column)| {
+                        Ok(format!(
+                            "{}: {}",
+                            field.name(),
+                            array_value_to_string(column, 0)?
+                        ))
+                    })
+                    .collect::<AnyResult<Vec<_>>>()?
+                    .join(", ");
+                // We should be able to fit at least one record in the buffer.
+                bail!("Parquet record exceeds maximum buffer size supported by the output transport. Max supported buffer size is {} bytes, but the following record requires {} bytes: '{}'.",
+                      self.max_buffer_size,
+                      buffer.len(),
+                      truncate_ellipse(&record, MAX_RECORD_LEN_IN_ERRMSG, "..."));
+            }
+            let half = batch.num_rows() / 2;
+            self.push_batch(&batch.slice(0, half))?;
+            self.push_batch(&batch.slice(half, batch.num_rows() - half))?;
+        } else {
+            self.output_consumer.push_buffer(&buffer);
+        }
+
+        Ok(())
+    }
+
+    /// Encode buffered records as a row group.
+    fn flush_rows(&mut self) -> AnyResult<()> {
+        if self.weights.is_empty() {
+            return Ok(());
+        }
+
+        let schema = self.schema();
+        let result = self
+            .record_batch(&schema, 0..self.weights.len())
+            .and_then(|batch| self.push_batch(&batch));
+
+        for column in self.columns.iter_mut() {
+            column.clear();
+        }
+        self.weights.clear();
+        result
+    }
+}
+
+impl Encoder for ParquetEncoder {
+    fn consumer(&mut self) -> &mut dyn OutputConsumer {
+        self.output_consumer.as_mut()
+    }
+
+    fn encode(&mut self, batches: &[Arc<dyn SerBatch>]) -> AnyResult<()> {
+        for batch in batches.iter() {
+            let mut cursor = batch.cursor(RecordFormat::Parquet)?;
+
+            while cursor.key_valid() {
+                if !cursor.val_valid() {
+                    cursor.step_key();
+                    continue;
+                }
+
+                self.fields.clear();
+                self.field_names.clear();
+                cursor.serialize_key_fields(&mut self.fields, &mut self.field_names)?;
+                let weight = cursor.weight();
+                self.push_record(weight)?;
+
+                if self.weights.len() >= self.config.buffer_size_records {
+                    self.flush_rows()?;
+                }
+
+                cursor.step_key();
+            }
+        }
+
+        self.flush_rows()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ParquetEncoder, DECIMAL_PRECISION, DECIMAL_SCALE, WEIGHT_COLUMN};
+    use crate::{
+        catalog::SerBatch,
+        format::Encoder,
+        serialize_table_record,
+        static_compile::seroutput::SerBatchImpl,
+        test::{MockOutputConsumer, TestStruct},
+        SerializeWithContext, DATE_NEWTYPE, TIMESTAMP_NEWTYPE,
+    };
+    use arrow::{
+        array::AsArray,
+        datatypes::{i256, DataType, Decimal256Type, TimeUnit},
+        json::writer::record_batches_to_json_rows,
+        record_batch::RecordBatch,
+    };
+    use bytes::Bytes;
+    use dbsp::{trace::Batch, OrdZSet};
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use pipeline_types::format::parquet::ParquetEncoderConfig;
+    use rust_decimal::Decimal;
+    use serde::Serializer;
+    use serde_json::Value as JsonValue;
+    use std::sync::Arc;
+
+    fn test_data() -> Vec<(TestStruct, i64)> {
+        vec![
+            (
+                TestStruct {
+                    id: 0,
+                    b: true,
+                    i: Some(1),
+                    s: "foo".to_string(),
+                },
+                1,
+            ),
+            (
+                TestStruct {
+                    id: 1,
+                    b: false,
+                    i: None,
+                    s: "bar".to_string(),
+                },
+                -1,
+            ),
+            (
+                TestStruct {
+                    id: 2,
+                    b: true,
+                    i: Some(-5),
+                    s: "".to_string(),
+                },
+                2,
+            ),
+        ]
+    }
+
+    /// Date serialized as days since UNIX epoch, like SQL `DATE` values.
+    struct TestDate(i32);
+
+    impl<C> SerializeWithContext<C> for TestDate {
+        fn serialize_with_context<S>(&self, serializer: S, _context: &C) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.serialize_newtype_struct(DATE_NEWTYPE, &self.0)
+        }
+    }
+
+    /// Timestamp serialized as milliseconds since UNIX epoch, like SQL
+    /// `TIMESTAMP` values.
+    struct TestTimestamp(i64);
+
+    impl<C> SerializeWithContext<C> for TestTimestamp {
+        fn serialize_with_context<S>(&self, serializer: S, _context: &C) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.serialize_newtype_struct(TIMESTAMP_NEWTYPE, &self.0)
+        }
+    }
+
+    /// Record with columns of SQL types that map to dedicated Arrow types.
+    struct TypedRecord {
+        id: u32,
+        d: Decimal,
+        date: TestDate,
+        ts: Option<TestTimestamp>,
+    }
+
+    impl From<TestStruct> for TypedRecord {
+        fn from(val: TestStruct) -> Self {
+            Self {
+                id: val.id,
+                d: Decimal::new(val.i.unwrap_or_default() * 15, 1),
+                date: TestDate(val.id as i32),
+                ts: val.i.map(TestTimestamp),
+            }
+        }
+    }
+
+    serialize_table_record!(TypedRecord[4]{
+        id["id"]: u32,
+        d["d"]: Decimal,
+        date["date"]: TestDate,
+        ts["ts"]: Option<TestTimestamp>
+    });
+
+    /// Read record batches back from a Parquet file.
+    fn read_batches(data: Vec<u8>) -> Vec<RecordBatch> {
+        let reader = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(data))
+            .unwrap()
+            .build()
+            .unwrap();
+        reader.map(|batch| batch.unwrap()).collect::<Vec<_>>()
+    }
+
+    /// Read rows back from a Parquet file.
+    fn read_parquet(data: Vec<u8>) -> Vec<(TestStruct, i64)> {
+        let batches = read_batches(data);
+
+        record_batches_to_json_rows(&batches.iter().collect::<Vec<_>>())
+            .unwrap()
+            .into_iter()
+            .map(|mut row| {
+                let weight = row.remove(WEIGHT_COLUMN).unwrap().as_i64().unwrap();
+                let val = serde_json::from_value::<TestStruct>(JsonValue::Object(row)).unwrap();
+                (val, weight)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parquet_readback() {
+        let consumer = MockOutputConsumer::new();
+        let consumer_data = consumer.data.clone();
+        let config = ParquetEncoderConfig {
+            buffer_size_records: 10,
+        };
+        let mut encoder = ParquetEncoder::new(Box::new(consumer), config);
+
+        let zset = OrdZSet::from_keys((), test_data());
+        let batch = Arc::new(<SerBatchImpl<_, TestStruct, ()>>::new(zset)) as Arc<dyn SerBatch>;
+        encoder.encode(&[batch]).unwrap();
+
+        let data = consumer_data.lock().unwrap().clone();
+        assert_eq!(read_parquet(data), test_data());
+    }
+
+    #[test]
+    fn test_parquet_schema_from_batch() {
+        let consumer = MockOutputConsumer::new();
+        let consumer_data = consumer.data.clone();
+        let config = ParquetEncoderConfig {
+            buffer_size_records: 10,
+        };
+        let mut encoder = ParquetEncoder::new(Box::new(consumer), config);
+
+        // Column `i` is null in every record of the first and the last file.
+        let data = test_data();
+        let mut schemas = Vec::new();
+        for records in [&data[1..2], &data[0..1], &data[1..2]] {
+            let zset = OrdZSet::from_keys((), records.to_vec());
+            let batch = Arc::new(<SerBatchImpl<_, TestStruct, ()>>::new(zset)) as Arc<dyn SerBatch>;
+            encoder.encode(&[batch]).unwrap();
+
+            let data = std::mem::take(&mut *consumer_data.lock().unwrap());
+            let reader = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(data)).unwrap();
+            schemas.push(reader.schema().clone());
+        }
+
+        let types = |index: usize| {
+            schemas[index]
+                .fields()
+                .iter()
+                .map(|field| (field.name().as_str(), field.data_type().clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            types(0),
+            vec![
+                ("id", DataType::UInt32),
+                ("b", DataType::Boolean),
+                ("i", DataType::Null),
+                ("s", DataType::Utf8),
+                (WEIGHT_COLUMN, DataType::Int64)
+            ]
+        );
+        assert_eq!(
+            types(1)[2],
+            ("i", DataType::Int64),
+            "the type of `i` is derived from its first non-null value"
+        );
+        assert_eq!(
+            schemas[1], schemas[2],
+            "the schema doesn't change once the types of all columns are known"
+        );
+    }
+
+    #[test]
+    fn test_parquet_sql_types() {
+        let consumer = MockOutputConsumer::new();
+        let consumer_data = consumer.data.clone();
+        let config = ParquetEncoderConfig {
+            buffer_size_records: 10,
+        };
+        let mut encoder = ParquetEncoder::new(Box::new(consumer), config);
+
+        let zset = OrdZSet::from_keys((), test_data());
+        let batch = Arc::new(<SerBatchImpl<_, TypedRecord, ()>>::new(zset)) as Arc<dyn SerBatch>;
+        encoder.encode(&[batch]).unwrap();
+
+        let data = consumer_data.lock().unwrap().clone();
+        let batches = read_batches(data);
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+
+        let schema = batch.schema();
+        assert_eq!(
+            schema.field_with_name("d").unwrap().data_type(),
+            &DataType::Decimal256(DECIMAL_PRECISION, DECIMAL_SCALE as i8)
+        );
+        assert_eq!(
+            schema.field_with_name("date").unwrap().data_type(),
+            &DataType::Date32
+        );
+        assert_eq!(
+            schema.field_with_name("ts").unwrap().data_type(),
+            &DataType::Timestamp(TimeUnit::Millisecond, None)
+        );
+
+        // 1.5 and -7.5.
+        let decimals = batch.column(1).as_primitive::<Decimal256Type>();
+        let scale = i256::from_i128(10).wrapping_pow(DECIMAL_SCALE - 1);
+        assert_eq!(decimals.value(0), i256::from_i128(15) * scale);
+        assert_eq!(decimals.value(2), i256::from_i128(-75) * scale);
+        assert!(batch.column(3).is_null(1));
+    }
+
+    #[test]
+    fn test_parquet_record_too_large() {
+        let consumer = MockOutputConsumer::with_max_buffer_size_bytes(32);
+        let config = ParquetEncoderConfig {
+            buffer_size_records: 10,
+        };
+        let mut encoder = ParquetEncoder::new(Box::new(consumer), config);
+
+        let zset = OrdZSet::from_keys((), test_data());
+        let batch = Arc::new(<SerBatchImpl<_, TestStruct, ()>>::new(zset)) as Arc<dyn SerBatch>;
+        let err = encoder.encode(&[batch]).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Parquet record exceeds maximum buffer size"));
+    }
+}
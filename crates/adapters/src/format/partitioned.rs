@@ -0,0 +1,416 @@
+use crate::{
+    catalog::{RecordFormat, SerBatch, SerCursor},
+    format::{Encoder, OutputConsumer},
+    static_compile::Field,
+    ControllerError,
+};
+use anyhow::{bail, Result as AnyResult};
+use pipeline_types::format::json::JsonFlavor;
+use std::sync::Arc;
+
+/// Function that assigns a record to a partition.
+///
+/// Takes the JSON encoding of the record and returns the index of the
+/// partition the record belongs to.
+pub type PartitionFunc = dyn Fn(&[u8]) -> usize + Send + Sync;
+
+/// Encoder that splits its input into partitions and encodes each partition
+/// using a separate encoder.
+///
+/// The encoder evaluates the partition function on every record in the
+/// input batches and forwards records that belong to partition `i` to the
+/// `i`th encoder.  Each inner encoder is connected to its own
+/// [`OutputConsumer`], e.g., a separate file per tenant, and enforces the
+/// `max_buffer_size_bytes` limit of this consumer independently of the
+/// other partitions.
+pub struct PartitionedEncoder {
+    partitions: PartitionConsumers,
+    partition_func: Box<PartitionFunc>,
+}
+
+impl PartitionedEncoder {
+    /// Create an encoder that routes records to `encoders` according to
+    /// `partition_func`.
+    ///
+    /// `partition_func` must return a value in the range
+    /// `0..encoders.len()`.
+    pub fn new(mut encoders: Vec<Box<dyn Encoder>>, partition_func: Box<PartitionFunc>) -> Self {
+        let max_buffer_size = encoders
+            .iter_mut()
+            .map(|encoder| encoder.consumer().max_buffer_size_bytes())
+            .min()
+            .unwrap_or(usize::MAX);
+
+        Self {
+            partitions: PartitionConsumers {
+                encoders,
+                max_buffer_size,
+            },
+            partition_func,
+        }
+    }
+
+    /// Number of partitions.
+    pub fn num_partitions(&self) -> usize {
+        self.partitions.encoders.len()
+    }
+
+    /// Compute partition assignment for each key in the batch, along with
+    /// the number of keys and tuples in each partition.
+    fn partition(&self, batch: &dyn SerBatch) -> AnyResult<(Vec<usize>, Vec<PartitionSize>)> {
+        let mut cursor = batch.cursor(RecordFormat::Json(JsonFlavor::Default.into()))?;
+        let mut partitions = Vec::with_capacity(batch.key_count());
+        let mut sizes = vec![PartitionSize::default(); self.num_partitions()];
+        let mut buffer = Vec::new();
+
+        while cursor.key_valid() {
+            buffer.clear();
+            cursor.serialize_key(&mut buffer)?;
+            let partition = (self.partition_func)(&buffer);
+            if partition >= self.num_partitions() {
+                bail!(
+                    "partition function returned partition index {partition}, but the encoder only has {} partitions",
+                    self.num_partitions()
+                );
+            }
+            partitions.push(partition);
+
+            let size = &mut sizes[partition];
+            size.key_count += 1;
+            while cursor.val_valid() {
+                size.len += 1;
+                cursor.step_val();
+            }
+            cursor.step_key();
+        }
+
+        Ok((partitions, sizes))
+    }
+}
+
+impl Encoder for PartitionedEncoder {
+    fn consumer(&mut self) -> &mut dyn OutputConsumer {
+        &mut self.partitions
+    }
+
+    fn encode(&mut self, batches: &[Arc<dyn SerBatch>]) -> AnyResult<()> {
+        let mut partitioned_batches = vec![Vec::new(); self.num_partitions()];
+
+        for batch in batches.iter() {
+            let (partitions, sizes) = self.partition(batch.as_ref())?;
+            let partitions = Arc::new(partitions);
+
+            for (partition, (partition_batches, size)) in partitioned_batches
+                .iter_mut()
+                .zip(sizes.into_iter())
+                .enumerate()
+            {
+                if size.len > 0 {
+                    let batch = PartitionBatch {
+                        batch: batch.clone(),
+                        partitions: partitions.clone(),
+                        partition,
+                        size,
+                    };
+                    partition_batches.push(Arc::new(batch) as Arc<dyn SerBatch>);
+                }
+            }
+        }
+
+        for (encoder, batches) in self
+            .partitions
+            .encoders
+            .iter_mut()
+            .zip(partitioned_batches.into_iter())
+        {
+            if !batches.is_empty() {
+                encoder.encode(&batches)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Consumers of all partitions, exposed as a single [`OutputConsumer`].
+///
+/// Forwards batch start and end notifications to all partitions.
+/// Encoded data is normally pushed directly to the consumers of individual
+/// partitions by their encoders; buffers pushed to this consumer are
+/// forwarded to all partitions.
+struct PartitionConsumers {
+    encoders: Vec<Box<dyn Encoder>>,
+    /// Smallest buffer size supported by all partitions, which bounds the
+    /// size of buffers pushed to this consumer.
+    max_buffer_size: usize,
+}
+
+impl OutputConsumer for PartitionConsumers {
+    fn max_buffer_size_bytes(&self) -> usize {
+        self.max_buffer_size
+    }
+
+    fn batch_start(&mut self) {
+        for encoder in self.encoders.iter_mut() {
+            encoder.consumer().batch_start();
+        }
+    }
+
+    fn push_buffer(&mut self, buffer: &[u8]) {
+        for encoder in self.encoders.iter_mut() {
+            encoder.consumer().push_buffer(buffer);
+        }
+    }
+
+    fn batch_end(&mut self) {
+        for encoder in self.encoders.iter_mut() {
+            encoder.consumer().batch_end();
+        }
+    }
+}
+
+/// Number of keys and tuples in a partition of a batch.
+#[derive(Clone, Copy, Default)]
+struct PartitionSize {
+    key_count: usize,
+    len: usize,
+}
+
+/// A subset of a batch that belongs to a single partition.
+struct PartitionBatch {
+    batch: Arc<dyn SerBatch>,
+    /// Partition assignment for each key in `batch`.
+    partitions: Arc<Vec<usize>>,
+    partition: usize,
+    size: PartitionSize,
+}
+
+impl SerBatch for PartitionBatch {
+    fn key_count(&self) -> usize {
+        self.size.key_count
+    }
+
+    fn len(&self) -> usize {
+        self.size.len
+    }
+
+    fn cursor<'a>(
+        &'a self,
+        record_format: RecordFormat,
+    ) -> Result<Box<dyn SerCursor + 'a>, ControllerError> {
+        Ok(Box::new(PartitionCursor::new(
+            self.batch.cursor(record_format)?,
+            &self.partitions,
+            self.partition,
+        )))
+    }
+}
+
+/// Cursor that skips keys that don't belong to the partition.
+struct PartitionCursor<'a> {
+    cursor: Box<dyn SerCursor + 'a>,
+    partitions: &'a [usize],
+    partition: usize,
+    /// Index of the current key in the underlying batch.
+    key_index: usize,
+}
+
+impl<'a> PartitionCursor<'a> {
+    fn new(cursor: Box<dyn SerCursor + 'a>, partitions: &'a [usize], partition: usize) -> Self {
+        let mut result = Self {
+            cursor,
+            partitions,
+            partition,
+            key_index: 0,
+        };
+        result.skip_keys();
+        result
+    }
+
+    /// Advance the cursor to the next key in the partition.
+    fn skip_keys(&mut self) {
+        while self.cursor.key_valid() && self.partitions[self.key_index] != self.partition {
+            self.cursor.step_key();
+            self.key_index += 1;
+        }
+    }
+}
+
+impl<'a> SerCursor for PartitionCursor<'a> {
+    fn key_valid(&self) -> bool {
+        self.cursor.key_valid()
+    }
+
+    fn val_valid(&self) -> bool {
+        self.cursor.val_valid()
+    }
+
+    fn serialize_key(&mut self, dst: &mut Vec<u8>) -> AnyResult<()> {
+        self.cursor.serialize_key(dst)
+    }
+
+    fn serialize_key_weight(&mut self, dst: &mut Vec<u8>) -> AnyResult<()> {
+        self.cursor.serialize_key_weight(dst)
+    }
+
+    fn serialize_key_fields(
+        &mut self,
+        fields: &mut Vec<Field>,
+        names: &mut Vec<Option<&'static str>>,
+    ) -> AnyResult<()> {
+        self.cursor.serialize_key_fields(fields, names)
+    }
+
+    fn serialize_val(&mut self, dst: &mut Vec<u8>) -> AnyResult<()> {
+        self.cursor.serialize_val(dst)
+    }
+
+    fn weight(&mut self) -> i64 {
+        self.cursor.weight()
+    }
+
+    fn step_key(&mut self) {
+        self.cursor.step_key();
+        self.key_index += 1;
+        self.skip_keys();
+    }
+
+    fn step_val(&mut self) {
+        self.cursor.step_val();
+    }
+
+    fn rewind_keys(&mut self) {
+        self.cursor.rewind_keys();
+        self.key_index = 0;
+        self.skip_keys();
+    }
+
+    fn rewind_vals(&mut self) {
+        self.cursor.rewind_vals();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PartitionedEncoder;
+    use crate::{
+        catalog::SerBatch,
+        format::{Encoder, OutputFormat},
+        static_compile::seroutput::SerBatchImpl,
+        test::{MockOutputConsumer, TestStruct},
+    };
+    use csv::ReaderBuilder as CsvReaderBuilder;
+    use dbsp::{trace::Batch, OrdZSet};
+    use pipeline_types::format::csv::CsvEncoderConfig;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    fn test_data() -> Vec<(TestStruct, i64)> {
+        (0..10)
+            .map(|id| {
+                (
+                    TestStruct {
+                        id,
+                        b: id % 3 == 0,
+                        i: Some(id as i64 * 10),
+                        s: format!("record {id}"),
+                    },
+                    1,
+                )
+            })
+            .collect()
+    }
+
+    /// Create a CSV encoder for each consumer.
+    fn csv_encoders<const N: usize>(consumers: [MockOutputConsumer; N]) -> Vec<Box<dyn Encoder>> {
+        let config = serde_yaml::to_value(CsvEncoderConfig {
+            buffer_size_records: 2,
+            include_weight: true,
+            float_precision: None,
+            header: false,
+            rename_columns: Default::default(),
+            column_order: None,
+            max_record_len_in_errmsg: 4096,
+            line_terminator: Default::default(),
+        })
+        .unwrap();
+        consumers
+            .into_iter()
+            .map(|consumer| {
+                <dyn OutputFormat>::get_format("csv")
+                    .unwrap()
+                    .new_encoder("test", &config, Box::new(consumer))
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_partitioned_encoder() {
+        // Consumers with different buffer sizes.
+        let consumers = [
+            MockOutputConsumer::with_max_buffer_size_bytes(32),
+            MockOutputConsumer::new(),
+        ];
+        let consumer_data = consumers
+            .iter()
+            .map(|consumer| consumer.data.clone())
+            .collect::<Vec<_>>();
+
+        let encoders = csv_encoders(consumers);
+
+        // Round-robin partitioning.
+        let counter = AtomicUsize::new(0);
+        let mut encoder = PartitionedEncoder::new(
+            encoders,
+            Box::new(move |_key| counter.fetch_add(1, Ordering::Relaxed) % 2),
+        );
+
+        let zset = OrdZSet::from_keys((), test_data());
+        let batch = Arc::new(<SerBatchImpl<_, TestStruct, ()>>::new(zset)) as Arc<dyn SerBatch>;
+        encoder.consumer().batch_start();
+        encoder.encode(&[batch]).unwrap();
+        encoder.consumer().batch_end();
+
+        for (partition, data) in consumer_data.iter().enumerate() {
+            let data = data.lock().unwrap();
+            let actual = CsvReaderBuilder::new()
+                .has_headers(false)
+                .from_reader(data.as_slice())
+                .deserialize::<(u32, bool, Option<i64>, String, i64)>()
+                .map(|record| record.unwrap())
+                .collect::<Vec<_>>();
+            let expected = test_data()
+                .into_iter()
+                .skip(partition)
+                .step_by(2)
+                .map(|(val, w)| (val.id, val.b, val.i, val.s, w))
+                .collect::<Vec<_>>();
+            assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_partitioned_push_buffer() {
+        let consumers = [
+            MockOutputConsumer::with_max_buffer_size_bytes(32),
+            MockOutputConsumer::new(),
+        ];
+        let consumer_data = consumers
+            .iter()
+            .map(|consumer| consumer.data.clone())
+            .collect::<Vec<_>>();
+
+        let encoders = csv_encoders(consumers);
+        let mut encoder = PartitionedEncoder::new(encoders, Box::new(|_key| 0));
+
+        // Buffers pushed to the encoder's consumer reach all partitions.
+        assert_eq!(encoder.consumer().max_buffer_size_bytes(), 32);
+        encoder.consumer().push_buffer(b"hello\n");
+        for data in consumer_data.iter() {
+            assert_eq!(data.lock().unwrap().as_slice(), b"hello\n");
+        }
+    }
+}
@@ -0,0 +1,524 @@
+//! Protobuf format parser.
+
+use crate::{
+    catalog::{DeCollectionStream, RecordFormat},
+    format::{InputFormat, ParseError, Parser},
+    ControllerError, DeCollectionHandle,
+};
+use actix_web::HttpRequest;
+use erased_serde::Serialize as ErasedSerialize;
+use pipeline_types::format::{json::JsonFlavor, protobuf::ProtobufParserConfig};
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor, SerializeOptions};
+use serde::Deserialize;
+use serde_urlencoded::Deserializer as UrlDeserializer;
+use serde_yaml::Value as YamlValue;
+use std::{borrow::Cow, mem::take};
+
+/// Maximal length of a varint-encoded 64-bit integer.
+const MAX_VARINT_LEN: usize = 10;
+
+/// Length-delimited protobuf format parser.
+pub struct ProtobufInputFormat;
+
+impl InputFormat for ProtobufInputFormat {
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("protobuf")
+    }
+
+    fn config_from_http_request(
+        &self,
+        endpoint_name: &str,
+        request: &HttpRequest,
+    ) -> Result<Box<dyn ErasedSerialize>, ControllerError> {
+        Ok(Box::new(
+            ProtobufParserConfig::deserialize(UrlDeserializer::new(form_urlencoded::parse(
+                request.query_string().as_bytes(),
+            )))
+            .map_err(|e| {
+                ControllerError::parser_config_parse_error(
+                    endpoint_name,
+                    &e,
+                    request.query_string(),
+                )
+            })?,
+        ))
+    }
+
+    fn new_parser(
+        &self,
+        endpoint_name: &str,
+        input_stream: &dyn DeCollectionHandle,
+        config: &YamlValue,
+    ) -> Result<Box<dyn Parser>, ControllerError> {
+        let config_error = |e: String| {
+            ControllerError::parser_config_parse_error(
+                endpoint_name,
+                &e,
+                &serde_yaml::to_string(&config).unwrap_or_default(),
+            )
+        };
+
+        let config =
+            ProtobufParserConfig::deserialize(config).map_err(|e| config_error(e.to_string()))?;
+        let descriptor_set = std::fs::read(&config.descriptor_path).map_err(|e| {
+            config_error(format!(
+                "error reading protobuf descriptor set '{}': {e}",
+                config.descriptor_path
+            ))
+        })?;
+        let pool = DescriptorPool::decode(descriptor_set.as_slice()).map_err(|e| {
+            config_error(format!(
+                "error decoding protobuf descriptor set '{}': {e}",
+                config.descriptor_path
+            ))
+        })?;
+        let descriptor = pool
+            .get_message_by_name(&config.message_name)
+            .ok_or_else(|| {
+                config_error(format!(
+                    "message type '{}' not found in protobuf descriptor set '{}'",
+                    config.message_name, config.descriptor_path
+                ))
+            })?;
+
+        // Decoded messages are pushed to the circuit as JSON objects.
+        let input_stream =
+            input_stream.configure_deserializer(RecordFormat::Json(JsonFlavor::Default.into()))?;
+        Ok(Box::new(ProtobufParser::new(
+            input_stream,
+            descriptor,
+            config.max_message_size,
+        )) as Box<dyn Parser>)
+    }
+}
+
+/// Decode a varint length prefix from the start of `data`.
+///
+/// Returns the decoded length and the number of bytes occupied by the
+/// prefix, `Ok(None)` if `data` ends in the middle of the prefix, or an
+/// error if the prefix is not a valid varint.
+fn decode_length_prefix(data: &[u8]) -> Result<Option<(usize, usize)>, ()> {
+    let mut value = 0u64;
+
+    for (i, byte) in data.iter().take(MAX_VARINT_LEN).enumerate() {
+        value |= ((byte & 0x7f) as u64) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok(Some((value as usize, i + 1)));
+        }
+    }
+
+    if data.len() >= MAX_VARINT_LEN {
+        Err(())
+    } else {
+        Ok(None)
+    }
+}
+
+struct ProtobufParser {
+    /// Input handle to push parsed data to.
+    input_stream: Box<dyn DeCollectionStream>,
+
+    /// Descriptor of the message type.
+    descriptor: MessageDescriptor,
+
+    /// Messages longer than this are skipped.
+    max_message_size: usize,
+
+    /// Number of bytes of a skipped message that haven't been received yet.
+    skip: usize,
+
+    /// Since we cannot assume that the input buffer ends on a message
+    /// boundary, we save the incomplete message (including possibly
+    /// incomplete length prefix) in the end of the buffer and prepend
+    /// it to the next input buffer.
+    leftover: Vec<u8>,
+
+    /// Reusable buffer to serialize messages to JSON.
+    json_buffer: Vec<u8>,
+
+    last_event_number: u64,
+}
+
+impl ProtobufParser {
+    fn new(
+        input_stream: Box<dyn DeCollectionStream>,
+        descriptor: MessageDescriptor,
+        max_message_size: usize,
+    ) -> Self {
+        Self {
+            input_stream,
+            descriptor,
+            max_message_size,
+            skip: 0,
+            leftover: Vec::new(),
+            json_buffer: Vec::new(),
+            last_event_number: 0,
+        }
+    }
+
+    /// Decode a single message and push it to the circuit.
+    fn parse_message(&mut self, message: &[u8]) -> Result<(), ParseError> {
+        let message = DynamicMessage::decode(self.descriptor.clone(), message).map_err(|e| {
+            ParseError::bin_event_error(
+                format!(
+                    "failed to decode protobuf message of type '{}': {e}",
+                    self.descriptor.full_name()
+                ),
+                self.last_event_number + 1,
+                message,
+                None,
+            )
+        })?;
+
+        self.json_buffer.clear();
+        let options = SerializeOptions::new()
+            .skip_default_fields(false)
+            .stringify_64_bit_integers(false)
+            .use_proto_field_name(true);
+        message
+            .serialize_with_options(
+                &mut serde_json::Serializer::new(&mut self.json_buffer),
+                &options,
+            )
+            .map_err(|e| {
+                ParseError::new(
+                    format!("failed to convert protobuf message to a record: {e}"),
+                    Some(self.last_event_number + 1),
+                    None,
+                    None,
+                    None,
+                    None,
+                )
+            })?;
+
+        self.input_stream.insert(&self.json_buffer).map_err(|e| {
+            ParseError::text_event_error(
+                "failed to deserialize protobuf message",
+                e,
+                self.last_event_number + 1,
+                Some(&String::from_utf8_lossy(&self.json_buffer)),
+                None,
+            )
+        })
+    }
+
+    /// Parse all complete messages in `buffer`.
+    ///
+    /// Returns the number of bytes consumed from the buffer, the number of
+    /// parsed records, and parser errors.
+    fn parse_from_buffer(&mut self, buffer: &[u8]) -> (usize, usize, Vec<ParseError>) {
+        let mut errors = Vec::new();
+        let mut num_records = 0;
+
+        // Discard the remainder of a skipped message.
+        let mut offset = self.skip.min(buffer.len());
+        self.skip -= offset;
+
+        while offset < buffer.len() {
+            let (len, prefix_len) = match decode_length_prefix(&buffer[offset..]) {
+                Ok(Some(prefix)) => prefix,
+                Ok(None) => break,
+                Err(()) => {
+                    // We cannot find the next message boundary; drop the
+                    // rest of the buffer.
+                    errors.push(ParseError::bin_envelope_error(
+                        "invalid length prefix in a length-delimited protobuf stream".to_string(),
+                        &buffer[offset..offset + MAX_VARINT_LEN],
+                        None,
+                    ));
+                    offset = buffer.len();
+                    break;
+                }
+            };
+
+            let start = offset + prefix_len;
+            if len > self.max_message_size {
+                // Report the error right away and discard the message as it
+                // arrives instead of buffering it.
+                errors.push(ParseError::bin_envelope_error(
+                    format!(
+                        "protobuf message length {len} exceeds the maximum message size of {} bytes",
+                        self.max_message_size
+                    ),
+                    &buffer[offset..start],
+                    None,
+                ));
+                self.last_event_number += 1;
+                let skipped = len.min(buffer.len() - start);
+                self.skip = len - skipped;
+                offset = start + skipped;
+                continue;
+            }
+
+            if buffer.len() - start < len {
+                // Incomplete message.
+                break;
+            }
+
+            match self.parse_message(&buffer[start..start + len]) {
+                Err(e) => errors.push(e),
+                Ok(()) => num_records += 1,
+            }
+            self.last_event_number += 1;
+            offset = start + len;
+        }
+
+        self.input_stream.flush();
+        (offset, num_records, errors)
+    }
+}
+
+impl Parser for ProtobufParser {
+    fn input_fragment(&mut self, data: &[u8]) -> (usize, Vec<ParseError>) {
+        let mut buffer = take(&mut self.leftover);
+        buffer.extend_from_slice(data);
+
+        let (consumed, num_records, errors) = self.parse_from_buffer(buffer.as_slice());
+
+        buffer.drain(0..consumed);
+        self.leftover = buffer;
+
+        (num_records, errors)
+    }
+
+    fn eoi(&mut self) -> (usize, Vec<ParseError>) {
+        // The truncated message, if any, has already been reported.
+        self.skip = 0;
+
+        if self.leftover.is_empty() {
+            return (0, Vec::new());
+        }
+
+        let error = ParseError::bin_envelope_error(
+            "incomplete protobuf message at the end of the input stream".to_string(),
+            &self.leftover,
+            None,
+        );
+        self.leftover.clear();
+
+        (0, vec![error])
+    }
+
+    fn fork(&self) -> Box<dyn Parser> {
+        Box::new(Self::new(
+            self.input_stream.fork(),
+            self.descriptor.clone(),
+            self.max_message_size,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::decode_length_prefix;
+    use crate::{
+        test::{mock_parser_pipeline, TestStruct},
+        transport::InputConsumer,
+        FormatConfig,
+    };
+    use pipeline_types::format::protobuf::ProtobufParserConfig;
+    use prost::Message;
+    use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor, Value};
+    use prost_types::{
+        field_descriptor_proto::{Label, Type},
+        DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet,
+    };
+    use std::{borrow::Cow, io::Write};
+    use tempfile::NamedTempFile;
+
+    /// Descriptor of the `test.TestStruct` message that matches `TestStruct`.
+    fn test_descriptor_set() -> FileDescriptorSet {
+        let field = |name: &str, number: i32, typ: Type| FieldDescriptorProto {
+            name: Some(name.to_string()),
+            number: Some(number),
+            label: Some(Label::Optional as i32),
+            r#type: Some(typ as i32),
+            ..Default::default()
+        };
+
+        FileDescriptorSet {
+            file: vec![FileDescriptorProto {
+                name: Some("test.proto".to_string()),
+                package: Some("test".to_string()),
+                syntax: Some("proto3".to_string()),
+                message_type: vec![DescriptorProto {
+                    name: Some("TestStruct".to_string()),
+                    field: vec![
+                        field("id", 1, Type::Uint32),
+                        field("b", 2, Type::Bool),
+                        field("i", 3, Type::Int64),
+                        field("s", 4, Type::String),
+                    ],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }
+    }
+
+    fn encode(descriptor: &MessageDescriptor, val: &TestStruct) -> Vec<u8> {
+        let mut message = DynamicMessage::new(descriptor.clone());
+        message.set_field_by_name("id", Value::U32(val.id));
+        message.set_field_by_name("b", Value::Bool(val.b));
+        message.set_field_by_name("i", Value::I64(val.i.unwrap()));
+        message.set_field_by_name("s", Value::String(val.s.clone()));
+        message.encode_length_delimited_to_vec()
+    }
+
+    #[test]
+    fn test_length_prefix() {
+        assert_eq!(decode_length_prefix(&[]), Ok(None));
+        assert_eq!(decode_length_prefix(&[5, 1, 2]), Ok(Some((5, 1))));
+        assert_eq!(decode_length_prefix(&[0xac]), Ok(None));
+        assert_eq!(decode_length_prefix(&[0xac, 0x02]), Ok(Some((300, 2))));
+        assert_eq!(decode_length_prefix(&[0xff; 10]), Err(()));
+    }
+
+    #[test]
+    fn test_protobuf_split_messages() {
+        let descriptor_set = test_descriptor_set();
+        let mut descriptor_file = NamedTempFile::new().unwrap();
+        descriptor_file
+            .write_all(&descriptor_set.encode_to_vec())
+            .unwrap();
+
+        let pool = DescriptorPool::decode(descriptor_set.encode_to_vec().as_slice()).unwrap();
+        let descriptor = pool.get_message_by_name("test.TestStruct").unwrap();
+
+        let expected = vec![
+            TestStruct {
+                id: 1,
+                b: true,
+                i: Some(-10),
+                s: "foo".to_string(),
+            },
+            // Long enough for the length prefix to take two bytes.
+            TestStruct {
+                id: 2,
+                b: false,
+                i: Some(1 << 40),
+                s: "bar".repeat(100),
+            },
+        ];
+
+        let mut stream = Vec::new();
+        for val in expected.iter() {
+            stream.extend_from_slice(&encode(&descriptor, val));
+        }
+
+        let format_config = FormatConfig {
+            name: Cow::from("protobuf"),
+            config: serde_yaml::to_value(ProtobufParserConfig {
+                descriptor_path: descriptor_file.path().to_string_lossy().to_string(),
+                message_name: "test.TestStruct".to_string(),
+                max_message_size: 1024,
+            })
+            .unwrap(),
+        };
+
+        // Split the stream at every possible position, including in the
+        // middle of a length prefix and in the middle of a message body.
+        for split in 0..=stream.len() {
+            let (mut consumer, outputs) =
+                mock_parser_pipeline::<TestStruct>(&format_config).unwrap();
+            consumer.on_error(Some(Box::new(|_| {})));
+
+            assert_eq!(consumer.input_fragment(&stream[..split]), Vec::new());
+            assert_eq!(consumer.input_fragment(&stream[split..]), Vec::new());
+            assert_eq!(consumer.eoi(), Vec::new());
+
+            assert_eq!(
+                outputs.state().flushed,
+                expected
+                    .iter()
+                    .map(|val| (val.clone(), true))
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_protobuf_incomplete_message() {
+        let descriptor_set = test_descriptor_set();
+        let mut descriptor_file = NamedTempFile::new().unwrap();
+        descriptor_file
+            .write_all(&descriptor_set.encode_to_vec())
+            .unwrap();
+
+        let format_config = FormatConfig {
+            name: Cow::from("protobuf"),
+            config: serde_yaml::to_value(ProtobufParserConfig {
+                descriptor_path: descriptor_file.path().to_string_lossy().to_string(),
+                message_name: "test.TestStruct".to_string(),
+                max_message_size: 1024,
+            })
+            .unwrap(),
+        };
+
+        let (mut consumer, outputs) = mock_parser_pipeline::<TestStruct>(&format_config).unwrap();
+        consumer.on_error(Some(Box::new(|_| {})));
+
+        // Length prefix promises 10 bytes, but only 2 are available.
+        assert_eq!(consumer.input_fragment(&[10, 8, 1]), Vec::new());
+        assert_eq!(consumer.eoi().len(), 1);
+        assert!(outputs.state().flushed.is_empty());
+    }
+
+    #[test]
+    fn test_protobuf_max_message_size() {
+        let descriptor_set = test_descriptor_set();
+        let mut descriptor_file = NamedTempFile::new().unwrap();
+        descriptor_file
+            .write_all(&descriptor_set.encode_to_vec())
+            .unwrap();
+
+        let pool = DescriptorPool::decode(descriptor_set.encode_to_vec().as_slice()).unwrap();
+        let descriptor = pool.get_message_by_name("test.TestStruct").unwrap();
+
+        let small = |id| TestStruct {
+            id,
+            b: true,
+            i: Some(-10),
+            s: "foo".to_string(),
+        };
+        let large = TestStruct {
+            id: 2,
+            b: false,
+            i: Some(1 << 40),
+            s: "bar".repeat(100),
+        };
+
+        let mut stream = Vec::new();
+        for val in [small(1), large, small(3)].iter() {
+            stream.extend_from_slice(&encode(&descriptor, val));
+        }
+
+        let format_config = FormatConfig {
+            name: Cow::from("protobuf"),
+            config: serde_yaml::to_value(ProtobufParserConfig {
+                descriptor_path: descriptor_file.path().to_string_lossy().to_string(),
+                message_name: "test.TestStruct".to_string(),
+                max_message_size: 100,
+            })
+            .unwrap(),
+        };
+
+        // The oversized message is reported once and skipped, wherever the
+        // stream is split, and the parser picks up the next message.
+        for split in 0..=stream.len() {
+            let (mut consumer, outputs) =
+                mock_parser_pipeline::<TestStruct>(&format_config).unwrap();
+            consumer.on_error(Some(Box::new(|_| {})));
+
+            let mut errors = consumer.input_fragment(&stream[..split]);
+            errors.extend(consumer.input_fragment(&stream[split..]));
+            errors.extend(consumer.eoi());
+            assert_eq!(errors.len(), 1);
+
+            assert_eq!(
+                outputs.state().flushed,
+                vec![(small(1), true), (small(3), true)]
+            );
+        }
+    }
+}
@@ -0,0 +1,275 @@
+use crate::{
+    catalog::SerBatch,
+    format::{Encoder, OutputConsumer},
+};
+use anyhow::{anyhow, Result as AnyResult};
+use std::sync::Arc;
+
+/// Create an encoder that feeds every batch to both `first` and `second`.
+///
+/// See [`TeeEncoder`].
+pub fn tee(first: Box<dyn Encoder>, second: Box<dyn Encoder>) -> TeeEncoder {
+    TeeEncoder::new(first, second)
+}
+
+/// Encoder that sends the same output stream to two encoders.
+///
+/// Allows sending the output of a circuit to two sinks in different formats,
+/// e.g., a CSV file and a JSON topic, without evaluating the circuit twice.
+/// Each inner encoder is connected to its own [`OutputConsumer`] and enforces
+/// the `max_buffer_size_bytes` limit of this consumer independently of the
+/// other encoder.
+///
+/// # Errors
+///
+/// Both encoders always receive every batch, even if the other encoder
+/// fails, so that a failure of one sink doesn't cause the other sink to
+/// lose data.  If one or both encoders fail, [`Encoder::encode`] returns an
+/// error that identifies the failed encoder(s) and includes their error
+/// messages.  The rows encoded by the encoder that succeeded are not rolled
+/// back.
+pub struct TeeEncoder {
+    consumers: TeeConsumers,
+}
+
+impl TeeEncoder {
+    /// Create an encoder that feeds every batch to both `first` and `second`.
+    pub fn new(mut first: Box<dyn Encoder>, mut second: Box<dyn Encoder>) -> Self {
+        // Buffers pushed to the tee's consumer are forwarded to both inner
+        // consumers, so they must fit in either of them.
+        let max_buffer_size_bytes = first
+            .consumer()
+            .max_buffer_size_bytes()
+            .min(second.consumer().max_buffer_size_bytes());
+
+        Self {
+            consumers: TeeConsumers {
+                first,
+                second,
+                max_buffer_size_bytes,
+            },
+        }
+    }
+}
+
+/// Combine the results of applying the same operation to both encoders.
+fn combine_results<T>(first: AnyResult<T>, second: AnyResult<T>) -> AnyResult<(T, T)> {
+    match (first, second) {
+        (Ok(first), Ok(second)) => Ok((first, second)),
+        (Err(error), Ok(_)) => Err(anyhow!("first encoder in tee failed: {error}")),
+        (Ok(_), Err(error)) => Err(anyhow!("second encoder in tee failed: {error}")),
+        (Err(first), Err(second)) => Err(anyhow!(
+            "both encoders in tee failed: first encoder: {first}; second encoder: {second}"
+        )),
+    }
+}
+
+impl Encoder for TeeEncoder {
+    fn consumer(&mut self) -> &mut dyn OutputConsumer {
+        &mut self.consumers
+    }
+
+    fn encode(&mut self, batches: &[Arc<dyn SerBatch>]) -> AnyResult<()> {
+        let first = self.consumers.first.encode(batches);
+        let second = self.consumers.second.encode(batches);
+
+        combine_results(first, second).map(|_| ())
+    }
+
+    fn flush_pending(&mut self) -> AnyResult<bool> {
+        let first = self.consumers.first.flush_pending();
+        let second = self.consumers.second.flush_pending();
+
+        combine_results(first, second).map(|(first, second)| first && second)
+    }
+}
+
+/// Consumers of both encoders, exposed as a single [`OutputConsumer`].
+///
+/// Forwards batch start and end notifications and buffers pushed to it to
+/// both consumers.  Data encoded by the inner encoders is pushed directly to
+/// each consumer by its own encoder.
+struct TeeConsumers {
+    first: Box<dyn Encoder>,
+    second: Box<dyn Encoder>,
+    max_buffer_size_bytes: usize,
+}
+
+impl OutputConsumer for TeeConsumers {
+    fn max_buffer_size_bytes(&self) -> usize {
+        self.max_buffer_size_bytes
+    }
+
+    fn batch_start(&mut self) {
+        self.first.consumer().batch_start();
+        self.second.consumer().batch_start();
+    }
+
+    fn push_buffer(&mut self, buffer: &[u8]) {
+        self.first.consumer().push_buffer(buffer);
+        self.second.consumer().push_buffer(buffer);
+    }
+
+    fn batch_end(&mut self) {
+        self.first.consumer().batch_end();
+        self.second.consumer().batch_end();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::tee;
+    use crate::{
+        catalog::SerBatch,
+        format::{Encoder, OutputFormat},
+        static_compile::seroutput::SerBatchImpl,
+        test::{MockOutputConsumer, TestStruct},
+    };
+    use csv::ReaderBuilder as CsvReaderBuilder;
+    use dbsp::{trace::Batch, OrdZSet};
+    use pipeline_types::format::{csv::CsvEncoderConfig, json::JsonEncoderConfig};
+    use serde_json::Value as JsonValue;
+    use std::sync::{Arc, Mutex};
+
+    fn test_data() -> Vec<(TestStruct, i64)> {
+        (0..10)
+            .map(|id| {
+                (
+                    TestStruct {
+                        id,
+                        b: id % 3 == 0,
+                        i: Some(id as i64 * 10),
+                        s: format!("record {id}"),
+                    },
+                    1,
+                )
+            })
+            .collect()
+    }
+
+    fn csv_encoder(consumer: MockOutputConsumer) -> Box<dyn Encoder> {
+        let config = serde_yaml::to_value(CsvEncoderConfig {
+            buffer_size_records: 2,
+            include_weight: false,
+            float_precision: None,
+            header: false,
+            rename_columns: Default::default(),
+            column_order: None,
+            max_record_len_in_errmsg: 4096,
+            line_terminator: Default::default(),
+        })
+        .unwrap();
+
+        <dyn OutputFormat>::get_format("csv")
+            .unwrap()
+            .new_encoder("test", &config, Box::new(consumer))
+            .unwrap()
+    }
+
+    fn json_encoder(consumer: MockOutputConsumer) -> Box<dyn Encoder> {
+        let config = serde_yaml::to_value(JsonEncoderConfig {
+            update_format: Default::default(),
+            json_flavor: None,
+            buffer_size_records: 3,
+            array: false,
+            rename_columns: Default::default(),
+            float_precision: None,
+        })
+        .unwrap();
+
+        <dyn OutputFormat>::get_format("json")
+            .unwrap()
+            .new_encoder("test", &config, Box::new(consumer))
+            .unwrap()
+    }
+
+    fn csv_rows(data: &Arc<Mutex<Vec<u8>>>) -> Vec<TestStruct> {
+        let data = data.lock().unwrap();
+        CsvReaderBuilder::new()
+            .has_headers(false)
+            .from_reader(data.as_slice())
+            .deserialize::<TestStruct>()
+            .map(|record| record.unwrap())
+            .collect()
+    }
+
+    fn json_rows(data: &Arc<Mutex<Vec<u8>>>) -> Vec<TestStruct> {
+        let data = data.lock().unwrap();
+        serde_json::Deserializer::from_slice(data.as_slice())
+            .into_iter::<JsonValue>()
+            .map(|update| serde_json::from_value(update.unwrap()["insert"].clone()).unwrap())
+            .collect()
+    }
+
+    fn encode(encoder: &mut dyn Encoder) -> anyhow::Result<()> {
+        let zset = OrdZSet::from_keys((), test_data());
+        let batch = Arc::new(<SerBatchImpl<_, TestStruct, ()>>::new(zset)) as Arc<dyn SerBatch>;
+
+        encoder.consumer().batch_start();
+        let result = encoder.encode(&[batch]);
+        encoder.consumer().batch_end();
+        result
+    }
+
+    #[test]
+    fn test_tee_encoder() {
+        // Consumers with different buffer sizes.
+        let csv_consumer = MockOutputConsumer::with_max_buffer_size_bytes(64);
+        let json_consumer = MockOutputConsumer::new();
+        let csv_data = csv_consumer.data.clone();
+        let json_data = json_consumer.data.clone();
+
+        let mut encoder = tee(csv_encoder(csv_consumer), json_encoder(json_consumer));
+        encode(&mut encoder).unwrap();
+
+        let expected = test_data()
+            .into_iter()
+            .map(|(val, _w)| val)
+            .collect::<Vec<_>>();
+        assert_eq!(csv_rows(&csv_data), expected);
+        assert_eq!(json_rows(&json_data), expected);
+    }
+
+    #[test]
+    fn test_tee_push_buffer() {
+        let first = MockOutputConsumer::with_max_buffer_size_bytes(64);
+        let second = MockOutputConsumer::new();
+        let first_data = first.data.clone();
+        let second_data = second.data.clone();
+
+        // Buffers pushed to the tee's consumer reach both inner consumers.
+        let mut encoder = tee(csv_encoder(first), json_encoder(second));
+        let consumer = encoder.consumer();
+        assert_eq!(consumer.max_buffer_size_bytes(), 64);
+        consumer.batch_start();
+        consumer.push_buffer(b"foo\n");
+        consumer.batch_end();
+
+        assert_eq!(first_data.lock().unwrap().as_slice(), b"foo\n");
+        assert_eq!(second_data.lock().unwrap().as_slice(), b"foo\n");
+    }
+
+    #[test]
+    fn test_tee_encoder_error() {
+        // JSON records don't fit in the buffer of the second consumer.
+        let csv_consumer = MockOutputConsumer::new();
+        let json_consumer = MockOutputConsumer::with_max_buffer_size_bytes(32);
+        let csv_data = csv_consumer.data.clone();
+
+        let mut encoder = tee(csv_encoder(csv_consumer), json_encoder(json_consumer));
+        let error = encode(&mut encoder).unwrap_err().to_string();
+        assert!(
+            error.starts_with(
+                "second encoder in tee failed: JSON record exceeds maximum buffer size"
+            ),
+            "{error}"
+        );
+
+        // The first encoder still received all rows.
+        let expected = test_data()
+            .into_iter()
+            .map(|(val, _w)| val)
+            .collect::<Vec<_>>();
+        assert_eq!(csv_rows(&csv_data), expected);
+    }
+}
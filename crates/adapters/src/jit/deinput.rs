@@ -1,8 +1,12 @@
 use anyhow::Result as AnyResult;
 use dataflow_jit::facade::{DeCollectionStream as JitDeCollectionStream, JsonZSetHandle};
-use pipeline_types::format::json::JsonFlavor;
+use pipeline_types::format::json::{JsonFlavor, JsonOverflowPolicy};
+use std::collections::HashMap;
 
-use crate::{catalog::RecordFormat, ControllerError, DeCollectionHandle, DeCollectionStream};
+use crate::{
+    catalog::{JsonOptions, RecordFormat},
+    ControllerError, DeCollectionHandle, DeCollectionStream,
+};
 
 impl<JS> DeCollectionStream for JS
 where
@@ -35,16 +39,13 @@ where
 /// [`DeCollectionHandle`] implementation using pre-compiled deserializers
 /// for all supported formats.
 pub struct DeZSetHandles {
-    default_json: JsonZSetHandle,
-    debezium_mysql_json: JsonZSetHandle,
+    /// JSON deserializers indexed by flavor and overflow policy.
+    json: HashMap<(JsonFlavor, JsonOverflowPolicy), JsonZSetHandle>,
 }
 
 impl DeZSetHandles {
-    pub fn new(default_json: JsonZSetHandle, debezium_mysql_json: JsonZSetHandle) -> Self {
-        Self {
-            default_json,
-            debezium_mysql_json,
-        }
+    pub fn new(json: HashMap<(JsonFlavor, JsonOverflowPolicy), JsonZSetHandle>) -> Self {
+        Self { json }
     }
 }
 
@@ -54,19 +55,30 @@ impl DeCollectionHandle for DeZSetHandles {
         record_format: RecordFormat,
     ) -> Result<Box<dyn DeCollectionStream>, ControllerError> {
         match record_format {
-            RecordFormat::Json(JsonFlavor::Default) => Ok(Box::new(self.default_json.clone())),
-            RecordFormat::Json(JsonFlavor::DebeziumMySql) => {
-                Ok(Box::new(self.debezium_mysql_json.clone()))
-            }
-            RecordFormat::Json(JsonFlavor::Snowflake) => {
-                Err(ControllerError::input_format_not_supported(
-                    "unknown endpoint",
-                    "Snowflake JSON input format is not supported in JIT mode",
-                ))
-            }
-            RecordFormat::Csv => {
+            RecordFormat::Json(JsonOptions {
+                flavor: JsonFlavor::Snowflake,
+                ..
+            }) => Err(ControllerError::input_format_not_supported(
+                "unknown endpoint",
+                "Snowflake JSON input format is not supported in JIT mode",
+            )),
+            RecordFormat::Json(JsonOptions {
+                flavor,
+                on_overflow,
+                ..
+            }) => match self.json.get(&(flavor.clone(), on_overflow)) {
+                Some(handle) => Ok(Box::new(handle.clone())),
+                None => Err(ControllerError::jit_error(&format!(
+                    "JsonSetHandle[{flavor:?}, {on_overflow:?}] not found"
+                ))),
+            },
+            RecordFormat::Csv(_) => {
                 todo!()
             }
+            RecordFormat::Parquet => Err(ControllerError::input_format_not_supported(
+                "unknown endpoint",
+                "Parquet input format is not supported",
+            )),
         }
     }
 }
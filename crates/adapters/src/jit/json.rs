@@ -1,4 +1,4 @@
-use pipeline_types::format::json::JsonFlavor;
+use pipeline_types::format::json::{JsonFlavor, JsonOverflowPolicy};
 
 use super::schema::{ColumnSchema, TableSchema};
 use dataflow_jit::{
@@ -39,6 +39,7 @@ pub(crate) fn build_json_deser_config(
     layout: LayoutId,
     table_schema: &TableSchema,
     flavor: &JsonFlavor,
+    on_overflow: JsonOverflowPolicy,
 ) -> JsonDeserConfig {
     let mappings = table_schema
         .fields
@@ -46,7 +47,12 @@ pub(crate) fn build_json_deser_config(
         .enumerate()
         .map(|(index, column)| (index, column_from_schema(column, true, flavor)))
         .collect();
-    JsonDeserConfig { layout, mappings }
+    JsonDeserConfig {
+        layout,
+        mappings,
+        reject_unknown: false,
+        on_overflow,
+    }
 }
 
 /// Build JSON serializer configuration for specified layout and table schema.
@@ -61,5 +67,11 @@ pub(crate) fn build_json_ser_config(
         .enumerate()
         .map(|(index, column)| (index, column_from_schema(column, false, flavor)))
         .collect();
-    JsonSerConfig { layout, mappings }
+    JsonSerConfig {
+        layout,
+        mappings,
+        // Serializers are compiled before output endpoints are configured, so
+        // endpoints that set `float_precision` are rejected by `SerZSet`.
+        float_precision: None,
+    }
 }
@@ -8,7 +8,7 @@ pub mod schema;
 pub mod seroutput;
 
 use crate::Catalog;
-use pipeline_types::format::json::JsonFlavor;
+use pipeline_types::format::json::{JsonFlavor, JsonOverflowPolicy};
 pub use schema::ProgramSchema;
 use std::{collections::HashMap, path::PathBuf};
 
@@ -107,20 +107,26 @@ pub fn start_circuit(
     // println!("sink_names: {source_names:?}");
 
     let mut demands = Demands::new();
-    let mut default_json_input_demands: HashMap<NodeId, DemandId> = HashMap::new();
-    let mut debezium_mysql_json_input_demands: HashMap<NodeId, DemandId> = HashMap::new();
+    let mut json_input_demands: HashMap<NodeId, Vec<(JsonFlavor, JsonOverflowPolicy, DemandId)>> =
+        HashMap::new();
     for table_schema in schema.inputs.iter() {
         let (node, layout) = source_names.get(&table_schema.name).ok_or_else(|| ControllerError::schema_validation_error(&format!("program schema specifies input table '{}', which does not exist in the dataflow graph", &table_schema.name)))?;
 
-        let default_json_config =
-            build_json_deser_config(*layout, table_schema, &JsonFlavor::Default);
-        let debezium_mysql_json_config =
-            build_json_deser_config(*layout, table_schema, &JsonFlavor::DebeziumMySql);
-        default_json_input_demands.insert(*node, demands.add_json_deserialize(default_json_config));
-        debezium_mysql_json_input_demands.insert(
-            *node,
-            demands.add_json_deserialize(debezium_mysql_json_config),
-        );
+        // Compile a deserializer for every combination of flavor and overflow
+        // policy, since input endpoints are configured after the circuit has
+        // been instantiated.
+        let node_demands = json_input_demands.entry(*node).or_default();
+        for flavor in [JsonFlavor::Default, JsonFlavor::DebeziumMySql] {
+            for on_overflow in [
+                JsonOverflowPolicy::Error,
+                JsonOverflowPolicy::Saturate,
+                JsonOverflowPolicy::Wrap,
+            ] {
+                let config = build_json_deser_config(*layout, table_schema, &flavor, on_overflow);
+                let demand = demands.add_json_deserialize(config);
+                node_demands.push((flavor.clone(), on_overflow, demand));
+            }
+        }
 
         // let csv_config = build_csv_deser_config(table_schema);
         // demands.add_csv_deserialize(*layout, csv_config);
@@ -172,30 +178,21 @@ pub fn start_circuit(
     for table_schema in schema.inputs.iter() {
         let node_id = source_names[&table_schema.name].0;
 
-        // FIXME: This is unsafe. The correct fix is to make sure `endpoint.disconnect`
-        // returns after all endpoint threads have terminated.
-        let default_json =
-            unsafe { circuit.json_input_zset(node_id, default_json_input_demands[&node_id]) }
-                .ok_or_else(|| {
-                    ControllerError::jit_error(&format!(
-                        "JsonSetHandle[Default] not found (table name: '{}', node id: {})",
-                        table_schema.name, node_id,
-                    ))
-                })?;
-        let debezium_mysql_json = unsafe {
-            circuit.json_input_zset(node_id, debezium_mysql_json_input_demands[&node_id])
+        let mut json_handles = HashMap::new();
+        for (flavor, on_overflow, demand) in json_input_demands[&node_id].iter() {
+            // FIXME: This is unsafe. The correct fix is to make sure `endpoint.disconnect`
+            // returns after all endpoint threads have terminated.
+            let handle = unsafe { circuit.json_input_zset(node_id, *demand) }.ok_or_else(|| {
+                ControllerError::jit_error(&format!(
+                    "JsonSetHandle[{flavor:?}, {on_overflow:?}] not found (table name: '{}', node id: {})",
+                    table_schema.name, node_id,
+                ))
+            })?;
+            json_handles.insert((flavor.clone(), *on_overflow), handle);
         }
-        .ok_or_else(|| {
-            ControllerError::jit_error(&format!(
-                "JsonSetHandle[DebeziumMySQL] not found (table name: '{}', node id: {})",
-                table_schema.name, node_id,
-            ))
-        })?;
 
-        catalog.register_input_collection_handle(
-            &table_schema.name,
-            DeZSetHandles::new(default_json, debezium_mysql_json),
-        )
+        catalog
+            .register_input_collection_handle(&table_schema.name, DeZSetHandles::new(json_handles))
     }
 
     for table_schema in schema.outputs.iter() {
@@ -9,7 +9,7 @@ use dbsp::{
 use pipeline_types::format::json::JsonFlavor;
 
 use crate::{
-    catalog::{RecordFormat, SerCollectionHandle, SerCursor},
+    catalog::{JsonOptions, RecordFormat, SerCollectionHandle, SerCursor},
     ControllerError, SerBatch,
 };
 
@@ -47,8 +47,33 @@ impl SerBatch for SerZSet {
         record_format: RecordFormat,
     ) -> Result<Box<dyn SerCursor + 'a>, ControllerError> {
         match record_format {
-            RecordFormat::Csv => todo!(),
-            RecordFormat::Json(JsonFlavor::Snowflake) => Ok(Box::new(SerZSetCursor::new(
+            RecordFormat::Csv(_) => todo!(),
+            RecordFormat::Parquet => Err(ControllerError::output_format_not_supported(
+                "unknown endpoint",
+                "Parquet output format is not supported in JIT mode",
+            )),
+            RecordFormat::Json(JsonOptions { rename_columns, .. })
+                if !rename_columns.is_empty() =>
+            {
+                Err(ControllerError::output_format_not_supported(
+                    "unknown endpoint",
+                    "renaming output columns is not supported in JIT mode",
+                ))
+            }
+            // JIT serializers are compiled with the default precision when
+            // the circuit is instantiated, before output endpoints are
+            // configured.
+            RecordFormat::Json(JsonOptions {
+                float_precision: Some(_),
+                ..
+            }) => Err(ControllerError::output_format_not_supported(
+                "unknown endpoint",
+                "'float_precision' is not supported in JIT mode",
+            )),
+            RecordFormat::Json(JsonOptions {
+                flavor: JsonFlavor::Snowflake,
+                ..
+            }) => Ok(Box::new(SerZSetCursor::new(
                 self.zset.cursor(),
                 self.snowflake_json,
             ))),
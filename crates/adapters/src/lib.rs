@@ -148,10 +148,13 @@ pub use circuit_handle::DbspCircuitHandle;
 pub use server::{ErrorResponse, PipelineError};
 
 pub use catalog::{
-    Catalog, CircuitCatalog, DeCollectionHandle, DeCollectionStream, OutputQueryHandles, SerBatch,
-    SerCollectionHandle,
+    Catalog, CircuitCatalog, DeCollectionHandle, DeCollectionStream, GatedCollectionStream,
+    IngestionGate, OutputQueryHandles, SerBatch, SerCollectionHandle,
+};
+pub use format::{
+    Encoder, FormatRegistry, InputFormat, OutputConsumer, OutputFormat, ParseError, ParseErrorKind,
+    Parser, ParserCheckpoint, PushStatus,
 };
-pub use format::{Encoder, InputFormat, OutputConsumer, OutputFormat, ParseError, Parser};
 
 pub use controller::{
     ConfigError, ConnectorConfig, Controller, ControllerError, ControllerStatus, FormatConfig,
@@ -165,4 +168,5 @@ pub use transport::{
 pub use static_compile::{
     DateFormat, DeserializationContext, DeserializeWithContext, FieldParseError,
     SerializationContext, SerializeWithContext, SqlSerdeConfig, TimeFormat, TimestampFormat,
+    DATE_NEWTYPE, DECIMAL_NEWTYPE, TIMESTAMP_NEWTYPE, TIME_NEWTYPE,
 };
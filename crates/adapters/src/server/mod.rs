@@ -824,7 +824,7 @@ async fn output_endpoint(
                         .neighborhood_descr_handle
                         .as_ref()
                         .ok_or_else(|| PipelineError::NeighborhoodNotSupported)?
-                        .configure_deserializer(RecordFormat::Json(JsonFlavor::Default))?
+                        .configure_deserializer(RecordFormat::Json(JsonFlavor::Default.into()))?
                         .set_for_all(json.as_bytes())
                     {
                         // Dropping `response` triggers the finalizer closure, which will
@@ -344,14 +344,20 @@ mod test {
     use std::{io::Write, ops::Deref};
 
     use crate::{
-        catalog::{OutputCollectionHandles, RecordFormat},
+        catalog::{JsonOptions, OutputCollectionHandles, RecordFormat},
         test::TestStruct,
         Catalog, CircuitCatalog, SerBatch,
     };
     use dbsp::Runtime;
-    use pipeline_types::format::json::JsonFlavor;
-
-    const RECORD_FORMAT: RecordFormat = RecordFormat::Json(JsonFlavor::Default);
+    use pipeline_types::format::json::{JsonFlavor, JsonOverflowPolicy};
+    use std::collections::BTreeMap;
+
+    const RECORD_FORMAT: RecordFormat = RecordFormat::Json(JsonOptions {
+        flavor: JsonFlavor::Default,
+        rename_columns: BTreeMap::new(),
+        float_precision: None,
+        on_overflow: JsonOverflowPolicy::Error,
+    });
 
     fn batch_to_json(batch: &dyn SerBatch) -> String {
         let mut cursor = batch.cursor(RECORD_FORMAT.clone()).unwrap();
@@ -1,14 +1,34 @@
 use crate::{
-    catalog::{DeCollectionStream, RecordFormat},
+    catalog::{DeCollectionStream, JsonOptions, RecordFormat},
     format::byte_record_deserializer,
     ControllerError, DeCollectionHandle, DeserializeWithContext,
 };
 use anyhow::{anyhow, Result as AnyResult};
 use dbsp::{algebra::ZRingValue, CollectionHandle, DBData, DBWeight, InputHandle, UpsertHandle};
+use pipeline_types::format::json::JsonOverflowPolicy;
 use std::{collections::VecDeque, marker::PhantomData};
 
 use super::SqlSerdeConfig;
 
+/// Error returned for JSON overflow policies other than
+/// [`JsonOverflowPolicy::Error`]: serde rejects integers that don't fit into
+/// the target type.
+fn unsupported_overflow_policy() -> ControllerError {
+    ControllerError::input_format_not_supported(
+        "unknown endpoint",
+        "'on_overflow' policies other than 'error' are only supported in JIT mode",
+    )
+}
+
+/// Error returned for [`RecordFormat::Parquet`], which is only supported by
+/// output endpoints.
+fn unsupported_parquet_input() -> ControllerError {
+    ControllerError::input_format_not_supported(
+        "unknown endpoint",
+        "Parquet input format is not supported",
+    )
+}
+
 /// A deserializer that parses byte arrays into a strongly typed representation.
 pub trait DeserializerFromBytes<C> {
     /// Create an instance of a deserializer.
@@ -49,11 +69,12 @@ impl<C> DeserializerFromBytes<C> for CsvDeserializerFromBytes<C> {
         self.reader.get_mut().extend(data.iter());
         self.reader.read_byte_record(&mut self.record)?;
 
-        T::deserialize_with_context(
+        // Keep the original error, so that parsers can extract the position of
+        // the field that failed to parse from it.
+        Ok(T::deserialize_with_context(
             &mut byte_record_deserializer(&self.record, None),
             &self.config,
-        )
-        .map_err(|e| anyhow!(e.to_string()))
+        )?)
     }
 }
 
@@ -162,7 +183,7 @@ where
         record_format: RecordFormat,
     ) -> Result<Box<dyn DeScalarStream>, ControllerError> {
         match record_format {
-            RecordFormat::Csv => {
+            RecordFormat::Csv(_) => {
                 let config = SqlSerdeConfig::default();
                 Ok(Box::new(DeScalarStreamImpl::<
                     CsvDeserializerFromBytes<_>,
@@ -176,7 +197,13 @@ where
                     config,
                 )))
             }
-            RecordFormat::Json(flavor) => {
+            RecordFormat::Parquet => Err(unsupported_parquet_input()),
+            RecordFormat::Json(JsonOptions { on_overflow, .. })
+                if on_overflow != JsonOverflowPolicy::Error =>
+            {
+                Err(unsupported_overflow_policy())
+            }
+            RecordFormat::Json(JsonOptions { flavor, .. }) => {
                 let config = SqlSerdeConfig::from(flavor);
                 Ok(Box::new(DeScalarStreamImpl::<
                     JsonDeserializerFromBytes<_>,
@@ -276,7 +303,7 @@ where
         record_format: RecordFormat,
     ) -> Result<Box<dyn DeCollectionStream>, ControllerError> {
         match record_format {
-            RecordFormat::Csv => {
+            RecordFormat::Csv(_) => {
                 let config = SqlSerdeConfig::default();
                 Ok(Box::new(DeZSetStream::<
                     CsvDeserializerFromBytes<_>,
@@ -286,7 +313,13 @@ where
                     _,
                 >::new(self.handle.clone(), config)))
             }
-            RecordFormat::Json(flavor) => {
+            RecordFormat::Parquet => Err(unsupported_parquet_input()),
+            RecordFormat::Json(JsonOptions { on_overflow, .. })
+                if on_overflow != JsonOverflowPolicy::Error =>
+            {
+                Err(unsupported_overflow_policy())
+            }
+            RecordFormat::Json(JsonOptions { flavor, .. }) => {
                 let config = SqlSerdeConfig::from(flavor);
                 Ok(Box::new(DeZSetStream::<
                     JsonDeserializerFromBytes<_>,
@@ -405,13 +438,19 @@ where
         record_format: RecordFormat,
     ) -> Result<Box<dyn DeCollectionStream>, ControllerError> {
         match record_format {
-            RecordFormat::Csv => Ok(Box::new(
+            RecordFormat::Csv(_) => Ok(Box::new(
                 DeSetStream::<CsvDeserializerFromBytes<_>, K, D, _>::new(
                     self.handle.clone(),
                     SqlSerdeConfig::default(),
                 ),
             )),
-            RecordFormat::Json(flavor) => {
+            RecordFormat::Parquet => Err(unsupported_parquet_input()),
+            RecordFormat::Json(JsonOptions { on_overflow, .. })
+                if on_overflow != JsonOverflowPolicy::Error =>
+            {
+                Err(unsupported_overflow_policy())
+            }
+            RecordFormat::Json(JsonOptions { flavor, .. }) => {
                 Ok(Box::new(
                     DeSetStream::<JsonDeserializerFromBytes<_>, K, D, _>::new(
                         self.handle.clone(),
@@ -527,7 +566,7 @@ where
         record_format: RecordFormat,
     ) -> Result<Box<dyn DeCollectionStream>, ControllerError> {
         match record_format {
-            RecordFormat::Csv => Ok(Box::new(DeMapStream::<
+            RecordFormat::Csv(_) => Ok(Box::new(DeMapStream::<
                 CsvDeserializerFromBytes<_>,
                 K,
                 KD,
@@ -540,7 +579,13 @@ where
                 self.key_func.clone(),
                 SqlSerdeConfig::default(),
             ))),
-            RecordFormat::Json(flavor) => Ok(Box::new(DeMapStream::<
+            RecordFormat::Parquet => Err(unsupported_parquet_input()),
+            RecordFormat::Json(JsonOptions { on_overflow, .. })
+                if on_overflow != JsonOverflowPolicy::Error =>
+            {
+                Err(unsupported_overflow_policy())
+            }
+            RecordFormat::Json(JsonOptions { flavor, .. }) => Ok(Box::new(DeMapStream::<
                 JsonDeserializerFromBytes<_>,
                 K,
                 KD,
@@ -645,20 +690,20 @@ where
 
 #[cfg(test)]
 mod test {
-    use crate::static_compile::deinput::RecordFormat;
+    use crate::static_compile::deinput::{JsonOptions, RecordFormat};
     use crate::{
         deserialize_without_context,
         static_compile::{
             DeMapHandle, DeScalarHandle, DeScalarHandleImpl, DeSetHandle, DeZSetHandle,
         },
-        DeCollectionHandle,
+        DeCollectionHandle, DeCollectionStream, IngestionGate,
     };
     use csv::WriterBuilder as CsvWriterBuilder;
     use csv_core::{ReadRecordResult, Reader as CsvReader};
     use dbsp::{
         algebra::F32, trace::Batch, DBSPHandle, OrdIndexedZSet, OrdZSet, OutputHandle, Runtime,
     };
-    use pipeline_types::format::json::JsonFlavor;
+    use pipeline_types::format::json::{JsonFlavor, JsonOverflowPolicy};
     use serde_json::to_string as to_json_string;
     use size_of::SizeOf;
     use std::hash::Hash;
@@ -748,7 +793,7 @@ mod test {
         ];
 
         let mut input_stream = input_handle
-            .configure_deserializer(RecordFormat::Json(JsonFlavor::Default))
+            .configure_deserializer(RecordFormat::Json(JsonFlavor::Default.into()))
             .unwrap();
 
         for input in inputs.iter() {
@@ -777,6 +822,21 @@ mod test {
         dbsp.kill().unwrap();
     }
 
+    #[test]
+    fn test_unsupported_overflow_policy() {
+        let (dbsp, input_handle, _output_handle) = descalar_test_circuit(1);
+
+        for on_overflow in [JsonOverflowPolicy::Saturate, JsonOverflowPolicy::Wrap] {
+            let record_format = RecordFormat::Json(JsonOptions {
+                on_overflow,
+                ..JsonFlavor::Default.into()
+            });
+            assert!(input_handle.configure_deserializer(record_format).is_err());
+        }
+
+        dbsp.kill().unwrap();
+    }
+
     // Test circuit for DeCollectionHandle handles.
     fn decollection_test_circuit(workers: usize) -> (DBSPHandle, InputHandles, OutputHandles) {
         let (dbsp, ((zset_input, zset_output), (set_input, set_output), (map_input, map_output))) =
@@ -818,15 +878,15 @@ mod test {
     ) {
         let mut zset_stream = input_handles
             .0
-            .configure_deserializer(RecordFormat::Csv)
+            .configure_deserializer(RecordFormat::Csv(Default::default()))
             .unwrap();
         let mut set_stream = input_handles
             .1
-            .configure_deserializer(RecordFormat::Csv)
+            .configure_deserializer(RecordFormat::Csv(Default::default()))
             .unwrap();
         let mut map_stream = input_handles
             .2
-            .configure_deserializer(RecordFormat::Csv)
+            .configure_deserializer(RecordFormat::Csv(Default::default()))
             .unwrap();
 
         let zset_output = &output_handles.0;
@@ -906,15 +966,15 @@ mod test {
     ) {
         let mut zset_input = input_handles
             .0
-            .configure_deserializer(RecordFormat::Json(JsonFlavor::Default))
+            .configure_deserializer(RecordFormat::Json(JsonFlavor::Default.into()))
             .unwrap();
         let mut set_input = input_handles
             .1
-            .configure_deserializer(RecordFormat::Json(JsonFlavor::Default))
+            .configure_deserializer(RecordFormat::Json(JsonFlavor::Default.into()))
             .unwrap();
         let mut map_input = input_handles
             .2
-            .configure_deserializer(RecordFormat::Json(JsonFlavor::Default))
+            .configure_deserializer(RecordFormat::Json(JsonFlavor::Default.into()))
             .unwrap();
 
         let zset_output = &output_handles.0;
@@ -985,4 +1045,68 @@ mod test {
 
         dbsp.kill().unwrap();
     }
+
+    #[test]
+    fn test_pause_resume() {
+        let (mut dbsp, input_handles, output_handles) = decollection_test_circuit(NUM_WORKERS);
+        let zset_output = &output_handles.0;
+
+        let inputs = (0..10)
+            .map(|id| TestStruct {
+                id,
+                s: format!("s{id}"),
+                b: id % 2 == 0,
+                o: None,
+            })
+            .collect::<Vec<_>>();
+
+        let gate = IngestionGate::new();
+        let mut zset_stream = gate.wrap(
+            input_handles
+                .0
+                .configure_deserializer(RecordFormat::Json(JsonFlavor::Default.into()))
+                .unwrap(),
+        );
+
+        // Updates flushed while the gate is paused are held back.
+        gate.pause();
+        for input in inputs[0..5].iter() {
+            zset_stream
+                .insert(to_json_string(input).unwrap().as_bytes())
+                .unwrap();
+            zset_stream.flush();
+        }
+        dbsp.step().unwrap();
+        assert_eq!(zset_output.consolidate(), OrdZSet::empty(()));
+
+        // Clearing the buffer doesn't affect held updates.
+        for input in inputs[5..].iter() {
+            zset_stream
+                .insert(to_json_string(input).unwrap().as_bytes())
+                .unwrap();
+        }
+        zset_stream.clear_buffer();
+        zset_stream
+            .insert(to_json_string(&inputs[5]).unwrap().as_bytes())
+            .unwrap();
+        zset_stream.flush();
+        dbsp.step().unwrap();
+        assert_eq!(zset_output.consolidate(), OrdZSet::empty(()));
+
+        // All held updates reach the circuit after resuming.
+        gate.resume();
+        for input in inputs[6..].iter() {
+            zset_stream
+                .insert(to_json_string(input).unwrap().as_bytes())
+                .unwrap();
+        }
+        zset_stream.flush();
+        dbsp.step().unwrap();
+        assert_eq!(
+            zset_output.consolidate(),
+            OrdZSet::from_keys((), inputs.iter().map(|v| (v.clone(), 1)).collect())
+        );
+
+        dbsp.kill().unwrap();
+    }
 }
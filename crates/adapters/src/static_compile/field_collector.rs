@@ -0,0 +1,491 @@
+//! Serializer that buffers the fields of a record, so that they can be
+//! written out in a different order.
+//!
+//! [`FieldCollector`] flattens a record into a sequence of primitive
+//! [`Field`]s, remembering the name of the struct field that each of them
+//! belongs to.  [`Permuted`] replays the buffered fields to another
+//! serializer in the order given by a permutation.  Since each field is
+//! replayed using the same serializer method that produced it, the output
+//! is identical to serializing the record directly, except for the order
+//! of fields.
+//!
+//! SQL dates, times, timestamps, and decimals are wrapped in newtype structs
+//! by their serializers (see [`DATE_NEWTYPE`] and friends).  The collector
+//! buffers them as dedicated variants of [`Field`], so that consumers that
+//! build typed columns can tell them apart from integers and strings.
+
+use crate::{DATE_NEWTYPE, DECIMAL_NEWTYPE, TIMESTAMP_NEWTYPE, TIME_NEWTYPE};
+use rust_decimal::Decimal;
+use serde::{
+    ser::{
+        Error as _, Impossible, SerializeSeq, SerializeStruct, SerializeTuple, SerializeTupleStruct,
+    },
+    Serialize, Serializer,
+};
+use std::fmt::{self, Display};
+
+/// A primitive value buffered by [`FieldCollector`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Field {
+    Bool(bool),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    I128(i128),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    F32(f32),
+    F64(f64),
+    Char(char),
+    Str(String),
+    Bytes(Vec<u8>),
+    None,
+    Unit,
+    UnitStruct(&'static str),
+    UnitVariant(&'static str, u32, &'static str),
+    /// Days since UNIX epoch.
+    Date(i32),
+    /// Microseconds since midnight.
+    Time(u64),
+    /// Milliseconds since UNIX epoch.
+    Timestamp(i64),
+    Decimal(Decimal),
+}
+
+impl Serialize for Field {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::Bool(v) => serializer.serialize_bool(*v),
+            Self::I8(v) => serializer.serialize_i8(*v),
+            Self::I16(v) => serializer.serialize_i16(*v),
+            Self::I32(v) => serializer.serialize_i32(*v),
+            Self::I64(v) => serializer.serialize_i64(*v),
+            Self::I128(v) => serializer.serialize_i128(*v),
+            Self::U8(v) => serializer.serialize_u8(*v),
+            Self::U16(v) => serializer.serialize_u16(*v),
+            Self::U32(v) => serializer.serialize_u32(*v),
+            Self::U64(v) => serializer.serialize_u64(*v),
+            Self::U128(v) => serializer.serialize_u128(*v),
+            Self::F32(v) => serializer.serialize_f32(*v),
+            Self::F64(v) => serializer.serialize_f64(*v),
+            Self::Char(v) => serializer.serialize_char(*v),
+            Self::Str(v) => serializer.serialize_str(v),
+            Self::Bytes(v) => serializer.serialize_bytes(v),
+            Self::None => serializer.serialize_none(),
+            Self::Unit => serializer.serialize_unit(),
+            Self::UnitStruct(name) => serializer.serialize_unit_struct(name),
+            Self::UnitVariant(name, index, variant) => {
+                serializer.serialize_unit_variant(name, *index, variant)
+            }
+            Self::Date(v) => serializer.serialize_newtype_struct(DATE_NEWTYPE, v),
+            Self::Time(v) => serializer.serialize_newtype_struct(TIME_NEWTYPE, v),
+            Self::Timestamp(v) => serializer.serialize_newtype_struct(TIMESTAMP_NEWTYPE, v),
+            Self::Decimal(v) => serializer.serialize_newtype_struct(DECIMAL_NEWTYPE, v),
+        }
+    }
+}
+
+/// Error returned by [`FieldCollector`].
+#[derive(Debug)]
+pub(crate) struct FieldCollectorError(String);
+
+impl Display for FieldCollectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for FieldCollectorError {}
+
+impl serde::ser::Error for FieldCollectorError {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+/// Serializer that flattens a record into its primitive fields.
+///
+/// Sequences, tuples, and structs are flattened recursively, like the CSV
+/// serializer does.  Maps and enum variants with fields are rejected.
+pub(crate) struct FieldCollector<'a> {
+    fields: &'a mut Vec<Field>,
+    /// Name of the innermost struct field that each element of `fields`
+    /// belongs to, or `None` for fields that are not part of a struct.
+    names: &'a mut Vec<Option<&'static str>>,
+}
+
+impl<'a> FieldCollector<'a> {
+    /// Create a serializer that appends fields and their names to `fields`
+    /// and `names`.
+    pub(crate) fn new(
+        fields: &'a mut Vec<Field>,
+        names: &'a mut Vec<Option<&'static str>>,
+    ) -> Self {
+        Self { fields, names }
+    }
+
+    fn push(&mut self, field: Field) -> Result<(), FieldCollectorError> {
+        self.fields.push(field);
+        self.names.push(None);
+        Ok(())
+    }
+}
+
+impl<'a, 'b> Serializer for &'b mut FieldCollector<'a> {
+    type Ok = ();
+    type Error = FieldCollectorError;
+
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Impossible<(), FieldCollectorError>;
+    type SerializeMap = Impossible<(), FieldCollectorError>;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Impossible<(), FieldCollectorError>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), Self::Error> {
+        self.push(Field::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), Self::Error> {
+        self.push(Field::I8(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), Self::Error> {
+        self.push(Field::I16(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), Self::Error> {
+        self.push(Field::I32(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), Self::Error> {
+        self.push(Field::I64(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<(), Self::Error> {
+        self.push(Field::I128(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), Self::Error> {
+        self.push(Field::U8(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), Self::Error> {
+        self.push(Field::U16(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), Self::Error> {
+        self.push(Field::U32(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), Self::Error> {
+        self.push(Field::U64(v))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), Self::Error> {
+        self.push(Field::U128(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), Self::Error> {
+        self.push(Field::F32(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), Self::Error> {
+        self.push(Field::F64(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), Self::Error> {
+        self.push(Field::Char(v))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), Self::Error> {
+        self.push(Field::Str(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), Self::Error> {
+        self.push(Field::Bytes(v.to_vec()))
+    }
+
+    fn serialize_none(self) -> Result<(), Self::Error> {
+        self.push(Field::None)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Self::Error> {
+        self.push(Field::Unit)
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<(), Self::Error> {
+        self.push(Field::UnitStruct(name))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<(), Self::Error> {
+        self.push(Field::UnitVariant(name, variant_index, variant))
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut *self)?;
+        if ![
+            DATE_NEWTYPE,
+            TIME_NEWTYPE,
+            TIMESTAMP_NEWTYPE,
+            DECIMAL_NEWTYPE,
+        ]
+        .contains(&name)
+        {
+            return Ok(());
+        }
+
+        let field = match (name, self.fields.pop()) {
+            (DATE_NEWTYPE, Some(Field::I32(days))) => Field::Date(days),
+            (TIME_NEWTYPE, Some(Field::U64(micros))) => Field::Time(micros),
+            (TIMESTAMP_NEWTYPE, Some(Field::I64(millis))) => Field::Timestamp(millis),
+            (DECIMAL_NEWTYPE, Some(Field::Str(decimal))) => {
+                Field::Decimal(decimal.parse().map_err(Self::Error::custom)?)
+            }
+            (_, field) => {
+                return Err(Self::Error::custom(format!(
+                    "unexpected representation of '{name}' value: {field:?}"
+                )))
+            }
+        };
+        self.fields.push(field);
+        Ok(())
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Err(Self::Error::custom(format!(
+            "cannot serialize enum variant '{name}::{variant}' as a CSV field"
+        )))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Err(Self::Error::custom("cannot serialize a map as a CSV field"))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Err(Self::Error::custom(format!(
+            "cannot serialize enum variant '{name}::{variant}' as a CSV field"
+        )))
+    }
+}
+
+impl<'a, 'b> SerializeSeq for &'b mut FieldCollector<'a> {
+    type Ok = ();
+    type Error = FieldCollectorError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<'a, 'b> SerializeTuple for &'b mut FieldCollector<'a> {
+    type Ok = ();
+    type Error = FieldCollectorError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<'a, 'b> SerializeTupleStruct for &'b mut FieldCollector<'a> {
+    type Ok = ();
+    type Error = FieldCollectorError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<'a, 'b> SerializeStruct for &'b mut FieldCollector<'a> {
+    type Ok = ();
+    type Error = FieldCollectorError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
+    where
+        T: ?Sized + Serialize,
+    {
+        let start = self.names.len();
+        value.serialize(&mut **self)?;
+        for name in self.names[start..].iter_mut() {
+            name.get_or_insert(key);
+        }
+        Ok(())
+    }
+
+    fn end(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// Record whose fields are serialized as a tuple, in the order given by
+/// `permutation`, followed by the fields past the end of the permutation.
+pub(crate) struct Permuted<'a> {
+    pub fields: &'a [Field],
+    pub permutation: &'a [usize],
+}
+
+impl<'a> Serialize for Permuted<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut tuple = serializer.serialize_tuple(self.fields.len())?;
+        for index in self
+            .permutation
+            .iter()
+            .copied()
+            .chain(self.permutation.len()..self.fields.len())
+        {
+            tuple.serialize_element(&self.fields[index])?;
+        }
+        tuple.end()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Field, FieldCollector, Permuted};
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct TestStruct {
+        id: u32,
+        name: Option<String>,
+        inner: (bool, f64),
+    }
+
+    #[test]
+    fn test_field_collector() {
+        let val = TestStruct {
+            id: 5,
+            name: None,
+            inner: (true, 0.5),
+        };
+
+        let mut fields = Vec::new();
+        let mut names = Vec::new();
+        (&val, -1i64)
+            .serialize(&mut FieldCollector::new(&mut fields, &mut names))
+            .unwrap();
+        assert_eq!(
+            fields,
+            vec![
+                Field::U32(5),
+                Field::None,
+                Field::Bool(true),
+                Field::F64(0.5),
+                Field::I64(-1)
+            ]
+        );
+        assert_eq!(
+            names,
+            vec![Some("id"), Some("name"), Some("inner"), Some("inner"), None]
+        );
+
+        // Replaying the fields produces the same CSV record, except for the
+        // order of the permuted fields.
+        let mut writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .from_writer(Vec::new());
+        writer
+            .serialize(Permuted {
+                fields: &fields,
+                permutation: &[3, 0, 2, 1],
+            })
+            .unwrap();
+        assert_eq!(writer.into_inner().unwrap(), b"0.5,5,true,,-1\n");
+    }
+}
@@ -3,6 +3,7 @@
 pub mod catalog;
 pub mod deinput;
 pub mod deserialize_with_context;
+mod field_collector;
 pub mod serde_config;
 pub mod serialize_with_context;
 pub mod seroutput;
@@ -11,6 +12,10 @@ pub use deinput::{DeMapHandle, DeScalarHandle, DeScalarHandleImpl, DeSetHandle,
 pub use deserialize_with_context::{
     DeserializationContext, DeserializeWithContext, FieldParseError,
 };
-pub use serde_config::{DateFormat, SqlSerdeConfig, TimeFormat, TimestampFormat};
+pub use field_collector::Field;
+pub use serde_config::{
+    DateFormat, SqlSerdeConfig, TimeFormat, TimestampFormat, DATE_NEWTYPE, DECIMAL_NEWTYPE,
+    TIMESTAMP_NEWTYPE, TIME_NEWTYPE,
+};
 pub use serialize_with_context::{SerializationContext, SerializeWithContext};
 pub use seroutput::SerCollectionHandleImpl;
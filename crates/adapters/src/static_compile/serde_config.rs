@@ -10,6 +10,10 @@
 //! `SerializeWithContext<SqlSerializerConfig>`.
 
 use pipeline_types::format::json::JsonFlavor;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Mutex,
+};
 
 /// Representation of the SQL `TIME` type.
 #[derive(Clone)]
@@ -52,6 +56,8 @@ pub enum TimestampFormat {
     // See [`chrono` documentation](https://docs.rs/chrono/0.4.31/chrono/format/strftime/)
     // for supported formatting syntax.
     String(&'static str),
+    /// Timestamp specified as the number of milliseconds since UNIX epoch.
+    MillisSinceEpoch,
 }
 
 impl Default for TimestampFormat {
@@ -60,6 +66,25 @@ impl Default for TimestampFormat {
     }
 }
 
+/// Name of the newtype struct that wraps `DATE` values serialized as
+/// [`DateFormat::DaysSinceEpoch`].
+///
+/// Newtype structs are transparent to JSON and CSV serializers.  Serializers
+/// that build typed columns use the name to tell dates, times, timestamps, and
+/// decimals apart from integer and string columns.
+pub const DATE_NEWTYPE: &str = "Date";
+
+/// Name of the newtype struct that wraps `TIME` values serialized as
+/// [`TimeFormat::Micros`].
+pub const TIME_NEWTYPE: &str = "Time";
+
+/// Name of the newtype struct that wraps `TIMESTAMP` values serialized as
+/// [`TimestampFormat::MillisSinceEpoch`].
+pub const TIMESTAMP_NEWTYPE: &str = "Timestamp";
+
+/// Name of the newtype struct that wraps `DECIMAL` values.
+pub const DECIMAL_NEWTYPE: &str = "Decimal";
+
 /// Deserializer configuration for parsing SQL records.
 #[derive(Clone, Default)]
 pub struct SqlSerdeConfig {
@@ -69,6 +94,55 @@ pub struct SqlSerdeConfig {
     pub date_format: DateFormat,
     /// `TIMESTAMP` format.
     pub timestamp_format: TimestampFormat,
+    /// Number of decimal places used to serialize finite floating point
+    /// values; `None` uses the default representation.
+    pub float_precision: Option<usize>,
+    /// Write floating point values formatted according to `float_precision`
+    /// as raw JSON numbers rather than strings.  Must only be set for JSON
+    /// serializers.
+    pub json_float_numbers: bool,
+    /// Output column names that replace the names declared in the table
+    /// schema, indexed by the declared name.
+    pub rename_columns: BTreeMap<String, &'static str>,
+}
+
+/// Column names passed to [`SqlSerdeConfig::with_rename_columns`].
+///
+/// Serializers require field names to be `&'static str`.  Names are
+/// allocated once and reused by all configs that rename columns to them.
+static COLUMN_NAMES: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+
+impl SqlSerdeConfig {
+    /// Serialize columns whose declared names are keys in `rename_columns`
+    /// under the corresponding values.
+    pub fn with_rename_columns(mut self, rename_columns: &BTreeMap<String, String>) -> Self {
+        if rename_columns.is_empty() {
+            return self;
+        }
+
+        let mut names = COLUMN_NAMES.lock().unwrap();
+        self.rename_columns = rename_columns
+            .iter()
+            .map(|(from, to)| {
+                let to = match names.get(to.as_str()) {
+                    Some(to) => *to,
+                    None => {
+                        let to: &'static str = Box::leak(to.clone().into_boxed_str());
+                        names.insert(to);
+                        to
+                    }
+                };
+                (from.clone(), to)
+            })
+            .collect();
+        self
+    }
+
+    /// Returns the name under which the column declared as `name` is
+    /// serialized.
+    pub fn column_name(&self, name: &'static str) -> &'static str {
+        self.rename_columns.get(name).copied().unwrap_or(name)
+    }
 }
 
 impl From<JsonFlavor> for SqlSerdeConfig {
@@ -79,11 +153,17 @@ impl From<JsonFlavor> for SqlSerdeConfig {
                 time_format: TimeFormat::Micros,
                 date_format: DateFormat::DaysSinceEpoch,
                 timestamp_format: TimestampFormat::String("%Y-%m-%dT%H:%M:%S%Z"),
+                float_precision: None,
+                json_float_numbers: false,
+                rename_columns: BTreeMap::new(),
             },
             JsonFlavor::Snowflake => Self {
                 time_format: TimeFormat::String("%H:%M:%S%.f"),
                 date_format: DateFormat::String("%Y-%m-%d"),
                 timestamp_format: TimestampFormat::String("%Y-%m-%dT%H:%M:%S%.f%:z"),
+                float_precision: None,
+                json_float_numbers: false,
+                rename_columns: BTreeMap::new(),
             },
         }
     }
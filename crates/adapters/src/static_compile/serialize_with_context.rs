@@ -32,9 +32,12 @@ use dbsp::{
 };
 use rust_decimal::Decimal;
 use serde::{
-    ser::{SerializeSeq, SerializeTuple},
+    ser::{Error as _, SerializeSeq, SerializeTuple},
     Serialize, Serializer,
 };
+use serde_json::value::RawValue;
+
+use crate::{SqlSerdeConfig, DECIMAL_NEWTYPE};
 
 /// Similar to [`Serialize`], but takes an extra `context` argument and
 /// threads it through all nested structures.
@@ -85,9 +88,47 @@ serialize_without_context!(usize);
 serialize_without_context!(isize);
 serialize_without_context!(String);
 serialize_without_context!(char);
-serialize_without_context!(F32);
-serialize_without_context!(F64);
-serialize_without_context!(Decimal);
+
+impl<C> SerializeWithContext<C> for Decimal {
+    fn serialize_with_context<S>(&self, serializer: S, _context: &C) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(DECIMAL_NEWTYPE, self)
+    }
+}
+
+macro_rules! serialize_float {
+    ($typ:ty) => {
+        impl SerializeWithContext<SqlSerdeConfig> for $typ {
+            fn serialize_with_context<S>(
+                &self,
+                serializer: S,
+                context: &SqlSerdeConfig,
+            ) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                let value = self.into_inner();
+
+                match context.float_precision {
+                    Some(precision) if value.is_finite() && context.json_float_numbers => {
+                        RawValue::from_string(format!("{value:.precision$}"))
+                            .map_err(S::Error::custom)?
+                            .serialize(serializer)
+                    }
+                    Some(precision) if value.is_finite() => {
+                        serializer.collect_str(&format_args!("{value:.precision$}"))
+                    }
+                    _ => self.serialize(serializer),
+                }
+            }
+        }
+    };
+}
+
+serialize_float!(F32);
+serialize_float!(F64);
 
 /// Used to pass the context to nested structures during serialization.
 // This is only public because it's used in a macro; it's not suppposed
@@ -291,6 +332,7 @@ macro_rules! serialize_struct {
         #[allow(unused_mut)]
         impl<C, $($arg),*> $crate::SerializeWithContext<C> for $struct<$($arg),*>
         where
+            $($type: $crate::SerializeWithContext<C>,)*
             $($arg: $crate::SerializeWithContext<C>),*
             $($($arg : $bound)?),*
         {
@@ -320,7 +362,7 @@ macro_rules! serialize_table_record {
             {
                 let mut struct_serializer = serializer.serialize_struct(stringify!($struct), $num_fields)?;
                 $(
-                    serde::ser::SerializeStruct::serialize_field(&mut struct_serializer, $column_name, &$crate::SerializationContext::new(context, &self.$field_name))?;
+                    serde::ser::SerializeStruct::serialize_field(&mut struct_serializer, context.column_name($column_name), &$crate::SerializationContext::new(context, &self.$field_name))?;
                 )*
                 serde::ser::SerializeStruct::end(struct_serializer)
             }
@@ -1,13 +1,17 @@
+use super::field_collector::{Field, FieldCollector, Permuted};
 use crate::{
     catalog::{RecordFormat, SerBatch, SerCollectionHandle, SerCursor},
-    ControllerError, SerializationContext, SerializeWithContext, SqlSerdeConfig,
+    ControllerError, DateFormat, SerializationContext, SerializeWithContext, SqlSerdeConfig,
+    TimeFormat, TimestampFormat,
 };
-use anyhow::Result as AnyResult;
-use csv::{Writer as CsvWriter, WriterBuilder as CsvWriterBuilder};
+use anyhow::{bail, Result as AnyResult};
+use csv::{Terminator as CsvTerminator, Writer as CsvWriter, WriterBuilder as CsvWriterBuilder};
 use dbsp::{
     trace::{Batch, BatchReader, Cursor},
     OutputHandle,
 };
+use pipeline_types::format::csv::CsvLineTerminator;
+use serde::Serialize;
 use std::{cell::RefCell, io, io::Write, marker::PhantomData, ops::DerefMut, sync::Arc};
 
 /// Implementation of the [`std::io::Write`] trait that allows swapping out
@@ -56,16 +60,127 @@ where
 /// A serializer that encodes values to a byte array.
 trait BytesSerializer<C>: Send {
     fn create(context: C) -> Self;
+    /// Called with a record before it or its header is serialized, so that
+    /// the serializer can inspect the columns of the record.
+    fn prepare<T: SerializeWithContext<C>>(&mut self, _val: &T) -> AnyResult<()> {
+        Ok(())
+    }
     fn serialize<T: SerializeWithContext<C>>(
         &mut self,
         val: &T,
         buf: &mut Vec<u8>,
     ) -> AnyResult<()>;
+    fn serialize_header<T: SerializeWithContext<C>>(
+        &mut self,
+        val: &T,
+        buf: &mut Vec<u8>,
+    ) -> AnyResult<()>;
+    /// Append the columns of `val` and their names to `fields` and `names`.
+    fn serialize_fields<T: SerializeWithContext<C>>(
+        &mut self,
+        _val: &T,
+        _fields: &mut Vec<Field>,
+        _names: &mut Vec<Option<&'static str>>,
+    ) -> AnyResult<()> {
+        bail!("serializing individual columns is not supported by this record format")
+    }
 }
 
 struct CsvSerializer<C> {
     writer: CsvWriter<SwappableWrite<Vec<u8>>>,
     context: C,
+    line_terminator: CsvLineTerminator,
+    column_order: Option<ColumnOrder>,
+}
+
+/// Reorders the columns of serialized records.
+struct ColumnOrder {
+    /// Names of the columns to write first.
+    names: Vec<String>,
+    /// Column names of the record, computed by `CsvSerializer::prepare`.
+    header: Option<Vec<&'static str>>,
+    /// Indexes of the columns of the record in output order.
+    permutation: Vec<usize>,
+    /// Fields of the current record and the names of the columns they
+    /// belong to, reused across records.
+    fields: Vec<Field>,
+    field_names: Vec<Option<&'static str>>,
+}
+
+impl ColumnOrder {
+    /// Buffer the fields of `val` in `self.fields`.
+    fn collect<C, T>(&mut self, context: &C, val: &T) -> AnyResult<()>
+    where
+        T: SerializeWithContext<C>,
+    {
+        self.fields.clear();
+        self.field_names.clear();
+        SerializationContext::new(context, val).serialize(&mut FieldCollector::new(
+            &mut self.fields,
+            &mut self.field_names,
+        ))?;
+        Ok(())
+    }
+}
+
+impl<C> CsvSerializer<C> {
+    fn with_line_terminator(context: C, line_terminator: CsvLineTerminator) -> Self {
+        Self {
+            writer: Self::builder(&line_terminator)
+                .has_headers(false)
+                .from_writer(SwappableWrite::new()),
+            context,
+            line_terminator,
+            column_order: None,
+        }
+    }
+
+    /// Write the columns listed in `column_order` first, followed by the
+    /// remaining columns in their declared order.
+    fn with_column_order(mut self, column_order: Option<Vec<String>>) -> Self {
+        self.column_order = column_order.map(|names| ColumnOrder {
+            names,
+            header: None,
+            permutation: Vec::new(),
+            fields: Vec::new(),
+            field_names: Vec::new(),
+        });
+        self
+    }
+
+    /// Returns a writer builder configured to terminate records with
+    /// `line_terminator`.
+    ///
+    /// The CSV writer only supports CRLF and single-byte terminators; the
+    /// encoder rejects other terminators.
+    fn builder(line_terminator: &CsvLineTerminator) -> CsvWriterBuilder {
+        let terminator = match line_terminator.as_bytes() {
+            [byte] => CsvTerminator::Any(*byte),
+            _ => CsvTerminator::CRLF,
+        };
+
+        let mut builder = CsvWriterBuilder::new();
+        builder.flexible(true).terminator(terminator);
+        builder
+    }
+
+    /// Append the output of `write` to `buf`.
+    fn write_to<F>(
+        writer: &mut CsvWriter<SwappableWrite<Vec<u8>>>,
+        buf: &mut Vec<u8>,
+        write: F,
+    ) -> AnyResult<()>
+    where
+        F: FnOnce(&mut CsvWriter<SwappableWrite<Vec<u8>>>) -> csv::Result<()>,
+    {
+        let owned_buf = std::mem::take(buf);
+        writer.get_ref().swap(Some(owned_buf));
+        let res = write(writer);
+        let _ = writer.flush();
+        *buf = writer.get_ref().swap(None).unwrap();
+        res?;
+        Ok(())
+    }
 }
 
 impl<C> BytesSerializer<C> for CsvSerializer<C>
@@ -73,26 +188,101 @@ where
     C: Send,
 {
     fn create(context: C) -> Self {
-        Self {
-            writer: CsvWriterBuilder::new()
-                .has_headers(false)
-                .flexible(true)
-                .from_writer(SwappableWrite::new()),
-            context,
+        Self::with_line_terminator(context, CsvLineTerminator::default())
+    }
+
+    fn prepare<T>(&mut self, val: &T) -> AnyResult<()>
+    where
+        T: SerializeWithContext<C>,
+    {
+        let Some(column_order) = &mut self.column_order else {
+            return Ok(());
+        };
+        if column_order.header.is_some() {
+            return Ok(());
         }
+
+        // Column names are the names of the struct fields that the record's
+        // fields belong to.
+        column_order.collect(&self.context, val)?;
+        let Some(header) = column_order
+            .field_names
+            .iter()
+            .copied()
+            .collect::<Option<Vec<_>>>()
+        else {
+            bail!("'column_order' requires records that serialize as structs with named columns");
+        };
+
+        let mut permutation = Vec::with_capacity(header.len());
+        for name in column_order.names.iter() {
+            match header.iter().position(|column| column == name) {
+                Some(index) => permutation.push(index),
+                None => bail!("unknown column '{name}' in 'column_order'"),
+            }
+        }
+        for index in 0..header.len() {
+            if !permutation.contains(&index) {
+                permutation.push(index);
+            }
+        }
+
+        column_order.permutation = permutation;
+        column_order.header = Some(header);
+        Ok(())
     }
 
     fn serialize<T>(&mut self, val: &T, buf: &mut Vec<u8>) -> AnyResult<()>
     where
         T: SerializeWithContext<C>,
     {
-        let owned_buf = std::mem::take(buf);
-        self.writer.get_ref().swap(Some(owned_buf));
+        if let Some(column_order) = &mut self.column_order {
+            if column_order.header.is_some() {
+                column_order.collect(&self.context, val)?;
+                let record = Permuted {
+                    fields: &column_order.fields,
+                    permutation: &column_order.permutation,
+                };
+                return Self::write_to(&mut self.writer, buf, |writer| writer.serialize(record));
+            }
+        }
+
         let val_with_context = SerializationContext::new(&self.context, val);
-        let res = self.writer.serialize(val_with_context);
-        let _ = self.writer.flush();
-        *buf = self.writer.get_ref().swap(None).unwrap();
-        Ok(res?)
+        Self::write_to(&mut self.writer, buf, |writer| {
+            writer.serialize(val_with_context)
+        })
+    }
+
+    fn serialize_header<T>(&mut self, val: &T, buf: &mut Vec<u8>) -> AnyResult<()>
+    where
+        T: SerializeWithContext<C>,
+    {
+        self.prepare(val)?;
+        if let Some(ColumnOrder {
+            header: Some(header),
+            permutation,
+            ..
+        }) = &self.column_order
+        {
+            return Self::write_to(&mut self.writer, buf, |writer| {
+                writer.write_record(permutation.iter().map(|index| header[*index]))
+            });
+        }
+
+        // The CSV writer derives the header from the field names of the first
+        // record it serializes.  Serialize `val` with headers enabled, then
+        // strip the record itself.
+        let mut writer = Self::builder(&self.line_terminator)
+            .has_headers(true)
+            .from_writer(Vec::new());
+        writer.serialize(SerializationContext::new(&self.context, val))?;
+        let header_and_record = writer.into_inner().map_err(|e| e.into_error())?;
+
+        let mut record = Vec::new();
+        self.serialize(val, &mut record)?;
+
+        buf.extend_from_slice(&header_and_record[..header_and_record.len() - record.len()]);
+        Ok(())
     }
 }
 
@@ -114,6 +304,26 @@ where
         val.serialize_with_context(&mut serde_json::Serializer::new(buf), &self.context)?;
         Ok(())
     }
+
+    fn serialize_header<T>(&mut self, _val: &T, _buf: &mut Vec<u8>) -> AnyResult<()>
+    where
+        T: SerializeWithContext<C>,
+    {
+        bail!("JSON records don't have a header")
+    }
+
+    fn serialize_fields<T>(
+        &mut self,
+        val: &T,
+        fields: &mut Vec<Field>,
+        names: &mut Vec<Option<&'static str>>,
+    ) -> AnyResult<()>
+    where
+        T: SerializeWithContext<C>,
+    {
+        val.serialize_with_context(&mut FieldCollector::new(fields, names), &self.context)?;
+        Ok(())
+    }
 }
 
 pub struct SerCollectionHandleImpl<B, KD, VD> {
@@ -212,14 +422,45 @@ where
         record_format: RecordFormat,
     ) -> Result<Box<dyn SerCursor + 'a>, ControllerError> {
         Ok(match record_format {
-            RecordFormat::Csv => {
-                Box::new(<SerCursorImpl<'a, CsvSerializer<_>, B, KD, VD, _>>::new(
-                    &self.batch,
-                    SqlSerdeConfig::default(),
-                ))
+            RecordFormat::Csv(options) => {
+                let config = SqlSerdeConfig {
+                    float_precision: options.float_precision,
+                    ..Default::default()
+                }
+                .with_rename_columns(&options.rename_columns);
+                Box::new(
+                    <SerCursorImpl<'a, CsvSerializer<_>, B, KD, VD, _>>::with_serializer(
+                        &self.batch,
+                        CsvSerializer::with_line_terminator(config, options.line_terminator)
+                            .with_column_order(options.column_order),
+                    ),
+                )
             }
-            RecordFormat::Json(json_flavor) => {
-                let config = SqlSerdeConfig::from(json_flavor);
+            RecordFormat::Json(options) => {
+                let config = SqlSerdeConfig {
+                    float_precision: options.float_precision,
+                    json_float_numbers: true,
+                    ..SqlSerdeConfig::from(options.flavor)
+                }
+                .with_rename_columns(&options.rename_columns);
+                Box::new(<SerCursorImpl<
+                    'a,
+                    JsonSerializer<_>,
+                    B,
+                    KD,
+                    VD,
+                    SqlSerdeConfig,
+                >>::new(&self.batch, config))
+            }
+            RecordFormat::Parquet => {
+                // Numeric representations of dates and times are tagged with
+                // their SQL types, so they can be stored in typed columns.
+                let config = SqlSerdeConfig {
+                    time_format: TimeFormat::Micros,
+                    date_format: DateFormat::DaysSinceEpoch,
+                    timestamp_format: TimestampFormat::MillisSinceEpoch,
+                    ..Default::default()
+                };
                 Box::new(<SerCursorImpl<
                     'a,
                     JsonSerializer<_>,
@@ -254,11 +495,15 @@ where
     VD: From<B::Val> + SerializeWithContext<C>,
 {
     pub fn new(batch: &'a B, config: C) -> Self {
+        Self::with_serializer(batch, Ser::create(config))
+    }
+
+    pub fn with_serializer(batch: &'a B, serializer: Ser) -> Self {
         let cursor = batch.cursor();
 
         let mut result = Self {
             cursor,
-            serializer: Ser::create(config),
+            serializer,
             key: None,
             val: None,
             phantom: PhantomData,
@@ -302,15 +547,31 @@ where
     }
 
     fn serialize_key(&mut self, dst: &mut Vec<u8>) -> AnyResult<()> {
+        self.serializer.prepare(self.key.as_ref().unwrap())?;
         self.serializer.serialize(self.key.as_ref().unwrap(), dst)
     }
 
     fn serialize_key_weight(&mut self, dst: &mut Vec<u8>) -> AnyResult<()> {
         let w = self.weight();
+        self.serializer.prepare(self.key.as_ref().unwrap())?;
         self.serializer
             .serialize(&(self.key.as_ref().unwrap(), w), dst)
     }
 
+    fn serialize_key_header(&mut self, dst: &mut Vec<u8>) -> AnyResult<()> {
+        self.serializer
+            .serialize_header(self.key.as_ref().unwrap(), dst)
+    }
+
+    fn serialize_key_fields(
+        &mut self,
+        fields: &mut Vec<Field>,
+        names: &mut Vec<Option<&'static str>>,
+    ) -> AnyResult<()> {
+        self.serializer
+            .serialize_fields(self.key.as_ref().unwrap(), fields, names)
+    }
+
     fn serialize_val(&mut self, dst: &mut Vec<u8>) -> AnyResult<()> {
         self.serializer.serialize(self.val.as_ref().unwrap(), dst)
     }
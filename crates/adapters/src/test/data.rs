@@ -2,7 +2,7 @@ use proptest::{collection, prelude::*};
 use proptest_derive::Arbitrary;
 use size_of::SizeOf;
 
-use crate::{deserialize_without_context, serialize_struct};
+use crate::{deserialize_without_context, serialize_table_record};
 
 #[derive(
     Debug,
@@ -30,7 +30,7 @@ pub struct TestStruct {
 
 deserialize_without_context!(TestStruct);
 
-serialize_struct!(TestStruct()[4]{
+serialize_table_record!(TestStruct[4]{
     id["id"]: u32,
     b["b"]: bool,
     i["i"]: Option<i64>,
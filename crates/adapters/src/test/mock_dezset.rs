@@ -1,5 +1,5 @@
 use crate::{
-    catalog::{DeCollectionStream, RecordFormat},
+    catalog::{DeCollectionStream, JsonOptions, RecordFormat},
     static_compile::deinput::{
         CsvDeserializerFromBytes, DeserializerFromBytes, JsonDeserializerFromBytes,
     },
@@ -79,19 +79,23 @@ where
         record_format: RecordFormat,
     ) -> Result<Box<dyn DeCollectionStream>, ControllerError> {
         match record_format {
-            RecordFormat::Csv => Ok(Box::new(
+            RecordFormat::Csv(_) => Ok(Box::new(
                 MockDeZSetStream::<CsvDeserializerFromBytes<_>, T>::new(
                     self.clone(),
                     SqlSerdeConfig::default(),
                 ),
             )),
-            RecordFormat::Json(flavor) => Ok(Box::new(MockDeZSetStream::<
+            RecordFormat::Json(JsonOptions { flavor, .. }) => Ok(Box::new(MockDeZSetStream::<
                 JsonDeserializerFromBytes<SqlSerdeConfig>,
                 T,
             >::new(
                 self.clone(),
                 SqlSerdeConfig::from(flavor),
             ))),
+            RecordFormat::Parquet => Err(ControllerError::input_format_not_supported(
+                "mock endpoint",
+                "Parquet input format is not supported",
+            )),
         }
     }
 }
@@ -21,7 +21,7 @@ pub struct MockInputConsumerState {
     pub parser_result: Option<(usize, Vec<ParseError>)>,
 
     /// Parser to push data to.
-    parser: Box<dyn Parser>,
+    pub parser: Box<dyn Parser>,
 
     /// Callback to invoke on transport or parser error.
     ///
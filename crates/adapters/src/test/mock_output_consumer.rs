@@ -1,5 +1,8 @@
-use crate::OutputConsumer;
-use std::sync::{Arc, Mutex};
+use crate::{OutputConsumer, PushStatus};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
 
 pub struct MockOutputConsumer {
     pub data: Arc<Mutex<Vec<u8>>>,
@@ -36,3 +39,52 @@ impl OutputConsumer for MockOutputConsumer {
     }
     fn batch_end(&mut self) {}
 }
+
+/// Consumer that refuses all non-blocking pushes while `blocked` is set.
+pub struct BlockingOutputConsumer {
+    pub data: Arc<Mutex<Vec<u8>>>,
+    pub blocked: Arc<AtomicBool>,
+}
+
+impl Default for BlockingOutputConsumer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BlockingOutputConsumer {
+    /// Create a consumer that is initially blocked.
+    pub fn new() -> Self {
+        Self {
+            data: Arc::new(Mutex::new(Vec::new())),
+            blocked: Arc::new(AtomicBool::new(true)),
+        }
+    }
+}
+
+impl OutputConsumer for BlockingOutputConsumer {
+    fn max_buffer_size_bytes(&self) -> usize {
+        usize::MAX
+    }
+
+    fn batch_start(&mut self) {}
+
+    fn push_buffer(&mut self, buffer: &[u8]) {
+        self.data.lock().unwrap().extend_from_slice(buffer)
+    }
+
+    fn try_push_buffer(&mut self, buffer: &[u8]) -> PushStatus {
+        if self.blocked.load(Ordering::Acquire) {
+            PushStatus::WouldBlock
+        } else {
+            self.push_buffer(buffer);
+            PushStatus::Pushed
+        }
+    }
+
+    fn would_block(&self) -> bool {
+        self.blocked.load(Ordering::Acquire)
+    }
+
+    fn batch_end(&mut self) {}
+}
@@ -28,7 +28,7 @@ pub use data::{
 };
 pub use mock_dezset::MockDeZSet;
 pub use mock_input_consumer::MockInputConsumer;
-pub use mock_output_consumer::MockOutputConsumer;
+pub use mock_output_consumer::{BlockingOutputConsumer, MockOutputConsumer};
 
 pub struct TestLogger;
 pub static TEST_LOGGER: TestLogger = TestLogger;
@@ -1,18 +1,43 @@
 use std::borrow::Cow;
 
-/// Returns the index of the first character following the last newline
-/// in `data`.
-pub(crate) fn split_on_newline(data: &[u8]) -> usize {
+/// Returns the index of the first character following the last occurrence
+/// of `byte` in `data`, e.g., the last newline.
+pub(crate) fn split_on_byte(data: &[u8], byte: u8) -> usize {
     let data_len = data.len();
     let index = data
         .iter()
         .rev()
-        .position(|&x| x == b'\n')
+        .position(|&x| x == byte)
         .unwrap_or(data_len);
 
     data_len - index
 }
 
+/// Like [`split_on_byte`], but ignores occurrences of `byte` inside
+/// double-quoted CSV fields.
+///
+/// `in_quotes` specifies whether `data` starts inside a quoted field, i.e.,
+/// whether the data preceding it contains an unterminated quoted field.
+/// Returns the index of the first character following the last `byte`
+/// outside of quotes, or `0` if there is no such character, along with the
+/// quote state at the end of `data`.
+///
+/// Escaped quotes (`""`) toggle the quote state twice, so they don't need
+/// special handling.
+pub(crate) fn split_on_unquoted_byte(data: &[u8], byte: u8, mut in_quotes: bool) -> (usize, bool) {
+    let mut split = 0;
+
+    for (i, &x) in data.iter().enumerate() {
+        if x == b'"' {
+            in_quotes = !in_quotes;
+        } else if x == byte && !in_quotes {
+            split = i + 1;
+        }
+    }
+
+    (split, in_quotes)
+}
+
 pub(crate) fn truncate_ellipse<'a>(s: &'a str, len: usize, ellipse: &str) -> Cow<'a, str> {
     if s.len() <= len {
         return Cow::Borrowed(s);
@@ -1,10 +1,47 @@
-use crate::{codegen::utils::str_from_raw_parts, utils::TimeExt, ThinStr};
+use crate::{
+    codegen::{json::JsonIntStatus, utils::str_from_raw_parts},
+    utils::{NativeRepr, TimeExt},
+    ThinStr,
+};
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use pipeline_types::format::json::JsonOverflowPolicy;
+use rust_decimal::{Decimal, RoundingStrategy};
 use serde_json::Value;
-use std::mem::MaybeUninit;
+use std::{borrow::Cow, fmt::Write, mem::MaybeUninit};
 
 // TODO: We can precompile the json pointers into something faster
 
+/// Checks `map` for keys that don't occur in `known_keys`, a sequence of
+/// json pointer tokens such as `/foo/bar`
+///
+/// Returns `true` and writes the first unknown key to `error` if one is found
+pub(super) extern "C" fn deserialize_json_reject_unknown(
+    known_keys_ptr: *const u8,
+    known_keys_len: usize,
+    map: &Value,
+    error: &mut String,
+) -> bool {
+    let known_keys = unsafe { str_from_raw_parts(known_keys_ptr, known_keys_len) };
+
+    if let Some(object) = map.as_object() {
+        for key in object.keys() {
+            // Json pointer tokens escape `~` and `/`
+            let token = if key.contains(['~', '/']) {
+                Cow::Owned(key.replace('~', "~0").replace('/', "~1"))
+            } else {
+                Cow::Borrowed(key.as_str())
+            };
+
+            if !known_keys.split('/').skip(1).any(|known| known == token) {
+                write!(error, "{key:?} (unknown key)").unwrap();
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
 pub(super) extern "C" fn deserialize_json_string(
     place: &mut MaybeUninit<ThinStr>,
     json_pointer_ptr: *const u8,
@@ -43,42 +80,64 @@ pub(super) extern "C" fn deserialize_json_bool(
     }
 }
 
-pub(super) extern "C" fn deserialize_json_i64(
-    place: &mut MaybeUninit<i64>,
-    json_pointer_ptr: *const u8,
-    json_pointer_len: usize,
-    map: &Value,
-) -> bool {
-    // The json pointer we're accessing the map with
-    let json_pointer = unsafe { str_from_raw_parts(json_pointer_ptr, json_pointer_len) };
-
-    if let Some(int) = map.pointer(json_pointer).and_then(Value::as_i64) {
-        place.write(int);
-        false
-
-    // Otherwise the value couldn't be found and is considered null
-    } else {
-        true
-    }
+macro_rules! deserialize_json_int {
+    ($($ty:ident),+ $(,)?) => {
+        paste::paste! {
+            $(
+                pub(super) extern "C" fn [<deserialize_json_ $ty>](
+                    place: &mut MaybeUninit<$ty>,
+                    json_pointer_ptr: *const u8,
+                    json_pointer_len: usize,
+                    on_overflow: JsonOverflowPolicy,
+                    map: &Value,
+                ) -> JsonIntStatus {
+                    // The json pointer we're accessing the map with
+                    let json_pointer =
+                        unsafe { str_from_raw_parts(json_pointer_ptr, json_pointer_len) };
+
+                    if let Some(int) = map.pointer(json_pointer).and_then(json_to_i128) {
+                        let value = match $ty::try_from(int) {
+                            Ok(value) => value,
+                            Err(_) => match on_overflow {
+                                JsonOverflowPolicy::Error => {
+                                    tracing::error!(
+                                        "{int} doesn't fit into {}",
+                                        stringify!($ty),
+                                    );
+                                    return JsonIntStatus::Overflow;
+                                }
+                                JsonOverflowPolicy::Saturate => {
+                                    if int < 0 { $ty::MIN } else { $ty::MAX }
+                                }
+                                JsonOverflowPolicy::Wrap => int as $ty,
+                            },
+                        };
+
+                        place.write(value);
+                        return JsonIntStatus::Ok;
+                    }
+
+                    // Otherwise the value couldn't be found and is considered null
+                    JsonIntStatus::Null
+                }
+            )+
+        }
+    };
 }
 
-pub(super) extern "C" fn deserialize_json_i32(
-    place: &mut MaybeUninit<i32>,
-    json_pointer_ptr: *const u8,
-    json_pointer_len: usize,
-    map: &Value,
-) -> bool {
-    // The json pointer we're accessing the map with
-    let json_pointer = unsafe { str_from_raw_parts(json_pointer_ptr, json_pointer_len) };
-
-    if let Some(int) = map.pointer(json_pointer).and_then(Value::as_i64) {
-        place.write(int as i32);
-        false
+deserialize_json_int! {
+    u8, i8,
+    u16, i16,
+    u32, i32,
+    u64, i64,
+}
 
-    // Otherwise the value couldn't be found and is considered null
-    } else {
-        true
-    }
+/// Returns the value of a json integer, or `None` if `value` isn't an integer
+fn json_to_i128(value: &Value) -> Option<i128> {
+    value
+        .as_i64()
+        .map(i128::from)
+        .or_else(|| value.as_u64().map(i128::from))
 }
 
 pub(super) extern "C" fn deserialize_json_f64(
@@ -160,6 +219,113 @@ pub(super) extern "C" fn deserialize_json_f32(
     true
 }
 
+/// Deserializes the discriminator of a tagged union into the index of its
+/// variant within `variants`, a sequence of json pointer tokens such as `/a/b`
+pub(super) extern "C" fn deserialize_json_enum_tag(
+    place: &mut MaybeUninit<i32>,
+    json_pointer_ptr: *const u8,
+    json_pointer_len: usize,
+    variants_ptr: *const u8,
+    variants_len: usize,
+    map: &Value,
+) -> bool {
+    // The json pointer we're accessing the map with
+    let json_pointer = unsafe { str_from_raw_parts(json_pointer_ptr, json_pointer_len) };
+    let variants = unsafe { str_from_raw_parts(variants_ptr, variants_len) };
+
+    if let Some(tag) = map.pointer(json_pointer).and_then(Value::as_str) {
+        // Variant names are escaped like json pointer tokens
+        let token = if tag.contains(['~', '/']) {
+            Cow::Owned(tag.replace('~', "~0").replace('/', "~1"))
+        } else {
+            Cow::Borrowed(tag)
+        };
+
+        if let Some(index) = variants
+            .split('/')
+            .skip(1)
+            .position(|variant| variant == token)
+        {
+            place.write(index as i32);
+            return false;
+        }
+
+        tracing::error!("unknown variant {tag:?} for the tagged union at {json_pointer}");
+    }
+
+    // Otherwise the value couldn't be found or parsed and is considered null
+    true
+}
+
+/// Parses a decimal from a json string or number
+fn json_to_decimal(value: &Value) -> Option<Decimal> {
+    let parse = |string: &str| {
+        string
+            .parse::<Decimal>()
+            .or_else(|_| Decimal::from_scientific(string))
+            .ok()
+    };
+
+    match value {
+        Value::String(string) => parse(string),
+        Value::Number(number) => parse(&number.to_string()),
+        _ => None,
+    }
+}
+
+pub(super) extern "C" fn deserialize_json_decimal(
+    place: &mut MaybeUninit<u128>,
+    json_pointer_ptr: *const u8,
+    json_pointer_len: usize,
+    map: &Value,
+) -> bool {
+    // The json pointer we're accessing the map with
+    let json_pointer = unsafe { str_from_raw_parts(json_pointer_ptr, json_pointer_len) };
+
+    if let Some(decimal) = map.pointer(json_pointer).and_then(json_to_decimal) {
+        place.write(decimal.to_repr());
+        false
+
+    // Otherwise the value couldn't be found and is considered null
+    } else {
+        true
+    }
+}
+
+/// Deserializes a decimal rounded to `scale` fractional digits, rejecting
+/// values with more than `precision` digits in total
+pub(super) extern "C" fn deserialize_json_decimal_with_scale(
+    place: &mut MaybeUninit<u128>,
+    json_pointer_ptr: *const u8,
+    json_pointer_len: usize,
+    precision: u32,
+    scale: u32,
+    map: &Value,
+) -> bool {
+    // The json pointer we're accessing the map with
+    let json_pointer = unsafe { str_from_raw_parts(json_pointer_ptr, json_pointer_len) };
+
+    if let Some(mut decimal) = map.pointer(json_pointer).and_then(json_to_decimal) {
+        decimal = decimal.round_dp_with_strategy(scale, RoundingStrategy::MidpointAwayFromZero);
+        // Pad the decimal with trailing zeroes so that it's always serialized
+        // with `scale` fractional digits
+        decimal.rescale(scale);
+
+        let fits = 10u128
+            .checked_pow(precision)
+            .map_or(true, |max| decimal.mantissa().unsigned_abs() < max);
+        if fits {
+            place.write(decimal.to_repr());
+            return false;
+        }
+
+        tracing::error!("decimal {decimal} doesn't fit into DECIMAL({precision}, {scale})");
+    }
+
+    // Otherwise the value couldn't be found or parsed and is considered null
+    true
+}
+
 pub(super) extern "C" fn deserialize_json_date(
     place: &mut MaybeUninit<i32>,
     json_pointer_ptr: *const u8,
@@ -250,6 +416,35 @@ pub(super) extern "C" fn deserialize_json_timestamp(
     }
 }
 
+pub(super) extern "C" fn deserialize_json_timestamp_from_seconds(
+    place: &mut MaybeUninit<i64>,
+    json_pointer_ptr: *const u8,
+    json_pointer_len: usize,
+    map: &Value,
+) -> bool {
+    // The json pointer we're accessing the map with
+    let json_pointer = unsafe { str_from_raw_parts(json_pointer_ptr, json_pointer_len) };
+
+    if let Some(millis) = map
+        .pointer(json_pointer)
+        .and_then(Value::as_i64)
+        .and_then(|seconds| {
+            let millis = seconds.checked_mul(1000);
+            if millis.is_none() {
+                tracing::error!("timestamp of {seconds} seconds is out of range");
+            }
+            millis
+        })
+    {
+        place.write(millis);
+        false
+
+    // Otherwise the value couldn't be found and is considered null
+    } else {
+        true
+    }
+}
+
 pub(super) extern "C" fn deserialize_json_timestamp_from_millis(
     place: &mut MaybeUninit<i64>,
     json_pointer_ptr: *const u8,
@@ -288,6 +483,25 @@ pub(super) extern "C" fn deserialize_json_timestamp_from_micros(
     }
 }
 
+pub(super) extern "C" fn deserialize_json_timestamp_from_nanos(
+    place: &mut MaybeUninit<i64>,
+    json_pointer_ptr: *const u8,
+    json_pointer_len: usize,
+    map: &Value,
+) -> bool {
+    // The json pointer we're accessing the map with
+    let json_pointer = unsafe { str_from_raw_parts(json_pointer_ptr, json_pointer_len) };
+
+    if let Some(nanos) = map.pointer(json_pointer).and_then(Value::as_i64) {
+        place.write(nanos / 1_000_000);
+        false
+
+    // Otherwise the value couldn't be found and is considered null
+    } else {
+        true
+    }
+}
+
 pub(super) extern "C" fn deserialize_json_time(
     place: &mut MaybeUninit<u64>,
     json_pointer_ptr: *const u8,
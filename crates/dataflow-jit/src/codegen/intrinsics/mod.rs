@@ -5,17 +5,23 @@ mod time;
 use self::{
     deserialize::{
         deserialize_json_bool, deserialize_json_date, deserialize_json_date_from_days,
-        deserialize_json_f32, deserialize_json_f64, deserialize_json_i32, deserialize_json_i64,
+        deserialize_json_decimal, deserialize_json_decimal_with_scale, deserialize_json_enum_tag,
+        deserialize_json_f32, deserialize_json_f64, deserialize_json_i16, deserialize_json_i32,
+        deserialize_json_i64, deserialize_json_i8, deserialize_json_reject_unknown,
         deserialize_json_string, deserialize_json_time, deserialize_json_time_from_micros,
         deserialize_json_time_from_millis, deserialize_json_timestamp,
         deserialize_json_timestamp_from_micros, deserialize_json_timestamp_from_millis,
+        deserialize_json_timestamp_from_nanos, deserialize_json_timestamp_from_seconds,
+        deserialize_json_u16, deserialize_json_u32, deserialize_json_u64, deserialize_json_u8,
     },
     serialize::{
         byte_vec_push, byte_vec_reserve, write_date_to_byte_vec, write_decimal_to_byte_vec,
-        write_escaped_string_to_byte_vec, write_f32_to_byte_vec, write_f64_to_byte_vec,
-        write_i16_to_byte_vec, write_i32_to_byte_vec, write_i64_to_byte_vec, write_i8_to_byte_vec,
-        write_time_to_byte_vec, write_timestamp_to_byte_vec, write_u16_to_byte_vec,
-        write_u32_to_byte_vec, write_u64_to_byte_vec, write_u8_to_byte_vec,
+        write_escaped_string_to_byte_vec, write_f32_to_byte_vec,
+        write_f32_to_byte_vec_with_precision, write_f64_to_byte_vec,
+        write_f64_to_byte_vec_with_precision, write_i16_to_byte_vec, write_i32_to_byte_vec,
+        write_i64_to_byte_vec, write_i8_to_byte_vec, write_time_to_byte_vec,
+        write_timestamp_to_byte_vec, write_u16_to_byte_vec, write_u32_to_byte_vec,
+        write_u64_to_byte_vec, write_u8_to_byte_vec,
     },
     time::{time_hour, time_microsecond, time_millisecond, time_minute, time_second},
 };
@@ -633,18 +639,30 @@ intrinsics! {
     // Json
     deserialize_json_bool = fn(ptr, ptr, usize, ptr) -> bool,
     deserialize_json_string = fn(ptr, ptr, usize, ptr) -> bool,
-    deserialize_json_i32 = fn(ptr, ptr, usize, ptr) -> bool,
-    deserialize_json_i64 = fn(ptr, ptr, usize, ptr) -> bool,
+    deserialize_json_u8 = fn(ptr, ptr, usize, u8, ptr) -> u8,
+    deserialize_json_i8 = fn(ptr, ptr, usize, u8, ptr) -> u8,
+    deserialize_json_u16 = fn(ptr, ptr, usize, u8, ptr) -> u8,
+    deserialize_json_i16 = fn(ptr, ptr, usize, u8, ptr) -> u8,
+    deserialize_json_u32 = fn(ptr, ptr, usize, u8, ptr) -> u8,
+    deserialize_json_i32 = fn(ptr, ptr, usize, u8, ptr) -> u8,
+    deserialize_json_u64 = fn(ptr, ptr, usize, u8, ptr) -> u8,
+    deserialize_json_i64 = fn(ptr, ptr, usize, u8, ptr) -> u8,
     deserialize_json_f32 = fn(ptr, ptr, usize, ptr) -> bool,
     deserialize_json_f64 = fn(ptr, ptr, usize, ptr) -> bool,
+    deserialize_json_decimal = fn(ptr, ptr, usize, ptr) -> bool,
+    deserialize_json_decimal_with_scale = fn(ptr, ptr, usize, u32, u32, ptr) -> bool,
+    deserialize_json_enum_tag = fn(ptr, ptr, usize, ptr, usize, ptr) -> bool,
     deserialize_json_date = fn(ptr, ptr, ptr, ptr, usize, ptr) -> bool,
     deserialize_json_timestamp = fn(ptr, ptr, ptr, ptr, usize, ptr) -> bool,
     deserialize_json_date_from_days = fn(ptr, ptr, usize, ptr) -> bool,
+    deserialize_json_timestamp_from_seconds = fn(ptr, ptr, usize, ptr) -> bool,
     deserialize_json_timestamp_from_millis = fn(ptr, ptr, usize, ptr) -> bool,
     deserialize_json_timestamp_from_micros = fn(ptr, ptr, usize, ptr) -> bool,
+    deserialize_json_timestamp_from_nanos = fn(ptr, ptr, usize, ptr) -> bool,
     deserialize_json_time = fn(ptr, ptr, ptr, ptr, usize, ptr) -> bool,
     deserialize_json_time_from_millis = fn(ptr, ptr, usize, ptr) -> bool,
     deserialize_json_time_from_micros = fn(ptr, ptr, usize, ptr) -> bool,
+    deserialize_json_reject_unknown = fn(ptr, usize, ptr, ptr) -> bool,
 
     byte_vec_push = fn(ptr, ptr, usize),
     byte_vec_reserve = fn(ptr, usize),
@@ -658,6 +676,8 @@ intrinsics! {
     write_i64_to_byte_vec = fn(ptr, i64),
     write_f32_to_byte_vec = fn(ptr, f32),
     write_f64_to_byte_vec = fn(ptr, f64),
+    write_f32_to_byte_vec_with_precision = fn(ptr, f32, usize),
+    write_f64_to_byte_vec_with_precision = fn(ptr, f64, usize),
     write_date_to_byte_vec = fn(ptr, ptr, ptr, date),
     write_timestamp_to_byte_vec = fn(ptr, ptr, ptr, timestamp),
     write_time_to_byte_vec = fn(ptr, ptr, ptr, time),
@@ -96,6 +96,18 @@ pub(super) unsafe extern "C" fn write_f64_to_byte_vec(buffer: &mut Vec<u8>, valu
     .unwrap();
 }
 
+pub(super) unsafe extern "C" fn write_f64_to_byte_vec_with_precision(
+    buffer: &mut Vec<u8>,
+    value: f64,
+    precision: usize,
+) {
+    if value.is_finite() {
+        write!(buffer, "{value:.precision$}").unwrap();
+    } else {
+        write_f64_to_byte_vec(buffer, value);
+    }
+}
+
 pub(super) unsafe extern "C" fn write_f32_to_byte_vec(buffer: &mut Vec<u8>, value: f32) {
     if value.is_finite() {
         write!(buffer, "{value}")
@@ -110,3 +122,15 @@ pub(super) unsafe extern "C" fn write_f32_to_byte_vec(buffer: &mut Vec<u8>, valu
     }
     .unwrap();
 }
+
+pub(super) unsafe extern "C" fn write_f32_to_byte_vec_with_precision(
+    buffer: &mut Vec<u8>,
+    value: f32,
+    precision: usize,
+) {
+    if value.is_finite() {
+        write!(buffer, "{value:.precision$}").unwrap();
+    } else {
+        write_f32_to_byte_vec(buffer, value);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     codegen::{
-        json::{ColumnIdx, JsonColumn, JsonColumnParseSpec},
+        json::{ColumnIdx, JsonColumn, JsonColumnParseSpec, JsonIntStatus},
         utils::{set_column_null, FunctionBuilderExt},
         Codegen, CodegenCtx,
     },
@@ -8,9 +8,10 @@ use crate::{
     utils::HashMap,
 };
 use anyhow::{anyhow, Error as AnyError, Result as AnyResult};
-use cranelift::prelude::FunctionBuilder;
+use cranelift::prelude::{FunctionBuilder, IntCC};
 use cranelift_codegen::ir::{types, Block, InstBuilder, MemFlags, Type, Value};
 use cranelift_module::{FuncId, Module};
+use pipeline_types::format::json::JsonOverflowPolicy;
 use serde::Deserialize;
 use std::mem::{self, align_of};
 
@@ -108,6 +109,35 @@ pub struct JsonDeserConfig {
     // an integer, a string or a combination of them
     // TODO: Allow specifying date & timestamp formats
     pub mappings: HashMap<ColumnIdx, JsonColumn>,
+    /// Reject input objects that contain keys not referenced by any of the
+    /// mappings.  By default such keys are ignored.
+    #[serde(default)]
+    pub reject_unknown: bool,
+    /// How integer columns handle numbers that don't fit into the column's
+    /// type.  By default such numbers are treated as invalid
+    #[serde(default)]
+    pub on_overflow: JsonOverflowPolicy,
+}
+
+impl JsonDeserConfig {
+    /// Returns the top-level keys referenced by the mappings, formatted as a
+    /// sequence of json pointer tokens, e.g. `/FOO/BAR`
+    fn known_keys(&self) -> String {
+        let mut known_keys = String::new();
+        for column in self.mappings.values() {
+            // FIXME: Hack for case insensitivity
+            let pointer = column.key().to_uppercase();
+            let token = pointer
+                .strip_prefix('/')
+                .and_then(|pointer| pointer.split('/').next())
+                .unwrap_or_default();
+
+            known_keys.push('/');
+            known_keys.push_str(token);
+        }
+
+        known_keys
+    }
 }
 
 impl Codegen {
@@ -162,6 +192,37 @@ impl Codegen {
             builder.append_block_param(return_error, ptr_ty);
             builder.append_block_param(return_error, ptr_ty);
 
+            // Check for unknown keys before deserializing any columns so that we
+            // don't have to drop any partially written values
+            if mappings.reject_unknown {
+                let (known_keys, known_keys_len) =
+                    ctx.import_string(mappings.known_keys(), &mut builder);
+
+                let reject_unknown =
+                    ctx.imports
+                        .get("deserialize_json_reject_unknown", ctx.module, builder.func);
+                let has_unknown = builder.call_fn(
+                    reject_unknown,
+                    &[known_keys, known_keys_len, json_map, error_string],
+                );
+
+                let unknown_key = builder.create_block();
+                let after = builder.create_block();
+                builder
+                    .ins()
+                    .brif(has_unknown, unknown_key, &[], after, &[]);
+
+                // The intrinsic has already written the unknown key to the error output
+                builder.switch_to_block(unknown_key);
+                builder.set_cold_block(unknown_key);
+                let err = builder
+                    .ins()
+                    .iconst(types::I8, DeserializeResult::Err as i64);
+                builder.ins().return_(&[err]);
+
+                builder.switch_to_block(after);
+            }
+
             for (column_idx, (column_ty, nullable)) in row_layout.iter().enumerate() {
                 // TODO: Json pointers include `/`s to delimit each token, so
                 // if a "pointer" has only one leading `/` then we can index
@@ -204,15 +265,143 @@ impl Codegen {
                         return_error,
                     ),
 
-                    ty @ (ColumnType::Bool
-                    | ColumnType::I64
+                    ColumnType::I32
+                        if matches!(json_column.spec(), Some(JsonColumnParseSpec::Enum { .. })) =>
+                    {
+                        let variants = json_column.spec().and_then(|spec| spec.enum_variants());
+                        let (variants_ptr, variants_len) =
+                            ctx.import_string(variants.unwrap(), &mut builder);
+
+                        let deserialize =
+                            ctx.imports
+                                .get("deserialize_json_enum_tag", ctx.module, builder.func);
+                        let value_is_null = builder.call_fn(
+                            deserialize,
+                            &[
+                                column_place,
+                                json_pointer,
+                                json_pointer_len,
+                                variants_ptr,
+                                variants_len,
+                                json_map,
+                            ],
+                        );
+
+                        // If the column is nullable, set its nullness
+                        if nullable {
+                            set_column_null(
+                                value_is_null,
+                                column_idx,
+                                place,
+                                MemFlags::trusted(),
+                                &layout,
+                                &mut builder,
+                            );
+
+                        // Otherwise return an error if deserialization fails or
+                        // the field is null
+                        } else {
+                            let after = builder.create_block();
+                            builder.ins().brif(
+                                value_is_null,
+                                return_error,
+                                &[json_pointer, json_pointer_len],
+                                after,
+                                &[],
+                            );
+
+                            builder.switch_to_block(after);
+                        }
+                    }
+
+                    ty @ (ColumnType::U8
+                    | ColumnType::I8
+                    | ColumnType::U16
+                    | ColumnType::I16
+                    | ColumnType::U32
                     | ColumnType::I32
-                    | ColumnType::F64
-                    | ColumnType::F32) => {
+                    | ColumnType::U64
+                    | ColumnType::I64) => {
                         let intrinsic = match ty {
-                            ColumnType::Bool => "deserialize_json_bool",
-                            ColumnType::I64 => "deserialize_json_i64",
+                            ColumnType::U8 => "deserialize_json_u8",
+                            ColumnType::I8 => "deserialize_json_i8",
+                            ColumnType::U16 => "deserialize_json_u16",
+                            ColumnType::I16 => "deserialize_json_i16",
+                            ColumnType::U32 => "deserialize_json_u32",
                             ColumnType::I32 => "deserialize_json_i32",
+                            ColumnType::U64 => "deserialize_json_u64",
+                            ColumnType::I64 => "deserialize_json_i64",
+                            ty => unreachable!("unhandled type in json deserialization: {ty}"),
+                        };
+
+                        // Call the deserialization function
+                        let deserialize = ctx.imports.get(intrinsic, ctx.module, builder.func);
+                        let on_overflow =
+                            builder.ins().iconst(types::I8, mappings.on_overflow as i64);
+                        let status = builder.call_fn(
+                            deserialize,
+                            &[
+                                column_place,
+                                json_pointer,
+                                json_pointer_len,
+                                on_overflow,
+                                json_map,
+                            ],
+                        );
+
+                        // Overflowing numbers fail deserialization under the
+                        // `error` policy, even for nullable columns
+                        let overflowed = builder.ins().icmp_imm(
+                            IntCC::Equal,
+                            status,
+                            JsonIntStatus::Overflow as i64,
+                        );
+                        let not_overflowed = builder.create_block();
+                        builder.ins().brif(
+                            overflowed,
+                            return_error,
+                            &[json_pointer, json_pointer_len],
+                            not_overflowed,
+                            &[],
+                        );
+                        builder.switch_to_block(not_overflowed);
+
+                        let value_is_null = builder.ins().icmp_imm(
+                            IntCC::Equal,
+                            status,
+                            JsonIntStatus::Null as i64,
+                        );
+
+                        // If the column is nullable, set its nullness
+                        if nullable {
+                            set_column_null(
+                                value_is_null,
+                                column_idx,
+                                place,
+                                MemFlags::trusted(),
+                                &layout,
+                                &mut builder,
+                            );
+
+                        // Otherwise return an error if deserialization fails or
+                        // the field is null
+                        } else {
+                            let after = builder.create_block();
+                            builder.ins().brif(
+                                value_is_null,
+                                return_error,
+                                &[json_pointer, json_pointer_len],
+                                after,
+                                &[],
+                            );
+
+                            builder.switch_to_block(after);
+                        }
+                    }
+
+                    ty @ (ColumnType::Bool | ColumnType::F64 | ColumnType::F32) => {
+                        let intrinsic = match ty {
+                            ColumnType::Bool => "deserialize_json_bool",
                             ColumnType::F64 => "deserialize_json_f64",
                             ColumnType::F32 => "deserialize_json_f32",
                             ty => unreachable!("unhandled type in json deserialization: {ty}"),
@@ -295,7 +484,13 @@ impl Codegen {
                             }
 
                             JsonColumnParseSpec::TimeFromMicros
-                            | JsonColumnParseSpec::TimeFromMillis => unreachable!(),
+                            | JsonColumnParseSpec::TimeFromMillis
+                            | JsonColumnParseSpec::TimestampFromSeconds
+                            | JsonColumnParseSpec::TimestampFromMillis
+                            | JsonColumnParseSpec::TimestampFromMicros
+                            | JsonColumnParseSpec::TimestampFromNanos
+                            | JsonColumnParseSpec::Decimal { .. }
+                            | JsonColumnParseSpec::Enum { .. } => unreachable!(),
                         };
 
                         // If the column is nullable, set its nullness
@@ -355,14 +550,26 @@ impl Codegen {
                             }
 
                             spec @ (JsonColumnParseSpec::TimeFromMicros
-                            | JsonColumnParseSpec::TimeFromMillis) => {
+                            | JsonColumnParseSpec::TimeFromMillis
+                            | JsonColumnParseSpec::TimestampFromSeconds
+                            | JsonColumnParseSpec::TimestampFromMillis
+                            | JsonColumnParseSpec::TimestampFromMicros
+                            | JsonColumnParseSpec::TimestampFromNanos) => {
                                 let intrinsic = match spec {
-                                    JsonColumnParseSpec::TimeFromMillis => {
+                                    JsonColumnParseSpec::TimestampFromSeconds => {
+                                        "deserialize_json_timestamp_from_seconds"
+                                    }
+                                    JsonColumnParseSpec::TimeFromMillis
+                                    | JsonColumnParseSpec::TimestampFromMillis => {
                                         "deserialize_json_timestamp_from_millis"
                                     }
-                                    JsonColumnParseSpec::TimeFromMicros => {
+                                    JsonColumnParseSpec::TimeFromMicros
+                                    | JsonColumnParseSpec::TimestampFromMicros => {
                                         "deserialize_json_timestamp_from_micros"
                                     }
+                                    JsonColumnParseSpec::TimestampFromNanos => {
+                                        "deserialize_json_timestamp_from_nanos"
+                                    }
                                     _ => unreachable!(),
                                 };
                                 let deserialize =
@@ -374,7 +581,9 @@ impl Codegen {
                                 )
                             }
 
-                            JsonColumnParseSpec::DateFromDays => unreachable!(),
+                            JsonColumnParseSpec::DateFromDays
+                            | JsonColumnParseSpec::Decimal { .. }
+                            | JsonColumnParseSpec::Enum { .. } => unreachable!(),
                         };
 
                         // If the column is nullable, set its nullness
@@ -453,7 +662,87 @@ impl Codegen {
                                 )
                             }
 
-                            JsonColumnParseSpec::DateFromDays => unreachable!(),
+                            JsonColumnParseSpec::DateFromDays
+                            | JsonColumnParseSpec::TimestampFromSeconds
+                            | JsonColumnParseSpec::TimestampFromMillis
+                            | JsonColumnParseSpec::TimestampFromMicros
+                            | JsonColumnParseSpec::TimestampFromNanos
+                            | JsonColumnParseSpec::Decimal { .. }
+                            | JsonColumnParseSpec::Enum { .. } => unreachable!(),
+                        };
+
+                        // If the column is nullable, set its nullness
+                        if nullable {
+                            set_column_null(
+                                value_is_null,
+                                column_idx,
+                                place,
+                                MemFlags::trusted(),
+                                &layout,
+                                &mut builder,
+                            );
+
+                        // Otherwise return an error if deserialization fails or
+                        // the field is null
+                        } else {
+                            let after = builder.create_block();
+                            builder.ins().brif(
+                                value_is_null,
+                                return_error,
+                                &[json_pointer, json_pointer_len],
+                                after,
+                                &[],
+                            );
+
+                            builder.switch_to_block(after);
+                        }
+                    }
+
+                    ColumnType::Decimal => {
+                        let value_is_null = match json_column.spec() {
+                            None => {
+                                let deserialize = ctx.imports.get(
+                                    "deserialize_json_decimal",
+                                    ctx.module,
+                                    builder.func,
+                                );
+
+                                builder.call_fn(
+                                    deserialize,
+                                    &[column_place, json_pointer, json_pointer_len, json_map],
+                                )
+                            }
+
+                            Some(&JsonColumnParseSpec::Decimal { precision, scale }) => {
+                                assert!(
+                                    scale <= 28,
+                                    "decimals can have at most 28 fractional digits (column {column_idx} of {layout_id} has a scale of {scale})",
+                                );
+
+                                let deserialize = ctx.imports.get(
+                                    "deserialize_json_decimal_with_scale",
+                                    ctx.module,
+                                    builder.func,
+                                );
+
+                                let precision = builder.ins().iconst(types::I32, precision as i64);
+                                let scale = builder.ins().iconst(types::I32, scale as i64);
+                                builder.call_fn(
+                                    deserialize,
+                                    &[
+                                        column_place,
+                                        json_pointer,
+                                        json_pointer_len,
+                                        precision,
+                                        scale,
+                                        json_map,
+                                    ],
+                                )
+                            }
+
+                            Some(spec) => {
+                                unreachable!("invalid json parsing spec for a decimal: {spec:?}")
+                            }
                         };
 
                         // If the column is nullable, set its nullness
@@ -74,6 +74,16 @@ impl JsonColumn {
         }
     }
 
+    pub fn decimal<K>(key: K, precision: u32, scale: u32) -> Self
+    where
+        K: Into<Box<str>>,
+    {
+        Self {
+            key: key.into(),
+            spec: Some(JsonColumnParseSpec::Decimal { precision, scale }),
+        }
+    }
+
     pub fn key(&self) -> &str {
         &self.key
     }
@@ -99,9 +109,67 @@ pub enum JsonColumnParseSpec {
     TimeFromMillis,
     /// Parses a date from an integer number of days
     DateFromDays,
+    /// Parses a timestamp from an integer number of seconds since the unix
+    /// epoch
+    TimestampFromSeconds,
+    /// Parses a timestamp from an integer number of milliseconds since the
+    /// unix epoch
+    TimestampFromMillis,
+    /// Parses a timestamp from an integer number of microseconds since the
+    /// unix epoch, truncating it to milliseconds
+    TimestampFromMicros,
+    /// Parses a timestamp from an integer number of nanoseconds since the
+    /// unix epoch, truncating it to milliseconds
+    TimestampFromNanos,
+    /// Parses a decimal from a string or a number, rounding it to `scale`
+    /// fractional digits. Values with more than `precision` digits in total
+    /// are rejected
+    Decimal { precision: u32, scale: u32 },
+    /// Parses the discriminator of a tagged union from a string, storing the
+    /// index of the matching variant in an `i32` column
+    ///
+    /// A tagged union is stored as the tag column followed by the fields of
+    /// all of its variants, which must be nullable columns: fields that don't
+    /// belong to the variant of the current record are absent from its json
+    /// and are therefore null. For example, events of the form
+    /// `{ "type": "a", "value": 1 }` or `{ "type": "b", "name": "x" }` can be
+    /// stored in the row `(i32, i64?, str?)` by mapping `/type` to the tag
+    /// column with the variants `["a", "b"]`, `/value` to the second column
+    /// and `/name` to the third one
+    Enum { variants: Vec<Box<str>> },
+}
+
+/// The outcome of deserializing an integer column, returned by the
+/// `deserialize_json_{int}` intrinsics
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub(crate) enum JsonIntStatus {
+    /// The value was written to the column
+    Ok = 0,
+    /// The value was missing or wasn't an integer
+    Null = 1,
+    /// The value didn't fit into the column's type under
+    /// [`JsonOverflowPolicy::Error`](pipeline_types::format::json::JsonOverflowPolicy::Error)
+    Overflow = 2,
 }
 
 impl JsonColumnParseSpec {
+    /// Returns the variants of an enum formatted as a sequence of json pointer
+    /// tokens, e.g. `/a/b`
+    fn enum_variants(&self) -> Option<String> {
+        if let Self::Enum { variants } = self {
+            let mut tokens = String::new();
+            for variant in variants {
+                tokens.push('/');
+                tokens.push_str(&variant.replace('~', "~0").replace('/', "~1"));
+            }
+
+            Some(tokens)
+        } else {
+            None
+        }
+    }
+
     #[must_use]
     pub const fn as_date_time_from_str(&self) -> Option<&str> {
         if let Self::DateTimeFromStr { format } = self {
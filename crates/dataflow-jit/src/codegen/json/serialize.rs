@@ -23,6 +23,10 @@ pub struct JsonSerConfig {
     // TODO: Allow serializing into nested structures
     // TODO: Allow specifying date & timestamp formats
     pub mappings: HashMap<ColumnIdx, JsonColumn>,
+    /// The number of decimal places to serialize floats with, `None` uses
+    /// the shortest representation that round-trips
+    #[serde(default)]
+    pub float_precision: Option<usize>,
 }
 
 impl Codegen {
@@ -208,6 +212,21 @@ impl Codegen {
                         builder.ins().call(intrinsic, &[buffer, ptr, len]);
                     }
 
+                    ty @ (ColumnType::F32 | ColumnType::F64)
+                        if mappings.float_precision.is_some() =>
+                    {
+                        let intrinsic = if ty == ColumnType::F32 {
+                            "write_f32_to_byte_vec_with_precision"
+                        } else {
+                            "write_f64_to_byte_vec_with_precision"
+                        };
+                        let intrinsic = ctx.imports.get(intrinsic, ctx.module, builder.func);
+
+                        let precision = mappings.float_precision.unwrap();
+                        let precision = builder.ins().iconst(ptr_ty, precision as i64);
+                        builder.ins().call(intrinsic, &[buffer, value, precision]);
+                    }
+
                     ty if ty.is_int() || ty.is_float() => {
                         let intrinsic = match ty {
                             ColumnType::I8 => "write_i8_to_byte_vec",
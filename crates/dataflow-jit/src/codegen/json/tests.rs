@@ -13,6 +13,8 @@ use crate::{
     utils::{self, HashMap},
 };
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use pipeline_types::format::json::JsonOverflowPolicy;
+use rust_decimal::Decimal;
 use std::mem::transmute;
 
 #[test]
@@ -52,6 +54,8 @@ fn deserialize_json_smoke() {
             .enumerate()
             .collect()
         },
+        reject_unknown: false,
+        on_overflow: JsonOverflowPolicy::Error,
     };
     let serialize = JsonSerConfig {
         layout,
@@ -70,6 +74,7 @@ fn deserialize_json_smoke() {
             .enumerate()
             .collect()
         },
+        float_precision: None,
     };
 
     let deserialize_json = codegen.deserialize_json(&deserialize);
@@ -158,6 +163,8 @@ fn deserialize_invalid_json() {
             mappings.insert(0, JsonColumn::normal("/foo"));
             mappings
         },
+        reject_unknown: false,
+        on_overflow: JsonOverflowPolicy::Error,
     };
 
     let deserialize_json = codegen.deserialize_json(&deserialize);
@@ -220,6 +227,8 @@ fn deserialize_parsing() {
 
             mappings
         },
+        reject_unknown: false,
+        on_overflow: JsonOverflowPolicy::Error,
     };
 
     let deserialize_json = codegen.deserialize_json(&deserialize);
@@ -268,3 +277,559 @@ fn deserialize_parsing() {
         jit.free_memory();
     }
 }
+
+#[test]
+fn deserialize_epoch_timestamps() {
+    utils::test_logger();
+
+    let layout_cache = RowLayoutCache::new();
+    let layout = layout_cache.add(
+        RowLayoutBuilder::new()
+            .with_column(ColumnType::Timestamp, false)
+            .with_column(ColumnType::Timestamp, false)
+            .with_column(ColumnType::Timestamp, false)
+            .with_column(ColumnType::Timestamp, false)
+            .build(),
+    );
+
+    let mut codegen = Codegen::new(layout_cache, CodegenConfig::debug());
+
+    let deserialize = JsonDeserConfig {
+        layout,
+        mappings: {
+            let columns = [
+                JsonColumn::new("/seconds", JsonColumnParseSpec::TimestampFromSeconds),
+                JsonColumn::new("/millis", JsonColumnParseSpec::TimestampFromMillis),
+                JsonColumn::new("/micros", JsonColumnParseSpec::TimestampFromMicros),
+                JsonColumn::new("/nanos", JsonColumnParseSpec::TimestampFromNanos),
+            ];
+
+            let mut mappings = HashMap::with_capacity_and_hasher(columns.len(), Default::default());
+            for (idx, column) in columns.into_iter().enumerate() {
+                mappings.insert(idx, column);
+            }
+
+            mappings
+        },
+        reject_unknown: false,
+        on_overflow: JsonOverflowPolicy::Error,
+    };
+
+    let deserialize_json = codegen.deserialize_json(&deserialize);
+    let vtable = codegen.vtable_for(layout);
+
+    let (jit, layout_cache) = codegen.finalize_definitions();
+    let vtable = Box::into_raw(Box::new(vtable.marshalled(&jit)));
+
+    {
+        let deserialize_json = unsafe {
+            transmute::<_, DeserializeJsonFn>(jit.get_finalized_function(deserialize_json))
+        };
+
+        // 2022-06-30 10:35:00 = 1656585300 seconds
+        // 2022-06-30 10:35:00 = 1656585300000 millis
+        // 2022-06-30 10:35:00 = 1656585300000000 micros
+        // 2022-06-30 10:35:00 = 1656585300000000000 nanos
+        let json_value = serde_json::from_str(
+            r#"{
+                "seconds": 1656585300,
+                "millis": 1656585300000,
+                "micros": 1656585300000000,
+                "nanos": 1656585300000000000
+            }"#,
+        )
+        .unwrap();
+        let mut uninit = UninitRow::new(unsafe { &*vtable });
+
+        unsafe {
+            if let Err(error) =
+                call_deserialize_fn(deserialize_json, uninit.as_mut_ptr(), &json_value)
+            {
+                panic!("{error}");
+            }
+        }
+
+        let row = unsafe { uninit.assume_init() };
+
+        let timestamp = NaiveDateTime::parse_from_str("2022-06-30 10:35:00", "%F %T").unwrap();
+        let expected = row![timestamp, timestamp, timestamp, timestamp];
+        let expected =
+            unsafe { row_from_literal(&expected, &*vtable, &layout_cache.layout_of(layout)) };
+
+        assert_eq!(row, expected);
+    }
+
+    unsafe {
+        drop(Box::from_raw(vtable));
+        jit.free_memory();
+    }
+}
+
+#[test]
+fn deserialize_unknown_keys() {
+    utils::test_logger();
+
+    let layout_cache = RowLayoutCache::new();
+    let layout = layout_cache.add(
+        RowLayoutBuilder::new()
+            .with_column(ColumnType::I64, false)
+            .with_column(ColumnType::I64, true)
+            .build(),
+    );
+
+    let mut codegen = Codegen::new(layout_cache, CodegenConfig::debug());
+
+    let mappings: HashMap<_, _> = [JsonColumn::normal("/foo"), JsonColumn::normal("/bar")]
+        .into_iter()
+        .enumerate()
+        .collect();
+    let lenient = codegen.deserialize_json(&JsonDeserConfig {
+        layout,
+        mappings: mappings.clone(),
+        reject_unknown: false,
+        on_overflow: JsonOverflowPolicy::Error,
+    });
+    let strict = codegen.deserialize_json(&JsonDeserConfig {
+        layout,
+        mappings,
+        reject_unknown: true,
+        on_overflow: JsonOverflowPolicy::Error,
+    });
+    let vtable = codegen.vtable_for(layout);
+
+    let (jit, layout_cache) = codegen.finalize_definitions();
+    let vtable = Box::into_raw(Box::new(vtable.marshalled(&jit)));
+
+    {
+        let (lenient, strict) = unsafe {
+            (
+                transmute::<_, DeserializeJsonFn>(jit.get_finalized_function(lenient)),
+                transmute::<_, DeserializeJsonFn>(jit.get_finalized_function(strict)),
+            )
+        };
+
+        let expected = unsafe {
+            row_from_literal(
+                &row![10i64, null],
+                &*vtable,
+                &layout_cache.layout_of(layout),
+            )
+        };
+
+        // Known keys are accepted in both modes, including keys of nullable
+        // columns that are missing from the input
+        let json_value = serde_json::from_str(r#"{ "foo": 10 }"#).unwrap();
+        for deserialize_json in [lenient, strict] {
+            let mut uninit = UninitRow::new(unsafe { &*vtable });
+            let row = unsafe {
+                call_deserialize_fn(deserialize_json, uninit.as_mut_ptr(), &json_value).unwrap();
+                uninit.assume_init()
+            };
+            assert_eq!(row, expected);
+        }
+
+        // The unknown key is ignored by default
+        let json_value = serde_json::from_str(r#"{ "foo": 10, "typo": 20 }"#).unwrap();
+        let mut uninit = UninitRow::new(unsafe { &*vtable });
+        let row = unsafe {
+            call_deserialize_fn(lenient, uninit.as_mut_ptr(), &json_value).unwrap();
+            uninit.assume_init()
+        };
+        assert_eq!(row, expected);
+
+        // ...and rejected in strict mode
+        let mut uninit = UninitRow::new(unsafe { &*vtable });
+        let error =
+            unsafe { call_deserialize_fn(strict, uninit.as_mut_ptr(), &json_value) }.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "an error occurred while parsing the key \"TYPO\" (unknown key)",
+        );
+    }
+
+    unsafe {
+        drop(Box::from_raw(vtable));
+        jit.free_memory();
+    }
+}
+
+#[test]
+fn serialize_float_precision() {
+    utils::test_logger();
+
+    let layout_cache = RowLayoutCache::new();
+    let layout = layout_cache.add(
+        RowLayoutBuilder::new()
+            .with_column(ColumnType::F64, false)
+            .with_column(ColumnType::F64, true)
+            .build(),
+    );
+
+    let mut codegen = Codegen::new(layout_cache, CodegenConfig::debug());
+
+    let mappings: HashMap<_, _> = [JsonColumn::normal("foo"), JsonColumn::normal("bar")]
+        .into_iter()
+        .enumerate()
+        .collect();
+    let default = codegen.serialize_json(&JsonSerConfig {
+        layout,
+        mappings: mappings.clone(),
+        float_precision: None,
+    });
+    let rounded = codegen.serialize_json(&JsonSerConfig {
+        layout,
+        mappings,
+        float_precision: Some(2),
+    });
+    let vtable = codegen.vtable_for(layout);
+
+    let (jit, layout_cache) = codegen.finalize_definitions();
+    let vtable = Box::into_raw(Box::new(vtable.marshalled(&jit)));
+
+    {
+        let (default, rounded) = unsafe {
+            (
+                transmute::<_, SerializeFn>(jit.get_finalized_function(default)),
+                transmute::<_, SerializeFn>(jit.get_finalized_function(rounded)),
+            )
+        };
+
+        let row = unsafe {
+            row_from_literal(
+                &row![96.542000000001f64, ?1.5f64],
+                &*vtable,
+                &layout_cache.layout_of(layout),
+            )
+        };
+
+        let mut buffer = Vec::new();
+        unsafe { default(row.as_ptr(), &mut buffer) };
+        assert_eq!(
+            std::str::from_utf8(&buffer).unwrap(),
+            r#"{"foo":96.542000000001,"bar":1.5}"#,
+        );
+
+        buffer.clear();
+        unsafe { rounded(row.as_ptr(), &mut buffer) };
+        assert_eq!(
+            std::str::from_utf8(&buffer).unwrap(),
+            r#"{"foo":96.54,"bar":1.50}"#,
+        );
+    }
+
+    unsafe {
+        drop(Box::from_raw(vtable));
+        jit.free_memory();
+    }
+}
+
+#[test]
+fn json_decimal_roundtrip() {
+    utils::test_logger();
+
+    let layout_cache = RowLayoutCache::new();
+    let layout = layout_cache.add(
+        RowLayoutBuilder::new()
+            .with_column(ColumnType::Decimal, false)
+            .with_column(ColumnType::Decimal, true)
+            .build(),
+    );
+
+    let mut codegen = Codegen::new(layout_cache, CodegenConfig::debug());
+
+    let deserialize_json = codegen.deserialize_json(&JsonDeserConfig {
+        layout,
+        mappings: [
+            JsonColumn::decimal("/foo", 10, 2),
+            JsonColumn::decimal("/bar", 4, 2),
+        ]
+        .into_iter()
+        .enumerate()
+        .collect(),
+        reject_unknown: false,
+        on_overflow: JsonOverflowPolicy::Error,
+    });
+    let serialize_json = codegen.serialize_json(&JsonSerConfig {
+        layout,
+        mappings: [JsonColumn::normal("foo"), JsonColumn::normal("bar")]
+            .into_iter()
+            .enumerate()
+            .collect(),
+        float_precision: None,
+    });
+    let vtable = codegen.vtable_for(layout);
+
+    let (jit, layout_cache) = codegen.finalize_definitions();
+    let vtable = Box::into_raw(Box::new(vtable.marshalled(&jit)));
+
+    {
+        let (deserialize_json, serialize_json) = unsafe {
+            (
+                transmute::<_, DeserializeJsonFn>(jit.get_finalized_function(deserialize_json)),
+                transmute::<_, SerializeFn>(jit.get_finalized_function(serialize_json)),
+            )
+        };
+
+        let roundtrip = |json: &str| {
+            let json_value = serde_json::from_str(json).unwrap();
+            let mut uninit = UninitRow::new(unsafe { &*vtable });
+            let row = unsafe {
+                call_deserialize_fn(deserialize_json, uninit.as_mut_ptr(), &json_value).unwrap();
+                uninit.assume_init()
+            };
+
+            let mut buffer = Vec::new();
+            unsafe { serialize_json(row.as_ptr(), &mut buffer) };
+            (row, String::from_utf8(buffer).unwrap())
+        };
+
+        // Strings are parsed exactly
+        let (row, json) = roundtrip(r#"{ "foo": "123.45" }"#);
+        let expected = unsafe {
+            row_from_literal(
+                &row![Decimal::new(12345, 2), null],
+                &*vtable,
+                &layout_cache.layout_of(layout),
+            )
+        };
+        assert_eq!(row, expected);
+        assert_eq!(json, r#"{"foo":123.45,"bar":null}"#);
+
+        // Numbers are rounded or padded to the column's scale
+        let (_, json) = roundtrip(r#"{ "foo": 7, "bar": 12.345 }"#);
+        assert_eq!(json, r#"{"foo":7.00,"bar":12.35}"#);
+
+        // Values that exceed the column's precision are rejected
+        let (_, json) = roundtrip(r#"{ "foo": "0.1", "bar": "123.4" }"#);
+        assert_eq!(json, r#"{"foo":0.10,"bar":null}"#);
+    }
+
+    unsafe {
+        drop(Box::from_raw(vtable));
+        jit.free_memory();
+    }
+}
+
+#[test]
+fn deserialize_tagged_union() {
+    utils::test_logger();
+
+    let layout_cache = RowLayoutCache::new();
+    let layout = layout_cache.add(
+        RowLayoutBuilder::new()
+            .with_column(ColumnType::I32, false)
+            .with_column(ColumnType::I64, true)
+            .with_column(ColumnType::String, true)
+            .build(),
+    );
+
+    let mut codegen = Codegen::new(layout_cache, CodegenConfig::debug());
+
+    // The variant `a` stores its field in column 1 and `b` in column 2
+    let deserialize_json = codegen.deserialize_json(&JsonDeserConfig {
+        layout,
+        mappings: [
+            JsonColumn::new(
+                "/type",
+                JsonColumnParseSpec::Enum {
+                    variants: vec!["a".into(), "b".into()],
+                },
+            ),
+            JsonColumn::normal("/value"),
+            JsonColumn::normal("/name"),
+        ]
+        .into_iter()
+        .enumerate()
+        .collect(),
+        reject_unknown: false,
+        on_overflow: JsonOverflowPolicy::Error,
+    });
+    let vtable = codegen.vtable_for(layout);
+
+    let (jit, layout_cache) = codegen.finalize_definitions();
+    let vtable = Box::into_raw(Box::new(vtable.marshalled(&jit)));
+
+    {
+        let deserialize_json = unsafe {
+            transmute::<_, DeserializeJsonFn>(jit.get_finalized_function(deserialize_json))
+        };
+
+        let deserialize = |json: &str| {
+            let json_value = serde_json::from_str(json).unwrap();
+            let mut uninit = UninitRow::new(unsafe { &*vtable });
+            unsafe {
+                call_deserialize_fn(deserialize_json, uninit.as_mut_ptr(), &json_value)
+                    .map(|()| uninit.assume_init())
+            }
+        };
+
+        let expected = [row![0i32, ?1i64, null], row![1i32, null, ?"x"]];
+        for (json, expected) in [
+            r#"{ "type": "a", "value": 1 }"#,
+            r#"{ "type": "b", "name": "x" }"#,
+        ]
+        .into_iter()
+        .zip(expected)
+        {
+            let row = deserialize(json).unwrap();
+            let expected =
+                unsafe { row_from_literal(&expected, &*vtable, &layout_cache.layout_of(layout)) };
+            assert_eq!(row, expected, "input json: {json:?}");
+        }
+
+        // Unknown variants are rejected
+        let error = deserialize(r#"{ "type": "c", "value": 1 }"#).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "an error occurred while parsing the key \"/TYPE\"",
+        );
+    }
+
+    unsafe {
+        drop(Box::from_raw(vtable));
+        jit.free_memory();
+    }
+}
+
+#[test]
+fn deserialize_integer_overflow() {
+    utils::test_logger();
+
+    let layout_cache = RowLayoutCache::new();
+    let layout = layout_cache.add(
+        RowLayoutBuilder::new()
+            .with_column(ColumnType::I8, false)
+            .build(),
+    );
+
+    let mut codegen = Codegen::new(layout_cache, CodegenConfig::debug());
+
+    let mappings: HashMap<_, _> = [(0, JsonColumn::normal("/x"))].into_iter().collect();
+    let policies = [
+        JsonOverflowPolicy::Error,
+        JsonOverflowPolicy::Saturate,
+        JsonOverflowPolicy::Wrap,
+    ];
+    let functions = policies.map(|on_overflow| {
+        codegen.deserialize_json(&JsonDeserConfig {
+            layout,
+            mappings: mappings.clone(),
+            reject_unknown: false,
+            on_overflow,
+        })
+    });
+    let vtable = codegen.vtable_for(layout);
+
+    let (jit, layout_cache) = codegen.finalize_definitions();
+    let vtable = Box::into_raw(Box::new(vtable.marshalled(&jit)));
+
+    {
+        let [error, saturate, wrap] = functions.map(|function| unsafe {
+            transmute::<_, DeserializeJsonFn>(jit.get_finalized_function(function))
+        });
+
+        let deserialize = |deserialize_json: DeserializeJsonFn, json: &str| {
+            let json_value = serde_json::from_str(json).unwrap();
+            let mut uninit = UninitRow::new(unsafe { &*vtable });
+            unsafe {
+                call_deserialize_fn(deserialize_json, uninit.as_mut_ptr(), &json_value)
+                    .map(|()| uninit.assume_init())
+            }
+        };
+        let expected = |value: i8| unsafe {
+            row_from_literal(&row![value], &*vtable, &layout_cache.layout_of(layout))
+        };
+
+        // Numbers that fit into the column are accepted under every policy
+        for deserialize_json in [error, saturate, wrap] {
+            let row = deserialize(deserialize_json, r#"{ "x": -100 }"#).unwrap();
+            assert_eq!(row, expected(-100));
+        }
+
+        // Numbers that overflow the column are rejected...
+        let err = deserialize(error, r#"{ "x": 300 }"#).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "an error occurred while parsing the key \"/X\"",
+        );
+
+        // ...saturated...
+        let row = deserialize(saturate, r#"{ "x": 300 }"#).unwrap();
+        assert_eq!(row, expected(127));
+        let row = deserialize(saturate, r#"{ "x": -300 }"#).unwrap();
+        assert_eq!(row, expected(-128));
+
+        // ...or wrapped around depending on the policy
+        let row = deserialize(wrap, r#"{ "x": 300 }"#).unwrap();
+        assert_eq!(row, expected(44));
+    }
+
+    unsafe {
+        drop(Box::from_raw(vtable));
+        jit.free_memory();
+    }
+}
+
+#[test]
+fn deserialize_nullable_integer_overflow() {
+    utils::test_logger();
+
+    let layout_cache = RowLayoutCache::new();
+    let layout = layout_cache.add(
+        RowLayoutBuilder::new()
+            .with_column(ColumnType::I8, true)
+            .build(),
+    );
+
+    let mut codegen = Codegen::new(layout_cache, CodegenConfig::debug());
+
+    let deserialize_json = codegen.deserialize_json(&JsonDeserConfig {
+        layout,
+        mappings: [(0, JsonColumn::normal("/x"))].into_iter().collect(),
+        reject_unknown: false,
+        on_overflow: JsonOverflowPolicy::Error,
+    });
+    let vtable = codegen.vtable_for(layout);
+
+    let (jit, layout_cache) = codegen.finalize_definitions();
+    let vtable = Box::into_raw(Box::new(vtable.marshalled(&jit)));
+
+    {
+        let deserialize_json = unsafe {
+            transmute::<_, DeserializeJsonFn>(jit.get_finalized_function(deserialize_json))
+        };
+
+        let deserialize = |json: &str| {
+            let json_value = serde_json::from_str(json).unwrap();
+            let mut uninit = UninitRow::new(unsafe { &*vtable });
+            unsafe {
+                call_deserialize_fn(deserialize_json, uninit.as_mut_ptr(), &json_value)
+                    .map(|()| uninit.assume_init())
+            }
+        };
+        let layout = layout_cache.layout_of(layout);
+
+        let row = deserialize(r#"{ "x": -100 }"#).unwrap();
+        assert_eq!(row, unsafe {
+            row_from_literal(&row![?-100i8], &*vtable, &layout)
+        });
+
+        // Missing values are null
+        let row = deserialize("{}").unwrap();
+        assert_eq!(row, unsafe {
+            row_from_literal(&row![null], &*vtable, &layout)
+        });
+
+        // Overflowing numbers are rejected rather than silently turned into nulls
+        let err = deserialize(r#"{ "x": 300 }"#).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "an error occurred while parsing the key \"/X\"",
+        );
+    }
+
+    unsafe {
+        drop(Box::from_raw(vtable));
+        jit.free_memory();
+    }
+}
@@ -0,0 +1,367 @@
+//! Benchmark incremental join throughput as the size of the join state grows.
+//!
+//! Every benchmark iteration feeds a new batch to both inputs of
+//! `join_incremental` and evaluates a single step of the circuit, so the
+//! reported time is per-step latency.  The number of allocations performed
+//! by a single step is printed after each benchmark.
+//!
+//! The `join-projection` benchmarks compare `join` and `join_distinct` on a
+//! join function that maps 10 pairs of input records to the same output
+//! record.
+//!
+//! The `join-asymmetric` benchmarks compare `stream_join` and
+//! `stream_hash_join` on inputs whose sizes differ by a factor of 1000.
+//!
+//! The `join-cow` benchmarks compare `stream_join` and `stream_join_cow` on a
+//! join function that outputs the string value of the left input unchanged.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use dbsp::{
+    mimalloc::MiMalloc, operator::HashJoinSide, CircuitHandle, CollectionHandle, OrdZSet,
+    RootCircuit,
+};
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256StarStar;
+use std::{
+    alloc::{GlobalAlloc, Layout},
+    borrow::Cow,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// A [`GlobalAlloc`] that counts allocations performed via [`MiMalloc`].
+struct CountingAlloc {
+    allocations: AtomicUsize,
+}
+
+impl CountingAlloc {
+    fn allocations(&self) -> usize {
+        self.allocations.load(Ordering::Relaxed)
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.allocations.fetch_add(1, Ordering::Relaxed);
+        MiMalloc.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        self.allocations.fetch_add(1, Ordering::Relaxed);
+        MiMalloc.alloc_zeroed(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        MiMalloc.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        self.allocations.fetch_add(1, Ordering::Relaxed);
+        MiMalloc.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc {
+    allocations: AtomicUsize::new(0),
+};
+
+/// The seed for our prng-generated benchmarks
+const SEED: [u8; 32] = [
+    0x7f, 0xc3, 0x59, 0x18, 0x45, 0x19, 0xc0, 0xaa, 0xd2, 0xec, 0x31, 0x26, 0xbb, 0x74, 0x2f, 0x8b,
+    0x11, 0x7d, 0xc, 0xe4, 0x64, 0xbf, 0x72, 0x17, 0x46, 0x28, 0x46, 0x42, 0xb2, 0x4b, 0x72, 0x18,
+];
+
+/// Number of records in each input batch.
+const BATCH_SIZES: [usize; 3] = [1_000, 10_000, 100_000];
+
+/// Shape of the input data.
+#[derive(Clone, Copy)]
+struct Scenario {
+    name: &'static str,
+    /// Number of distinct keys.
+    keys: u64,
+    /// Number of distinct values per key.
+    values_per_key: u64,
+}
+
+const SCENARIOS: [Scenario; 2] = [
+    // Many distinct keys: most records find few or no matches.
+    Scenario {
+        name: "low-selectivity",
+        keys: 1_000_000,
+        values_per_key: 4,
+    },
+    // Every record matches all values of its key on the other side.  Since
+    // the join state contains at most `values_per_key` distinct values per
+    // key, a step produces at most `2 * batch_size * values_per_key` output
+    // tuples, i.e., 2M tuples for the largest batch size.
+    Scenario {
+        name: "high-selectivity",
+        keys: 10_000,
+        values_per_key: 10,
+    },
+];
+
+type Input = CollectionHandle<u64, (u64, isize)>;
+
+fn join_circuit() -> (CircuitHandle, (Input, Input)) {
+    RootCircuit::build(|circuit| {
+        let (left, left_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+        let (right, right_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+
+        left.join_incremental::<_, _, OrdZSet<(u64, u64, u64), isize>>(&right, |k, v1, v2| {
+            (*k, *v1, *v2)
+        });
+
+        Ok((left_handle, right_handle))
+    })
+    .unwrap()
+}
+
+/// Scenario for the `join-projection` benchmarks.
+const PROJECTION_SCENARIO: Scenario = Scenario {
+    name: "projection",
+    keys: 10_000,
+    values_per_key: 100,
+};
+
+fn projection_circuit(distinct: bool) -> (CircuitHandle, (Input, Input)) {
+    RootCircuit::build(move |circuit| {
+        let (left, left_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+        let (right, right_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+
+        // Collapse every 10 values of the right input into one output record.
+        let project = |k: &u64, v1: &u64, v2: &u64| (*k, *v1, *v2 / 10);
+        if distinct {
+            left.join_distinct(&right, project);
+        } else {
+            left.join(&right, project);
+        }
+
+        Ok((left_handle, right_handle))
+    })
+    .unwrap()
+}
+
+/// Number of records in the large input of the `join-asymmetric` benchmarks.
+/// The small input contains 1000 times fewer records.
+const ASYMMETRIC_SIZE: usize = 1_000_000;
+
+const ASYMMETRIC_SCENARIO: Scenario = Scenario {
+    name: "asymmetric",
+    keys: 100_000,
+    values_per_key: 10,
+};
+
+fn asymmetric_circuit(hash: bool) -> (CircuitHandle, (Input, Input)) {
+    RootCircuit::build(move |circuit| {
+        let (left, left_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+        let (right, right_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+
+        let join = |k: &u64, v1: &u64, v2: &u64| (*k, *v1, *v2);
+        if hash {
+            left.stream_hash_join(&right, join, HashJoinSide::Auto);
+        } else {
+            left.stream_join(&right, join);
+        }
+
+        Ok((left_handle, right_handle))
+    })
+    .unwrap()
+}
+
+/// Scenario for the `join-cow` benchmarks: every left value matches 100
+/// values on the right.
+const COW_SCENARIO: Scenario = Scenario {
+    name: "cow",
+    keys: 1_000,
+    values_per_key: 100,
+};
+
+type StringInput = CollectionHandle<u64, (String, isize)>;
+
+fn cow_circuit(cow: bool) -> (CircuitHandle, (StringInput, Input)) {
+    RootCircuit::build(move |circuit| {
+        let (left, left_handle) = circuit.add_input_indexed_zset::<u64, String, isize>();
+        let (right, right_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+
+        if cow {
+            left.stream_join_cow::<_, _, OrdZSet<String, isize>>(&right, |_k, v1, _v2| {
+                Cow::Borrowed(v1)
+            });
+        } else {
+            left.stream_join(&right, |_k, v1: &String, _v2| v1.clone());
+        }
+
+        Ok((left_handle, right_handle))
+    })
+    .unwrap()
+}
+
+fn generate_batch(
+    rng: &mut Xoshiro256StarStar,
+    scenario: Scenario,
+    size: usize,
+) -> Vec<(u64, (u64, isize))> {
+    (0..size)
+        .map(|_| {
+            (
+                rng.gen_range(0..scenario.keys),
+                (rng.gen_range(0..scenario.values_per_key), 1),
+            )
+        })
+        .collect()
+}
+
+fn join_benches(c: &mut Criterion) {
+    for scenario in SCENARIOS {
+        let mut group = c.benchmark_group(format!("join-incremental-{}", scenario.name));
+
+        for size in BATCH_SIZES {
+            let (circuit, (left, right)) = join_circuit();
+            let mut rng = Xoshiro256StarStar::from_seed(SEED);
+
+            group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+                b.iter_batched(
+                    || {
+                        (
+                            generate_batch(&mut rng, scenario, size),
+                            generate_batch(&mut rng, scenario, size),
+                        )
+                    },
+                    |(mut left_batch, mut right_batch)| {
+                        left.append(&mut left_batch);
+                        right.append(&mut right_batch);
+                        circuit.step().unwrap();
+                    },
+                    BatchSize::LargeInput,
+                )
+            });
+
+            // Report the number of allocations in a single step against the
+            // state accumulated during the benchmark.
+            let mut left_batch = generate_batch(&mut rng, scenario, size);
+            let mut right_batch = generate_batch(&mut rng, scenario, size);
+            let before = ALLOC.allocations();
+            left.append(&mut left_batch);
+            right.append(&mut right_batch);
+            circuit.step().unwrap();
+            let allocations = ALLOC.allocations() - before;
+            println!(
+                "join-incremental-{}/{size}: {allocations} allocations per step",
+                scenario.name
+            );
+        }
+
+        group.finish();
+    }
+}
+
+fn projection_benches(c: &mut Criterion) {
+    let mut group = c.benchmark_group("join-projection");
+
+    for distinct in [false, true] {
+        let name = if distinct { "join_distinct" } else { "join" };
+
+        for size in BATCH_SIZES {
+            let (circuit, (left, right)) = projection_circuit(distinct);
+            let mut rng = Xoshiro256StarStar::from_seed(SEED);
+
+            group.bench_with_input(BenchmarkId::new(name, size), &size, |b, &size| {
+                b.iter_batched(
+                    || {
+                        (
+                            generate_batch(&mut rng, PROJECTION_SCENARIO, size),
+                            generate_batch(&mut rng, PROJECTION_SCENARIO, size),
+                        )
+                    },
+                    |(mut left_batch, mut right_batch)| {
+                        left.append(&mut left_batch);
+                        right.append(&mut right_batch);
+                        circuit.step().unwrap();
+                    },
+                    BatchSize::LargeInput,
+                )
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn asymmetric_benches(c: &mut Criterion) {
+    let mut group = c.benchmark_group("join-asymmetric");
+
+    let mut rng = Xoshiro256StarStar::from_seed(SEED);
+    let large = generate_batch(&mut rng, ASYMMETRIC_SCENARIO, ASYMMETRIC_SIZE);
+    let small = generate_batch(&mut rng, ASYMMETRIC_SCENARIO, ASYMMETRIC_SIZE / 1000);
+
+    for hash in [false, true] {
+        let name = if hash {
+            "stream_hash_join"
+        } else {
+            "stream_join"
+        };
+        let (circuit, (left, right)) = asymmetric_circuit(hash);
+
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || (large.clone(), small.clone()),
+                |(mut left_batch, mut right_batch)| {
+                    left.append(&mut left_batch);
+                    right.append(&mut right_batch);
+                    circuit.step().unwrap();
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn cow_benches(c: &mut Criterion) {
+    let mut group = c.benchmark_group("join-cow");
+
+    let mut rng = Xoshiro256StarStar::from_seed(SEED);
+    let left = (0..COW_SCENARIO.keys)
+        .map(|k| (k, (format!("record {k:>64}"), 1)))
+        .collect::<Vec<_>>();
+    let right = generate_batch(
+        &mut rng,
+        COW_SCENARIO,
+        (COW_SCENARIO.keys * COW_SCENARIO.values_per_key) as usize,
+    );
+
+    for cow in [false, true] {
+        let name = if cow {
+            "stream_join_cow"
+        } else {
+            "stream_join"
+        };
+        let (circuit, (left_handle, right_handle)) = cow_circuit(cow);
+
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || (left.clone(), right.clone()),
+                |(mut left_batch, mut right_batch)| {
+                    left_handle.append(&mut left_batch);
+                    right_handle.append(&mut right_batch);
+                    circuit.step().unwrap();
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    join_benches,
+    projection_benches,
+    asymmetric_benches,
+    cow_benches
+);
+criterion_main!(benches);
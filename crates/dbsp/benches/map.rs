@@ -0,0 +1,100 @@
+//! Benchmark rekeying a large Z-set with `map` and `map_keys_partitioned`.
+//!
+//! Every benchmark iteration feeds a batch of random keys to the circuit and
+//! evaluates a single step, so the reported time is the time to rekey one
+//! batch.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use dbsp::{
+    operator::{communication::PartitionScheme, FilterMap},
+    CircuitHandle, CollectionHandle, RootCircuit,
+};
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256StarStar;
+
+/// The seed for our prng-generated benchmarks
+const SEED: [u8; 32] = [
+    0x7f, 0xc3, 0x59, 0x18, 0x45, 0x19, 0xc0, 0xaa, 0xd2, 0xec, 0x31, 0x26, 0xbb, 0x74, 0x2f, 0x8b,
+    0x11, 0x7d, 0xc, 0xe4, 0x64, 0xbf, 0x72, 0x17, 0x46, 0x28, 0x46, 0x42, 0xb2, 0x4b, 0x72, 0x18,
+];
+
+/// Number of records in each input batch.
+const BATCH_SIZE: usize = 1_000_000;
+
+/// Numbers of partitions to benchmark `map_keys_partitioned` with.
+const PARTITIONS: [usize; 3] = [2, 4, 8];
+
+type Input = CollectionHandle<(u64, u64), isize>;
+
+/// Swap the components of each key, as when reversing the edges of a graph
+/// before joining them.
+fn rekey(&(from, to): &(u64, u64)) -> (u64, u64) {
+    (to, from)
+}
+
+/// Build a circuit that rekeys its input using `map` if `partitions` is
+/// `None` and `map_keys_partitioned` otherwise.
+fn map_circuit(partitions: Option<usize>) -> (CircuitHandle, Input) {
+    RootCircuit::build(move |circuit| {
+        let (input, input_handle) = circuit.add_input_zset::<(u64, u64), isize>();
+
+        match partitions {
+            None => {
+                input.map(rekey);
+            }
+            Some(partitions) => {
+                input.map_keys_partitioned(partitions, PartitionScheme::default(), rekey);
+            }
+        }
+
+        Ok(input_handle)
+    })
+    .unwrap()
+}
+
+fn generate_batch(rng: &mut Xoshiro256StarStar, size: usize) -> Vec<((u64, u64), isize)> {
+    (0..size).map(|_| ((rng.gen(), rng.gen()), 1)).collect()
+}
+
+fn map_benches(c: &mut Criterion) {
+    let mut group = c.benchmark_group("map-keys");
+    group.sample_size(10);
+
+    let mut rng = Xoshiro256StarStar::from_seed(SEED);
+    let batch = generate_batch(&mut rng, BATCH_SIZE);
+
+    let (circuit, input) = map_circuit(None);
+    group.bench_function("map", |b| {
+        b.iter_batched(
+            || batch.clone(),
+            |mut batch| {
+                input.append(&mut batch);
+                circuit.step().unwrap();
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    for partitions in PARTITIONS {
+        let (circuit, input) = map_circuit(Some(partitions));
+        group.bench_with_input(
+            BenchmarkId::new("map_keys_partitioned", partitions),
+            &partitions,
+            |b, _| {
+                b.iter_batched(
+                    || batch.clone(),
+                    |mut batch| {
+                        input.append(&mut batch);
+                        circuit.step().unwrap();
+                    },
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, map_benches);
+criterion_main!(benches);
@@ -8,6 +8,7 @@ use crate::{
     trace::{cursor::Cursor, Batch, Builder},
     NumEntries,
 };
+use std::vec::IntoIter;
 
 /// A set of weighted key-value pairs.
 ///
@@ -100,6 +101,21 @@ pub trait IndexedZSet: Batch<Time = ()> + GroupValue + NumEntries {
     fn iter(&self) -> IndexedZSetIterator<Self> {
         IndexedZSetIterator::new(self.cursor())
     }
+
+    /// Returns an iterator over updates in the indexed Z-set in ascending
+    /// `(key, value)` order.
+    ///
+    /// Unlike [`Self::iter`], which yields updates in the order determined
+    /// by the cursor of the underlying batch, this method guarantees a
+    /// stable iteration order regardless of the batch implementation, which
+    /// makes it suitable for reproducible logging and test assertions.
+    fn iter_sorted(&self) -> IntoIter<(Self::Key, Self::Val, Self::R)> {
+        let mut updates = self.iter().collect::<Vec<_>>();
+        // Cursors over ordered batches already yield updates in this order,
+        // in which case sorting takes linear time.
+        updates.sort_by(|(k1, v1, _), (k2, v2, _)| (k1, v1).cmp(&(k2, v2)));
+        updates.into_iter()
+    }
 }
 
 impl<Z> IndexedZSet for Z where Z: Batch<Time = ()> + GroupValue + NumEntries {}
@@ -175,7 +191,8 @@ where
 #[cfg(test)]
 mod test {
     use crate::trace::Batch;
-    use crate::{IndexedZSet, OrdIndexedZSet};
+    use crate::{IndexedZSet, OrdIndexedZSet, OrdZSet};
+    use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
 
     #[test]
     fn test_indexed_zset_iterator() {
@@ -206,4 +223,31 @@ mod test {
             Vec::new()
         );
     }
+
+    #[test]
+    fn test_iter_sorted() {
+        let mut tuples: Vec<((usize, usize), i32)> = (0..10)
+            .flat_map(|k| (0..10).map(move |v| ((k, v), (k + v) as i32 % 3 - 1)))
+            .filter(|(_, w)| *w != 0)
+            .collect();
+        let expected = tuples.clone();
+        tuples.shuffle(&mut StdRng::seed_from_u64(0));
+
+        let indexed_zset = <OrdIndexedZSet<usize, usize, i32>>::from_tuples((), tuples.clone());
+        assert_eq!(
+            indexed_zset
+                .iter_sorted()
+                .map(|(k, v, w)| ((k, v), w))
+                .collect::<Vec<_>>(),
+            expected
+        );
+
+        let zset = <OrdZSet<(usize, usize), i32>>::from_keys(
+            (),
+            tuples.into_iter().map(|((k, v), w)| ((v, k), w)).collect(),
+        );
+        let sorted = zset.iter_sorted().map(|(k, _, _)| k).collect::<Vec<_>>();
+        assert!(sorted.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(sorted.len(), expected.len());
+    }
 }
@@ -35,16 +35,17 @@ use crate::{
 use crate::{
     circuit::{
         cache::{CircuitCache, CircuitStoreMarker},
-        metadata::OperatorMeta,
+        metadata::{OperatorMeta, OperatorStats},
         operator_traits::{
-            BinaryOperator, BinarySinkOperator, Data, ImportOperator, NaryOperator,
-            QuaternaryOperator, SinkOperator, SourceOperator, StrictUnaryOperator, TernaryOperator,
-            UnaryOperator,
+            BinaryOperator, BinarySinkOperator, Data, ImportOperator, MemoryStats, NaryOperator,
+            Operator, QuaternaryOperator, SinkOperator, SourceOperator, StrictUnaryOperator,
+            TernaryOperator, UnaryOperator,
         },
         schedule::{
-            DynamicScheduler, Error as SchedulerError, Executor, IterativeExecutor, OnceExecutor,
-            Scheduler,
+            set_step_deadline, DynamicScheduler, Error as SchedulerError, Executor,
+            IterativeExecutor, OnceExecutor, Scheduler,
         },
+        snapshot::{CircuitSnapshot, OperatorSnapshot},
         trace::{CircuitEvent, SchedulerEvent},
     },
     circuit_cache_key,
@@ -56,8 +57,8 @@ use anyhow::Error as AnyError;
 use serde::Serialize;
 use std::{
     borrow::Cow,
-    cell::{Ref, RefCell, RefMut, UnsafeCell},
-    collections::HashMap,
+    cell::{Cell, Ref, RefCell, RefMut, UnsafeCell},
+    collections::{BTreeMap, BTreeSet, HashMap},
     fmt,
     fmt::{Debug, Display, Write},
     iter::repeat,
@@ -65,6 +66,7 @@ use std::{
     panic::Location,
     rc::Rc,
     thread::panicking,
+    time::{Duration, Instant},
 };
 use typedmap::{TypedMap, TypedMapKey};
 
@@ -619,6 +621,28 @@ where
         }
     }
 
+    /// Attach a user-defined label to the operator that writes to this
+    /// stream.
+    ///
+    /// The label replaces the default name of the operator, e.g., `"Join"`,
+    /// in debugging and profiling output, such as
+    /// [`RootCircuit::operator_stats`], which makes it possible to tell
+    /// apart multiple instances of the same operator.
+    ///
+    /// Many operators, including [`Stream::join`], are implemented using
+    /// several internal operators.  The label is also applied to operators
+    /// upstream of this stream whose outputs are only consumed by labeled
+    /// operators at the time this method is called.  Source operators and
+    /// operators that already have a label are not relabeled, and neither
+    /// are operators shared with other parts of the circuit, e.g., the
+    /// trace of an input stream used by several joins.
+    ///
+    /// Returns `self`, so the method can be chained with other operators.
+    pub fn with_name(&self, name: &str) -> Self {
+        self.circuit().set_label(self.local_node_id(), name);
+        self.clone()
+    }
+
     /// Export stream to the parent circuit.
     ///
     /// Creates a stream in the parent circuit that contains the last value in
@@ -787,9 +811,35 @@ pub trait Node {
 
     fn metadata(&self, output: &mut OperatorMeta);
 
+    /// Memory usage statistics of the operator (see
+    /// [`Operator::memory_stats`]).  Returns `None` for subcircuits.
+    fn memory_stats(&self) -> Option<&dyn MemoryStats> {
+        None
+    }
+
+    /// Serialized state of the operator (see [`Operator::snapshot`]).
+    /// Returns `None` for subcircuits.
+    fn snapshot(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Restore the state of the operator (see [`Operator::restore`]).
+    fn restore(&mut self, _snapshot: &[u8]) {}
+
+    /// Returns `false` if the node holds state that [`Self::snapshot`]
+    /// doesn't capture (see [`Operator::can_snapshot`]).
+    fn can_snapshot(&self) -> bool {
+        true
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool;
 
     fn map_nodes_recursive(&self, _f: &mut dyn FnMut(&dyn Node)) {}
+
+    /// User-defined label attached to the node using [`Stream::with_name`].
+    fn label(&self) -> Option<&str> {
+        None
+    }
 }
 
 /// Id of an operator, guaranteed to be unique within a circuit.
@@ -1122,6 +1172,10 @@ pub trait Circuit: WithClock + Clone + 'static {
     /// Check if `this` and `other` refer to the same circuit instance.
     fn ptr_eq(this: &Self, other: &Self) -> bool;
 
+    /// Attach `label` to node `id` and to nodes upstream of it that are
+    /// only consumed by labeled nodes (see [`Stream::with_name`]).
+    fn set_label(&self, id: NodeId, label: &str);
+
     /// Returns circuit event handlers attached to the circuit.
     fn circuit_event_handlers(&self) -> CircuitEventHandlers;
 
@@ -1150,6 +1204,16 @@ pub trait Circuit: WithClock + Clone + 'static {
     /// operators have been evaluated.
     fn tick(&self);
 
+    /// Logical time of the current clock cycle of the circuit: the number of
+    /// clock cycles the circuit has completed so far.
+    ///
+    /// While the circuit is being evaluated, returns the index of the current
+    /// step, starting from 0.  Unlike [`WithClock::time`], which is `()` for
+    /// the root circuit, this counter advances at every clock cycle of every
+    /// circuit.  The counter of a nested circuit is not reset when the parent
+    /// circuit starts a new clock cycle.
+    fn current_step(&self) -> u64;
+
     /// Deliver `clock_start` notification to all nodes in the circuit.
     fn clock_start(&self, scope: Scope);
 
@@ -1189,6 +1253,12 @@ pub trait Circuit: WithClock + Clone + 'static {
 
     fn is_async_node(&self, id: NodeId) -> bool;
 
+    /// Returns ids of feedback nodes created via
+    /// [`add_feedback`](`Self::add_feedback`) or
+    /// [`add_feedback_with_export`](`Self::add_feedback_with_export`) whose
+    /// input hasn't been connected.
+    fn unconnected_feedback(&self) -> Vec<NodeId>;
+
     /// Evaluate operator with the given id.
     ///
     /// This method should only be used by schedulers.
@@ -1733,6 +1803,9 @@ where
     circuit_event_handlers: CircuitEventHandlers,
     scheduler_event_handlers: SchedulerEventHandlers,
     store: CircuitCache,
+    // Feedback output nodes whose `FeedbackConnector` hasn't been connected
+    // yet.
+    unconnected_feedback: BTreeSet<NodeId>,
 }
 
 impl<P> CircuitInner<P>
@@ -1757,6 +1830,7 @@ where
             circuit_event_handlers,
             scheduler_event_handlers,
             store: TypedMap::new(),
+            unconnected_feedback: BTreeSet::new(),
         }
     }
 
@@ -1771,6 +1845,37 @@ where
         self.nodes.push(Box::new(node) as Box<dyn Node>);
     }
 
+    fn label_node(&mut self, id: NodeId, label: &str) {
+        let node = self.nodes.remove(id.0);
+        self.nodes.insert(
+            id.0,
+            Box::new(LabeledNode::new(node, label)) as Box<dyn Node>,
+        );
+    }
+
+    fn set_label(&mut self, id: NodeId, label: &str) {
+        self.label_node(id, label);
+
+        let mut labeled = BTreeSet::from([id]);
+
+        // Propagate the label upstream to unlabeled nodes whose consumers are
+        // all labeled.  Nodes without inputs are sources, which are never
+        // internal to another operator.
+        while let Some(from) = self.edges.iter().map(|edge| edge.from).find(|from| {
+            !labeled.contains(from)
+                && self.nodes[from.0].label().is_none()
+                && self.edges.iter().any(|edge| edge.to == *from)
+                && self
+                    .edges
+                    .iter()
+                    .filter(|edge| edge.from == *from)
+                    .all(|edge| labeled.contains(&edge.to))
+        }) {
+            self.label_node(from, label);
+            labeled.insert(from);
+        }
+    }
+
     fn clear(&mut self) {
         self.nodes.clear();
         self.edges.clear();
@@ -1844,6 +1949,10 @@ where
 {
     inner: Rc<RefCell<CircuitInner<P>>>,
     time: Rc<RefCell<<P::Time as Timestamp>::Nested>>,
+    // Number of clock cycles completed by the circuit (see
+    // `Circuit::current_step`).  Kept outside of `inner`, since it can be read
+    // by operators while the circuit is being evaluated.
+    step: Rc<Cell<u64>>,
 }
 
 /// Top-level circuit.
@@ -1870,6 +1979,7 @@ where
         Self {
             inner: self.inner.clone(),
             time: self.time.clone(),
+            step: self.step.clone(),
         }
     }
 }
@@ -1979,6 +2089,7 @@ impl RootCircuit {
                 Rc::new(RefCell::new(HashMap::new())),
             ))),
             time: Rc::new(RefCell::new(())),
+            step: Rc::new(Cell::new(0)),
         }
     }
 }
@@ -2044,6 +2155,99 @@ impl RootCircuit {
     pub fn unregister_scheduler_event_handler(&self, name: &str) -> bool {
         self.inner_mut().unregister_scheduler_event_handler(name)
     }
+
+    /// Collect memory usage statistics of all operators in the circuit,
+    /// including operators in nested circuits.
+    ///
+    /// Returns a map from the global id of each operator that implements
+    /// [`MemoryStats`] to its name and memory usage.  Operators that don't
+    /// track their memory usage are not included in the map.
+    pub fn operator_stats(&self) -> BTreeMap<GlobalNodeId, OperatorStats> {
+        let mut stats = BTreeMap::new();
+
+        self.map_nodes_recursive(&mut |node: &dyn Node| {
+            if let Some(memory_stats) = node.memory_stats() {
+                stats.insert(
+                    node.global_id().clone(),
+                    OperatorStats {
+                        name: node.name(),
+                        tuples: memory_stats.tuples(),
+                        bytes: memory_stats.bytes(),
+                    },
+                );
+            }
+        });
+
+        stats
+    }
+
+    /// Take a snapshot of the state of the circuit.
+    ///
+    /// The snapshot contains the state of operators that implement
+    /// [`Operator::snapshot`](`super::operator_traits::Operator::snapshot`),
+    /// e.g., the traces maintained by joins.  It can be restored into another
+    /// instance of the same circuit, built by the same code, using
+    /// [`Self::restore`].  This method must be called between steps.
+    ///
+    /// Fails with [`SchedulerError::SnapshotUnsupported`] if an operator holds
+    /// state that the snapshot can't capture, e.g., a
+    /// [`delay`](`crate::Stream::delay`) operator that has received a
+    /// non-zero input, or a nested circuit with stateful operators.
+    pub fn snapshot(&self) -> Result<CircuitSnapshot, SchedulerError> {
+        let inner = self.inner();
+
+        if let Some(node) = inner.nodes.iter().find(|node| !node.can_snapshot()) {
+            return Err(SchedulerError::SnapshotUnsupported {
+                node_id: node.global_id().clone(),
+                operator: node.name().into_owned(),
+            });
+        }
+
+        let operators = inner
+            .nodes
+            .iter()
+            .filter_map(|node| {
+                node.snapshot().map(|state| OperatorSnapshot {
+                    node_id: node.local_id().id(),
+                    name: node.name().into_owned(),
+                    state,
+                })
+            })
+            .collect();
+
+        Ok(CircuitSnapshot { operators })
+    }
+
+    /// Restore the state of the circuit from a snapshot produced by
+    /// [`Self::snapshot`].
+    ///
+    /// Operators not included in the snapshot keep their current state.
+    /// Fails with [`SchedulerError::SnapshotMismatch`] without modifying the
+    /// circuit if the snapshot refers to an operator that doesn't exist in
+    /// this circuit or has a different name, which indicates that the
+    /// snapshot was taken from a different circuit.
+    pub fn restore(&self, snapshot: &CircuitSnapshot) -> Result<(), SchedulerError> {
+        for operator in snapshot.operators.iter() {
+            let matches = match self.inner().nodes.get(operator.node_id) {
+                Some(node) => node.name() == operator.name.as_str(),
+                None => false,
+            };
+
+            if !matches {
+                return Err(SchedulerError::SnapshotMismatch {
+                    node_id: GlobalNodeId::child_of(self, NodeId(operator.node_id)),
+                    operator: operator.name.clone(),
+                });
+            }
+        }
+
+        let mut inner = self.inner_mut();
+        for operator in snapshot.operators.iter() {
+            inner.nodes[operator.node_id].restore(&operator.state);
+        }
+
+        Ok(())
+    }
 }
 
 impl<P> ChildCircuit<P>
@@ -2067,6 +2271,7 @@ where
                 sched_handlers,
             ))),
             time: Rc::new(RefCell::new(Timestamp::clock_start())),
+            step: Rc::new(Cell::new(0)),
         }
     }
 
@@ -2213,6 +2418,10 @@ where
         Rc::ptr_eq(&this.inner, &other.inner)
     }
 
+    fn set_label(&self, id: NodeId, label: &str) {
+        self.inner_mut().set_label(id, label);
+    }
+
     fn circuit_event_handlers(&self) -> CircuitEventHandlers {
         self.inner().circuit_event_handlers.clone()
     }
@@ -2274,6 +2483,11 @@ where
     fn tick(&self) {
         let mut time = self.time.borrow_mut();
         *time = time.advance(0);
+        self.step.set(self.step.get() + 1);
+    }
+
+    fn current_step(&self) -> u64 {
+        self.step.get()
     }
 
     fn clock_start(&self, scope: Scope) {
@@ -2327,6 +2541,10 @@ where
         self.inner().nodes[id.0].is_async()
     }
 
+    fn unconnected_feedback(&self) -> Vec<NodeId> {
+        self.inner().unconnected_feedback.iter().cloned().collect()
+    }
+
     fn eval_node(&self, id: NodeId) -> Result<(), SchedulerError> {
         let mut circuit = self.inner_mut();
         debug_assert!(id.0 < circuit.nodes.len());
@@ -2810,7 +3028,7 @@ where
         O: Data,
         Op: StrictUnaryOperator<I, O>,
     {
-        self.add_node(|id| {
+        let (local, connector) = self.add_node(|id| {
             self.log_circuit_event(&CircuitEvent::strict_operator_output(
                 GlobalNodeId::child_of(self, id),
                 operator.name(),
@@ -2822,7 +3040,12 @@ where
             let output_node = FeedbackOutputNode::new(operator, self.clone(), id);
             let local = output_node.output_stream();
             (output_node, (local, connector))
-        })
+        });
+
+        self.inner_mut()
+            .unconnected_feedback
+            .insert(connector.output_node_id);
+        (local, connector)
     }
 
     fn add_feedback_with_export<I, O, Op>(
@@ -2834,7 +3057,7 @@ where
         O: Data,
         Op: StrictUnaryOperator<I, O>,
     {
-        self.add_node(|id| {
+        let (export, connector) = self.add_node(|id| {
             self.log_circuit_event(&CircuitEvent::strict_operator_output(
                 GlobalNodeId::child_of(self, id),
                 operator.name(),
@@ -2847,7 +3070,12 @@ where
             let local = output_node.output_stream();
             let export = output_node.export_stream.clone().unwrap();
             (output_node, (ExportStream { local, export }, connector))
-        })
+        });
+
+        self.inner_mut()
+            .unconnected_feedback
+            .insert(connector.output_node_id);
+        (export, connector)
     }
 
     fn connect_feedback_with_preference<I, O, Op>(
@@ -2872,6 +3100,10 @@ where
             self.add_dependency(output_node_id, id);
             (output_node, ())
         });
+
+        self.inner_mut()
+            .unconnected_feedback
+            .remove(&output_node_id);
     }
 
     fn subcircuit<F, T, E>(
@@ -3080,6 +3312,21 @@ where
     }
 }
 
+/// Convert an error reported by `operator` after evaluation into a
+/// [`SchedulerError`].
+fn check_operator_error<Op>(id: &GlobalNodeId, operator: &mut Op) -> Result<(), SchedulerError>
+where
+    Op: Operator + ?Sized,
+{
+    match operator.take_error() {
+        Some(error) => Err(SchedulerError::OperatorError {
+            node_id: id.clone(),
+            error,
+        }),
+        None => Ok(()),
+    }
+}
+
 struct ImportNode<C, I, O, Op>
 where
     C: Circuit,
@@ -3145,7 +3392,7 @@ where
 
     unsafe fn eval(&mut self) -> Result<(), SchedulerError> {
         self.output_stream.put(self.operator.eval());
-        Ok(())
+        check_operator_error(&self.id, &mut self.operator)
     }
 
     fn clock_start(&mut self, scope: Scope) {
@@ -3166,6 +3413,22 @@ where
         self.operator.metadata(output);
     }
 
+    fn memory_stats(&self) -> Option<&dyn MemoryStats> {
+        self.operator.memory_stats()
+    }
+
+    fn snapshot(&self) -> Option<Vec<u8>> {
+        self.operator.snapshot()
+    }
+
+    fn restore(&mut self, snapshot: &[u8]) {
+        self.operator.restore(snapshot)
+    }
+
+    fn can_snapshot(&self) -> bool {
+        self.operator.can_snapshot()
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         self.operator.fixedpoint(scope)
     }
@@ -3227,7 +3490,7 @@ where
 
     unsafe fn eval(&mut self) -> Result<(), SchedulerError> {
         self.output_stream.put(self.operator.eval());
-        Ok(())
+        check_operator_error(&self.id, &mut self.operator)
     }
 
     fn clock_start(&mut self, scope: Scope) {
@@ -3242,6 +3505,22 @@ where
         self.operator.metadata(output);
     }
 
+    fn memory_stats(&self) -> Option<&dyn MemoryStats> {
+        self.operator.memory_stats()
+    }
+
+    fn snapshot(&self) -> Option<Vec<u8>> {
+        self.operator.snapshot()
+    }
+
+    fn restore(&mut self, snapshot: &[u8]) {
+        self.operator.restore(snapshot)
+    }
+
+    fn can_snapshot(&self) -> bool {
+        self.operator.can_snapshot()
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         self.operator.fixedpoint(scope)
     }
@@ -3309,7 +3588,7 @@ where
             Cow::Owned(v) => self.operator.eval_owned(v),
             Cow::Borrowed(v) => self.operator.eval(v),
         });
-        Ok(())
+        check_operator_error(&self.id, &mut self.operator)
     }
 
     fn clock_start(&mut self, scope: Scope) {
@@ -3324,6 +3603,22 @@ where
         self.operator.metadata(output);
     }
 
+    fn memory_stats(&self) -> Option<&dyn MemoryStats> {
+        self.operator.memory_stats()
+    }
+
+    fn snapshot(&self) -> Option<Vec<u8>> {
+        self.operator.snapshot()
+    }
+
+    fn restore(&mut self, snapshot: &[u8]) {
+        self.operator.restore(snapshot)
+    }
+
+    fn can_snapshot(&self) -> bool {
+        self.operator.can_snapshot()
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         self.operator.fixedpoint(scope)
     }
@@ -3384,7 +3679,7 @@ where
             Cow::Owned(v) => self.operator.eval_owned(v),
             Cow::Borrowed(v) => self.operator.eval(v),
         };
-        Ok(())
+        check_operator_error(&self.id, &mut self.operator)
     }
 
     fn clock_start(&mut self, scope: Scope) {
@@ -3399,6 +3694,22 @@ where
         self.operator.metadata(output);
     }
 
+    fn memory_stats(&self) -> Option<&dyn MemoryStats> {
+        self.operator.memory_stats()
+    }
+
+    fn snapshot(&self) -> Option<Vec<u8>> {
+        self.operator.snapshot()
+    }
+
+    fn restore(&mut self, snapshot: &[u8]) {
+        self.operator.restore(snapshot)
+    }
+
+    fn can_snapshot(&self) -> bool {
+        self.operator.can_snapshot()
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         self.operator.fixedpoint(scope)
     }
@@ -3479,7 +3790,7 @@ where
         let input2 = self.input_stream2.take();
 
         self.operator.eval(input1, input2);
-        Ok(())
+        check_operator_error(&self.id, &mut self.operator)
     }
 
     fn clock_start(&mut self, scope: Scope) {
@@ -3494,6 +3805,22 @@ where
         self.operator.metadata(output);
     }
 
+    fn memory_stats(&self) -> Option<&dyn MemoryStats> {
+        self.operator.memory_stats()
+    }
+
+    fn snapshot(&self) -> Option<Vec<u8>> {
+        self.operator.snapshot()
+    }
+
+    fn restore(&mut self, snapshot: &[u8]) {
+        self.operator.restore(snapshot)
+    }
+
+    fn can_snapshot(&self) -> bool {
+        self.operator.can_snapshot()
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         self.operator.fixedpoint(scope)
     }
@@ -3594,7 +3921,7 @@ where
                 },
             );
         }
-        Ok(())
+        check_operator_error(&self.id, &mut self.operator)
     }
 
     fn clock_start(&mut self, scope: Scope) {
@@ -3609,6 +3936,22 @@ where
         self.operator.metadata(output);
     }
 
+    fn memory_stats(&self) -> Option<&dyn MemoryStats> {
+        self.operator.memory_stats()
+    }
+
+    fn snapshot(&self) -> Option<Vec<u8>> {
+        self.operator.snapshot()
+    }
+
+    fn restore(&mut self, snapshot: &[u8]) {
+        self.operator.restore(snapshot)
+    }
+
+    fn can_snapshot(&self) -> bool {
+        self.operator.can_snapshot()
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         self.operator.fixedpoint(scope)
     }
@@ -3713,7 +4056,7 @@ where
 
         self.output_stream
             .put(self.operator.eval(input1, input2, input3));
-        Ok(())
+        check_operator_error(&self.id, &mut self.operator)
     }
 
     fn clock_start(&mut self, scope: Scope) {
@@ -3728,6 +4071,22 @@ where
         self.operator.metadata(output);
     }
 
+    fn memory_stats(&self) -> Option<&dyn MemoryStats> {
+        self.operator.memory_stats()
+    }
+
+    fn snapshot(&self) -> Option<Vec<u8>> {
+        self.operator.snapshot()
+    }
+
+    fn restore(&mut self, snapshot: &[u8]) {
+        self.operator.restore(snapshot)
+    }
+
+    fn can_snapshot(&self) -> bool {
+        self.operator.can_snapshot()
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         self.operator.fixedpoint(scope)
     }
@@ -3850,7 +4209,7 @@ where
 
         self.output_stream
             .put(self.operator.eval(input1, input2, input3, input4));
-        Ok(())
+        check_operator_error(&self.id, &mut self.operator)
     }
 
     fn clock_start(&mut self, scope: Scope) {
@@ -3865,6 +4224,22 @@ where
         self.operator.metadata(output);
     }
 
+    fn memory_stats(&self) -> Option<&dyn MemoryStats> {
+        self.operator.memory_stats()
+    }
+
+    fn snapshot(&self) -> Option<Vec<u8>> {
+        self.operator.snapshot()
+    }
+
+    fn restore(&mut self, snapshot: &[u8]) {
+        self.operator.restore(snapshot)
+    }
+
+    fn can_snapshot(&self) -> bool {
+        self.operator.can_snapshot()
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         self.operator.fixedpoint(scope)
     }
@@ -3972,7 +4347,7 @@ where
         for i in self.aliases.iter() {
             let _ = self.input_streams[*i].0.take();
         }
-        Ok(())
+        check_operator_error(&self.id, &mut self.operator)
     }
 
     fn clock_start(&mut self, scope: Scope) {
@@ -3987,6 +4362,22 @@ where
         self.operator.metadata(output);
     }
 
+    fn memory_stats(&self) -> Option<&dyn MemoryStats> {
+        self.operator.memory_stats()
+    }
+
+    fn snapshot(&self) -> Option<Vec<u8>> {
+        self.operator.snapshot()
+    }
+
+    fn restore(&mut self, snapshot: &[u8]) {
+        self.operator.restore(snapshot)
+    }
+
+    fn can_snapshot(&self) -> bool {
+        self.operator.can_snapshot()
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         self.operator.fixedpoint(scope)
     }
@@ -4071,7 +4462,7 @@ where
 
     unsafe fn eval(&mut self) -> Result<(), SchedulerError> {
         self.output_stream.put((*self.operator.get()).get_output());
-        Ok(())
+        check_operator_error(&self.id, &mut *self.operator.get())
     }
 
     fn clock_start(&mut self, scope: Scope) {
@@ -4091,6 +4482,24 @@ where
         unsafe { (*self.operator.get()).metadata(output) }
     }
 
+    fn memory_stats(&self) -> Option<&dyn MemoryStats> {
+        unsafe { (*self.operator.get()).memory_stats() }
+    }
+
+    // The operator is shared with the matching `FeedbackInputNode`, which
+    // doesn't report its state, so that it is only snapshotted once.
+    fn snapshot(&self) -> Option<Vec<u8>> {
+        unsafe { (*self.operator.get()).snapshot() }
+    }
+
+    fn restore(&mut self, snapshot: &[u8]) {
+        unsafe { (*self.operator.get()).restore(snapshot) }
+    }
+
+    fn can_snapshot(&self) -> bool {
+        unsafe { (*self.operator.get()).can_snapshot() }
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         unsafe { (*self.operator.get()).fixedpoint(scope) }
     }
@@ -4154,7 +4563,7 @@ where
             Cow::Owned(v) => (*self.operator.get()).eval_strict_owned(v),
             Cow::Borrowed(v) => (*self.operator.get()).eval_strict(v),
         };
-        Ok(())
+        check_operator_error(&self.id, &mut *self.operator.get())
     }
 
     // Don't call `clock_start`/`clock_end` on the operator.  `FeedbackOutputNode`
@@ -4167,6 +4576,10 @@ where
         unsafe { (*self.operator.get()).metadata(output) }
     }
 
+    fn memory_stats(&self) -> Option<&dyn MemoryStats> {
+        unsafe { (*self.operator.get()).memory_stats() }
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         unsafe { (*self.operator.get()).fixedpoint(scope) }
     }
@@ -4231,6 +4644,91 @@ where
     }
 }
 
+/// Node with a user-defined label that overrides the name of the inner node.
+struct LabeledNode {
+    label: String,
+    node: Box<dyn Node>,
+}
+
+impl LabeledNode {
+    fn new(node: Box<dyn Node>, label: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            node,
+        }
+    }
+}
+
+impl Node for LabeledNode {
+    fn local_id(&self) -> NodeId {
+        self.node.local_id()
+    }
+
+    fn global_id(&self) -> &GlobalNodeId {
+        self.node.global_id()
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Owned(self.label.clone())
+    }
+
+    fn is_async(&self) -> bool {
+        self.node.is_async()
+    }
+
+    fn ready(&self) -> bool {
+        self.node.ready()
+    }
+
+    fn register_ready_callback(&mut self, cb: Box<dyn Fn() + Send + Sync>) {
+        self.node.register_ready_callback(cb);
+    }
+
+    unsafe fn eval(&mut self) -> Result<(), SchedulerError> {
+        self.node.eval()
+    }
+
+    fn clock_start(&mut self, scope: Scope) {
+        self.node.clock_start(scope);
+    }
+
+    unsafe fn clock_end(&mut self, scope: Scope) {
+        self.node.clock_end(scope);
+    }
+
+    fn metadata(&self, output: &mut OperatorMeta) {
+        self.node.metadata(output);
+    }
+
+    fn memory_stats(&self) -> Option<&dyn MemoryStats> {
+        self.node.memory_stats()
+    }
+
+    fn snapshot(&self) -> Option<Vec<u8>> {
+        self.node.snapshot()
+    }
+
+    fn restore(&mut self, snapshot: &[u8]) {
+        self.node.restore(snapshot)
+    }
+
+    fn can_snapshot(&self) -> bool {
+        self.node.can_snapshot()
+    }
+
+    fn fixedpoint(&self, scope: Scope) -> bool {
+        self.node.fixedpoint(scope)
+    }
+
+    fn map_nodes_recursive(&self, f: &mut dyn FnMut(&dyn Node)) {
+        self.node.map_nodes_recursive(f);
+    }
+
+    fn label(&self) -> Option<&str> {
+        Some(&self.label)
+    }
+}
+
 // A nested circuit instantiated as a node in a parent circuit.
 struct ChildNode<P>
 where
@@ -4310,6 +4808,16 @@ where
         self.circuit.inner().fixedpoint(scope + 1)
     }
 
+    /// Snapshots don't include nested circuits, so the subcircuit can only be
+    /// captured if none of its operators hold any state.
+    fn can_snapshot(&self) -> bool {
+        let mut can_snapshot = true;
+        self.circuit.map_nodes_recursive(&mut |node: &dyn Node| {
+            can_snapshot &= node.can_snapshot() && node.snapshot().is_none();
+        });
+        can_snapshot
+    }
+
     fn map_nodes_recursive(&self, f: &mut dyn FnMut(&dyn Node)) {
         self.circuit.map_nodes_recursive(f);
     }
@@ -4360,6 +4868,33 @@ impl CircuitHandle {
         self.executor.run(&self.circuit)
     }
 
+    /// Like [`step`](`Self::step`), but aborts the step if it takes longer
+    /// than `timeout`.
+    ///
+    /// The timeout is enforced cooperatively by nested circuits, which check
+    /// it after every iteration and return
+    /// [`SchedulerError::StepTimeout`] once it has expired.  This protects
+    /// the caller from iterative computations that fail to converge.  The
+    /// outputs of an aborted step are undefined, but the circuit remains
+    /// usable and can be stepped again.
+    pub fn step_with_timeout(&self, timeout: Duration) -> Result<(), SchedulerError> {
+        self.step_with_deadline(Instant::now() + timeout, timeout)
+    }
+
+    /// Like [`step_with_timeout`](`Self::step_with_timeout`), but with an
+    /// absolute `deadline`, so that all workers of a multithreaded circuit
+    /// abort at the same time.  `timeout` is reported in the error.
+    pub(crate) fn step_with_deadline(
+        &self,
+        deadline: Instant,
+        timeout: Duration,
+    ) -> Result<(), SchedulerError> {
+        set_step_deadline(Some((deadline, timeout)));
+        let result = self.executor.run(&self.circuit);
+        set_step_deadline(None);
+        result
+    }
+
     /// Attach a scheduler event handler to the circuit.
     ///
     /// This method is identical to
@@ -4384,6 +4919,30 @@ impl CircuitHandle {
     pub fn unregister_scheduler_event_handler(&self, name: &str) -> bool {
         self.circuit.unregister_scheduler_event_handler(name)
     }
+
+    /// Collect memory usage statistics of all operators in the circuit.
+    ///
+    /// This method is identical to [`RootCircuit::operator_stats`], but it can
+    /// be used at runtime, between steps of the circuit.
+    pub fn operator_stats(&self) -> BTreeMap<GlobalNodeId, OperatorStats> {
+        self.circuit.operator_stats()
+    }
+
+    /// Take a snapshot of the state of the circuit.
+    ///
+    /// This method is identical to [`RootCircuit::snapshot`], but it can be
+    /// used at runtime, between steps of the circuit.
+    pub fn snapshot(&self) -> Result<CircuitSnapshot, SchedulerError> {
+        self.circuit.snapshot()
+    }
+
+    /// Restore the state of the circuit from a snapshot.
+    ///
+    /// This method is identical to [`RootCircuit::restore`], but it can be
+    /// used at runtime, between steps of the circuit.
+    pub fn restore(&self, snapshot: &CircuitSnapshot) -> Result<(), SchedulerError> {
+        self.circuit.restore(snapshot)
+    }
 }
 
 #[cfg(test)]
@@ -4392,10 +4951,16 @@ mod tests {
         circuit::schedule::{DynamicScheduler, Scheduler, StaticScheduler},
         monitor::TraceMonitor,
         operator::{Generator, Z1},
-        Circuit, Error as DBSPError, RootCircuit,
+        Circuit, Error as DBSPError, RootCircuit, SchedulerError,
     };
     use anyhow::anyhow;
-    use std::{cell::RefCell, ops::Deref, rc::Rc, vec::Vec};
+    use std::{
+        cell::{Cell, RefCell},
+        ops::Deref,
+        rc::Rc,
+        time::Duration,
+        vec::Vec,
+    };
 
     // Compute the sum of numbers from 0 to 99.
     #[test]
@@ -4566,6 +5131,40 @@ mod tests {
         }
     }
 
+    // A nested circuit that only converges when `converge` is set.
+    #[test]
+    fn step_with_timeout() {
+        let converge = Rc::new(Cell::new(false));
+        let converge_clone = converge.clone();
+
+        let circuit = RootCircuit::build(move |circuit| {
+            circuit
+                .iterate(|child| {
+                    let mut n: usize = 0;
+                    child.add_source(Generator::new(move || {
+                        n += 1;
+                        n
+                    }));
+                    Ok((move || Ok(converge_clone.get()), ()))
+                })
+                .unwrap();
+            Ok(())
+        })
+        .unwrap()
+        .0;
+
+        let timeout = Duration::from_millis(50);
+        assert_eq!(
+            circuit.step_with_timeout(timeout),
+            Err(SchedulerError::StepTimeout { timeout })
+        );
+
+        // The circuit can be stepped again once the iteration converges.
+        converge.set(true);
+        circuit.step_with_timeout(timeout).unwrap();
+        circuit.step().unwrap();
+    }
+
     #[test]
     fn init_circuit_constructor_error() {
         match RootCircuit::build(|_circuit| Err::<(), _>(anyhow!("constructor failed"))) {
@@ -4,7 +4,7 @@ use crate::{
 };
 use anyhow::Error as AnyError;
 use core::fmt;
-use crossbeam::channel::{bounded, Receiver, Sender, TryRecvError};
+use crossbeam::channel::{bounded, Receiver, Select, Sender, TryRecvError};
 use itertools::Either;
 use serde::{Deserialize, Serialize};
 use std::{
@@ -18,7 +18,7 @@ use std::{
     ops::Range,
     path::{Path, PathBuf},
     thread::Result as ThreadResult,
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 #[cfg(doc)]
@@ -307,9 +307,15 @@ impl Runtime {
             while !Runtime::kill_in_progress() {
                 // Wait for command.
                 match command_receiver.try_recv() {
-                    Ok(Command::Step) => {
+                    Ok(Command::Step(deadline)) => {
                         //moregc = true;
-                        let status = circuit.step().map(|_| Response::Unit);
+                        let status = match deadline {
+                            None => circuit.step(),
+                            Some((deadline, timeout)) => {
+                                circuit.step_with_deadline(deadline, timeout)
+                            }
+                        }
+                        .map(|_| Response::Unit);
                         // Send response.
                         if status_sender.send(status).is_err() {
                             return;
@@ -388,7 +394,9 @@ impl Runtime {
 
 #[derive(Clone)]
 enum Command {
-    Step,
+    /// Evaluate the circuit, aborting the step at the given deadline, if any,
+    /// computed from the given time budget.
+    Step(Option<(Instant, Duration)>),
     EnableProfiler,
     DumpProfile,
 }
@@ -448,9 +456,22 @@ impl DBSPHandle {
             self.runtime.as_ref().unwrap().unpark_worker(worker);
         }
 
-        // Receive responses.
-        for (worker, receiver) in self.status_receivers.iter().enumerate() {
-            match receiver.recv() {
+        // Receive responses in the order in which workers complete, so that an
+        // error reported by one worker kills the runtime even if other
+        // workers are blocked waiting for it.
+        let mut responses: Vec<Option<Response>> =
+            (0..self.status_receivers.len()).map(|_| None).collect();
+        let mut select = Select::new();
+        for receiver in self.status_receivers.iter() {
+            select.recv(receiver);
+        }
+
+        for _ in 0..responses.len() {
+            let operation = select.select();
+            let worker = operation.index();
+            select.remove(worker);
+
+            match operation.recv(&self.status_receivers[worker]) {
                 Err(_) => {
                     let _ = self.kill_inner();
                     return Err(DBSPError::Runtime(RuntimeError::WorkerPanic { worker }));
@@ -459,16 +480,36 @@ impl DBSPHandle {
                     let _ = self.kill_inner();
                     return Err(DBSPError::Scheduler(e));
                 }
-                Ok(Ok(resp)) => handler(resp),
+                Ok(Ok(resp)) => responses[worker] = Some(resp),
             }
         }
 
+        for resp in responses {
+            handler(resp.unwrap());
+        }
+
         Ok(())
     }
 
     /// Evaluate the circuit for one clock cycle.
     pub fn step(&mut self) -> Result<(), DBSPError> {
-        self.broadcast_command(Command::Step, |_| {})
+        self.broadcast_command(Command::Step(None), |_| {})
+    }
+
+    /// Like [`step`](`Self::step`), but aborts the step if it takes longer
+    /// than `timeout`.
+    ///
+    /// All workers share the same deadline, which nested circuits check after
+    /// every iteration (see
+    /// [`CircuitHandle::step_with_timeout`](`crate::CircuitHandle::step_with_timeout`)).
+    /// When the deadline expires, returns
+    /// [`SchedulerError::StepTimeout`] and, as with any other error
+    /// reported by a worker, terminates the runtime.
+    pub fn step_with_timeout(&mut self, timeout: Duration) -> Result<(), DBSPError> {
+        self.broadcast_command(
+            Command::Step(Some((Instant::now() + timeout, timeout))),
+            |_| {},
+        )
     }
 
     /// Enable CPU profiler.
@@ -536,8 +577,12 @@ impl Drop for DBSPHandle {
 
 #[cfg(test)]
 mod tests {
-    use crate::{operator::Generator, Circuit, Error as DBSPError, Runtime, RuntimeError};
+    use crate::{
+        operator::{FilterMap, Generator},
+        zset, Circuit, Error as DBSPError, OrdZSet, Runtime, RuntimeError, SchedulerError, Stream,
+    };
     use anyhow::anyhow;
+    use std::time::Duration;
 
     // Panic during initialization in worker thread.
     #[test]
@@ -656,4 +701,28 @@ mod tests {
             _ => panic!(),
         }
     }
+
+    // A recursive query that never converges, since every iteration derives
+    // a new number.  Workers exchange data at every iteration and agree on
+    // the fixed point, so they must all abort at the same time.
+    #[test]
+    fn test_step_with_timeout() {
+        let (mut handle, _) = Runtime::init_circuit(4, |circuit| {
+            let seed = circuit.add_source(Generator::new(|| zset! { 0u64 => 1 }));
+
+            circuit
+                .recursive(|child, numbers: Stream<_, OrdZSet<u64, isize>>| {
+                    Ok(seed.delta0(child).plus(&numbers.map(|n| n + 1)))
+                })
+                .unwrap();
+            Ok(())
+        })
+        .unwrap();
+
+        let timeout = Duration::from_millis(100);
+        assert!(matches!(
+            handle.step_with_timeout(timeout),
+            Err(DBSPError::Scheduler(SchedulerError::StepTimeout { timeout: t })) if t == timeout
+        ));
+    }
 }
@@ -13,6 +13,18 @@ pub type OperatorLocation = Option<&'static Location<'static>>;
 /// The label to a metadata item
 pub type MetaLabel = Cow<'static, str>;
 
+/// Memory usage of an operator, as reported by
+/// [`RootCircuit::operator_stats`](`crate::RootCircuit::operator_stats`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperatorStats {
+    /// Operator name.
+    pub name: Cow<'static, str>,
+    /// The number of tuples stored by the operator.
+    pub tuples: usize,
+    /// The number of bytes allocated by the operator.
+    pub bytes: usize,
+}
+
 /// General metadata about an operator's execution
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct OperatorMeta {
@@ -25,6 +25,7 @@ pub mod cache;
 pub mod circuit_builder;
 pub mod operator_traits;
 pub mod schedule;
+pub mod snapshot;
 pub mod trace;
 
 pub use activations::{Activations, Activator};
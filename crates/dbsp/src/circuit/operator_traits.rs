@@ -145,6 +145,53 @@ pub trait Operator: 'static {
     {
     }
 
+    /// Returns an error encountered by the operator during the most recent
+    /// evaluation, if any, and clears it.
+    ///
+    /// Operators cannot return errors from their `eval` methods directly.
+    /// Instead, an operator that fails to produce an output records the error
+    /// and yields a placeholder output value.  The circuit invokes this method
+    /// after each evaluation of the operator and aborts the current step with
+    /// [`SchedulerError::OperatorError`](`crate::SchedulerError::OperatorError`)
+    /// if it returns an error.
+    fn take_error(&mut self) -> Option<String> {
+        None
+    }
+
+    /// Returns the memory usage statistics of the operator, if it
+    /// implements [`MemoryStats`].
+    ///
+    /// Stateful operators that implement [`MemoryStats`] should override
+    /// this method to return `Some(self)`.  Statistics of all such operators
+    /// in a circuit are collected by
+    /// [`RootCircuit::operator_stats`](`crate::RootCircuit::operator_stats`).
+    fn memory_stats(&self) -> Option<&dyn MemoryStats> {
+        None
+    }
+
+    /// Serialize the state of the operator, so that it can be restored into
+    /// an instance of the same operator in a new circuit using
+    /// [`Self::restore`].
+    ///
+    /// Returns `None` for stateless operators.  The circuit only calls this
+    /// method between steps (see
+    /// [`RootCircuit::snapshot`](`crate::RootCircuit::snapshot`)).
+    fn snapshot(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Replace the state of the operator with a snapshot produced by
+    /// [`Self::snapshot`].
+    fn restore(&mut self, _snapshot: &[u8]) {}
+
+    /// Returns `false` if the operator currently holds state that
+    /// [`Self::snapshot`] doesn't capture, in which case
+    /// [`RootCircuit::snapshot`](`crate::RootCircuit::snapshot`) fails rather
+    /// than producing an incomplete snapshot.
+    fn can_snapshot(&self) -> bool {
+        true
+    }
+
     /// Check if the operator is in a stable state.
     ///
     /// This method is invoked as part of checking if the circuit has reached a
@@ -196,6 +243,15 @@ pub trait Operator: 'static {
     fn fixedpoint(&self, scope: Scope) -> bool;
 }
 
+/// Operators that hold state in memory and can report its size.
+pub trait MemoryStats {
+    /// The number of tuples stored by the operator.
+    fn tuples(&self) -> usize;
+
+    /// The number of bytes allocated by the operator to store its state.
+    fn bytes(&self) -> usize;
+}
+
 /// A source operator that injects data from the outside world or from the
 /// parent circuit into the local circuit.  Consumes no input streams and emits
 /// a single output stream.
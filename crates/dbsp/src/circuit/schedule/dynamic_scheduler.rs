@@ -49,7 +49,7 @@ use std::{
 use crate::circuit::{
     runtime::Runtime,
     schedule::{
-        util::{circuit_graph, ownership_constraints},
+        util::{check_feedback_connected, circuit_graph, ownership_constraints},
         Error, Scheduler,
     },
     trace::SchedulerEvent,
@@ -231,6 +231,8 @@ impl Inner {
     where
         C: Circuit,
     {
+        check_feedback_connected(circuit)?;
+
         // Check that ownership constraints don't introduce cycles.
         let mut g = circuit_graph(circuit);
 
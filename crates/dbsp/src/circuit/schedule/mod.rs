@@ -6,9 +6,11 @@ use itertools::Itertools;
 use serde::Serialize;
 use std::{
     borrow::Cow,
+    cell::Cell,
     error::Error as StdError,
     fmt::{Display, Error as FmtError, Formatter},
     string::ToString,
+    time::{Duration, Instant},
 };
 
 mod static_scheduler;
@@ -33,6 +35,34 @@ pub enum Error {
     /// Execution of the circuit interrupted by the user (via
     /// [`RuntimeHandle::kill`](`crate::circuit::RuntimeHandle::kill`)).
     Killed,
+    /// Operator `node_id` reported an error during evaluation (see
+    /// [`Operator::take_error`](`crate::circuit::operator_traits::Operator::take_error`)).
+    OperatorError {
+        node_id: GlobalNodeId,
+        error: String,
+    },
+    /// The step did not complete within the time budget passed to
+    /// [`CircuitHandle::step_with_timeout`](`crate::CircuitHandle::step_with_timeout`).
+    StepTimeout { timeout: Duration },
+    /// Feedback operator `node_id` was created, e.g., via
+    /// [`DelayedFeedback::new`](`crate::operator::DelayedFeedback::new`),
+    /// but its input was never connected.
+    UnconnectedFeedback { node_id: GlobalNodeId },
+    /// The snapshot passed to
+    /// [`RootCircuit::restore`](`crate::RootCircuit::restore`) contains the
+    /// state of `operator` for node `node_id`, which doesn't exist or is a
+    /// different operator in this circuit.
+    SnapshotMismatch {
+        node_id: GlobalNodeId,
+        operator: String,
+    },
+    /// [`RootCircuit::snapshot`](`crate::RootCircuit::snapshot`) failed
+    /// because `operator` at node `node_id` holds state that can't be
+    /// captured in a snapshot.
+    SnapshotUnsupported {
+        node_id: GlobalNodeId,
+        operator: String,
+    },
 }
 
 impl DetailedError for Error {
@@ -41,6 +71,11 @@ impl DetailedError for Error {
             Self::OwnershipConflict { .. } => Cow::from("OwnershipConflict"),
             Self::CyclicCircuit { .. } => Cow::from("CyclicCircuit"),
             Self::Killed => Cow::from("Killed"),
+            Self::OperatorError { .. } => Cow::from("OperatorError"),
+            Self::StepTimeout { .. } => Cow::from("StepTimeout"),
+            Self::UnconnectedFeedback { .. } => Cow::from("UnconnectedFeedback"),
+            Self::SnapshotMismatch { .. } => Cow::from("SnapshotMismatch"),
+            Self::SnapshotUnsupported { .. } => Cow::from("SnapshotUnsupported"),
         }
     }
 }
@@ -56,12 +91,59 @@ impl Display for Error {
                 write!(f, "unschedulable circuit due to a cyclic topology: cycle through node '{node_id}'")
             }
             Self::Killed => f.write_str("circuit has been killed by the user"),
+            Self::OperatorError { node_id, error } => {
+                write!(f, "error evaluating operator '{node_id}': {error}")
+            }
+            Self::StepTimeout { timeout } => {
+                write!(f, "circuit step did not complete within {timeout:?}")
+            }
+            Self::UnconnectedFeedback { node_id } => {
+                write!(
+                    f,
+                    "input of feedback operator '{node_id}' was never connected"
+                )
+            }
+            Self::SnapshotMismatch { node_id, operator } => {
+                write!(
+                    f,
+                    "snapshot of operator '{operator}' cannot be restored into node '{node_id}'"
+                )
+            }
+            Self::SnapshotUnsupported { node_id, operator } => {
+                write!(
+                    f,
+                    "state of operator '{operator}' at node '{node_id}' cannot be captured in a snapshot"
+                )
+            }
         }
     }
 }
 
 impl StdError for Error {}
 
+thread_local! {
+    /// Deadline and time budget of the current step of the root circuit
+    /// evaluated by this thread, if any.
+    static STEP_DEADLINE: Cell<Option<(Instant, Duration)>> = Cell::new(None);
+}
+
+/// Set the deadline for the current step of the root circuit evaluated by
+/// this thread, along with the time budget it was computed from.  `None`
+/// removes the deadline.
+pub(crate) fn set_step_deadline(deadline: Option<(Instant, Duration)>) {
+    STEP_DEADLINE.with(|step_deadline| step_deadline.set(deadline));
+}
+
+/// Returns an error if the deadline of the current step has passed.
+fn check_step_deadline() -> Result<(), Error> {
+    match STEP_DEADLINE.with(Cell::get) {
+        Some((deadline, timeout)) if Instant::now() >= deadline => {
+            Err(Error::StepTimeout { timeout })
+        }
+        _ => Ok(()),
+    }
+}
+
 /// A scheduler defines the order in which nodes in a circuit are evaluated at
 /// runtime.
 ///
@@ -152,6 +234,15 @@ where
             if (self.termination_check)()? {
                 break;
             }
+
+            // Abort an iteration that does not converge within the time budget
+            // of the step.  We still end the clock epoch, so that the nested
+            // circuit starts from a clean state at the next step.
+            if let Err(error) = check_step_deadline() {
+                circuit.log_scheduler_event(&SchedulerEvent::clock_end());
+                circuit.clock_end(0);
+                return Err(error);
+            }
         }
 
         circuit.log_scheduler_event(&SchedulerEvent::clock_end());
@@ -216,6 +307,27 @@ mod util {
         g
     }
 
+    /// Check that the inputs of all feedback operators in the circuit have
+    /// been connected.
+    ///
+    /// # Errors
+    ///
+    /// The function fails with [`Error::UnconnectedFeedback`] if the circuit
+    /// contains a feedback operator whose input was never connected, e.g., a
+    /// [`DelayedFeedback`](`crate::operator::DelayedFeedback`) that was
+    /// dropped without calling `connect`.
+    pub(crate) fn check_feedback_connected<C>(circuit: &C) -> Result<(), Error>
+    where
+        C: Circuit,
+    {
+        match circuit.unconnected_feedback().first() {
+            Some(node_id) => Err(Error::UnconnectedFeedback {
+                node_id: GlobalNodeId::child_of(circuit, *node_id),
+            }),
+            None => Ok(()),
+        }
+    }
+
     /// Helper function used by schedulers to enforce ownership preferences.
     ///
     /// Individual schedulers can implement their own algorithms to enforce (or
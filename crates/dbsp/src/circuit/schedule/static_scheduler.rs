@@ -3,7 +3,7 @@
 use crate::circuit::{
     runtime::Runtime,
     schedule::{
-        util::{circuit_graph, ownership_constraints},
+        util::{check_feedback_connected, circuit_graph, ownership_constraints},
         Error, Scheduler,
     },
     trace::SchedulerEvent,
@@ -27,6 +27,8 @@ impl Scheduler for StaticScheduler {
     where
         C: Circuit,
     {
+        check_feedback_connected(circuit)?;
+
         let mut g = circuit_graph(circuit);
 
         // Add ownership constraints to the graph.
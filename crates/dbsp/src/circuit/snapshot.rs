@@ -0,0 +1,223 @@
+//! Snapshots of the state of a circuit.
+//!
+//! A [`CircuitSnapshot`] captures the state of the stateful operators of a
+//! circuit between steps, e.g., the traces that join operators use to match
+//! new updates against the updates received at earlier steps.  Restoring the
+//! snapshot into a freshly built instance of the same circuit with
+//! [`RootCircuit::restore`](`crate::RootCircuit::restore`) lets it continue
+//! from where the original circuit left off without replaying its inputs.
+
+use crate::trace::{cursor::Cursor, unaligned_deserialize, Batch, Trace};
+use rkyv::{Archive, Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Serialized state of a single operator.
+#[derive(Clone, Debug, PartialEq, Eq, Archive, Serialize, Deserialize)]
+pub struct OperatorSnapshot {
+    /// Id of the operator within the root circuit (see
+    /// [`NodeId::id`](`crate::circuit::NodeId::id`)).
+    pub node_id: usize,
+    /// Operator name, used to check that the snapshot is restored into the
+    /// same operator.
+    pub name: String,
+    /// Operator state, as returned by
+    /// [`Operator::snapshot`](`crate::circuit::operator_traits::Operator::snapshot`).
+    pub state: Vec<u8>,
+}
+
+/// Serialized state of the stateful operators of a circuit, as returned by
+/// [`RootCircuit::snapshot`](`crate::RootCircuit::snapshot`).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Archive, Serialize, Deserialize)]
+pub struct CircuitSnapshot {
+    /// Snapshots of individual operators, in the order of their node ids.
+    pub operators: Vec<OperatorSnapshot>,
+}
+
+impl CircuitSnapshot {
+    /// Serialize the snapshot with `rkyv`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        rkyv::to_bytes::<_, 1024>(self).unwrap().into_vec()
+    }
+
+    /// Deserialize a snapshot produced by [`Self::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        unaligned_deserialize(bytes)
+    }
+}
+
+/// A single update in a serialized trace.
+///
+/// `Spine` doesn't support `rkyv` serialization, so traces are serialized
+/// as a list of updates, each of whose components is serialized separately.
+#[derive(Archive, Serialize, Deserialize)]
+struct UpdateSnapshot {
+    key: Vec<u8>,
+    val: Vec<u8>,
+    time: Vec<u8>,
+    weight: Vec<u8>,
+}
+
+/// Serialize the contents of `trace`, including timestamps.
+pub(crate) fn trace_to_bytes<T>(trace: &T) -> Vec<u8>
+where
+    T: Trace,
+{
+    let mut updates = Vec::new();
+
+    let mut cursor = trace.cursor();
+    while cursor.key_valid() {
+        let key = rkyv::to_bytes::<_, 1024>(cursor.key()).unwrap().into_vec();
+        while cursor.val_valid() {
+            let val = rkyv::to_bytes::<_, 1024>(cursor.val()).unwrap().into_vec();
+            cursor.map_times(|time, weight| {
+                updates.push(UpdateSnapshot {
+                    key: key.clone(),
+                    val: val.clone(),
+                    time: rkyv::to_bytes::<_, 1024>(time).unwrap().into_vec(),
+                    weight: rkyv::to_bytes::<_, 1024>(weight).unwrap().into_vec(),
+                });
+            });
+            cursor.step_val();
+        }
+        cursor.step_key();
+    }
+
+    rkyv::to_bytes::<_, 1024>(&updates).unwrap().into_vec()
+}
+
+/// Rebuild a trace serialized by [`trace_to_bytes`].
+pub(crate) fn trace_from_bytes<T>(bytes: &[u8]) -> T
+where
+    T: Trace,
+{
+    let updates: Vec<UpdateSnapshot> = unaligned_deserialize(bytes);
+
+    let mut batches = BTreeMap::<T::Time, Vec<_>>::new();
+    for update in updates {
+        let key = unaligned_deserialize(&update.key);
+        let val = unaligned_deserialize(&update.val);
+        batches
+            .entry(unaligned_deserialize(&update.time))
+            .or_default()
+            .push((
+                T::Batch::item_from(key, val),
+                unaligned_deserialize(&update.weight),
+            ));
+    }
+
+    let mut trace = T::new(None);
+    for (time, tuples) in batches {
+        trace.insert(T::Batch::from_tuples(time, tuples));
+    }
+    trace
+}
+
+#[cfg(test)]
+mod test {
+    use super::CircuitSnapshot;
+    use crate::{
+        indexed_zset, CircuitHandle, CollectionHandle, OrdIndexedZSet, OutputHandle, RootCircuit,
+        SchedulerError,
+    };
+
+    type JoinCircuit = (
+        CircuitHandle,
+        CollectionHandle<u64, (u64, isize)>,
+        CollectionHandle<u64, (u64, isize)>,
+        OutputHandle<OrdIndexedZSet<u64, (u64, u64), isize>>,
+    );
+
+    fn join_circuit() -> JoinCircuit {
+        let (circuit, (left_handle, right_handle, output)) = RootCircuit::build(move |circuit| {
+            let (left, left_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+            let (right, right_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+
+            let output = left
+                .join_index(&right, |k, v1, v2| Some((*k, (*v1, *v2))))
+                .output();
+
+            Ok((left_handle, right_handle, output))
+        })
+        .unwrap();
+
+        (circuit, left_handle, right_handle, output)
+    }
+
+    #[test]
+    fn snapshot_restore_test() {
+        let (circuit, left_handle, right_handle, output) = join_circuit();
+
+        left_handle.append(&mut vec![(1, (10, 1)), (2, (20, 1))]);
+        right_handle.append(&mut vec![(1, (100, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), indexed_zset! {1 => {(10, 100) => 1}});
+
+        right_handle.append(&mut vec![(3, (300, 1))]);
+        circuit.step().unwrap();
+
+        let snapshot = CircuitSnapshot::from_bytes(&circuit.snapshot().unwrap().to_bytes());
+        assert!(!snapshot.operators.is_empty());
+
+        let (restored, restored_left, restored_right, restored_output) = join_circuit();
+        restored.restore(&snapshot).unwrap();
+
+        // New updates join with the state accumulated before the snapshot
+        // the same way in both circuits.
+        for circuit_output in [
+            (&circuit, &left_handle, &right_handle, &output),
+            (&restored, &restored_left, &restored_right, &restored_output),
+        ] {
+            let (circuit, left_handle, right_handle, output) = circuit_output;
+
+            left_handle.append(&mut vec![(1, (11, 1)), (3, (30, 1))]);
+            right_handle.append(&mut vec![(2, (200, 1)), (1, (100, -1))]);
+            circuit.step().unwrap();
+            assert_eq!(
+                output.consolidate(),
+                indexed_zset! {
+                    1 => {(10, 100) => -1},
+                    2 => {(20, 200) => 1},
+                    3 => {(30, 300) => 1}
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn restore_mismatch_test() {
+        let (circuit, left_handle, _right_handle, _output) = join_circuit();
+        left_handle.append(&mut vec![(1, (10, 1))]);
+        circuit.step().unwrap();
+
+        let mut snapshot = circuit.snapshot().unwrap();
+        snapshot.operators[0].name = "NotAnOperator".to_string();
+
+        let (restored, ..) = join_circuit();
+        assert!(matches!(
+            restored.restore(&snapshot),
+            Err(SchedulerError::SnapshotMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn snapshot_unsupported_test() {
+        let (circuit, input_handle) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) = circuit.add_input_zset::<u64, isize>();
+            input.integrate();
+            Ok(input_handle)
+        })
+        .unwrap();
+
+        // The integral is still zero, so there is no state to lose.
+        circuit.snapshot().unwrap();
+
+        // The `Z^-1` operator inside `integrate` now holds the integral, which
+        // the snapshot can't capture.
+        input_handle.push(1, 1);
+        circuit.step().unwrap();
+        assert!(matches!(
+            circuit.snapshot(),
+            Err(SchedulerError::SnapshotUnsupported { operator, .. }) if operator == "Z^-1"
+        ));
+    }
+}
@@ -3,11 +3,20 @@
 use std::hash::{Hash, Hasher};
 use xxhash_rust::xxh3::Xxh3;
 
-const SEED: u64 = 0x7f95_ef85_be33_c337u64;
+/// Seed used by [`default_hash`].
+pub const DEFAULT_HASH_SEED: u64 = 0x7f95_ef85_be33_c337u64;
 
 /// Default hashing function used to shard records across workers.
 pub fn default_hash<T: Hash>(x: &T) -> u64 {
-    let mut hasher = Xxh3::with_seed(SEED);
+    seeded_hash(x, DEFAULT_HASH_SEED)
+}
+
+/// Hash `x` with a fixed `seed`.
+///
+/// The result only depends on `x` and `seed`, so it is stable across runs
+/// and machines.
+pub fn seeded_hash<T: Hash>(x: &T, seed: u64) -> u64 {
+    let mut hasher = Xxh3::with_seed(seed);
     x.hash(&mut hasher);
     hasher.finish()
 }
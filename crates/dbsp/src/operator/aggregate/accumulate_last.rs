@@ -0,0 +1,121 @@
+use crate::{
+    algebra::{UnimplementedSemigroup, ZRingValue},
+    circuit::WithClock,
+    operator::aggregate::Aggregator,
+    trace::Cursor,
+    Circuit, DBData, DBTimestamp, OrdIndexedZSet, Stream, Timestamp,
+};
+use std::{collections::VecDeque, marker::PhantomData};
+
+/// An [aggregator](`crate::operator::Aggregator`) that applies a
+/// user-supplied reducer to the `k` largest values with positive weight.
+///
+/// Values are scanned in ascending order and collected into a bounded
+/// window: once the window holds `k` values, each new value evicts the
+/// oldest, i.e., smallest, one.  The reducer receives the window ordered
+/// from the oldest to the most recent value.  Values are included once
+/// regardless of their weights.
+#[derive(Clone)]
+pub struct AccumulateLast<V, F> {
+    k: usize,
+    reducer: F,
+    _type: PhantomData<fn(&V)>,
+}
+
+impl<V, F> AccumulateLast<V, F> {
+    /// Create an aggregator over the last `k` values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is 0.
+    pub fn new(k: usize, reducer: F) -> Self {
+        assert_ne!(k, 0);
+
+        Self {
+            k,
+            reducer,
+            _type: PhantomData,
+        }
+    }
+}
+
+impl<V, T, R, O, F> Aggregator<V, T, R> for AccumulateLast<V, F>
+where
+    V: DBData,
+    T: Timestamp,
+    R: ZRingValue,
+    O: DBData,
+    F: Fn(&VecDeque<V>) -> O + Clone + 'static,
+{
+    type Accumulator = O;
+    type Output = O;
+    // Windows of two subsets of values can't be combined without knowing
+    // the values evicted from them.
+    type Semigroup = UnimplementedSemigroup<O>;
+
+    fn aggregate<C>(&self, cursor: &mut C) -> Option<Self::Accumulator>
+    where
+        C: Cursor<V, (), T, R>,
+    {
+        let mut window = VecDeque::with_capacity(self.k);
+
+        while cursor.key_valid() {
+            let weight = cursor.fold_times(R::zero(), |mut acc, _, weight| {
+                acc.add_assign_by_ref(weight);
+                acc
+            });
+
+            if !weight.le0() {
+                if window.len() == self.k {
+                    window.pop_front();
+                }
+                window.push_back(cursor.key().clone());
+            }
+
+            cursor.step_key();
+        }
+
+        if window.is_empty() {
+            None
+        } else {
+            Some((self.reducer)(&window))
+        }
+    }
+
+    fn finalize(&self, accumulator: Self::Accumulator) -> Self::Output {
+        accumulator
+    }
+}
+
+impl<C, K, V, R> Stream<C, OrdIndexedZSet<K, V, R>>
+where
+    C: Circuit,
+    <C as WithClock>::Time: DBTimestamp,
+    K: DBData,
+    V: DBData,
+    R: DBData + ZRingValue,
+{
+    /// Incrementally apply `reducer` to the `k` most recent values of each
+    /// key.
+    ///
+    /// Values are ordered by their natural order, so the most recent values
+    /// are the largest ones, e.g., values of the form `(timestamp, x)` are
+    /// ordered by timestamp.  For each key, `reducer` receives a window of
+    /// up to `k` values ordered from the oldest to the most recent one, and
+    /// the operator outputs changes to the result of the reducer.  When a
+    /// new value arrives, the oldest value in the window ages out; when a
+    /// value in the window is retracted, the next older value takes its
+    /// place.  This generalizes [`lag`](`Stream::lag`) and is useful for,
+    /// e.g., moving averages.  See [`AccumulateLast`] for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is 0.
+    pub fn accumulate_last<O, F>(&self, k: usize, reducer: F) -> Stream<C, OrdIndexedZSet<K, O, R>>
+    where
+        O: DBData,
+        F: Fn(&VecDeque<V>) -> O + Clone + 'static,
+    {
+        self.aggregate(AccumulateLast::new(k, reducer))
+    }
+}
@@ -0,0 +1,93 @@
+use crate::{
+    algebra::{MonoidValue, UnimplementedSemigroup, ZRingValue},
+    circuit::WithClock,
+    operator::aggregate::Aggregator,
+    trace::Cursor,
+    Circuit, DBData, DBTimestamp, OrdIndexedZSet, Stream, Timestamp,
+};
+use std::cmp::Ordering;
+
+/// An [aggregator](`crate::operator::Aggregator`) that returns the value
+/// with non-zero weight that is smallest according to a user-supplied
+/// comparator.
+///
+/// Ties are broken in favor of the value that is smaller in the natural
+/// order of the value type.  Unlike [`Min`](`crate::operator::Min`), which
+/// stops at the first value with non-zero weight, this aggregator scans all
+/// values of the key.
+#[derive(Clone)]
+pub struct ArgMinBy<F> {
+    compare: F,
+}
+
+impl<F> ArgMinBy<F> {
+    pub fn new(compare: F) -> Self {
+        Self { compare }
+    }
+}
+
+impl<V, T, R, F> Aggregator<V, T, R> for ArgMinBy<F>
+where
+    V: DBData,
+    T: Timestamp,
+    R: MonoidValue,
+    F: Fn(&V, &V) -> Ordering + Clone + 'static,
+{
+    type Accumulator = V;
+    type Output = V;
+    // The comparator isn't available to a static semigroup implementation.
+    type Semigroup = UnimplementedSemigroup<V>;
+
+    fn aggregate<C>(&self, cursor: &mut C) -> Option<Self::Accumulator>
+    where
+        C: Cursor<V, (), T, R>,
+    {
+        let mut result: Option<V> = None;
+
+        while cursor.key_valid() {
+            let weight = cursor.fold_times(R::zero(), |mut acc, _, weight| {
+                acc.add_assign_by_ref(weight);
+                acc
+            });
+
+            if !weight.is_zero() {
+                let value = cursor.key();
+                match &result {
+                    Some(min) if (self.compare)(value, min) != Ordering::Less => {}
+                    _ => result = Some(value.clone()),
+                }
+            }
+
+            cursor.step_key();
+        }
+
+        result
+    }
+
+    fn finalize(&self, accumulator: Self::Accumulator) -> Self::Output {
+        accumulator
+    }
+}
+
+impl<C, K, V, R> Stream<C, OrdIndexedZSet<K, V, R>>
+where
+    C: Circuit,
+    <C as WithClock>::Time: DBTimestamp,
+    K: DBData,
+    V: DBData,
+    R: DBData + ZRingValue,
+{
+    /// Incrementally compute the value of each key that is smallest according
+    /// to `compare`.
+    ///
+    /// Outputs changes to an indexed Z-set that maps each key to its smallest
+    /// value, emitting an update whenever the smallest value of a key
+    /// changes.  When the current smallest value is retracted, the next
+    /// smallest value takes its place.  See [`ArgMinBy`] for details.
+    pub fn arg_min_by<F>(&self, compare: F) -> Stream<C, OrdIndexedZSet<K, V, R>>
+    where
+        F: Fn(&V, &V) -> Ordering + Clone + 'static,
+    {
+        self.aggregate(ArgMinBy::new(compare))
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{
+    algebra::{MonoidValue, UnimplementedSemigroup, ZRingValue},
+    circuit::WithClock,
+    operator::aggregate::Aggregator,
+    trace::Cursor,
+    Circuit, DBData, DBTimestamp, OrdIndexedZSet, Stream, Timestamp,
+};
+use std::cmp::Ordering;
+
+/// An [aggregator](`crate::operator::Aggregator`) that returns both the
+/// smallest and the largest value with non-zero weight according to a
+/// user-supplied comparator, as a `(min, max)` pair.
+///
+/// Both extremes are computed in a single scan over the values of the key.
+/// Ties are broken in favor of the value that is smaller (for the minimum)
+/// or larger (for the maximum) in the natural order of the value type.
+#[derive(Clone)]
+pub struct MinMaxBy<F> {
+    compare: F,
+}
+
+impl<F> MinMaxBy<F> {
+    pub fn new(compare: F) -> Self {
+        Self { compare }
+    }
+}
+
+impl<V, T, R, F> Aggregator<V, T, R> for MinMaxBy<F>
+where
+    V: DBData,
+    T: Timestamp,
+    R: MonoidValue,
+    F: Fn(&V, &V) -> Ordering + Clone + 'static,
+{
+    type Accumulator = (V, V);
+    type Output = (V, V);
+    // The comparator isn't available to a static semigroup implementation.
+    type Semigroup = UnimplementedSemigroup<(V, V)>;
+
+    fn aggregate<C>(&self, cursor: &mut C) -> Option<Self::Accumulator>
+    where
+        C: Cursor<V, (), T, R>,
+    {
+        let mut result: Option<(V, V)> = None;
+
+        while cursor.key_valid() {
+            let weight = cursor.fold_times(R::zero(), |mut acc, _, weight| {
+                acc.add_assign_by_ref(weight);
+                acc
+            });
+
+            if !weight.is_zero() {
+                let value = cursor.key();
+                match &mut result {
+                    Some((min, max)) => {
+                        if (self.compare)(value, min) == Ordering::Less {
+                            *min = value.clone();
+                        }
+                        if (self.compare)(value, max) != Ordering::Less {
+                            *max = value.clone();
+                        }
+                    }
+                    None => result = Some((value.clone(), value.clone())),
+                }
+            }
+
+            cursor.step_key();
+        }
+
+        result
+    }
+
+    fn finalize(&self, accumulator: Self::Accumulator) -> Self::Output {
+        accumulator
+    }
+}
+
+impl<C, K, V, R> Stream<C, OrdIndexedZSet<K, V, R>>
+where
+    C: Circuit,
+    <C as WithClock>::Time: DBTimestamp,
+    K: DBData,
+    V: DBData,
+    R: DBData + ZRingValue,
+{
+    /// Incrementally compute the smallest and the largest value of each key
+    /// according to `compare`.
+    ///
+    /// Outputs changes to an indexed Z-set that maps each key to a
+    /// `(min, max)` pair, emitting an update whenever either extreme of a key
+    /// changes.  This is equivalent to, but cheaper than, computing the two
+    /// aggregates separately and joining them, as both extremes are computed
+    /// from the same integrated trace of the input stream.  When the current
+    /// minimum or maximum is retracted, the next value in order takes its
+    /// place.  See [`MinMaxBy`] for details.
+    pub fn min_max_by<F>(&self, compare: F) -> Stream<C, OrdIndexedZSet<K, (V, V), R>>
+    where
+        F: Fn(&V, &V) -> Ordering + Clone + 'static,
+    {
+        self.aggregate(MinMaxBy::new(compare))
+    }
+}
@@ -26,15 +26,23 @@ use crate::{
 };
 
 // Some standard aggregators.
+mod accumulate_last;
+mod arg_min;
 mod average;
 mod fold;
 mod max;
 mod min;
+mod min_max;
+mod pivot;
 
+pub use accumulate_last::AccumulateLast;
+pub use arg_min::ArgMinBy;
 pub use average::Avg;
 pub use fold::Fold;
 pub use max::{Max, MaxSemigroup};
 pub use min::{Min, MinSemigroup};
+pub use min_max::MinMaxBy;
+pub use pivot::{Pivot, PivotSemigroup};
 
 /// A trait for aggregator objects.  An aggregator summarizes the contents
 /// of a Z-set into a single value.
@@ -279,6 +287,34 @@ where
             .mark_sharded()
     }
 
+    /// Incrementally compute the largest value associated with each key.
+    ///
+    /// Shorthand for `self.aggregate(Max)`.  The operator maintains a trace
+    /// of the input, which stores the values of each key in sorted order
+    /// along with their weights.  When the current maximum is retracted, the
+    /// operator finds the next largest value with non-zero weight in the
+    /// trace and outputs it as the new maximum.
+    #[allow(clippy::type_complexity)]
+    pub fn aggregate_max(&self) -> Stream<C, OrdIndexedZSet<Z::Key, Z::Val, Z::R>>
+    where
+        Z: IndexedZSet + Send,
+        Z::R: ZRingValue,
+    {
+        self.aggregate(Max)
+    }
+
+    /// Incrementally compute the smallest value associated with each key.
+    ///
+    /// Shorthand for `self.aggregate(Min)`.  See [`Self::aggregate_max`].
+    #[allow(clippy::type_complexity)]
+    pub fn aggregate_min(&self) -> Stream<C, OrdIndexedZSet<Z::Key, Z::Val, Z::R>>
+    where
+        Z: IndexedZSet + Send,
+        Z::R: ZRingValue,
+    {
+        self.aggregate(Min)
+    }
+
     /// A version of [`Self::aggregate`] optimized for linear
     /// aggregation functions.
     ///
@@ -1077,4 +1113,239 @@ mod test {
     fn count_test4() {
         count_test(4);
     }
+
+    #[test]
+    fn pivot_test() {
+        let (dbsp, (input_handle, output)) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) =
+                circuit.add_input_indexed_zset::<u64, (String, i64), isize>();
+
+            let output = input
+                .pivot(vec![
+                    "cpu".to_string(),
+                    "mem".to_string(),
+                    "disk".to_string(),
+                ])
+                .output();
+
+            Ok((input_handle, output))
+        })
+        .unwrap();
+
+        input_handle.append(&mut vec![
+            (1, (("cpu".to_string(), 10), 1)),
+            (1, (("mem".to_string(), 20), 1)),
+            (1, (("disk".to_string(), 30), 1)),
+            (2, (("mem".to_string(), 5), 1)),
+            (2, (("net".to_string(), 7), 1)),
+            (3, (("net".to_string(), 1), 1)),
+        ]);
+        dbsp.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {
+                1 => {vec![Some(10), Some(20), Some(30)] => 1},
+                2 => {vec![None, Some(5), None] => 1},
+                3 => {vec![None, None, None] => 1}
+            }
+        );
+
+        input_handle.append(&mut vec![
+            (1, (("mem".to_string(), 20), -1)),
+            (1, (("mem".to_string(), 25), 1)),
+            (2, (("cpu".to_string(), 3), 1)),
+            (3, (("net".to_string(), 1), -1)),
+        ]);
+        dbsp.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {
+                1 => {vec![Some(10), Some(20), Some(30)] => -1, vec![Some(10), Some(25), Some(30)] => 1},
+                2 => {vec![None, Some(5), None] => -1, vec![Some(3), Some(5), None] => 1},
+                3 => {vec![None, None, None] => -1}
+            }
+        );
+    }
+
+    #[test]
+    fn aggregate_max_min_retraction_test() {
+        let (dbsp, (input_handle, max, min)) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, i64, isize>();
+
+            let max = input.aggregate_max().integrate().output();
+            let min = input.aggregate_min().integrate().output();
+
+            Ok((input_handle, max, min))
+        })
+        .unwrap();
+
+        for v in [5, 3, 5] {
+            input_handle.push(1, (v, 1));
+            dbsp.step().unwrap();
+        }
+        assert_eq!(max.consolidate(), indexed_zset! {1 => {5 => 1}});
+        assert_eq!(min.consolidate(), indexed_zset! {1 => {3 => 1}});
+
+        // Another copy of `5` is still in the input.
+        input_handle.push(1, (5, -1));
+        dbsp.step().unwrap();
+        assert_eq!(max.consolidate(), indexed_zset! {1 => {5 => 1}});
+        assert_eq!(min.consolidate(), indexed_zset! {1 => {3 => 1}});
+
+        input_handle.push(1, (5, -1));
+        dbsp.step().unwrap();
+        assert_eq!(max.consolidate(), indexed_zset! {1 => {3 => 1}});
+        assert_eq!(min.consolidate(), indexed_zset! {1 => {3 => 1}});
+
+        input_handle.push(1, (3, -1));
+        dbsp.step().unwrap();
+        assert_eq!(max.consolidate(), indexed_zset! {});
+        assert_eq!(min.consolidate(), indexed_zset! {});
+    }
+
+    #[test]
+    fn arg_min_by_test() {
+        let (dbsp, (input_handle, output)) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) =
+                circuit.add_input_indexed_zset::<u64, (String, i64), isize>();
+
+            // Select the value with the smallest price (second tuple element).
+            let output = input
+                .arg_min_by(|(_, p1): &(String, i64), (_, p2)| p1.cmp(p2))
+                .output();
+
+            Ok((input_handle, output))
+        })
+        .unwrap();
+
+        input_handle.append(&mut vec![
+            (1, (("a".to_string(), 30), 1)),
+            (1, (("b".to_string(), 10), 1)),
+            (1, (("c".to_string(), 20), 1)),
+            (2, (("d".to_string(), 5), 1)),
+        ]);
+        dbsp.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {
+                1 => {("b".to_string(), 10) => 1},
+                2 => {("d".to_string(), 5) => 1}
+            }
+        );
+
+        // Inserting a value that isn't smaller doesn't change the output.
+        input_handle.push(1, (("d".to_string(), 40), 1));
+        dbsp.step().unwrap();
+        assert_eq!(output.consolidate(), indexed_zset! {});
+
+        // Retracting the current minimum promotes the runner-up.
+        input_handle.push(1, (("b".to_string(), 10), -1));
+        dbsp.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {
+                1 => {("b".to_string(), 10) => -1, ("c".to_string(), 20) => 1}
+            }
+        );
+
+        input_handle.push(2, (("d".to_string(), 5), -1));
+        dbsp.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {2 => {("d".to_string(), 5) => -1}}
+        );
+    }
+
+    #[test]
+    fn min_max_by_test() {
+        let (dbsp, (input_handle, output)) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, i64, isize>();
+
+            let output = input.min_max_by(|v1: &i64, v2: &i64| v1.cmp(v2)).output();
+
+            Ok((input_handle, output))
+        })
+        .unwrap();
+
+        input_handle.append(&mut vec![
+            (1, (30, 1)),
+            (1, (10, 1)),
+            (1, (20, 1)),
+            (2, (5, 1)),
+        ]);
+        dbsp.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {1 => {(10, 30) => 1}, 2 => {(5, 5) => 1}}
+        );
+
+        // Retracting the current maximum keeps the minimum and promotes the
+        // runner-up.
+        input_handle.push(1, (30, -1));
+        dbsp.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {1 => {(10, 30) => -1, (10, 20) => 1}}
+        );
+
+        // A value between the extremes doesn't change the output.
+        input_handle.push(1, (15, 1));
+        dbsp.step().unwrap();
+        assert_eq!(output.consolidate(), indexed_zset! {});
+
+        // Retracting the current minimum keeps the maximum.
+        input_handle.push(1, (10, -1));
+        dbsp.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {1 => {(10, 20) => -1, (15, 20) => 1}}
+        );
+    }
+
+    #[test]
+    fn accumulate_last_test() {
+        let (dbsp, (input_handle, output)) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, (u64, i64), isize>();
+
+            // 3-point moving average of `(time, value)` pairs.
+            let output = input
+                .accumulate_last(3, |window| {
+                    window.iter().map(|(_, value)| value).sum::<i64>() / window.len() as i64
+                })
+                .output();
+
+            Ok((input_handle, output))
+        })
+        .unwrap();
+
+        input_handle.append(&mut vec![
+            (1, ((1, 3), 1)),
+            (1, ((2, 6), 1)),
+            (2, ((1, 10), 1)),
+        ]);
+        dbsp.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {1 => {4 => 1}, 2 => {10 => 1}}
+        );
+
+        input_handle.push(1, ((3, 9), 1));
+        dbsp.step().unwrap();
+        assert_eq!(output.consolidate(), indexed_zset! {1 => {4 => -1, 6 => 1}});
+
+        // The oldest value ages out of the full window.
+        input_handle.push(1, ((4, 12), 1));
+        dbsp.step().unwrap();
+        assert_eq!(output.consolidate(), indexed_zset! {1 => {6 => -1, 9 => 1}});
+
+        // Retracting a value in the window brings the evicted value back.
+        input_handle.push(1, ((3, 9), -1));
+        dbsp.step().unwrap();
+        assert_eq!(output.consolidate(), indexed_zset! {1 => {9 => -1, 7 => 1}});
+
+        // Values that arrive out of order are placed by their time.
+        input_handle.push(1, ((0, 30), 1));
+        dbsp.step().unwrap();
+        assert_eq!(output.consolidate(), indexed_zset! {});
+    }
 }
@@ -0,0 +1,113 @@
+use crate::{
+    algebra::{MonoidValue, Semigroup, ZRingValue},
+    circuit::WithClock,
+    operator::aggregate::Aggregator,
+    trace::Cursor,
+    Circuit, DBData, DBTimestamp, OrdIndexedZSet, Stream, Timestamp,
+};
+use std::marker::PhantomData;
+
+/// An [aggregator](`crate::operator::Aggregator`) that turns
+/// `(category, value)` pairs into a row with one column per category.
+///
+/// The aggregator is configured with a fixed list of categories and outputs
+/// a vector with one element per category, in the same order.  The element
+/// for a category is the smallest value with non-zero weight in this
+/// category, or `None` if there is no such value.  Values in categories not
+/// on the list are ignored.
+#[derive(Clone)]
+pub struct Pivot<Cat> {
+    categories: Vec<Cat>,
+}
+
+impl<Cat> Pivot<Cat> {
+    pub fn new(categories: Vec<Cat>) -> Self {
+        Self { categories }
+    }
+}
+
+#[derive(Clone)]
+pub struct PivotSemigroup<V>(PhantomData<V>);
+
+impl<V> Semigroup<Vec<Option<V>>> for PivotSemigroup<V>
+where
+    V: Ord + Clone,
+{
+    fn combine(left: &Vec<Option<V>>, right: &Vec<Option<V>>) -> Vec<Option<V>> {
+        left.iter()
+            .zip(right.iter())
+            .map(|(left, right)| match (left, right) {
+                (Some(left), Some(right)) => Some(left.min(right).clone()),
+                (left, right) => left.as_ref().or(right.as_ref()).cloned(),
+            })
+            .collect()
+    }
+}
+
+impl<Cat, V, T, R> Aggregator<(Cat, V), T, R> for Pivot<Cat>
+where
+    Cat: DBData,
+    V: DBData,
+    T: Timestamp,
+    R: MonoidValue,
+{
+    type Accumulator = Vec<Option<V>>;
+    type Output = Vec<Option<V>>;
+    type Semigroup = PivotSemigroup<V>;
+
+    fn aggregate<C>(&self, cursor: &mut C) -> Option<Self::Accumulator>
+    where
+        C: Cursor<(Cat, V), (), T, R>,
+    {
+        let mut row = vec![None; self.categories.len()];
+        let mut non_empty = false;
+
+        // Values within each category are sorted, so the first value with
+        // non-zero weight is the smallest one.
+        while cursor.key_valid() {
+            let weight = cursor.fold_times(R::zero(), |mut acc, _, weight| {
+                acc.add_assign_by_ref(weight);
+                acc
+            });
+
+            if !weight.is_zero() {
+                non_empty = true;
+
+                let (category, value) = cursor.key();
+                if let Some(index) = self.categories.iter().position(|c| c == category) {
+                    if row[index].is_none() {
+                        row[index] = Some(value.clone());
+                    }
+                }
+            }
+
+            cursor.step_key();
+        }
+
+        non_empty.then_some(row)
+    }
+
+    fn finalize(&self, accumulator: Self::Accumulator) -> Self::Output {
+        accumulator
+    }
+}
+
+impl<C, K, Cat, V, R> Stream<C, OrdIndexedZSet<K, (Cat, V), R>>
+where
+    C: Circuit,
+    <C as WithClock>::Time: DBTimestamp,
+    K: DBData,
+    Cat: DBData,
+    V: DBData,
+    R: DBData + ZRingValue,
+{
+    /// Incrementally pivot an indexed Z-set of `(category, value)` pairs into
+    /// wide rows.
+    ///
+    /// Outputs one row per key with one column per element of `categories`,
+    /// containing the value of the key in this category or `None` if there
+    /// is no such value.  See [`Pivot`] for details.
+    pub fn pivot(&self, categories: Vec<Cat>) -> Stream<C, OrdIndexedZSet<K, Vec<Option<V>>, R>> {
+        self.aggregate(Pivot::new(categories))
+    }
+}
@@ -0,0 +1,180 @@
+//! Defines an operator that checks an invariant over every element of its
+//! input stream.
+
+use crate::circuit::{
+    metadata::OperatorLocation,
+    operator_traits::{Operator, UnaryOperator},
+    Circuit, Scope, Stream,
+};
+use std::{borrow::Cow, fmt::Display, marker::PhantomData, panic::Location};
+
+impl<C, D> Stream<C, D>
+where
+    D: Clone + 'static,
+    C: Circuit,
+{
+    /// Check an invariant over each value in the stream.
+    ///
+    /// At each clock cycle, applies `check` to the current value of the
+    /// stream.  If `check` returns an error, the current step of the circuit
+    /// fails with
+    /// [`SchedulerError::OperatorError`](`crate::SchedulerError::OperatorError`)
+    /// describing the error and the location of the assertion in the source
+    /// code.  Unlike `assert!` inside [`inspect`](`Self::inspect`), this
+    /// doesn't panic the worker thread.
+    ///
+    /// Returns the input stream unmodified, so assertions can be chained
+    /// between other operators.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use dbsp::{IndexedZSet, OrdZSet, RootCircuit};
+    /// let (circuit, input_handle) = RootCircuit::build(move |circuit| {
+    ///     let (input, input_handle) = circuit.add_input_zset::<u64, isize>();
+    ///     input.stream_distinct().assert(|zset: &OrdZSet<u64, isize>| {
+    ///         match zset.iter().find(|(_, _, w)| *w < 0) {
+    ///             Some((k, _, w)) => Err(format!("key {k} has negative weight {w}")),
+    ///             None => Ok(()),
+    ///         }
+    ///     });
+    ///     Ok(input_handle)
+    /// })
+    /// .unwrap();
+    ///
+    /// input_handle.push(1, 1);
+    /// circuit.step().unwrap();
+    /// ```
+    #[track_caller]
+    pub fn assert<F, E>(&self, check: F) -> Self
+    where
+        F: FnMut(&D) -> Result<(), E> + 'static,
+        E: Display + 'static,
+    {
+        let asserted = self
+            .circuit()
+            .add_unary_operator(Assert::new(check, Location::caller()), self);
+        asserted.mark_sharded_if(self);
+        asserted
+    }
+}
+
+/// Operator that applies a user-provided check to each input and reports
+/// failed checks as step errors.
+///
+/// See [`Stream::assert`].
+pub struct Assert<T, E, F> {
+    check: F,
+    location: &'static Location<'static>,
+    error: Option<String>,
+    phantom: PhantomData<fn(&T) -> E>,
+}
+
+impl<T, E, F> Assert<T, E, F>
+where
+    F: FnMut(&T) -> Result<(), E>,
+{
+    /// Create a new instance of the `Assert` operator that will apply
+    /// `check` to each value in the input stream.
+    pub fn new(check: F, location: &'static Location<'static>) -> Self {
+        Self {
+            check,
+            location,
+            error: None,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, E, F> Assert<T, E, F>
+where
+    F: FnMut(&T) -> Result<(), E>,
+    E: Display,
+{
+    fn check(&mut self, i: &T) {
+        if let Err(error) = (self.check)(i) {
+            self.error = Some(format!("assertion failed at {}: {error}", self.location));
+        }
+    }
+}
+
+impl<T, E, F> Operator for Assert<T, E, F>
+where
+    T: 'static,
+    E: 'static,
+    F: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("Assert")
+    }
+
+    fn location(&self) -> OperatorLocation {
+        Some(self.location)
+    }
+
+    fn take_error(&mut self) -> Option<String> {
+        self.error.take()
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<T, E, F> UnaryOperator<T, T> for Assert<T, E, F>
+where
+    T: Clone + 'static,
+    E: Display + 'static,
+    F: FnMut(&T) -> Result<(), E> + 'static,
+{
+    fn eval(&mut self, i: &T) -> T {
+        self.check(i);
+        i.clone()
+    }
+
+    fn eval_owned(&mut self, i: T) -> T {
+        self.check(&i);
+        i
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{zset, IndexedZSet, OrdZSet, RootCircuit, SchedulerError};
+
+    fn non_negative(zset: &OrdZSet<u64, isize>) -> Result<(), String> {
+        match zset.iter().find(|(_, _, w)| *w < 0) {
+            Some((k, _, w)) => Err(format!("key {k} has negative weight {w}")),
+            None => Ok(()),
+        }
+    }
+
+    #[test]
+    fn assert_test() {
+        let (circuit, (input_handle, distinct_output)) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) = circuit.add_input_zset::<u64, isize>();
+
+            // `stream_distinct` never outputs negative weights.
+            let distinct_output = input.stream_distinct().assert(non_negative).output();
+            // The raw input may contain retractions.
+            input.assert(non_negative);
+
+            Ok((input_handle, distinct_output))
+        })
+        .unwrap();
+
+        input_handle.push(1, 1);
+        input_handle.push(2, 3);
+        circuit.step().unwrap();
+        assert_eq!(distinct_output.consolidate(), zset! {1 => 1, 2 => 1});
+
+        input_handle.push(1, -1);
+        match circuit.step() {
+            Err(SchedulerError::OperatorError { error, .. }) => {
+                assert!(error.starts_with("assertion failed at "), "{error}");
+                assert!(error.ends_with(": key 1 has negative weight -1"), "{error}");
+            }
+            result => panic!("unexpected result: {result:?}"),
+        }
+    }
+}
@@ -0,0 +1,202 @@
+//! Channel-backed input handles for feeding circuits from async tasks.
+//!
+//! [`CollectionHandle`] methods push updates to the circuit synchronously.
+//! Async transports, e.g., ones that read from a socket in a Tokio task, can
+//! instead send batches of updates through an [`AsyncCollectionHandle`].
+//! Batches are queued in a bounded channel until the thread that drives the
+//! circuit calls [`AsyncCollectionReceiver::flush`], which pushes them to the
+//! underlying [`CollectionHandle`] to be applied at the next `step`.
+
+use crate::{operator::CollectionHandle, DBData};
+use tokio::sync::mpsc::{
+    self,
+    error::{SendError, TryRecvError, TrySendError},
+};
+
+impl<K, V> CollectionHandle<K, V>
+where
+    K: DBData,
+    V: DBData,
+{
+    /// Create a channel that carries batches of updates to this input
+    /// stream.
+    ///
+    /// Returns the sending half of the channel, which can be cloned and
+    /// moved to async tasks, and the receiving half, which must be flushed
+    /// by the thread that drives the circuit before each step.
+    ///
+    /// # Backpressure
+    ///
+    /// The channel holds up to `capacity` batches.  Once it is full,
+    /// [`AsyncCollectionHandle::append`] waits until the next
+    /// [`AsyncCollectionReceiver::flush`] makes room, and
+    /// [`AsyncCollectionHandle::try_append`] fails with
+    /// [`TrySendError::Full`].  The size of individual batches is not
+    /// limited.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is 0.
+    pub fn channel(
+        &self,
+        capacity: usize,
+    ) -> (AsyncCollectionHandle<K, V>, AsyncCollectionReceiver<K, V>) {
+        let (sender, receiver) = mpsc::channel(capacity);
+
+        (
+            AsyncCollectionHandle { sender },
+            AsyncCollectionReceiver {
+                handle: self.clone(),
+                receiver,
+                closed: false,
+            },
+        )
+    }
+}
+
+/// Sending half of a channel created by [`CollectionHandle::channel`].
+pub struct AsyncCollectionHandle<K, V> {
+    sender: mpsc::Sender<Vec<(K, V)>>,
+}
+
+impl<K, V> Clone for AsyncCollectionHandle<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<K, V> AsyncCollectionHandle<K, V> {
+    /// Send a batch of `(key,value)` pairs to the input stream, waiting for
+    /// room in the channel if it is full.
+    ///
+    /// Fails if the receiving half of the channel has been dropped, returning
+    /// the batch to the caller.
+    pub async fn append(&self, vals: Vec<(K, V)>) -> Result<(), SendError<Vec<(K, V)>>> {
+        self.sender.send(vals).await
+    }
+
+    /// Send a batch of `(key,value)` pairs to the input stream without
+    /// waiting.
+    ///
+    /// Fails if the channel is full or the receiving half has been dropped,
+    /// returning the batch to the caller.
+    pub fn try_append(&self, vals: Vec<(K, V)>) -> Result<(), TrySendError<Vec<(K, V)>>> {
+        self.sender.try_send(vals)
+    }
+}
+
+/// Receiving half of a channel created by [`CollectionHandle::channel`].
+pub struct AsyncCollectionReceiver<K, V> {
+    handle: CollectionHandle<K, V>,
+    receiver: mpsc::Receiver<Vec<(K, V)>>,
+    closed: bool,
+}
+
+impl<K, V> AsyncCollectionReceiver<K, V>
+where
+    K: DBData,
+    V: DBData,
+{
+    /// Push all batches queued in the channel to the input stream, where
+    /// they will be consumed at the next clock cycle.
+    ///
+    /// Returns the number of batches pushed.
+    pub fn flush(&mut self) -> usize {
+        let mut batches = 0;
+
+        loop {
+            match self.receiver.try_recv() {
+                Ok(mut vals) => {
+                    self.handle.append(&mut vals);
+                    batches += 1;
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.closed = true;
+                    break;
+                }
+            }
+        }
+
+        batches
+    }
+
+    /// Returns `true` if all senders have been dropped and
+    /// [`Self::flush`] has pushed all of their batches to the input stream.
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{zset, OrdZSet, RootCircuit};
+    use tokio::sync::mpsc::error::TrySendError;
+
+    #[test]
+    fn async_input_test() {
+        let (circuit, (input_handle, output)) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) = circuit.add_input_zset::<u64, isize>();
+
+            Ok((input_handle, input.integrate().output()))
+        })
+        .unwrap();
+
+        let (sender, mut receiver) = input_handle.channel(2);
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let task = runtime.spawn(async move {
+            for i in 0..10 {
+                sender.append(vec![(i, 1)]).await.unwrap();
+            }
+        });
+
+        // Drive the circuit until the task completes and all of its batches
+        // have been consumed.
+        while !receiver.is_closed() {
+            receiver.flush();
+            circuit.step().unwrap();
+        }
+        runtime.block_on(task).unwrap();
+
+        let expected: OrdZSet<u64, isize> =
+            zset! {0 => 1, 1 => 1, 2 => 1, 3 => 1, 4 => 1, 5 => 1, 6 => 1, 7 => 1, 8 => 1, 9 => 1};
+        assert_eq!(output.consolidate(), expected);
+    }
+
+    #[test]
+    fn async_input_backpressure_test() {
+        let (circuit, (input_handle, output)) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) = circuit.add_input_zset::<u64, isize>();
+
+            Ok((input_handle, input.output()))
+        })
+        .unwrap();
+
+        let (sender, mut receiver) = input_handle.channel(2);
+
+        sender.try_append(vec![(1, 1)]).unwrap();
+        sender.try_append(vec![(2, 1), (3, 1)]).unwrap();
+        assert!(matches!(
+            sender.try_append(vec![(4, 1)]),
+            Err(TrySendError::Full(_))
+        ));
+
+        // Nothing reaches the circuit until the receiver is flushed.
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! {});
+
+        assert_eq!(receiver.flush(), 2);
+        sender.try_append(vec![(4, 1)]).unwrap();
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! {1 => 1, 2 => 1, 3 => 1});
+
+        drop(sender);
+        assert_eq!(receiver.flush(), 1);
+        assert!(receiver.is_closed());
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! {4 => 1});
+    }
+}
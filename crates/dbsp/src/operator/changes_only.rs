@@ -0,0 +1,152 @@
+//! Operator that suppresses updates that don't change the collection.
+
+use crate::{
+    algebra::{HasZero, IndexedZSet},
+    circuit::{
+        metadata::OperatorLocation,
+        operator_traits::{Operator, UnaryOperator},
+        Circuit, Scope, Stream,
+    },
+    trace::{cursor::Cursor, Batch, BatchReader},
+};
+use std::{borrow::Cow, marker::PhantomData, panic::Location};
+
+impl<C, B> Stream<C, B>
+where
+    C: Circuit,
+    B: IndexedZSet + Send,
+{
+    /// Drop updates that cancel out within each input batch.
+    ///
+    /// Operators that recompute a value, e.g., an aggregate, may retract
+    /// the old value of a key and insert a new value that is identical to
+    /// it.  This operator consolidates each input batch, adding up the
+    /// weights of all updates to the same `(key, value)` pair and dropping
+    /// pairs whose weights add up to zero, so that a `-old, +new` pair with
+    /// `old == new` produces no output.  Keys whose updates all cancel out
+    /// are dropped from the output, which reduces the number of writes
+    /// performed by downstream operators and sinks.
+    ///
+    /// Batches that are already consolidated are output unmodified.
+    #[track_caller]
+    pub fn changes_only(&self) -> Self {
+        let output = self
+            .circuit()
+            .add_unary_operator(ChangesOnly::new(Location::caller()), self);
+        output.mark_sharded_if(self);
+        output
+    }
+}
+
+/// Operator that consolidates its input, dropping updates with zero net
+/// weight.
+///
+/// The input can be a batch or a trace.
+///
+/// See [`Stream::changes_only`].
+pub struct ChangesOnly<T, O> {
+    location: &'static Location<'static>,
+    _types: PhantomData<(T, O)>,
+}
+
+impl<T, O> ChangesOnly<T, O> {
+    pub fn new(location: &'static Location<'static>) -> Self {
+        Self {
+            location,
+            _types: PhantomData,
+        }
+    }
+}
+
+impl<T, O> Operator for ChangesOnly<T, O>
+where
+    T: 'static,
+    O: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("ChangesOnly")
+    }
+
+    fn location(&self) -> OperatorLocation {
+        Some(self.location)
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<T, O> UnaryOperator<T, O> for ChangesOnly<T, O>
+where
+    T: BatchReader<Time = ()>,
+    O: Batch<Key = T::Key, Val = T::Val, Time = (), R = T::R>,
+{
+    fn eval(&mut self, input: &T) -> O {
+        let mut cursor = input.cursor();
+        let mut tuples = Vec::with_capacity(input.len());
+
+        while cursor.key_valid() {
+            while cursor.val_valid() {
+                let weight = cursor.weight();
+                if !weight.is_zero() {
+                    tuples.push((
+                        O::item_from(cursor.key().clone(), cursor.val().clone()),
+                        weight,
+                    ));
+                }
+                cursor.step_val();
+            }
+            cursor.step_key();
+        }
+
+        // The batcher consolidates updates to the same `(key, value)` pair
+        // and drops updates that cancel out.
+        O::from_tuples((), tuples)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        indexed_zset,
+        operator::Generator,
+        trace::{Batch, Builder},
+        Circuit, OrdIndexedZSet, RootCircuit,
+    };
+
+    #[test]
+    fn changes_only_test() {
+        // Batches built from ordered updates aren't consolidated by the builder.
+        let mut batches = vec![
+            vec![((1, 5), -1), ((1, 5), 1), ((2, 3), -1), ((2, 4), 1)],
+            vec![((1, 5), -1), ((1, 5), 1)],
+            vec![((1, 5), -1), ((1, 6), 1)],
+        ]
+        .into_iter()
+        .map(|updates| {
+            let mut builder = <OrdIndexedZSet<u64, i64, isize> as Batch>::Builder::new_builder(());
+            for update in updates {
+                builder.push(update);
+            }
+            builder.done()
+        });
+
+        let (circuit, output) = RootCircuit::build(move |circuit| {
+            Ok(circuit
+                .add_source(Generator::new(move || batches.next().unwrap()))
+                .changes_only()
+                .output())
+        })
+        .unwrap();
+
+        // The recomputed value of key 1 is unchanged.
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), indexed_zset! {2 => {3 => -1, 4 => 1}});
+
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), OrdIndexedZSet::empty(()));
+
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), indexed_zset! {1 => {5 => -1, 6 => 1}});
+    }
+}
@@ -0,0 +1,263 @@
+//! Operator that merges small consecutive batches into larger ones.
+
+use crate::{
+    algebra::IndexedZSet,
+    circuit::{
+        metadata::OperatorLocation,
+        operator_traits::{Operator, UnaryOperator},
+        Circuit, Scope,
+    },
+    RootCircuit, Stream,
+};
+use std::{
+    borrow::Cow,
+    panic::Location,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+impl<Z> Stream<RootCircuit, Z>
+where
+    Z: IndexedZSet + Send,
+{
+    /// Buffer changes across clock cycles and release them in batches of
+    /// at least `min_records` records.
+    ///
+    /// At each clock cycle, the operator adds the input batch to its buffer.
+    /// Once the buffer contains at least `min_records` records, the operator
+    /// outputs the entire buffer and clears it; otherwise it outputs an empty
+    /// batch.  This trades latency for throughput at sinks, such as output
+    /// encoders, that are more efficient with larger batches.
+    ///
+    /// `handle` releases the buffer regardless of its size at the next clock
+    /// cycle.  In a multi-worker circuit, create the handle once, outside of
+    /// the circuit constructor, and pass a clone of it to every worker, so
+    /// that a single [`CoalesceHandle::flush`] call releases the buffers of
+    /// all workers.
+    ///
+    /// The circuit does not evaluate operators at teardown.  To avoid losing
+    /// buffered records, call [`CoalesceHandle::flush`] and perform one more
+    /// step before destroying the circuit.
+    /// [`CoalesceHandle::buffered_records`] returns `0` once all workers
+    /// have released their buffers.
+    ///
+    /// Each worker buffers its own share of the input, so the threshold
+    /// applies to each worker separately.
+    #[track_caller]
+    pub fn coalesce(&self, min_records: usize, handle: &CoalesceHandle) -> Self {
+        let output = self.circuit().add_unary_operator(
+            Coalesce::new(min_records, handle.clone(), Location::caller()),
+            self,
+        );
+        output.mark_sharded_if(self);
+
+        output
+    }
+}
+
+/// Handle used to release the contents of a [`Stream::coalesce`] buffer
+/// before it reaches the threshold.
+///
+/// Clones of the handle share state, so a handle can be cloned into each
+/// worker of a multi-worker circuit.
+#[derive(Clone, Debug, Default)]
+pub struct CoalesceHandle(Arc<CoalesceState>);
+
+#[derive(Debug, Default)]
+struct CoalesceState {
+    /// Number of flush requests issued so far.  Each operator remembers the
+    /// last value it observed, so every worker sees every request.
+    flushes: AtomicU64,
+    /// Number of records buffered across all operators sharing the handle.
+    buffered: AtomicUsize,
+}
+
+impl CoalesceHandle {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Request all operators sharing this handle to output their buffered
+    /// records at the next clock cycle.
+    pub fn flush(&self) {
+        self.0.flushes.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Returns the number of records buffered across all workers.
+    pub fn buffered_records(&self) -> usize {
+        self.0.buffered.load(Ordering::Acquire)
+    }
+
+    fn flushes(&self) -> u64 {
+        self.0.flushes.load(Ordering::Acquire)
+    }
+}
+
+/// Operator that buffers its inputs until they reach a size threshold.
+///
+/// See [`Stream::coalesce`].
+pub struct Coalesce<Z> {
+    min_records: usize,
+    buffer: Option<Z>,
+    handle: CoalesceHandle,
+    // Value of the handle's flush counter last observed by this operator.
+    flushes: u64,
+    location: &'static Location<'static>,
+}
+
+impl<Z> Coalesce<Z> {
+    pub fn new(
+        min_records: usize,
+        handle: CoalesceHandle,
+        location: &'static Location<'static>,
+    ) -> Self {
+        Self {
+            min_records,
+            buffer: None,
+            flushes: handle.flushes(),
+            handle,
+            location,
+        }
+    }
+}
+
+impl<Z> Operator for Coalesce<Z>
+where
+    Z: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("Coalesce")
+    }
+
+    fn location(&self) -> OperatorLocation {
+        Some(self.location)
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        self.buffer.is_none()
+    }
+}
+
+impl<Z> UnaryOperator<Z, Z> for Coalesce<Z>
+where
+    Z: IndexedZSet,
+{
+    fn eval(&mut self, delta: &Z) -> Z {
+        let buffer = match self.buffer.take() {
+            Some(buffer) => {
+                self.handle
+                    .0
+                    .buffered
+                    .fetch_sub(buffer.len(), Ordering::AcqRel);
+                buffer.merge(delta)
+            }
+            None => delta.clone(),
+        };
+
+        let flushes = self.handle.flushes();
+        let flush = flushes != self.flushes;
+        self.flushes = flushes;
+
+        if flush || buffer.len() >= self.min_records {
+            buffer
+        } else {
+            if !buffer.is_empty() {
+                self.handle
+                    .0
+                    .buffered
+                    .fetch_add(buffer.len(), Ordering::AcqRel);
+                self.buffer = Some(buffer);
+            }
+            Z::empty(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CoalesceHandle;
+    use crate::{trace::Batch, zset, OrdZSet, RootCircuit, Runtime};
+
+    #[test]
+    fn coalesce_test() {
+        let flush = CoalesceHandle::new();
+        let handle = flush.clone();
+        let (circuit, (input_handle, output)) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) = circuit.add_input_zset::<u64, isize>();
+            let coalesced = input.coalesce(3, &handle);
+
+            Ok((input_handle, coalesced.output()))
+        })
+        .unwrap();
+
+        input_handle.push(1, 1);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), OrdZSet::empty(()));
+
+        input_handle.push(2, 1);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), OrdZSet::empty(()));
+
+        // The third batch reaches the threshold and releases all buffered
+        // records.
+        input_handle.push(3, 1);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! {1 => 1, 2 => 1, 3 => 1});
+
+        // Records that cancel out don't count towards the threshold.
+        input_handle.push(4, 1);
+        circuit.step().unwrap();
+        input_handle.push(4, -1);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), OrdZSet::empty(()));
+
+        // Flushing releases buffered records below the threshold.
+        input_handle.push(5, 1);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), OrdZSet::empty(()));
+
+        assert_eq!(flush.buffered_records(), 1);
+        flush.flush();
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! {5 => 1});
+        assert_eq!(flush.buffered_records(), 0);
+    }
+
+    #[test]
+    fn coalesce_multiworker_test() {
+        let flush = CoalesceHandle::new();
+        let handle = flush.clone();
+        let (mut dbsp, (input_handle, output)) = Runtime::init_circuit(4, move |circuit| {
+            let (input, input_handle) = circuit.add_input_zset::<u64, isize>();
+            let coalesced = input.coalesce(100, &handle);
+
+            Ok((input_handle, coalesced.output()))
+        })
+        .unwrap();
+
+        // Inputs are distributed across workers, none of which reaches the
+        // threshold.
+        let mut inputs = (0..20).map(|k| (k, 1)).collect::<Vec<_>>();
+        input_handle.append(&mut inputs);
+        dbsp.step().unwrap();
+        assert_eq!(output.consolidate(), OrdZSet::empty(()));
+        assert_eq!(flush.buffered_records(), 20);
+
+        // A single flush request releases the buffers of all workers, as it
+        // would before shutting down the circuit.
+        flush.flush();
+        dbsp.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            OrdZSet::from_keys((), (0..20).map(|k| (k, 1)).collect())
+        );
+        assert_eq!(flush.buffered_records(), 0);
+
+        dbsp.step().unwrap();
+        assert_eq!(output.consolidate(), OrdZSet::empty(()));
+
+        dbsp.kill().unwrap();
+    }
+}
@@ -4,3 +4,4 @@ mod shard;
 
 pub(crate) use exchange::Exchange;
 pub use exchange::{new_exchange_operators, ExchangeReceiver, ExchangeSender};
+pub use shard::PartitionScheme;
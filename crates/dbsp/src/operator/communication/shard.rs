@@ -7,6 +7,7 @@
 use crate::{
     circuit::GlobalNodeId,
     circuit_cache_key, default_hash,
+    hash::{seeded_hash, DEFAULT_HASH_SEED},
     operator::communication::exchange::new_exchange_operators,
     trace::{cursor::Cursor, Batch, BatchReader, Builder, Trace},
     Circuit, Runtime, Stream,
@@ -14,7 +15,7 @@ use crate::{
 // Import `spine_fueled::Spine` here instead of `trace::Spine` because it is
 // strictly used for non-persistent data-communication between threads.
 use crate::trace::spine_fueled::Spine;
-use std::{hash::Hash, panic::Location};
+use std::{hash::Hash, panic::Location, sync::Arc};
 
 circuit_cache_key!(ShardId<C, D>((GlobalNodeId, ShardingPolicy) => Stream<C, D>));
 
@@ -27,6 +28,73 @@ fn sharding_policy<C>(_circuit: &C) -> ShardingPolicy {
     ShardingPolicy
 }
 
+/// Function that assigns keys to partitions, e.g., to worker threads.
+///
+/// All schemes are deterministic: the partition of a key only depends on the
+/// key, the scheme, and the number of partitions, so the same input is
+/// partitioned identically across runs and machines.  The default scheme
+/// hashes keys with a fixed seed, which matches the partitioning used by
+/// [`Stream::shard`].
+#[allow(clippy::type_complexity)]
+pub enum PartitionScheme<K> {
+    /// Partition keys by their hash computed with `seed`.
+    Hash { seed: u64 },
+    /// Partition keys into contiguous ranges.
+    ///
+    /// `bounds` must be sorted.  It splits the key space into
+    /// `bounds.len() + 1` ranges, where range `i` contains keys `k` such that
+    /// `bounds[i-1] <= k < bounds[i]`.  Range `i` is assigned to partition
+    /// `i % partitions`.
+    Range { bounds: Vec<K> },
+    /// Partition keys using a user-defined function, which takes a key and
+    /// the number of partitions and returns the index of the partition.
+    /// Indexes that are out of range are wrapped around.
+    Custom(Arc<dyn Fn(&K, usize) -> usize + Send + Sync>),
+}
+
+impl<K> Default for PartitionScheme<K> {
+    fn default() -> Self {
+        Self::Hash {
+            seed: DEFAULT_HASH_SEED,
+        }
+    }
+}
+
+impl<K> Clone for PartitionScheme<K>
+where
+    K: Clone,
+{
+    fn clone(&self) -> Self {
+        match self {
+            Self::Hash { seed } => Self::Hash { seed: *seed },
+            Self::Range { bounds } => Self::Range {
+                bounds: bounds.clone(),
+            },
+            Self::Custom(func) => Self::Custom(func.clone()),
+        }
+    }
+}
+
+impl<K> PartitionScheme<K>
+where
+    K: Ord + Hash,
+{
+    /// Returns the partition of `key` out of `partitions` partitions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `partitions` is 0.
+    pub fn partition(&self, key: &K, partitions: usize) -> usize {
+        assert_ne!(partitions, 0);
+
+        match self {
+            Self::Hash { seed } => seeded_hash(key, *seed) as usize % partitions,
+            Self::Range { bounds } => bounds.partition_point(|bound| bound <= key) % partitions,
+            Self::Custom(func) => func(key, partitions) % partitions,
+        }
+    }
+}
+
 impl<C, IB> Stream<C, IB>
 where
     C: Circuit,
@@ -134,7 +202,13 @@ where
                                 Runtime::worker_index(),
                                 Some(location),
                                 move |batch: IB, batches: &mut Vec<OB>| {
-                                    Self::shard_batch(&batch, num_workers, &mut builders, batches);
+                                    Self::shard_batch(
+                                        &batch,
+                                        num_workers,
+                                        &mut builders,
+                                        batches,
+                                        |key| default_hash(key) as usize % num_workers,
+                                    );
                                 },
                                 |trace: &mut Spine<OB>, batch: OB| trace.insert(batch),
                             );
@@ -164,14 +238,59 @@ where
         })
     }
 
-    // Partitions the batch into `nshards` partitions based on the hash of the key.
-    fn shard_batch<OB>(
+    /// Shard batches across multiple worker threads using a custom
+    /// [`PartitionScheme`].
+    ///
+    /// Like [`Self::shard`], this guarantees that tuples with the same key
+    /// end up at the same worker, but the worker is selected by `scheme`.
+    /// Since the result is not partitioned by the default scheme, it isn't
+    /// marked as sharded, and operators that shard their inputs will
+    /// re-shard it.  Operators that consume two streams sharded this way must
+    /// use the same scheme for both.
+    #[track_caller]
+    pub fn shard_with(&self, scheme: &PartitionScheme<IB::Key>) -> Stream<C, IB>
+    where
+        IB: Batch + Send,
+    {
+        let location = Location::caller();
+
+        Runtime::runtime()
+            .filter(|runtime| runtime.num_workers() > 1)
+            .map(|runtime| {
+                let num_workers = runtime.num_workers();
+                let scheme = scheme.clone();
+
+                let mut builders = Vec::with_capacity(num_workers);
+                let (sender, receiver) = new_exchange_operators(
+                    &runtime,
+                    Runtime::worker_index(),
+                    Some(location),
+                    move |batch: IB, batches: &mut Vec<IB>| {
+                        Self::shard_batch(&batch, num_workers, &mut builders, batches, |key| {
+                            scheme.partition(key, num_workers)
+                        });
+                    },
+                    |trace: &mut Spine<IB>, batch: IB| trace.insert(batch),
+                );
+
+                self.circuit()
+                    .add_exchange(sender, receiver, self)
+                    .consolidate()
+            })
+            .unwrap_or_else(|| self.clone())
+    }
+
+    // Partitions the batch into `shards` partitions, sending each key to the
+    // partition returned by `partition`.
+    fn shard_batch<OB, P>(
         batch: &IB,
         shards: usize,
         builders: &mut Vec<OB::Builder>,
         outputs: &mut Vec<OB>,
+        partition: P,
     ) where
         OB: Batch<Key = IB::Key, Val = IB::Val, Time = (), R = IB::R>,
+        P: Fn(&IB::Key) -> usize,
     {
         builders.clear();
 
@@ -185,7 +304,7 @@ where
         let mut cursor = batch.cursor();
 
         while cursor.key_valid() {
-            let batch_index = default_hash(cursor.key()) as usize % shards;
+            let batch_index = partition(cursor.key());
             while cursor.val_valid() {
                 builders[batch_index].push((
                     OB::item_from(cursor.key().clone(), cursor.val().clone()),
@@ -3,8 +3,9 @@
 use crate::{
     algebra::{HasOne, IndexedZSet, ZRingValue},
     circuit::{Circuit, Stream, WithClock},
+    operator::FilterMap,
     trace::Batch,
-    DBTimestamp, OrdIndexedZSet,
+    DBData, DBTimestamp, DBWeight, OrdIndexedZSet, OrdZSet,
 };
 
 impl<C, Z> Stream<C, Z>
@@ -51,6 +52,23 @@ where
         self.distinct().weighted_count_generic()
     }
 
+    /// Incrementally counts the distinct values of each key in `self`.
+    ///
+    /// This is [`Self::distinct_count`] under a name that reflects its use
+    /// for monitoring fan-out: outputs an indexed Z-set that maps each key to
+    /// the number of its distinct values with positive weights, e.g., to
+    /// detect keys accumulating too many values before a join.  Both the
+    /// input and output are streams of updates: when a value is inserted or
+    /// retracted, the operator retracts the old count of its key and inserts
+    /// the new one.
+    #[allow(clippy::type_complexity)]
+    pub fn value_count(&self) -> Stream<C, OrdIndexedZSet<Z::Key, Z::R, Z::R>>
+    where
+        Z: Send,
+    {
+        self.distinct_count()
+    }
+
     /// Non-incrementally sums the weights for each key `self` into an indexed
     /// Z-set that maps from the original keys to the weights.  Both the
     /// input and output are streams of data (not updates).
@@ -93,6 +111,30 @@ where
     }
 }
 
+impl<C, K, R> Stream<C, OrdZSet<K, R>>
+where
+    C: Circuit,
+    <C as WithClock>::Time: DBTimestamp,
+    K: DBData,
+    R: DBWeight + ZRingValue,
+{
+    /// Incrementally counts the values in `self` that fall into each bucket
+    /// of a histogram.
+    ///
+    /// `bounds` must be sorted.  It splits the range of values into
+    /// `bounds.len() + 1` buckets, where bucket `i` contains values `v` such
+    /// that `bounds[i-1] <= v < bounds[i]`.  Outputs an indexed Z-set that
+    /// maps each non-empty bucket index to the total weight of the values in
+    /// the bucket.  Both the input and output are streams of updates: a
+    /// change to a bucket is output as a retraction of the old count and an
+    /// insertion of the new one.
+    #[allow(clippy::type_complexity)]
+    pub fn histogram(&self, bounds: Vec<K>) -> Stream<C, OrdIndexedZSet<usize, R, R>> {
+        self.map(move |v| bounds.partition_point(|bound| bound <= v))
+            .weighted_count()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::{
@@ -104,6 +146,69 @@ mod test {
     use core::ops::Range;
     use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 
+    #[test]
+    fn histogram_test() {
+        let (circuit, (input_handle, output)) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) = circuit.add_input_zset::<u64, isize>();
+
+            Ok((input_handle, input.histogram(vec![10, 100]).output()))
+        })
+        .unwrap();
+
+        input_handle.append(&mut vec![(1, 1), (5, 1), (50, 1), (500, 1)]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {0 => {2 => 1}, 1 => {1 => 1}, 2 => {1 => 1}}
+        );
+
+        // Retracting a value decrements its bucket.
+        input_handle.append(&mut vec![(5, -1), (60, 1)]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {0 => {2 => -1, 1 => 1}, 1 => {1 => -1, 2 => 1}}
+        );
+
+        input_handle.append(&mut vec![(500, -1)]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), indexed_zset! {2 => {1 => -1}});
+    }
+
+    #[test]
+    fn value_count_test() {
+        let (circuit, (input_handle, output)) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+
+            Ok((input_handle, input.value_count().output()))
+        })
+        .unwrap();
+
+        input_handle.append(&mut vec![(1, (10, 1)), (1, (11, 1)), (2, (20, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {1 => {2 => 1}, 2 => {1 => 1}}
+        );
+
+        // Duplicates of an existing value don't change the count.
+        input_handle.append(&mut vec![(1, (10, 1)), (1, (12, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), indexed_zset! {1 => {2 => -1, 3 => 1}});
+
+        // Retracting a value drops the count, retracting the last value of a
+        // key removes the key.
+        input_handle.append(&mut vec![(1, (11, -1)), (2, (20, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {1 => {3 => -1, 2 => 1}, 2 => {1 => -1}}
+        );
+
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), OrdIndexedZSet::empty(()));
+    }
+
     #[test]
     fn weighted_count_test() {
         let (circuit, (counts, stream_counts, expected_counts)) =
@@ -0,0 +1,202 @@
+//! Operator that suppresses rapid oscillations in the weights of keys.
+
+use crate::{
+    algebra::IndexedZSet,
+    circuit::{
+        metadata::OperatorLocation,
+        operator_traits::{Operator, UnaryOperator},
+        Circuit, Scope,
+    },
+    trace::{cursor::Cursor, BatchReader, Builder},
+    RootCircuit, Stream,
+};
+use std::{borrow::Cow, collections::BTreeMap, panic::Location};
+
+impl<Z> Stream<RootCircuit, Z>
+where
+    Z: IndexedZSet + Send,
+{
+    /// Delay changes to each key until the key has been stable for `steps`
+    /// clock cycles.
+    ///
+    /// The operator accumulates changes to each key in its buffer.  A key
+    /// with a change at clock cycle `t` is held back until cycle
+    /// `t + steps`.  Any change to the same key in the meantime restarts the
+    /// countdown.  Once the countdown expires, the operator outputs the net
+    /// change to the key accumulated in the buffer, which is empty if the
+    /// changes cancelled out.  For instance, with `steps = 2` a key updated
+    /// with weights `+1`, `-1`, `+1` at cycles 1, 2, and 3 is output with
+    /// weight `+1` at cycle 5.
+    ///
+    /// This reduces churn in the output of the circuit at the cost of
+    /// latency, which helps sinks that can't keep up with high update rates.
+    /// With `steps = 0`, the operator outputs its input unmodified.
+    ///
+    /// The circuit does not evaluate operators at teardown, so changes still
+    /// buffered when the circuit is destroyed are lost.
+    #[track_caller]
+    pub fn debounce(&self, steps: u64) -> Self {
+        self.circuit()
+            .add_unary_operator(Debounce::new(steps, Location::caller()), &self.shard())
+            .mark_sharded()
+    }
+}
+
+/// Operator that holds back changes to each key until the key has been stable
+/// for a number of clock cycles.
+///
+/// See [`Stream::debounce`].
+pub struct Debounce<Z>
+where
+    Z: BatchReader,
+{
+    steps: u64,
+    // The number of clock cycles evaluated so far.
+    step: u64,
+    // Net changes accumulated for keys that are waiting for their countdown
+    // to expire.
+    pending: Option<Z>,
+    // Clock cycle at which the changes to each key will be released.
+    deadlines: BTreeMap<Z::Key, u64>,
+    location: &'static Location<'static>,
+}
+
+impl<Z> Debounce<Z>
+where
+    Z: BatchReader,
+{
+    pub fn new(steps: u64, location: &'static Location<'static>) -> Self {
+        Self {
+            steps,
+            step: 0,
+            pending: None,
+            deadlines: BTreeMap::new(),
+            location,
+        }
+    }
+}
+
+impl<Z> Operator for Debounce<Z>
+where
+    Z: BatchReader,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("Debounce")
+    }
+
+    fn location(&self) -> OperatorLocation {
+        Some(self.location)
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        self.deadlines.is_empty()
+    }
+}
+
+impl<Z> UnaryOperator<Z, Z> for Debounce<Z>
+where
+    Z: IndexedZSet,
+{
+    fn eval(&mut self, delta: &Z) -> Z {
+        self.step += 1;
+
+        let mut cursor = delta.cursor();
+        while cursor.key_valid() {
+            self.deadlines
+                .insert(cursor.key().clone(), self.step + self.steps);
+            cursor.step_key();
+        }
+
+        let pending = match self.pending.take() {
+            Some(pending) => pending.merge(delta),
+            None => delta.clone(),
+        };
+
+        let expired = self
+            .deadlines
+            .iter()
+            .filter(|(_, deadline)| **deadline <= self.step)
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>();
+
+        if expired.is_empty() {
+            if !pending.is_empty() {
+                self.pending = Some(pending);
+            }
+            return Z::empty(());
+        }
+
+        for key in expired.iter() {
+            self.deadlines.remove(key);
+        }
+
+        // Split pending changes into keys whose countdown has expired and
+        // keys that are still waiting.
+        let mut output = Z::Builder::new_builder(());
+        let mut waiting = Z::Builder::new_builder(());
+
+        let mut cursor = pending.cursor();
+        while cursor.key_valid() {
+            let builder = if self.deadlines.contains_key(cursor.key()) {
+                &mut waiting
+            } else {
+                &mut output
+            };
+
+            while cursor.val_valid() {
+                builder.push((
+                    Z::item_from(cursor.key().clone(), cursor.val().clone()),
+                    cursor.weight(),
+                ));
+                cursor.step_val();
+            }
+            cursor.step_key();
+        }
+
+        let waiting = waiting.done();
+        if !waiting.is_empty() {
+            self.pending = Some(waiting);
+        }
+
+        output.done()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{trace::Batch, zset, OrdZSet, RootCircuit};
+
+    #[test]
+    fn debounce_test() {
+        let (circuit, (input_handle, output)) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) = circuit.add_input_zset::<u64, isize>();
+
+            Ok((input_handle, input.debounce(2).output()))
+        })
+        .unwrap();
+
+        // Key 1 flips at every step; key 2 is stable after the first step.
+        input_handle.push(1, 1);
+        input_handle.push(2, 1);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), OrdZSet::empty(()));
+
+        input_handle.push(1, -1);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), OrdZSet::empty(()));
+
+        input_handle.push(1, 1);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! {2 => 1});
+
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), OrdZSet::empty(()));
+
+        // Key 1 has been stable for two steps.
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! {1 => 1});
+
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), OrdZSet::empty(()));
+    }
+}
@@ -0,0 +1,152 @@
+//! Operator that collapses repeated updates within a clock cycle.
+
+use crate::{
+    algebra::{HasOne, IndexedZSet, ZRingValue},
+    circuit::{
+        metadata::OperatorLocation,
+        operator_traits::{Operator, UnaryOperator},
+        Circuit, Scope, Stream,
+    },
+    trace::{cursor::Cursor, Builder},
+};
+use std::{borrow::Cow, marker::PhantomData, ops::Neg, panic::Location};
+
+impl<C, Z> Stream<C, Z>
+where
+    C: Circuit,
+    Z: IndexedZSet + Send,
+    Z::R: ZRingValue,
+{
+    /// Collapse identical `(key, value)` tuples received during the same
+    /// clock cycle into a single occurrence.
+    ///
+    /// Replaces the weight of each tuple in the input batch with its sign:
+    /// tuples with positive weights are output with weight `1`, tuples with
+    /// negative weights with weight `-1`.  This makes insertions and
+    /// deletions idempotent within a step, which is useful for sources with
+    /// at-least-once delivery semantics that can deliver the same update
+    /// more than once.
+    ///
+    /// Unlike [`distinct`](`Self::distinct`), this operator only looks at the
+    /// current batch and keeps no state: a tuple inserted at two different
+    /// steps is output twice, and deletions are preserved rather than
+    /// dropped.
+    #[track_caller]
+    pub fn dedup_within_step(&self) -> Stream<C, Z> {
+        self.circuit()
+            .add_unary_operator(DedupWithinStep::new(Location::caller()), &self.shard())
+            .mark_sharded()
+    }
+}
+
+/// Operator that replaces the weight of each tuple in its input with the
+/// sign of the weight.
+///
+/// See [`Stream::dedup_within_step`].
+pub struct DedupWithinStep<Z> {
+    location: &'static Location<'static>,
+    _type: PhantomData<Z>,
+}
+
+impl<Z> DedupWithinStep<Z> {
+    pub fn new(location: &'static Location<'static>) -> Self {
+        Self {
+            location,
+            _type: PhantomData,
+        }
+    }
+}
+
+impl<Z> Operator for DedupWithinStep<Z>
+where
+    Z: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("DedupWithinStep")
+    }
+
+    fn location(&self) -> OperatorLocation {
+        Some(self.location)
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<Z> UnaryOperator<Z, Z> for DedupWithinStep<Z>
+where
+    Z: IndexedZSet,
+    Z::R: ZRingValue,
+{
+    fn eval(&mut self, input: &Z) -> Z {
+        let mut builder = Z::Builder::with_capacity((), input.len());
+        let mut cursor = input.cursor();
+
+        while cursor.key_valid() {
+            while cursor.val_valid() {
+                let weight = cursor.weight();
+                let sign = if weight.ge0() {
+                    Z::R::one()
+                } else {
+                    Z::R::one().neg()
+                };
+                builder.push((
+                    Z::item_from(cursor.key().clone(), cursor.val().clone()),
+                    sign,
+                ));
+                cursor.step_val();
+            }
+            cursor.step_key();
+        }
+
+        builder.done()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{indexed_zset, trace::Batch, OrdIndexedZSet, RootCircuit};
+
+    #[test]
+    fn dedup_within_step_test() {
+        let (circuit, (input_handle, deduped, counts)) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+
+            let deduped = input.dedup_within_step();
+            let counts = deduped.weighted_count().output();
+
+            Ok((input_handle, deduped.output(), counts))
+        })
+        .unwrap();
+
+        // The same tuple delivered twice in one step is counted once.
+        input_handle.append(&mut vec![
+            (1, (10, 1)),
+            (1, (10, 1)),
+            (1, (11, 1)),
+            (2, (20, 3)),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(
+            deduped.consolidate(),
+            indexed_zset! {1 => {10 => 1, 11 => 1}, 2 => {20 => 1}}
+        );
+        assert_eq!(
+            counts.consolidate(),
+            indexed_zset! {1 => {2 => 1}, 2 => {1 => 1}}
+        );
+
+        // Repeated deletions collapse too, and nothing is remembered across
+        // steps.
+        input_handle.append(&mut vec![(1, (10, -1)), (1, (10, -1)), (3, (30, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            deduped.consolidate(),
+            indexed_zset! {1 => {10 => -1}, 3 => {30 => 1}}
+        );
+
+        circuit.step().unwrap();
+        assert_eq!(deduped.consolidate(), OrdIndexedZSet::empty(()));
+    }
+}
@@ -1,15 +1,18 @@
 //! Distinct operator.
 
 use crate::{
-    algebra::{AddByRef, HasOne, HasZero, IndexedZSet, Lattice, PartialOrder, Present, ZRingValue},
+    algebra::{
+        AddByRef, HasOne, HasZero, IndexedZSet, Lattice, PartialOrder, Present, ZRingValue, ZSet,
+    },
     circuit::{
         metadata::{MetaItem, OperatorMeta},
-        operator_traits::{BinaryOperator, Operator, UnaryOperator},
+        operator_traits::{BinaryOperator, Operator, QuaternaryOperator, UnaryOperator},
         Circuit, GlobalNodeId, Scope, Stream, WithClock,
     },
     circuit_cache_key,
+    operator::{FilterMap, Min},
     trace::{ord::OrdValSpine, Batch, BatchReader, Builder, Cursor as TraceCursor, Trace},
-    DBTimestamp, OrdIndexedZSet, Timestamp,
+    DBData, DBTimestamp, OrdIndexedZSet, OrdZSet, Timestamp,
 };
 use size_of::SizeOf;
 use std::{
@@ -151,6 +154,92 @@ where
             )
             .clone()
     }
+
+    /// Incrementally deduplicate input stream by a projection of its
+    /// records.
+    ///
+    /// Groups records with positive weights in the integral of the input
+    /// stream by the key returned by `eq_key` and keeps exactly one
+    /// representative per group with weight `1`.  The representative is the
+    /// smallest record in the group according to the record type's [`Ord`]
+    /// implementation.  For example, to keep the earliest row for each id in
+    /// a relation of `(id, timestamp, ..)` tuples, use `|r| r.0` as `eq_key`.
+    ///
+    /// When the representative is deleted, it is replaced with the smallest
+    /// remaining record in its group, if any.
+    pub fn distinct_by<F, EK>(&self, eq_key: F) -> Stream<C, OrdZSet<Z::Key, Z::R>>
+    where
+        Z: ZSet + Send,
+        Z::R: ZRingValue,
+        <C as WithClock>::Time: DBTimestamp,
+        F: Fn(&Z::Key) -> EK + Clone + 'static,
+        EK: DBData,
+    {
+        self.distinct()
+            .group_by(eq_key)
+            .aggregate(Min)
+            .map(|(_eq_key, record)| record.clone())
+    }
+
+    /// Compute the union of two streams of batches with duplicates
+    /// eliminated.
+    ///
+    /// At each clock cycle, outputs `(key, value, 1)` for each `(key, value)`
+    /// pair whose weights in the `self` and `other` batches add up to a
+    /// positive number.  This is equivalent to
+    /// `self.plus(other).stream_distinct()`, but computes the result in a
+    /// single pass over both batches without materializing their sum.
+    pub fn stream_union_distinct(&self, other: &Stream<C, Z>) -> Stream<C, Z>
+    where
+        Z: IndexedZSet + Send,
+        Z::R: ZRingValue,
+    {
+        self.circuit()
+            .add_binary_operator(UnionDistinct::new(), &self.shard(), &other.shard())
+            .mark_sharded()
+    }
+
+    /// Incremental version of
+    /// [`stream_union_distinct`](`Self::stream_union_distinct`), which
+    /// implements SQL `UNION`.
+    ///
+    /// Given streams of changes to relations `A` and `B`, computes the stream
+    /// of changes to `distinct(A + B)`.  A record that occurs in both
+    /// relations appears in the output once, and is only retracted once it
+    /// has been deleted from both relations.
+    ///
+    /// In the root scope, the operator looks up the changes to both inputs in
+    /// the integrals of `A` and `B` in a single pass, without materializing
+    /// `A + B` or its integral.  The integrals are shared with other
+    /// operators that integrate the same streams, e.g., joins.  In nested
+    /// scopes, it is equivalent to `self.plus(other).distinct()`.
+    pub fn union_distinct(&self, other: &Stream<C, Z>) -> Stream<C, Z>
+    where
+        Z: IndexedZSet + Send,
+        Z::R: ZRingValue,
+        <C as WithClock>::Time: DBTimestamp,
+    {
+        let circuit = self.circuit();
+        let left = self.shard();
+        let right = other.shard();
+
+        if circuit.root_scope() == 0 {
+            circuit.region("union_distinct", || {
+                circuit
+                    .add_quaternary_operator(
+                        UnionDistinctIncrementalTotal::new(),
+                        &left,
+                        &right,
+                        &left.integrate_trace().delay_trace(),
+                        &right.integrate_trace().delay_trace(),
+                    )
+                    .mark_sharded()
+                    .mark_distinct()
+            })
+        } else {
+            left.plus(&right).distinct()
+        }
+    }
 }
 
 /// `Distinct` operator changes all weights in the support of a Z-set to 1.
@@ -197,6 +286,226 @@ where
     }
 }
 
+/// `UnionDistinct` operator computes the sum of two Z-sets and changes all
+/// weights in its support to 1.
+///
+/// See [`Stream::stream_union_distinct`].
+pub struct UnionDistinct<Z> {
+    _type: PhantomData<Z>,
+}
+
+impl<Z> UnionDistinct<Z> {
+    pub fn new() -> Self {
+        Self { _type: PhantomData }
+    }
+}
+
+impl<Z> Default for UnionDistinct<Z> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Z> Operator for UnionDistinct<Z>
+where
+    Z: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("UnionDistinct")
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<Z> BinaryOperator<Z, Z, Z> for UnionDistinct<Z>
+where
+    Z: IndexedZSet,
+    Z::R: ZRingValue,
+{
+    fn eval(&mut self, left: &Z, right: &Z) -> Z {
+        let mut builder = Z::Builder::with_capacity((), left.len() + right.len());
+
+        for_each_sum(left, right, |key, val, weight| {
+            if weight.ge0() && !weight.is_zero() {
+                builder.push((Z::item_from(key.clone(), val.clone()), HasOne::one()));
+            }
+        });
+
+        builder.done()
+    }
+}
+
+/// Call `f` for each key/value pair in the union of the supports of `left`
+/// and `right`, in order, with the sum of its weights in both batches.
+fn for_each_sum<Z, F>(left: &Z, right: &Z, mut f: F)
+where
+    Z: IndexedZSet,
+    F: FnMut(&Z::Key, &Z::Val, Z::R),
+{
+    let mut left_cursor = left.cursor();
+    let mut right_cursor = right.cursor();
+
+    while left_cursor.key_valid() || right_cursor.key_valid() {
+        let key_order = match (left_cursor.key_valid(), right_cursor.key_valid()) {
+            (true, true) => left_cursor.key().cmp(right_cursor.key()),
+            (true, false) => Ordering::Less,
+            _ => Ordering::Greater,
+        };
+
+        match key_order {
+            Ordering::Less => {
+                while left_cursor.val_valid() {
+                    let weight = left_cursor.weight();
+                    f(left_cursor.key(), left_cursor.val(), weight);
+                    left_cursor.step_val();
+                }
+                left_cursor.step_key();
+            }
+            Ordering::Greater => {
+                while right_cursor.val_valid() {
+                    let weight = right_cursor.weight();
+                    f(right_cursor.key(), right_cursor.val(), weight);
+                    right_cursor.step_val();
+                }
+                right_cursor.step_key();
+            }
+            Ordering::Equal => {
+                while left_cursor.val_valid() || right_cursor.val_valid() {
+                    let val_order = match (left_cursor.val_valid(), right_cursor.val_valid()) {
+                        (true, true) => left_cursor.val().cmp(right_cursor.val()),
+                        (true, false) => Ordering::Less,
+                        _ => Ordering::Greater,
+                    };
+
+                    match val_order {
+                        Ordering::Less => {
+                            let weight = left_cursor.weight();
+                            f(left_cursor.key(), left_cursor.val(), weight);
+                            left_cursor.step_val();
+                        }
+                        Ordering::Greater => {
+                            let weight = right_cursor.weight();
+                            f(right_cursor.key(), right_cursor.val(), weight);
+                            right_cursor.step_val();
+                        }
+                        Ordering::Equal => {
+                            let weight = left_cursor.weight().add_by_ref(&right_cursor.weight());
+                            f(left_cursor.key(), left_cursor.val(), weight);
+                            left_cursor.step_val();
+                            right_cursor.step_val();
+                        }
+                    }
+                }
+                left_cursor.step_key();
+                right_cursor.step_key();
+            }
+        }
+    }
+}
+
+/// Incremental version of [`UnionDistinct`] that only works in the top-level
+/// scope.
+///
+/// Takes streams `a` and `b` of changes to relations `A` and `B` and the
+/// delayed integrals `z^-1(A)` and `z^-1(B)`, and computes
+/// `distinct(A + B) - distinct(z^-1(A) + z^-1(B))` incrementally, by only
+/// considering values in the support of `a + b`.
+struct UnionDistinctIncrementalTotal<Z, I> {
+    _type: PhantomData<(Z, I)>,
+}
+
+impl<Z, I> UnionDistinctIncrementalTotal<Z, I> {
+    pub fn new() -> Self {
+        Self { _type: PhantomData }
+    }
+}
+
+impl<Z, I> Default for UnionDistinctIncrementalTotal<Z, I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Z, I> Operator for UnionDistinctIncrementalTotal<Z, I>
+where
+    Z: 'static,
+    I: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("UnionDistinctIncrementalTotal")
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<Z, I> QuaternaryOperator<Z, Z, I, I, Z> for UnionDistinctIncrementalTotal<Z, I>
+where
+    Z: IndexedZSet,
+    Z::R: ZRingValue,
+    I: BatchReader<Key = Z::Key, Val = Z::Val, Time = (), R = Z::R> + Clone,
+{
+    fn eval<'a>(
+        &mut self,
+        delta_a: Cow<'a, Z>,
+        delta_b: Cow<'a, Z>,
+        delayed_integral_a: Cow<'a, I>,
+        delayed_integral_b: Cow<'a, I>,
+    ) -> Z {
+        let mut builder = Z::Builder::with_capacity((), delta_a.len() + delta_b.len());
+        let mut integral_cursor_a = delayed_integral_a.cursor();
+        let mut integral_cursor_b = delayed_integral_b.cursor();
+
+        for_each_sum(delta_a.as_ref(), delta_b.as_ref(), |key, val, w: Z::R| {
+            let old_weight = weight_of(&mut integral_cursor_a, key, val).add_by_ref(&weight_of(
+                &mut integral_cursor_b,
+                key,
+                val,
+            ));
+            let new_weight = old_weight.add_by_ref(&w);
+
+            if old_weight.le0() {
+                // Weight changes from non-positive to positive.
+                if new_weight.ge0() && !new_weight.is_zero() {
+                    builder.push((Z::item_from(key.clone(), val.clone()), HasOne::one()));
+                }
+            } else if new_weight.le0() {
+                // Weight changes from positive to non-positive.
+                builder.push((Z::item_from(key.clone(), val.clone()), Z::R::one().neg()));
+            }
+        });
+
+        builder.done()
+    }
+}
+
+/// Look up the weight of `(key, val)` in the batch under `cursor`.
+///
+/// Since the cursor only moves forward, successive lookups must be in
+/// ascending key/value order.
+fn weight_of<K, V, R, C>(cursor: &mut C, key: &K, val: &V) -> R
+where
+    K: Ord,
+    V: Ord,
+    R: HasZero,
+    C: TraceCursor<K, V, (), R>,
+{
+    cursor.seek_key(key);
+    if cursor.get_key() != Some(key) {
+        return HasZero::zero();
+    }
+
+    cursor.seek_val(val);
+    if cursor.get_val() == Some(val) {
+        cursor.weight()
+    } else {
+        HasZero::zero()
+    }
+}
+
 /// Incremental version of the distinct operator that only works in the
 /// top-level scope (i.e., for totally ordered timestamps).
 ///
@@ -1130,4 +1439,112 @@ mod test {
             circuit.kill().unwrap();
         }
     }
+
+    #[test]
+    fn distinct_by_test() {
+        let (circuit, (input, output)) = RootCircuit::build(move |circuit| {
+            // `(id, timestamp, name)` records.
+            let (input, input_handle) = circuit.add_input_zset::<(u64, u64, String), isize>();
+
+            let output = input.distinct_by(|(id, _, _)| *id).output();
+
+            Ok((input_handle, output))
+        })
+        .unwrap();
+
+        // Keep the record with the smallest timestamp for each id.
+        input.append(&mut vec![
+            ((1, 10, "a".to_string()), 1),
+            ((1, 5, "b".to_string()), 1),
+            ((1, 5, "b".to_string()), 1),
+            ((2, 7, "c".to_string()), 1),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            zset! { (1, 5, "b".to_string()) => 1, (2, 7, "c".to_string()) => 1 }
+        );
+
+        // Deleting the representative promotes the next smallest record.
+        input.append(&mut vec![((1, 5, "b".to_string()), -2)]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            zset! { (1, 5, "b".to_string()) => -1, (1, 10, "a".to_string()) => 1 }
+        );
+
+        // A record with a smaller timestamp replaces the representative.
+        input.append(&mut vec![((2, 3, "d".to_string()), 1)]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            zset! { (2, 7, "c".to_string()) => -1, (2, 3, "d".to_string()) => 1 }
+        );
+    }
+
+    #[test]
+    fn union_distinct_test() {
+        let (circuit, (input1, input2, stream_output, output)) =
+            RootCircuit::build(move |circuit| {
+                let (stream1, input1) = circuit.add_input_zset::<u64, isize>();
+                let (stream2, input2) = circuit.add_input_zset::<u64, isize>();
+
+                let stream_output = stream1.stream_union_distinct(&stream2).output();
+                let output = stream1.union_distinct(&stream2).output();
+
+                Ok((input1, input2, stream_output, output))
+            })
+            .unwrap();
+
+        // Key 2 occurs in both inputs; key 4 cancels out.
+        input1.append(&mut vec![(1, 1), (2, 2), (4, -1)]);
+        input2.append(&mut vec![(2, 1), (3, 3), (4, 1)]);
+        circuit.step().unwrap();
+        assert_eq!(stream_output.consolidate(), zset! {1 => 1, 2 => 1, 3 => 1});
+        assert_eq!(output.consolidate(), zset! {1 => 1, 2 => 1, 3 => 1});
+
+        // Deleting key 2 from one input doesn't remove it from the union.
+        input1.append(&mut vec![(2, -2)]);
+        circuit.step().unwrap();
+        assert_eq!(stream_output.consolidate(), OrdZSet::empty(()));
+        assert_eq!(output.consolidate(), OrdZSet::empty(()));
+
+        // Retracting the last occurrence of a key removes it.
+        input1.append(&mut vec![(1, -1)]);
+        input2.append(&mut vec![(2, -1)]);
+        circuit.step().unwrap();
+        assert_eq!(stream_output.consolidate(), OrdZSet::empty(()));
+        assert_eq!(output.consolidate(), zset! {1 => -1, 2 => -1});
+    }
+
+    // Compare the fused operator against `plus` followed by `distinct` on
+    // updates with weights other than 1.
+    #[test]
+    fn union_distinct_matches_plus_distinct_test() {
+        let (circuit, (input1, input2, output, expected)) = RootCircuit::build(move |circuit| {
+            let (stream1, input1) = circuit.add_input_zset::<u64, isize>();
+            let (stream2, input2) = circuit.add_input_zset::<u64, isize>();
+
+            let output = stream1.union_distinct(&stream2).output();
+            let expected = stream1.plus(&stream2).distinct().output();
+
+            Ok((input1, input2, output, expected))
+        })
+        .unwrap();
+
+        #[allow(clippy::type_complexity)]
+        let steps: Vec<(Vec<(u64, isize)>, Vec<(u64, isize)>)> = vec![
+            (vec![(1, 3), (2, -1), (3, 1)], vec![(2, 2), (3, -1), (4, 1)]),
+            (vec![(1, -2), (2, 1), (5, 2)], vec![(3, 1), (4, -1)]),
+            (vec![(1, -1), (3, -1)], vec![(2, -2), (5, -1), (6, 1)]),
+            (vec![(5, -1)], vec![(1, 1), (3, 1)]),
+        ];
+
+        for (mut updates1, mut updates2) in steps {
+            input1.append(&mut updates1);
+            input2.append(&mut updates2);
+            circuit.step().unwrap();
+            assert_eq!(output.consolidate(), expected.consolidate());
+        }
+    }
 }
@@ -0,0 +1,182 @@
+//! Operators that report keys occurring more than once in an indexed Z-set.
+
+use crate::{
+    algebra::{AddAssignByRef, HasOne, HasZero, IndexedZSet, ZRingValue},
+    circuit::{
+        metadata::OperatorLocation,
+        operator_traits::{Operator, UnaryOperator},
+        Circuit, Scope, Stream,
+    },
+    trace::{cursor::Cursor, Batch, BatchReader, Builder, Spine},
+    OrdZSet, RootCircuit,
+};
+use size_of::SizeOf;
+use std::{borrow::Cow, marker::PhantomData, panic::Location};
+
+impl<C, B> Stream<C, B>
+where
+    C: Circuit,
+    B: IndexedZSet + Send,
+    B::R: ZRingValue,
+{
+    /// Output the keys of each input batch whose total weight exceeds one.
+    ///
+    /// The total weight of a key is the sum of the weights of all of its
+    /// values.  Each reported key is output with weight one.  This is useful
+    /// for checking uniqueness constraints: in a collection whose keys are
+    /// meant to be unique, every key reported by this operator is a
+    /// violation.
+    #[track_caller]
+    pub fn stream_duplicates(&self) -> Stream<C, OrdZSet<B::Key, B::R>> {
+        self.circuit()
+            .add_unary_operator(Duplicates::new(Location::caller()), &self.shard())
+            .mark_sharded()
+    }
+}
+
+impl<B> Stream<RootCircuit, B>
+where
+    B: IndexedZSet + Send,
+    B::R: ZRingValue,
+    Spine<B>: SizeOf,
+{
+    /// Incremental version of [`stream_duplicates`](`Self::stream_duplicates`).
+    ///
+    /// Maintains the set of keys whose total weight in the integral of the
+    /// input stream exceeds one, and outputs changes to this set: a key is
+    /// inserted once it becomes a duplicate and retracted once its total
+    /// weight drops back to one or below.
+    #[track_caller]
+    pub fn duplicates(&self) -> Stream<RootCircuit, OrdZSet<B::Key, B::R>> {
+        self.circuit()
+            .add_unary_operator(
+                Duplicates::new(Location::caller()),
+                &self.shard().integrate_trace(),
+            )
+            .mark_sharded()
+            .differentiate()
+    }
+}
+
+/// Operator that outputs the keys of its input whose total weight exceeds
+/// one.
+///
+/// The input can be a batch or a trace.
+///
+/// See [`Stream::stream_duplicates`] and [`Stream::duplicates`].
+pub struct Duplicates<T, O> {
+    location: &'static Location<'static>,
+    _types: PhantomData<(T, O)>,
+}
+
+impl<T, O> Duplicates<T, O> {
+    pub fn new(location: &'static Location<'static>) -> Self {
+        Self {
+            location,
+            _types: PhantomData,
+        }
+    }
+}
+
+impl<T, O> Operator for Duplicates<T, O>
+where
+    T: 'static,
+    O: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("Duplicates")
+    }
+
+    fn location(&self) -> OperatorLocation {
+        Some(self.location)
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<T, O> UnaryOperator<T, O> for Duplicates<T, O>
+where
+    T: BatchReader<Time = ()>,
+    T::R: ZRingValue,
+    O: Batch<Key = T::Key, Val = (), Time = (), R = T::R>,
+{
+    fn eval(&mut self, input: &T) -> O {
+        let mut cursor = input.cursor();
+        let mut builder = O::Builder::new_builder(());
+
+        while cursor.key_valid() {
+            let mut total = T::R::zero();
+            while cursor.val_valid() {
+                total.add_assign_by_ref(&cursor.weight());
+                cursor.step_val();
+            }
+
+            if total > T::R::one() {
+                builder.push((O::item_from(cursor.key().clone(), ()), T::R::one()));
+            }
+            cursor.step_key();
+        }
+
+        builder.done()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{trace::Batch, zset, OrdZSet, RootCircuit};
+
+    #[test]
+    fn stream_duplicates_test() {
+        let (circuit, (input_handle, output)) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+
+            Ok((input_handle, input.stream_duplicates().output()))
+        })
+        .unwrap();
+
+        // Key 1 occurs twice with the same value, key 2 has two distinct
+        // values, key 3 is unique.
+        input_handle.append(&mut vec![
+            (1, (10, 2)),
+            (2, (20, 1)),
+            (2, (21, 1)),
+            (3, (30, 1)),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! {1 => 1, 2 => 1});
+
+        // The operator only looks at the current batch.
+        input_handle.append(&mut vec![(3, (30, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), OrdZSet::empty(()));
+    }
+
+    #[test]
+    fn duplicates_test() {
+        let (circuit, (input_handle, output)) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+
+            Ok((input_handle, input.duplicates().output()))
+        })
+        .unwrap();
+
+        input_handle.append(&mut vec![(1, (10, 2)), (2, (20, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! {1 => 1});
+
+        // A second occurrence of key 2 at a later step makes it a duplicate.
+        input_handle.append(&mut vec![(2, (21, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! {2 => 1});
+
+        // Removing one occurrence of key 1 resolves the violation.
+        input_handle.append(&mut vec![(1, (10, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! {1 => -1});
+
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), OrdZSet::empty(()));
+    }
+}
@@ -5,7 +5,9 @@ use crate::{
         operator_traits::{Operator, UnaryOperator},
         Circuit, OwnershipPreference, Scope, Stream,
     },
-    trace::{Batch, BatchReader, Builder, Consumer, Cursor, ValueConsumer},
+    trace::{
+        consolidation::consolidate, Batch, BatchReader, Builder, Consumer, Cursor, ValueConsumer,
+    },
     DBData, DBWeight, OrdIndexedZSet, OrdZSet,
 };
 use std::{
@@ -275,6 +277,114 @@ where
     }
 }
 
+impl<C, K, R> Stream<C, OrdZSet<K, R>>
+where
+    C: Circuit,
+    K: DBData,
+    R: DBWeight,
+{
+    /// Apply a fallible transformation to each record, routing failures to a
+    /// separate dead-letter stream.
+    ///
+    /// Returns a pair of streams.  The first contains `Ok` results of
+    /// `map_func`.  The second contains `(record, error)` pairs for records
+    /// for which `map_func` returned an error.  Each input record appears in
+    /// exactly one of the outputs, with its original weight, so retracting a
+    /// record retracts its output in whichever stream it was sent to.  This
+    /// requires `map_func` to be deterministic.
+    #[track_caller]
+    #[allow(clippy::type_complexity)]
+    pub fn map_or_dead_letter<F, U, E>(
+        &self,
+        map_func: F,
+    ) -> (Stream<C, OrdZSet<U, R>>, Stream<C, OrdZSet<(K, E), R>>)
+    where
+        F: Fn(&K) -> Result<U, E> + Clone + 'static,
+        U: DBData,
+        E: DBData,
+    {
+        // Evaluate `map_func` once per record, then split the results.
+        let results: Stream<C, OrdZSet<(Option<U>, Option<(K, E)>), R>> =
+            self.map(move |k| match map_func(k) {
+                Ok(u) => (Some(u), None),
+                Err(e) => (None, Some((k.clone(), e))),
+            });
+        let ok = results.flat_map(|(u, _)| u.clone());
+        let dead_letter = results.flat_map(|(_, e)| e.clone());
+
+        (ok, dead_letter)
+    }
+}
+
+impl<C, K, V, R> Stream<C, OrdIndexedZSet<K, V, R>>
+where
+    C: Circuit,
+    R: DBWeight,
+    K: DBData,
+    V: DBData,
+{
+    /// Filter an indexed stream, only retaining keys that satisfy the
+    /// `filter_func` predicate, along with all of their values.
+    ///
+    /// Unlike [`FilterMap::filter`], which evaluates its predicate for every
+    /// `(key, value)` pair, this operator evaluates `filter_func` once per
+    /// key and skips all values under rejected keys.
+    pub fn filter_keys<F>(&self, filter_func: F) -> Self
+    where
+        F: Fn(&K) -> bool + 'static,
+    {
+        let filtered = self
+            .circuit()
+            .add_unary_operator(FilterKeys::new(filter_func), &self.try_sharded_version());
+        filtered.mark_sharded_if(self);
+        filtered.mark_distinct_if(self);
+        filtered
+    }
+
+    /// Transform the values of an indexed stream, keeping the original keys.
+    ///
+    /// Applies `map_func` to every `(key, value)` pair and indexes the result
+    /// by the same key.  Since keys don't change, the output is built
+    /// directly from the input ordering without re-indexing it, and remains
+    /// sharded if the input stream is sharded.
+    #[track_caller]
+    pub fn map_values<F, NV>(&self, map_func: F) -> Stream<C, OrdIndexedZSet<K, NV, R>>
+    where
+        F: Fn(&K, &V) -> NV + 'static,
+        NV: DBData,
+    {
+        let mapped = self
+            .circuit()
+            .add_unary_operator(MapValues::new(map_func), self);
+        mapped.mark_sharded_if(self);
+        mapped
+    }
+
+    /// Expand collection-valued values into one record per element, keeping
+    /// the original keys (SQL `UNNEST`).
+    ///
+    /// Applies `func` to every value and outputs a `(key, element)` pair with
+    /// the weight of the original record for each element of the returned
+    /// collection.  A value whose collection is empty produces no output
+    /// records.
+    #[track_caller]
+    pub fn explode<F, I>(&self, func: F) -> Stream<C, OrdIndexedZSet<K, I::Item, R>>
+    where
+        F: Fn(&V) -> I + 'static,
+        I: IntoIterator + 'static,
+        I::Item: DBData,
+    {
+        let exploded = self.flat_map_index(move |(k, v)| {
+            func(v)
+                .into_iter()
+                .map(|item| (k.clone(), item))
+                .collect::<Vec<_>>()
+        });
+        exploded.mark_sharded_if(self);
+        exploded
+    }
+}
+
 /// Internal implementation for filtering [`BatchReader`]s
 pub struct FilterKeys<CI, CO, F> {
     filter: F,
@@ -309,12 +419,12 @@ impl<CI, CO, F> FilterKeys<CI, CO, F> {
         while consumer.key_valid() {
             let (key, mut values) = consumer.next_key();
 
-            // FIXME: We assume that this operator will only be used with `OrdZSet` and that
-            // it has either zero or one values, meaning that it'll skip any additional
-            // values
-            if (self.filter)(&key) && values.value_valid() {
-                let (value, weight, ()) = values.next_value();
-                builder.push((CO::item_from(key, value), weight));
+            // Values under rejected keys are skipped without being consumed.
+            if (self.filter)(&key) {
+                while values.value_valid() {
+                    let (value, weight, ()) = values.next_value();
+                    builder.push((CO::item_from(key.clone(), value), weight));
+                }
             }
         }
 
@@ -561,6 +671,67 @@ where
     }
 }
 
+/// Internal implementation of `OrdIndexedZSet::map_values`.
+pub struct MapValues<CI, CO, F> {
+    map: F,
+    _type: PhantomData<(CI, CO)>,
+}
+
+impl<CI, CO, F> MapValues<CI, CO, F> {
+    pub fn new(map: F) -> Self {
+        Self {
+            map,
+            _type: PhantomData,
+        }
+    }
+}
+
+impl<CI, CO, F> Operator for MapValues<CI, CO, F>
+where
+    CI: 'static,
+    CO: 'static,
+    F: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("MapValues")
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<CI, CO, F> UnaryOperator<CI, CO> for MapValues<CI, CO, F>
+where
+    CI: BatchReader<Time = ()>,
+    CO: Batch<Key = CI::Key, Time = (), R = CI::R>,
+    F: Fn(&CI::Key, &CI::Val) -> CO::Val + 'static,
+{
+    fn eval(&mut self, input: &CI) -> CO {
+        // Keys are unchanged, so the cursor yields them in the order expected
+        // by the builder.  Values of each key only need to be sorted and
+        // consolidated, since `map` may map several values to the same one.
+        let mut builder = CO::Builder::with_capacity((), input.len());
+        let mut values = Vec::new();
+
+        let mut cursor = input.cursor();
+        while cursor.key_valid() {
+            while cursor.val_valid() {
+                values.push(((self.map)(cursor.key(), cursor.val()), cursor.weight()));
+                cursor.step_val();
+            }
+
+            consolidate(&mut values);
+            for (value, weight) in values.drain(..) {
+                builder.push((CO::item_from(cursor.key().clone(), value), weight));
+            }
+            cursor.step_key();
+        }
+
+        builder.done()
+    }
+}
+
 /// Internal implementation of `OrdZSet::map`.
 pub struct MapKeys<CI, CO, FB, FO> {
     map_borrowed: FB,
@@ -716,7 +887,7 @@ mod test {
         trace::ord::OrdZSet,
         zset, Circuit, RootCircuit,
     };
-    use std::vec;
+    use std::{cell::Cell, rc::Rc, vec};
 
     #[test]
     fn filter_map_test() {
@@ -872,4 +1043,139 @@ mod test {
             circuit.step().unwrap();
         }
     }
+
+    #[test]
+    fn filter_keys_test() {
+        let circuit = RootCircuit::build(move |circuit| {
+            let mut input = vec![
+                indexed_zset! { 1 => {1 => 1, 2 => 1}, 2 => {3 => 1, 4 => -1}, 3 => {5 => 1}, 4 => {6 => 2, 7 => 1} },
+                indexed_zset! { 5 => {1 => 1}, 7 => {2 => -1} },
+            ]
+            .into_iter();
+            let expected = vec![
+                indexed_zset! { 2 => {3 => 1, 4 => -1}, 4 => {6 => 2, 7 => 1} },
+                indexed_zset! {},
+            ];
+            let mut output = expected.clone().into_iter();
+            let mut owned_output = expected.into_iter();
+
+            let input = circuit.add_source(Generator::new(move || input.next().unwrap()));
+
+            // Consume the input by reference and by value.
+            let even = input.filter_keys(|k| k % 2 == 0);
+            let even_owned = input.map_index(|(k, v)| (*k, *v)).filter_keys(|k| k % 2 == 0);
+
+            even.inspect(move |batch| assert_eq!(*batch, output.next().unwrap()));
+            even_owned.inspect(move |batch| assert_eq!(*batch, owned_output.next().unwrap()));
+            Ok(())
+        })
+        .unwrap()
+        .0;
+
+        for _ in 0..2 {
+            circuit.step().unwrap();
+        }
+    }
+
+    #[test]
+    fn map_values_test() {
+        let circuit = RootCircuit::build(move |circuit| {
+            let mut input = vec![
+                indexed_zset! { 1 => {1 => 1, 2 => 1}, 2 => {3 => 1, 4 => -1} },
+                indexed_zset! { 3 => {10 => 1, 20 => 2} },
+            ]
+            .into_iter();
+            let mut output = vec![
+                indexed_zset! { 1 => {"1".to_string() => 1, "2".to_string() => 1}, 2 => {"3".to_string() => 1, "4".to_string() => -1} },
+                indexed_zset! { 3 => {"10".to_string() => 1, "20".to_string() => 2} },
+            ]
+            .into_iter();
+
+            circuit
+                .add_source(Generator::new(move || input.next().unwrap()))
+                .map_values(|_k, v: &i64| v.to_string())
+                .inspect(move |batch| assert_eq!(*batch, output.next().unwrap()));
+            Ok(())
+        })
+        .unwrap()
+        .0;
+
+        for _ in 0..2 {
+            circuit.step().unwrap();
+        }
+    }
+
+    #[test]
+    fn explode_test() {
+        let circuit = RootCircuit::build(move |circuit| {
+            let mut input = vec![
+                indexed_zset! { 1 => {vec![10, 20, 30] => 1}, 2 => {vec![] => 1} },
+                indexed_zset! { 1 => {vec![10, 20, 30] => -1}, 3 => {vec![40, 50, 60] => 2} },
+            ]
+            .into_iter();
+            let mut output = vec![
+                indexed_zset! { 1 => {10 => 1, 20 => 1, 30 => 1} },
+                indexed_zset! { 1 => {10 => -1, 20 => -1, 30 => -1}, 3 => {40 => 2, 50 => 2, 60 => 2} },
+            ]
+            .into_iter();
+
+            circuit
+                .add_source(Generator::new(move || input.next().unwrap()))
+                .explode(|v: &Vec<i64>| v.clone())
+                .inspect(move |batch| {
+                    // Empty collections don't produce any rows.
+                    assert_eq!(*batch, output.next().unwrap());
+                });
+            Ok(())
+        })
+        .unwrap()
+        .0;
+
+        for _ in 0..2 {
+            circuit.step().unwrap();
+        }
+    }
+
+    #[test]
+    fn map_or_dead_letter_test() {
+        let calls = Rc::new(Cell::new(0));
+        let calls_clone = calls.clone();
+        let (circuit, (input_handle, ok_output, dead_letter_output)) =
+            RootCircuit::build(move |circuit| {
+                let (input, input_handle) = circuit.add_input_zset::<String, isize>();
+
+                let (ok, dead_letter) = input.map_or_dead_letter(move |s: &String| {
+                    calls_clone.set(calls_clone.get() + 1);
+                    s.parse::<i64>().map_err(|e| e.to_string())
+                });
+
+                Ok((input_handle, ok.output(), dead_letter.output()))
+            })
+            .unwrap();
+
+        let error = "invalid digit found in string".to_string();
+
+        input_handle.push("1".to_string(), 1);
+        input_handle.push("2".to_string(), 2);
+        input_handle.push("foo".to_string(), 1);
+        circuit.step().unwrap();
+        assert_eq!(ok_output.consolidate(), zset! { 1 => 1, 2 => 2 });
+        assert_eq!(
+            dead_letter_output.consolidate(),
+            zset! { ("foo".to_string(), error.clone()) => 1 }
+        );
+        // `map_func` is evaluated once per record.
+        assert_eq!(calls.get(), 3);
+
+        // Retracting a failing record retracts its dead-letter entry without
+        // affecting the main stream.
+        input_handle.push("foo".to_string(), -1);
+        input_handle.push("3".to_string(), 1);
+        circuit.step().unwrap();
+        assert_eq!(ok_output.consolidate(), zset! { 3 => 1 });
+        assert_eq!(
+            dead_letter_output.consolidate(),
+            zset! { ("foo".to_string(), error) => -1 }
+        );
+    }
 }
@@ -1,11 +1,15 @@
 //! Defines an operator that generates an infinite output stream from a single
 //! seed value.
 
-use crate::circuit::{
-    operator_traits::{Data, Operator, SourceOperator},
-    Scope,
+use crate::{
+    algebra::HasZero,
+    circuit::{
+        operator_traits::{Data, Operator, SourceOperator},
+        Scope,
+    },
+    trace::Batch,
 };
-use std::{borrow::Cow, marker::PhantomData};
+use std::{borrow::Cow, fmt::Display, marker::PhantomData, time::Instant};
 
 /// A source operator that yields an infinite output stream
 /// from a generator function.
@@ -27,6 +31,35 @@ where
     }
 }
 
+impl<T> Generator<T, ()> {
+    /// Creates a generator from a fallible closure that signals the end of
+    /// its input.
+    ///
+    /// See [`TryGenerator`].
+    pub fn try_new<E, F>(g: F) -> TryGenerator<T, E, F>
+    where
+        F: FnMut() -> Option<Result<T, E>>,
+    {
+        TryGenerator::new(g)
+    }
+
+    /// Creates a generator that emits at most `records_per_second` records
+    /// per second of wall-clock time.
+    ///
+    /// See [`RateLimitedGenerator`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `records_per_second` is 0.
+    pub fn rate_limited<F>(records_per_second: u64, g: F) -> RateLimitedGenerator<T, F>
+    where
+        T: Batch<Time = ()>,
+        F: FnMut() -> Option<(T::Item, T::R)>,
+    {
+        RateLimitedGenerator::new(records_per_second, g)
+    }
+}
+
 impl<T, F> Operator for Generator<T, F>
 where
     T: Data,
@@ -50,6 +83,181 @@ where
     }
 }
 
+/// A source operator that yields values produced by a fallible generator
+/// function until the function signals the end of input.
+///
+/// At each clock cycle the operator invokes the generator function:
+///
+/// * `Some(Ok(value))` - the operator outputs `value`.
+/// * `Some(Err(error))` - the operator outputs zero and reports `error`,
+///   causing the current step of the circuit to fail with
+///   [`SchedulerError::OperatorError`](`crate::SchedulerError::OperatorError`).
+///   As with other step errors, the circuit should not be evaluated further
+///   after this.
+/// * `None` - the input is exhausted.  The operator outputs zero at this and
+///   all subsequent clock cycles without invoking the generator function
+///   again, i.e., the circuit continues to step normally, receiving no new
+///   inputs from this source.
+///
+/// Use [`Generator::try_new`] to create a `TryGenerator`.
+pub struct TryGenerator<T, E, F> {
+    generator: F,
+    exhausted: bool,
+    error: Option<String>,
+    _t: PhantomData<(T, E)>,
+}
+
+impl<T, E, F> TryGenerator<T, E, F> {
+    fn new(g: F) -> Self {
+        Self {
+            generator: g,
+            exhausted: false,
+            error: None,
+            _t: PhantomData,
+        }
+    }
+}
+
+impl<T, E, F> Operator for TryGenerator<T, E, F>
+where
+    T: Data,
+    E: 'static,
+    F: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("TryGenerator")
+    }
+
+    fn take_error(&mut self) -> Option<String> {
+        self.error.take()
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        self.exhausted
+    }
+}
+
+impl<T, E, F> SourceOperator<T> for TryGenerator<T, E, F>
+where
+    F: FnMut() -> Option<Result<T, E>> + 'static,
+    T: Data + HasZero,
+    E: Display + 'static,
+{
+    fn eval(&mut self) -> T {
+        if self.exhausted {
+            return T::zero();
+        }
+
+        match (self.generator)() {
+            Some(Ok(value)) => value,
+            Some(Err(error)) => {
+                self.error = Some(error.to_string());
+                T::zero()
+            }
+            None => {
+                self.exhausted = true;
+                T::zero()
+            }
+        }
+    }
+}
+
+/// A source operator that emits records produced by a generator function at
+/// a bounded rate.
+///
+/// The rate is enforced by a token bucket that fills up at
+/// `records_per_second` tokens per second of wall-clock time and holds at
+/// most one second worth of tokens.  The bucket is empty when the operator is
+/// created.  At each clock cycle, the operator invokes the generator function
+/// once per available token and outputs the records it returns as a single
+/// batch.  The generator function returns `None` to signal the end of input,
+/// after which the operator outputs empty batches without invoking it again.
+///
+/// The operator never blocks the circuit waiting for tokens.  If no tokens
+/// are available, e.g., because the circuit is stepped more often than
+/// `records_per_second` times per second, the step outputs an empty batch.
+/// To replay an input at a realistic speed, step the circuit in a loop,
+/// sleeping between steps.
+///
+/// Use [`Generator::rate_limited`] to create a `RateLimitedGenerator`.
+pub struct RateLimitedGenerator<T, F> {
+    generator: F,
+    records_per_second: u64,
+    tokens: f64,
+    last_refill: Instant,
+    exhausted: bool,
+    _t: PhantomData<T>,
+}
+
+impl<T, F> RateLimitedGenerator<T, F> {
+    fn new(records_per_second: u64, g: F) -> Self {
+        assert_ne!(records_per_second, 0);
+
+        Self {
+            generator: g,
+            records_per_second,
+            tokens: 0.0,
+            last_refill: Instant::now(),
+            exhausted: false,
+            _t: PhantomData,
+        }
+    }
+
+    /// Add tokens accumulated since the last refill to the bucket.
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.last_refill = now;
+
+        let capacity = self.records_per_second as f64;
+        self.tokens = (self.tokens + elapsed * capacity).min(capacity);
+    }
+}
+
+impl<T, F> Operator for RateLimitedGenerator<T, F>
+where
+    T: Data,
+    F: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("RateLimitedGenerator")
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        self.exhausted
+    }
+}
+
+impl<T, F> SourceOperator<T> for RateLimitedGenerator<T, F>
+where
+    T: Batch<Time = ()> + Data,
+    F: FnMut() -> Option<(T::Item, T::R)> + 'static,
+{
+    fn eval(&mut self) -> T {
+        if self.exhausted {
+            return T::empty(());
+        }
+
+        self.refill();
+
+        let mut tuples = Vec::new();
+        while self.tokens >= 1.0 {
+            match (self.generator)() {
+                Some(tuple) => {
+                    tuples.push(tuple);
+                    self.tokens -= 1.0;
+                }
+                None => {
+                    self.exhausted = true;
+                    break;
+                }
+            }
+        }
+
+        T::from_tuples((), tuples)
+    }
+}
+
 /// Generator operator for nested circuits.
 ///
 /// At each parent clock tick, invokes a user-provided reset closure, which
@@ -100,3 +308,124 @@ where
         (self.generator.as_mut().unwrap())()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{operator::Generator, zset, Circuit, OrdZSet, RootCircuit, SchedulerError};
+    use std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+        thread::sleep,
+        time::{Duration, Instant},
+    };
+
+    #[test]
+    fn try_generator_test() {
+        let outputs = Rc::new(RefCell::new(Vec::new()));
+        let outputs_clone = outputs.clone();
+
+        let (circuit, ()) = RootCircuit::build(move |circuit| {
+            let mut input = vec![zset! { 1 => 1 }, zset! { 2 => 1, 3 => -1 }].into_iter();
+            circuit
+                .add_source(Generator::try_new(move || {
+                    input.next().map(Ok::<OrdZSet<u64, isize>, String>)
+                }))
+                .inspect(move |zset| outputs_clone.borrow_mut().push(zset.clone()));
+            Ok(())
+        })
+        .unwrap();
+
+        for _ in 0..4 {
+            circuit.step().unwrap();
+        }
+
+        assert_eq!(
+            &*outputs.borrow(),
+            &[
+                zset! { 1 => 1 },
+                zset! { 2 => 1, 3 => -1 },
+                zset! {},
+                zset! {}
+            ]
+        );
+    }
+
+    #[test]
+    fn try_generator_error_test() {
+        let (circuit, ()) = RootCircuit::build(move |circuit| {
+            let mut input = vec![
+                Ok::<OrdZSet<u64, isize>, _>(zset! { 1 => 1 }),
+                Err("read error"),
+            ]
+            .into_iter();
+            circuit.add_source(Generator::try_new(move || input.next()));
+            Ok(())
+        })
+        .unwrap();
+
+        circuit.step().unwrap();
+        match circuit.step() {
+            Err(SchedulerError::OperatorError { error, .. }) => assert_eq!(error, "read error"),
+            result => panic!("unexpected result: {result:?}"),
+        }
+    }
+
+    #[test]
+    fn rate_limited_generator_test() {
+        const RATE: u64 = 1000;
+
+        let start = Instant::now();
+        let records = Rc::new(Cell::new(0));
+        let records_clone = records.clone();
+
+        let (circuit, ()) = RootCircuit::build(move |circuit| {
+            let mut n = 0;
+            circuit
+                .add_source(Generator::rate_limited(RATE, move || {
+                    n += 1;
+                    Some((n, 1))
+                }))
+                .inspect(move |zset: &OrdZSet<u64, isize>| {
+                    records_clone.set(records_clone.get() + zset.len())
+                });
+            Ok(())
+        })
+        .unwrap();
+
+        for _ in 0..50 {
+            circuit.step().unwrap();
+            sleep(Duration::from_millis(5));
+        }
+        circuit.step().unwrap();
+
+        let elapsed = start.elapsed().as_secs_f64();
+        assert!(records.get() > 0);
+        assert!(
+            records.get() as f64 <= RATE as f64 * elapsed,
+            "{} records in {elapsed}s",
+            records.get()
+        );
+    }
+
+    #[test]
+    fn rate_limited_generator_empty_step_test() {
+        let outputs = Rc::new(RefCell::new(Vec::new()));
+        let outputs_clone = outputs.clone();
+
+        let (circuit, ()) = RootCircuit::build(move |circuit| {
+            let mut input = vec![(1, 1), (2, 1)].into_iter();
+            circuit
+                .add_source(Generator::rate_limited(1, move || input.next()))
+                .inspect(move |zset: &OrdZSet<u64, isize>| {
+                    outputs_clone.borrow_mut().push(zset.clone())
+                });
+            Ok(())
+        })
+        .unwrap();
+
+        // The bucket starts empty and fills up at one token per second, so
+        // the first step is rate-limited.
+        circuit.step().unwrap();
+        assert_eq!(&*outputs.borrow(), &[zset! {}]);
+    }
+}
@@ -23,6 +23,14 @@ where
     /// ascending order of values), applies projection function `project` to
     /// it and outputs the input value along with this projection.
     ///
+    /// The operator is incremental: a value received at the current clock
+    /// cycle is matched with values received at earlier cycles.  When a new
+    /// value changes the previous value of existing rows, the operator
+    /// retracts their old outputs and emits updated ones.  For instance, with
+    /// `(date, count)` values, `lag(1, ...)` pairs each daily measurement with
+    /// the measurement for the previous date, which can be used to compute
+    /// day-over-day deltas.
+    ///
     /// # Arguments
     ///
     /// * `offset` - offset to the previous value.
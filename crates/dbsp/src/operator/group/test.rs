@@ -2,6 +2,7 @@
 
 use crate::{
     algebra::ZRingValue,
+    indexed_zset,
     trace::{
         cursor::Cursor,
         test_batch::{assert_batch_eq, TestBatch},
@@ -169,6 +170,52 @@ fn lead_test_circuit(
     Ok((input_handle, lead_handle))
 }
 
+#[test]
+fn lag_across_steps_test() {
+    let (circuit, (input_handle, output)) = RootCircuit::build(move |circuit| {
+        let (input, input_handle) = circuit.add_input_indexed_zset::<String, (u32, i64), isize>();
+
+        // Pair each `(day, count)` value with the count of the previous day.
+        let output = input
+            .lag(1, |prev: Option<&(u32, i64)>| prev.map(|(_, count)| *count))
+            .output();
+
+        Ok((input_handle, output))
+    })
+    .unwrap();
+
+    input_handle.append(&mut vec![("usa".to_string(), ((1, 100), 1))]);
+    circuit.step().unwrap();
+    assert_eq!(
+        output.consolidate(),
+        indexed_zset! {"usa".to_string() => {((1, 100), None) => 1}}
+    );
+
+    // A value received in the next step is matched with the previous one.
+    input_handle.append(&mut vec![("usa".to_string(), ((2, 150), 1))]);
+    circuit.step().unwrap();
+    assert_eq!(
+        output.consolidate(),
+        indexed_zset! {"usa".to_string() => {((2, 150), Some(100)) => 1}}
+    );
+
+    // Correcting the value for day 1 retracts the old lag output for day 2.
+    input_handle.append(&mut vec![
+        ("usa".to_string(), ((1, 100), -1)),
+        ("usa".to_string(), ((1, 120), 1)),
+    ]);
+    circuit.step().unwrap();
+    assert_eq!(
+        output.consolidate(),
+        indexed_zset! {"usa".to_string() => {
+            ((1, 100), None) => -1,
+            ((1, 120), None) => 1,
+            ((2, 150), Some(100)) => -1,
+            ((2, 150), Some(120)) => 1
+        }}
+    );
+}
+
 proptest! {
     #[test]
     fn test_topk(trace in input_trace(5, 1_000, 200, 20)) {
@@ -79,6 +79,22 @@ where
         self.circuit()
             .add_unary_operator(IndexWith::new(index_func), self)
     }
+
+    /// Group the records of a Z-set by a key computed from each record.
+    ///
+    /// Returns an indexed Z-set that maps each key returned by `key_func` to
+    /// all records that produced this key.  This is a shorthand for
+    /// `self.index_with(move |r| (key_func(r), r.clone()))`, typically
+    /// followed by an aggregation operator, e.g.,
+    /// `stream.group_by(|r| r.location.clone()).aggregate(..)`.
+    pub fn group_by<K, F>(&self, key_func: F) -> Stream<C, OrdIndexedZSet<K, CI::Key, CI::R>>
+    where
+        CI: BatchReader<Time = (), Val = ()>,
+        F: Fn(&CI::Key) -> K + Clone + 'static,
+        K: DBData,
+    {
+        self.index_with(move |record| (key_func(record), record.clone()))
+    }
 }
 
 /// Operator that generates an indexed representation of a Z-set.
@@ -236,6 +252,8 @@ mod test {
     use crate::{
         indexed_zset, operator::Generator, trace::ord::OrdIndexedZSet, zset, Circuit, RootCircuit,
     };
+    use rkyv::{Archive, Deserialize, Serialize};
+    use size_of::SizeOf;
 
     #[test]
     fn index_test() {
@@ -304,4 +322,66 @@ mod test {
             circuit.step().unwrap();
         }
     }
+
+    #[derive(
+        Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq, SizeOf, Archive, Serialize, Deserialize,
+    )]
+    struct Record {
+        location: String,
+        day: u32,
+        daily_vaccinations: Option<u64>,
+    }
+
+    fn record(location: &str, day: u32, daily_vaccinations: Option<u64>) -> Record {
+        Record {
+            location: location.to_string(),
+            day,
+            daily_vaccinations,
+        }
+    }
+
+    #[test]
+    fn group_by_test() {
+        let circuit = RootCircuit::build(move |circuit| {
+            let mut inputs = vec![
+                zset! {
+                    record("England", 1, Some(100)) => 1,
+                    record("England", 2, None) => 1,
+                    record("Wales", 1, Some(10)) => 1,
+                },
+                zset! {
+                    record("England", 2, None) => -1,
+                    record("Scotland", 1, Some(20)) => 1,
+                },
+            ]
+            .into_iter();
+            let mut outputs = vec![
+                indexed_zset! {
+                    "England".to_string() => { record("England", 1, Some(100)) => 1, record("England", 2, None) => 1 },
+                    "Wales".to_string() => { record("Wales", 1, Some(10)) => 1 }
+                },
+                indexed_zset! {
+                    "England".to_string() => { record("England", 1, Some(100)) => 1 },
+                    "Scotland".to_string() => { record("Scotland", 1, Some(20)) => 1 },
+                    "Wales".to_string() => { record("Wales", 1, Some(10)) => 1 }
+                },
+            ]
+            .into_iter();
+
+            circuit
+                .add_source(Generator::new(move || inputs.next().unwrap()))
+                .group_by(|r| r.location.clone())
+                .integrate()
+                .inspect(move |groups: &OrdIndexedZSet<_, _, _>| {
+                    assert_eq!(groups, &outputs.next().unwrap())
+                });
+            Ok(())
+        })
+        .unwrap()
+        .0;
+
+        for _ in 0..2 {
+            circuit.step().unwrap();
+        }
+    }
 }
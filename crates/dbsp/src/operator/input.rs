@@ -658,6 +658,10 @@ where
     }
 }
 
+/// The number of updates [`CollectionHandle::append_iter`] sends to a
+/// worker at a time.
+const APPEND_ITER_CHUNK_SIZE: usize = 1024;
+
 impl<K, V> CollectionHandle<K, V>
 where
     K: DBData,
@@ -761,6 +765,58 @@ where
         }
     }
 
+    /// Reserve capacity for at least `additional` more `(key,value)` pairs
+    /// to be buffered before the next clock cycle.
+    ///
+    /// The capacity is divided evenly across workers.  Call this method
+    /// before loading a large number of updates with
+    /// [`Self::append_iter`] or [`Self::push`] to avoid repeatedly growing
+    /// the internal buffers.
+    pub fn reserve(&self, additional: usize) {
+        let num_partitions = self.num_partitions();
+        let per_worker = additional / num_partitions + 1;
+
+        for worker in self.input_handle.workers() {
+            self.input_handle
+                .update_for_worker(worker, |tuples| tuples.reserve(per_worker));
+        }
+    }
+
+    /// Push `(key,value)` pairs produced by an iterator to the input stream.
+    ///
+    /// Unlike [`Self::append`], this method does not require the caller to
+    /// collect updates into a `Vec` first, which reduces peak memory usage
+    /// when loading large inputs.  Updates are moved directly into
+    /// per-worker buffers in chunks of up to
+    /// `APPEND_ITER_CHUNK_SIZE` pairs, which are assigned to workers in a
+    /// round robin fashion.
+    ///
+    /// # Concurrency
+    ///
+    /// Unlike [`Self::append`], this method buffers each chunk separately,
+    /// so a concurrent [`DBSPHandle::step`](`crate::DBSPHandle::step`) call
+    /// may observe a subset of the updates pushed to the same worker.  The
+    /// remaining updates will appear during subsequent logical clock cycles.
+    pub fn append_iter<I>(&self, vals: I)
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let num_partitions = self.num_partitions();
+        let worker_ofs = self.input_handle.workers().start;
+        let mut vals = vals.into_iter().peekable();
+
+        while vals.peek().is_some() {
+            let worker = match num_partitions {
+                1 => 0,
+                n => self.next_worker.fetch_add(1, Ordering::AcqRel) % n,
+            };
+            self.input_handle
+                .update_for_worker(worker + worker_ofs, |tuples| {
+                    tuples.extend(vals.by_ref().take(APPEND_ITER_CHUNK_SIZE))
+                });
+        }
+    }
+
     /// Clear all inputs buffered since the start of the last clock cycle.
     ///
     /// # Concurrency
@@ -1206,6 +1262,47 @@ mod test {
         zset_test_mt(4);
     }
 
+    fn append_iter_test(workers: usize) {
+        let (mut dbsp, (vec_handle, iter_handle, vec_output, iter_output)) =
+            Runtime::init_circuit(workers, |circuit| {
+                let (vec_stream, vec_handle) = circuit.add_input_zset::<usize, isize>();
+                let (iter_stream, iter_handle) = circuit.add_input_zset::<usize, isize>();
+
+                Ok((
+                    vec_handle,
+                    iter_handle,
+                    vec_stream.output(),
+                    iter_stream.output(),
+                ))
+            })
+            .unwrap();
+
+        let tuples = || (0..10_000).map(|i| (i % 3000, 1));
+
+        for _ in 0..2 {
+            vec_handle.append(&mut tuples().collect());
+            iter_handle.reserve(10_000);
+            iter_handle.append_iter(tuples());
+            dbsp.step().unwrap();
+
+            let expected = vec_output.consolidate();
+            assert_eq!(expected.len(), 3000);
+            assert_eq!(iter_output.consolidate(), expected);
+        }
+
+        dbsp.kill().unwrap();
+    }
+
+    #[test]
+    fn append_iter_test_mt1() {
+        append_iter_test(1);
+    }
+
+    #[test]
+    fn append_iter_test_mt4() {
+        append_iter_test(4);
+    }
+
     fn input_indexed_batches() -> Vec<OrdIndexedZSet<usize, usize, isize>> {
         vec![
             indexed_zset! { 1 => {1 => 1, 2 => 1}, 2 => { 3 => 1 }, 3 => {4 => -1, 5 => 5} },
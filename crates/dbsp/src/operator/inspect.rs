@@ -44,6 +44,78 @@ where
         inspected.mark_sharded_if(self);
         inspected
     }
+
+    /// Like [`inspect`](`Self::inspect`), but only applies `callback` to
+    /// every `n`th value in the stream, starting from the first one.
+    ///
+    /// The operator counts its own evaluations, so `callback` fires at clock
+    /// cycles `0`, `n`, `2n`, etc.  This is useful for periodic logging in
+    /// long-running circuits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use dbsp::RootCircuit;
+    /// let (circuit, input_handle) = RootCircuit::build(move |circuit| {
+    ///     let (input, input_handle) = circuit.add_input_zset::<u64, isize>();
+    ///     // Print changes to per-key counts every 1000 steps.
+    ///     input
+    ///         .weighted_count()
+    ///         .inspect_every(1000, |counts| println!("counts: {counts:?}"));
+    ///     Ok(input_handle)
+    /// })
+    /// .unwrap();
+    ///
+    /// input_handle.push(1, 1);
+    /// circuit.step().unwrap();
+    /// ```
+    pub fn inspect_every<F>(&self, n: usize, mut callback: F) -> Self
+    where
+        F: FnMut(&D) + 'static,
+    {
+        assert_ne!(n, 0, "inspect_every: n must be greater than 0");
+
+        let mut step = 0;
+        self.inspect(move |value| {
+            if step % n == 0 {
+                callback(value);
+            }
+            step += 1;
+        })
+    }
+
+    /// Like [`inspect`](`Self::inspect`), but also passes the logical time of
+    /// the current clock cycle of the circuit to `callback` (see
+    /// [`Circuit::current_step`]).
+    ///
+    /// This helps correlating the contents of the stream with the steps that
+    /// produced them, e.g., when logging outputs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use dbsp::RootCircuit;
+    /// let (circuit, input_handle) = RootCircuit::build(move |circuit| {
+    ///     let (input, input_handle) = circuit.add_input_zset::<u64, isize>();
+    ///     input.inspect_with_time(|zset, step| println!("step {step}: {zset:?}"));
+    ///     Ok(input_handle)
+    /// })
+    /// .unwrap();
+    ///
+    /// input_handle.push(1, 1);
+    /// circuit.step().unwrap();
+    /// ```
+    pub fn inspect_with_time<F>(&self, mut callback: F) -> Self
+    where
+        F: FnMut(&D, u64) + 'static,
+    {
+        let circuit = self.circuit().clone();
+        self.inspect(move |value| callback(value, circuit.current_step()))
+    }
 }
 
 /// Sink operator that consumes a stream of values of type `T` and
@@ -95,3 +167,65 @@ where
         i
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{operator::Generator, Circuit, RootCircuit};
+    use std::{cell::RefCell, rc::Rc};
+
+    #[test]
+    fn inspect_every_test() {
+        let inspected = Rc::new(RefCell::new(Vec::new()));
+        let inspected_clone = inspected.clone();
+
+        let (circuit, ()) = RootCircuit::build(move |circuit| {
+            let mut step = 0;
+            circuit
+                .add_source(Generator::new(move || {
+                    let res = step;
+                    step += 1;
+                    res
+                }))
+                .inspect_every(3, move |step: &usize| {
+                    inspected_clone.borrow_mut().push(*step)
+                });
+            Ok(())
+        })
+        .unwrap();
+
+        for _ in 0..8 {
+            circuit.step().unwrap();
+        }
+
+        assert_eq!(*inspected.borrow(), vec![0, 3, 6]);
+    }
+
+    #[test]
+    fn inspect_with_time_test() {
+        let inspected = Rc::new(RefCell::new(Vec::new()));
+        let inspected_clone = inspected.clone();
+
+        let (circuit, ()) = RootCircuit::build(move |circuit| {
+            let mut n = 0;
+            circuit
+                .add_source(Generator::new(move || {
+                    n += 10;
+                    n
+                }))
+                .inspect_with_time(move |n: &usize, step| {
+                    inspected_clone.borrow_mut().push((*n, step))
+                });
+            Ok(())
+        })
+        .unwrap();
+
+        for _ in 0..4 {
+            circuit.step().unwrap();
+        }
+
+        assert_eq!(
+            *inspected.borrow(),
+            vec![(10, 0), (20, 1), (30, 2), (40, 3)]
+        );
+    }
+}
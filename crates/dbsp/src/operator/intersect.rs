@@ -0,0 +1,234 @@
+//! Operators that compute the intersection of two indexed Z-sets by key.
+
+use crate::{
+    algebra::{IndexedZSet, ZRingValue},
+    circuit::{
+        metadata::OperatorLocation,
+        operator_traits::{BinaryOperator, Operator},
+        Circuit, GlobalNodeId, Scope, Stream, WithClock,
+    },
+    circuit_cache_key,
+    trace::{cursor::Cursor, Batch, BatchReader, Builder},
+    DBTimestamp,
+};
+use std::{
+    borrow::Cow,
+    cmp::{min, Ordering},
+    iter::once,
+    marker::PhantomData,
+    panic::Location,
+};
+
+circuit_cache_key!(IntersectKeysId<C, D>((GlobalNodeId, GlobalNodeId) => Stream<C, D>));
+
+impl<C, I1> Stream<C, I1>
+where
+    C: Circuit,
+    I1: IndexedZSet + Send,
+{
+    /// Intersect two streams of batches by key.
+    ///
+    /// At each clock cycle, outputs the records of the `self` batch whose
+    /// keys also occur in the `other` batch.  Values and weights are taken
+    /// from `self`; the values and weights of `other` are ignored.  Unlike
+    /// [`stream_join`](`Self::stream_join`), this doesn't produce the cross
+    /// product of the values of matching keys.
+    #[track_caller]
+    pub fn stream_intersect_keys<I2>(&self, other: &Stream<C, I2>) -> Stream<C, I1>
+    where
+        I2: Batch<Key = I1::Key, Time = ()> + Send,
+    {
+        self.circuit()
+            .add_binary_operator(
+                IntersectKeys::new(Location::caller()),
+                &self.shard(),
+                &other.shard(),
+            )
+            .mark_sharded()
+    }
+
+    /// Incremental version of
+    /// [`stream_intersect_keys`](`Self::stream_intersect_keys`).
+    ///
+    /// Returns the records of `self` whose keys are present in `other`,
+    /// i.e., have at least one value with positive weight in `other`.
+    /// Values and weights are taken from `self`.  When a key is removed
+    /// from `other`, the records of `self` with that key are retracted from
+    /// the output.
+    pub fn intersect_keys<I2>(&self, other: &Stream<C, I2>) -> Stream<C, I1>
+    where
+        I2: IndexedZSet<Key = I1::Key, R = I1::R> + Send,
+        I1::R: ZRingValue,
+        <C as WithClock>::Time: DBTimestamp,
+    {
+        self.circuit()
+            .cache_get_or_insert_with(
+                IntersectKeysId::new((
+                    self.origin_node_id().clone(),
+                    other.origin_node_id().clone(),
+                )),
+                move || {
+                    // Reduce `other` to a single record with weight 1 per key, so
+                    // that the join doesn't scale the weights of `self`.
+                    let keys = other.distinct().weighted_count();
+
+                    self.shard()
+                        .join_generic(&keys, |k, v1, _count| once((k.clone(), v1.clone())))
+                        .mark_sharded()
+                },
+            )
+            .clone()
+    }
+}
+
+/// Operator that outputs the records of its first input whose keys occur in
+/// its second input.
+///
+/// See [`Stream::stream_intersect_keys`].
+pub struct IntersectKeys<I1, I2> {
+    location: &'static Location<'static>,
+    _types: PhantomData<(I1, I2)>,
+}
+
+impl<I1, I2> IntersectKeys<I1, I2> {
+    pub fn new(location: &'static Location<'static>) -> Self {
+        Self {
+            location,
+            _types: PhantomData,
+        }
+    }
+}
+
+impl<I1, I2> Operator for IntersectKeys<I1, I2>
+where
+    I1: 'static,
+    I2: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("IntersectKeys")
+    }
+
+    fn location(&self) -> OperatorLocation {
+        Some(self.location)
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<I1, I2> BinaryOperator<I1, I2, I1> for IntersectKeys<I1, I2>
+where
+    I1: IndexedZSet,
+    I2: BatchReader<Key = I1::Key, Time = ()>,
+{
+    fn eval(&mut self, left: &I1, right: &I2) -> I1 {
+        let mut left_cursor = left.cursor();
+        let mut right_cursor = right.cursor();
+
+        // Choose capacity heuristically.
+        let mut builder = I1::Builder::with_capacity((), min(left.len(), right.key_count()));
+
+        while left_cursor.key_valid() && right_cursor.key_valid() {
+            match left_cursor.key().cmp(right_cursor.key()) {
+                Ordering::Less => left_cursor.seek_key(right_cursor.key()),
+                Ordering::Greater => right_cursor.seek_key(left_cursor.key()),
+
+                Ordering::Equal => {
+                    while left_cursor.val_valid() {
+                        builder.push((
+                            I1::item_from(left_cursor.key().clone(), left_cursor.val().clone()),
+                            left_cursor.weight(),
+                        ));
+                        left_cursor.step_val();
+                    }
+
+                    left_cursor.step_key();
+                    right_cursor.step_key();
+                }
+            }
+        }
+
+        builder.done()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{indexed_zset, trace::Batch, OrdIndexedZSet, RootCircuit};
+
+    #[test]
+    fn stream_intersect_keys_test() {
+        let (circuit, (left_handle, right_handle, output)) = RootCircuit::build(move |circuit| {
+            let (left, left_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+            let (right, right_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+
+            Ok((
+                left_handle,
+                right_handle,
+                left.stream_intersect_keys(&right).output(),
+            ))
+        })
+        .unwrap();
+
+        left_handle.append(&mut vec![(1, (10, 1)), (1, (11, 2)), (2, (20, 1))]);
+        right_handle.append(&mut vec![(1, (100, 1)), (1, (101, 1)), (3, (300, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {1 => {10 => 1, 11 => 2}}
+        );
+
+        // The operator only looks at the current batches.
+        left_handle.append(&mut vec![(2, (21, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), OrdIndexedZSet::empty(()));
+    }
+
+    #[test]
+    fn intersect_keys_test() {
+        let (circuit, (left_handle, right_handle, output)) = RootCircuit::build(move |circuit| {
+            let (left, left_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+            let (right, right_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+
+            Ok((
+                left_handle,
+                right_handle,
+                left.intersect_keys(&right).output(),
+            ))
+        })
+        .unwrap();
+
+        // Multiple values per key in `right` don't affect output weights.
+        left_handle.append(&mut vec![(1, (10, 1)), (1, (11, 2)), (2, (20, 1))]);
+        right_handle.append(&mut vec![(1, (100, 1)), (1, (101, 1)), (3, (300, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {1 => {10 => 1, 11 => 2}}
+        );
+
+        // New records match keys added at earlier steps.
+        left_handle.append(&mut vec![(3, (30, 1))]);
+        right_handle.append(&mut vec![(2, (200, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {2 => {20 => 1}, 3 => {30 => 1}}
+        );
+
+        // Removing one of the values of key 1 from `right` doesn't change the
+        // output.
+        right_handle.append(&mut vec![(1, (100, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), OrdIndexedZSet::empty(()));
+
+        // Once key 1 leaves `right`, its intersection entries are retracted.
+        right_handle.append(&mut vec![(1, (101, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {1 => {10 => -1, 11 => -2}}
+        );
+    }
+}
@@ -1,14 +1,16 @@
 //! Relational join operator.
 
 use crate::{
-    algebra::{IndexedZSet, Lattice, MulByRef, PartialOrder, ZRingValue, ZSet},
+    algebra::{
+        AddAssignByRef, HasZero, IndexedZSet, Lattice, MulByRef, PartialOrder, ZRingValue, ZSet,
+    },
     circuit::{
         metadata::{MetaItem, OperatorLocation, OperatorMeta},
-        operator_traits::{BinaryOperator, Operator},
+        operator_traits::{BinaryOperator, MemoryStats, Operator},
         Circuit, GlobalNodeId, RootCircuit, Scope, Stream, WithClock,
     },
     circuit_cache_key,
-    operator::FilterMap,
+    operator::{communication::PartitionScheme, FilterMap, TraceRetention},
     time::Timestamp,
     trace::{cursor::Cursor as TraceCursor, Batch, BatchReader, Batcher, Builder, Spine, Trace},
     DBData, DBTimestamp, OrdIndexedZSet, OrdZSet,
@@ -16,12 +18,14 @@ use crate::{
 use size_of::{Context, SizeOf};
 use std::{
     borrow::Cow,
+    cell::RefCell,
     cmp::{min, Ordering},
     collections::HashMap,
     iter::once,
     marker::PhantomData,
     mem::{needs_drop, MaybeUninit},
     panic::Location,
+    rc::Rc,
 };
 
 circuit_cache_key!(AntijoinId<C, D>((GlobalNodeId, GlobalNodeId) => Stream<C, D>));
@@ -83,6 +87,127 @@ where
         )
     }
 
+    /// Like [`Self::stream_join`], but partitions both inputs across workers
+    /// using `scheme` instead of the default hash-based sharding.
+    ///
+    /// Since both inputs are partitioned by the same deterministic scheme,
+    /// the output doesn't depend on the number of workers.
+    #[track_caller]
+    #[allow(clippy::type_complexity)]
+    pub fn stream_join_partitioned<F, I2, V>(
+        &self,
+        other: &Stream<C, I2>,
+        join: F,
+        scheme: &PartitionScheme<I1::Key>,
+    ) -> Stream<C, OrdZSet<V, <I1::R as MulByRef<I2::R>>::Output>>
+    where
+        I1: Batch<Time = ()> + Send,
+        I2: Batch<Key = I1::Key, Time = ()> + Send,
+        I1::R: MulByRef<I2::R>,
+        <I1::R as MulByRef<I2::R>>::Output: DBData + ZRingValue,
+        F: Fn(&I1::Key, &I1::Val, &I2::Val) -> V + 'static,
+        V: DBData,
+    {
+        self.circuit().add_binary_operator(
+            Join::new(join, Location::caller()),
+            &self.shard_with(scheme),
+            &other.shard_with(scheme),
+        )
+    }
+
+    /// Like [`Self::stream_join`], but fails if the join produces more than
+    /// `max_fanout_per_key` output tuples for a single key.
+    ///
+    /// The fan-out of a key is the product of the number of values of the
+    /// key in both inputs.  A single hot key with many values on both sides
+    /// can otherwise produce a huge output.  When the fan-out of any key
+    /// exceeds the limit, the operator produces no output and the step fails
+    /// with
+    /// [`SchedulerError::OperatorError`](`crate::SchedulerError::OperatorError`).
+    #[track_caller]
+    #[allow(clippy::type_complexity)]
+    pub fn stream_join_with_max_fanout<F, I2, V>(
+        &self,
+        other: &Stream<C, I2>,
+        join: F,
+        max_fanout_per_key: usize,
+    ) -> Stream<C, OrdZSet<V, <I1::R as MulByRef<I2::R>>::Output>>
+    where
+        I1: Batch<Time = ()> + Send,
+        I2: Batch<Key = I1::Key, Time = ()> + Send,
+        I1::R: MulByRef<I2::R>,
+        <I1::R as MulByRef<I2::R>>::Output: DBData + ZRingValue,
+        F: Fn(&I1::Key, &I1::Val, &I2::Val) -> V + 'static,
+        V: DBData,
+    {
+        self.circuit().add_binary_operator(
+            Join::new(join, Location::caller()).with_max_fanout_per_key(Some(max_fanout_per_key)),
+            &self.shard(),
+            &other.shard(),
+        )
+    }
+
+    /// Like [`Self::stream_join`], but uses a hash join instead of a
+    /// sort-merge join.
+    ///
+    /// The operator builds a hash table of the input selected by `side` and
+    /// probes it with every key of the other input.  This can be faster than
+    /// [`Self::stream_join`] when one input is much smaller than the other,
+    /// since each key of the larger input is looked up in constant time
+    /// instead of seeking through the smaller input.  With
+    /// [`HashJoinSide::Auto`], the operator hashes the smaller input at each
+    /// clock cycle.
+    ///
+    /// The output of the operator is identical to the output of
+    /// [`Self::stream_join`].
+    #[track_caller]
+    #[allow(clippy::type_complexity)]
+    pub fn stream_hash_join<F, I2, V>(
+        &self,
+        other: &Stream<C, I2>,
+        join: F,
+        side: HashJoinSide,
+    ) -> Stream<C, OrdZSet<V, <I1::R as MulByRef<I2::R>>::Output>>
+    where
+        I1: Batch<Time = ()> + Send,
+        I2: Batch<Key = I1::Key, Time = ()> + Send,
+        I1::R: MulByRef<I2::R>,
+        <I1::R as MulByRef<I2::R>>::Output: DBData + ZRingValue,
+        F: Fn(&I1::Key, &I1::Val, &I2::Val) -> V + 'static,
+        V: DBData,
+    {
+        self.circuit().add_binary_operator(
+            HashJoin::new(join, side, Location::caller()),
+            &self.shard(),
+            &other.shard(),
+        )
+    }
+
+    /// Like [`Self::stream_join_generic`], but the join function returns a
+    /// [`Cow`] that can borrow the output from the key or the value of the
+    /// first input.
+    ///
+    /// Consecutive identical outputs produced for the same key and value of
+    /// the first input are combined into a single output tuple before the
+    /// output is cloned.  In particular, joins that output the first value
+    /// unchanged (e.g., `|_k, v1, _v2| Cow::Borrowed(v1)`) clone each value
+    /// once instead of once per matching value of the second input.
+    #[track_caller]
+    pub fn stream_join_cow<F, I2, Z>(&self, other: &Stream<C, I2>, join: F) -> Stream<C, Z>
+    where
+        I1: Batch<Time = ()> + Send,
+        I2: Batch<Key = I1::Key, Time = ()> + Send,
+        Z: ZSet,
+        I1::R: MulByRef<I2::R, Output = Z::R>,
+        F: for<'a, 'b> Fn(&'a I1::Key, &'a I1::Val, &'b I2::Val) -> Cow<'a, Z::Key> + 'static,
+    {
+        self.circuit().add_binary_operator(
+            JoinCow::new(join, Location::caller()),
+            &self.shard(),
+            &other.shard(),
+        )
+    }
+
     /// More efficient than [`Self::stream_join`], but the output of the join
     /// function must grow monotonically as `(k, v1, v2)` tuples are fed to it
     /// in lexicographic order.
@@ -160,6 +285,163 @@ impl<I1> Stream<RootCircuit, I1> {
             .stream_join_inner(&right, join_func.clone(), Location::caller())
             .plus(&left.stream_join_inner(&right.integrate_trace(), join_func, Location::caller()))
     }
+
+    /// Incremental join of two streams of batches with per-input trace
+    /// retention policies.
+    ///
+    /// Computes the same changes as
+    /// [`join_incremental`](`Self::join_incremental`), but the trace of
+    /// each input only retains updates within the window specified by the
+    /// corresponding field of `retention` (see
+    /// [`integrate_trace_bounded`](`Stream::integrate_trace_bounded`)).
+    /// This bounds the memory footprint of joins where one input is large but
+    /// new updates to the other input only need to match its recent updates.
+    ///
+    /// The integral of the output is equal to the join of the retained parts
+    /// of both inputs: pairs of tuples are retracted once either of them
+    /// falls out of its retention window, and retractions of tuples that
+    /// have already expired don't produce any outputs.
+    #[track_caller]
+    pub fn join_with_retention<F, I2, V>(
+        &self,
+        other: &Stream<RootCircuit, I2>,
+        join_func: F,
+        retention: JoinRetention,
+    ) -> Stream<RootCircuit, OrdZSet<V, I1::R>>
+    where
+        I1: IndexedZSet + Send,
+        I2: IndexedZSet<Key = I1::Key, R = I1::R> + Send,
+        I1::R: ZRingValue,
+        F: Fn(&I1::Key, &I1::Val, &I2::Val) -> V + Clone + 'static,
+        V: DBData,
+    {
+        let left = self.shard();
+        let right = other.shard();
+
+        // Changes to the retained part of each input.
+        let left = match retention.left {
+            Some(retention) => left.retained_updates(retention),
+            None => left,
+        };
+        let right = match retention.right {
+            Some(retention) => right.retained_updates(retention),
+            None => right,
+        };
+
+        let left_trace = left.integrate_trace();
+        let right_trace = right.integrate_trace();
+
+        left_trace
+            .delay_trace()
+            .stream_join_inner(&right, join_func.clone(), Location::caller())
+            .plus(&left.stream_join_inner(&right_trace, join_func, Location::caller()))
+    }
+
+    /// Incremental join of two streams of batches using a custom key
+    /// comparator.
+    ///
+    /// Like [`join`](`crate::circuit::Stream::join`), but matches keys that
+    /// are equal according to `comparator` rather than the natural [`Ord`]
+    /// of the key type, e.g., to join string keys case-insensitively.  The
+    /// key passed to `join_func` is the key of `self`.
+    ///
+    /// # Ordering requirement
+    ///
+    /// The operator walks the keys of both inputs in their index order, so
+    /// the natural ordering of keys in each input must be consistent with
+    /// `comparator`: if `comparator(a, b)` returns [`Ordering::Less`], then
+    /// `a < b` must also hold for any two keys `a` and `b` in the same input.
+    /// For example, a case-insensitive comparator can be used to join keys
+    /// that differ in case as long as their natural order is the same as the
+    /// order of their lowercase versions.  The output is unspecified if this
+    /// requirement is violated.
+    ///
+    /// Keys that are equal according to `comparator` can be assigned to
+    /// different shards, so both inputs are gathered at worker 0, which
+    /// computes the entire join.
+    #[track_caller]
+    pub fn join_by<F, CMP, I2, V>(
+        &self,
+        other: &Stream<RootCircuit, I2>,
+        comparator: CMP,
+        join_func: F,
+    ) -> Stream<RootCircuit, OrdZSet<V, I1::R>>
+    where
+        I1: IndexedZSet + Send,
+        I2: IndexedZSet<Key = I1::Key, R = I1::R> + Send,
+        I1::R: ZRingValue,
+        CMP: Fn(&I1::Key, &I1::Key) -> Ordering + Clone + 'static,
+        F: Fn(&I1::Key, &I1::Val, &I2::Val) -> V + Clone + 'static,
+        V: DBData,
+    {
+        let left = self.gather(0);
+        let right = other.gather(0);
+
+        let delta_left = self.circuit().add_binary_operator(
+            JoinBy::new(join_func.clone(), comparator.clone(), Location::caller()),
+            &left,
+            &right.integrate_trace(),
+        );
+        let delta_right = self.circuit().add_binary_operator(
+            JoinBy::new(join_func, comparator, Location::caller()),
+            &left.integrate_trace().delay_trace(),
+            &right,
+        );
+
+        delta_left.plus(&delta_right)
+    }
+
+    /// Incremental Cartesian product of two streams of batches.
+    ///
+    /// Pairs every `(key, value)` tuple of `self` with every `(key, value)`
+    /// tuple of `other` regardless of their keys, applies `join_func` to
+    /// each pair, and multiplies their weights.  This is useful, e.g., to
+    /// combine a relation with a small dimension table that has no join key.
+    ///
+    /// Given streams `a` and `b` of changes to relations `A` and `B`,
+    /// computes changes to `A × B` as:
+    ///
+    /// ```text
+    /// delta(A × B) = a × B + z^-1(A) × b
+    /// ```
+    ///
+    /// The size of a Cartesian product can explode quickly.  If either term
+    /// of the above formula exceeds `max_output` tuples at some step, the
+    /// step fails with
+    /// [`SchedulerError::OperatorError`](`crate::SchedulerError::OperatorError`).
+    ///
+    /// Both inputs are gathered at worker 0, which computes the entire
+    /// product.
+    #[track_caller]
+    pub fn cross_join<F, I2, V>(
+        &self,
+        other: &Stream<RootCircuit, I2>,
+        join_func: F,
+        max_output: usize,
+    ) -> Stream<RootCircuit, OrdZSet<V, I1::R>>
+    where
+        I1: IndexedZSet + Send,
+        I2: IndexedZSet<R = I1::R> + Send,
+        I1::R: ZRingValue,
+        F: Fn(&I1::Key, &I1::Val, &I2::Key, &I2::Val) -> V + Clone + 'static,
+        V: DBData,
+    {
+        let left = self.gather(0);
+        let right = other.gather(0);
+
+        let delta_left = self.circuit().add_binary_operator(
+            CrossJoin::new(join_func.clone(), max_output, Location::caller()),
+            &left,
+            &right.integrate_trace(),
+        );
+        let delta_right = self.circuit().add_binary_operator(
+            CrossJoin::new(join_func, max_output, Location::caller()),
+            &left.integrate_trace().delay_trace(),
+            &right,
+        );
+
+        delta_left.plus(&delta_right)
+    }
 }
 
 impl<C, I1> Stream<C, I1>
@@ -222,6 +504,157 @@ where
     /// Like [`Self::join_index`], but can return any indexed Z-set type.
     #[track_caller]
     pub fn join_generic<I2, F, Z, It>(&self, other: &Stream<C, I2>, join_func: F) -> Stream<C, Z>
+    where
+        I2: IndexedZSet<Key = I1::Key, R = I1::R> + Send,
+        Z: IndexedZSet<R = I1::R>,
+        Z::R: MulByRef<Output = Z::R>,
+        F: Fn(&I1::Key, &I1::Val, &I2::Val) -> It + Clone + 'static,
+        It: IntoIterator<Item = (Z::Key, Z::Val)> + 'static,
+    {
+        self.join_generic_inner(other, join_func, false, None, JoinWatermark::new())
+    }
+
+    /// Like [`Self::join`], but evicts outputs buffered for times that fall
+    /// behind `watermark`.
+    ///
+    /// In nested circuits, the join computes outputs for future times ahead of
+    /// time and buffers them until the clock reaches them.  The watermark
+    /// asserts that the clock will never reach times less than or equal to
+    /// it, e.g., because the nested circuit stops iterating before reaching
+    /// them.  At each clock cycle, buffered outputs for such times are
+    /// evicted: outputs for past times are merged into the current output,
+    /// while outputs for future times are dropped.
+    #[track_caller]
+    pub fn join_with_watermark<I2, F, V>(
+        &self,
+        other: &Stream<C, I2>,
+        join_func: F,
+        watermark: &JoinWatermark<C::Time>,
+    ) -> Stream<C, OrdZSet<V, I1::R>>
+    where
+        I2: IndexedZSet<Key = I1::Key, R = I1::R> + Send,
+        F: Fn(&I1::Key, &I1::Val, &I2::Val) -> V + Clone + 'static,
+        V: DBData,
+    {
+        self.join_generic_inner(
+            other,
+            move |k, v1, v2| once((join_func(k, v1, v2), ())),
+            false,
+            None,
+            watermark.clone(),
+        )
+    }
+
+    /// Like [`Self::join`], but fails if the join produces more than
+    /// `max_fanout_per_key` output tuples for a single key at some step.
+    ///
+    /// See [`Self::stream_join_with_max_fanout`].  The limit applies
+    /// separately to the two terms of the incremental join, i.e., to the
+    /// product of the number of values of the key in the change to one input
+    /// and in the integral of the other input.
+    #[track_caller]
+    pub fn join_with_max_fanout<I2, F, V>(
+        &self,
+        other: &Stream<C, I2>,
+        join_func: F,
+        max_fanout_per_key: usize,
+    ) -> Stream<C, OrdZSet<V, I1::R>>
+    where
+        I2: IndexedZSet<Key = I1::Key, R = I1::R> + Send,
+        F: Fn(&I1::Key, &I1::Val, &I2::Val) -> V + Clone + 'static,
+        V: DBData,
+    {
+        self.join_generic_inner(
+            other,
+            move |k, v1, v2| once((join_func(k, v1, v2), ())),
+            false,
+            Some(max_fanout_per_key),
+            JoinWatermark::new(),
+        )
+    }
+
+    /// Like [`Self::join`], but consolidates output tuples as they are
+    /// computed.
+    ///
+    /// [`join`](`Self::join`) buffers all tuples produced by the join function
+    /// during a clock cycle before consolidating them into an output batch.
+    /// When many pairs of input tuples map to the same output tuple, e.g.,
+    /// when the join function projects away the columns that distinguish
+    /// them, this buffer can be much larger than the output.  This operator
+    /// instead accumulates the weight of each output tuple in a hash map,
+    /// trading the cost of hashing for a smaller memory footprint.
+    ///
+    /// Despite its name, the operator does not compute distinct output
+    /// tuples: its output is identical to the output of `join`.
+    #[track_caller]
+    pub fn join_distinct<I2, F, V>(
+        &self,
+        other: &Stream<C, I2>,
+        join_func: F,
+    ) -> Stream<C, OrdZSet<V, I1::R>>
+    where
+        I2: IndexedZSet<Key = I1::Key, R = I1::R> + Send,
+        F: Fn(&I1::Key, &I1::Val, &I2::Val) -> V + Clone + 'static,
+        V: DBData,
+    {
+        self.join_generic_inner(
+            other,
+            move |k, v1, v2| once((join_func(k, v1, v2), ())),
+            true,
+            None,
+            JoinWatermark::new(),
+        )
+    }
+
+    /// Incremental left outer join.
+    ///
+    /// Applies `join_func` to every pair of matching tuples in `self` and
+    /// `other`, and to every tuple in `self` that doesn't have a matching key
+    /// in `other`, with `None` in place of the missing value.
+    ///
+    /// The unmatched part of the output is not linear in its inputs, so it
+    /// cannot be computed by splitting the join into deltas like
+    /// [`Self::join`].  Instead it is computed incrementally as the
+    /// [antijoin](`Self::antijoin`) of `self` and `other`.  In particular,
+    /// when a key of `other` first matches a key previously emitted as
+    /// unmatched, the output retracts `join_func(k, v1, None)` along with
+    /// inserting `join_func(k, v1, Some(v2))`, and vice versa when the last
+    /// matching tuple is deleted from `other`.
+    #[track_caller]
+    pub fn left_join<I2, F, V>(
+        &self,
+        other: &Stream<C, I2>,
+        join_func: F,
+    ) -> Stream<C, OrdZSet<V, I1::R>>
+    where
+        Self: for<'a> FilterMap<C, R = I1::R, ItemRef<'a> = (&'a I1::Key, &'a I1::Val)>,
+        Stream<C, I2>: for<'a> FilterMap<C, R = I1::R, ItemRef<'a> = (&'a I1::Key, &'a I2::Val)>,
+        I2: IndexedZSet<Key = I1::Key, R = I1::R> + Send,
+        F: Fn(&I1::Key, &I1::Val, Option<&I2::Val>) -> V + Clone + 'static,
+        V: DBData,
+    {
+        let join_func_left = join_func.clone();
+
+        let matched = self.join(other, move |k, v1, v2| join_func(k, v1, Some(v2)));
+
+        // `antijoin` excludes each key once per distinct value of that key in
+        // `other`, so it is applied to the distinct keys of `other`.
+        let other_keys = other.map_index(|(k, _)| (k.clone(), ()));
+        let unmatched = self
+            .antijoin(&other_keys)
+            .map(move |(k, v1)| join_func_left(k, v1, None));
+        matched.plus(&unmatched)
+    }
+
+    #[track_caller]
+    fn join_generic_inner<I2, F, Z, It>(
+        &self,
+        other: &Stream<C, I2>,
+        join_func: F,
+        consolidate_outputs: bool,
+        max_fanout_per_key: Option<usize>,
+        watermark: JoinWatermark<C::Time>,
+    ) -> Stream<C, Z>
     where
         I2: IndexedZSet<Key = I1::Key, R = I1::R> + Send,
         Z: IndexedZSet<R = I1::R>,
@@ -280,7 +713,10 @@ where
                 join_func.clone(),
                 Location::caller(),
                 self.circuit().clone(),
-            ),
+            )
+            .with_consolidated_outputs(consolidate_outputs)
+            .with_max_fanout_per_key(max_fanout_per_key)
+            .with_watermark(watermark.clone()),
             &left,
             &right_trace,
         );
@@ -290,7 +726,10 @@ where
                 move |k: &I1::Key, v2: &I2::Val, v1: &I1::Val| join_func(k, v1, v2),
                 Location::caller(),
                 self.circuit().clone(),
-            ),
+            )
+            .with_consolidated_outputs(consolidate_outputs)
+            .with_max_fanout_per_key(max_fanout_per_key)
+            .with_watermark(watermark),
             &right,
             &left_trace.delay_trace(),
         );
@@ -327,41 +766,119 @@ where
     }
 }
 
-impl<C, Z> Stream<C, Z>
+impl<C, K, V1, R> Stream<C, OrdIndexedZSet<Option<K>, V1, R>>
 where
     C: Circuit,
     <C as WithClock>::Time: DBTimestamp,
-    Z: IndexedZSet + Send,
-    Z::Key: Default,
-    Z::Val: Default,
-    Z::R: ZRingValue,
+    K: DBData,
+    V1: DBData,
+    R: DBData + ZRingValue,
 {
-    /// Outer join:
-    /// - returns the output of `join_func` for common keys.
-    /// - returns the output of `left_func` for keys only found in `self`, but
-    ///   not `other`.
-    /// - returns the output of `right_func` for keys only found in `other`, but
-    ///   not `self`.
-    pub fn outer_join<Z2, F, FL, FR, O>(
+    /// Incremental join with SQL semantics for nullable keys.
+    ///
+    /// Like [`Self::join`], but a `None` key never matches any key,
+    /// including another `None` key, i.e., `NULL != NULL`.  [`Option`]'s
+    /// implementation of [`Ord`] considers two `None`s equal, so
+    /// [`Self::join`] would match them.  Rows with `None` keys are dropped
+    /// from both inputs before the join, and `join_func` is only invoked
+    /// for non-null keys.
+    #[track_caller]
+    pub fn join_nullable<V2, F, V>(
         &self,
-        other: &Stream<C, Z2>,
+        other: &Stream<C, OrdIndexedZSet<Option<K>, V2, R>>,
         join_func: F,
-        left_func: FL,
-        right_func: FR,
-    ) -> Stream<C, OrdZSet<O, Z::R>>
+    ) -> Stream<C, OrdZSet<V, R>>
     where
-        Self: FilterMap<C, R = Z::R>,
-        Z2: IndexedZSet<Key = Z::Key, R = Z::R> + Send,
-        Z2::Val: Default,
-        Stream<C, Z2>: FilterMap<C, R = Z::R>,
-        O: DBData + Default,
-        F: Fn(&Z::Key, &Z::Val, &Z2::Val) -> O + Clone + 'static,
-        for<'a> FL: Fn(<Self as FilterMap<C>>::ItemRef<'a>) -> O + Clone + 'static,
-        for<'a> FR: Fn(<Stream<C, Z2> as FilterMap<C>>::ItemRef<'a>) -> O + Clone + 'static,
+        V2: DBData,
+        F: Fn(&K, &V1, &V2) -> V + Clone + 'static,
+        V: DBData,
     {
-        let center = self.join_generic(other, move |k, v1, v2| {
-            std::iter::once((join_func(k, v1, v2), ()))
-        });
+        self.filter_keys(Option::is_some)
+            .join(&other.filter_keys(Option::is_some), move |k, v1, v2| {
+                join_func(k.as_ref().unwrap(), v1, v2)
+            })
+    }
+
+    /// Incremental left outer join with SQL semantics for nullable keys.
+    ///
+    /// Like [`Self::left_join`], but a `None` key never matches any key, so
+    /// every row of `self` with a `None` key is output as unmatched, with
+    /// `None` in place of the missing value.  See [`Self::join_nullable`].
+    #[track_caller]
+    pub fn left_join_nullable<V2, F, V>(
+        &self,
+        other: &Stream<C, OrdIndexedZSet<Option<K>, V2, R>>,
+        join_func: F,
+    ) -> Stream<C, OrdZSet<V, R>>
+    where
+        V2: DBData,
+        F: Fn(&Option<K>, &V1, Option<&V2>) -> V + Clone + 'static,
+        V: DBData,
+    {
+        // Since rows with `None` keys are removed from `other`, rows of `self`
+        // with `None` keys always end up in the unmatched part of the output.
+        self.left_join(&other.filter_keys(Option::is_some), join_func)
+    }
+
+    /// Incremental join with null-safe equality for nullable keys.
+    ///
+    /// Matches keys using SQL's `IS NOT DISTINCT FROM` semantics: a `None`
+    /// key matches another `None` key, and `Some` keys match as usual.  This
+    /// is the opposite of [`Self::join_nullable`].  Since [`Option`]'s
+    /// implementation of [`Ord`] considers two `None`s equal, the join
+    /// merges `None` keys like any other key; this method makes that
+    /// policy explicit at the call site.  `join_func` receives the
+    /// nullable key.
+    #[track_caller]
+    pub fn join_null_safe<V2, F, V>(
+        &self,
+        other: &Stream<C, OrdIndexedZSet<Option<K>, V2, R>>,
+        join_func: F,
+    ) -> Stream<C, OrdZSet<V, R>>
+    where
+        V2: DBData,
+        F: Fn(&Option<K>, &V1, &V2) -> V + Clone + 'static,
+        V: DBData,
+    {
+        self.join(other, join_func)
+    }
+}
+
+impl<C, Z> Stream<C, Z>
+where
+    C: Circuit,
+    <C as WithClock>::Time: DBTimestamp,
+    Z: IndexedZSet + Send,
+    Z::Key: Default,
+    Z::Val: Default,
+    Z::R: ZRingValue,
+{
+    /// Outer join:
+    /// - returns the output of `join_func` for common keys.
+    /// - returns the output of `left_func` for keys only found in `self`, but
+    ///   not `other`.
+    /// - returns the output of `right_func` for keys only found in `other`, but
+    ///   not `self`.
+    pub fn outer_join<Z2, F, FL, FR, O>(
+        &self,
+        other: &Stream<C, Z2>,
+        join_func: F,
+        left_func: FL,
+        right_func: FR,
+    ) -> Stream<C, OrdZSet<O, Z::R>>
+    where
+        Self: FilterMap<C, R = Z::R>,
+        Z2: IndexedZSet<Key = Z::Key, R = Z::R> + Send,
+        Z2::Val: Default,
+        Stream<C, Z2>: FilterMap<C, R = Z::R>,
+        O: DBData + Default,
+        F: Fn(&Z::Key, &Z::Val, &Z2::Val) -> O + Clone + 'static,
+        for<'a> FL: Fn(<Self as FilterMap<C>>::ItemRef<'a>) -> O + Clone + 'static,
+        for<'a> FR: Fn(<Stream<C, Z2> as FilterMap<C>>::ItemRef<'a>) -> O + Clone + 'static,
+    {
+        let center = self.join_generic(other, move |k, v1, v2| {
+            std::iter::once((join_func(k, v1, v2), ()))
+        });
         let left = self.antijoin(other).map_generic(left_func);
         let right = other.antijoin(self).map_generic(right_func);
         center.sum(&[left, right])
@@ -393,11 +910,75 @@ where
     }
 }
 
+/// Retention policies for the input traces of
+/// [`Stream::join_with_retention`](`crate::circuit::Stream::join_with_retention`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct JoinRetention {
+    /// Retention policy for the trace of the left input.  `None` retains the
+    /// entire history of the input.
+    pub left: Option<TraceRetention>,
+
+    /// Retention policy for the trace of the right input.  `None` retains the
+    /// entire history of the input.
+    pub right: Option<TraceRetention>,
+}
+
+/// Low watermark of
+/// [`Stream::join_with_watermark`](`crate::circuit::Stream::join_with_watermark`).
+///
+/// The watermark asserts that the clock will never reach times less than or
+/// equal to it.  The writer can advance the watermark at each clock cycle.  The
+/// watermark never moves backward: a value that is not greater than or equal
+/// to the current one is ignored.
+#[derive(Clone)]
+pub struct JoinWatermark<T>(Rc<RefCell<Option<T>>>);
+
+impl<T> Default for JoinWatermark<T> {
+    fn default() -> Self {
+        Self(Rc::new(RefCell::new(None)))
+    }
+}
+
+impl<T> JoinWatermark<T>
+where
+    T: PartialOrder + Clone,
+{
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Advance the watermark.
+    pub fn set(&self, watermark: T) {
+        let mut current = self.0.borrow_mut();
+        match &*current {
+            Some(current) if !current.less_equal(&watermark) => {}
+            _ => *current = Some(watermark),
+        }
+    }
+
+    /// Get the current value of the watermark.
+    pub fn get(&self) -> Option<T> {
+        self.0.borrow().clone()
+    }
+}
+
 /// Join two streams of batches.
 ///
 /// See [`Stream::join`](`crate::circuit::Stream::join`).
+///
+/// # Output batch type
+///
+/// The output can be any [`ZSet`] implementation, not just
+/// [`OrdZSet`].  The operator only constructs output batches using
+///
+/// * [`Batch::from_keys`](`crate::trace::Batch::from_keys`), which receives
+///   pairs in arbitrary order that may contain duplicate keys and must
+///   consolidate them, and
+/// * [`Batch::empty`](`crate::trace::Batch::empty`).
 pub struct Join<F, I1, I2, Z> {
     join_func: F,
+    max_fanout_per_key: Option<usize>,
+    error: Option<String>,
     location: &'static Location<'static>,
     _types: PhantomData<(I1, I2, Z)>,
 }
@@ -406,10 +987,21 @@ impl<F, I1, I2, Z> Join<F, I1, I2, Z> {
     pub fn new(join_func: F, location: &'static Location<'static>) -> Self {
         Self {
             join_func,
+            max_fanout_per_key: None,
+            error: None,
             location,
             _types: PhantomData,
         }
     }
+
+    /// Fail if the number of output tuples produced for a single key exceeds
+    /// `max_fanout_per_key`.
+    ///
+    /// See [`Stream::stream_join_with_max_fanout`].
+    pub fn with_max_fanout_per_key(mut self, max_fanout_per_key: Option<usize>) -> Self {
+        self.max_fanout_per_key = max_fanout_per_key;
+        self
+    }
 }
 
 impl<F, I1, I2, Z> Operator for Join<F, I1, I2, Z>
@@ -427,6 +1019,10 @@ where
         Some(self.location)
     }
 
+    fn take_error(&mut self) -> Option<String> {
+        self.error.take()
+    }
+
     fn fixedpoint(&self, _scope: Scope) -> bool {
         true
     }
@@ -452,6 +1048,14 @@ where
                 Ordering::Less => cursor1.seek_key(cursor2.key()),
                 Ordering::Greater => cursor2.seek_key(cursor1.key()),
                 Ordering::Equal => {
+                    if let Some(max_fanout) = self.max_fanout_per_key {
+                        let fanout = count_vals(&mut cursor1) * count_vals(&mut cursor2);
+                        if fanout > max_fanout {
+                            self.error = Some(fanout_error(fanout, max_fanout));
+                            return Z::empty(());
+                        }
+                    }
+
                     while cursor1.val_valid() {
                         let w1 = cursor1.weight();
                         let v1 = cursor1.val();
@@ -482,23 +1086,73 @@ where
     // TODO: Impls using consumers
 }
 
-pub struct MonotonicJoin<F, I1, I2, Z> {
+/// Returns the number of values under the current key of `cursor` and rewinds
+/// the cursor to the first value.
+fn count_vals<K, V, T, R, C>(cursor: &mut C) -> usize
+where
+    C: TraceCursor<K, V, T, R>,
+{
+    let mut count = 0;
+    while cursor.val_valid() {
+        count += 1;
+        cursor.step_val();
+    }
+    cursor.rewind_vals();
+    count
+}
+
+/// Returns the number of bytes of heap memory owned by `key` and `val`.
+fn children_bytes<K, V>(key: &K, val: &V) -> usize
+where
+    K: SizeOf,
+    V: SizeOf,
+{
+    let mut context = Context::new();
+    key.size_of_children(&mut context);
+    val.size_of_children(&mut context);
+    context.total_size().total_bytes()
+}
+
+fn fanout_error(fanout: usize, max_fanout: usize) -> String {
+    format!(
+        "join fan-out of {fanout} tuples for a single key exceeds the limit of {max_fanout} tuples"
+    )
+}
+
+/// Input of [`HashJoin`] to build the hash table from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HashJoinSide {
+    /// Hash the smaller of the two inputs at each clock cycle.
+    #[default]
+    Auto,
+    /// Hash the left input.
+    Left,
+    /// Hash the right input.
+    Right,
+}
+
+/// Join two streams of batches using a hash join.
+///
+/// See [`Stream::stream_hash_join`].
+pub struct HashJoin<F, I1, I2, Z> {
     join_func: F,
+    side: HashJoinSide,
     location: &'static Location<'static>,
     _types: PhantomData<(I1, I2, Z)>,
 }
 
-impl<F, I1, I2, Z> MonotonicJoin<F, I1, I2, Z> {
-    pub fn new(join_func: F, location: &'static Location<'static>) -> Self {
+impl<F, I1, I2, Z> HashJoin<F, I1, I2, Z> {
+    pub fn new(join_func: F, side: HashJoinSide, location: &'static Location<'static>) -> Self {
         Self {
             join_func,
+            side,
             location,
             _types: PhantomData,
         }
     }
 }
 
-impl<F, I1, I2, Z> Operator for MonotonicJoin<F, I1, I2, Z>
+impl<F, I1, I2, Z> Operator for HashJoin<F, I1, I2, Z>
 where
     I1: 'static,
     I2: 'static,
@@ -506,7 +1160,7 @@ where
     Z: 'static,
 {
     fn name(&self) -> Cow<'static, str> {
-        Cow::Borrowed("MonotonicJoin")
+        Cow::Borrowed("HashJoin")
     }
 
     fn location(&self) -> OperatorLocation {
@@ -518,20 +1172,143 @@ where
     }
 }
 
-impl<F, I1, I2, Z> BinaryOperator<I1, I2, Z> for MonotonicJoin<F, I1, I2, Z>
+/// Build a hash table that maps each key of `batch` to its values and
+/// weights.
+#[allow(clippy::type_complexity)]
+fn hash_batch<B>(batch: &B) -> HashMap<B::Key, Vec<(B::Val, B::R)>>
+where
+    B: BatchReader<Time = ()>,
+{
+    let mut table = HashMap::with_capacity(batch.key_count());
+    let mut cursor = batch.cursor();
+
+    while cursor.key_valid() {
+        let mut vals = Vec::new();
+        while cursor.val_valid() {
+            vals.push((cursor.val().clone(), cursor.weight()));
+            cursor.step_val();
+        }
+        table.insert(cursor.key().clone(), vals);
+        cursor.step_key();
+    }
+
+    table
+}
+
+impl<F, I1, I2, Z> BinaryOperator<I1, I2, Z> for HashJoin<F, I1, I2, Z>
 where
     I1: BatchReader<Time = ()>,
+    I1::R: MulByRef<I2::R, Output = Z::R>,
     I2: BatchReader<Key = I1::Key, Time = ()>,
     F: Fn(&I1::Key, &I1::Val, &I2::Val) -> Z::Key + 'static,
     Z: ZSet,
+{
+    fn eval(&mut self, i1: &I1, i2: &I2) -> Z {
+        let hash_left = match self.side {
+            HashJoinSide::Auto => i1.len() <= i2.len(),
+            HashJoinSide::Left => true,
+            HashJoinSide::Right => false,
+        };
+
+        let mut batch = Vec::with_capacity(min(i1.len(), i2.len()));
+
+        if hash_left {
+            let table = hash_batch(i1);
+            let mut cursor2 = i2.cursor();
+
+            while cursor2.key_valid() {
+                if let Some(vals1) = table.get(cursor2.key()) {
+                    while cursor2.val_valid() {
+                        let w2 = cursor2.weight();
+                        let v2 = cursor2.val();
+                        for (v1, w1) in vals1 {
+                            batch.push((
+                                (self.join_func)(cursor2.key(), v1, v2),
+                                w1.mul_by_ref(&w2),
+                            ));
+                        }
+                        cursor2.step_val();
+                    }
+                }
+                cursor2.step_key();
+            }
+        } else {
+            let table = hash_batch(i2);
+            let mut cursor1 = i1.cursor();
+
+            while cursor1.key_valid() {
+                if let Some(vals2) = table.get(cursor1.key()) {
+                    while cursor1.val_valid() {
+                        let w1 = cursor1.weight();
+                        let v1 = cursor1.val();
+                        for (v2, w2) in vals2 {
+                            batch
+                                .push(((self.join_func)(cursor1.key(), v1, v2), w1.mul_by_ref(w2)));
+                        }
+                        cursor1.step_val();
+                    }
+                }
+                cursor1.step_key();
+            }
+        }
+
+        Z::from_keys((), batch)
+    }
+}
+
+/// Join two streams of batches using a join function that returns a [`Cow`].
+///
+/// See [`Stream::stream_join_cow`](`crate::circuit::Stream::stream_join_cow`).
+pub struct JoinCow<F, I1, I2, Z> {
+    join_func: F,
+    location: &'static Location<'static>,
+    _types: PhantomData<(I1, I2, Z)>,
+}
+
+impl<F, I1, I2, Z> JoinCow<F, I1, I2, Z> {
+    pub fn new(join_func: F, location: &'static Location<'static>) -> Self {
+        Self {
+            join_func,
+            location,
+            _types: PhantomData,
+        }
+    }
+}
+
+impl<F, I1, I2, Z> Operator for JoinCow<F, I1, I2, Z>
+where
+    I1: 'static,
+    I2: 'static,
+    F: 'static,
+    Z: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("JoinCow")
+    }
+
+    fn location(&self) -> OperatorLocation {
+        Some(self.location)
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<F, I1, I2, Z> BinaryOperator<I1, I2, Z> for JoinCow<F, I1, I2, Z>
+where
+    I1: BatchReader<Time = ()>,
     I1::R: MulByRef<I2::R, Output = Z::R>,
+    I2: BatchReader<Key = I1::Key, Time = ()>,
+    F: for<'a, 'b> Fn(&'a I1::Key, &'a I1::Val, &'b I2::Val) -> Cow<'a, Z::Key> + 'static,
+    Z: ZSet,
 {
     fn eval(&mut self, i1: &I1, i2: &I2) -> Z {
         let mut cursor1 = i1.cursor();
         let mut cursor2 = i2.cursor();
 
         // Choose capacity heuristically.
-        let mut builder = Z::Builder::with_capacity((), min(i1.len(), i2.len()));
+        let mut batch = Vec::with_capacity(min(i1.len(), i2.len()));
 
         while cursor1.key_valid() && cursor2.key_valid() {
             match cursor1.key().cmp(cursor2.key()) {
@@ -541,17 +1318,35 @@ where
                     while cursor1.val_valid() {
                         let w1 = cursor1.weight();
                         let v1 = cursor1.val();
-                        while cursor2.val_valid() {
-                            let w2 = cursor2.weight();
-                            let v2 = cursor2.val();
 
-                            builder.push((
-                                Z::item_from((self.join_func)(cursor1.key(), v1, v2), ()),
-                                w1.mul_by_ref(&w2),
-                            ));
+                        // Output computed for the previous value in `cursor2`, which
+                        // hasn't been added to `batch` yet.
+                        let mut pending: Option<(Cow<'_, Z::Key>, Z::R)> = None;
+
+                        while cursor2.val_valid() {
+                            let w = w1.mul_by_ref(&cursor2.weight());
+                            let output = (self.join_func)(cursor1.key(), v1, cursor2.val());
+
+                            let merged = match &mut pending {
+                                Some((key, weight)) if *key == output => {
+                                    weight.add_assign_by_ref(&w);
+                                    true
+                                }
+                                _ => false,
+                            };
+
+                            if !merged {
+                                if let Some((key, weight)) = pending.replace((output, w)) {
+                                    batch.push((key.into_owned(), weight));
+                                }
+                            }
                             cursor2.step_val();
                         }
 
+                        if let Some((key, weight)) = pending {
+                            batch.push((key.into_owned(), weight));
+                        }
+
                         cursor2.rewind_vals();
                         cursor1.step_val();
                     }
@@ -562,354 +1357,845 @@ where
             }
         }
 
-        builder.done()
-    }
-}
-
-#[derive(Debug, Clone, PartialEq, Default)]
-struct JoinStats {
-    lhs_tuples: usize,
-    rhs_tuples: usize,
-    output_tuples: usize,
-    produced_tuples: usize,
-}
-
-impl JoinStats {
-    pub const fn new() -> Self {
-        Self {
-            lhs_tuples: 0,
-            rhs_tuples: 0,
-            output_tuples: 0,
-            produced_tuples: 0,
-        }
+        Z::from_keys((), batch)
     }
 }
 
-pub struct JoinTrace<F, I, T, Z, It, Clk>
-where
-    T: BatchReader,
-    Z: IndexedZSet,
-{
-    clock: Clk,
+pub struct MonotonicJoin<F, I1, I2, Z> {
     join_func: F,
     location: &'static Location<'static>,
-    // Future update batches computed ahead of time, indexed by time
-    // when each batch should be output.
-    output_batchers: HashMap<T::Time, Z::Batcher>,
-    // True if empty input batch was received at the current clock cycle.
-    empty_input: bool,
-    // True if empty output was produced at the current clock cycle.
-    empty_output: bool,
-    stats: JoinStats,
-    _types: PhantomData<(I, T, Z, It)>,
+    _types: PhantomData<(I1, I2, Z)>,
 }
 
-impl<F, I, T, Z, It, Clk> JoinTrace<F, I, T, Z, It, Clk>
-where
-    T: BatchReader,
-    Z: IndexedZSet,
-{
-    pub fn new(join_func: F, location: &'static Location<'static>, clock: Clk) -> Self {
+impl<F, I1, I2, Z> MonotonicJoin<F, I1, I2, Z> {
+    pub fn new(join_func: F, location: &'static Location<'static>) -> Self {
         Self {
-            clock,
             join_func,
             location,
-            output_batchers: HashMap::new(),
-            empty_input: false,
-            empty_output: false,
-            stats: JoinStats::new(),
             _types: PhantomData,
         }
     }
 }
 
-impl<F, I, T, Z, It, Clk> Operator for JoinTrace<F, I, T, Z, It, Clk>
+impl<F, I1, I2, Z> Operator for MonotonicJoin<F, I1, I2, Z>
 where
+    I1: 'static,
+    I2: 'static,
     F: 'static,
-    I: 'static,
-    T: BatchReader,
-    Z: IndexedZSet,
-    It: 'static,
-    Clk: WithClock<Time = T::Time> + 'static,
+    Z: 'static,
 {
     fn name(&self) -> Cow<'static, str> {
-        Cow::Borrowed("JoinTrace")
+        Cow::Borrowed("MonotonicJoin")
     }
 
     fn location(&self) -> OperatorLocation {
         Some(self.location)
     }
 
-    fn clock_start(&mut self, scope: Scope) {
-        if scope == 0 {
-            self.empty_input = false;
-            self.empty_output = false;
-        }
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
     }
+}
 
-    fn clock_end(&mut self, _scope: Scope) {
-        debug_assert!(self
-            .output_batchers
-            .keys()
-            .all(|time| !time.less_equal(&self.clock.time())));
-    }
+impl<F, I1, I2, Z> BinaryOperator<I1, I2, Z> for MonotonicJoin<F, I1, I2, Z>
+where
+    I1: BatchReader<Time = ()>,
+    I2: BatchReader<Key = I1::Key, Time = ()>,
+    F: Fn(&I1::Key, &I1::Val, &I2::Val) -> Z::Key + 'static,
+    Z: ZSet,
+    I1::R: MulByRef<I2::R, Output = Z::R>,
+{
+    fn eval(&mut self, i1: &I1, i2: &I2) -> Z {
+        let mut cursor1 = i1.cursor();
+        let mut cursor2 = i2.cursor();
 
-    fn metadata(&self, meta: &mut OperatorMeta) {
-        let total_size: usize = self
-            .output_batchers
-            .values()
-            .map(|batcher| batcher.tuples())
-            .sum();
+        // Choose capacity heuristically.
+        let mut builder = Z::Builder::with_capacity((), min(i1.len(), i2.len()));
 
-        let batch_sizes = MetaItem::Array(
-            self.output_batchers
-                .values()
-                .map(|batcher| {
-                    let size = batcher.size_of();
+        while cursor1.key_valid() && cursor2.key_valid() {
+            match cursor1.key().cmp(cursor2.key()) {
+                Ordering::Less => cursor1.seek_key(cursor2.key()),
+                Ordering::Greater => cursor2.seek_key(cursor1.key()),
+                Ordering::Equal => {
+                    while cursor1.val_valid() {
+                        let w1 = cursor1.weight();
+                        let v1 = cursor1.val();
+                        while cursor2.val_valid() {
+                            let w2 = cursor2.weight();
+                            let v2 = cursor2.val();
 
-                    MetaItem::Map(
-                        metadata! {
-                            "allocated" => MetaItem::bytes(size.total_bytes()),
-                            "used" => MetaItem::bytes(size.used_bytes()),
+                            builder.push((
+                                Z::item_from((self.join_func)(cursor1.key(), v1, v2), ()),
+                                w1.mul_by_ref(&w2),
+                            ));
+                            cursor2.step_val();
                         }
-                        .into(),
-                    )
-                })
-                .collect(),
-        );
-
-        let bytes = {
-            let mut context = Context::new();
-            for batcher in self.output_batchers.values() {
-                batcher.size_of_with_context(&mut context);
-            }
 
-            context.size_of()
-        };
+                        cursor2.rewind_vals();
+                        cursor1.step_val();
+                    }
 
-        // Find the percentage of consolidated outputs
-        let mut output_redundancy = ((self.stats.output_tuples as f64
-            - self.stats.produced_tuples as f64)
-            / self.stats.output_tuples as f64)
-            * 100.0;
-        if output_redundancy.is_nan() {
-            output_redundancy = 0.0;
-        } else if output_redundancy.is_infinite() {
-            output_redundancy = 100.0;
+                    cursor1.step_key();
+                    cursor2.step_key();
+                }
+            }
         }
 
-        meta.extend(metadata! {
-            "total size" => total_size,
-            "batch sizes" => batch_sizes,
-            "used bytes" => MetaItem::bytes(bytes.used_bytes()),
-            "allocations" => bytes.distinct_allocations(),
-            "shared bytes" => MetaItem::bytes(bytes.shared_bytes()),
-            "left inputs" => self.stats.lhs_tuples,
-            "right inputs" => self.stats.rhs_tuples,
-            "computed outputs" => self.stats.output_tuples,
-            "produced outputs" => self.stats.produced_tuples,
-            "output redundancy" => MetaItem::Percent(output_redundancy),
-        });
+        builder.done()
     }
+}
 
-    fn fixedpoint(&self, scope: Scope) -> bool {
-        let epoch_end = self.clock.time().epoch_end(scope);
-        // We're in a stable state if input and output at the current clock cycle are
-        // both empty, and there are no precomputed outputs before the end of the
-        // clock epoch.
-        self.empty_input
-            && self.empty_output
-            && self
-                .output_batchers
-                .keys()
-                .all(|time| !time.less_equal(&epoch_end))
+/// Join two streams of batches using a custom key comparator.
+///
+/// See [`Stream::join_by`](`crate::circuit::Stream::join_by`).
+pub struct JoinBy<F, CMP, I1, I2, Z> {
+    join_func: F,
+    comparator: CMP,
+    location: &'static Location<'static>,
+    _types: PhantomData<(I1, I2, Z)>,
+}
+
+impl<F, CMP, I1, I2, Z> JoinBy<F, CMP, I1, I2, Z> {
+    pub fn new(join_func: F, comparator: CMP, location: &'static Location<'static>) -> Self {
+        Self {
+            join_func,
+            comparator,
+            location,
+            _types: PhantomData,
+        }
     }
 }
 
-impl<F, I, T, Z, It, Clk> BinaryOperator<I, T, Z> for JoinTrace<F, I, T, Z, It, Clk>
+impl<F, CMP, I1, I2, Z> Operator for JoinBy<F, CMP, I1, I2, Z>
 where
-    I: IndexedZSet,
-    T: Trace<Key = I::Key, R = I::R>,
-    F: Clone + Fn(&I::Key, &I::Val, &T::Val) -> It + 'static,
-    Z: IndexedZSet<R = I::R>, /* + ::std::fmt::Display */
-    Z::R: ZRingValue,
-    It: IntoIterator<Item = (Z::Key, Z::Val)> + 'static,
-    Clk: WithClock<Time = T::Time> + 'static,
+    I1: 'static,
+    I2: 'static,
+    F: 'static,
+    CMP: 'static,
+    Z: 'static,
 {
-    fn eval(&mut self, index: &I, trace: &T) -> Z {
-        self.stats.lhs_tuples += index.len();
-        self.stats.rhs_tuples = trace.len();
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("JoinBy")
+    }
 
-        self.empty_input = index.is_empty();
+    fn location(&self) -> OperatorLocation {
+        Some(self.location)
+    }
 
-        // Buffer to collect output tuples.
-        // One allocation per clock tick is acceptable; however the actual output can be
-        // larger than `index.len()`.  If re-allocations becomes a problem, we
-        // may need to do something smarter, like a chain of buffers.
-        // TODO: Sub-scopes can cause a lot of inner clock cycles to be set off, so this
-        //       actually could be significant
-        let mut output_tuples = Vec::with_capacity(index.len());
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
 
-        let mut index_cursor = index.cursor();
-        let mut trace_cursor = trace.cursor();
+impl<F, CMP, I1, I2, Z> BinaryOperator<I1, I2, Z> for JoinBy<F, CMP, I1, I2, Z>
+where
+    I1: BatchReader<Time = ()>,
+    I1::R: MulByRef<I2::R, Output = Z::R>,
+    I2: BatchReader<Key = I1::Key, Time = ()>,
+    CMP: Fn(&I1::Key, &I1::Key) -> Ordering + 'static,
+    F: Fn(&I1::Key, &I1::Val, &I2::Val) -> Z::Key + 'static,
+    Z: ZSet,
+{
+    fn eval(&mut self, i1: &I1, i2: &I2) -> Z {
+        let mut cursor1 = i1.cursor();
+        let mut cursor2 = i2.cursor();
 
-        let time = self.clock.time();
+        let mut batch = Vec::with_capacity(min(i1.len(), i2.len()));
 
-        while index_cursor.key_valid() && trace_cursor.key_valid() {
-            match index_cursor.key().cmp(trace_cursor.key()) {
-                Ordering::Less => index_cursor.seek_key(trace_cursor.key()),
-                Ordering::Greater => trace_cursor.seek_key(index_cursor.key()),
+        // Values of a run of keys in `i2` that are equal according to the
+        // comparator.  We can't rewind the cursor to the start of the run,
+        // so we buffer the run instead.
+        let mut run = Vec::new();
+
+        while cursor1.key_valid() && cursor2.key_valid() {
+            match (self.comparator)(cursor1.key(), cursor2.key()) {
+                // We can't use `seek_key`, which relies on the natural
+                // ordering of keys.
+                Ordering::Less => cursor1.step_key(),
+                Ordering::Greater => cursor2.step_key(),
                 Ordering::Equal => {
-                    //println!("key: {}", index_cursor.key(index));
+                    let run_key = cursor2.key().clone();
 
-                    while index_cursor.val_valid() {
-                        let w1 = index_cursor.weight();
-                        let v1 = index_cursor.val();
-                        //println!("v1: {}, w1: {}", v1, w1);
+                    run.clear();
+                    while cursor2.key_valid()
+                        && (self.comparator)(&run_key, cursor2.key()) == Ordering::Equal
+                    {
+                        while cursor2.val_valid() {
+                            run.push((cursor2.val().clone(), cursor2.weight()));
+                            cursor2.step_val();
+                        }
+                        cursor2.step_key();
+                    }
 
-                        while trace_cursor.val_valid() {
-                            let output =
-                                (self.join_func)(index_cursor.key(), v1, trace_cursor.val());
-                            for (k, v) in output {
-                                trace_cursor.map_times(|ts, w2| {
-                                    output_tuples.push((
-                                        ts.join(&time),
-                                        MaybeUninit::new((
-                                            Z::item_from(k.clone(), v.clone()),
-                                            w1.mul_by_ref(w2),
-                                        )),
-                                    ));
-                                });
+                    while cursor1.key_valid()
+                        && (self.comparator)(cursor1.key(), &run_key) == Ordering::Equal
+                    {
+                        while cursor1.val_valid() {
+                            let w1 = cursor1.weight();
+                            let v1 = cursor1.val();
+                            for (v2, w2) in run.iter() {
+                                batch.push((
+                                    (self.join_func)(cursor1.key(), v1, v2),
+                                    w1.mul_by_ref(w2),
+                                ));
                             }
-                            trace_cursor.step_val();
+                            cursor1.step_val();
                         }
-                        trace_cursor.rewind_vals();
-                        index_cursor.step_val();
+                        cursor1.step_key();
                     }
-
-                    index_cursor.step_key();
-                    trace_cursor.step_key();
                 }
             }
         }
 
-        self.stats.output_tuples += output_tuples.len();
-        // Sort `output_tuples` by timestamp and push all tuples for each unique
-        // timestamp to the appropriate batcher.
-        output_tuples.sort_by(|(t1, _), (t2, _)| t1.cmp(t2));
-
-        /// Ensures that we don't leak any values if we panic while creating
-        /// output batches
-        struct BatchPusher<T, U> {
-            // Invariant: `tuples[start..]` are all initialized
-            start: usize,
-            tuples: Vec<(T, MaybeUninit<U>)>,
-        }
-
-        impl<T, U> BatchPusher<T, U> {
-            const fn new(tuples: Vec<(T, MaybeUninit<U>)>) -> Self {
-                Self { start: 0, tuples }
-            }
+        Z::from_keys((), batch)
+    }
+}
 
-            fn is_empty(&self) -> bool {
-                self.start >= self.tuples.len()
-            }
+/// Cartesian product of two streams of batches.
+///
+/// See [`Stream::cross_join`](`crate::circuit::Stream::cross_join`).
+pub struct CrossJoin<F, I1, I2, Z> {
+    join_func: F,
+    max_output: usize,
+    error: Option<String>,
+    location: &'static Location<'static>,
+    _types: PhantomData<(I1, I2, Z)>,
+}
 
-            fn current_time(&self) -> &T {
-                &self.tuples[self.start].0
-            }
+impl<F, I1, I2, Z> CrossJoin<F, I1, I2, Z> {
+    pub fn new(join_func: F, max_output: usize, location: &'static Location<'static>) -> Self {
+        Self {
+            join_func,
+            max_output,
+            error: None,
+            location,
+            _types: PhantomData,
         }
+    }
+}
 
-        impl<T, U> Drop for BatchPusher<T, U> {
-            fn drop(&mut self) {
-                if needs_drop::<U>() {
-                    for (_, tuple) in &mut self.tuples[self.start..] {
-                        // Safety: `tuples[start..]` are all initialized
-                        unsafe { tuple.assume_init_drop() };
-                    }
-                }
-            }
-        }
+impl<F, I1, I2, Z> Operator for CrossJoin<F, I1, I2, Z>
+where
+    I1: 'static,
+    I2: 'static,
+    F: 'static,
+    Z: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("CrossJoin")
+    }
 
-        let mut batch = Vec::new();
-        let mut pusher = BatchPusher::new(output_tuples);
+    fn location(&self) -> OperatorLocation {
+        Some(self.location)
+    }
 
-        while !pusher.is_empty() {
-            let batch_time = pusher.current_time().clone();
+    fn take_error(&mut self) -> Option<String> {
+        self.error.take()
+    }
 
-            let run_length =
-                pusher.tuples[pusher.start..].partition_point(|(time, _)| *time == batch_time);
-            batch.reserve(run_length);
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
 
-            let run = pusher.tuples[pusher.start..pusher.start + run_length]
-                .iter_mut()
-                // Safety: All values in `pusher.tuples[pusher.start..]` are initialized and we
-                //         mark the values we take from it by incrementing `pusher.start`
-                .map(|(_, tuple)| unsafe { tuple.assume_init_read() });
-            pusher.start += run_length;
-            batch.extend(run);
+impl<F, I1, I2, Z> BinaryOperator<I1, I2, Z> for CrossJoin<F, I1, I2, Z>
+where
+    I1: BatchReader<Time = ()>,
+    I1::R: MulByRef<I2::R, Output = Z::R>,
+    I2: BatchReader<Time = ()>,
+    F: Fn(&I1::Key, &I1::Val, &I2::Key, &I2::Val) -> Z::Key + 'static,
+    Z: ZSet,
+{
+    fn eval(&mut self, i1: &I1, i2: &I2) -> Z {
+        let mut cursor1 = i1.cursor();
+        let mut cursor2 = i2.cursor();
 
-            self.output_batchers
-                .entry(batch_time)
-                .or_insert_with(|| Z::Batcher::new_batcher(()))
-                .push_batch(&mut batch);
-            batch.clear();
+        let mut batch = Vec::new();
+
+        while cursor1.key_valid() {
+            while cursor1.val_valid() {
+                let w1 = cursor1.weight();
+                let v1 = cursor1.val();
+                while cursor2.key_valid() {
+                    while cursor2.val_valid() {
+                        if batch.len() >= self.max_output {
+                            self.error = Some(format!(
+                                "cross join output exceeds the limit of {} tuples",
+                                self.max_output
+                            ));
+                            return Z::empty(());
+                        }
+
+                        let w2 = cursor2.weight();
+                        batch.push((
+                            (self.join_func)(cursor1.key(), v1, cursor2.key(), cursor2.val()),
+                            w1.mul_by_ref(&w2),
+                        ));
+                        cursor2.step_val();
+                    }
+                    cursor2.step_key();
+                }
+
+                cursor2.rewind_keys();
+                cursor1.step_val();
+            }
+            cursor1.step_key();
         }
 
-        // Finalize the batch for the current timestamp and return it.
-        let batcher = self
+        Z::from_keys((), batch)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+struct JoinStats {
+    lhs_tuples: usize,
+    rhs_tuples: usize,
+    output_tuples: usize,
+    produced_tuples: usize,
+}
+
+impl JoinStats {
+    pub const fn new() -> Self {
+        Self {
+            lhs_tuples: 0,
+            rhs_tuples: 0,
+            output_tuples: 0,
+            produced_tuples: 0,
+        }
+    }
+}
+
+/// Join the changes in a stream with an integrated trace.
+///
+/// Used to implement [`Stream::join_generic`](`crate::circuit::Stream::join_generic`)
+/// and the operators built on top of it.
+///
+/// # Output batch type
+///
+/// The output can be any [`IndexedZSet`] implementation.  The operator
+/// doesn't rely on the internals of [`OrdZSet`] or its batcher, only on the
+/// following methods of the output type:
+///
+/// * [`Batch::item_from`](`crate::trace::Batch::item_from`), to convert
+///   the output of the join function into items.
+/// * [`Batch::Batcher`](`crate::trace::Batch::Batcher`), which accumulates
+///   unsorted, unconsolidated items across calls to
+///   [`push_batch`](`crate::trace::Batcher::push_batch`) before
+///   [`seal`](`crate::trace::Batcher::seal`) turns them into a batch.  The
+///   operator keeps batchers for future timestamps between clock cycles and
+///   reports their [`tuples`](`crate::trace::Batcher::tuples`) and
+///   [`SizeOf`] in its metadata.
+/// * [`Batch::merge`](`crate::trace::Batch::merge`), which in turn uses
+///   [`Batch::Merger`](`crate::trace::Batch::Merger`).
+/// * [`Batch::empty`](`crate::trace::Batch::empty`).
+pub struct JoinTrace<F, I, T, Z, It, Clk>
+where
+    T: BatchReader,
+    Z: IndexedZSet,
+{
+    clock: Clk,
+    join_func: F,
+    location: &'static Location<'static>,
+    // Future update batches computed ahead of time, indexed by time
+    // when each batch should be output.
+    output_batchers: HashMap<T::Time, Z::Batcher>,
+    // Estimated size of each batcher in `output_batchers`.
+    batcher_bytes: HashMap<T::Time, usize>,
+    // Running sum of `batcher_bytes`, so that `MemoryStats::bytes` doesn't
+    // need to traverse all batchers.
+    total_batcher_bytes: usize,
+    // Consolidate output tuples in a hash map as they are produced instead of
+    // buffering all of them before pushing them to the batchers.
+    consolidate_outputs: bool,
+    // True if empty input batch was received at the current clock cycle.
+    empty_input: bool,
+    // True if empty output was produced at the current clock cycle.
+    empty_output: bool,
+    // Low watermark: output batchers for past times less than or equal to
+    // the watermark are flushed at the next clock cycle.
+    watermark: JoinWatermark<T::Time>,
+    max_fanout_per_key: Option<usize>,
+    error: Option<String>,
+    stats: JoinStats,
+    _types: PhantomData<(I, T, Z, It)>,
+}
+
+impl<F, I, T, Z, It, Clk> JoinTrace<F, I, T, Z, It, Clk>
+where
+    T: BatchReader,
+    Z: IndexedZSet,
+{
+    pub fn new(join_func: F, location: &'static Location<'static>, clock: Clk) -> Self {
+        Self {
+            clock,
+            join_func,
+            location,
+            output_batchers: HashMap::new(),
+            batcher_bytes: HashMap::new(),
+            total_batcher_bytes: 0,
+            consolidate_outputs: false,
+            empty_input: false,
+            empty_output: false,
+            watermark: JoinWatermark::new(),
+            max_fanout_per_key: None,
+            error: None,
+            stats: JoinStats::new(),
+            _types: PhantomData,
+        }
+    }
+
+    /// Consolidate output tuples with identical keys, values, and timestamps
+    /// as they are computed.
+    ///
+    /// See [`Stream::join_distinct`].
+    pub fn with_consolidated_outputs(mut self, consolidate_outputs: bool) -> Self {
+        self.consolidate_outputs = consolidate_outputs;
+        self
+    }
+
+    /// Fail if the number of output tuples produced for a single key exceeds
+    /// `max_fanout_per_key`.
+    ///
+    /// See [`Stream::join_with_max_fanout`].
+    pub fn with_max_fanout_per_key(mut self, max_fanout_per_key: Option<usize>) -> Self {
+        self.max_fanout_per_key = max_fanout_per_key;
+        self
+    }
+
+    /// Evict output batchers for times behind `watermark`.
+    ///
+    /// At each clock cycle, the operator removes the output batchers for
+    /// times that are less than or equal to the watermark.  Batchers for
+    /// times less than or equal to the current time are merged into the
+    /// current output; batchers for future times are dropped.
+    ///
+    /// See [`Stream::join_with_watermark`].
+    pub fn with_watermark(mut self, watermark: JoinWatermark<T::Time>) -> Self {
+        self.watermark = watermark;
+        self
+    }
+
+    /// Record the size of the batcher for `time` in the running byte counter;
+    /// `bytes` is 0 if the batcher has been removed.
+    fn set_batcher_bytes(&mut self, time: &T::Time, bytes: usize) {
+        let old_bytes = if bytes == 0 {
+            self.batcher_bytes.remove(time)
+        } else {
+            self.batcher_bytes.insert(time.clone(), bytes)
+        }
+        .unwrap_or(0);
+
+        self.total_batcher_bytes = self.total_batcher_bytes - old_bytes + bytes;
+    }
+}
+
+impl<F, I, T, Z, It, Clk> Operator for JoinTrace<F, I, T, Z, It, Clk>
+where
+    F: 'static,
+    I: 'static,
+    T: BatchReader,
+    Z: IndexedZSet,
+    It: 'static,
+    Clk: WithClock<Time = T::Time> + 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("JoinTrace")
+    }
+
+    fn location(&self) -> OperatorLocation {
+        Some(self.location)
+    }
+
+    fn take_error(&mut self) -> Option<String> {
+        self.error.take()
+    }
+
+    fn clock_start(&mut self, scope: Scope) {
+        if scope == 0 {
+            self.empty_input = false;
+            self.empty_output = false;
+        }
+    }
+
+    fn clock_end(&mut self, _scope: Scope) {
+        debug_assert!(self
             .output_batchers
-            .remove(&time)
-            .unwrap_or_else(|| Z::Batcher::new_batcher(()));
+            .keys()
+            .all(|time| !time.less_equal(&self.clock.time())));
+    }
 
-        let result = batcher.seal();
-        self.stats.produced_tuples += result.len();
-        self.empty_output = result.is_empty();
+    fn metadata(&self, meta: &mut OperatorMeta) {
+        let total_size = self.tuples();
 
-        result
+        let batch_sizes = MetaItem::Array(
+            self.output_batchers
+                .values()
+                .map(|batcher| {
+                    let size = batcher.size_of();
+
+                    MetaItem::Map(
+                        metadata! {
+                            "allocated" => MetaItem::bytes(size.total_bytes()),
+                            "used" => MetaItem::bytes(size.used_bytes()),
+                        }
+                        .into(),
+                    )
+                })
+                .collect(),
+        );
+
+        let bytes = {
+            let mut context = Context::new();
+            for batcher in self.output_batchers.values() {
+                batcher.size_of_with_context(&mut context);
+            }
+
+            context.size_of()
+        };
+
+        // Find the percentage of consolidated outputs
+        let mut output_redundancy = ((self.stats.output_tuples as f64
+            - self.stats.produced_tuples as f64)
+            / self.stats.output_tuples as f64)
+            * 100.0;
+        if output_redundancy.is_nan() {
+            output_redundancy = 0.0;
+        } else if output_redundancy.is_infinite() {
+            output_redundancy = 100.0;
+        }
+
+        meta.extend(metadata! {
+            "total size" => total_size,
+            "batch sizes" => batch_sizes,
+            "used bytes" => MetaItem::bytes(bytes.used_bytes()),
+            "allocations" => bytes.distinct_allocations(),
+            "shared bytes" => MetaItem::bytes(bytes.shared_bytes()),
+            "left inputs" => self.stats.lhs_tuples,
+            "right inputs" => self.stats.rhs_tuples,
+            "computed outputs" => self.stats.output_tuples,
+            "produced outputs" => self.stats.produced_tuples,
+            "output redundancy" => MetaItem::Percent(output_redundancy),
+        });
+    }
+
+    fn memory_stats(&self) -> Option<&dyn MemoryStats> {
+        Some(self)
+    }
+
+    fn fixedpoint(&self, scope: Scope) -> bool {
+        let epoch_end = self.clock.time().epoch_end(scope);
+        // We're in a stable state if input and output at the current clock cycle are
+        // both empty, and there are no precomputed outputs before the end of the
+        // clock epoch.
+        self.empty_input
+            && self.empty_output
+            && self
+                .output_batchers
+                .keys()
+                .all(|time| !time.less_equal(&epoch_end))
     }
 }
 
-#[cfg(test)]
-mod test {
-    use crate::{
-        circuit::WithClock,
-        indexed_zset,
-        operator::{DelayedFeedback, FilterMap, Generator},
-        trace::{
-            ord::{OrdIndexedZSet, OrdZSet},
-            Batch,
-        },
-        zset, Circuit, DBTimestamp, RootCircuit, Runtime, Stream, Timestamp,
-    };
-    use rkyv::{Archive, Deserialize, Serialize};
-    use size_of::SizeOf;
-    use std::{
-        fmt::{Display, Formatter},
-        hash::Hash,
-        sync::{Arc, Mutex},
-        vec,
-    };
+impl<F, I, T, Z, It, Clk> MemoryStats for JoinTrace<F, I, T, Z, It, Clk>
+where
+    T: BatchReader,
+    Z: IndexedZSet,
+{
+    fn tuples(&self) -> usize {
+        self.output_batchers
+            .values()
+            .map(|batcher| batcher.tuples())
+            .sum()
+    }
 
-    #[test]
-    fn join_test() {
-        let circuit = RootCircuit::build(move |circuit| {
-            let mut input1 = vec![
-                zset! {
-                    (1, "a".to_string()) => 1,
-                    (1, "b".to_string()) => 2,
-                    (2, "c".to_string()) => 3,
-                    (2, "d".to_string()) => 4,
-                    (3, "e".to_string()) => 5,
-                    (3, "f".to_string()) => -2,
-                },
-                zset! {(1, "a".to_string()) => 1},
-                zset! {(1, "a".to_string()) => 1},
-                zset! {(4, "n".to_string()) => 2},
-                zset! {(1, "a".to_string()) => 0},
-            ]
-            .into_iter();
-            let mut input2 = vec![
+    /// Returns the value of a running byte counter, which the operator
+    /// updates with the size of the tuples it pushes to each batcher, instead
+    /// of traversing all batchers.  The counter is an estimate: batchers are
+    /// only measured after they cancel out some of their tuples.
+    fn bytes(&self) -> usize {
+        // Include the memory allocated by the map itself.
+        self.output_batchers.capacity() * std::mem::size_of::<(T::Time, Z::Batcher)>()
+            + self.total_batcher_bytes
+    }
+}
+
+impl<F, I, T, Z, It, Clk> BinaryOperator<I, T, Z> for JoinTrace<F, I, T, Z, It, Clk>
+where
+    I: IndexedZSet,
+    T: Trace<Key = I::Key, R = I::R>,
+    F: Clone + Fn(&I::Key, &I::Val, &T::Val) -> It + 'static,
+    Z: IndexedZSet<R = I::R>, /* + ::std::fmt::Display */
+    Z::R: ZRingValue,
+    It: IntoIterator<Item = (Z::Key, Z::Val)> + 'static,
+    Clk: WithClock<Time = T::Time> + 'static,
+{
+    fn eval(&mut self, index: &I, trace: &T) -> Z {
+        self.stats.lhs_tuples += index.len();
+        self.stats.rhs_tuples = trace.len();
+
+        self.empty_input = index.is_empty();
+
+        // Buffer to collect output tuples.
+        // One allocation per clock tick is acceptable; however the actual output can be
+        // larger than `index.len()`.  If re-allocations becomes a problem, we
+        // may need to do something smarter, like a chain of buffers.
+        // TODO: Sub-scopes can cause a lot of inner clock cycles to be set off, so this
+        //       actually could be significant
+        let mut output_tuples = Vec::with_capacity(index.len());
+
+        let mut index_cursor = index.cursor();
+        let mut trace_cursor = trace.cursor();
+
+        let time = self.clock.time();
+
+        // Output tuples consolidated by time, key, and value.
+        #[allow(clippy::type_complexity)]
+        let mut consolidated_tuples: Option<HashMap<(T::Time, Z::Key, Z::Val), Z::R>> =
+            self.consolidate_outputs.then(HashMap::new);
+
+        // Heap memory owned by the keys and values of output tuples, by time,
+        // used to update the size of the batchers they are pushed to.
+        let mut heap_bytes: HashMap<T::Time, usize> = HashMap::new();
+
+        while index_cursor.key_valid() && trace_cursor.key_valid() {
+            match index_cursor.key().cmp(trace_cursor.key()) {
+                Ordering::Less => index_cursor.seek_key(trace_cursor.key()),
+                Ordering::Greater => trace_cursor.seek_key(index_cursor.key()),
+                Ordering::Equal => {
+                    //println!("key: {}", index_cursor.key(index));
+
+                    if let Some(max_fanout) = self.max_fanout_per_key {
+                        let fanout = count_vals(&mut index_cursor) * count_vals(&mut trace_cursor);
+                        if fanout > max_fanout {
+                            self.error = Some(fanout_error(fanout, max_fanout));
+                            self.empty_output = true;
+                            return Z::empty(());
+                        }
+                    }
+
+                    while index_cursor.val_valid() {
+                        let w1 = index_cursor.weight();
+                        let v1 = index_cursor.val();
+                        //println!("v1: {}, w1: {}", v1, w1);
+
+                        while trace_cursor.val_valid() {
+                            let output =
+                                (self.join_func)(index_cursor.key(), v1, trace_cursor.val());
+                            for (k, v) in output {
+                                let tuple_heap_bytes = children_bytes(&k, &v);
+                                trace_cursor.map_times(|ts, w2| {
+                                    if let Some(consolidated_tuples) = &mut consolidated_tuples {
+                                        consolidated_tuples
+                                            .entry((ts.join(&time), k.clone(), v.clone()))
+                                            .or_insert_with(HasZero::zero)
+                                            .add_assign_by_ref(&w1.mul_by_ref(w2));
+                                    } else {
+                                        let output_time = ts.join(&time);
+                                        *heap_bytes.entry(output_time.clone()).or_insert(0) +=
+                                            tuple_heap_bytes;
+                                        output_tuples.push((
+                                            output_time,
+                                            MaybeUninit::new((
+                                                Z::item_from(k.clone(), v.clone()),
+                                                w1.mul_by_ref(w2),
+                                            )),
+                                        ));
+                                    }
+                                });
+                            }
+                            trace_cursor.step_val();
+                        }
+                        trace_cursor.rewind_vals();
+                        index_cursor.step_val();
+                    }
+
+                    index_cursor.step_key();
+                    trace_cursor.step_key();
+                }
+            }
+        }
+
+        if let Some(consolidated_tuples) = consolidated_tuples {
+            output_tuples.extend(
+                consolidated_tuples
+                    .into_iter()
+                    .filter(|(_, w)| !w.is_zero())
+                    .map(|((ts, k, v), w)| {
+                        *heap_bytes.entry(ts.clone()).or_insert(0) += children_bytes(&k, &v);
+                        (ts, MaybeUninit::new((Z::item_from(k, v), w)))
+                    }),
+            );
+        }
+
+        self.stats.output_tuples += output_tuples.len();
+        // Sort `output_tuples` by timestamp and push all tuples for each unique
+        // timestamp to the appropriate batcher.
+        output_tuples.sort_by(|(t1, _), (t2, _)| t1.cmp(t2));
+
+        /// Ensures that we don't leak any values if we panic while creating
+        /// output batches
+        struct BatchPusher<T, U> {
+            // Invariant: `tuples[start..]` are all initialized
+            start: usize,
+            tuples: Vec<(T, MaybeUninit<U>)>,
+        }
+
+        impl<T, U> BatchPusher<T, U> {
+            const fn new(tuples: Vec<(T, MaybeUninit<U>)>) -> Self {
+                Self { start: 0, tuples }
+            }
+
+            fn is_empty(&self) -> bool {
+                self.start >= self.tuples.len()
+            }
+
+            fn current_time(&self) -> &T {
+                &self.tuples[self.start].0
+            }
+        }
+
+        impl<T, U> Drop for BatchPusher<T, U> {
+            fn drop(&mut self) {
+                if needs_drop::<U>() {
+                    for (_, tuple) in &mut self.tuples[self.start..] {
+                        // Safety: `tuples[start..]` are all initialized
+                        unsafe { tuple.assume_init_drop() };
+                    }
+                }
+            }
+        }
+
+        let mut batch = Vec::new();
+        let mut pusher = BatchPusher::new(output_tuples);
+        while !pusher.is_empty() {
+            let batch_time = pusher.current_time().clone();
+
+            let run_length =
+                pusher.tuples[pusher.start..].partition_point(|(time, _)| *time == batch_time);
+            batch.reserve(run_length);
+
+            let run = pusher.tuples[pusher.start..pusher.start + run_length]
+                .iter_mut()
+                // Safety: All values in `pusher.tuples[pusher.start..]` are initialized and we
+                //         mark the values we take from it by incrementing `pusher.start`
+                .map(|(_, tuple)| unsafe { tuple.assume_init_read() });
+            pusher.start += run_length;
+            batch.extend(run);
+
+            // The batcher takes ownership of the pushed tuples, so, unless it
+            // consolidates them with earlier tuples, it grows by their size.
+            // When the number of tuples in the batcher grows by less than the
+            // number of pushed tuples, the batcher has merged and cancelled
+            // out some tuples, so its size is measured instead.
+            let pushed_tuples = batch.len();
+            let pushed_bytes = batch.capacity() * std::mem::size_of::<(Z::Item, Z::R)>()
+                + heap_bytes.get(&batch_time).copied().unwrap_or(0);
+            let batcher = self
+                .output_batchers
+                .entry(batch_time.clone())
+                .or_insert_with(|| Z::Batcher::new_batcher(()));
+            let old_tuples = batcher.tuples();
+            batcher.push_batch(&mut batch);
+            let bytes = if batcher.tuples() == old_tuples + pushed_tuples {
+                self.batcher_bytes.get(&batch_time).copied().unwrap_or(0) + pushed_bytes
+            } else {
+                batcher.size_of().total_bytes()
+            };
+            self.set_batcher_bytes(&batch_time, bytes);
+            batch.clear();
+        }
+
+        // Finalize the batch for the current timestamp and return it.
+        let batcher = self
+            .output_batchers
+            .remove(&time)
+            .unwrap_or_else(|| Z::Batcher::new_batcher(()));
+        self.set_batcher_bytes(&time, 0);
+
+        let mut result = batcher.seal();
+
+        // Evict batchers for times that fall behind the watermark.  Outputs
+        // for past times are merged into the current output.  Outputs for
+        // future times are dropped, since the clock will never reach them.
+        if let Some(watermark) = self.watermark.get() {
+            let stale_times: Vec<T::Time> = self
+                .output_batchers
+                .keys()
+                .filter(|batch_time| batch_time.less_equal(&watermark))
+                .cloned()
+                .collect();
+
+            for batch_time in stale_times {
+                if let Some(batcher) = self.output_batchers.remove(&batch_time) {
+                    let batch = batcher.seal();
+                    if batch_time.less_equal(&time) {
+                        result = result.merge(&batch);
+                    }
+                }
+                self.set_batcher_bytes(&batch_time, 0);
+            }
+        }
+
+        self.stats.produced_tuples += result.len();
+        self.empty_output = result.is_empty();
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{JoinTrace, JoinWatermark};
+    use crate::{
+        circuit::{
+            operator_traits::{BinaryOperator, MemoryStats},
+            WithClock,
+        },
+        indexed_zset,
+        operator::{
+            communication::PartitionScheme, DelayedFeedback, FilterMap, Generator, HashJoinSide,
+            JoinRetention, TraceRetention,
+        },
+        time::NestedTimestamp32,
+        trace::{
+            ord::{OrdIndexedZSet, OrdValBatch, OrdValSpine, OrdZSet},
+            Batch, BatchReader, Trace,
+        },
+        zset, Circuit, DBTimestamp, IndexedZSet, RootCircuit, Runtime, SchedulerError, Stream,
+        Timestamp,
+    };
+    use rkyv::{Archive, Deserialize, Serialize};
+    use size_of::{Context, SizeOf};
+    use std::{
+        borrow::Cow,
+        cell::Cell,
+        fmt::{Display, Formatter},
+        hash::Hash,
+        panic::Location,
+        rc::Rc,
+        sync::{Arc, Mutex},
+        vec,
+    };
+
+    #[test]
+    fn join_test() {
+        let circuit = RootCircuit::build(move |circuit| {
+            let mut input1 = vec![
+                zset! {
+                    (1, "a".to_string()) => 1,
+                    (1, "b".to_string()) => 2,
+                    (2, "c".to_string()) => 3,
+                    (2, "d".to_string()) => 4,
+                    (3, "e".to_string()) => 5,
+                    (3, "f".to_string()) => -2,
+                },
+                zset! {(1, "a".to_string()) => 1},
+                zset! {(1, "a".to_string()) => 1},
+                zset! {(4, "n".to_string()) => 2},
+                zset! {(1, "a".to_string()) => 0},
+            ]
+            .into_iter();
+            let mut input2 = vec![
                 zset! {
                     (2, "g".to_string()) => 3,
                     (2, "h".to_string()) => 4,
@@ -969,432 +2255,1468 @@ mod test {
                 zset! {},
             ];
 
-            let mut inc_outputs = inc_outputs_vec.clone().into_iter();
-            let mut inc_outputs2 = inc_outputs_vec.into_iter();
+            let mut inc_outputs = inc_outputs_vec.clone().into_iter();
+            let mut inc_outputs2 = inc_outputs_vec.into_iter();
+
+            let index1: Stream<_, OrdIndexedZSet<usize, String, isize>> = circuit
+                .add_source(Generator::new(move || {
+                    if Runtime::worker_index() == 0 {
+                        input1.next().unwrap()
+                    } else {
+                        <OrdZSet<_, _>>::empty(())
+                    }
+                }))
+                .index();
+            let index2: Stream<_, OrdIndexedZSet<usize, String, isize>> = circuit
+                .add_source(Generator::new(move || {
+                    if Runtime::worker_index() == 0 {
+                        input2.next().unwrap()
+                    } else {
+                        <OrdZSet<_, _>>::empty(())
+                    }
+                }))
+                .index();
+            index1
+                .stream_join(&index2, |&k: &usize, s1, s2| (k, format!("{} {}", s1, s2)))
+                .gather(0)
+                .inspect(move |fm: &OrdZSet<(usize, String), _>| {
+                    if Runtime::worker_index() == 0 {
+                        assert_eq!(fm, &outputs.next().unwrap())
+                    }
+                });
+            index1
+                .join_incremental(&index2, |&k: &usize, s1, s2| (k, format!("{} {}", s1, s2)))
+                .gather(0)
+                .inspect(move |fm: &OrdZSet<(usize, String), _>| {
+                    if Runtime::worker_index() == 0 {
+                        assert_eq!(fm, &inc_outputs.next().unwrap())
+                    }
+                });
+
+            index1
+                .join(&index2, |&k: &usize, s1, s2| (k, format!("{} {}", s1, s2)))
+                .gather(0)
+                .inspect(move |fm: &OrdZSet<(usize, String), _>| {
+                    if Runtime::worker_index() == 0 {
+                        assert_eq!(fm, &inc_outputs2.next().unwrap())
+                    }
+                });
+            Ok(())
+        })
+        .unwrap()
+        .0;
+
+        for _ in 0..5 {
+            circuit.step().unwrap();
+        }
+    }
+
+    fn do_join_test_mt(workers: usize) {
+        let hruntime = Runtime::run(workers, || {
+            join_test();
+        });
+
+        hruntime.join().unwrap();
+    }
+
+    #[test]
+    fn join_test_mt() {
+        do_join_test_mt(1);
+        do_join_test_mt(2);
+        do_join_test_mt(4);
+        do_join_test_mt(16);
+    }
+
+    // Compute pairwise reachability relation between graph nodes as the
+    // transitive closure of the edge relation.
+    #[test]
+    fn join_trace_test() {
+        let circuit = RootCircuit::build(move |circuit| {
+            // Changes to the edges relation.
+            let mut edges: vec::IntoIter<OrdZSet<(usize, usize), isize>> = vec![
+                zset! { (1, 2) => 1 },
+                zset! { (2, 3) => 1},
+                zset! { (1, 3) => 1},
+                zset! { (3, 1) => 1},
+                zset! { (3, 1) => -1},
+                zset! { (1, 2) => -1},
+                zset! { (2, 4) => 1, (4, 1) => 1 },
+                zset! { (2, 3) => -1, (3, 2) => 1 },
+            ]
+            .into_iter();
+
+            // Expected content of the reachability relation.
+            let mut outputs: vec::IntoIter<OrdZSet<(usize, usize), isize>> = vec![
+                zset! { (1, 2) => 1 },
+                zset! { (1, 2) => 1, (2, 3) => 1, (1, 3) => 1 },
+                zset! { (1, 2) => 1, (2, 3) => 1, (1, 3) => 1 },
+                zset! { (1, 1) => 1, (2, 2) => 1, (3, 3) => 1, (1, 2) => 1, (1, 3) => 1, (2, 3) => 1, (2, 1) => 1, (3, 1) => 1, (3, 2) => 1},
+                zset! { (1, 2) => 1, (2, 3) => 1, (1, 3) => 1 },
+                zset! { (2, 3) => 1, (1, 3) => 1 },
+                zset! { (1, 3) => 1, (2, 3) => 1, (2, 4) => 1, (2, 1) => 1, (4, 1) => 1, (4, 3) => 1 },
+                zset! { (1, 1) => 1, (2, 2) => 1, (3, 3) => 1, (4, 4) => 1,
+                              (1, 2) => 1, (1, 3) => 1, (1, 4) => 1,
+                              (2, 1) => 1, (2, 3) => 1, (2, 4) => 1,
+                              (3, 1) => 1, (3, 2) => 1, (3, 4) => 1,
+                              (4, 1) => 1, (4, 2) => 1, (4, 3) => 1 },
+            ]
+            .into_iter();
+
+            let edges: Stream<_, OrdZSet<(usize, usize), isize>> =
+                circuit
+                    .add_source(Generator::new(move || edges.next().unwrap()));
+
+            let paths = circuit.recursive(|child, paths_delayed: Stream<_, OrdZSet<(usize, usize), isize>>| {
+                // ```text
+                //                             distinct
+                //               ┌───┐          ┌───┐
+                // edges         │   │          │   │  paths
+                // ────┬────────►│ + ├──────────┤   ├────────┬───►
+                //     │         │   │          │   │        │
+                //     │         └───┘          └───┘        │
+                //     │           ▲                         │
+                //     │           │                         │
+                //     │         ┌─┴─┐                       │
+                //     │         │   │                       │
+                //     └────────►│ X │ ◄─────────────────────┘
+                //               │   │
+                //               └───┘
+                //               join
+                // ```
+                let edges = edges.delta0(child);
+
+                let paths_inverted: Stream<_, OrdZSet<(usize, usize), isize>> = paths_delayed
+                    .map(|&(x, y)| (y, x));
+
+                let paths_inverted_indexed = paths_inverted.index();
+                let edges_indexed = edges.index();
+
+                Ok(edges.plus(&paths_inverted_indexed.join(&edges_indexed, |_via, from, to| (*from, *to))))
+            })
+            .unwrap();
+
+            paths.integrate().stream_distinct().inspect(move |ps| {
+                assert_eq!(*ps, outputs.next().unwrap());
+            });
+            Ok(())
+        })
+        .unwrap().0;
+
+        for _ in 0..8 {
+            //eprintln!("{}", i);
+            circuit.step().unwrap();
+        }
+    }
+
+    #[derive(
+        Clone,
+        Debug,
+        Default,
+        Hash,
+        Ord,
+        PartialOrd,
+        Eq,
+        PartialEq,
+        SizeOf,
+        Archive,
+        Serialize,
+        Deserialize,
+    )]
+    struct Label(pub usize, pub u16);
+
+    impl Display for Label {
+        fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+            write!(f, "L({},{})", self.0, self.1)
+        }
+    }
+
+    #[derive(
+        Clone,
+        Debug,
+        Default,
+        Ord,
+        PartialOrd,
+        Hash,
+        Eq,
+        PartialEq,
+        SizeOf,
+        Archive,
+        Serialize,
+        Deserialize,
+    )]
+    struct Edge(pub usize, pub usize);
+
+    impl Display for Edge {
+        fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+            write!(f, "E({},{})", self.0, self.1)
+        }
+    }
+    // Recursively propagate node labels in an acyclic graph.
+    // The reason for supporting acyclic graphs only is that we use this to test
+    // the join operator in isolation, so we don't want to use `distinct`.
+    fn propagate<C>(
+        circuit: &C,
+        edges: &Stream<C, OrdZSet<Edge, isize>>,
+        labels: &Stream<C, OrdZSet<Label, isize>>,
+    ) -> Stream<C, OrdZSet<Label, isize>>
+    where
+        C: Circuit,
+        <<C as WithClock>::Time as Timestamp>::Nested: DBTimestamp,
+    {
+        let computed_labels = circuit
+            .fixedpoint(|child| {
+                let edges = edges.delta0(child);
+                let labels = labels.delta0(child);
+
+                let computed_labels = <DelayedFeedback<_, OrdZSet<_, _>>>::new(child);
+                let result: Stream<_, OrdZSet<Label, isize>> =
+                    labels.plus(computed_labels.stream());
+
+                computed_labels.connect(&result.index_with(|label| (label.0, label.1)).join(
+                    &edges.index_with(|edge| (edge.0, edge.1)),
+                    |_from, label, to| Label(*to, *label),
+                ));
+
+                Ok(result.integrate_trace().export())
+            })
+            .unwrap();
+
+        computed_labels.consolidate()
+    }
+
+    #[test]
+    fn propagate_test() {
+        let circuit = RootCircuit::build(move |circuit| {
+            let mut edges: vec::IntoIter<OrdZSet<Edge, isize>> = vec![
+                zset! { Edge(1, 2) => 1, Edge(1, 3) => 1, Edge(2, 4) => 1, Edge(3, 4) => 1 },
+                zset! { Edge(5, 7) => 1, Edge(6, 7) => 1 },
+                zset! { Edge(4, 5) => 1, Edge(4, 6) => 1, },
+                zset! { Edge(3, 8) => 1, Edge(8, 9) => 1 },
+                zset! { Edge(2, 4) => -1, Edge(7, 10) => 1 },
+                zset! { Edge(3, 4) => -1 },
+                zset! { Edge(1, 4) => 1 },
+                zset! { Edge(9, 7) => 1 },
+            ]
+            .into_iter();
+
+            let mut labels: vec::IntoIter<OrdZSet<Label, isize>> = vec![
+                zset! { Label(1, 0) => 1 },
+                zset! { Label(4, 1) => 1 },
+                zset! { },
+                zset! { Label(1, 0) => -1, Label(1, 2) => 1 },
+                zset! { },
+                zset! { Label(8, 3) => 1 },
+                zset! { Label(4, 1) => -1 },
+                zset! { },
+            ]
+            .into_iter();
+
+            let mut outputs: vec::IntoIter<OrdZSet<Label, isize>> = vec![
+                zset! { Label(1, 0) => 1, Label(2, 0) => 1, Label(3, 0) => 1, Label(4, 0) => 2 },
+                zset! { Label(4, 1) => 1 },
+                zset! { Label(5, 0) => 2, Label(5, 1) => 1, Label(6, 0) => 2, Label(6, 1) => 1, Label(7, 0) => 4, Label(7, 1) => 2 },
+                zset! { Label(1, 0) => -1, Label(1, 2) => 1, Label(2, 0) => -1, Label(2, 2) => 1, Label(3, 0) => -1, Label(3, 2) => 1, Label(4, 0) => -2, Label(4, 2) => 2, Label(5, 0) => -2, Label(5, 2) => 2, Label(6, 0) => -2, Label(6, 2) => 2, Label(7, 0) => -4, Label(7, 2) => 4, Label(8, 2) => 1, Label(9, 2) => 1 },
+                zset! { Label(4, 2) => -1, Label(5, 2) => -1, Label(6, 2) => -1, Label(7, 2) => -2, Label(10, 1) => 2, Label(10, 2) => 2 },
+                zset! { Label(4, 2) => -1, Label(5, 2) => -1, Label(6, 2) => -1, Label(7, 2) => -2, Label(8, 3) => 1, Label(9, 3) => 1, Label(10, 2) => -2 },
+                zset! { Label(4, 1) => -1, Label(4, 2) => 1, Label(5, 1) => -1, Label(5, 2) => 1, Label(6, 1) => -1, Label(6, 2) => 1, Label(7, 1) => -2, Label(7, 2) => 2, Label(10, 1) => -2, Label(10, 2) => 2 },
+                zset! { Label(7, 2) => 1, Label(7, 3) => 1, Label(10, 2) => 1, Label(10, 3) => 1 },
+            ]
+            .into_iter();
+
+            let edges: Stream<_, OrdZSet<Edge, isize>> =
+                circuit
+                    .add_source(Generator::new(move || edges.next().unwrap()));
+
+            let labels: Stream<_, OrdZSet<Label, isize>> =
+                circuit
+                    .add_source(Generator::new(move || labels.next().unwrap()));
+
+            propagate(circuit, &edges, &labels).inspect(move |labeled| {
+                assert_eq!(*labeled, outputs.next().unwrap());
+            });
+            Ok(())
+        })
+        .unwrap().0;
+
+        for _ in 0..8 {
+            //eprintln!("{}", i);
+            circuit.step().unwrap();
+        }
+    }
+
+    #[test]
+    fn propagate_nested_test() {
+        let circuit = RootCircuit::build(move |circuit| {
+            let mut edges: vec::IntoIter<OrdZSet<Edge, isize>> = vec![
+                zset! { Edge(1, 2) => 1, Edge(1, 3) => 1, Edge(2, 4) => 1, Edge(3, 4) => 1 },
+                zset! { Edge(5, 7) => 1, Edge(6, 7) => 1 },
+                zset! { Edge(4, 5) => 1, Edge(4, 6) => 1 },
+                zset! { Edge(3, 8) => 1, Edge(8, 9) => 1 },
+                zset! { Edge(2, 4) => -1, Edge(7, 10) => 1 },
+                zset! { Edge(3, 4) => -1 },
+                zset! { Edge(1, 4) => 1 },
+                zset! { Edge(9, 7) => 1 },
+            ]
+            .into_iter();
+
+            let mut labels: vec::IntoIter<OrdZSet<Label, isize>> = vec![
+                zset! { Label(1, 0) => 1 },
+                zset! { Label(4, 1) => 1 },
+                zset! { },
+                zset! { Label(1, 0) => -1, Label(1, 2) => 1 },
+                zset! { },
+                zset! { Label(8, 3) => 1 },
+                zset! { Label(4, 1) => -1 },
+                zset! { },
+            ]
+            .into_iter();
+
+            let mut outputs: vec::IntoIter<OrdZSet<Label, isize>> = vec![
+                zset!{ Label(1,0) => 2, Label(2,0) => 3, Label(3,0) => 3, Label(4,0) => 8 },
+                zset!{ Label(4,1) => 2 },
+                zset!{ Label(5,0) => 10, Label(5,1) => 3, Label(6,0) => 10, Label(6,1) => 3, Label(7,0) => 24, Label(7,1) => 8 },
+                zset!{ Label(1,0) => -2, Label(1,2) => 2, Label(2,0) => -3, Label(2,2) => 3, Label(3,0) => -3, Label(3,2) => 3,
+                       Label(4,0) => -8, Label(4,2) => 8, Label(5,0) => -10, Label(5,2) => 10, Label(6,0) => -10, Label(6,2) => 10,
+                       Label(7,0) => -24, Label(7,2) => 24, Label(8,2) => 4, Label(9,2) => 5 },
+                zset!{ Label(4,2) => -4, Label(5,2) => -5, Label(6,2) => -5, Label(7,2) => -12, Label(10,1) => 10, Label(10,2) => 14 },
+                zset!{ Label(4,2) => -4, Label(5,2) => -5, Label(6,2) => -5, Label(7,2) => -12, Label(8,3) => 2,
+                       Label(9,3) => 3, Label(10,2) => -14 },
+                zset!{ Label(4,1) => -2, Label(4,2) => 3, Label(5,1) => -3, Label(5,2) => 4, Label(6,1) => -3, Label(6,2) => 4,
+                       Label(7,1) => -8, Label(7,2) => 10, Label(10,1) => -10, Label(10,2) => 12 },
+                zset!{ Label(7,2) => 6, Label(7,3) => 4, Label(10,2) => 7, Label(10,3) => 5 },
+            ].into_iter();
+
+            let edges: Stream<_, OrdZSet<Edge, isize>> =
+                circuit
+                    .add_source(Generator::new(move || edges.next().unwrap()));
+
+            let labels: Stream<_, OrdZSet<Label, isize>> =
+                circuit
+                    .add_source(Generator::new(move || labels.next().unwrap()));
+
+            let result = circuit.iterate(|child| {
+
+                let counter = std::cell::RefCell::new(0);
+                let edges = edges.delta0(child);
+                let labels = labels.delta0(child);
+
+                let computed_labels = <DelayedFeedback<_, OrdZSet<_, _>>>::new(child);
+                let result = propagate(child, &edges, &labels.plus(computed_labels.stream()));
+                computed_labels.connect(&result);
+
+                //result.inspect(|res: &OrdZSet<Label, isize>| println!("delta: {}", res));
+                Ok((move || {
+                    let mut counter = counter.borrow_mut();
+                    // reset to 0 on each outer loop iteration.
+                    if *counter == 2 {
+                        *counter = 0;
+                    }
+                    *counter += 1;
+                    //println!("counter: {}", *counter);
+                    Ok(*counter == 2)
+                },
+                result.integrate_trace().export()))
+            }).unwrap();
+
+            result.consolidate().inspect(move |res: &OrdZSet<Label, isize>| {
+                assert_eq!(*res, outputs.next().unwrap());
+            });
+            Ok(())
+        })
+        .unwrap().0;
+
+        for _ in 0..8 {
+            //eprintln!("{}", i);
+            circuit.step().unwrap();
+        }
+    }
+
+    #[test]
+    fn antijoin_test() {
+        let output = Arc::new(Mutex::new(OrdIndexedZSet::empty(())));
+        let output_clone = output.clone();
+
+        let (mut circuit, (input1, input2)) = Runtime::init_circuit(4, move |circuit| {
+            let (input1, input_handle1) = circuit.add_input_indexed_zset::<usize, usize, isize>();
+            let (input2, input_handle2) = circuit.add_input_indexed_zset::<usize, usize, isize>();
+
+            input1.antijoin(&input2).gather(0).inspect(move |batch| {
+                if Runtime::worker_index() == 0 {
+                    *output_clone.lock().unwrap() = batch.clone();
+                }
+            });
+
+            Ok((input_handle1, input_handle2))
+        })
+        .unwrap();
+
+        input1.append(&mut vec![
+            (1, (0, 1)),
+            (1, (1, 2)),
+            (2, (0, 1)),
+            (2, (1, 1)),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(
+            &*output.lock().unwrap(),
+            &indexed_zset! { 1 => { 0 => 1, 1 => 2}, 2 => { 0 => 1, 1 => 1 } }
+        );
+
+        input1.append(&mut vec![(3, (1, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(&*output.lock().unwrap(), &indexed_zset! { 3 => { 1 => 1 } });
+
+        input2.append(&mut vec![(1, (1, 3))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            &*output.lock().unwrap(),
+            &indexed_zset! { 1 => { 0 => -1, 1 => -2 } }
+        );
+
+        input2.append(&mut vec![(2, (5, 1))]);
+        input1.append(&mut vec![(2, (2, 1)), (4, (1, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            &*output.lock().unwrap(),
+            &indexed_zset! { 2 => { 0 => -1, 1 => -1 }, 4 => { 1 => 1 } }
+        );
+
+        circuit.kill().unwrap();
+    }
+
+    thread_local! {
+        static CLONES: Cell<usize> = Cell::new(0);
+    }
+
+    /// Value type that counts how many times it has been cloned.
+    #[derive(
+        Debug, Hash, Ord, PartialOrd, Eq, PartialEq, SizeOf, Archive, Serialize, Deserialize,
+    )]
+    struct Counted(usize);
+
+    impl Clone for Counted {
+        fn clone(&self) -> Self {
+            CLONES.with(|clones| clones.set(clones.get() + 1));
+            Self(self.0)
+        }
+    }
+
+    /// Count clones of `Counted` values during a single step of a circuit
+    /// that joins two values on the left with 100 values on the right and
+    /// outputs the left value.
+    fn passthrough_join_clones(cow: bool) -> usize {
+        let (circuit, (input1, input2)) = RootCircuit::build(move |circuit| {
+            let (input1, input_handle1) = circuit.add_input_indexed_zset::<usize, Counted, isize>();
+            let (input2, input_handle2) = circuit.add_input_indexed_zset::<usize, usize, isize>();
+
+            let output: Stream<_, OrdZSet<Counted, isize>> = if cow {
+                input1.stream_join_cow(&input2, |_k, v1, _v2| Cow::Borrowed(v1))
+            } else {
+                input1.stream_join(&input2, |_k, v1, _v2| v1.clone())
+            };
+
+            output.inspect(|batch| {
+                assert_eq!(
+                    batch.iter().map(|(k, (), w)| (k.0, w)).collect::<Vec<_>>(),
+                    vec![(10, 100), (20, 100)]
+                );
+            });
+
+            Ok((input_handle1, input_handle2))
+        })
+        .unwrap();
+
+        input1.append(&mut vec![(1, (Counted(10), 1)), (1, (Counted(20), 1))]);
+        input2.append(&mut (0..100).map(|v| (1, (v, 1))).collect());
+
+        CLONES.with(|clones| clones.set(0));
+        circuit.step().unwrap();
+        CLONES.with(|clones| clones.get())
+    }
+
+    #[test]
+    fn stream_join_cow_test() {
+        let join_clones = passthrough_join_clones(false);
+        let cow_clones = passthrough_join_clones(true);
+
+        // `stream_join` clones the output value once per matching pair;
+        // `stream_join_cow` clones it once per left value.
+        assert!(
+            cow_clones < join_clones,
+            "stream_join_cow: {cow_clones} clones, stream_join: {join_clones} clones"
+        );
+    }
+
+    #[test]
+    fn join_distinct_test() {
+        let (circuit, (input1, input2, join_output, join_distinct_output)) =
+            RootCircuit::build(move |circuit| {
+                let (input1, input_handle1) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+                let (input2, input_handle2) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+
+                // Project away the values of the second input, so that each output
+                // tuple is produced by 10 pairs of input tuples.
+                let join_output = input1.join(&input2, |k, v1, _v2| (*k, *v1)).output();
+                let join_distinct_output = input1
+                    .join_distinct(&input2, |k, v1, _v2| (*k, *v1))
+                    .output();
+
+                Ok((
+                    input_handle1,
+                    input_handle2,
+                    join_output,
+                    join_distinct_output,
+                ))
+            })
+            .unwrap();
+
+        for step in 0..5u64 {
+            input1.append(&mut (0..10).map(|v| (step % 3, (v + step, 1))).collect());
+            input2.append(&mut (0..10).map(|v| (step % 3, (v, 1))).collect());
+            if step > 0 {
+                input1.append(&mut vec![(0, (step, -1))]);
+            }
+            circuit.step().unwrap();
+
+            let expected = join_output.consolidate();
+            assert!(!expected.is_empty());
+            assert_eq!(join_distinct_output.consolidate(), expected);
+        }
+    }
+
+    #[test]
+    fn left_join_test() {
+        let (circuit, (input1, input2, output)) = RootCircuit::build(move |circuit| {
+            let (input1, input_handle1) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+            let (input2, input_handle2) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+
+            let output = input1
+                .left_join(&input2, |k, v1, v2| (*k, *v1, v2.copied()))
+                .output();
+
+            Ok((input_handle1, input_handle2, output))
+        })
+        .unwrap();
+
+        // Unmatched left row.
+        input1.append(&mut vec![(1, (10, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! { (1, 10, None) => 1 });
+
+        // A matching right row retracts the unmatched row.
+        input2.append(&mut vec![(1, (20, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            zset! { (1, 10, None) => -1, (1, 10, Some(20)) => 1 }
+        );
+
+        // Another matching row doesn't affect the unmatched part.
+        input2.append(&mut vec![(1, (30, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! { (1, 10, Some(30)) => 1 });
+
+        // Deleting all matching rows reinstates the unmatched row.
+        input2.append(&mut vec![(1, (20, -1)), (1, (30, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            zset! { (1, 10, None) => 1, (1, 10, Some(20)) => -1, (1, 10, Some(30)) => -1 }
+        );
+    }
+
+    #[test]
+    fn join_by_test() {
+        let (circuit, (input1, input2, output)) = RootCircuit::build(move |circuit| {
+            let (input1, input_handle1) = circuit.add_input_indexed_zset::<String, u64, isize>();
+            let (input2, input_handle2) = circuit.add_input_indexed_zset::<String, u64, isize>();
+
+            let output = input1
+                .join_by(
+                    &input2,
+                    |k1, k2| k1.to_lowercase().cmp(&k2.to_lowercase()),
+                    |k, v1, v2| (k.clone(), *v1, *v2),
+                )
+                .output();
+
+            Ok((input_handle1, input_handle2, output))
+        })
+        .unwrap();
+
+        input1.append(&mut vec![
+            ("Apple".to_string(), (1, 1)),
+            ("banana".to_string(), (2, 1)),
+            ("cherry".to_string(), (3, 1)),
+        ]);
+        input2.append(&mut vec![
+            ("APPLE".to_string(), (10, 1)),
+            ("Apple".to_string(), (11, 1)),
+            ("BANANA".to_string(), (20, 1)),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            zset! {
+                ("Apple".to_string(), 1, 10) => 1,
+                ("Apple".to_string(), 1, 11) => 1,
+                ("banana".to_string(), 2, 20) => 1,
+            }
+        );
+
+        input2.append(&mut vec![
+            ("Cherry".to_string(), (30, 1)),
+            ("BANANA".to_string(), (20, -1)),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            zset! {
+                ("banana".to_string(), 2, 20) => -1,
+                ("cherry".to_string(), 3, 30) => 1,
+            }
+        );
+    }
+
+    #[test]
+    fn join_with_retention_test() {
+        let (circuit, (input1, input2, output)) = RootCircuit::build(move |circuit| {
+            let (input1, input_handle1) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+            let (input2, input_handle2) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+
+            // Retain the entire history of the small input, but only the last
+            // two steps of the large input.
+            let output = input1
+                .join_with_retention(
+                    &input2,
+                    |k, v1, v2| (*k, *v1, *v2),
+                    JoinRetention {
+                        left: None,
+                        right: Some(TraceRetention::Steps(2)),
+                    },
+                )
+                .output();
+
+            Ok((input_handle1, input_handle2, output))
+        })
+        .unwrap();
+
+        input1.append(&mut vec![(1, (10, 1))]);
+        input2.append(&mut vec![(1, (100, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! { (1, 10, 100) => 1 });
+
+        // `(1, 100)` is still within the retention window.
+        input1.append(&mut vec![(1, (11, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! { (1, 11, 100) => 1 });
+
+        // `(1, 100)` expires, retracting the pairs it produced, while new
+        // updates to the large input still match the entire history of the
+        // small input.
+        input1.append(&mut vec![(1, (12, 1))]);
+        input2.append(&mut vec![(1, (200, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            zset! {
+                (1, 10, 100) => -1,
+                (1, 11, 100) => -1,
+                (1, 10, 200) => 1,
+                (1, 11, 200) => 1,
+                (1, 12, 200) => 1,
+            }
+        );
+
+        // Retracting `(1, 100)` after it has been evicted doesn't produce
+        // any outputs.
+        input2.append(&mut vec![(1, (100, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! {});
+    }
+
+    #[test]
+    fn cross_join_test() {
+        let (circuit, (input1, input2, output)) = RootCircuit::build(move |circuit| {
+            let (input1, input_handle1) = circuit.add_input_zset::<u64, isize>();
+            let (input2, input_handle2) = circuit.add_input_zset::<String, isize>();
+
+            let output = input1
+                .cross_join(&input2, |v1, (), v2, ()| (*v1, v2.clone()), 100)
+                .output();
+
+            Ok((input_handle1, input_handle2, output))
+        })
+        .unwrap();
+
+        input1.append(&mut vec![(1, 1), (2, 2)]);
+        input2.append(&mut vec![
+            ("a".to_string(), 1),
+            ("b".to_string(), 1),
+            ("c".to_string(), -1),
+        ]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            zset! {
+                (1, "a".to_string()) => 1,
+                (1, "b".to_string()) => 1,
+                (1, "c".to_string()) => -1,
+                (2, "a".to_string()) => 2,
+                (2, "b".to_string()) => 2,
+                (2, "c".to_string()) => -2,
+            }
+        );
+
+        // Changes to either input are paired with the integral of the other.
+        input1.append(&mut vec![(1, -1)]);
+        input2.append(&mut vec![("d".to_string(), 1)]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            zset! {
+                (1, "a".to_string()) => -1,
+                (1, "b".to_string()) => -1,
+                (1, "c".to_string()) => 1,
+                (2, "d".to_string()) => 2,
+            }
+        );
+    }
+
+    #[test]
+    fn cross_join_max_output_test() {
+        let (circuit, (input1, input2)) = RootCircuit::build(move |circuit| {
+            let (input1, input_handle1) = circuit.add_input_zset::<u64, isize>();
+            let (input2, input_handle2) = circuit.add_input_zset::<u64, isize>();
+
+            input1.cross_join(&input2, |v1, (), v2, ()| (*v1, *v2), 5);
+
+            Ok((input_handle1, input_handle2))
+        })
+        .unwrap();
+
+        input1.append(&mut vec![(1, 1), (2, 1)]);
+        input2.append(&mut vec![(1, 1), (2, 1)]);
+        circuit.step().unwrap();
+
+        input2.append(&mut vec![(3, 1), (4, 1)]);
+        input1.append(&mut vec![(3, 1), (4, 1)]);
+        match circuit.step() {
+            Err(SchedulerError::OperatorError { error, .. }) => {
+                assert_eq!(error, "cross join output exceeds the limit of 5 tuples")
+            }
+            result => panic!("unexpected result: {result:?}"),
+        }
+    }
+
+    #[test]
+    fn hash_join_test() {
+        let (circuit, (input1, input2)) = RootCircuit::build(move |circuit| {
+            let (input1, input_handle1) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+            let (input2, input_handle2) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+
+            let expected = input1.stream_join(&input2, |k, v1, v2| (*k, *v1, *v2));
+
+            for side in [HashJoinSide::Auto, HashJoinSide::Left, HashJoinSide::Right] {
+                let expected = expected.clone();
+                input1
+                    .stream_hash_join(&input2, |k, v1, v2| (*k, *v1, *v2), side)
+                    .apply2(&expected, |actual, expected| assert_eq!(actual, expected));
+            }
+
+            Ok((input_handle1, input_handle2))
+        })
+        .unwrap();
+
+        // Small left side.
+        input1.append(&mut vec![(1, (1, 1)), (5, (2, -1)), (5, (3, 2))]);
+        input2.append(&mut (0..1000).map(|v| (v % 10, (v, 1))).collect());
+        circuit.step().unwrap();
+
+        // Small right side.
+        input1.append(&mut (0..1000).map(|v| (v % 10, (v, 1))).collect());
+        input2.append(&mut vec![(3, (1, 2)), (7, (2, 1)), (11, (3, 1))]);
+        circuit.step().unwrap();
+
+        // Empty input.
+        input1.append(&mut vec![(1, (1, 1))]);
+        circuit.step().unwrap();
+    }
+
+    #[test]
+    fn join_nullable_test() {
+        let (circuit, (input1, input2, inner, left)) = RootCircuit::build(move |circuit| {
+            let (input1, input_handle1) =
+                circuit.add_input_indexed_zset::<Option<u64>, u64, isize>();
+            let (input2, input_handle2) =
+                circuit.add_input_indexed_zset::<Option<u64>, u64, isize>();
+
+            let inner = input1
+                .join_nullable(&input2, |k, v1, v2| (*k, *v1, *v2))
+                .output();
+            let left = input1
+                .left_join_nullable(&input2, |k, v1, v2| (*k, *v1, v2.cloned()))
+                .output();
+
+            Ok((input_handle1, input_handle2, inner, left))
+        })
+        .unwrap();
+
+        // `None` keys don't match each other.
+        input1.append(&mut vec![(None, (1, 1)), (Some(1), (2, 1))]);
+        input2.append(&mut vec![(None, (3, 1)), (Some(1), (4, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(inner.consolidate(), zset! {(1, 2, 4) => 1});
+        assert_eq!(
+            left.consolidate(),
+            zset! {(Some(1), 2, Some(4)) => 1, (None, 1, None) => 1}
+        );
+
+        // Deleting the `None` key from `other` doesn't affect the output.
+        input2.append(&mut vec![(None, (3, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(inner.consolidate(), zset! {});
+        assert_eq!(left.consolidate(), zset! {});
+    }
+
+    #[test]
+    fn join_null_safe_test() {
+        let (circuit, (input1, input2, output)) = RootCircuit::build(move |circuit| {
+            let (input1, input_handle1) =
+                circuit.add_input_indexed_zset::<Option<u64>, u64, isize>();
+            let (input2, input_handle2) =
+                circuit.add_input_indexed_zset::<Option<u64>, u64, isize>();
+
+            let output = input1
+                .join_null_safe(&input2, |k, v1, v2| (*k, *v1, *v2))
+                .output();
+
+            Ok((input_handle1, input_handle2, output))
+        })
+        .unwrap();
+
+        // `None` keys match each other, but not `Some` keys.
+        input1.append(&mut vec![
+            (None, (1, 1)),
+            (Some(1), (2, 1)),
+            (Some(2), (5, 1)),
+        ]);
+        input2.append(&mut vec![(None, (3, 1)), (Some(1), (4, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            zset! {(None, 1, 3) => 1, (Some(1), 2, 4) => 1}
+        );
 
-            let index1: Stream<_, OrdIndexedZSet<usize, String, isize>> = circuit
-                .add_source(Generator::new(move || {
-                    if Runtime::worker_index() == 0 {
-                        input1.next().unwrap()
-                    } else {
-                        <OrdZSet<_, _>>::empty(())
-                    }
-                }))
-                .index();
-            let index2: Stream<_, OrdIndexedZSet<usize, String, isize>> = circuit
-                .add_source(Generator::new(move || {
-                    if Runtime::worker_index() == 0 {
-                        input2.next().unwrap()
-                    } else {
-                        <OrdZSet<_, _>>::empty(())
-                    }
-                }))
-                .index();
-            index1
-                .stream_join(&index2, |&k: &usize, s1, s2| (k, format!("{} {}", s1, s2)))
-                .gather(0)
-                .inspect(move |fm: &OrdZSet<(usize, String), _>| {
-                    if Runtime::worker_index() == 0 {
-                        assert_eq!(fm, &outputs.next().unwrap())
-                    }
-                });
-            index1
-                .join_incremental(&index2, |&k: &usize, s1, s2| (k, format!("{} {}", s1, s2)))
-                .gather(0)
-                .inspect(move |fm: &OrdZSet<(usize, String), _>| {
-                    if Runtime::worker_index() == 0 {
-                        assert_eq!(fm, &inc_outputs.next().unwrap())
-                    }
-                });
+        // Retracting the `None` key from `other` retracts its matches.
+        input2.append(&mut vec![(None, (3, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! {(None, 1, 3) => -1});
+    }
 
-            index1
-                .join(&index2, |&k: &usize, s1, s2| (k, format!("{} {}", s1, s2)))
-                .gather(0)
-                .inspect(move |fm: &OrdZSet<(usize, String), _>| {
-                    if Runtime::worker_index() == 0 {
-                        assert_eq!(fm, &inc_outputs2.next().unwrap())
-                    }
-                });
-            Ok(())
+    #[test]
+    fn join_max_fanout_test() {
+        let (circuit, (input1, input2, output)) = RootCircuit::build(move |circuit| {
+            let (input1, input_handle1) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+            let (input2, input_handle2) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+
+            let output = input1
+                .join_with_max_fanout(&input2, |k, v1, v2| (*k, *v1, *v2), 4)
+                .output();
+
+            Ok((input_handle1, input_handle2, output))
         })
-        .unwrap()
-        .0;
+        .unwrap();
 
-        for _ in 0..5 {
-            circuit.step().unwrap();
+        // Fan-out of key 1 is 2 * 2, which is within the limit.
+        input1.append(&mut vec![(1, (1, 1)), (1, (2, 1))]);
+        input2.append(&mut vec![(1, (3, 1)), (1, (4, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            zset! {(1, 1, 3) => 1, (1, 1, 4) => 1, (1, 2, 3) => 1, (1, 2, 4) => 1}
+        );
+
+        // 3 new values of key 1 joined with 2 existing values exceed the limit.
+        input1.append(&mut vec![(1, (5, 1)), (1, (6, 1)), (1, (7, 1))]);
+        match circuit.step() {
+            Err(SchedulerError::OperatorError { error, .. }) => assert_eq!(
+                error,
+                "join fan-out of 6 tuples for a single key exceeds the limit of 4 tuples"
+            ),
+            result => panic!("unexpected result: {result:?}"),
         }
     }
 
-    fn do_join_test_mt(workers: usize) {
-        let hruntime = Runtime::run(workers, || {
-            join_test();
-        });
+    #[test]
+    fn stream_join_max_fanout_test() {
+        let (circuit, (input1, input2)) = RootCircuit::build(move |circuit| {
+            let (input1, input_handle1) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+            let (input2, input_handle2) = circuit.add_input_indexed_zset::<u64, u64, isize>();
 
-        hruntime.join().unwrap();
+            input1.stream_join_with_max_fanout(&input2, |k, v1, v2| (*k, *v1, *v2), 4);
+
+            Ok((input_handle1, input_handle2))
+        })
+        .unwrap();
+
+        input1.append(&mut (0..3).map(|v| (1, (v, 1))).collect());
+        input2.append(&mut (0..3).map(|v| (1, (v, 1))).collect());
+        match circuit.step() {
+            Err(SchedulerError::OperatorError { error, .. }) => assert_eq!(
+                error,
+                "join fan-out of 9 tuples for a single key exceeds the limit of 4 tuples"
+            ),
+            result => panic!("unexpected result: {result:?}"),
+        }
     }
 
     #[test]
-    fn join_test_mt() {
-        do_join_test_mt(1);
-        do_join_test_mt(2);
-        do_join_test_mt(4);
-        do_join_test_mt(16);
+    fn join_operator_stats_test() {
+        let (circuit, (input1, input2)) = RootCircuit::build(move |circuit| {
+            let (input1, input_handle1) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+            let (input2, input_handle2) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+
+            input1
+                .join(&input2, |k, v1, v2| (*k, *v1, *v2))
+                .inspect(|batch| assert!(batch.len() <= 300));
+
+            Ok((input_handle1, input_handle2))
+        })
+        .unwrap();
+
+        // The second step feeds both `JoinTrace` operators a non-empty
+        // trace, so both of them push tuples to their batchers.
+        for step in 0..2 {
+            input1.append(&mut (0..10).map(|v| (1, (step * 10 + v, 1))).collect());
+            input2.append(&mut (0..10).map(|v| (1, (step * 10 + v, 1))).collect());
+            circuit.step().unwrap();
+        }
+
+        let stats = circuit.operator_stats();
+        let join_stats = stats
+            .values()
+            .filter(|stats| stats.name == "JoinTrace")
+            .collect::<Vec<_>>();
+
+        // `join` is implemented using two `JoinTrace` operators.
+        assert_eq!(join_stats.len(), 2);
+        assert!(join_stats.iter().all(|stats| stats.bytes > 0));
     }
 
-    // Compute pairwise reachability relation between graph nodes as the
-    // transitive closure of the edge relation.
     #[test]
-    fn join_trace_test() {
+    fn join_with_name_test() {
+        let (circuit, (input1, input2)) = RootCircuit::build(move |circuit| {
+            let (input1, input_handle1) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+            let (input2, input_handle2) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+
+            input1
+                .join(&input2, |k, v1, v2| (*k, *v1, *v2))
+                .with_name("join1");
+            input1
+                .join(&input2, |k, v1, v2| (*k, *v2, *v1))
+                .with_name("join2");
+
+            Ok((input_handle1, input_handle2))
+        })
+        .unwrap();
+
+        input1.append(&mut (0..10).map(|v| (1, (v, 1))).collect());
+        input2.append(&mut (0..10).map(|v| (1, (v, 1))).collect());
+        circuit.step().unwrap();
+
+        let stats = circuit.operator_stats();
+        let names = stats
+            .values()
+            .map(|stats| stats.name.as_ref())
+            .collect::<Vec<_>>();
+
+        // The label is propagated to the `JoinTrace` operators that
+        // implement each join.
+        assert_eq!(names, vec!["join1", "join1", "join2", "join2"]);
+    }
+
+    /// Clock that can be advanced manually by the test.
+    struct TestClock(Rc<Cell<u32>>);
+
+    impl WithClock for TestClock {
+        type Time = u32;
+        const NESTING_DEPTH: usize = 1;
+
+        fn time(&self) -> u32 {
+            self.0.get()
+        }
+    }
+
+    #[test]
+    fn join_with_watermark_test() {
+        let watermark = JoinWatermark::new();
+        let watermark_clone = watermark.clone();
+
+        // Number of iterations the nested circuit performs at the next step.
+        let iterations = Rc::new(Cell::new(3));
+        let iterations_clone = iterations.clone();
+
         let circuit = RootCircuit::build(move |circuit| {
-            // Changes to the edges relation.
             let mut edges: vec::IntoIter<OrdZSet<(usize, usize), isize>> = vec![
                 zset! { (1, 2) => 1 },
-                zset! { (2, 3) => 1},
-                zset! { (1, 3) => 1},
-                zset! { (3, 1) => 1},
-                zset! { (3, 1) => -1},
-                zset! { (1, 2) => -1},
-                zset! { (2, 4) => 1, (4, 1) => 1 },
-                zset! { (2, 3) => -1, (3, 2) => 1 },
-            ]
-            .into_iter();
-
-            // Expected content of the reachability relation.
-            let mut outputs: vec::IntoIter<OrdZSet<(usize, usize), isize>> = vec![
-                zset! { (1, 2) => 1 },
-                zset! { (1, 2) => 1, (2, 3) => 1, (1, 3) => 1 },
-                zset! { (1, 2) => 1, (2, 3) => 1, (1, 3) => 1 },
-                zset! { (1, 1) => 1, (2, 2) => 1, (3, 3) => 1, (1, 2) => 1, (1, 3) => 1, (2, 3) => 1, (2, 1) => 1, (3, 1) => 1, (3, 2) => 1},
-                zset! { (1, 2) => 1, (2, 3) => 1, (1, 3) => 1 },
-                zset! { (2, 3) => 1, (1, 3) => 1 },
-                zset! { (1, 3) => 1, (2, 3) => 1, (2, 4) => 1, (2, 1) => 1, (4, 1) => 1, (4, 3) => 1 },
-                zset! { (1, 1) => 1, (2, 2) => 1, (3, 3) => 1, (4, 4) => 1,
-                              (1, 2) => 1, (1, 3) => 1, (1, 4) => 1,
-                              (2, 1) => 1, (2, 3) => 1, (2, 4) => 1,
-                              (3, 1) => 1, (3, 2) => 1, (3, 4) => 1,
-                              (4, 1) => 1, (4, 2) => 1, (4, 3) => 1 },
+                zset! { (2, 3) => 1 },
+                zset! { (3, 4) => 1 },
             ]
             .into_iter();
 
             let edges: Stream<_, OrdZSet<(usize, usize), isize>> =
-                circuit
-                    .add_source(Generator::new(move || edges.next().unwrap()));
+                circuit.add_source(Generator::new(move || edges.next().unwrap()));
+
+            circuit
+                .iterate(move |child| {
+                    let iteration = Rc::new(Cell::new(0));
+                    let iteration_clone = iteration.clone();
+
+                    // Node labels arrive at the third iteration of each epoch.
+                    let labels: Stream<_, OrdIndexedZSet<usize, usize, isize>> =
+                        child.add_source(Generator::new(move || {
+                            if iteration_clone.get() == 2 {
+                                OrdIndexedZSet::from_tuples(
+                                    (),
+                                    (1..5).map(|node| ((node, node * 10), 1)).collect(),
+                                )
+                            } else {
+                                OrdIndexedZSet::empty(())
+                            }
+                        }));
+
+                    // Joining new edges with labels received during earlier
+                    // epochs produces outputs for the third iteration.
+                    edges.delta0(child).index().join_with_watermark(
+                        &labels,
+                        |from, to, label| (*from, *to, *label),
+                        &watermark_clone,
+                    );
+
+                    let condition = move || {
+                        let done = iteration.get() + 1 >= iterations_clone.get();
+                        iteration.set(if done { 0 } else { iteration.get() + 1 });
+                        Ok(done)
+                    };
+                    Ok((condition, ()))
+                })
+                .unwrap();
+            Ok(())
+        })
+        .unwrap()
+        .0;
 
-            let paths = circuit.recursive(|child, paths_delayed: Stream<_, OrdZSet<(usize, usize), isize>>| {
-                // ```text
-                //                             distinct
-                //               ┌───┐          ┌───┐
-                // edges         │   │          │   │  paths
-                // ────┬────────►│ + ├──────────┤   ├────────┬───►
-                //     │         │   │          │   │        │
-                //     │         └───┘          └───┘        │
-                //     │           ▲                         │
-                //     │           │                         │
-                //     │         ┌─┴─┐                       │
-                //     │         │   │                       │
-                //     └────────►│ X │ ◄─────────────────────┘
-                //               │   │
-                //               └───┘
-                //               join
-                // ```
-                let edges = edges.delta0(child);
+        let join_tuples = || {
+            circuit
+                .operator_stats()
+                .values()
+                .filter(|stats| stats.name == "JoinTrace")
+                .map(|stats| stats.tuples)
+                .sum::<usize>()
+        };
 
-                let paths_inverted: Stream<_, OrdZSet<(usize, usize), isize>> = paths_delayed
-                    .map(|&(x, y)| (y, x));
+        circuit.step().unwrap();
+        assert_eq!(join_tuples(), 0);
 
-                let paths_inverted_indexed = paths_inverted.index();
-                let edges_indexed = edges.index();
+        // The nested circuit stops before the third iteration, so the outputs
+        // computed for it remain buffered.
+        iterations.set(1);
+        circuit.step().unwrap();
+        assert!(join_tuples() > 0);
 
-                Ok(edges.plus(&paths_inverted_indexed.join(&edges_indexed, |_via, from, to| (*from, *to))))
-            })
-            .unwrap();
+        // Advancing the watermark past all times of the nested clock evicts
+        // them.
+        watermark.set(NestedTimestamp32::new(true, u32::MAX >> 1));
+        circuit.step().unwrap();
+        assert_eq!(join_tuples(), 0);
+    }
 
-            paths.integrate().stream_distinct().inspect(move |ps| {
-                assert_eq!(*ps, outputs.next().unwrap());
-            });
-            Ok(())
-        })
-        .unwrap().0;
+    /// Measure the memory footprint of `join` by traversing all of its
+    /// batchers.
+    fn deep_size_of<F, I, T, Z, It, Clk>(join: &JoinTrace<F, I, T, Z, It, Clk>) -> usize
+    where
+        T: BatchReader,
+        Z: IndexedZSet,
+    {
+        let mut context = Context::new();
+        for batcher in join.output_batchers.values() {
+            batcher.size_of_with_context(&mut context);
+        }
 
-        for _ in 0..8 {
-            //eprintln!("{}", i);
-            circuit.step().unwrap();
+        join.output_batchers.capacity() * std::mem::size_of::<(T::Time, Z::Batcher)>()
+            + context.total_size().total_bytes()
+    }
+
+    #[test]
+    fn join_trace_bytes_test() {
+        let time = Rc::new(Cell::new(0));
+        let watermark = JoinWatermark::new();
+        let mut join = JoinTrace::new(
+            |k: &u64, v1: &String, v2: &u64| std::iter::once(((*k, v1.clone(), *v2), ())),
+            Location::caller(),
+            TestClock(time.clone()),
+        )
+        .with_watermark(watermark.clone());
+        assert_eq!(join.bytes(), deep_size_of(&join));
+        let mut bytes = join.bytes();
+
+        let mut trace = <OrdValSpine<u64, u64, u32, isize>>::new(None);
+        trace.insert(OrdValBatch::from_tuples(
+            5,
+            (0..10).map(|k| ((k, 10), 1)).collect(),
+        ));
+        trace.insert(OrdValBatch::from_tuples(
+            10,
+            (0..10).map(|k| ((k, 20), 1)).collect(),
+        ));
+
+        // Each clock cycle pushes more tuples to the batchers for times 5 and
+        // 10, which merge them with the tuples pushed earlier.  None of the
+        // tuples cancel out, so the size of the pushed tuples is added to the
+        // counter.
+        for step in 0..5 {
+            time.set(step);
+            let index = <OrdIndexedZSet<u64, String, isize>>::from_tuples(
+                (),
+                (0..10)
+                    .map(|k| ((k, format!("value {step} of key {k}")), 1))
+                    .collect(),
+            );
+            let _output: OrdZSet<(u64, String, u64), isize> = join.eval(&index, &trace);
+            assert!(join.bytes() > bytes);
+            bytes = join.bytes();
         }
+
+        // Flushing batchers behind the watermark releases their memory.  The
+        // batcher for time 5 is output at its own clock cycle; the batcher
+        // for time 10 is never output because the clock skips time 10.
+        time.set(5);
+        let _output: OrdZSet<(u64, String, u64), isize> =
+            join.eval(&OrdIndexedZSet::empty(()), &trace);
+        assert!(join.bytes() < bytes);
+        assert_eq!(join.output_batchers.len(), 1);
+
+        time.set(11);
+        watermark.set(10);
+        let _output: OrdZSet<(u64, String, u64), isize> =
+            join.eval(&OrdIndexedZSet::empty(()), &trace);
+        assert!(join.output_batchers.is_empty());
+        assert_eq!(join.bytes(), deep_size_of(&join));
     }
 
-    #[derive(
-        Clone,
-        Debug,
-        Default,
-        Hash,
-        Ord,
-        PartialOrd,
-        Eq,
-        PartialEq,
-        SizeOf,
-        Archive,
-        Serialize,
-        Deserialize,
-    )]
-    struct Label(pub usize, pub u16);
+    /// Minimal alternative [`ZSet`](`crate::ZSet`) implementation.
+    ///
+    /// Wraps an `OrdZSet` but only exposes it through the batch traits, so
+    /// the join operators can't rely on `OrdZSet` internals when producing
+    /// it.
+    mod custom_zset {
+        use crate::{
+            algebra::{AddAssignByRef, AddByRef, NegByRef},
+            time::AntichainRef,
+            trace::{
+                ord::zset_batch::{OrdZSetBuilder, OrdZSetConsumer, OrdZSetCursor, OrdZSetMerger},
+                Batch, BatchReader, Batcher, Builder, Filter, Merger,
+            },
+            DBData, NumEntries, OrdZSet,
+        };
+        use rand::Rng;
+        use rkyv::{Archive, Deserialize, Serialize};
+        use size_of::SizeOf;
+        use std::ops::{Add, AddAssign, Neg};
+
+        #[derive(Clone, Debug, PartialEq, Eq, SizeOf, Archive, Serialize, Deserialize)]
+        pub struct WrapperZSet<K: 'static>(pub OrdZSet<K, isize>);
+
+        impl<K: DBData> NumEntries for WrapperZSet<K> {
+            const CONST_NUM_ENTRIES: Option<usize> = None;
+
+            fn num_entries_shallow(&self) -> usize {
+                self.0.num_entries_shallow()
+            }
+
+            fn num_entries_deep(&self) -> usize {
+                self.0.num_entries_deep()
+            }
+        }
+
+        impl<K: DBData> Add for WrapperZSet<K> {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self {
+                Self(self.0 + rhs.0)
+            }
+        }
+
+        impl<K: DBData> AddByRef for WrapperZSet<K> {
+            fn add_by_ref(&self, rhs: &Self) -> Self {
+                Self(self.0.add_by_ref(&rhs.0))
+            }
+        }
+
+        impl<K: DBData> AddAssign for WrapperZSet<K> {
+            fn add_assign(&mut self, rhs: Self) {
+                self.0 += rhs.0;
+            }
+        }
+
+        impl<K: DBData> AddAssignByRef for WrapperZSet<K> {
+            fn add_assign_by_ref(&mut self, rhs: &Self) {
+                self.0.add_assign_by_ref(&rhs.0);
+            }
+        }
+
+        impl<K: DBData> Neg for WrapperZSet<K> {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                Self(-self.0)
+            }
+        }
+
+        impl<K: DBData> NegByRef for WrapperZSet<K> {
+            fn neg_by_ref(&self) -> Self {
+                Self(self.0.neg_by_ref())
+            }
+        }
+
+        impl<K: DBData> BatchReader for WrapperZSet<K> {
+            type Key = K;
+            type Val = ();
+            type Time = ();
+            type R = isize;
+            type Cursor<'s> = OrdZSetCursor<'s, K, isize>;
+            type Consumer = OrdZSetConsumer<K, isize>;
+
+            fn cursor(&self) -> Self::Cursor<'_> {
+                self.0.cursor()
+            }
+
+            fn consumer(self) -> Self::Consumer {
+                self.0.consumer()
+            }
+
+            fn key_count(&self) -> usize {
+                self.0.key_count()
+            }
+
+            fn len(&self) -> usize {
+                self.0.len()
+            }
+
+            fn lower(&self) -> AntichainRef<'_, ()> {
+                self.0.lower()
+            }
+
+            fn upper(&self) -> AntichainRef<'_, ()> {
+                self.0.upper()
+            }
+
+            fn truncate_keys_below(&mut self, lower_bound: &K) {
+                self.0.truncate_keys_below(lower_bound);
+            }
 
-    impl Display for Label {
-        fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
-            write!(f, "L({},{})", self.0, self.1)
+            fn sample_keys<RG>(&self, rng: &mut RG, sample_size: usize, sample: &mut Vec<K>)
+            where
+                RG: Rng,
+            {
+                self.0.sample_keys(rng, sample_size, sample);
+            }
         }
-    }
 
-    #[derive(
-        Clone,
-        Debug,
-        Default,
-        Ord,
-        PartialOrd,
-        Hash,
-        Eq,
-        PartialEq,
-        SizeOf,
-        Archive,
-        Serialize,
-        Deserialize,
-    )]
-    struct Edge(pub usize, pub usize);
+        impl<K: DBData> Batch for WrapperZSet<K> {
+            type Item = K;
+            type Batcher = WrapperZSetBatcher<K>;
+            type Builder = WrapperZSetBuilder<K>;
+            type Merger = WrapperZSetMerger<K>;
 
-    impl Display for Edge {
-        fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
-            write!(f, "E({},{})", self.0, self.1)
+            fn item_from(key: K, _val: ()) -> K {
+                key
+            }
+
+            fn from_keys(time: (), keys: Vec<(K, isize)>) -> Self {
+                Self::from_tuples(time, keys)
+            }
+
+            fn recede_to(&mut self, _frontier: &()) {}
         }
-    }
-    // Recursively propagate node labels in an acyclic graph.
-    // The reason for supporting acyclic graphs only is that we use this to test
-    // the join operator in isolation, so we don't want to use `distinct`.
-    fn propagate<C>(
-        circuit: &C,
-        edges: &Stream<C, OrdZSet<Edge, isize>>,
-        labels: &Stream<C, OrdZSet<Label, isize>>,
-    ) -> Stream<C, OrdZSet<Label, isize>>
-    where
-        C: Circuit,
-        <<C as WithClock>::Time as Timestamp>::Nested: DBTimestamp,
-    {
-        let computed_labels = circuit
-            .fixedpoint(|child| {
-                let edges = edges.delta0(child);
-                let labels = labels.delta0(child);
 
-                let computed_labels = <DelayedFeedback<_, OrdZSet<_, _>>>::new(child);
-                let result: Stream<_, OrdZSet<Label, isize>> =
-                    labels.plus(computed_labels.stream());
+        /// Batcher that buffers unsorted tuples until it is sealed.
+        #[derive(SizeOf)]
+        pub struct WrapperZSetBatcher<K: DBData> {
+            tuples: Vec<(K, isize)>,
+        }
 
-                computed_labels.connect(&result.index_with(|label| (label.0, label.1)).join(
-                    &edges.index_with(|edge| (edge.0, edge.1)),
-                    |_from, label, to| Label(*to, *label),
-                ));
+        impl<K: DBData> Batcher<K, (), isize, WrapperZSet<K>> for WrapperZSetBatcher<K> {
+            fn new_batcher(_time: ()) -> Self {
+                Self { tuples: Vec::new() }
+            }
 
-                Ok(result.integrate_trace().export())
-            })
-            .unwrap();
+            fn push_batch(&mut self, batch: &mut Vec<(K, isize)>) {
+                self.tuples.append(batch);
+            }
 
-        computed_labels.consolidate()
-    }
+            fn push_consolidated_batch(&mut self, batch: &mut Vec<(K, isize)>) {
+                self.tuples.append(batch);
+            }
 
-    #[test]
-    fn propagate_test() {
-        let circuit = RootCircuit::build(move |circuit| {
-            let mut edges: vec::IntoIter<OrdZSet<Edge, isize>> = vec![
-                zset! { Edge(1, 2) => 1, Edge(1, 3) => 1, Edge(2, 4) => 1, Edge(3, 4) => 1 },
-                zset! { Edge(5, 7) => 1, Edge(6, 7) => 1 },
-                zset! { Edge(4, 5) => 1, Edge(4, 6) => 1, },
-                zset! { Edge(3, 8) => 1, Edge(8, 9) => 1 },
-                zset! { Edge(2, 4) => -1, Edge(7, 10) => 1 },
-                zset! { Edge(3, 4) => -1 },
-                zset! { Edge(1, 4) => 1 },
-                zset! { Edge(9, 7) => 1 },
-            ]
-            .into_iter();
+            fn tuples(&self) -> usize {
+                self.tuples.len()
+            }
 
-            let mut labels: vec::IntoIter<OrdZSet<Label, isize>> = vec![
-                zset! { Label(1, 0) => 1 },
-                zset! { Label(4, 1) => 1 },
-                zset! { },
-                zset! { Label(1, 0) => -1, Label(1, 2) => 1 },
-                zset! { },
-                zset! { Label(8, 3) => 1 },
-                zset! { Label(4, 1) => -1 },
-                zset! { },
-            ]
-            .into_iter();
+            fn seal(self) -> WrapperZSet<K> {
+                WrapperZSet(OrdZSet::from_tuples((), self.tuples))
+            }
+        }
 
-            let mut outputs: vec::IntoIter<OrdZSet<Label, isize>> = vec![
-                zset! { Label(1, 0) => 1, Label(2, 0) => 1, Label(3, 0) => 1, Label(4, 0) => 2 },
-                zset! { Label(4, 1) => 1 },
-                zset! { Label(5, 0) => 2, Label(5, 1) => 1, Label(6, 0) => 2, Label(6, 1) => 1, Label(7, 0) => 4, Label(7, 1) => 2 },
-                zset! { Label(1, 0) => -1, Label(1, 2) => 1, Label(2, 0) => -1, Label(2, 2) => 1, Label(3, 0) => -1, Label(3, 2) => 1, Label(4, 0) => -2, Label(4, 2) => 2, Label(5, 0) => -2, Label(5, 2) => 2, Label(6, 0) => -2, Label(6, 2) => 2, Label(7, 0) => -4, Label(7, 2) => 4, Label(8, 2) => 1, Label(9, 2) => 1 },
-                zset! { Label(4, 2) => -1, Label(5, 2) => -1, Label(6, 2) => -1, Label(7, 2) => -2, Label(10, 1) => 2, Label(10, 2) => 2 },
-                zset! { Label(4, 2) => -1, Label(5, 2) => -1, Label(6, 2) => -1, Label(7, 2) => -2, Label(8, 3) => 1, Label(9, 3) => 1, Label(10, 2) => -2 },
-                zset! { Label(4, 1) => -1, Label(4, 2) => 1, Label(5, 1) => -1, Label(5, 2) => 1, Label(6, 1) => -1, Label(6, 2) => 1, Label(7, 1) => -2, Label(7, 2) => 2, Label(10, 1) => -2, Label(10, 2) => 2 },
-                zset! { Label(7, 2) => 1, Label(7, 3) => 1, Label(10, 2) => 1, Label(10, 3) => 1 },
-            ]
-            .into_iter();
+        #[derive(SizeOf)]
+        pub struct WrapperZSetBuilder<K: DBData>(OrdZSetBuilder<K, isize>);
 
-            let edges: Stream<_, OrdZSet<Edge, isize>> =
-                circuit
-                    .add_source(Generator::new(move || edges.next().unwrap()));
+        impl<K: DBData> Builder<K, (), isize, WrapperZSet<K>> for WrapperZSetBuilder<K> {
+            fn new_builder(time: ()) -> Self {
+                Self(OrdZSetBuilder::new_builder(time))
+            }
 
-            let labels: Stream<_, OrdZSet<Label, isize>> =
-                circuit
-                    .add_source(Generator::new(move || labels.next().unwrap()));
+            fn with_capacity(time: (), cap: usize) -> Self {
+                Self(OrdZSetBuilder::with_capacity(time, cap))
+            }
 
-            propagate(circuit, &edges, &labels).inspect(move |labeled| {
-                assert_eq!(*labeled, outputs.next().unwrap());
-            });
-            Ok(())
-        })
-        .unwrap().0;
+            fn push(&mut self, element: (K, isize)) {
+                self.0.push(element);
+            }
 
-        for _ in 0..8 {
-            //eprintln!("{}", i);
-            circuit.step().unwrap();
+            fn reserve(&mut self, additional: usize) {
+                self.0.reserve(additional);
+            }
+
+            fn done(self) -> WrapperZSet<K> {
+                WrapperZSet(self.0.done())
+            }
         }
-    }
 
-    #[test]
-    fn propagate_nested_test() {
-        let circuit = RootCircuit::build(move |circuit| {
-            let mut edges: vec::IntoIter<OrdZSet<Edge, isize>> = vec![
-                zset! { Edge(1, 2) => 1, Edge(1, 3) => 1, Edge(2, 4) => 1, Edge(3, 4) => 1 },
-                zset! { Edge(5, 7) => 1, Edge(6, 7) => 1 },
-                zset! { Edge(4, 5) => 1, Edge(4, 6) => 1 },
-                zset! { Edge(3, 8) => 1, Edge(8, 9) => 1 },
-                zset! { Edge(2, 4) => -1, Edge(7, 10) => 1 },
-                zset! { Edge(3, 4) => -1 },
-                zset! { Edge(1, 4) => 1 },
-                zset! { Edge(9, 7) => 1 },
-            ]
-            .into_iter();
+        #[derive(SizeOf)]
+        pub struct WrapperZSetMerger<K: DBData>(OrdZSetMerger<K, isize>);
 
-            let mut labels: vec::IntoIter<OrdZSet<Label, isize>> = vec![
-                zset! { Label(1, 0) => 1 },
-                zset! { Label(4, 1) => 1 },
-                zset! { },
-                zset! { Label(1, 0) => -1, Label(1, 2) => 1 },
-                zset! { },
-                zset! { Label(8, 3) => 1 },
-                zset! { Label(4, 1) => -1 },
-                zset! { },
-            ]
-            .into_iter();
+        impl<K: DBData> Merger<K, (), (), isize, WrapperZSet<K>> for WrapperZSetMerger<K> {
+            fn new_merger(source1: &WrapperZSet<K>, source2: &WrapperZSet<K>) -> Self {
+                Self(OrdZSetMerger::new_merger(&source1.0, &source2.0))
+            }
 
-            let mut outputs: vec::IntoIter<OrdZSet<Label, isize>> = vec![
-                zset!{ Label(1,0) => 2, Label(2,0) => 3, Label(3,0) => 3, Label(4,0) => 8 },
-                zset!{ Label(4,1) => 2 },
-                zset!{ Label(5,0) => 10, Label(5,1) => 3, Label(6,0) => 10, Label(6,1) => 3, Label(7,0) => 24, Label(7,1) => 8 },
-                zset!{ Label(1,0) => -2, Label(1,2) => 2, Label(2,0) => -3, Label(2,2) => 3, Label(3,0) => -3, Label(3,2) => 3,
-                       Label(4,0) => -8, Label(4,2) => 8, Label(5,0) => -10, Label(5,2) => 10, Label(6,0) => -10, Label(6,2) => 10,
-                       Label(7,0) => -24, Label(7,2) => 24, Label(8,2) => 4, Label(9,2) => 5 },
-                zset!{ Label(4,2) => -4, Label(5,2) => -5, Label(6,2) => -5, Label(7,2) => -12, Label(10,1) => 10, Label(10,2) => 14 },
-                zset!{ Label(4,2) => -4, Label(5,2) => -5, Label(6,2) => -5, Label(7,2) => -12, Label(8,3) => 2,
-                       Label(9,3) => 3, Label(10,2) => -14 },
-                zset!{ Label(4,1) => -2, Label(4,2) => 3, Label(5,1) => -3, Label(5,2) => 4, Label(6,1) => -3, Label(6,2) => 4,
-                       Label(7,1) => -8, Label(7,2) => 10, Label(10,1) => -10, Label(10,2) => 12 },
-                zset!{ Label(7,2) => 6, Label(7,3) => 4, Label(10,2) => 7, Label(10,3) => 5 },
-            ].into_iter();
+            fn work(
+                &mut self,
+                source1: &WrapperZSet<K>,
+                source2: &WrapperZSet<K>,
+                key_filter: &Option<Filter<K>>,
+                value_filter: &Option<Filter<()>>,
+                fuel: &mut isize,
+            ) {
+                self.0
+                    .work(&source1.0, &source2.0, key_filter, value_filter, fuel);
+            }
 
-            let edges: Stream<_, OrdZSet<Edge, isize>> =
-                circuit
-                    .add_source(Generator::new(move || edges.next().unwrap()));
+            fn done(self) -> WrapperZSet<K> {
+                WrapperZSet(self.0.done())
+            }
+        }
+    }
 
-            let labels: Stream<_, OrdZSet<Label, isize>> =
-                circuit
-                    .add_source(Generator::new(move || labels.next().unwrap()));
+    #[test]
+    fn join_custom_zset_test() {
+        use custom_zset::WrapperZSet;
 
-            let result = circuit.iterate(|child| {
+        let (circuit, (input1, input2)) = RootCircuit::build(move |circuit| {
+            let (input1, input_handle1) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+            let (input2, input_handle2) = circuit.add_input_indexed_zset::<u64, u64, isize>();
 
-                let counter = std::cell::RefCell::new(0);
-                let edges = edges.delta0(child);
-                let labels = labels.delta0(child);
+            let join = |k: &u64, v1: &u64, v2: &u64| (*k, *v1, *v2);
 
-                let computed_labels = <DelayedFeedback<_, OrdZSet<_, _>>>::new(child);
-                let result = propagate(child, &edges, &labels.plus(computed_labels.stream()));
-                computed_labels.connect(&result);
+            // `Join` operator.
+            let expected = input1.stream_join(&input2, join);
+            input1
+                .stream_join_generic::<_, _, WrapperZSet<(u64, u64, u64)>>(&input2, join)
+                .apply2(&expected, |actual, expected| {
+                    assert_eq!(&actual.0, expected)
+                });
 
-                //result.inspect(|res: &OrdZSet<Label, isize>| println!("delta: {}", res));
-                Ok((move || {
-                    let mut counter = counter.borrow_mut();
-                    // reset to 0 on each outer loop iteration.
-                    if *counter == 2 {
-                        *counter = 0;
-                    }
-                    *counter += 1;
-                    //println!("counter: {}", *counter);
-                    Ok(*counter == 2)
-                },
-                result.integrate_trace().export()))
-            }).unwrap();
+            // `JoinTrace` operators.
+            let expected = input1.join(&input2, join);
+            input1
+                .join_generic::<_, _, WrapperZSet<(u64, u64, u64)>, _>(&input2, move |k, v1, v2| {
+                    [(join(k, v1, v2), ())]
+                })
+                .apply2(&expected, |actual, expected| {
+                    assert_eq!(&actual.0, expected)
+                });
 
-            result.consolidate().inspect(move |res: &OrdZSet<Label, isize>| {
-                assert_eq!(*res, outputs.next().unwrap());
-            });
-            Ok(())
+            Ok((input_handle1, input_handle2))
         })
-        .unwrap().0;
+        .unwrap();
 
-        for _ in 0..8 {
-            //eprintln!("{}", i);
-            circuit.step().unwrap();
-        }
+        input1.append(&mut vec![(1, (10, 1)), (1, (11, 2)), (2, (20, 1))]);
+        input2.append(&mut vec![(1, (100, 1)), (3, (300, 1))]);
+        circuit.step().unwrap();
+
+        input1.append(&mut vec![(3, (30, 1)), (1, (10, -1))]);
+        input2.append(&mut vec![(2, (200, -1)), (1, (101, 1))]);
+        circuit.step().unwrap();
+
+        input2.append(&mut vec![(1, (100, -1))]);
+        circuit.step().unwrap();
     }
 
-    #[test]
-    fn antijoin_test() {
-        let output = Arc::new(Mutex::new(OrdIndexedZSet::empty(())));
+    fn partitioned_join(
+        workers: usize,
+        scheme: PartitionScheme<usize>,
+    ) -> OrdZSet<(usize, usize, usize), isize> {
+        let output = Arc::new(Mutex::new(OrdZSet::empty(())));
         let output_clone = output.clone();
 
-        let (mut circuit, (input1, input2)) = Runtime::init_circuit(4, move |circuit| {
+        let (mut circuit, (input1, input2)) = Runtime::init_circuit(workers, move |circuit| {
             let (input1, input_handle1) = circuit.add_input_indexed_zset::<usize, usize, isize>();
             let (input2, input_handle2) = circuit.add_input_indexed_zset::<usize, usize, isize>();
 
-            input1.antijoin(&input2).gather(0).inspect(move |batch| {
-                if Runtime::worker_index() == 0 {
-                    *output_clone.lock().unwrap() = batch.clone();
-                }
-            });
+            input1
+                .stream_join_partitioned(&input2, |k, v1, v2| (*k, *v1, *v2), &scheme)
+                .gather(0)
+                .inspect(move |batch| {
+                    if Runtime::worker_index() == 0 {
+                        *output_clone.lock().unwrap() = batch.clone();
+                    }
+                });
 
             Ok((input_handle1, input_handle2))
         })
         .unwrap();
 
-        input1.append(&mut vec![
-            (1, (0, 1)),
-            (1, (1, 2)),
-            (2, (0, 1)),
-            (2, (1, 1)),
-        ]);
-        circuit.step().unwrap();
-        assert_eq!(
-            &*output.lock().unwrap(),
-            &indexed_zset! { 1 => { 0 => 1, 1 => 2}, 2 => { 0 => 1, 1 => 1 } }
-        );
-
-        input1.append(&mut vec![(3, (1, 1))]);
+        input1.append(&mut (0..100).map(|k| (k, (k % 7, 1))).collect());
+        input2.append(&mut (0..100).map(|k| (k % 50, (k, 1))).collect());
         circuit.step().unwrap();
-        assert_eq!(&*output.lock().unwrap(), &indexed_zset! { 3 => { 1 => 1 } });
+        circuit.kill().unwrap();
 
-        input2.append(&mut vec![(1, (1, 3))]);
-        circuit.step().unwrap();
-        assert_eq!(
-            &*output.lock().unwrap(),
-            &indexed_zset! { 1 => { 0 => -1, 1 => -2 } }
-        );
+        let output = output.lock().unwrap().clone();
+        output
+    }
 
-        input2.append(&mut vec![(2, (5, 1))]);
-        input1.append(&mut vec![(2, (2, 1)), (4, (1, 1))]);
-        circuit.step().unwrap();
-        assert_eq!(
-            &*output.lock().unwrap(),
-            &indexed_zset! { 2 => { 0 => -1, 1 => -1 }, 4 => { 1 => 1 } }
+    #[test]
+    fn stream_join_partitioned_test() {
+        let expected: OrdZSet<(usize, usize, usize), isize> = OrdZSet::from_keys(
+            (),
+            (0..100).map(|k| ((k % 50, (k % 50) % 7, k), 1)).collect(),
         );
 
-        circuit.kill().unwrap();
+        let schemes = [
+            PartitionScheme::default(),
+            PartitionScheme::Hash { seed: 42 },
+            PartitionScheme::Range {
+                bounds: vec![10, 20, 30, 40, 50],
+            },
+            PartitionScheme::Custom(Arc::new(|k: &usize, _partitions: usize| k / 3)),
+        ];
+
+        for scheme in schemes {
+            let output2 = partitioned_join(2, scheme.clone());
+            let output4 = partitioned_join(4, scheme);
+            assert_eq!(output2, output4);
+            assert_eq!(output2, expected);
+        }
     }
 }
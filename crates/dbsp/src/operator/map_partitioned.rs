@@ -0,0 +1,250 @@
+//! Parallel version of [`map`](`crate::operator::FilterMap::map`) for large
+//! batches.
+
+use crate::{
+    circuit::{
+        metadata::OperatorLocation,
+        operator_traits::{Operator, UnaryOperator},
+        Circuit, Scope, Stream,
+    },
+    operator::communication::PartitionScheme,
+    trace::{cursor::Cursor, Batch, BatchReader},
+    DBData, DBWeight, OrdZSet,
+};
+use std::{borrow::Cow, marker::PhantomData, panic::Location, thread};
+
+impl<C, K, R> Stream<C, OrdZSet<K, R>>
+where
+    C: Circuit,
+    K: DBData,
+    R: DBWeight,
+{
+    /// Applies `map_func` to each key in the input stream, splitting the work
+    /// across `partitions` threads.
+    ///
+    /// Produces the same output as [`map`](`crate::operator::FilterMap::map`),
+    /// but evaluates `map_func` in parallel.  Each thread rekeys a slice of
+    /// the input batch and assigns the new keys to partitions using `scheme`.
+    /// The tuples in each partition are then sorted and consolidated by a
+    /// separate thread, and the resulting batches, whose keys don't overlap,
+    /// are merged into the output batch.
+    ///
+    /// This helps when rekeying large batches, e.g., before a join.  For small
+    /// batches the cost of spawning threads outweighs the gain, so `map`
+    /// should be preferred.  Unlike sharding, this does not distribute the
+    /// output across workers: the whole output batch is produced in the
+    /// worker that owns the input batch.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `partitions` is 0.
+    #[track_caller]
+    pub fn map_keys_partitioned<F, K2>(
+        &self,
+        partitions: usize,
+        scheme: PartitionScheme<K2>,
+        map_func: F,
+    ) -> Stream<C, OrdZSet<K2, R>>
+    where
+        K2: DBData + Sync,
+        F: Fn(&K) -> K2 + Send + Sync + 'static,
+    {
+        self.map_keys_partitioned_generic(partitions, scheme, map_func)
+    }
+
+    /// Like [`Self::map_keys_partitioned`], but can return any batch type.
+    #[track_caller]
+    pub fn map_keys_partitioned_generic<F, O>(
+        &self,
+        partitions: usize,
+        scheme: PartitionScheme<O::Key>,
+        map_func: F,
+    ) -> Stream<C, O>
+    where
+        O: Batch<Val = (), Time = (), R = R> + Send,
+        O::Key: Sync,
+        O::Item: Send,
+        F: Fn(&K) -> O::Key + Send + Sync + 'static,
+    {
+        assert_ne!(partitions, 0);
+
+        self.circuit().add_unary_operator(
+            MapKeysPartitioned::new(partitions, scheme, map_func, Location::caller()),
+            self,
+        )
+    }
+}
+
+/// Operator that rekeys its input batch using multiple threads.
+///
+/// See [`Stream::map_keys_partitioned`].
+pub struct MapKeysPartitioned<I, O, F>
+where
+    O: BatchReader,
+{
+    partitions: usize,
+    scheme: PartitionScheme<O::Key>,
+    map_func: F,
+    location: &'static Location<'static>,
+    _types: PhantomData<I>,
+}
+
+impl<I, O, F> MapKeysPartitioned<I, O, F>
+where
+    O: BatchReader,
+{
+    pub fn new(
+        partitions: usize,
+        scheme: PartitionScheme<O::Key>,
+        map_func: F,
+        location: &'static Location<'static>,
+    ) -> Self {
+        Self {
+            partitions,
+            scheme,
+            map_func,
+            location,
+            _types: PhantomData,
+        }
+    }
+}
+
+impl<I, O, F> Operator for MapKeysPartitioned<I, O, F>
+where
+    I: 'static,
+    O: BatchReader,
+    F: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("MapKeysPartitioned")
+    }
+
+    fn location(&self) -> OperatorLocation {
+        Some(self.location)
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<I, O, F> UnaryOperator<I, O> for MapKeysPartitioned<I, O, F>
+where
+    I: BatchReader<Val = (), Time = ()>,
+    O: Batch<Val = (), Time = (), R = I::R> + Send,
+    O::Key: Sync,
+    O::Item: Send,
+    F: Fn(&I::Key) -> O::Key + Send + Sync + 'static,
+{
+    fn eval(&mut self, input: &I) -> O {
+        let partitions = self.partitions;
+        let scheme = &self.scheme;
+        let map_func = &self.map_func;
+
+        // Split the input into one slice per thread.  Cursors can't be shared
+        // across threads, so the slices are copied out of the batch.
+        let chunk_size = input.len() / partitions + 1;
+        let mut chunks = vec![Vec::with_capacity(chunk_size)];
+        let mut cursor = input.cursor();
+        while cursor.key_valid() {
+            let weight = cursor.weight();
+            if chunks.last().unwrap().len() == chunk_size {
+                chunks.push(Vec::with_capacity(chunk_size));
+            }
+            chunks
+                .last_mut()
+                .unwrap()
+                .push((cursor.key().clone(), weight));
+            cursor.step_key();
+        }
+
+        thread::scope(|scope| {
+            // Rekey each slice and assign the new keys to partitions.
+            let rekeyed = chunks
+                .into_iter()
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        let mut buckets = (0..partitions).map(|_| Vec::new()).collect::<Vec<_>>();
+                        for (key, weight) in chunk {
+                            let key = map_func(&key);
+                            let partition = scheme.partition(&key, partitions);
+                            buckets[partition].push((O::item_from(key, ()), weight));
+                        }
+                        buckets
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let mut partitioned = (0..partitions).map(|_| Vec::new()).collect::<Vec<_>>();
+            for handle in rekeyed {
+                for (partition, bucket) in handle.join().unwrap().into_iter().enumerate() {
+                    partitioned[partition].extend(bucket);
+                }
+            }
+
+            // Sort and consolidate each partition.
+            let batches = partitioned
+                .into_iter()
+                .map(|tuples| scope.spawn(move || O::from_tuples((), tuples)))
+                .collect::<Vec<_>>()
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .filter(|batch| !batch.is_empty());
+
+            batches.fold(O::empty(()), |output, batch| output.merge(&batch))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        operator::{communication::PartitionScheme, FilterMap},
+        trace::Batch,
+        zset, OrdZSet, RootCircuit,
+    };
+
+    #[test]
+    fn map_keys_partitioned_test() {
+        let schemes = vec![
+            PartitionScheme::default(),
+            PartitionScheme::Range {
+                bounds: vec![10, 20],
+            },
+        ];
+
+        for scheme in schemes {
+            for partitions in [1, 3, 8] {
+                let scheme = scheme.clone();
+                let (circuit, (input_handle, sequential, partitioned)) =
+                    RootCircuit::build(move |circuit| {
+                        let (input, input_handle) = circuit.add_input_zset::<u64, isize>();
+
+                        let sequential = input.map(|k| k % 30).output();
+                        let partitioned = input
+                            .map_keys_partitioned(partitions, scheme, |k| k % 30)
+                            .output();
+
+                        Ok((input_handle, sequential, partitioned))
+                    })
+                    .unwrap();
+
+                input_handle.append(&mut (0..1000).map(|k| (k, 1)).collect());
+                circuit.step().unwrap();
+                let expected = sequential.consolidate();
+                assert_eq!(partitioned.consolidate(), expected);
+                assert_eq!(expected.len(), 30);
+
+                // Keys that map to the same output key cancel out.
+                input_handle.append(&mut vec![(1, 1), (31, -1), (2, 5)]);
+                circuit.step().unwrap();
+                assert_eq!(sequential.consolidate(), zset! {2 => 5});
+                assert_eq!(partitioned.consolidate(), zset! {2 => 5});
+
+                circuit.step().unwrap();
+                assert_eq!(sequential.consolidate(), OrdZSet::empty(()));
+                assert_eq!(partitioned.consolidate(), OrdZSet::empty(()));
+            }
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! Operator that rewrites the weights of a batch.
+
+use crate::{
+    algebra::HasZero,
+    circuit::{
+        metadata::OperatorLocation,
+        operator_traits::{Operator, UnaryOperator},
+        Circuit, Scope, Stream,
+    },
+    trace::{cursor::Cursor, Batch, BatchReader, Builder},
+};
+use std::{borrow::Cow, marker::PhantomData, panic::Location};
+
+impl<C, B> Stream<C, B>
+where
+    C: Circuit,
+    B: BatchReader<Time = ()> + Clone + 'static,
+{
+    /// Apply `map_func` to the weight of each `(key, value)` tuple in the
+    /// input stream, producing a batch of type `O` with the same keys and
+    /// values.
+    ///
+    /// This is typically used to convert a Z-set to a different weight type,
+    /// e.g., from `isize` to `i64`.  Tuples whose new weight is zero are
+    /// dropped.  Keys and values are visited in order, so the output is
+    /// built without re-sorting.  The operator processes each tuple
+    /// independently and preserves the sharding of its input.
+    #[track_caller]
+    pub fn map_weights<O, F>(&self, map_func: F) -> Stream<C, O>
+    where
+        O: Batch<Key = B::Key, Val = B::Val, Time = ()> + Clone + 'static,
+        F: Fn(&B::R) -> O::R + 'static,
+    {
+        let output = self.circuit().add_unary_operator(
+            MapWeights::new(map_func, Location::caller()),
+            &self.try_sharded_version(),
+        );
+        output.mark_sharded_if(self);
+        output
+    }
+}
+
+/// Operator that applies a function to the weights of its input.
+///
+/// See [`Stream::map_weights`].
+pub struct MapWeights<B, O, F> {
+    map_func: F,
+    location: &'static Location<'static>,
+    _types: PhantomData<(B, O)>,
+}
+
+impl<B, O, F> MapWeights<B, O, F> {
+    pub fn new(map_func: F, location: &'static Location<'static>) -> Self {
+        Self {
+            map_func,
+            location,
+            _types: PhantomData,
+        }
+    }
+}
+
+impl<B, O, F> Operator for MapWeights<B, O, F>
+where
+    B: 'static,
+    O: 'static,
+    F: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("MapWeights")
+    }
+
+    fn location(&self) -> OperatorLocation {
+        Some(self.location)
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<B, O, F> UnaryOperator<B, O> for MapWeights<B, O, F>
+where
+    B: BatchReader<Time = ()> + 'static,
+    O: Batch<Key = B::Key, Val = B::Val, Time = ()>,
+    F: Fn(&B::R) -> O::R + 'static,
+{
+    fn eval(&mut self, input: &B) -> O {
+        let mut builder = O::Builder::with_capacity((), input.len());
+        let mut cursor = input.cursor();
+
+        while cursor.key_valid() {
+            while cursor.val_valid() {
+                let weight = (self.map_func)(&cursor.weight());
+                if !weight.is_zero() {
+                    builder.push((
+                        O::item_from(cursor.key().clone(), cursor.val().clone()),
+                        weight,
+                    ));
+                }
+                cursor.step_val();
+            }
+            cursor.step_key();
+        }
+
+        builder.done()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{indexed_zset, trace::Batch, zset, OrdIndexedZSet, OrdZSet, RootCircuit};
+
+    #[test]
+    fn map_weights_test() {
+        let (circuit, (input_handle, indexed_input_handle, output, indexed_output)) =
+            RootCircuit::build(move |circuit| {
+                let (input, input_handle) = circuit.add_input_zset::<u64, isize>();
+                let (indexed_input, indexed_input_handle) =
+                    circuit.add_input_indexed_zset::<u64, u64, isize>();
+
+                let output = input
+                    .map_weights::<OrdZSet<u64, i64>, _>(|w| *w as i64)
+                    .output();
+                let indexed_output = indexed_input
+                    .map_weights::<OrdIndexedZSet<u64, u64, i64>, _>(|w| *w as i64 * 10)
+                    .output();
+
+                Ok((input_handle, indexed_input_handle, output, indexed_output))
+            })
+            .unwrap();
+
+        input_handle.append(&mut vec![(3, 1), (1, -2), (2, 5)]);
+        indexed_input_handle.append(&mut vec![(2, (21, 1)), (1, (10, -1)), (2, (20, 2))]);
+        circuit.step().unwrap();
+
+        let zset: OrdZSet<u64, i64> = output.consolidate();
+        assert_eq!(zset, zset! {1 => -2, 2 => 5, 3 => 1});
+        let indexed_zset: OrdIndexedZSet<u64, u64, i64> = indexed_output.consolidate();
+        assert_eq!(
+            indexed_zset,
+            indexed_zset! {1 => {10 => -10}, 2 => {20 => 20, 21 => 10}}
+        );
+
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), OrdZSet::empty(()));
+        assert_eq!(indexed_output.consolidate(), OrdIndexedZSet::empty(()));
+    }
+}
@@ -10,52 +10,95 @@ pub(crate) mod inspect;
 pub(crate) mod upsert;
 
 mod aggregate;
+mod assert;
+mod async_input;
+mod changes_only;
+mod coalesce;
 mod condition;
 mod consolidate;
 mod count;
 #[cfg(feature = "with-csv")]
 mod csv;
+mod debounce;
+mod dedup;
 mod delta0;
 mod differentiate;
 mod distinct;
+mod duplicates;
 mod filter_map;
 mod generator;
 mod group;
 mod index;
 mod input;
 mod integrate;
+mod intersect;
 mod join;
 pub mod join_range;
+mod map_partitioned;
+mod map_weights;
 mod neg;
 pub mod neighborhood;
+mod outer_union;
 mod output;
+mod partition_by_match;
 mod plus;
+mod rebatch;
+mod replay;
 pub mod sample;
+mod scan;
 mod semijoin;
 mod stream_fold;
 mod sum;
+mod take_while;
+mod throttle;
 pub mod time_series;
 mod trace;
+mod union_by_key;
 mod z1;
 
 #[cfg(feature = "with-csv")]
 pub use self::csv::CsvSource;
-pub use aggregate::{Aggregator, Avg, Fold, Max, MaxSemigroup, Min, MinSemigroup};
+pub use aggregate::{
+    AccumulateLast, Aggregator, ArgMinBy, Avg, Fold, Max, MaxSemigroup, Min, MinMaxBy,
+    MinSemigroup, Pivot, PivotSemigroup,
+};
 pub use apply::Apply;
+pub use assert::Assert;
+pub use async_input::{AsyncCollectionHandle, AsyncCollectionReceiver};
+pub use changes_only::ChangesOnly;
+pub use coalesce::{Coalesce, CoalesceHandle};
 pub use condition::Condition;
+pub use debounce::Debounce;
+pub use dedup::DedupWithinStep;
 pub use delta0::Delta0;
-pub use distinct::Distinct;
-pub use filter_map::{FilterKeys, FilterMap, FilterVals, FlatMap, Map, MapKeys};
-pub use generator::{Generator, GeneratorNested};
+pub use distinct::{Distinct, UnionDistinct};
+pub use duplicates::Duplicates;
+pub use filter_map::{FilterKeys, FilterMap, FilterVals, FlatMap, Map, MapKeys, MapValues};
+pub use generator::{Generator, GeneratorNested, RateLimitedGenerator, TryGenerator};
 pub use index::Index;
 use input::Mailbox;
 pub use input::{CollectionHandle, InputHandle, UpsertHandle};
 pub use inspect::Inspect;
-pub use join::Join;
+pub use intersect::IntersectKeys;
+pub use join::{
+    CrossJoin, HashJoin, HashJoinSide, Join, JoinBy, JoinCow, JoinRetention, JoinWatermark,
+};
 pub use join_range::StreamJoinRange;
+pub use map_partitioned::MapKeysPartitioned;
+pub use map_weights::MapWeights;
 pub use neg::UnaryMinus;
 pub use neighborhood::{Neighborhood, NeighborhoodDescr};
 pub use output::OutputHandle;
+pub use partition_by_match::PartitionByMatch;
 pub use plus::{Minus, Plus};
+pub use rebatch::Rebatch;
+pub use replay::{
+    InputLog, InputLogEntry, InputRecorder, InputReplayer, RecordingCollectionHandle,
+};
+pub use scan::Scan;
 pub use sum::Sum;
-pub use z1::{DelayedFeedback, DelayedNestedFeedback, Z1Nested, Z1};
+pub use take_while::TakeWhileKey;
+pub use throttle::Throttle;
+pub use trace::{CompactionHandle, TraceRetention};
+pub use union_by_key::UnionByKey;
+pub use z1::{DelayedFeedback, DelayedNestedFeedback, Z1Nested, Z1, ZN};
@@ -0,0 +1,119 @@
+//! Union of two streams with different record types.
+
+use crate::{
+    circuit::{Circuit, Stream},
+    operator::FilterMap,
+    DBData, DBWeight, OrdZSet,
+};
+
+impl<C, K1, R> Stream<C, OrdZSet<K1, R>>
+where
+    C: Circuit,
+    K1: DBData,
+    R: DBWeight,
+{
+    /// Merge two streams whose records have different types into a stream of
+    /// a common type.
+    ///
+    /// Applies `left` to each record in `self` and `right` to each record in
+    /// `other` and adds up the results.  The common type is typically an enum
+    /// with a variant per input, so that downstream operators can tell
+    /// records from different inputs apart, e.g., when merging events from
+    /// heterogeneous sources into one pipeline.  Records from both inputs
+    /// that map to the same value have their weights added up.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use dbsp::{zset, OrdZSet, RootCircuit};
+    /// let (circuit, (left_handle, right_handle, output)) = RootCircuit::build(|circuit| {
+    ///     let (left, left_handle) = circuit.add_input_zset::<u64, isize>();
+    ///     let (right, right_handle) = circuit.add_input_zset::<String, isize>();
+    ///     let output = left
+    ///         .outer_union(&right, |n| n.to_string(), |s| s.clone())
+    ///         .output();
+    ///     Ok((left_handle, right_handle, output))
+    /// })
+    /// .unwrap();
+    ///
+    /// left_handle.push(1, 1);
+    /// right_handle.push("1".to_string(), 1);
+    /// right_handle.push("2".to_string(), 1);
+    /// circuit.step().unwrap();
+    /// assert_eq!(
+    ///     output.consolidate(),
+    ///     zset! {"1".to_string() => 2, "2".to_string() => 1}
+    /// );
+    /// ```
+    pub fn outer_union<K2, T, F1, F2>(
+        &self,
+        other: &Stream<C, OrdZSet<K2, R>>,
+        left: F1,
+        right: F2,
+    ) -> Stream<C, OrdZSet<T, R>>
+    where
+        K2: DBData,
+        T: DBData,
+        F1: Fn(&K1) -> T + Clone + 'static,
+        F2: Fn(&K2) -> T + Clone + 'static,
+    {
+        self.map(left).plus(&other.map(right))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{trace::Batch, zset, OrdZSet, RootCircuit};
+    use rkyv::{Archive, Deserialize, Serialize};
+    use size_of::SizeOf;
+
+    #[derive(
+        Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, SizeOf, Archive, Serialize, Deserialize,
+    )]
+    enum Either<A, B> {
+        Left(A),
+        Right(B),
+    }
+
+    #[test]
+    fn outer_union_test() {
+        let (circuit, (left_handle, right_handle, output)) = RootCircuit::build(|circuit| {
+            let (left, left_handle) = circuit.add_input_zset::<u64, isize>();
+            let (right, right_handle) = circuit.add_input_zset::<String, isize>();
+
+            let output = left
+                .outer_union(&right, |n| Either::Left(*n), |s| Either::Right(s.clone()))
+                .output();
+
+            Ok((left_handle, right_handle, output))
+        })
+        .unwrap();
+
+        left_handle.push(1, 1);
+        left_handle.push(2, 2);
+        right_handle.push("foo".to_string(), 1);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            zset! {
+                Either::Left(1) => 1,
+                Either::Left(2) => 2,
+                Either::Right("foo".to_string()) => 1,
+            }
+        );
+
+        left_handle.push(2, -2);
+        right_handle.push("bar".to_string(), 1);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            zset! {
+                Either::Left(2) => -2,
+                Either::Right("bar".to_string()) => 1,
+            }
+        );
+
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), OrdZSet::empty(()));
+    }
+}
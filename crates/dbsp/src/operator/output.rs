@@ -1,15 +1,17 @@
 use super::Mailbox;
 use crate::{
+    algebra::{AddAssignByRef, HasZero},
     circuit::{
         operator_traits::{BinarySinkOperator, Operator, SinkOperator},
         LocalStoreMarker, OwnershipPreference, RootCircuit, Scope,
     },
-    trace::{Batch, Spine, Trace},
+    trace::{Batch, Consumer, Spine, Trace, ValueConsumer},
     Circuit, Runtime, Stream,
 };
 use std::{
     borrow::Cow,
     hash::{Hash, Hasher},
+    iter::from_fn,
     marker::PhantomData,
     sync::Arc,
 };
@@ -243,6 +245,75 @@ where
     }
 }
 
+impl<T> OutputHandle<T>
+where
+    T: Batch<Val = (), Time = ()> + Send,
+{
+    /// Read Z-sets produced by all worker threads during the last clock
+    /// cycle as an iterator over consolidated `(key, weight)` pairs.
+    ///
+    /// This method returns the same tuples as
+    /// [`consolidate`](`Self::consolidate`), but instead of assembling them
+    /// into a new batch it merges batches produced by individual workers on
+    /// the fly as the iterator is consumed, moving keys out of the original
+    /// batches.  Use it to export large outputs without allocating a copy
+    /// of the entire output.
+    ///
+    /// # Ordering
+    ///
+    /// The iterator yields keys in ascending order.  Each key occurs at most
+    /// once; keys whose weights cancel out across workers are skipped.
+    ///
+    /// Like `consolidate`, this method removes batches from the output
+    /// mailboxes, so subsequent calls return an empty iterator until the
+    /// next clock cycle.
+    pub fn drain(&self) -> impl Iterator<Item = (T::Key, T::R)> {
+        let mut consumers = self
+            .take_from_all()
+            .into_iter()
+            .map(|batch| batch.consumer())
+            .collect::<Vec<_>>();
+
+        from_fn(move || loop {
+            // Find the consumer with the smallest key.
+            let (index, _) = consumers
+                .iter()
+                .enumerate()
+                .filter(|(_, consumer)| consumer.key_valid())
+                .min_by(|(_, c1), (_, c2)| c1.peek_key().cmp(c2.peek_key()))?;
+
+            let (key, mut weight) = next_key_weight(&mut consumers[index]);
+
+            for consumer in consumers[index + 1..].iter_mut() {
+                if consumer.key_valid() && consumer.peek_key() == &key {
+                    weight.add_assign_by_ref(&next_key_weight(consumer).1);
+                }
+            }
+
+            if !weight.is_zero() {
+                return Some((key, weight));
+            }
+        })
+    }
+}
+
+/// Take the next key and its total weight from a Z-set consumer.
+fn next_key_weight<K, R, C>(consumer: &mut C) -> (K, R)
+where
+    R: HasZero + AddAssignByRef,
+    C: Consumer<K, (), R, ()>,
+{
+    let (key, mut values) = consumer.next_key();
+
+    let mut weight = R::zero();
+    while values.value_valid() {
+        let ((), w, ()) = values.next_value();
+        weight.add_assign_by_ref(&w);
+    }
+
+    (key, weight)
+}
+
 /// Sink operator that stores the contents of its input stream in
 /// an `OutputHandle`.
 struct Output<T> {
@@ -344,7 +415,46 @@ where
 
 #[cfg(test)]
 mod test {
-    use crate::{trace::Batch, OrdZSet, Runtime};
+    use crate::{
+        trace::{cursor::Cursor, Batch, BatchReader},
+        OrdZSet, Runtime,
+    };
+
+    #[test]
+    fn test_output_handle_drain() {
+        let (mut dbsp, (input, output, drain_output)) = Runtime::init_circuit(4, |circuit| {
+            let (zset, zset_handle) = circuit.add_input_zset::<u64, isize>();
+
+            Ok((zset_handle, zset.output(), zset.output()))
+        })
+        .unwrap();
+
+        // Duplicate keys and weights that cancel out across workers.
+        let inputs = vec![
+            (0..100).map(|k| (k % 30, 1)).collect::<Vec<_>>(),
+            vec![(1, 1), (1, -1), (2, 1), (3, 2), (2, -1), (4, 1)],
+            vec![],
+        ];
+
+        for mut input_vec in inputs {
+            input.append(&mut input_vec);
+            dbsp.step().unwrap();
+
+            let expected = output.consolidate();
+            let mut cursor = expected.cursor();
+            let mut expected_tuples = Vec::new();
+            while cursor.key_valid() {
+                expected_tuples.push((*cursor.key(), cursor.weight()));
+                cursor.step_key();
+            }
+
+            let drained = drain_output.drain().collect::<Vec<_>>();
+            assert_eq!(drained, expected_tuples);
+            assert_eq!(drain_output.drain().count(), 0);
+        }
+
+        dbsp.kill().unwrap();
+    }
 
     #[test]
     fn test_output_handle() {
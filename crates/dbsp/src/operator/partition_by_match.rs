@@ -0,0 +1,283 @@
+//! Operators that split a stream into rows whose keys match another stream
+//! and rows whose keys don't.
+
+use crate::{
+    algebra::{IndexedZSet, ZRingValue},
+    circuit::{
+        metadata::OperatorLocation,
+        operator_traits::{BinaryOperator, Operator},
+        Circuit, Scope, Stream, WithClock,
+    },
+    operator::FilterMap,
+    trace::{cursor::Cursor, BatchReader, Builder},
+    DBData, DBTimestamp, DBWeight, OrdIndexedZSet,
+};
+use std::{borrow::Cow, cell::RefCell, iter::once, marker::PhantomData, panic::Location, rc::Rc};
+
+impl<C, I1> Stream<C, I1>
+where
+    C: Circuit,
+    I1: IndexedZSet + Send,
+{
+    /// Split each batch of `self` into rows whose keys occur in the
+    /// corresponding batch of `other` and rows whose keys don't.
+    ///
+    /// Returns a pair of streams.  The first contains the rows of `self`
+    /// whose keys occur in `other` (the semi-join of the two batches), the
+    /// second contains the remaining rows (the anti-join).  Each row of
+    /// `self` appears in exactly one of the outputs, with its original
+    /// weight.  Both outputs are computed in a single merge pass over the
+    /// two batches.
+    ///
+    /// This is the non-incremental version of
+    /// [`partition_by_match`](`Self::partition_by_match`).
+    #[track_caller]
+    pub fn stream_partition_by_match<I2>(
+        &self,
+        other: &Stream<C, I2>,
+    ) -> (Stream<C, I1>, Stream<C, I1>)
+    where
+        I2: IndexedZSet<Key = I1::Key> + Send,
+    {
+        let unmatched = Rc::new(RefCell::new(None));
+
+        let matched = self
+            .circuit()
+            .add_binary_operator(
+                PartitionByMatch::new(unmatched.clone(), Location::caller()),
+                &self.shard(),
+                &other.shard(),
+            )
+            .mark_sharded();
+
+        // The operator stashes the unmatched rows in a cell shared with an
+        // operator that depends on `matched`, and therefore runs after it in
+        // every step, so neither output is cloned.
+        let unmatched = matched
+            .apply_named("PartitionByMatchUnmatched", move |_| {
+                unmatched
+                    .borrow_mut()
+                    .take()
+                    .expect("PartitionByMatch must run before its second output")
+            })
+            .mark_sharded();
+
+        (matched, unmatched)
+    }
+}
+
+impl<C, K, V, R> Stream<C, OrdIndexedZSet<K, V, R>>
+where
+    C: Circuit,
+    <C as WithClock>::Time: DBTimestamp,
+    K: DBData,
+    V: DBData,
+    R: DBWeight + ZRingValue,
+{
+    /// Incrementally split `self` into rows whose keys are present in `other`
+    /// and rows whose keys aren't.
+    ///
+    /// Returns a pair of streams of changes.  The first stream contains the
+    /// rows of `self` whose keys occur in `other` with a positive weight,
+    /// like a semi-join.  The second stream contains the remaining rows of
+    /// `self`, like [`antijoin`](`Self::antijoin`).  Each row of `self`
+    /// appears in exactly one of the outputs at any time: when a key is
+    /// inserted into or removed from `other`, its rows move from one output
+    /// to the other.
+    ///
+    /// Both outputs are derived from a single semi-join, the second one by
+    /// subtracting the first one from `self`, so this is cheaper than
+    /// computing the semi-join and the anti-join separately.
+    #[track_caller]
+    pub fn partition_by_match<V2>(
+        &self,
+        other: &Stream<C, OrdIndexedZSet<K, V2, R>>,
+    ) -> (Self, Self)
+    where
+        V2: DBData,
+    {
+        let left = self.shard();
+        // Only the keys of `other` matter, so that each row of `self` is
+        // matched at most once regardless of the number of values of its key
+        // in `other`.
+        let right = other.map_index(|(k, _)| (k.clone(), ())).distinct().shard();
+
+        let matched = left
+            .join_generic(&right, |k, v1, _v2| once((k.clone(), v1.clone())))
+            .mark_sharded();
+        let unmatched = left.minus(&matched).mark_sharded();
+
+        (matched, unmatched)
+    }
+}
+
+/// Operator that splits its first input into rows whose keys occur in its
+/// second input and rows whose keys don't.
+///
+/// Outputs the matching rows and stores the remaining rows in `unmatched`.
+///
+/// See [`Stream::stream_partition_by_match`].
+pub struct PartitionByMatch<I1, I2> {
+    unmatched: Rc<RefCell<Option<I1>>>,
+    location: &'static Location<'static>,
+    _types: PhantomData<I2>,
+}
+
+impl<I1, I2> PartitionByMatch<I1, I2> {
+    pub fn new(unmatched: Rc<RefCell<Option<I1>>>, location: &'static Location<'static>) -> Self {
+        Self {
+            unmatched,
+            location,
+            _types: PhantomData,
+        }
+    }
+}
+
+impl<I1, I2> Operator for PartitionByMatch<I1, I2>
+where
+    I1: 'static,
+    I2: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("PartitionByMatch")
+    }
+
+    fn location(&self) -> OperatorLocation {
+        Some(self.location)
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<I1, I2> BinaryOperator<I1, I2, I1> for PartitionByMatch<I1, I2>
+where
+    I1: IndexedZSet,
+    I2: BatchReader<Key = I1::Key, Time = ()>,
+{
+    fn eval(&mut self, left: &I1, right: &I2) -> I1 {
+        let mut left_cursor = left.cursor();
+        let mut right_cursor = right.cursor();
+
+        let mut matched = I1::Builder::new_builder(());
+        let mut unmatched = I1::Builder::new_builder(());
+
+        while left_cursor.key_valid() {
+            right_cursor.seek_key(left_cursor.key());
+            let builder = if right_cursor.key_valid() && right_cursor.key() == left_cursor.key() {
+                &mut matched
+            } else {
+                &mut unmatched
+            };
+
+            while left_cursor.val_valid() {
+                builder.push((
+                    I1::item_from(left_cursor.key().clone(), left_cursor.val().clone()),
+                    left_cursor.weight(),
+                ));
+                left_cursor.step_val();
+            }
+            left_cursor.step_key();
+        }
+
+        *self.unmatched.borrow_mut() = Some(unmatched.done());
+        matched.done()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{indexed_zset, trace::Batch, OrdIndexedZSet, RootCircuit};
+
+    #[test]
+    fn stream_partition_by_match_test() {
+        let (circuit, (left_handle, right_handle, matched, unmatched)) =
+            RootCircuit::build(move |circuit| {
+                let (left, left_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+                let (right, right_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+
+                let (matched, unmatched) = left.stream_partition_by_match(&right);
+
+                Ok((
+                    left_handle,
+                    right_handle,
+                    matched.output(),
+                    unmatched.output(),
+                ))
+            })
+            .unwrap();
+
+        left_handle.append(&mut vec![
+            (1, (10, 1)),
+            (1, (11, 1)),
+            (2, (20, 1)),
+            (3, (30, 2)),
+        ]);
+        right_handle.append(&mut vec![(1, (100, 1)), (3, (300, 1)), (4, (400, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            matched.consolidate(),
+            indexed_zset! {1 => {10 => 1, 11 => 1}, 3 => {30 => 2}}
+        );
+        assert_eq!(unmatched.consolidate(), indexed_zset! {2 => {20 => 1}});
+
+        // The operator only looks at the current batches.
+        left_handle.append(&mut vec![(1, (12, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(matched.consolidate(), OrdIndexedZSet::empty(()));
+        assert_eq!(unmatched.consolidate(), indexed_zset! {1 => {12 => 1}});
+    }
+
+    #[test]
+    fn partition_by_match_test() {
+        let (circuit, (left_handle, right_handle, matched, unmatched)) =
+            RootCircuit::build(move |circuit| {
+                let (left, left_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+                let (right, right_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+
+                let (matched, unmatched) = left.partition_by_match(&right);
+
+                Ok((
+                    left_handle,
+                    right_handle,
+                    matched.integrate().output(),
+                    unmatched.integrate().output(),
+                ))
+            })
+            .unwrap();
+
+        left_handle.append(&mut vec![(1, (10, 1)), (2, (20, 1)), (3, (30, 1))]);
+        right_handle.append(&mut vec![(1, (100, 1)), (4, (400, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(matched.consolidate(), indexed_zset! {1 => {10 => 1}});
+        assert_eq!(
+            unmatched.consolidate(),
+            indexed_zset! {2 => {20 => 1}, 3 => {30 => 1}}
+        );
+
+        // A new key on the right moves the matching rows from the second
+        // output to the first one, including rows that arrive later, and
+        // each row is matched once even if the key has several values.
+        left_handle.append(&mut vec![(2, (21, 1))]);
+        right_handle.append(&mut vec![(2, (200, 1)), (2, (201, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            matched.consolidate(),
+            indexed_zset! {1 => {10 => 1}, 2 => {20 => 1, 21 => 1}}
+        );
+        assert_eq!(unmatched.consolidate(), indexed_zset! {3 => {30 => 1}});
+
+        // Removing the key from the right moves them back.
+        right_handle.append(&mut vec![(1, (100, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            matched.consolidate(),
+            indexed_zset! {2 => {20 => 1, 21 => 1}}
+        );
+        assert_eq!(
+            unmatched.consolidate(),
+            indexed_zset! {1 => {10 => 1}, 3 => {30 => 1}}
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! Operator that reshapes a stream of batches into batches of a fixed size.
+
+use crate::{
+    algebra::IndexedZSet,
+    circuit::{
+        metadata::OperatorLocation,
+        operator_traits::{Operator, UnaryOperator},
+        Circuit, Scope,
+    },
+    trace::{cursor::Cursor, Builder},
+    RootCircuit, Stream,
+};
+use std::{borrow::Cow, panic::Location};
+
+impl<Z> Stream<RootCircuit, Z>
+where
+    Z: IndexedZSet,
+{
+    /// Buffer updates across clock cycles and release them in batches of
+    /// `target_size` tuples.
+    ///
+    /// The operator adds each input batch to its buffer.  Whenever the buffer
+    /// contains at least `target_size` tuples, the operator outputs
+    /// `target_size` of them, in key order, and keeps the rest for the
+    /// following clock cycles, releasing at most one batch per cycle.  Small
+    /// input batches are thus combined into larger ones, and large batches
+    /// are split and released over several cycles.  This normalizes the
+    /// size of the batches that reach sinks with a preferred batch size,
+    /// e.g., output encoders, regardless of the size of upstream updates.
+    ///
+    /// Updates to the same tuple that arrive at different clock cycles are
+    /// consolidated in the buffer, so updates that cancel out before being
+    /// released are never output.  Fewer than `target_size` buffered tuples
+    /// are held until more updates arrive.  Each worker buffers the updates
+    /// it receives independently.
+    ///
+    /// The circuit does not evaluate operators at teardown, so updates still
+    /// buffered when the circuit is destroyed are lost.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `target_size` is 0.
+    #[track_caller]
+    pub fn rebatch(&self, target_size: usize) -> Self {
+        assert_ne!(target_size, 0);
+
+        self.circuit()
+            .add_unary_operator(Rebatch::new(target_size, Location::caller()), self)
+    }
+}
+
+/// Operator that releases its input in batches of a fixed size.
+///
+/// See [`Stream::rebatch`].
+pub struct Rebatch<Z> {
+    target_size: usize,
+    // Updates that haven't been released yet.
+    buffer: Option<Z>,
+    location: &'static Location<'static>,
+}
+
+impl<Z> Rebatch<Z> {
+    pub fn new(target_size: usize, location: &'static Location<'static>) -> Self {
+        Self {
+            target_size,
+            buffer: None,
+            location,
+        }
+    }
+}
+
+impl<Z> Operator for Rebatch<Z>
+where
+    Z: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("Rebatch")
+    }
+
+    fn location(&self) -> OperatorLocation {
+        Some(self.location)
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        self.buffer.is_none()
+    }
+}
+
+impl<Z> UnaryOperator<Z, Z> for Rebatch<Z>
+where
+    Z: IndexedZSet,
+{
+    fn eval(&mut self, delta: &Z) -> Z {
+        let buffer = match self.buffer.take() {
+            Some(buffer) => buffer.merge(delta),
+            None => delta.clone(),
+        };
+
+        if buffer.len() < self.target_size {
+            if !buffer.is_empty() {
+                self.buffer = Some(buffer);
+            }
+            return Z::empty(());
+        }
+
+        if buffer.len() == self.target_size {
+            return buffer;
+        }
+
+        // Split the buffer into the first `target_size` tuples and the rest.
+        let mut output = Z::Builder::with_capacity((), self.target_size);
+        let mut rest = Z::Builder::with_capacity((), buffer.len() - self.target_size);
+        let mut count = 0;
+
+        let mut cursor = buffer.cursor();
+        while cursor.key_valid() {
+            while cursor.val_valid() {
+                let builder = if count < self.target_size {
+                    &mut output
+                } else {
+                    &mut rest
+                };
+                builder.push((
+                    Z::item_from(cursor.key().clone(), cursor.val().clone()),
+                    cursor.weight(),
+                ));
+                count += 1;
+                cursor.step_val();
+            }
+            cursor.step_key();
+        }
+
+        self.buffer = Some(rest.done());
+        output.done()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{trace::Batch, zset, OrdZSet, RootCircuit};
+
+    #[test]
+    fn rebatch_test() {
+        let (circuit, (input_handle, output)) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) = circuit.add_input_zset::<u64, isize>();
+
+            Ok((input_handle, input.rebatch(4).output()))
+        })
+        .unwrap();
+
+        // A large batch is released in chunks of 4 tuples over several steps.
+        input_handle.append(&mut (0..10).map(|k| (k, 1)).collect());
+        let mut released = Vec::new();
+        for _ in 0..2 {
+            circuit.step().unwrap();
+            let batch = output.consolidate();
+            assert_eq!(batch.len(), 4);
+            released.push(batch);
+        }
+        assert_eq!(released[0], zset! {0 => 1, 1 => 1, 2 => 1, 3 => 1});
+        assert_eq!(released[1], zset! {4 => 1, 5 => 1, 6 => 1, 7 => 1});
+
+        // The remaining 2 tuples are held until more updates arrive.
+        circuit.step().unwrap();
+        assert!(output.consolidate().is_empty());
+
+        // Small batches are combined, and updates cancel out in the buffer.
+        input_handle.append(&mut vec![(8, -1), (10, 1)]);
+        circuit.step().unwrap();
+        assert!(output.consolidate().is_empty());
+
+        input_handle.append(&mut vec![(11, 1), (12, 1)]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            zset! {9 => 1, 10 => 1, 11 => 1, 12 => 1}
+        );
+
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), OrdZSet::empty(()));
+    }
+}
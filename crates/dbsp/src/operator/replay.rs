@@ -0,0 +1,295 @@
+//! Recording and deterministic replay of circuit inputs.
+//!
+//! An [`InputRecorder`] wraps [`CollectionHandle`]s and logs every batch of
+//! updates pushed through them, tagged with the clock cycle at which it was
+//! pushed.  The resulting [`InputLog`] can be serialized, e.g., saved to a
+//! file when reproducing a bug in production, and later fed to a freshly
+//! built circuit by an [`InputReplayer`], which pushes the same updates to
+//! the same inputs and steps the circuit the same number of times.
+
+use crate::{operator::CollectionHandle, trace::unaligned_deserialize, DBData, Rkyv};
+use rkyv::{Archive, Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+/// A batch of updates pushed to one of the recorded inputs.
+#[derive(Clone, Debug, PartialEq, Eq, Archive, Serialize, Deserialize)]
+pub struct InputLogEntry {
+    /// Clock cycle at which the updates were pushed, i.e., the number of
+    /// steps completed by the circuit before the updates were pushed.
+    pub step: u64,
+    /// Index of the input in the order in which inputs were registered with
+    /// the recorder.
+    pub input: usize,
+    /// Updates serialized with `rkyv`.
+    pub data: Vec<u8>,
+}
+
+/// Log of all updates pushed to the inputs of a circuit.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Archive, Serialize, Deserialize)]
+pub struct InputLog {
+    /// Number of steps performed by the circuit.
+    pub steps: u64,
+    /// Recorded updates in the order in which they were pushed.
+    pub entries: Vec<InputLogEntry>,
+}
+
+impl InputLog {
+    /// Serialize the log with `rkyv`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        rkyv::to_bytes::<_, 1024>(self).unwrap().into_vec()
+    }
+
+    /// Deserialize a log produced by [`Self::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        unaligned_deserialize(bytes)
+    }
+}
+
+/// Records all updates pushed to a set of input handles.
+///
+/// Wrap each input handle of the circuit using [`Self::record`] and call
+/// [`Self::step`] after each step of the circuit.  Replaying the log with an
+/// [`InputReplayer`] requires registering inputs of the new circuit in the
+/// same order.
+#[derive(Clone, Default)]
+pub struct InputRecorder {
+    log: Arc<Mutex<InputLog>>,
+    num_inputs: usize,
+}
+
+impl InputRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start recording updates pushed to `handle`.
+    ///
+    /// Returns a handle that must be used instead of `handle` to push
+    /// updates to the circuit.
+    pub fn record<K, V>(
+        &mut self,
+        handle: CollectionHandle<K, V>,
+    ) -> RecordingCollectionHandle<K, V>
+    where
+        K: DBData,
+        V: DBData,
+        Vec<(K, V)>: Rkyv,
+    {
+        let input = self.num_inputs;
+        self.num_inputs += 1;
+
+        RecordingCollectionHandle {
+            handle,
+            input,
+            log: self.log.clone(),
+        }
+    }
+
+    /// Mark the end of a clock cycle.  Call this method after each step of
+    /// the circuit.
+    pub fn step(&self) {
+        self.log.lock().unwrap().steps += 1;
+    }
+
+    /// Returns a copy of the log recorded so far.
+    pub fn log(&self) -> InputLog {
+        self.log.lock().unwrap().clone()
+    }
+}
+
+/// Input handle that logs all updates pushed through it.
+///
+/// Created by [`InputRecorder::record`].
+pub struct RecordingCollectionHandle<K, V> {
+    handle: CollectionHandle<K, V>,
+    input: usize,
+    log: Arc<Mutex<InputLog>>,
+}
+
+impl<K, V> RecordingCollectionHandle<K, V>
+where
+    K: DBData,
+    V: DBData,
+    Vec<(K, V)>: Rkyv,
+{
+    fn log_updates(&self, data: Vec<u8>) {
+        let mut log = self.log.lock().unwrap();
+        let step = log.steps;
+        log.entries.push(InputLogEntry {
+            step,
+            input: self.input,
+            data,
+        });
+    }
+
+    /// Record and push a single `(key,value)` pair to the input stream.
+    ///
+    /// See [`CollectionHandle::push`].
+    pub fn push(&self, k: K, v: V) {
+        let vals = vec![(k.clone(), v.clone())];
+        self.log_updates(rkyv::to_bytes::<_, 1024>(&vals).unwrap().into_vec());
+        self.handle.push(k, v);
+    }
+
+    /// Record and push multiple `(key,value)` pairs to the input stream.
+    ///
+    /// See [`CollectionHandle::append`].
+    pub fn append(&self, vals: &mut Vec<(K, V)>) {
+        self.log_updates(rkyv::to_bytes::<_, 1024>(&*vals).unwrap().into_vec());
+        self.handle.append(vals);
+    }
+}
+
+/// Replays an [`InputLog`] into a circuit.
+///
+/// Register the inputs of the circuit using [`Self::replay`] in the same
+/// order in which they were registered with the [`InputRecorder`] that
+/// produced the log, then call [`Self::run`] to feed the updates to the
+/// circuit.
+#[allow(clippy::type_complexity)]
+pub struct InputReplayer {
+    log: InputLog,
+    inputs: Vec<Box<dyn Fn(&[u8])>>,
+}
+
+impl InputReplayer {
+    pub fn new(log: InputLog) -> Self {
+        Self {
+            log,
+            inputs: Vec::new(),
+        }
+    }
+
+    /// Register the next input of the circuit.
+    pub fn replay<K, V>(&mut self, handle: CollectionHandle<K, V>)
+    where
+        K: DBData,
+        V: DBData,
+        Vec<(K, V)>: Rkyv,
+    {
+        self.inputs.push(Box::new(move |data| {
+            let mut vals: Vec<(K, V)> = unaligned_deserialize(data);
+            handle.append(&mut vals);
+        }));
+    }
+
+    /// Feed recorded updates to the circuit.
+    ///
+    /// For each recorded clock cycle, pushes updates recorded during this
+    /// cycle to their inputs and calls `step` to evaluate the circuit.
+    /// Updates recorded after the last step are pushed to the inputs, but
+    /// not processed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the log references an input that wasn't registered with
+    /// [`Self::replay`].
+    pub fn run<F, E>(&self, mut step: F) -> Result<(), E>
+    where
+        F: FnMut() -> Result<(), E>,
+    {
+        let mut entries = self.log.entries.iter().peekable();
+
+        for current_step in 0..=self.log.steps {
+            while let Some(entry) = entries.next_if(|entry| entry.step == current_step) {
+                (self.inputs[entry.input])(&entry.data);
+            }
+
+            if current_step < self.log.steps {
+                step()?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{InputLog, InputRecorder, InputReplayer};
+    use crate::{
+        operator::FilterMap, zset, CircuitHandle, CollectionHandle, OrdZSet, OutputHandle,
+        RootCircuit, SchedulerError,
+    };
+
+    type Handles = (
+        CollectionHandle<u64, (u64, isize)>,
+        CollectionHandle<u64, (u64, isize)>,
+        OutputHandle<OrdZSet<(u64, u64, u64), isize>>,
+    );
+
+    fn join_circuit() -> (CircuitHandle, Handles) {
+        RootCircuit::build(move |circuit| {
+            let (input1, input_handle1) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+            let (input2, input_handle2) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+
+            let output = input1
+                .join(&input2, |k, v1, v2| (*k, *v1, *v2))
+                .filter(|(_, v1, v2)| v1 != v2)
+                .output();
+
+            Ok((input_handle1, input_handle2, output))
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn replay_test() {
+        // Record inputs of a multi-step circuit.
+        let (circuit, (input1, input2, output)) = join_circuit();
+        let mut recorder = InputRecorder::new();
+        let input1 = recorder.record(input1);
+        let input2 = recorder.record(input2);
+
+        let mut expected = Vec::new();
+
+        input1.append(&mut vec![(1, (10, 1)), (2, (20, 1))]);
+        input2.append(&mut vec![(1, (11, 1))]);
+        circuit.step().unwrap();
+        recorder.step();
+        expected.push(output.consolidate());
+
+        // Step without inputs.
+        circuit.step().unwrap();
+        recorder.step();
+        expected.push(output.consolidate());
+
+        input2.push(2, (21, 1));
+        input2.push(2, (20, 1));
+        input1.append(&mut vec![(1, (10, -1))]);
+        circuit.step().unwrap();
+        recorder.step();
+        expected.push(output.consolidate());
+
+        assert_eq!(
+            expected,
+            vec![
+                zset! {(1, 10, 11) => 1},
+                zset! {},
+                zset! {(1, 10, 11) => -1, (2, 20, 21) => 1},
+            ]
+        );
+
+        // Round-trip the log through its serialized representation and
+        // replay it into a fresh circuit.
+        let log = InputLog::from_bytes(&recorder.log().to_bytes());
+        assert_eq!(log, recorder.log());
+        assert_eq!(log.steps, 3);
+
+        let (circuit, (input1, input2, output)) = join_circuit();
+        let mut replayer = InputReplayer::new(log);
+        replayer.replay(input1);
+        replayer.replay(input2);
+
+        let mut actual = Vec::new();
+        replayer
+            .run(|| {
+                circuit.step()?;
+                actual.push(output.consolidate());
+                Ok::<_, SchedulerError>(())
+            })
+            .unwrap();
+
+        assert_eq!(actual, expected);
+    }
+}
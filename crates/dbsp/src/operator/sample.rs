@@ -6,11 +6,18 @@ use crate::{
         operator_traits::{BinaryOperator, Operator},
         Scope,
     },
+    operator::FilterMap,
     trace::{Batch, BatchReader, Builder, Cursor},
     Circuit, DBData, DBWeight, OrdZSet, RootCircuit, Stream,
 };
 use rand::thread_rng;
-use std::{borrow::Cow, cmp::min, marker::PhantomData};
+use std::{
+    borrow::Cow,
+    cmp::min,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
+use xxhash_rust::xxh3::Xxh3;
 
 // Prevent gigantic memory allocations by bounding sample size.
 pub const MAX_SAMPLE_SIZE: usize = 10_000_000;
@@ -192,6 +199,40 @@ where
     }
 }
 
+impl<C, K, R> Stream<C, OrdZSet<K, R>>
+where
+    C: Circuit,
+    K: DBData,
+    R: DBWeight,
+{
+    /// Deterministically sample keys of a Z-set at the specified `rate`.
+    ///
+    /// Each key is hashed with `seed`, and included in the output along with
+    /// its weight if the hash falls below `rate` (a value between `0.0` and
+    /// `1.0`).  Unlike [`Stream::stream_sample_keys`], the outcome only depends
+    /// on the key and the seed, so the operator is linear: a key is either
+    /// always included or always excluded, and retractions of included keys
+    /// are emitted just like insertions.  Rerunning the circuit with the
+    /// same seed produces the same sample.
+    #[track_caller]
+    pub fn sample(&self, rate: f64, seed: u64) -> Self {
+        let sampled = self.filter(move |key| sample_includes(key, rate, seed));
+        sampled.mark_distinct_if(self);
+        sampled
+    }
+}
+
+/// Returns `true` if `key` belongs to the sample with the given `rate` and
+/// `seed`.
+fn sample_includes<K: Hash>(key: &K, rate: f64, seed: u64) -> bool {
+    let mut hasher = Xxh3::with_seed(seed);
+    key.hash(&mut hasher);
+
+    // Map the hash to a uniformly distributed value in `[0, 1)`.
+    let x = (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64;
+    x < rate
+}
+
 struct SampleKeys<T>
 where
     T: BatchReader,
@@ -330,8 +371,8 @@ mod test {
             test_batch::{batch_to_tuples, TestBatch},
             BatchReader, Trace,
         },
-        CollectionHandle, InputHandle, OrdIndexedZSet, OrdZSet, OutputHandle, RootCircuit, Runtime,
-        UpsertHandle,
+        zset, CircuitHandle, CollectionHandle, InputHandle, OrdIndexedZSet, OrdZSet, OutputHandle,
+        RootCircuit, Runtime, UpsertHandle,
     };
     use anyhow::Result as AnyResult;
     use proptest::{collection::vec, prelude::*};
@@ -540,4 +581,73 @@ mod test {
         }
 
     }
+
+    /// Feed keys `0..n` to `Stream::sample` and return the output of the
+    /// first step along with the input handle.
+    fn sample_keys(
+        n: u64,
+        rate: f64,
+        seed: u64,
+    ) -> (
+        CircuitHandle,
+        CollectionHandle<u64, i32>,
+        OutputHandle<OrdZSet<u64, i32>>,
+        OrdZSet<u64, i32>,
+    ) {
+        let (circuit, (input, output)) = RootCircuit::build(move |circuit| {
+            let (stream, handle) = circuit.add_input_zset::<u64, i32>();
+            Ok((handle, stream.sample(rate, seed).output()))
+        })
+        .unwrap();
+
+        input.append(&mut (0..n).map(|k| (k, 1)).collect());
+        circuit.step().unwrap();
+        let sample = output.consolidate();
+
+        (circuit, input, output, sample)
+    }
+
+    #[test]
+    fn sample_test() {
+        // The size of the sample is within 5 standard deviations of its
+        // expected value (the standard deviation is 50 for 10000 keys sampled
+        // at rate 0.5 and 30 at rate 0.1).
+        for (rate, min, max) in [(0.5, 4750, 5250), (0.1, 850, 1150)] {
+            let sample = sample_keys(10000, rate, 42).3;
+            assert!(
+                (min..=max).contains(&sample.len()),
+                "sampled {} keys at rate {rate}",
+                sample.len()
+            );
+        }
+
+        let (circuit, input, output, sample) = sample_keys(100, 0.5, 42);
+
+        // Keys are sampled along with their weights.
+        let mut cursor = sample.cursor();
+        while cursor.key_valid() {
+            assert!(*cursor.key() < 100);
+            assert_eq!(cursor.weight(), 1);
+            cursor.step_key();
+        }
+
+        // The same seed produces the same sample; a different seed doesn't.
+        assert_eq!(sample_keys(100, 0.5, 42).3, sample);
+        assert_ne!(sample_keys(100, 0.5, 43).3, sample);
+
+        // Retractions of included keys are emitted; retractions of excluded
+        // keys are not.
+        let included = *sample.cursor().key();
+        let excluded = (0..100)
+            .find(|k| {
+                let mut cursor = sample.cursor();
+                cursor.seek_key(k);
+                !(cursor.key_valid() && cursor.key() == k)
+            })
+            .unwrap();
+
+        input.append(&mut vec![(included, -1), (excluded, -1)]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! { included => -1 });
+    }
 }
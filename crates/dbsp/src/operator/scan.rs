@@ -0,0 +1,214 @@
+//! Running fold over the ordered keys of an indexed Z-set.
+
+use crate::{
+    algebra::{AddAssignByRef, HasZero, ZRingValue},
+    circuit::{
+        metadata::OperatorLocation,
+        operator_traits::{BinaryOperator, Operator},
+        Circuit, Scope,
+    },
+    trace::{cursor::Cursor, BatchReader},
+    DBData, OrdIndexedZSet, RootCircuit, Stream,
+};
+use std::{borrow::Cow, marker::PhantomData, panic::Location};
+
+impl<K, V, R> Stream<RootCircuit, OrdIndexedZSet<K, V, R>>
+where
+    K: DBData,
+    V: DBData,
+    R: DBData + ZRingValue,
+{
+    /// Incrementally compute a running fold over the keys of the input
+    /// collection.
+    ///
+    /// Keys are visited in ascending order.  The accumulator starts with
+    /// `init` and is updated by applying `step` to every `(key, value,
+    /// weight)` tuple with non-zero weight.  The output collection contains
+    /// a `(key, acc)` pair for each key in the input collection, where `acc`
+    /// is the value of the accumulator after folding all values of this key
+    /// and all preceding keys, e.g., a cumulative sum when `step` adds up
+    /// the values.
+    ///
+    /// An update to a key changes the prefix aggregates of all keys that
+    /// follow it.  The operator retracts the old output of all such keys and
+    /// inserts the new one.
+    ///
+    /// The fold requires a global ordering of keys, so all inputs are
+    /// gathered to worker 0, which computes the entire output.  The operator
+    /// re-scans the integral of the input at every step where the input
+    /// changes.
+    #[track_caller]
+    pub fn scan<A, F>(&self, init: A, step: F) -> Stream<RootCircuit, OrdIndexedZSet<K, A, R>>
+    where
+        A: DBData,
+        F: Fn(&mut A, &K, &V, R) + 'static,
+    {
+        let stream = self.gather(0);
+        let trace = stream.integrate_trace();
+
+        self.circuit()
+            .add_binary_operator(Scan::new(init, step, Location::caller()), &stream, &trace)
+            .upsert::<OrdIndexedZSet<K, A, R>>()
+    }
+}
+
+/// Operator that computes upserts for all keys whose prefix aggregate may
+/// have changed.
+///
+/// Takes the current change to the input collection and its integral,
+/// including the current change.  Folds the integral from the beginning and
+/// emits the new accumulator value for each key starting from the smallest
+/// key modified by the change.  Keys removed from the collection are mapped
+/// to `None`.
+pub struct Scan<A, F, I, T> {
+    init: A,
+    step: F,
+    location: &'static Location<'static>,
+    _types: PhantomData<(I, T)>,
+}
+
+impl<A, F, I, T> Scan<A, F, I, T> {
+    pub fn new(init: A, step: F, location: &'static Location<'static>) -> Self {
+        Self {
+            init,
+            step,
+            location,
+            _types: PhantomData,
+        }
+    }
+}
+
+impl<A, F, I, T> Operator for Scan<A, F, I, T>
+where
+    A: 'static,
+    F: 'static,
+    I: 'static,
+    T: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("Scan")
+    }
+
+    fn location(&self) -> OperatorLocation {
+        Some(self.location)
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<A, F, I, T> BinaryOperator<I, T, Vec<(I::Key, Option<A>)>> for Scan<A, F, I, T>
+where
+    A: DBData,
+    F: Fn(&mut A, &I::Key, &I::Val, I::R) + 'static,
+    I: BatchReader<Time = ()>,
+    T: BatchReader<Key = I::Key, Val = I::Val, Time = (), R = I::R>,
+{
+    fn eval(&mut self, delta: &I, trace: &T) -> Vec<(I::Key, Option<A>)> {
+        let mut delta_cursor = delta.cursor();
+        if !delta_cursor.key_valid() {
+            return Vec::new();
+        }
+
+        // Keys before the first modified key retain their old output.
+        let first_modified = delta_cursor.key().clone();
+
+        let mut upserts = Vec::new();
+        let mut acc = self.init.clone();
+        let mut trace_cursor = trace.cursor();
+
+        while trace_cursor.key_valid() {
+            let key = trace_cursor.key().clone();
+
+            // Keys that only occur in `delta` were removed from the collection.
+            while delta_cursor.key_valid() && delta_cursor.key() < &key {
+                upserts.push((delta_cursor.key().clone(), None));
+                delta_cursor.step_key();
+            }
+            if delta_cursor.key_valid() && delta_cursor.key() == &key {
+                delta_cursor.step_key();
+            }
+
+            let mut non_empty = false;
+            while trace_cursor.val_valid() {
+                let mut weight = I::R::zero();
+                trace_cursor.map_times(|_, w| weight.add_assign_by_ref(w));
+
+                if !weight.is_zero() {
+                    non_empty = true;
+                    (self.step)(&mut acc, &key, trace_cursor.val(), weight);
+                }
+
+                trace_cursor.step_val();
+            }
+
+            if key >= first_modified {
+                upserts.push((key, non_empty.then(|| acc.clone())));
+            }
+
+            trace_cursor.step_key();
+        }
+
+        while delta_cursor.key_valid() {
+            upserts.push((delta_cursor.key().clone(), None));
+            delta_cursor.step_key();
+        }
+
+        upserts
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{indexed_zset, RootCircuit};
+
+    #[test]
+    fn scan_test() {
+        let (circuit, (input_handle, output)) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) = circuit.add_input_indexed_zset::<u64, i64, isize>();
+
+            let output = input
+                .scan(0i64, |acc, _key, val, weight| *acc += *val * weight as i64)
+                .output();
+
+            Ok((input_handle, output))
+        })
+        .unwrap();
+
+        input_handle.append(&mut vec![(1, (10, 1)), (3, (30, 1)), (5, (50, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {1 => {10 => 1}, 3 => {40 => 1}, 5 => {90 => 1}}
+        );
+
+        // Inserting a key in the middle shifts all subsequent prefix sums.
+        input_handle.append(&mut vec![(2, (20, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {
+                2 => {30 => 1},
+                3 => {40 => -1, 60 => 1},
+                5 => {90 => -1, 110 => 1}
+            }
+        );
+
+        // Removing a key retracts its output.
+        input_handle.append(&mut vec![(3, (30, -1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {3 => {60 => -1}, 5 => {110 => -1, 80 => 1}}
+        );
+
+        // Updates to the last key don't affect preceding keys.
+        input_handle.append(&mut vec![(5, (5, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {5 => {80 => -1, 85 => 1}}
+        );
+    }
+}
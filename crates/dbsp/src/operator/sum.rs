@@ -32,6 +32,16 @@ where
         self.circuit()
             .add_nary_operator(Sum::new(), once(self).chain(streams))
     }
+
+    /// Compute the `UNION ALL` of `self` and all streams in `streams`.
+    ///
+    /// Records that occur in multiple inputs are preserved, i.e., their
+    /// weights are added up.  This is the same as [`Self::sum`]; all inputs
+    /// are combined by a single [`Sum`] operator, which is cheaper than
+    /// chaining [`plus`](`Self::plus`) calls for wide unions.
+    pub fn union_all(&self, streams: &[&Self]) -> Stream<C, D> {
+        self.sum(streams.iter().copied())
+    }
 }
 
 /// Operator that computes the sum of values across all its input streams at
@@ -138,6 +148,33 @@ mod test {
         zset, Circuit, RootCircuit,
     };
 
+    #[test]
+    fn union_all_test() {
+        let (circuit, (input_handles, output)) = RootCircuit::build(move |circuit| {
+            let (streams, input_handles): (Vec<_>, Vec<_>) = (0..4)
+                .map(|_| circuit.add_input_zset::<u64, isize>())
+                .unzip();
+
+            let output = streams[0]
+                .union_all(&[&streams[1], &streams[2], &streams[3]])
+                .output();
+
+            Ok((input_handles, output))
+        })
+        .unwrap();
+
+        input_handles[0].append(&mut vec![(1, 1), (2, 1)]);
+        input_handles[1].append(&mut vec![(1, 1), (3, 2)]);
+        input_handles[2].append(&mut vec![(1, 1), (2, -1)]);
+        input_handles[3].append(&mut vec![(1, 2), (4, 1)]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! { 1 => 5, 3 => 2, 4 => 1 });
+
+        input_handles[2].append(&mut vec![(5, 1)]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), zset! { 5 => 1 });
+    }
+
     #[test]
     fn zset_sum() {
         let build_circuit = |circuit: &RootCircuit| {
@@ -0,0 +1,236 @@
+//! Operators that output the longest prefix of a Z-set whose keys satisfy a
+//! predicate.
+
+use crate::{
+    algebra::{HasZero, IndexedZSet},
+    circuit::{
+        metadata::OperatorLocation,
+        operator_traits::{Operator, UnaryOperator},
+        Circuit, Scope, Stream,
+    },
+    trace::{cursor::Cursor, Batch, BatchReader, Builder, Spine},
+    RootCircuit,
+};
+use size_of::SizeOf;
+use std::{borrow::Cow, marker::PhantomData, panic::Location};
+
+impl<C, B> Stream<C, B>
+where
+    C: Circuit,
+    B: IndexedZSet + Send,
+{
+    /// Output the records of each input batch in key order, up to the first
+    /// key that doesn't satisfy `pred`.
+    ///
+    /// Unlike [`filter`](`crate::operator::FilterMap::filter`), this operator
+    /// stops scanning the batch at the first key for which `pred` returns
+    /// `false`, so `pred` is not evaluated for the remaining keys.  For
+    /// predicates that are monotonic in the key, e.g., `k < 100`, the output
+    /// is the same as for `filter`, but is computed in time proportional to
+    /// the size of the output.
+    ///
+    /// The prefix is computed over the union of batches across all workers,
+    /// which are gathered in worker 0.  The output stream in all other
+    /// workers contains empty batches.
+    #[track_caller]
+    pub fn stream_take_while_key<F>(&self, pred: F) -> Self
+    where
+        F: Fn(&B::Key) -> bool + 'static,
+    {
+        self.circuit()
+            .add_unary_operator(TakeWhileKey::new(pred, Location::caller()), &self.gather(0))
+    }
+}
+
+impl<B> Stream<RootCircuit, B>
+where
+    B: IndexedZSet + Send,
+    Spine<B>: SizeOf,
+{
+    /// Incremental version of
+    /// [`stream_take_while_key`](`Self::stream_take_while_key`).
+    ///
+    /// Maintains the longest prefix of the integral of the input stream whose
+    /// keys satisfy `pred`, and outputs changes to this prefix.  A change to
+    /// a key that ends the prefix moves the boundary: inserting a key that
+    /// doesn't satisfy `pred` before the end of the current prefix retracts
+    /// all records that follow it, and deleting it re-emits them.
+    ///
+    /// The prefix is computed in worker 0.  The output stream in all other
+    /// workers contains empty batches.
+    #[track_caller]
+    pub fn take_while_key<F>(&self, pred: F) -> Self
+    where
+        F: Fn(&B::Key) -> bool + 'static,
+    {
+        self.circuit()
+            .add_unary_operator(
+                TakeWhileKey::new(pred, Location::caller()),
+                &self.gather(0).integrate_trace(),
+            )
+            .differentiate()
+    }
+}
+
+/// Operator that outputs the longest prefix of its input whose keys satisfy
+/// a predicate.
+///
+/// The input can be a batch or a trace.  Keys whose values all have zero
+/// weights are ignored.
+///
+/// See [`Stream::stream_take_while_key`] and [`Stream::take_while_key`].
+pub struct TakeWhileKey<T, O, F> {
+    pred: F,
+    location: &'static Location<'static>,
+    _types: PhantomData<(T, O)>,
+}
+
+impl<T, O, F> TakeWhileKey<T, O, F> {
+    pub fn new(pred: F, location: &'static Location<'static>) -> Self {
+        Self {
+            pred,
+            location,
+            _types: PhantomData,
+        }
+    }
+}
+
+impl<T, O, F> Operator for TakeWhileKey<T, O, F>
+where
+    T: 'static,
+    O: 'static,
+    F: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("TakeWhileKey")
+    }
+
+    fn location(&self) -> OperatorLocation {
+        Some(self.location)
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<T, O, F> UnaryOperator<T, O> for TakeWhileKey<T, O, F>
+where
+    T: BatchReader<Time = ()>,
+    O: Batch<Key = T::Key, Val = T::Val, Time = (), R = T::R>,
+    F: Fn(&T::Key) -> bool + 'static,
+{
+    fn eval(&mut self, input: &T) -> O {
+        let mut cursor = input.cursor();
+        let mut builder = O::Builder::new_builder(());
+
+        while cursor.key_valid() {
+            let mut tuples = Vec::new();
+            while cursor.val_valid() {
+                let weight = cursor.weight();
+                if !weight.is_zero() {
+                    tuples.push((cursor.val().clone(), weight));
+                }
+                cursor.step_val();
+            }
+
+            // Keys with no non-zero weights aren't part of the collection and
+            // don't end the prefix.
+            if !tuples.is_empty() {
+                if !(self.pred)(cursor.key()) {
+                    break;
+                }
+
+                for (val, weight) in tuples {
+                    builder.push((O::item_from(cursor.key().clone(), val), weight));
+                }
+            }
+            cursor.step_key();
+        }
+
+        builder.done()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{trace::Batch, zset, OrdZSet, RootCircuit};
+    use std::{cell::Cell, rc::Rc};
+
+    #[test]
+    fn stream_take_while_key_test() {
+        let calls = Rc::new(Cell::new(0));
+        let calls_clone = calls.clone();
+
+        let (circuit, (input_handle, output)) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) = circuit.add_input_zset::<u64, isize>();
+
+            let output = input
+                .stream_take_while_key(move |k| {
+                    calls_clone.set(calls_clone.get() + 1);
+                    *k < 100
+                })
+                .output();
+
+            Ok((input_handle, output))
+        })
+        .unwrap();
+
+        input_handle.append(&mut (0..1000).map(|k| (k, 1)).collect());
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            OrdZSet::from_keys((), (0..100).map(|k| (k, 1)).collect())
+        );
+        // The operator stops at the first key that fails the predicate.
+        assert_eq!(calls.get(), 101);
+    }
+
+    #[test]
+    fn take_while_key_test() {
+        let (circuit, (input_handle, below_100, until_multiple_of_10)) =
+            RootCircuit::build(move |circuit| {
+                let (input, input_handle) = circuit.add_input_zset::<u64, isize>();
+
+                let below_100 = input.take_while_key(|k| *k < 100).output();
+                let until_multiple_of_10 = input.take_while_key(|k| k % 10 != 0).output();
+
+                Ok((input_handle, below_100, until_multiple_of_10))
+            })
+            .unwrap();
+
+        input_handle.append(&mut vec![(1, 1), (2, 1), (11, 1), (12, 1), (151, 1)]);
+        circuit.step().unwrap();
+        assert_eq!(
+            below_100.consolidate(),
+            zset! {1 => 1, 2 => 1, 11 => 1, 12 => 1}
+        );
+        assert_eq!(
+            until_multiple_of_10.consolidate(),
+            zset! {1 => 1, 2 => 1, 11 => 1, 12 => 1, 151 => 1}
+        );
+
+        // Inserting a key that fails the predicate inside the prefix moves
+        // the boundary and retracts the records that follow it.
+        input_handle.append(&mut vec![(10, 1), (99, 1)]);
+        circuit.step().unwrap();
+        assert_eq!(below_100.consolidate(), zset! {10 => 1, 99 => 1});
+        assert_eq!(
+            until_multiple_of_10.consolidate(),
+            zset! {11 => -1, 12 => -1, 151 => -1}
+        );
+
+        // Deleting it re-emits them.
+        input_handle.append(&mut vec![(10, -1)]);
+        circuit.step().unwrap();
+        assert_eq!(below_100.consolidate(), zset! {10 => -1});
+        assert_eq!(
+            until_multiple_of_10.consolidate(),
+            zset! {11 => 1, 12 => 1, 99 => 1, 151 => 1}
+        );
+
+        circuit.step().unwrap();
+        assert_eq!(below_100.consolidate(), OrdZSet::empty(()));
+        assert_eq!(until_multiple_of_10.consolidate(), OrdZSet::empty(()));
+    }
+}
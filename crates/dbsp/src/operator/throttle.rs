@@ -0,0 +1,248 @@
+//! Operator that limits the rate at which a stream releases updates.
+
+use crate::{
+    algebra::IndexedZSet,
+    circuit::{
+        metadata::OperatorLocation,
+        operator_traits::{Operator, UnaryOperator},
+        Circuit, Scope,
+    },
+    trace::{cursor::Cursor, Builder},
+    RootCircuit, Stream,
+};
+use std::{borrow::Cow, panic::Location, time::Instant};
+
+impl<Z> Stream<RootCircuit, Z>
+where
+    Z: IndexedZSet + Send,
+{
+    /// Buffer updates across clock cycles and release them at a rate of at
+    /// most `records_per_second` tuples per second.
+    ///
+    /// The operator adds each input batch to its buffer and releases
+    /// buffered tuples, in key order, using a token bucket that refills at
+    /// `records_per_second` tokens per second and holds at most one second
+    /// worth of tokens.  The bucket is empty when the operator is created.
+    /// Tuples that exceed the available tokens stay in the buffer until
+    /// later clock cycles.  This is useful for sinks with a hard quota, such
+    /// as rate-limited APIs.  Unlike [`coalesce`](`Self::coalesce`) and
+    /// [`rebatch`](`Self::rebatch`), which control the size of output
+    /// batches, this operator controls the rate of the output.  See
+    /// [`Generator::rate_limited`](`crate::operator::Generator::rate_limited`)
+    /// for limiting the rate of a source instead.
+    ///
+    /// The operator never blocks the circuit waiting for tokens: if none are
+    /// available, the step outputs an empty batch.  Updates to the same
+    /// tuple that arrive at different clock cycles are consolidated in the
+    /// buffer.
+    ///
+    /// In a multi-worker circuit, the operator gathers the input of all
+    /// workers in worker 0, which buffers and releases all updates, so the
+    /// limit applies to the circuit as a whole.  The output stream of the
+    /// other workers is empty.
+    ///
+    /// The circuit does not evaluate operators at teardown, so updates still
+    /// buffered when the circuit is destroyed are lost.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `records_per_second` is 0.
+    #[track_caller]
+    pub fn throttle(&self, records_per_second: u64) -> Self {
+        assert_ne!(records_per_second, 0);
+
+        self.circuit().add_unary_operator(
+            Throttle::new(records_per_second, Location::caller()),
+            &self.gather(0),
+        )
+    }
+}
+
+/// Operator that releases its input at a bounded rate.
+///
+/// See [`Stream::throttle`].
+pub struct Throttle<Z> {
+    records_per_second: u64,
+    tokens: f64,
+    last_refill: Instant,
+    // Updates that haven't been released yet.
+    buffer: Option<Z>,
+    location: &'static Location<'static>,
+}
+
+impl<Z> Throttle<Z> {
+    pub fn new(records_per_second: u64, location: &'static Location<'static>) -> Self {
+        Self {
+            records_per_second,
+            tokens: 0.0,
+            last_refill: Instant::now(),
+            buffer: None,
+            location,
+        }
+    }
+
+    /// Add tokens accumulated since the last refill to the bucket.
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.last_refill = now;
+
+        let capacity = self.records_per_second as f64;
+        self.tokens = (self.tokens + elapsed * capacity).min(capacity);
+    }
+}
+
+impl<Z> Operator for Throttle<Z>
+where
+    Z: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("Throttle")
+    }
+
+    fn location(&self) -> OperatorLocation {
+        Some(self.location)
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        self.buffer.is_none()
+    }
+}
+
+impl<Z> UnaryOperator<Z, Z> for Throttle<Z>
+where
+    Z: IndexedZSet,
+{
+    fn eval(&mut self, delta: &Z) -> Z {
+        let buffer = match self.buffer.take() {
+            Some(buffer) => buffer.merge(delta),
+            None => delta.clone(),
+        };
+
+        self.refill();
+        let available = self.tokens as usize;
+
+        if buffer.len() <= available {
+            self.tokens -= buffer.len() as f64;
+            return buffer;
+        }
+
+        if available == 0 {
+            self.buffer = Some(buffer);
+            return Z::empty(());
+        }
+
+        // Split the buffer into the first `available` tuples and the rest.
+        let mut output = Z::Builder::with_capacity((), available);
+        let mut rest = Z::Builder::with_capacity((), buffer.len() - available);
+        let mut count = 0;
+
+        let mut cursor = buffer.cursor();
+        while cursor.key_valid() {
+            while cursor.val_valid() {
+                let builder = if count < available {
+                    &mut output
+                } else {
+                    &mut rest
+                };
+                builder.push((
+                    Z::item_from(cursor.key().clone(), cursor.val().clone()),
+                    cursor.weight(),
+                ));
+                count += 1;
+                cursor.step_val();
+            }
+            cursor.step_key();
+        }
+
+        self.tokens -= available as f64;
+        self.buffer = Some(rest.done());
+        output.done()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{trace::Batch, OrdZSet, RootCircuit, Runtime};
+    use std::{
+        thread::sleep,
+        time::{Duration, Instant},
+    };
+
+    #[test]
+    fn throttle_test() {
+        const RATE: u64 = 1000;
+
+        let start = Instant::now();
+        let (circuit, (input_handle, output)) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) = circuit.add_input_zset::<u64, isize>();
+
+            Ok((input_handle, input.throttle(RATE).output()))
+        })
+        .unwrap();
+
+        input_handle.append(&mut (0..10_000).map(|k| (k, 1)).collect());
+
+        let mut released = 0;
+        for _ in 0..50 {
+            circuit.step().unwrap();
+
+            // Tuples are released in key order.
+            let batch = output.consolidate();
+            let expected = (released..released + batch.len() as u64)
+                .map(|k| (k, 1))
+                .collect();
+            assert_eq!(batch, OrdZSet::from_keys((), expected));
+            released += batch.len() as u64;
+
+            let elapsed = start.elapsed().as_secs_f64();
+            assert!(
+                released as f64 <= RATE as f64 * elapsed,
+                "{released} records in {elapsed}s"
+            );
+            sleep(Duration::from_millis(5));
+        }
+        assert!(released > 0);
+        assert!(released < 10_000);
+    }
+
+    #[test]
+    fn throttle_multiworker_test() {
+        const RATE: u64 = 1000;
+
+        let start = Instant::now();
+        let (mut dbsp, (input_handle, output)) = Runtime::init_circuit(4, move |circuit| {
+            let (input, input_handle) = circuit.add_input_zset::<u64, isize>();
+
+            Ok((input_handle, input.throttle(RATE).output()))
+        })
+        .unwrap();
+
+        input_handle.append(&mut (0..10_000).map(|k| (k, 1)).collect());
+
+        // The limit applies to all workers together, and tuples are released
+        // in key order across workers.
+        let mut released = 0;
+        for _ in 0..50 {
+            dbsp.step().unwrap();
+
+            let batch = output.consolidate();
+            let expected = (released..released + batch.len() as u64)
+                .map(|k| (k, 1))
+                .collect();
+            assert_eq!(batch, OrdZSet::from_keys((), expected));
+            released += batch.len() as u64;
+
+            let elapsed = start.elapsed().as_secs_f64();
+            assert!(
+                released as f64 <= RATE as f64 * elapsed,
+                "{released} records in {elapsed}s"
+            );
+            sleep(Duration::from_millis(5));
+        }
+        assert!(released > 0);
+        assert!(released < 10_000);
+
+        dbsp.kill().unwrap();
+    }
+}
@@ -1,7 +1,11 @@
 use crate::{
+    algebra::{HasZero, IndexedZSet, ZRingValue},
     circuit::{
-        metadata::{MetaItem, OperatorMeta},
-        operator_traits::{BinaryOperator, Operator, StrictOperator, StrictUnaryOperator},
+        metadata::{MetaItem, OperatorLocation, OperatorMeta},
+        operator_traits::{
+            BinaryOperator, Operator, StrictOperator, StrictUnaryOperator, UnaryOperator,
+        },
+        snapshot::{trace_from_bytes, trace_to_bytes},
         Circuit, ExportId, ExportStream, FeedbackConnector, GlobalNodeId, OwnershipPreference,
         Scope, Stream, WithClock,
     },
@@ -10,10 +14,19 @@ use crate::{
     DBData, Timestamp,
 };
 use size_of::SizeOf;
-use std::{borrow::Cow, cell::RefCell, marker::PhantomData, ops::DerefMut, rc::Rc};
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    marker::PhantomData,
+    ops::DerefMut,
+    panic::Location,
+    rc::Rc,
+};
 
 circuit_cache_key!(TraceId<B, D, K, V>(GlobalNodeId => (Stream<B, D>, TraceBounds<K, V>)));
 circuit_cache_key!(DelayedTraceId<B, D>(GlobalNodeId => Stream<B, D>));
+circuit_cache_key!(TraceBoundsId<K, V>(GlobalNodeId => TraceBounds<K, V>));
 
 /// Lower bound on keys or values in a trace.
 ///
@@ -74,6 +87,7 @@ where
         Self(Rc::new(RefCell::new(TraceBoundsInner {
             key_bounds: Vec::new(),
             val_bounds: Vec::new(),
+            compaction: CompactionHandle::new(),
         })))
     }
 
@@ -83,6 +97,7 @@ where
         Self(Rc::new(RefCell::new(TraceBoundsInner {
             key_bounds: vec![TraceBound::new()],
             val_bounds: vec![TraceBound::new()],
+            compaction: CompactionHandle::new(),
         })))
     }
 
@@ -115,9 +130,58 @@ where
     }
 }
 
+impl<K, V> TraceBounds<K, V> {
+    pub(crate) fn compaction(&self) -> CompactionHandle {
+        self.0.borrow().compaction.clone()
+    }
+}
+
 struct TraceBoundsInner<K, V> {
     key_bounds: Vec<TraceBound<K>>,
     val_bounds: Vec<TraceBound<V>>,
+    compaction: CompactionHandle,
+}
+
+/// Handle used to request compaction of a trace.
+///
+/// See [`Stream::compact_trace`].
+#[derive(Clone, Debug, Default)]
+pub struct CompactionHandle(Rc<Cell<bool>>);
+
+impl CompactionHandle {
+    fn new() -> Self {
+        Default::default()
+    }
+
+    /// Request the trace to merge all of its batches at the end of the next
+    /// clock cycle.
+    pub fn request(&self) {
+        self.0.set(true);
+    }
+
+    /// Returns `true` if compaction has been requested, but hasn't been
+    /// performed yet.
+    pub fn is_requested(&self) -> bool {
+        self.0.get()
+    }
+
+    /// Clear the request flag, returning its previous value.
+    fn take(&self) -> bool {
+        self.0.replace(false)
+    }
+}
+
+/// Retention policy for [`Stream::integrate_trace_bounded`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceRetention {
+    /// Retain insertions received during the last `n` clock cycles.
+    Steps(usize),
+    /// Retain insertions from the most recent clock cycles whose combined
+    /// size does not exceed `n` records.
+    ///
+    /// Insertions received during the latest clock cycle that inserted any
+    /// records are always retained, even if they exceed the limit.
+    Records(usize),
 }
 
 // TODO: add infrastructure to compact the trace during slack time.
@@ -217,6 +281,10 @@ where
 
                     circuit
                         .cache_insert(DelayedTraceId::new(trace.origin_node_id().clone()), local);
+                    circuit.cache_insert(
+                        TraceBoundsId::new(trace.origin_node_id().clone()),
+                        bounds.clone(),
+                    );
                     (trace, bounds)
                 })
             },
@@ -240,6 +308,50 @@ where
         self.integrate_trace_with_bound(TraceBound::new(), TraceBound::new())
     }
 
+    /// Like [`Self::integrate_trace`], but only retains insertions within
+    /// the window defined by `retention`.
+    ///
+    /// Insertions are evicted from the trace once the clock cycle in which
+    /// they were received falls outside the window, so that the memory
+    /// footprint of the trace remains bounded for streams that never
+    /// terminate.  A retraction cancels the oldest retained insertion of the
+    /// same tuple.  Retractions of tuples whose insertions have already been
+    /// evicted are dropped, so the trace never contains negative weights:
+    /// at every clock cycle, it is equal to the sum of the insertions in the
+    /// window that haven't been retracted.
+    #[track_caller]
+    pub fn integrate_trace_bounded(&self, retention: TraceRetention) -> Stream<C, Spine<B>>
+    where
+        B: IndexedZSet,
+        B::R: ZRingValue,
+        Spine<B>: SizeOf,
+    {
+        self.retained_updates(retention).integrate_trace()
+    }
+
+    /// Changes to the trace computed by
+    /// [`integrate_trace_bounded`](`Self::integrate_trace_bounded`).
+    ///
+    /// Contains the input updates that were applied to the trace, along with
+    /// the retraction of the insertions evicted during the current clock
+    /// cycle.
+    #[track_caller]
+    pub(crate) fn retained_updates(&self, retention: TraceRetention) -> Stream<C, B>
+    where
+        B: IndexedZSet,
+        B::R: ZRingValue,
+    {
+        let input = self.try_sharded_version();
+        let updates = self
+            .circuit()
+            .add_unary_operator(RetentionWindow::new(retention, Location::caller()), &input);
+        if self.has_sharded_version() {
+            updates.mark_sharded();
+        }
+
+        updates
+    }
+
     #[track_caller]
     pub fn integrate_trace_with_bound(
         &self,
@@ -286,6 +398,10 @@ where
                     circuit
                         .cache_insert(DelayedTraceId::new(trace.origin_node_id().clone()), local);
                     circuit.cache_insert(ExportId::new(trace.origin_node_id().clone()), export);
+                    circuit.cache_insert(
+                        TraceBoundsId::new(trace.origin_node_id().clone()),
+                        bounds.clone(),
+                    );
 
                     (trace, bounds)
                 })
@@ -355,6 +471,10 @@ where
             ExportId::new(trace.origin_node_id().clone()),
             self.export_trace,
         );
+        circuit.cache_insert(
+            TraceBoundsId::new(trace.origin_node_id().clone()),
+            self.bounds,
+        );
     }
 }
 
@@ -416,6 +536,221 @@ where
             })
             .clone()
     }
+
+    /// Returns a handle that can be used to force compaction of the trace.
+    ///
+    /// Traces merge their batches incrementally as new updates arrive.
+    /// Calling [`CompactionHandle::request`] on the returned handle between
+    /// clock cycles asks the trace to merge all of its batches into a
+    /// single batch (see [`Trace::compact`]) at the end of the next clock
+    /// cycle.  This is useful for bounding the memory footprint of a trace
+    /// at a quiescent point, e.g., while the pipeline is idle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `self` is not a trace created via [`Stream::trace`],
+    /// [`Stream::integrate_trace`], or a similar method.
+    pub fn compact_trace(&self) -> CompactionHandle {
+        self.circuit()
+            .cache_get(&<TraceBoundsId<T::Key, T::Val>>::new(
+                self.origin_node_id().clone(),
+            ))
+            .expect("called `.compact_trace()` on a stream that is not a trace")
+            .compaction()
+    }
+}
+
+/// Maintains the insertions retained by
+/// [`Stream::integrate_trace_bounded`] and outputs changes to them.
+struct RetentionWindow<B> {
+    retention: TraceRetention,
+    location: &'static Location<'static>,
+    /// Current clock cycle.
+    step: usize,
+    /// Insertions that haven't been retracted or evicted yet, along with
+    /// the clock cycle in which they were received, oldest first.  Empty
+    /// batches are dropped.
+    generations: VecDeque<(usize, B)>,
+}
+
+impl<B> RetentionWindow<B> {
+    fn new(retention: TraceRetention, location: &'static Location<'static>) -> Self {
+        Self {
+            retention,
+            location,
+            step: 0,
+            generations: VecDeque::new(),
+        }
+    }
+}
+
+impl<B> RetentionWindow<B>
+where
+    B: IndexedZSet,
+    B::R: ZRingValue,
+{
+    fn expired(&self) -> bool {
+        match self.retention {
+            TraceRetention::Steps(steps) => self
+                .generations
+                .front()
+                .map_or(false, |(step, _)| step + steps <= self.step),
+            TraceRetention::Records(records) => {
+                self.generations.len() > 1
+                    && self
+                        .generations
+                        .iter()
+                        .map(|(_, batch)| batch.len())
+                        .sum::<usize>()
+                        > records
+            }
+        }
+    }
+
+    fn retain(&mut self, delta: &B) -> B {
+        let (insertions, mut retractions) = split_by_sign(delta);
+        let mut updates = insertions.clone();
+
+        // Cancel retractions against the oldest retained insertions.
+        for (_, generation) in self.generations.iter_mut() {
+            if retractions.is_empty() {
+                break;
+            }
+
+            let (remaining, matched) = cancel(generation, &retractions);
+            if !matched.is_empty() {
+                retractions += -matched.clone();
+                updates += -matched;
+                *generation = remaining;
+            }
+        }
+        self.generations
+            .retain(|(_, generation)| !generation.is_empty());
+
+        if !insertions.is_empty() {
+            self.generations.push_back((self.step, insertions));
+        }
+
+        while self.expired() {
+            let (_, generation) = self.generations.pop_front().unwrap();
+            updates += -generation;
+        }
+
+        self.step += 1;
+        updates
+    }
+}
+
+/// Split `batch` into insertions and retractions.  The weights of
+/// retractions are negated, so both outputs only contain positive weights.
+fn split_by_sign<B>(batch: &B) -> (B, B)
+where
+    B: IndexedZSet,
+    B::R: ZRingValue,
+{
+    let mut insertions = B::Builder::with_capacity((), batch.len());
+    let mut retractions = B::Builder::with_capacity((), 0);
+    let mut cursor = batch.cursor();
+
+    while cursor.key_valid() {
+        while cursor.val_valid() {
+            let weight = cursor.weight();
+            let item = B::item_from(cursor.key().clone(), cursor.val().clone());
+            if weight.ge0() {
+                insertions.push((item, weight));
+            } else {
+                retractions.push((item, -weight));
+            }
+            cursor.step_val();
+        }
+        cursor.step_key();
+    }
+
+    (insertions.done(), retractions.done())
+}
+
+/// Cancel `retractions` against the insertions in `generation`, both with
+/// positive weights.
+///
+/// Returns the insertions that remain in `generation` and the part of
+/// `retractions` that cancelled them.
+fn cancel<B>(generation: &B, retractions: &B) -> (B, B)
+where
+    B: IndexedZSet,
+    B::R: ZRingValue,
+{
+    let mut remaining = B::Builder::with_capacity((), generation.len());
+    let mut matched = B::Builder::with_capacity((), 0);
+    let mut cursor = generation.cursor();
+    let mut retractions = retractions.cursor();
+
+    while cursor.key_valid() {
+        retractions.seek_key(cursor.key());
+        let key_matches = retractions.key_valid() && retractions.key() == cursor.key();
+
+        while cursor.val_valid() {
+            let mut weight = cursor.weight();
+            if key_matches {
+                retractions.seek_val(cursor.val());
+                if retractions.val_valid() && retractions.val() == cursor.val() {
+                    let retracted = retractions.weight().min(weight.clone());
+                    weight += -retracted.clone();
+                    matched.push((
+                        B::item_from(cursor.key().clone(), cursor.val().clone()),
+                        retracted,
+                    ));
+                }
+            }
+            if !weight.is_zero() {
+                remaining.push((
+                    B::item_from(cursor.key().clone(), cursor.val().clone()),
+                    weight,
+                ));
+            }
+            cursor.step_val();
+        }
+        cursor.step_key();
+    }
+
+    (remaining.done(), matched.done())
+}
+
+impl<B> Operator for RetentionWindow<B>
+where
+    B: IndexedZSet,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("RetentionWindow")
+    }
+
+    fn location(&self) -> OperatorLocation {
+        Some(self.location)
+    }
+
+    fn clock_start(&mut self, scope: Scope) {
+        if scope == 0 {
+            self.step = 0;
+            self.generations.clear();
+        }
+    }
+
+    fn fixedpoint(&self, scope: Scope) -> bool {
+        if scope == 0 {
+            self.generations.is_empty()
+        } else {
+            true
+        }
+    }
+}
+
+impl<B> UnaryOperator<B, B> for RetentionWindow<B>
+where
+    B: IndexedZSet,
+    B::R: ZRingValue,
+{
+    fn eval(&mut self, delta: &B) -> B {
+        self.retain(delta)
+    }
 }
 
 pub struct UntimedTraceAppend<T>
@@ -640,6 +975,14 @@ where
         });
     }
 
+    fn snapshot(&self) -> Option<Vec<u8>> {
+        self.trace.as_ref().map(trace_to_bytes)
+    }
+
+    fn restore(&mut self, snapshot: &[u8]) {
+        self.trace = Some(trace_from_bytes(snapshot));
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         !self.dirty[scope as usize]
     }
@@ -650,7 +993,9 @@ where
     T: Trace,
 {
     fn get_output(&mut self) -> T {
-        let mut result = self.trace.take().unwrap();
+        // The trace is missing if the previous step was aborted by an operator
+        // error before it reached this operator's input.
+        let mut result = self.trace.take().unwrap_or_else(|| T::new(None));
         result.clear_dirty_flag();
         result
     }
@@ -693,6 +1038,10 @@ where
         }
         self.effective_val_bound = effective_val_bound;
 
+        if self.bounds.compaction().take() {
+            i.compact();
+        }
+
         self.trace = Some(i);
 
         self.dirty[0] = dirty;
@@ -705,3 +1054,128 @@ where
         OwnershipPreference::PREFER_OWNED
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::TraceRetention;
+    use crate::{
+        trace::{cursor::Cursor, Batch, BatchReader},
+        zset, OrdZSet, RootCircuit,
+    };
+    use std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+    };
+
+    #[test]
+    fn test_compact_trace() {
+        let num_batches = Rc::new(Cell::new(0));
+        let key_count = Rc::new(Cell::new(0));
+        let num_batches_clone = num_batches.clone();
+        let key_count_clone = key_count.clone();
+
+        let (circuit, (input_handle, compaction)) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) = circuit.add_input_zset::<u64, isize>();
+            let trace = input.integrate_trace();
+            trace.apply(move |trace| {
+                num_batches_clone.set(trace.num_batches());
+                key_count_clone.set(trace.key_count());
+            });
+            Ok((input_handle, trace.compact_trace()))
+        })
+        .unwrap();
+
+        // Build a trace out of many small batches.
+        for key in 0..100 {
+            input_handle.append(&mut vec![(key, 1)]);
+            circuit.step().unwrap();
+        }
+        let batches_before = num_batches.get();
+        assert!(batches_before > 1);
+        assert_eq!(key_count.get(), 100);
+
+        // Compaction happens at the end of the step in which it was
+        // requested and is observable from the next step onward.
+        compaction.request();
+        assert!(compaction.is_requested());
+        circuit.step().unwrap();
+        assert!(!compaction.is_requested());
+        circuit.step().unwrap();
+
+        assert!(num_batches.get() < batches_before);
+        assert_eq!(num_batches.get(), 1);
+        assert_eq!(key_count.get(), 100);
+    }
+
+    fn integrate_bounded(retention: TraceRetention) -> Vec<OrdZSet<u64, isize>> {
+        let outputs = Rc::new(RefCell::new(Vec::new()));
+        let outputs_clone = outputs.clone();
+
+        let (circuit, input_handle) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) = circuit.add_input_zset::<u64, isize>();
+            input
+                .integrate_trace_bounded(retention)
+                .apply(move |trace| {
+                    let mut tuples = Vec::new();
+                    let mut cursor = trace.cursor();
+                    while cursor.key_valid() {
+                        let weight = cursor.weight();
+                        if weight != 0 {
+                            tuples.push((*cursor.key(), weight));
+                        }
+                        cursor.step_key();
+                    }
+                    outputs_clone
+                        .borrow_mut()
+                        .push(OrdZSet::from_keys((), tuples))
+                });
+            Ok(input_handle)
+        })
+        .unwrap();
+
+        for tuples in [
+            vec![(1, 1)],
+            vec![(2, 1), (3, 1)],
+            vec![(1, -1), (4, 1)],
+            vec![],
+            vec![],
+        ] {
+            input_handle.append(&mut tuples.clone());
+            circuit.step().unwrap();
+        }
+
+        let outputs = outputs.borrow().clone();
+        outputs
+    }
+
+    #[test]
+    fn test_integrate_trace_bounded_steps() {
+        assert_eq!(
+            integrate_bounded(TraceRetention::Steps(2)),
+            vec![
+                zset! { 1 => 1 },
+                zset! { 1 => 1, 2 => 1, 3 => 1 },
+                // The retraction of `1` cancels its retained insertion.
+                zset! { 2 => 1, 3 => 1, 4 => 1 },
+                // Insertions received at step 1 are evicted.
+                zset! { 4 => 1 },
+                zset! {},
+            ]
+        );
+    }
+
+    #[test]
+    fn test_integrate_trace_bounded_records() {
+        assert_eq!(
+            integrate_bounded(TraceRetention::Records(2)),
+            vec![
+                zset! { 1 => 1 },
+                zset! { 2 => 1, 3 => 1 },
+                // `1` has already been evicted, so its retraction is dropped.
+                zset! { 4 => 1 },
+                zset! { 4 => 1 },
+                zset! { 4 => 1 },
+            ]
+        );
+    }
+}
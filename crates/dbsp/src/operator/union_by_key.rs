@@ -0,0 +1,157 @@
+//! Operator that merges the values of two indexed Z-sets under shared keys.
+
+use crate::{
+    algebra::{HasZero, IndexedZSet},
+    circuit::{
+        metadata::OperatorLocation,
+        operator_traits::{BinaryOperator, Operator},
+        Circuit, Scope, Stream,
+    },
+    trace::{
+        cursor::{Cursor, CursorPair},
+        Batch, BatchReader, Builder,
+    },
+};
+use std::{borrow::Cow, marker::PhantomData, panic::Location};
+
+impl<C, I1> Stream<C, I1>
+where
+    C: Circuit,
+    I1: IndexedZSet + Send,
+{
+    /// Union the values of `self` and `other` under each key.
+    ///
+    /// For every key that occurs in either input, the output contains the
+    /// values of the key from both inputs.  A value present in both inputs
+    /// is output once with the sum of its weights.  Unlike
+    /// [`join`](`Self::join`), this doesn't produce the cross product of the
+    /// values of matching keys, and unlike [`plus`](`Self::plus`), the two
+    /// inputs can be batches of different types, as long as they have the
+    /// same key, value, and weight types.  This is useful for assembling a
+    /// combined view from several indexes over the same key space.
+    ///
+    /// The operator walks both input batches in key order with a
+    /// [`CursorPair`].  It is linear, so it can be applied to streams of
+    /// changes directly.
+    #[track_caller]
+    pub fn union_by_key<I2>(&self, other: &Stream<C, I2>) -> Stream<C, I1>
+    where
+        I2: Batch<Key = I1::Key, Val = I1::Val, Time = (), R = I1::R> + Send,
+    {
+        self.circuit()
+            .add_binary_operator(
+                UnionByKey::new(Location::caller()),
+                &self.shard(),
+                &other.shard(),
+            )
+            .mark_sharded()
+    }
+}
+
+/// Operator that merges the values of its two inputs under shared keys.
+///
+/// See [`Stream::union_by_key`].
+pub struct UnionByKey<I1, I2> {
+    location: &'static Location<'static>,
+    _types: PhantomData<(I1, I2)>,
+}
+
+impl<I1, I2> UnionByKey<I1, I2> {
+    pub fn new(location: &'static Location<'static>) -> Self {
+        Self {
+            location,
+            _types: PhantomData,
+        }
+    }
+}
+
+impl<I1, I2> Operator for UnionByKey<I1, I2>
+where
+    I1: 'static,
+    I2: 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::Borrowed("UnionByKey")
+    }
+
+    fn location(&self) -> OperatorLocation {
+        Some(self.location)
+    }
+
+    fn fixedpoint(&self, _scope: Scope) -> bool {
+        true
+    }
+}
+
+impl<I1, I2> BinaryOperator<I1, I2, I1> for UnionByKey<I1, I2>
+where
+    I1: IndexedZSet,
+    I2: BatchReader<Key = I1::Key, Val = I1::Val, Time = (), R = I1::R>,
+{
+    fn eval(&mut self, left: &I1, right: &I2) -> I1 {
+        let mut left_cursor = left.cursor();
+        let mut right_cursor = right.cursor();
+        let mut cursor = CursorPair::new(&mut left_cursor, &mut right_cursor);
+
+        let mut builder = I1::Builder::with_capacity((), left.len() + right.len());
+
+        while cursor.key_valid() {
+            while cursor.val_valid() {
+                // Values that occur in both inputs can cancel out.
+                let weight = cursor.weight();
+                if !weight.is_zero() {
+                    builder.push((
+                        I1::item_from(cursor.key().clone(), cursor.val().clone()),
+                        weight,
+                    ));
+                }
+                cursor.step_val();
+            }
+            cursor.step_key();
+        }
+
+        builder.done()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{indexed_zset, trace::Batch, OrdIndexedZSet, RootCircuit};
+
+    #[test]
+    fn union_by_key_test() {
+        let (circuit, (left_handle, right_handle, output)) = RootCircuit::build(move |circuit| {
+            let (left, left_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+            let (right, right_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+
+            Ok((
+                left_handle,
+                right_handle,
+                left.union_by_key(&right).output(),
+            ))
+        })
+        .unwrap();
+
+        // Key 1 occurs in both inputs with complementary values.
+        left_handle.append(&mut vec![(1, (10, 1)), (1, (11, 1)), (2, (20, 1))]);
+        right_handle.append(&mut vec![(1, (12, 1)), (3, (30, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(
+            output.consolidate(),
+            indexed_zset! {
+                1 => {10 => 1, 11 => 1, 12 => 1},
+                2 => {20 => 1},
+                3 => {30 => 1},
+            }
+        );
+
+        // Weights of values present in both inputs are added up.
+        left_handle.append(&mut vec![(1, (10, 1)), (2, (20, -1))]);
+        right_handle.append(&mut vec![(1, (10, 1)), (2, (20, 1))]);
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), indexed_zset! {1 => {10 => 2}});
+
+        circuit.step().unwrap();
+        assert_eq!(output.consolidate(), OrdIndexedZSet::empty(()));
+    }
+}
@@ -11,7 +11,7 @@ use crate::{
     circuit_cache_key, NumEntries,
 };
 use size_of::{Context, SizeOf};
-use std::{borrow::Cow, mem::replace};
+use std::{borrow::Cow, collections::VecDeque, mem::replace};
 
 circuit_cache_key!(DelayedId<C, D>(GlobalNodeId => Stream<C, D>));
 circuit_cache_key!(NestedDelayedId<C, D>(GlobalNodeId => Stream<C, D>));
@@ -48,6 +48,25 @@ where
         }
     }
 
+    /// Create a feedback loop with `Z1` operator seeded with an initial
+    /// value.
+    ///
+    /// Unlike [`Self::new`], whose [`stream`](`Self::stream`) outputs zero in
+    /// the first timestamp after `clock_start`, the stream returned by this
+    /// feedback outputs `initial` in the first timestamp.  In a nested
+    /// circuit this means that `initial` is fed into the first iteration of
+    /// every fixedpoint computation.
+    pub fn with_initial(circuit: &C, initial: D) -> Self {
+        let (ExportStream { local, export }, feedback) =
+            circuit.add_feedback_with_export(Z1::with_initial(D::zero(), initial));
+
+        Self {
+            feedback,
+            output: local,
+            export,
+        }
+    }
+
     /// Output stream of the `Z1` operator.
     pub fn stream(&self) -> &Stream<C, D> {
         &self.output
@@ -125,6 +144,25 @@ where
             .clone()
     }
 
+    /// Delays `self` by `n` clock cycles using the [`ZN`] operator.
+    ///
+    /// Outputs zero during the first `n` clock cycles and the value received
+    /// as input `n` clock cycles earlier after that.  `delay_by(1)` is
+    /// equivalent to [`delay`](`Self::delay`), except that [`ZN`] is not a
+    /// strict operator and therefore cannot be used to close feedback loops.
+    /// `delay_by(0)` returns `self`.
+    pub fn delay_by(&self, n: usize) -> Stream<C, D>
+    where
+        D: Eq + SizeOf + NumEntries + Clone + HasZero + 'static,
+    {
+        if n == 0 {
+            return self.clone();
+        }
+
+        self.circuit()
+            .add_unary_operator(ZN::new(D::zero(), n), self)
+    }
+
     /// Applies [`Z1Nested`] operator to `self`.
     pub fn delay_nested(&self) -> Stream<C, D>
     where
@@ -145,7 +183,8 @@ where
 /// after [clock_start](`Z1::clock_start`).  For all subsequent timestamps, it
 /// outputs the value received as input at the previous timestamp.  The zero
 /// value is typically the neutral element of a monoid (e.g., 0 for addition
-/// or 1 for multiplication).
+/// or 1 for multiplication).  Use [`Z1::with_initial`] to output a different
+/// value in the first timestamp.
 ///
 /// It is a [strict
 /// operator](`crate::circuit::operator_traits::StrictOperator`).
@@ -163,6 +202,8 @@ where
 /// ```
 pub struct Z1<T> {
     zero: T,
+    /// Value output in the first timestamp after `clock_start`.
+    initial: T,
     empty_output: bool,
     values: T,
 }
@@ -172,10 +213,17 @@ where
     T: Clone,
 {
     pub fn new(zero: T) -> Self {
+        Self::with_initial(zero.clone(), zero)
+    }
+
+    /// Create a `Z1` operator that outputs `initial` instead of `zero` in the
+    /// first timestamp after `clock_start`.
+    pub fn with_initial(zero: T, initial: T) -> Self {
         Self {
-            zero: zero.clone(),
+            zero,
+            initial: initial.clone(),
             empty_output: false,
-            values: zero,
+            values: initial,
         }
     }
 }
@@ -191,7 +239,7 @@ where
     fn clock_start(&mut self, _scope: Scope) {}
     fn clock_end(&mut self, _scope: Scope) {
         self.empty_output = false;
-        self.values = self.zero.clone();
+        self.values = self.initial.clone();
     }
 
     fn metadata(&self, meta: &mut OperatorMeta) {
@@ -205,6 +253,12 @@ where
         });
     }
 
+    // The stored value isn't serializable, so the operator can only be
+    // captured in a snapshot while it holds its initial value.
+    fn can_snapshot(&self) -> bool {
+        self.values == self.initial
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         if scope == 0 {
             self.values.num_entries_shallow() == 0 && self.empty_output
@@ -262,6 +316,123 @@ where
     }
 }
 
+/// z^-n operator delays its input by `n` timestamps.
+///
+/// The operator keeps the last `n` inputs in a ring buffer.  It outputs the
+/// zero value in the first `n` timestamps after
+/// [clock_start](`ZN::clock_start`), and the value received as input `n`
+/// timestamps earlier after that.
+///
+/// Unlike [`Z1`], this is not a strict operator.
+///
+/// # Examples
+///
+/// With `n = 2`:
+///
+/// ```text
+/// time | input | output
+/// ---------------------
+///   0  |   5   |   0
+///   1  |   6   |   0
+///   2  |   7   |   5
+///   3  |   8   |   6
+///         ...
+/// ```
+pub struct ZN<T> {
+    zero: T,
+    empty_output: bool,
+    values: VecDeque<T>,
+}
+
+impl<T> ZN<T>
+where
+    T: Clone,
+{
+    /// Create a `ZN` operator that delays its input by `n` timestamps.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is 0.
+    pub fn new(zero: T, n: usize) -> Self {
+        assert_ne!(n, 0, "ZN: delay must be greater than 0");
+
+        Self {
+            values: VecDeque::from(vec![zero.clone(); n]),
+            zero,
+            empty_output: false,
+        }
+    }
+
+    fn push(&mut self, value: T) -> T {
+        self.values.push_back(value);
+        self.values.pop_front().unwrap()
+    }
+}
+
+impl<T> Operator for ZN<T>
+where
+    T: Eq + SizeOf + NumEntries + Clone + 'static,
+{
+    fn name(&self) -> Cow<'static, str> {
+        Cow::from("Z^-n")
+    }
+
+    fn clock_start(&mut self, _scope: Scope) {}
+    fn clock_end(&mut self, _scope: Scope) {
+        self.empty_output = false;
+        for value in self.values.iter_mut() {
+            *value = self.zero.clone();
+        }
+    }
+
+    fn metadata(&self, meta: &mut OperatorMeta) {
+        let bytes = self.values.size_of();
+        meta.extend(metadata! {
+            "delay" => self.values.len(),
+            "total size" => self.values.iter().map(NumEntries::num_entries_deep).sum::<usize>(),
+            "allocated bytes" => MetaItem::bytes(bytes.total_bytes()),
+            "used bytes" => MetaItem::bytes(bytes.used_bytes()),
+            "allocations" => bytes.distinct_allocations(),
+            "shared bytes" => MetaItem::bytes(bytes.shared_bytes()),
+        });
+    }
+
+    fn can_snapshot(&self) -> bool {
+        self.values.iter().all(|value| *value == self.zero)
+    }
+
+    fn fixedpoint(&self, scope: Scope) -> bool {
+        if scope == 0 {
+            self.empty_output
+                && self
+                    .values
+                    .iter()
+                    .all(|value| value.num_entries_shallow() == 0)
+        } else {
+            true
+        }
+    }
+}
+
+impl<T> UnaryOperator<T, T> for ZN<T>
+where
+    T: Eq + SizeOf + NumEntries + Clone + 'static,
+{
+    fn eval(&mut self, i: &T) -> T {
+        self.eval_owned(i.clone())
+    }
+
+    fn eval_owned(&mut self, i: T) -> T {
+        let output = self.push(i);
+        self.empty_output = output.num_entries_shallow() == 0;
+        output
+    }
+
+    fn input_preference(&self) -> OwnershipPreference {
+        OwnershipPreference::PREFER_OWNED
+    }
+}
+
 /// z^-1 operator over streams of streams.
 ///
 /// The operator stores a complete nested stream consumed at the last iteration
@@ -370,6 +541,10 @@ where
         });
     }
 
+    fn can_snapshot(&self) -> bool {
+        self.values.iter().all(|value| *value == self.zero)
+    }
+
     fn fixedpoint(&self, scope: Scope) -> bool {
         if scope == 0 {
             self.values
@@ -472,8 +647,14 @@ where
 mod test {
     use crate::{
         circuit::operator_traits::{Operator, StrictOperator, StrictUnaryOperator, UnaryOperator},
-        operator::{Z1Nested, Z1},
+        operator::{DelayedFeedback, FilterMap, Generator, Z1Nested, Z1},
+        trace::{
+            ord::{OrdIndexedZSet, OrdZSet},
+            Batch,
+        },
+        zset, Circuit, Error, RootCircuit, SchedulerError, Stream,
     };
+    use std::{cell::RefCell, rc::Rc};
 
     #[test]
     fn z1_test() {
@@ -520,6 +701,116 @@ mod test {
         assert_eq!(res, expected_result);
     }
 
+    #[test]
+    fn z1_with_initial_test() {
+        let mut z1 = Z1::with_initial(0, -1);
+
+        let mut res = Vec::new();
+        z1.clock_start(0);
+        res.push(z1.eval(&1));
+        res.push(z1.eval(&2));
+        z1.clock_end(0);
+
+        z1.clock_start(0);
+        res.push(z1.get_output());
+        z1.eval_strict_owned(3);
+        res.push(z1.get_output());
+        z1.clock_end(0);
+
+        assert_eq!(res, vec![-1, 1, -1, 3]);
+    }
+
+    #[test]
+    fn delay_by_test() {
+        let (circuit, (input_handle, output)) = RootCircuit::build(move |circuit| {
+            let (input, input_handle) = circuit.add_input_zset::<u64, isize>();
+
+            Ok((input_handle, input.delay_by(2).output()))
+        })
+        .unwrap();
+
+        let mut outputs = Vec::new();
+        for i in 1..=5 {
+            input_handle.push(i, 1);
+            circuit.step().unwrap();
+            outputs.push(output.consolidate());
+        }
+
+        assert_eq!(
+            outputs,
+            vec![
+                OrdZSet::empty(()),
+                OrdZSet::empty(()),
+                zset! {1 => 1},
+                zset! {2 => 1},
+                zset! {3 => 1},
+            ]
+        );
+    }
+
+    // Compute transitive closure of the edge relation, seeding the feedback
+    // loop with an edge that is not part of the input.
+    #[test]
+    fn delayed_feedback_with_initial_test() {
+        let first_iterations = Rc::new(RefCell::new(Vec::new()));
+        let first_iterations_clone = first_iterations.clone();
+
+        let circuit = RootCircuit::build(move |circuit| {
+            let edges: Stream<_, OrdZSet<(usize, usize), isize>> =
+                circuit.add_source(Generator::new(|| zset! { (1, 2) => 1 }));
+
+            let paths = circuit
+                .iterate_with_condition(|child| {
+                    let edges = edges.delta0(child).integrate();
+                    // Edges indexed by destination node.
+                    let edges_inverted: Stream<_, OrdZSet<(usize, usize), isize>> =
+                        edges.map(|&(from, to)| (to, from));
+                    let edges_indexed: Stream<_, OrdIndexedZSet<usize, usize, isize>> =
+                        edges_inverted.index();
+
+                    let feedback =
+                        <DelayedFeedback<_, OrdZSet<(usize, usize), isize>>>::with_initial(
+                            child,
+                            zset! { (2, 3) => 1 },
+                        );
+
+                    let iteration = Rc::new(RefCell::new(0));
+                    feedback.stream().inspect(move |paths| {
+                        if *iteration.borrow() == 0 {
+                            first_iterations_clone.borrow_mut().push(paths.clone());
+                        }
+                        *iteration.borrow_mut() += 1;
+                    });
+
+                    let paths_indexed: Stream<_, OrdIndexedZSet<usize, usize, isize>> =
+                        feedback.stream().index();
+                    let extended: Stream<_, OrdZSet<(usize, usize), isize>> =
+                        edges_indexed.stream_join(&paths_indexed, |_node, &from, &to| (from, to));
+
+                    let paths = edges
+                        .plus(feedback.stream())
+                        .plus(&extended)
+                        .stream_distinct();
+                    feedback.connect(&paths);
+
+                    let condition = paths.differentiate().condition(|z| z.is_empty());
+                    Ok((condition, paths.export()))
+                })
+                .unwrap();
+
+            paths.inspect(|paths| {
+                assert_eq!(paths, &zset! { (1, 2) => 1, (1, 3) => 1, (2, 3) => 1 })
+            });
+            Ok(())
+        })
+        .unwrap()
+        .0;
+
+        circuit.step().unwrap();
+
+        assert_eq!(&*first_iterations.borrow(), &[zset! { (2, 3) => 1 }]);
+    }
+
     #[test]
     fn z1_nested_test() {
         let mut z1 = Z1Nested::new(0);
@@ -596,4 +887,18 @@ mod test {
 
         z1.clock_end(1);
     }
+
+    #[test]
+    fn unconnected_feedback_test() {
+        let result = RootCircuit::build(move |circuit| {
+            let feedback = DelayedFeedback::<_, OrdZSet<u64, isize>>::new(circuit);
+            feedback.stream().output();
+            Ok(())
+        });
+
+        assert!(matches!(
+            result,
+            Err(Error::Scheduler(SchedulerError::UnconnectedFeedback { .. }))
+        ));
+    }
 }
@@ -85,6 +85,89 @@ pub use reverse::ReverseKeyCursor;
 /// }
 /// ```
 ///
+/// # Custom operators
+///
+/// `Cursor` is the interface for reading batches and traces in custom
+/// operators, e.g., operators that walk two cursors in lockstep like the ones
+/// that implement joins.  The navigation methods of this trait, including
+/// [`key_valid`], [`val_valid`], [`seek_key`], [`seek_val`], [`step_val`],
+/// [`rewind_vals`], [`map_times`], and [`weight`], are part of the public API
+/// of the crate and behave as documented for all batch and trace types.  The
+/// cursor types that implement the trait, e.g., the ones in
+/// [`ord`](`crate::trace::ord`), are implementation details: operators should
+/// be written against `Cursor` and obtain cursors via
+/// [`BatchReader::cursor`](`crate::trace::BatchReader::cursor`).
+///
+/// The following operator outputs the values of each key that are greater
+/// than or equal to a threshold, using [`seek_val`] to skip smaller values:
+///
+/// ```
+/// use dbsp::{
+///     circuit::{
+///         operator_traits::{Operator, UnaryOperator},
+///         Scope,
+///     },
+///     indexed_zset,
+///     trace::{Batch, BatchReader, Builder, Cursor},
+///     Circuit, OrdIndexedZSet, RootCircuit,
+/// };
+/// use std::borrow::Cow;
+///
+/// type Indexed = OrdIndexedZSet<u64, u64, isize>;
+///
+/// struct ValsFrom {
+///     min: u64,
+/// }
+///
+/// impl Operator for ValsFrom {
+///     fn name(&self) -> Cow<'static, str> {
+///         Cow::Borrowed("ValsFrom")
+///     }
+///
+///     fn fixedpoint(&self, _scope: Scope) -> bool {
+///         true
+///     }
+/// }
+///
+/// impl UnaryOperator<Indexed, Indexed> for ValsFrom {
+///     fn eval(&mut self, input: &Indexed) -> Indexed {
+///         let mut builder = <Indexed as Batch>::Builder::new_builder(());
+///
+///         let mut cursor = input.cursor();
+///         while cursor.key_valid() {
+///             cursor.seek_val(&self.min);
+///             while cursor.val_valid() {
+///                 builder.push(((*cursor.key(), *cursor.val()), cursor.weight()));
+///                 cursor.step_val();
+///             }
+///             cursor.step_key();
+///         }
+///
+///         builder.done()
+///     }
+/// }
+///
+/// let (circuit, (input_handle, output)) = RootCircuit::build(|circuit| {
+///     let (input, input_handle) = circuit.add_input_indexed_zset::<u64, u64, isize>();
+///     let output = circuit
+///         .add_unary_operator(ValsFrom { min: 10 }, &input)
+///         .output();
+///     Ok((input_handle, output))
+/// })
+/// .unwrap();
+///
+/// input_handle.append(&mut vec![(1, (5, 1)), (1, (10, 1)), (1, (15, 1)), (2, (3, 1))]);
+/// circuit.step().unwrap();
+/// assert_eq!(
+///     output.consolidate(),
+///     indexed_zset! {1 => {10 => 1, 15 => 1}}
+/// );
+/// ```
+///
+/// [`key_valid`]: `Self::key_valid`
+/// [`val_valid`]: `Self::val_valid`
+/// [`map_times`]: `Self::map_times`
+/// [`weight`]: `Self::weight`
 /// [`step_key`]: `Self::step_key`
 /// [`seek_key`]: `Self::seek_key`
 /// [`step_key_reverse`]: `Self::step_key_reverse`
@@ -133,8 +216,13 @@ pub trait Cursor<K, V, T, R> {
         }
     }
 
-    /// Applies `logic` to each pair of time and difference. Intended for
-    /// mutation of the closure's scope.
+    /// Applies `logic` to each pair of time and difference associated with
+    /// the current key/value pair.  Intended for mutation of the closure's
+    /// scope.
+    ///
+    /// Times are not necessarily visited in order and may repeat (see the
+    /// trait-level documentation).  If the current key and value are not
+    /// valid, behavior is unspecified.
     fn map_times<L>(&mut self, mut logic: L)
     where
         L: FnMut(&T, &R),
@@ -220,10 +308,23 @@ pub trait Cursor<K, V, T, R> {
     /// Moves the cursor to the previous value.
     fn step_val_reverse(&mut self);
 
-    /// Advances the cursor to the specified value.
+    /// Advances the cursor to the specified value of the current key.  If
+    /// `val` itself is not present, advances to the first value greater than
+    /// `val`; if there is no such value, the value position becomes invalid,
+    /// while the cursor remains on the current key.
+    ///
+    /// This has no effect if the cursor is already positioned past `val`, so
+    /// it might be desirable to call [`rewind_vals`](Self::rewind_vals)
+    /// first.
     fn seek_val(&mut self, val: &V);
 
-    /// Moves the cursor back to the specified value.
+    /// Moves the cursor back to the specified value of the current key.  If
+    /// `val` itself is not present, moves back to the first value less than
+    /// `val`; if there is no such value, the value position becomes invalid,
+    /// while the cursor remains on the current key.
+    ///
+    /// Only valid in the reverse value mode (see
+    /// [`fast_forward_vals`](Self::fast_forward_vals)).
     fn seek_val_reverse(&mut self, val: &V);
 
     /// Move the cursor to the first value (for the current key) that satisfies
@@ -413,3 +514,61 @@ impl<C, K: Clone, V: Clone, T: Clone, R: Clone> CursorDebug<K, V, T, R> for C wh
     C: Cursor<K, V, T, R>
 {
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        indexed_zset,
+        trace::{BatchReader, Cursor, Spine, Trace},
+        OrdIndexedZSet,
+    };
+
+    fn check_seek_val<C>(mut cursor: C)
+    where
+        C: Cursor<u64, u64, (), isize>,
+    {
+        cursor.seek_key(&1);
+        assert_eq!(cursor.get_key(), Some(&1));
+
+        // Seeks to the next value if the value is missing.
+        cursor.seek_val(&15);
+        assert_eq!(cursor.get_val(), Some(&20));
+
+        // Doesn't move backward.
+        cursor.seek_val(&10);
+        assert_eq!(cursor.get_val(), Some(&20));
+
+        cursor.rewind_vals();
+        cursor.seek_val(&10);
+        assert_eq!(cursor.get_val(), Some(&10));
+
+        // Past the last value, the value position is invalid, but the cursor
+        // stays on the same key.
+        cursor.seek_val(&100);
+        assert!(!cursor.val_valid());
+        assert_eq!(cursor.get_key(), Some(&1));
+
+        cursor.fast_forward_vals();
+        cursor.seek_val_reverse(&25);
+        assert_eq!(cursor.get_val(), Some(&20));
+        cursor.seek_val_reverse(&5);
+        assert!(!cursor.val_valid());
+
+        cursor.step_key();
+        assert_eq!(cursor.get_key(), Some(&2));
+        assert_eq!(cursor.get_val(), Some(&5));
+    }
+
+    #[test]
+    fn seek_val_test() {
+        let batch: OrdIndexedZSet<u64, u64, isize> = indexed_zset! {
+            1 => {10 => 1, 20 => 1, 30 => 1},
+            2 => {5 => 1}
+        };
+        check_seek_val(batch.cursor());
+
+        let mut spine = Spine::new(None);
+        spine.insert(batch);
+        check_seek_val(spine.cursor());
+    }
+}
@@ -177,6 +177,15 @@ pub trait Trace: BatchReader {
     /// Merge all updates in a trace into a single batch.
     fn consolidate(self) -> Option<Self::Batch>;
 
+    /// Merge all batches in the trace, completing any merges in progress.
+    ///
+    /// Unlike [`Self::consolidate`], the trace remains usable after this call.
+    /// Traces normally merge batches incrementally as new updates arrive;
+    /// this method can be used to force compaction at a quiescent point,
+    /// e.g., to reclaim memory while the trace is idle.  The default
+    /// implementation does nothing.
+    fn compact(&mut self) {}
+
     /// Introduces a batch of updates to the trace.
     ///
     /// Batches describe the time intervals they contain, and they should be
@@ -387,6 +387,12 @@ where
         s
     }
 
+    /// Number of batches in the spine, including both inputs of each merge in
+    /// progress.
+    pub fn num_batches(&self) -> usize {
+        self.fold_batches(0, |n, _| n + 1)
+    }
+
     #[allow(dead_code)]
     fn map_batches<F>(&self, mut map: F)
     where
@@ -708,10 +714,7 @@ where
 
     fn consolidate(mut self) -> Option<B> {
         // Merge batches until there is nothing left to merge.
-        let mut fuel = isize::max_value();
-        while !self.reduced() {
-            self.exert(&mut fuel);
-        }
+        self.compact();
         // Return the sole remaining batch (if one exists).
         for merging in self.merging.into_iter() {
             if let MergeState::Single(Some(batch)) = merging {
@@ -725,6 +728,13 @@ where
         None
     }
 
+    fn compact(&mut self) {
+        let mut fuel = isize::max_value();
+        while !self.reduced() {
+            self.exert(&mut fuel);
+        }
+    }
+
     // Ideally, this method acts as insertion of `batch`, even if we are not yet
     // able to begin merging the batch. This means it is a good time to perform
     // amortized work proportional to the size of batch.
@@ -1359,7 +1369,7 @@ mod test {
 
     use crate::{
         trace::{
-            cursor::CursorPair,
+            cursor::{Cursor, CursorPair},
             ord::{OrdKeyBatch, OrdValBatch},
             test_batch::{
                 assert_batch_cursors_eq, assert_batch_eq, assert_trace_eq, test_batch_sampling,
@@ -1801,4 +1811,49 @@ mod test {
         }
 
     }
+
+    #[test]
+    fn test_val_spine_reverse_iteration() {
+        let mut trace: Spine<OrdValBatch<i32, i32, u32, i32>> = Spine::new(None);
+
+        // Overlapping keys across batches, so that the cursor has to merge
+        // several batch cursors.
+        for time in 0..3u32 {
+            let tuples = (0..10)
+                .map(|key| ((key * 3 + time as i32 % 2, time as i32), 1))
+                .collect::<Vec<_>>();
+            trace.insert(OrdValBatch::from_tuples(time, tuples));
+        }
+
+        let mut expected = Vec::new();
+        let mut cursor = trace.cursor();
+        while cursor.key_valid() {
+            let mut vals = Vec::new();
+            while cursor.val_valid() {
+                vals.push(*cursor.val());
+                cursor.step_val();
+            }
+            vals.reverse();
+            expected.push((*cursor.key(), vals));
+            cursor.step_key();
+        }
+        expected.reverse();
+
+        let mut actual = Vec::new();
+        cursor.fast_forward_keys();
+        while cursor.key_valid() {
+            let mut vals = Vec::new();
+            cursor.fast_forward_vals();
+            while cursor.val_valid() {
+                vals.push(*cursor.val());
+                cursor.step_val_reverse();
+            }
+            actual.push((*cursor.key(), vals));
+            cursor.step_key_reverse();
+        }
+
+        assert_eq!(expected.len(), 20);
+        assert!(expected.windows(2).all(|w| w[0].0 > w[1].0));
+        assert_eq!(actual, expected);
+    }
 }
@@ -69,6 +69,10 @@ pub struct RuntimeConfig {
     /// get buffered by the controller, defaults to 0.
     #[serde(default)]
     pub max_buffering_delay_usecs: u64,
+
+    /// Resource limits enforced by the runner on the pipeline process.
+    #[serde(default)]
+    pub resources: ResourceConfig,
 }
 
 impl RuntimeConfig {
@@ -81,6 +85,22 @@ impl RuntimeConfig {
     }
 }
 
+/// Resource limits of a pipeline.
+///
+/// Limits that are not set default to the limits configured for the runner.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ResourceConfig {
+    /// Maximal amount of resident memory in megabytes that the pipeline
+    /// process can use.  The process is killed once it exceeds this limit.
+    #[serde(default)]
+    pub memory_mb_max: Option<u64>,
+
+    /// Maximal amount of CPU time in seconds that the pipeline process can
+    /// consume.  The process is killed once it exceeds this limit.
+    #[serde(default)]
+    pub cpu_secs_max: Option<u64>,
+}
+
 /// Describes an input connector configuration
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct InputEndpointConfig {
@@ -1,15 +1,225 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use utoipa::ToSchema;
 
-#[derive(Deserialize, Serialize, ToSchema)]
-pub struct CsvParserConfig {}
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct CsvParserConfig {
+    /// Expected number of columns in each record.
+    ///
+    /// When set, records with a different number of columns are rejected
+    /// with a parse error.  By default, validation is left to the record
+    /// deserializer.
+    #[serde(default)]
+    pub strict_columns: Option<usize>,
+
+    /// Per-column type coercion hints.
+    ///
+    /// Coercions are applied to the raw text of each record before it is
+    /// passed to the record deserializer, e.g., to strip thousands
+    /// separators and currency symbols from numeric columns.
+    #[serde(default)]
+    pub coercions: Vec<CsvColumnCoercion>,
+
+    /// Whitespace trimming applied to each record before it is passed to
+    /// the record deserializer.
+    ///
+    /// Trimming removes leading and trailing ASCII whitespace, including
+    /// inside quoted fields, e.g., `" 42 "` is parsed as `42`.
+    #[serde(default)]
+    pub trim: TrimMode,
+
+    /// Maximum length of a record included in a parse error message.
+    /// Longer records are truncated.
+    #[serde(default = "default_max_record_len_in_errmsg")]
+    pub max_record_len_in_errmsg: usize,
+
+    /// Maximum length of a record in bytes.
+    ///
+    /// Guards against inputs with missing line terminators, which would
+    /// otherwise be buffered in memory indefinitely.  When a record,
+    /// excluding its terminator, exceeds this length, the parser reports an
+    /// error and discards the record.  Incomplete records are discarded as
+    /// soon as they exceed the limit, skipping the input up to the next
+    /// record terminator.  By default, the length of records is not limited.
+    #[serde(default)]
+    pub max_record_len: Option<usize>,
+
+    /// Indexes of the columns to read from each record.
+    ///
+    /// When set, only the listed columns are passed to the record
+    /// deserializer, in the specified order, and all other columns are
+    /// skipped without being validated.  This is useful for reading a few
+    /// columns from wide files.  Indexes in `coercions` and `strict_columns`
+    /// refer to columns of the original record.  Records that don't contain
+    /// all listed columns are rejected with a parse error.  When
+    /// `strict_columns` is set, listed indexes must be smaller than it.
+    #[serde(default)]
+    pub columns: Option<Vec<usize>>,
+
+    /// Per-column date and time formats, indexed by the zero-based index of
+    /// the column.
+    ///
+    /// Values in the listed columns are parsed using the specified
+    /// [`chrono` format string](https://docs.rs/chrono/0.4.31/chrono/format/strftime/),
+    /// e.g., `"%m/%d/%Y"` for US-format dates, and converted to the default
+    /// format of the corresponding SQL type before they are passed to the
+    /// record deserializer.  Empty values are passed unmodified.
+    #[serde(default)]
+    pub date_formats: BTreeMap<usize, String>,
+
+    /// Drop a single trailing empty field from each record.
+    ///
+    /// Some producers terminate every field with a delimiter, e.g.,
+    /// `a,b,`, which is otherwise parsed as a record with an extra empty
+    /// column.  When set, such records are parsed as if the trailing
+    /// delimiter was absent, including when checking `strict_columns`.
+    #[serde(default)]
+    pub allow_trailing_delimiter: bool,
+
+    /// Byte that terminates each record.
+    ///
+    /// Defaults to `\n`, in which case records can also be terminated by
+    /// `\r\n`.  Some exports use a different byte instead, e.g., the ASCII
+    /// record separator (`0x1E`).  Terminators inside quoted fields don't
+    /// end the record.
+    #[serde(default = "default_record_terminator")]
+    pub record_terminator: u8,
+}
+
+impl Default for CsvParserConfig {
+    fn default() -> Self {
+        Self {
+            strict_columns: None,
+            coercions: Vec::new(),
+            trim: TrimMode::None,
+            max_record_len_in_errmsg: default_max_record_len_in_errmsg(),
+            max_record_len: None,
+            columns: None,
+            date_formats: BTreeMap::new(),
+            allow_trailing_delimiter: false,
+            record_terminator: default_record_terminator(),
+        }
+    }
+}
+
+/// Specifies which CSV fields are trimmed of leading and trailing whitespace.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+pub enum TrimMode {
+    /// Don't trim any fields.
+    #[default]
+    #[serde(rename = "none")]
+    None,
+    /// Trim data fields.
+    #[serde(rename = "fields")]
+    Fields,
+}
+
+/// Sequence of bytes written at the end of each CSV record.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq, ToSchema)]
+pub enum CsvLineTerminator {
+    /// Unix line terminator (`\n`).
+    #[default]
+    #[serde(rename = "lf")]
+    Lf,
+    /// Windows line terminator (`\r\n`).
+    #[serde(rename = "crlf")]
+    Crlf,
+    /// A custom single-byte terminator, e.g., `";"`.  Fields that contain
+    /// the terminator are quoted.
+    #[serde(rename = "custom")]
+    Custom(String),
+}
+
+impl CsvLineTerminator {
+    /// Returns the bytes of the terminator.
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::Lf => b"\n",
+            Self::Crlf => b"\r\n",
+            Self::Custom(terminator) => terminator.as_bytes(),
+        }
+    }
+}
+
+/// Type coercion hint for a single CSV column.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct CsvColumnCoercion {
+    /// Zero-based index of the column.
+    pub column: usize,
+
+    /// Characters to remove from the column before deserializing it,
+    /// e.g., `"$,"` turns `"$1,234"` into `1234`.
+    #[serde(default)]
+    pub strip_chars: String,
+}
 
 const fn default_buffer_size_records() -> usize {
     10_000
 }
 
+const fn default_include_weight() -> bool {
+    true
+}
+
+const fn default_max_record_len_in_errmsg() -> usize {
+    4096
+}
+
+const fn default_record_terminator() -> u8 {
+    b'\n'
+}
+
 #[derive(Deserialize, Serialize, ToSchema)]
 pub struct CsvEncoderConfig {
     #[serde(default = "default_buffer_size_records")]
     pub buffer_size_records: usize,
+
+    /// Append the weight of each record as the last column.
+    ///
+    /// When `false`, only the fields of the record are written, and a record
+    /// with weight `n` is written `n` times.  This is suitable for
+    /// append-only outputs; records with negative weights cannot be
+    /// represented in this mode and are rejected with an error.
+    #[serde(default = "default_include_weight")]
+    pub include_weight: bool,
+
+    /// Number of decimal places used to serialize floating point values.
+    ///
+    /// By default, floats are written using the shortest representation
+    /// that converts back to the same value.
+    #[serde(default)]
+    pub float_precision: Option<usize>,
+
+    /// Write a header row with column names before the first record.
+    ///
+    /// The header is written once, when the encoder receives its first
+    /// non-empty batch.  When `include_weight` is `true`, the last column
+    /// is named `weight`.
+    #[serde(default)]
+    pub header: bool,
+
+    /// Output column names that replace the names declared in the table
+    /// schema, indexed by the declared name.
+    #[serde(default)]
+    pub rename_columns: BTreeMap<String, String>,
+
+    /// Output column names, after renaming, in the order in which they are
+    /// written.
+    ///
+    /// The listed columns are written first, followed by the remaining
+    /// columns in their declared order.  The weight column, if any, is
+    /// always written last.  By default, columns are written in the order
+    /// in which they are declared in the table schema.
+    #[serde(default)]
+    pub column_order: Option<Vec<String>>,
+
+    /// Maximum length of a record included in an error message.  Longer
+    /// records are truncated.
+    #[serde(default = "default_max_record_len_in_errmsg")]
+    pub max_record_len_in_errmsg: usize,
+
+    /// Sequence of bytes written at the end of each record, including the
+    /// header row.  Defaults to `\n`.
+    #[serde(default)]
+    pub line_terminator: CsvLineTerminator,
 }
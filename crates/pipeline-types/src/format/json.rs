@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use utoipa::ToSchema;
 
 /// JSON parser configuration.
@@ -52,6 +53,32 @@ pub struct JsonParserConfig {
     /// ```
     #[serde(default)]
     pub array: bool,
+
+    /// How integer columns handle JSON numbers that don't fit into the
+    /// column's type.
+    ///
+    /// Only JIT-compiled pipelines support policies other than `error`.
+    #[serde(default)]
+    pub on_overflow: JsonOverflowPolicy,
+}
+
+/// Handling of JSON numbers that overflow the type of an integer column.
+#[derive(Clone, Copy, Default, Deserialize, Serialize, Debug, PartialEq, Eq, Hash, ToSchema)]
+#[repr(u8)]
+pub enum JsonOverflowPolicy {
+    /// Treat the number as invalid: the record fails to parse, even if the
+    /// column is nullable.
+    #[default]
+    #[serde(rename = "error")]
+    Error = 0,
+    /// Clamp the number to the minimum or maximum value of the column's
+    /// type, e.g., `300` becomes `127` in a `TINYINT` column.
+    #[serde(rename = "saturate")]
+    Saturate = 1,
+    /// Truncate the number to the width of the column's type, e.g., `300`
+    /// becomes `44` in a `TINYINT` column.
+    #[serde(rename = "wrap")]
+    Wrap = 2,
 }
 
 /// Supported JSON data change event formats.
@@ -123,7 +150,7 @@ impl Default for JsonUpdateFormat {
 }
 
 /// Specifies JSON encoding used of table records.
-#[derive(Clone, Default, Deserialize, Serialize, Debug, PartialEq, Eq, ToSchema)]
+#[derive(Clone, Default, Deserialize, Serialize, Debug, PartialEq, Eq, Hash, ToSchema)]
 pub enum JsonFlavor {
     /// Default encoding used by Feldera, documented
     /// [here](https://www.feldera.com/docs/api/json#types).
@@ -154,4 +181,14 @@ pub struct JsonEncoderConfig {
     pub buffer_size_records: usize,
     #[serde(default)]
     pub array: bool,
+    /// Output column names that replace the names declared in the table
+    /// schema, indexed by the declared name.
+    #[serde(default)]
+    pub rename_columns: BTreeMap<String, String>,
+    /// Number of decimal places used to serialize floating point values.
+    ///
+    /// `None` uses the shortest representation that round-trips.  Not
+    /// supported by JIT-compiled pipelines.
+    #[serde(default)]
+    pub float_precision: Option<usize>,
 }
@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+const fn default_buffer_size_records() -> usize {
+    10_000
+}
+
+#[derive(Deserialize, Serialize, ToSchema)]
+pub struct ParquetEncoderConfig {
+    /// Maximal number of records in a single row group.
+    ///
+    /// Each row group is emitted as a self-contained Parquet file.
+    #[serde(default = "default_buffer_size_records")]
+    pub buffer_size_records: usize,
+}
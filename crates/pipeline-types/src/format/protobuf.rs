@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+const fn default_max_message_size() -> usize {
+    64 * 1024 * 1024
+}
+
+/// Protobuf parser configuration.
+///
+/// Describes an input stream of length-delimited protobuf messages.  Each
+/// message is preceded by its length in bytes, encoded as a varint (the
+/// framing produced by `writeDelimitedTo` in the Java protobuf library
+/// and `encode_length_delimited` in `prost`).  Every message is inserted
+/// as a single record into the input table.
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct ProtobufParserConfig {
+    /// Path to a serialized `FileDescriptorSet` that contains the
+    /// definition of the message type, e.g., as generated by
+    /// `protoc --include_imports --descriptor_set_out`.
+    pub descriptor_path: String,
+
+    /// Fully qualified name of the message type, e.g., `my.package.Record`.
+    pub message_name: String,
+
+    /// Maximal size of a single message in bytes.
+    ///
+    /// A message whose length prefix exceeds this limit is reported as a
+    /// parse error and skipped without buffering it.  The default is 64 MiB.
+    #[serde(default = "default_max_message_size")]
+    pub max_message_size: usize,
+}
@@ -175,6 +175,7 @@ request is rejected."
         pipeline_types::config::TransportConfig,
         pipeline_types::config::FormatConfig,
         pipeline_types::config::RuntimeConfig,
+        pipeline_types::config::ResourceConfig,
         pipeline_types::config::ConnectorConfig,
         pipeline_types::config::TransportConfig,
         pipeline_types::config::FormatConfig,
@@ -187,11 +188,17 @@ request is rejected."
         pipeline_types::transport::http::Chunk,
         pipeline_types::transport::http::EgressMode,
         pipeline_types::format::csv::CsvEncoderConfig,
+        pipeline_types::format::csv::CsvLineTerminator,
         pipeline_types::format::csv::CsvParserConfig,
+        pipeline_types::format::csv::CsvColumnCoercion,
+        pipeline_types::format::csv::TrimMode,
         pipeline_types::format::json::JsonEncoderConfig,
         pipeline_types::format::json::JsonParserConfig,
         pipeline_types::format::json::JsonFlavor,
+        pipeline_types::format::json::JsonOverflowPolicy,
         pipeline_types::format::json::JsonUpdateFormat,
+        pipeline_types::format::parquet::ParquetEncoderConfig,
+        pipeline_types::format::protobuf::ProtobufParserConfig,
         pipeline_types::error::ErrorResponse,
         TenantId,
         ProgramId,
@@ -1,6 +1,7 @@
 use crate::db::{PipelineId, ProgramId, Version};
 use anyhow::{Error as AnyError, Result as AnyResult};
 use clap::Parser;
+use pipeline_types::config::ResourceConfig;
 use serde::Deserialize;
 use std::{
     fs::{canonicalize, create_dir_all},
@@ -404,6 +405,20 @@ pub struct LocalRunnerConfig {
     #[serde(default = "default_server_address")]
     #[arg(long, default_value_t = default_server_address())]
     pub pipeline_host: String,
+
+    /// Default limit on the amount of resident memory in megabytes that a
+    /// pipeline process can use.  Pipelines can override this limit in the
+    /// `resources` section of their configuration.
+    #[serde(default)]
+    #[arg(long)]
+    pub default_memory_mb_max: Option<u64>,
+
+    /// Default limit on the amount of CPU time in seconds that a pipeline
+    /// process can consume.  Pipelines can override this limit in the
+    /// `resources` section of their configuration.
+    #[serde(default)]
+    #[arg(long)]
+    pub default_cpu_secs_max: Option<u64>,
 }
 
 impl LocalRunnerConfig {
@@ -431,6 +446,17 @@ impl LocalRunnerConfig {
 
         Ok(self)
     }
+
+    /// Resource limits for a pipeline with the specified resource
+    /// configuration.  Limits not set by the pipeline default to the limits
+    /// configured for the runner.
+    pub(crate) fn resource_limits(&self, resources: &ResourceConfig) -> ResourceConfig {
+        ResourceConfig {
+            memory_mb_max: resources.memory_mb_max.or(self.default_memory_mb_max),
+            cpu_secs_max: resources.cpu_secs_max.or(self.default_cpu_secs_max),
+        }
+    }
+
     /// Location to store pipeline files at runtime.
     pub(crate) fn pipeline_dir(&self, pipeline_id: PipelineId) -> PathBuf {
         Path::new(&self.runner_working_directory)
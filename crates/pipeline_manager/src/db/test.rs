@@ -9,7 +9,7 @@ use crate::db::Relation;
 use async_trait::async_trait;
 use chrono::{DateTime, NaiveDateTime, Utc};
 use openssl::sha::{self};
-use pipeline_types::config::{ConnectorConfig, RuntimeConfig};
+use pipeline_types::config::{ConnectorConfig, ResourceConfig, RuntimeConfig};
 use pretty_assertions::assert_eq;
 use proptest::test_runner::{Config, TestRunner};
 use proptest::{bool, prelude::*};
@@ -838,6 +838,7 @@ async fn versioning() {
         cpu_profiler: true,
         min_batch_size_records: 0,
         max_buffering_delay_usecs: 0,
+        resources: ResourceConfig::default(),
     };
     handle
         .db
@@ -1404,6 +1405,7 @@ fn db_impl_behaves_like_model() {
                                     cpu_profiler: config.1,
                                     min_batch_size_records: config.2,
                                     max_buffering_delay_usecs: config.3,
+                                    resources: ResourceConfig::default(),
                                 };
                                 let model_response =
                                     model.new_pipeline(tenant_id, id, program_id, &name, &description, &config, &connectors.clone()).await;
@@ -1418,6 +1420,7 @@ fn db_impl_behaves_like_model() {
                                     cpu_profiler: config.1,
                                     min_batch_size_records: config.2,
                                     max_buffering_delay_usecs: config.3,
+                                    resources: ResourceConfig::default(),
                                 });
                                 let model_response = model
                                     .update_pipeline(tenant_id, pipeline_id, program_id, &name, &description, &config, &connectors.clone())
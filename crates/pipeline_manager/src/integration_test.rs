@@ -122,6 +122,8 @@ async fn initialize_local_pipeline_manager_instance() -> TempDir {
     let local_runner_config = LocalRunnerConfig {
         runner_working_directory: workdir.to_owned(),
         pipeline_host: "127.0.0.1".to_owned(),
+        default_memory_mb_max: None,
+        default_cpu_secs_max: None,
     }
     .canonicalize()
     .unwrap();
@@ -11,11 +11,18 @@ use crate::{
 };
 use async_trait::async_trait;
 use log::trace;
+use pipeline_types::config::ResourceConfig;
 use std::{
     collections::BTreeMap,
+    io::{Error as IoError, Result as IoResult},
+    os::unix::process::{CommandExt, ExitStatusExt},
     process::Stdio,
-    process::{Child, Command},
-    sync::Arc,
+    process::{Child, Command, ExitStatus},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
 };
 use tokio::sync::Notify;
 use tokio::{
@@ -23,18 +30,118 @@ use tokio::{
     fs::{create_dir_all, remove_dir_all},
     spawn,
     sync::Mutex,
+    task::JoinHandle,
+    time::sleep,
 };
 
+/// How often the runner samples the resident memory of a pipeline process
+/// with a memory limit.
+const MEMORY_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
 /// A handle to the pipeline process that kills the pipeline
 /// on `drop`.
 pub struct ProcessRunner {
     pipeline_id: PipelineId,
     pipeline_process: Option<Child>,
     config: Arc<LocalRunnerConfig>,
+    /// Resource limits applied to the running pipeline process.
+    limits: ResourceConfig,
+    /// Task that enforces the memory limit of the pipeline process.
+    memory_monitor: Option<JoinHandle<()>>,
+    /// Set by `memory_monitor` when it kills the pipeline process for
+    /// exceeding its memory limit.
+    memory_limit_exceeded: Arc<AtomicBool>,
+}
+
+impl ProcessRunner {
+    fn new(pipeline_id: PipelineId, config: Arc<LocalRunnerConfig>) -> Self {
+        Self {
+            pipeline_id,
+            pipeline_process: None,
+            config,
+            limits: ResourceConfig::default(),
+            memory_monitor: None,
+            memory_limit_exceeded: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Describe why the pipeline process terminated with `status`.
+    fn termination_reason(&self, status: ExitStatus) -> String {
+        if self.memory_limit_exceeded.load(Ordering::Acquire) {
+            format!(
+                "pipeline process was killed after exceeding its memory limit of {} MB",
+                self.limits.memory_mb_max.unwrap_or_default()
+            )
+        } else if status.signal() == Some(libc::SIGXCPU) {
+            format!(
+                "pipeline process was killed after exceeding its CPU time limit of {} s",
+                self.limits.cpu_secs_max.unwrap_or_default()
+            )
+        } else {
+            format!("pipeline process exited with {status}")
+        }
+    }
+
+    fn stop_memory_monitor(&mut self) {
+        if let Some(monitor) = self.memory_monitor.take() {
+            monitor.abort();
+        }
+    }
+}
+
+/// Apply resource limits to the current process.
+///
+/// Invoked in the forked child process before executing the pipeline binary,
+/// so it must only perform async-signal-safe operations.  The memory limit
+/// is not enforced here: `RLIMIT_AS` limits virtual address space rather
+/// than memory use, so it is enforced by [`monitor_memory`] instead.
+fn set_resource_limits(limits: &ResourceConfig) -> IoResult<()> {
+    if let Some(cpu_secs_max) = limits.cpu_secs_max {
+        let limit = libc::rlimit {
+            rlim_cur: cpu_secs_max as libc::rlim_t,
+            rlim_max: cpu_secs_max as libc::rlim_t,
+        };
+        if unsafe { libc::setrlimit(libc::RLIMIT_CPU, &limit) } != 0 {
+            return Err(IoError::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Resident set size of process `pid` in bytes, or `None` if the process no
+/// longer exists.
+async fn resident_memory(pid: u32) -> Option<u64> {
+    // The second field of `statm` is the number of resident pages.
+    let statm = fs::read_to_string(format!("/proc/{pid}/statm"))
+        .await
+        .ok()?;
+    let pages = statm.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as u64;
+
+    Some(pages * page_size)
+}
+
+/// Kill process `pid` once its resident memory exceeds `memory_mb_max`
+/// megabytes, setting `exceeded` before doing so.
+async fn monitor_memory(pid: u32, memory_mb_max: u64, exceeded: Arc<AtomicBool>) {
+    let limit = memory_mb_max.saturating_mul(1024 * 1024);
+
+    while let Some(memory) = resident_memory(pid).await {
+        if memory > limit {
+            log::warn!(
+                "Pipeline process {pid} uses {memory} bytes of memory, exceeding its limit of {memory_mb_max} MB; killing the process"
+            );
+            exceeded.store(true, Ordering::Release);
+            unsafe { libc::kill(pid as libc::pid_t, libc::SIGKILL) };
+            return;
+        }
+        sleep(MEMORY_POLL_INTERVAL).await;
+    }
 }
 
 impl Drop for ProcessRunner {
     fn drop(&mut self) {
+        self.stop_memory_monitor();
         let _ = self.pipeline_process.as_mut().map(|p| p.kill());
     }
 }
@@ -81,17 +188,42 @@ impl PipelineExecutor for ProcessRunner {
 
         // Run executable, set current directory to pipeline directory, pass metadata
         // file and config as arguments.
-        let pipeline_process = Command::new(fetched_executable)
+        let limits = self.config.resource_limits(&ped.config.global.resources);
+        let mut command = Command::new(fetched_executable);
+        command
             .current_dir(self.config.pipeline_dir(pipeline_id))
             .arg("--config-file")
             .arg(&config_file_path)
-            .stdin(Stdio::null())
+            .stdin(Stdio::null());
+        if limits != ResourceConfig::default() {
+            let limits = limits.clone();
+            // Safety: `set_resource_limits` only invokes `setrlimit`, which is
+            // async-signal-safe.
+            unsafe {
+                command.pre_exec(move || set_resource_limits(&limits));
+            }
+        }
+        let pipeline_process = command
             .spawn()
             .map_err(|e| RunnerError::PipelineStartupError {
                 pipeline_id,
                 error: e.to_string(),
             })?;
+
+        // The process is not reaped until `check_if_terminated` or `shutdown`
+        // stop the monitor, so its pid cannot be reused while being monitored.
+        self.stop_memory_monitor();
+        self.memory_limit_exceeded.store(false, Ordering::Release);
+        if let Some(memory_mb_max) = limits.memory_mb_max {
+            self.memory_monitor = Some(spawn(monitor_memory(
+                pipeline_process.id(),
+                memory_mb_max,
+                self.memory_limit_exceeded.clone(),
+            )));
+        }
+
         self.pipeline_process = Some(pipeline_process);
+        self.limits = limits;
         Ok(())
     }
 
@@ -121,7 +253,22 @@ impl PipelineExecutor for ProcessRunner {
             .unwrap_or(true)
     }
 
+    async fn check_if_terminated(&mut self) -> Result<(), ManagerError> {
+        let status = match self.pipeline_process.as_mut().map(|p| p.try_wait()) {
+            Some(Ok(Some(status))) => status,
+            _ => return Ok(()),
+        };
+        self.stop_memory_monitor();
+
+        Err(RunnerError::PipelineTerminated {
+            pipeline_id: self.pipeline_id,
+            error: self.termination_reason(status),
+        }
+        .into())
+    }
+
     async fn shutdown(&mut self) -> Result<(), ManagerError> {
+        self.stop_memory_monitor();
         self.pipeline_process = None;
         match remove_dir_all(self.config.pipeline_dir(self.pipeline_id)).await {
             Ok(_) => (),
@@ -177,11 +324,7 @@ async fn reconcile(
                         .entry(pipeline_id)
                         .or_insert_with(|| {
                             let notifier = Arc::new(Notify::new());
-                            let pipeline_handle = ProcessRunner {
-                                pipeline_id,
-                                pipeline_process: None,
-                                config: config.clone(),
-                            };
+                            let pipeline_handle = ProcessRunner::new(pipeline_id, config.clone());
                             spawn(
                                 PipelineAutomaton::new(
                                     pipeline_id,
@@ -206,3 +349,79 @@ async fn reconcile(
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::ProcessRunner;
+    use crate::{
+        config::LocalRunnerConfig,
+        db::{PipelineId, ProgramId, Version},
+        pipeline_automata::{PipelineExecutionDesc, PipelineExecutor},
+    };
+    use pipeline_types::config::PipelineConfig;
+    use std::{
+        fs,
+        os::unix::fs::PermissionsExt,
+        sync::{atomic::Ordering, Arc},
+        time::Duration,
+    };
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn memory_limit_exceeded() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // A "pipeline" that keeps allocating memory: `tail` buffers its
+        // input until the end of the line, which never comes.  `exec` makes
+        // sure that the memory is allocated by the pipeline process itself.
+        let binary = dir.path().join("pipeline.sh");
+        fs::write(&binary, "#!/bin/sh\nexec tail /dev/zero\n").unwrap();
+        fs::set_permissions(&binary, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let config = LocalRunnerConfig {
+            runner_working_directory: dir.path().to_string_lossy().into_owned(),
+            pipeline_host: "127.0.0.1".to_owned(),
+            default_memory_mb_max: Some(4096),
+            default_cpu_secs_max: None,
+        };
+        let pipeline_id = PipelineId(Uuid::now_v7());
+        let mut runner = ProcessRunner::new(pipeline_id, Arc::new(config));
+
+        // The pipeline config overrides the runner's default memory limit.
+        let pipeline_config: PipelineConfig =
+            serde_yaml::from_str("workers: 1\nresources:\n  memory_mb_max: 64\ninputs: {}\n")
+                .unwrap();
+        runner
+            .start(PipelineExecutionDesc {
+                pipeline_id,
+                pipeline_name: "memory_hog".to_string(),
+                program_id: ProgramId(Uuid::now_v7()),
+                version: Version(1),
+                config: pipeline_config,
+                binary_ref: format!("file://{}", binary.display()),
+            })
+            .await
+            .unwrap();
+
+        let mut error = None;
+        for _ in 0..100 {
+            if let Err(e) = runner.check_if_terminated().await {
+                error = Some(e);
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        let error = error
+            .expect("pipeline did not terminate after exceeding its memory limit")
+            .to_string();
+        // The process was killed by the runner for exceeding the limit, and
+        // not, e.g., by failing to allocate memory.
+        assert!(runner.memory_limit_exceeded.load(Ordering::Acquire));
+        assert!(error.contains("terminated unexpectedly"), "{error}");
+        assert!(
+            error.contains("killed after exceeding its memory limit of 64 MB"),
+            "{error}"
+        );
+    }
+}
@@ -39,6 +39,10 @@ pub trait PipelineExecutor {
     /// Returns whether the pipeline has been shutdown
     async fn check_if_shutdown(&mut self) -> bool;
 
+    /// Returns an error if the pipeline has terminated without being asked
+    /// to, e.g., because it exceeded its resource limits.
+    async fn check_if_terminated(&mut self) -> Result<(), ManagerError>;
+
     /// Initiates pipeline shutdown (e.g., send a SIGTERM successfully to the
     /// process)
     async fn shutdown(&mut self) -> Result<(), ManagerError>;
@@ -212,6 +216,21 @@ impl<T: PipelineExecutor> PipelineAutomaton<T> {
                 drop(db);
             }
 
+            // The pipeline process is supposed to be running.  Make sure it
+            // hasn't terminated, e.g., by exceeding its resource limits.
+            if matches!(
+                pipeline.current_status,
+                PipelineStatus::Provisioning
+                    | PipelineStatus::Initializing
+                    | PipelineStatus::Paused
+                    | PipelineStatus::Running
+            ) {
+                if let Err(e) = self.pipeline_handle.check_if_terminated().await {
+                    self.mark_pipeline_as_failed(&mut pipeline, Some(e)).await?;
+                    continue;
+                }
+            }
+
             match (pipeline.current_status, pipeline.desired_status) {
                 // We're waiting for the pipeline's HTTP server to come online.
                 // Poll its port file.  On success, go to `Initializing` state.
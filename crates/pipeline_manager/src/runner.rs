@@ -53,6 +53,10 @@ pub enum RunnerError {
         // similar to `DBSPError::IO`.
         error: String,
     },
+    PipelineTerminated {
+        pipeline_id: PipelineId,
+        error: String,
+    },
     IllegalPipelineStateTransition {
         pipeline_id: PipelineId,
         error: String,
@@ -79,6 +83,7 @@ impl DetailedError for RunnerError {
             Self::PipelineShutdownTimeout { .. } => Cow::from("PipelineShutdownTimeout"),
             Self::PipelineStartupError { .. } => Cow::from("PipelineStartupError"),
             Self::PipelineShutdownError { .. } => Cow::from("PipelineShutdownError"),
+            Self::PipelineTerminated { .. } => Cow::from("PipelineTerminated"),
             Self::IllegalPipelineStateTransition { .. } => {
                 Cow::from("IllegalPipelineStateTransition")
             }
@@ -138,6 +143,9 @@ impl Display for RunnerError {
             Self::PipelineShutdownError { pipeline_id, error } => {
                 write!(f, "Failed to shutdown pipeline '{pipeline_id}': '{error}'")
             }
+            Self::PipelineTerminated { pipeline_id, error } => {
+                write!(f, "Pipeline {pipeline_id} terminated unexpectedly: {error}")
+            }
             Self::IllegalPipelineStateTransition { error, .. } => {
                 write!(
                     f,
@@ -167,6 +175,7 @@ impl ResponseError for RunnerError {
             Self::PipelineShutdownTimeout { .. } => StatusCode::INTERNAL_SERVER_ERROR,
             Self::PipelineStartupError { .. } => StatusCode::INTERNAL_SERVER_ERROR,
             Self::PipelineShutdownError { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::PipelineTerminated { .. } => StatusCode::INTERNAL_SERVER_ERROR,
             Self::IllegalPipelineStateTransition { .. } => StatusCode::BAD_REQUEST,
             Self::BinaryFetchError { .. } => StatusCode::INTERNAL_SERVER_ERROR,
         }
@@ -4,7 +4,7 @@ use crate::interval::{LongInterval, ShortInterval};
 use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc};
 use dbsp_adapters::{
     DateFormat, DeserializeWithContext, SerializeWithContext, SqlSerdeConfig, TimeFormat,
-    TimestampFormat,
+    TimestampFormat, DATE_NEWTYPE, TIMESTAMP_NEWTYPE, TIME_NEWTYPE,
 };
 use serde::{de::Error as _, ser::Error as _, Deserialize, Deserializer, Serializer};
 use size_of::SizeOf;
@@ -72,6 +72,9 @@ impl SerializeWithContext<SqlSerdeConfig> for Timestamp {
             TimestampFormat::String(format_string) => {
                 serializer.serialize_str(&datetime.format(format_string).to_string())
             }
+            TimestampFormat::MillisSinceEpoch => {
+                serializer.serialize_newtype_struct(TIMESTAMP_NEWTYPE, &self.milliseconds)
+            }
         }
     }
 }
@@ -97,6 +100,7 @@ impl<'de> DeserializeWithContext<'de, SqlSerdeConfig> for Timestamp {
 
                 Ok(Self::new(timestamp.timestamp_millis()))
             }
+            TimestampFormat::MillisSinceEpoch => Ok(Self::new(i64::deserialize(deserializer)?)),
         }
     }
 }
@@ -411,7 +415,9 @@ impl SerializeWithContext<SqlSerdeConfig> for Date {
 
                 serializer.serialize_str(&datetime.format(format_string).to_string())
             }
-            DateFormat::DaysSinceEpoch => serializer.serialize_i32(self.days),
+            DateFormat::DaysSinceEpoch => {
+                serializer.serialize_newtype_struct(DATE_NEWTYPE, &self.days)
+            }
         }
     }
 }
@@ -704,7 +710,9 @@ impl SerializeWithContext<SqlSerdeConfig> for Time {
                 let time = self.to_time();
                 serializer.serialize_str(&time.format(format_string).to_string())
             }
-            TimeFormat::Micros => serializer.serialize_u64(self.nanoseconds / 1_000),
+            TimeFormat::Micros => {
+                serializer.serialize_newtype_struct(TIME_NEWTYPE, &(self.nanoseconds / 1_000))
+            }
         }
     }
 }